@@ -24,9 +24,58 @@ fn main() {
     // propagate to dependent crates.
     setup_libzpl();
 
+    if std::env::var_os("CARGO_FEATURE_MTP").is_some() {
+        link_libmtp();
+    }
+
     tauri_build::build()
 }
 
+/// Point the linker at libmtp for the `mtp` feature. The bindings only name the
+/// library, which isn't on the default search path for Homebrew installs.
+fn link_libmtp() {
+    let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    if target_os == "windows" {
+        return;
+    }
+    println!("cargo:rerun-if-env-changed=PKG_CONFIG_PATH");
+
+    // pkg-config knows where distro packages and most Homebrew setups put it
+    let pkg_config_dirs = std::process::Command::new("pkg-config")
+        .args(["--libs-only-L", "libmtp"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .filter_map(|flag| flag.strip_prefix("-L"))
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        });
+    if let Some(dirs) = pkg_config_dirs {
+        for dir in dirs {
+            println!("cargo:rustc-link-search=native={}", dir.display());
+        }
+        return;
+    }
+
+    // Homebrew without pkg-config on the PATH
+    if target_os == "macos" {
+        for prefix in ["/opt/homebrew", "/usr/local"] {
+            let lib_dir = Path::new(prefix).join("lib");
+            if lib_dir.join("libmtp.dylib").exists() {
+                println!("cargo:rustc-link-search=native={}", lib_dir.display());
+                return;
+            }
+        }
+    }
+
+    println!(
+        "cargo:warning=libmtp wasn't found with pkg-config; if linking fails, install it with `brew install libmtp` (macOS) or `apt install libmtp-dev` (Linux)"
+    );
+}
+
 /// Read the Linux deb install directory for libzpl from tauri.conf.json.
 ///
 /// The `bundle.linux.deb.files` map uses destination-in-package as key and
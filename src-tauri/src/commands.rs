@@ -1,12 +1,12 @@
 use base64::Engine as _;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
 use dirs;
 use git2::{Branch, BranchType, ErrorCode as GitErrorCode, Oid, Repository, Status, StatusOptions};
 use log::{info, warn};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 #[cfg(target_os = "linux")]
 use std::env;
 #[cfg(any(target_os = "windows", target_os = "linux"))]
@@ -19,7 +19,7 @@ use std::process::Command as OsCommand;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tauri::{command, AppHandle, Emitter, Manager};
+use tauri::{command, AppHandle, Emitter, Manager, Runtime};
 use tokio::process::Command as TokioCommand;
 use tokio::sync::OnceCell;
 use tokio::time::sleep;
@@ -31,31 +31,44 @@ use walkdir::WalkDir;
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::{MetadataExt, PermissionsExt};
 
+use crate::file_plan;
+use crate::file_tags::{self, FileTag};
+use crate::file_templates::{self, FileTemplate};
+use crate::folder_size_cache::{self, FolderTotals};
 use crate::fs_utils::{
     self, allocate_unique_path, delete_file_or_directory, expand_path, read_directory_streaming,
-    resolve_symlink_parent, DiskUsage, FileItem, SymlinkResolution,
+    resolve_symlink_parent, ConflictPolicy, CopyReport, DiskUsage, FileItem, SymlinkResolution,
+    TreeCopyJournal, TreeCopyOutcome, TreeCopyProgress,
 };
 use crate::fs_watcher;
+use crate::locations::archive::names::{self as archive_names, zip_entry_name};
+use crate::locations::archive::{open_zip_entry, rar_first_volume};
 use crate::locations::gdrive::provider::{
-    download_file_to_temp, extract_gdrive_zip, fetch_url_with_auth, get_file_id_by_path,
-    get_folder_id_by_path, name_exists_in_folder, resolve_file_id_to_path, resolve_folder_id,
-    upload_file_to_gdrive,
+    download_file_head, download_file_to_temp, download_file_uncached, extract_gdrive_zip,
+    fetch_url_with_auth, get_file_id_by_path, get_folder_id_by_path, name_exists_in_folder,
+    resolve_file_id_to_path, resolve_folder_id, set_export_format, upload_file_to_gdrive,
+    upload_file_to_gdrive_with_unique_name, GoogleExportFormat,
 };
 use crate::locations::gdrive::url_parser::{is_google_drive_url, parse_google_drive_url};
 use crate::locations::gdrive::{
     add_google_account as add_gdrive_account, get_google_accounts as get_gdrive_accounts,
     remove_google_account as remove_gdrive_account, GoogleAccountInfo,
 };
+use crate::locations::transfer::{TransferProgress, UPLOAD_CANCELLED};
 use crate::locations::{
-    resolve_location, Location, LocationCapabilities, LocationInput, LocationSummary,
+    resolve_location, EntryFilter, Location, LocationCapabilities, LocationError, LocationInput,
+    LocationSummary, ProviderRef,
 };
 #[cfg(target_os = "macos")]
 use crate::macos_security;
+use crate::organize::{self, OrganizeStrategy};
+use crate::rename_pattern;
 #[cfg(target_os = "macos")]
 use crate::state::MacTrashUndoItem;
 use crate::state::{
-    DirectoryStreamHandle, DirectoryStreamState, FolderSizeState, FolderSizeTaskHandle,
-    TrashUndoRecord, TrashUndoState,
+    CopyTreeHandle, CopyTreeState, DirectoryStreamHandle, DirectoryStreamState, FolderSizeState,
+    FolderSizeTaskHandle, GitHistoryState, SearchState, SortPreferencesState, TrashUndoRecord,
+    TrashUndoState, UploadState,
 };
 use bzip2::read::BzDecoder;
 use flate2::read::GzDecoder;
@@ -85,6 +98,7 @@ use std::os::raw::c_char;
 
 const FOLDER_SIZE_EVENT: &str = "folder-size-progress";
 const FOLDER_SIZE_INIT_EVENT: &str = "folder-size:init";
+const FOLDER_SIZE_BREAKDOWN_EVENT: &str = "folder-size:breakdown";
 const FOLDER_SIZE_WINDOW_LABEL: &str = "folder-size";
 const ARCHIVE_PROGRESS_EVENT: &str = "archive-progress:init";
 const ARCHIVE_PROGRESS_UPDATE_EVENT: &str = "archive-progress:update";
@@ -92,6 +106,14 @@ const ARCHIVE_PROGRESS_WINDOW_LABEL: &str = "archive-progress";
 const COMPRESS_PROGRESS_INIT_EVENT: &str = "compress-progress:init";
 const COMPRESS_PROGRESS_UPDATE_EVENT: &str = "compress-progress:update";
 const COMPRESS_PROGRESS_WINDOW_LABEL: &str = "compress-progress";
+const COPY_TREE_PROGRESS_EVENT: &str = "copy-tree-progress";
+const COPY_TREE_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+const UPLOAD_PROGRESS_EVENT: &str = "upload-progress";
+const UPLOAD_PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+const ORGANIZE_PROGRESS_EVENT: &str = "organize-progress";
+const ORGANIZE_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+const THUMBNAIL_BATCH_EVENT: &str = "thumbnail:batch";
+const DIRECTORY_SEARCH_BATCH_EVENT: &str = "directory-search:batch";
 
 // Error codes for structured error handling
 // These constants define the API contract with the frontend
@@ -99,14 +121,23 @@ const COMPRESS_PROGRESS_WINDOW_LABEL: &str = "compress-progress";
 pub mod error_codes {
     pub const ENOENT: &str = "ENOENT"; // Path does not exist
     pub const ENOTDIR: &str = "ENOTDIR"; // Path is not a directory
+    pub const EISDIR: &str = "EISDIR"; // Path is a directory where a file was expected
     pub const EPERM: &str = "EPERM"; // Permission denied / Operation not permitted
-    pub const EOPEN: &str = "EOPEN"; // Failed to launch file browser
+    pub const EOPEN: &str = "EOPEN"; // Failed to launch file browser or terminal
+    pub const EREAUTH: &str = "EREAUTH"; // Account credentials revoked; user must sign in again
+    pub const ELIMIT: &str = "ELIMIT"; // Request would exceed a resource limit
+    pub const EEXIST: &str = "EEXIST"; // Something already exists at the destination path
+    pub const EBINARY: &str = "EBINARY"; // File is binary where text was expected
+    pub const ENETWORK: &str = "ENETWORK"; // Server unreachable or the connection dropped
+    pub const EUNSUPPORTED: &str = "EUNSUPPORTED"; // The location can't perform this operation
+    pub const ENOSPC: &str = "ENOSPC"; // Destination lacks free space for the operation
+    pub const EBUSY: &str = "EBUSY"; // Volume or file is in use by another program
 }
 
 /// Format an error with a code prefix for structured error handling
 /// Format: "[CODE] Human readable message"
 #[allow(dead_code)] // Will be used by future location providers
-fn format_error(code: &str, message: &str) -> String {
+pub(crate) fn format_error(code: &str, message: &str) -> String {
     format!("[{code}] {message}")
 }
 // Always emit progress updates - even small batches of large files benefit from progress indication
@@ -120,6 +151,10 @@ const CLIPBOARD_PROGRESS_WINDOW_LABEL: &str = "clipboard-progress";
 const SMB_CONNECT_INIT_EVENT: &str = "smb-connect:init";
 const SFTP_CONNECT_INIT_EVENT: &str = "sftp-connect:init";
 const PINNED_DIRECTORIES_CHANGED_EVENT: &str = "pinned-directories:changed";
+/// Emitted by the preferences window after it saves global preferences
+pub const PREFERENCES_UPDATED_EVENT: &str = "preferences:updated";
+#[cfg(target_os = "macos")]
+const NATIVE_DRAG_ENDED_EVENT: &str = "native-drag:ended";
 const SMB_CONNECT_WINDOW_LABEL: &str = "smb-connect";
 const SFTP_CONNECT_WINDOW_LABEL: &str = "sftp-connect";
 const PERMISSIONS_WINDOW_LABEL: &str = "permissions";
@@ -297,7 +332,7 @@ fn cleanup_trash_undo_records(state: &TrashUndoState) {
 }
 
 #[cfg(target_os = "macos")]
-fn nsstring_to_string(ns_string: *mut AnyObject) -> Option<String> {
+pub(crate) fn nsstring_to_string(ns_string: *mut AnyObject) -> Option<String> {
     if ns_string.is_null() {
         return None;
     }
@@ -554,6 +589,11 @@ struct ArchiveProgressUpdatePayload {
     entry_name: Option<String>,
     format: String,
     finished: bool,
+    /// Uncompressed bytes written so far, including `entry_name`
+    extracted_bytes: u64,
+    /// Uncompressed size of the whole archive; `None` when it can't be known without
+    /// decompressing everything first
+    total_bytes: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -590,6 +630,54 @@ struct ClipboardProgressUpdatePayload {
     finished: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    /// Byte-level progress for the current item, when the operation streams data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes_copied: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes_total: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyTreeProgressPayload {
+    request_id: String,
+    #[serde(flatten)]
+    progress: TreeCopyProgress,
+    finished: bool,
+    cancelled: bool,
+    rolled_back: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Byte progress of one upload to a remote provider, keyed by the copy or move's request id
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UploadProgressPayload {
+    transfer_id: String,
+    bytes_transferred: u64,
+    bytes_total: u64,
+    finished: bool,
+    cancelled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Folder transfers only: the file being sent and how many are done
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files_done: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    files_total: Option<usize>,
+}
+
+/// Final state of a `copy_tree` request.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyTreeResult {
+    pub cancelled: bool,
+    pub rolled_back: bool,
+    pub files_copied: u64,
+    pub bytes_copied: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -693,6 +781,31 @@ struct FolderSizeProgressPayload {
     error: Option<String>,
 }
 
+/// Size of one direct child of a scanned folder.
+#[derive(Debug, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct FolderSizeChild {
+    path: String,
+    bytes: u64,
+    items: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FolderSizeRootBreakdown {
+    root: String,
+    /// Largest first
+    children: Vec<FolderSizeChild>,
+}
+
+/// Sent once a folder-size walk completes, after the final progress event.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct FolderSizeBreakdownPayload {
+    request_id: String,
+    roots: Vec<FolderSizeRootBreakdown>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct DiskUsageResponse {
@@ -701,12 +814,50 @@ pub struct DiskUsageResponse {
     pub available_bytes: u64,
 }
 
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHashResponse {
+    pub algorithm: String,
+    /// Lowercase hex digest
+    pub digest: String,
+    pub elapsed_ms: u64,
+}
+
+/// Whether a copy will fit at its destination, returned by `check_transfer_feasibility`.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferFeasibility {
+    /// Combined size of everything being copied
+    pub total_source_bytes: u64,
+    /// Bytes the copy will actually consume (reflink clones on the same volume are free)
+    pub required_bytes: u64,
+    /// Free space at the destination, if the destination can report it
+    pub available_bytes: Option<u64>,
+    /// `None` when the destination's free space is unknown
+    pub fits: Option<bool>,
+    /// Space left over after the copy; negative means that many bytes short
+    pub margin_bytes: Option<i64>,
+    /// True when some remote folder couldn't be walked and its listed size was used
+    pub is_estimate: bool,
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ExtractArchiveResponse {
     pub folder_path: String,
     pub used_system_fallback: bool,
     pub format: String,
+    /// Whether extracted files were checked against the archive (`verify` was set and the check ran)
+    pub verified: bool,
+    /// Entries whose extracted file is missing or doesn't match the archive's size/CRC
+    pub failed_entries: Vec<ExtractVerificationFailure>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractVerificationFailure {
+    pub path: String,
+    pub reason: String,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -747,6 +898,21 @@ pub struct GitStatusResponse {
     pub has_untracked: bool,
     pub remote_url: Option<String>,
     pub remote_branch_url: Option<String>,
+    /// Set when the working-tree scan didn't finish in time; `dirty` and
+    /// `has_untracked` are unknown and the next call may have them.
+    pub status_unavailable: Option<String>,
+    pub stash_count: u32,
+    /// Every remote with a branch of the same name as the current one
+    pub remotes: Vec<GitRemoteStatus>,
+}
+
+/// Ahead/behind of the current branch against one remote's branch of the same name
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRemoteStatus {
+    pub name: String,
+    pub ahead: u32,
+    pub behind: u32,
 }
 
 #[derive(Debug, Default)]
@@ -919,8 +1085,64 @@ fn build_remote_branch_url(
     None
 }
 
-fn compute_git_status(path: &Path) -> Result<Option<GitStatusResponse>, String> {
-    let repo = match Repository::discover(path) {
+fn stash_count(repo: &mut Repository) -> u32 {
+    let mut count = 0u32;
+    let _ = repo.stash_foreach(|_, _, _| {
+        count += 1;
+        true
+    });
+    count
+}
+
+/// Compare the current branch with `<remote>/<branch>` on every remote that has it,
+/// so work pushed to one remote but not another shows up.
+fn remote_statuses(
+    repo: &Repository,
+    branch: Option<&str>,
+    detached: bool,
+) -> Vec<GitRemoteStatus> {
+    let (Some(branch), false) = (branch, detached) else {
+        return Vec::new();
+    };
+    let Some(local_oid) = repo
+        .find_branch(branch, BranchType::Local)
+        .ok()
+        .and_then(|local| local.get().target())
+    else {
+        return Vec::new();
+    };
+    let Ok(remotes) = repo.remotes() else {
+        return Vec::new();
+    };
+
+    remotes
+        .iter()
+        .filter_map(|name| name.ok().flatten())
+        .filter_map(|name| {
+            let tracking = repo
+                .find_branch(&format!("{name}/{branch}"), BranchType::Remote)
+                .ok()?;
+            let (ahead, behind) = repo
+                .graph_ahead_behind(local_oid, tracking.get().target()?)
+                .ok()?;
+            Some(GitRemoteStatus {
+                name: name.to_string(),
+                ahead: ahead as u32,
+                behind: behind as u32,
+            })
+        })
+        .collect()
+}
+
+/// Everything about a repository that is cheap to read, plus the key its
+/// working-tree scan is cached under. `dirty`/`has_untracked` are filled in later.
+struct GitSummary {
+    response: GitStatusResponse,
+    scan_key: crate::git_status::ScanKey,
+}
+
+fn read_git_summary(path: &Path) -> Result<Option<GitSummary>, String> {
+    let mut repo = match Repository::discover(path) {
         Ok(repo) => repo,
         Err(err) => {
             if err.code() == GitErrorCode::NotFound {
@@ -938,11 +1160,10 @@ fn compute_git_status(path: &Path) -> Result<Option<GitStatusResponse>, String>
         }
     };
 
-    let repository_root = workdir
+    let root = workdir
         .canonicalize()
-        .unwrap_or_else(|_| workdir.to_path_buf())
-        .to_string_lossy()
-        .to_string();
+        .unwrap_or_else(|_| workdir.to_path_buf());
+    let repository_root = root.to_string_lossy().to_string();
 
     let BranchState {
         name: branch_name,
@@ -953,10 +1174,45 @@ fn compute_git_status(path: &Path) -> Result<Option<GitStatusResponse>, String>
         remote_url,
     } = resolve_branch_state(&repo);
 
+    let remote_branch_url = remote_url.as_ref().and_then(|remote| {
+        build_remote_branch_url(remote, branch_name.as_deref(), detached, head_oid)
+    });
+    let scan_key =
+        crate::git_status::ScanKey::new(&root, repo.path(), head_oid.map(|oid| oid.to_string()));
+    let remotes = remote_statuses(&repo, branch_name.as_deref(), detached);
+    let stash_count = stash_count(&mut repo);
+
+    Ok(Some(GitSummary {
+        response: GitStatusResponse {
+            repository_root,
+            branch: branch_name,
+            detached,
+            ahead,
+            behind,
+            dirty: false,
+            has_untracked: false,
+            remote_url,
+            remote_branch_url,
+            status_unavailable: None,
+            stash_count,
+            remotes,
+        },
+        scan_key,
+    }))
+}
+
+/// Walk the working tree for changes. This is the slow part on huge repositories.
+fn scan_worktree(
+    root: &Path,
+    recurse_untracked: bool,
+) -> Result<crate::git_status::WorktreeState, String> {
+    let repo =
+        Repository::open(root).map_err(|err| format!("Failed to open Git repository: {err}"))?;
+
     let mut status_options = StatusOptions::new();
     status_options
         .include_untracked(true)
-        .recurse_untracked_dirs(true)
+        .recurse_untracked_dirs(recurse_untracked)
         .renames_head_to_index(true)
         .renames_index_to_workdir(true);
 
@@ -980,21 +1236,43 @@ fn compute_git_status(path: &Path) -> Result<Option<GitStatusResponse>, String>
         }
     }
 
-    let remote_branch_url = remote_url.as_ref().and_then(|remote| {
-        build_remote_branch_url(remote, branch_name.as_deref(), detached, head_oid)
-    });
-
-    Ok(Some(GitStatusResponse {
-        repository_root,
-        branch: branch_name,
-        detached,
-        ahead,
-        behind,
+    Ok(crate::git_status::WorktreeState {
         dirty,
         has_untracked,
-        remote_url,
-        remote_branch_url,
-    }))
+    })
+}
+
+/// Start (or join) the working-tree scan for `key` and wait up to the configured
+/// timeout. A scan that runs past the timeout keeps going and caches its result,
+/// so the next call for an unchanged repository returns immediately.
+async fn wait_for_worktree_scan(
+    key: crate::git_status::ScanKey,
+) -> Option<Result<crate::git_status::WorktreeState, String>> {
+    if crate::git_status::begin_scan(&key.root) {
+        let scan_key = key.clone();
+        let recurse_untracked = crate::git_status::recurse_untracked();
+        tauri::async_runtime::spawn_blocking(move || {
+            let started = Instant::now();
+            let result = scan_worktree(&scan_key.root, recurse_untracked);
+            log::debug!(
+                "Git status scan of {} took {}ms",
+                scan_key.root.display(),
+                started.elapsed().as_millis()
+            );
+            crate::git_status::finish_scan(scan_key, result);
+        });
+    }
+
+    let deadline = Instant::now() + crate::git_status::timeout();
+    loop {
+        if let Some(result) = crate::git_status::cached(&key) {
+            return Some(result);
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        sleep(Duration::from_millis(25)).await;
+    }
 }
 
 fn emit_folder_size_event(
@@ -1023,54 +1301,100 @@ fn emit_folder_size_event(
     }
 }
 
+/// A finished result outside of a walk: a cached one, or a re-verified one that changed.
+fn emit_folder_size_totals(app: &AppHandle, request_id: &str, totals: FolderTotals) {
+    emit_folder_size_event(
+        app,
+        request_id,
+        totals.bytes,
+        totals.apparent_bytes,
+        totals.items,
+        None,
+        true,
+        false,
+        None,
+    );
+}
+
 const FOLDER_SIZE_EMIT_INTERVAL: Duration = Duration::from_millis(150);
 const FOLDER_SIZE_EMIT_STEP: u64 = 256;
 
 struct ProgressReporter<'a> {
     app: &'a AppHandle,
     request_id: &'a str,
+    /// Re-verifying a cached result: count without emitting anything
+    quiet: bool,
     last_emit: Instant,
     items_since_emit: u64,
     total_bytes: u64,
     total_apparent_bytes: u64,
     total_items: u64,
+    /// Totals for the direct children of each directory root, in walk order
+    breakdown: Vec<(PathBuf, HashMap<PathBuf, FolderSizeChild>)>,
 }
 
 impl<'a> ProgressReporter<'a> {
-    fn new(app: &'a AppHandle, request_id: &'a str) -> Self {
+    fn new(app: &'a AppHandle, request_id: &'a str, quiet: bool) -> Self {
         let mut reporter = Self {
             app,
             request_id,
+            quiet,
             last_emit: Instant::now(),
             items_since_emit: 0,
             total_bytes: 0,
             total_apparent_bytes: 0,
             total_items: 0,
+            breakdown: Vec::new(),
         };
         reporter.emit_internal(None, false, false, None);
         reporter
     }
 
+    /// Start collecting the breakdown for a directory root.
+    fn begin_root(&mut self, root: &Path) {
+        self.breakdown.push((root.to_path_buf(), HashMap::new()));
+    }
+
+    /// Count an entry towards `child` of the current root, `None` for the root itself.
+    fn add_to_child(&mut self, child: Option<&Path>, bytes: u64) {
+        let (Some(child), Some((_, children))) = (child, self.breakdown.last_mut()) else {
+            return;
+        };
+        let totals = children
+            .entry(child.to_path_buf())
+            .or_insert_with(|| FolderSizeChild {
+                path: child.to_string_lossy().to_string(),
+                ..Default::default()
+            });
+        totals.bytes = totals.bytes.saturating_add(bytes);
+        totals.items = totals.items.saturating_add(1);
+    }
+
     fn add_file(
         &mut self,
         metadata: &fs::Metadata,
         seen_inodes: &mut HashSet<(u64, u64)>,
         current_path: Option<&Path>,
+        child: Option<&Path>,
     ) {
         self.total_apparent_bytes = self.total_apparent_bytes.saturating_add(metadata.len());
         let should_add_physical = match file_identity(metadata) {
             Some(identity) => seen_inodes.insert(identity),
             None => true,
         };
-        if should_add_physical {
-            self.total_bytes = self
-                .total_bytes
-                .saturating_add(physical_file_size(metadata));
-        }
+        // A hardlink seen before counts as an item but adds no bytes, here and per child
+        let physical = if should_add_physical {
+            physical_file_size(metadata)
+        } else {
+            0
+        };
+        self.total_bytes = self.total_bytes.saturating_add(physical);
+        self.add_to_child(child, physical);
         self.record_item(current_path);
     }
 
-    fn add_entry(&mut self, current_path: Option<&Path>) {
+    fn add_entry(&mut self, current_path: Option<&Path>, child: Option<&Path>) {
+        self.add_to_child(child, 0);
         self.record_item(current_path);
     }
 
@@ -1088,12 +1412,35 @@ impl<'a> ProgressReporter<'a> {
         self.emit_internal(None, true, cancelled, None);
     }
 
-    fn totals(&self) -> (u64, u64, u64) {
-        (
-            self.total_bytes,
-            self.total_apparent_bytes,
-            self.total_items,
-        )
+    /// Emit the per-child breakdown. Sent even for a quiet walk, since a cached
+    /// result only carries totals.
+    fn emit_breakdown(&mut self) {
+        let roots = std::mem::take(&mut self.breakdown)
+            .into_iter()
+            .map(|(root, children)| {
+                let mut children: Vec<FolderSizeChild> = children.into_values().collect();
+                children.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+                FolderSizeRootBreakdown {
+                    root: root.to_string_lossy().to_string(),
+                    children,
+                }
+            })
+            .collect();
+        let payload = FolderSizeBreakdownPayload {
+            request_id: self.request_id.to_string(),
+            roots,
+        };
+        if let Err(err) = self.app.emit(FOLDER_SIZE_BREAKDOWN_EVENT, payload) {
+            warn!("Failed to emit folder size breakdown: {err}");
+        }
+    }
+
+    fn totals(&self) -> FolderTotals {
+        FolderTotals {
+            bytes: self.total_bytes,
+            apparent_bytes: self.total_apparent_bytes,
+            items: self.total_items,
+        }
     }
 
     fn record_item(&mut self, current_path: Option<&Path>) {
@@ -1113,6 +1460,9 @@ impl<'a> ProgressReporter<'a> {
         cancelled: bool,
         error: Option<String>,
     ) {
+        if self.quiet {
+            return;
+        }
         emit_folder_size_event(
             self.app,
             self.request_id,
@@ -1191,13 +1541,15 @@ fn should_skip_path(_path: &Path) -> bool {
     false
 }
 
+/// Total up `roots`, emitting progress unless `quiet`. Returns `None` if cancelled.
 fn walk_paths_for_size(
     app: &AppHandle,
     request_id: &str,
     roots: &[PathBuf],
     cancel_flag: &Arc<AtomicBool>,
-) -> bool {
-    let mut reporter = ProgressReporter::new(app, request_id);
+    quiet: bool,
+) -> Option<FolderTotals> {
+    let mut reporter = ProgressReporter::new(app, request_id, quiet);
     let mut seen_inodes: HashSet<(u64, u64)> = HashSet::new();
 
     info!(
@@ -1215,7 +1567,7 @@ fn walk_paths_for_size(
 
         if cancel_flag.load(Ordering::Relaxed) {
             reporter.finish(true);
-            return true;
+            return None;
         }
 
         #[cfg(target_os = "macos")]
@@ -1275,7 +1627,7 @@ fn walk_paths_for_size(
                 }
                 Err(err) => {
                     warn!("Failed to resolve symlink target for {:?}: {}", root, err);
-                    reporter.add_entry(Some(root.as_path()));
+                    reporter.add_entry(Some(root.as_path()), None);
                     persist_bookmark_for_scan(root);
                     continue;
                 }
@@ -1285,18 +1637,19 @@ fn walk_paths_for_size(
         let metadata = target_metadata.as_ref().unwrap_or(&symlink_meta);
 
         if metadata.is_file() {
-            reporter.add_file(metadata, &mut seen_inodes, Some(root.as_path()));
+            reporter.add_file(metadata, &mut seen_inodes, Some(root.as_path()), None);
             persist_bookmark_for_scan(root);
             continue;
         }
 
         if metadata.is_dir() {
             info!("Starting directory walk for {:?}", root);
+            reporter.begin_root(root);
             let walker = WalkDir::new(root).follow_links(false).into_iter();
             for entry in walker {
                 if cancel_flag.load(Ordering::Relaxed) {
                     reporter.finish(true);
-                    return true;
+                    return None;
                 }
 
                 let entry = match entry {
@@ -1317,12 +1670,20 @@ fn walk_paths_for_size(
                     }
                 };
 
+                // The direct child of the root this entry lives under
+                let child = entry_path
+                    .strip_prefix(root)
+                    .ok()
+                    .and_then(|rel| rel.components().next())
+                    .map(|first| root.join(first));
+                let child = child.as_deref();
+
                 if file_type.is_symlink() {
-                    reporter.add_entry(Some(entry_path));
+                    reporter.add_entry(Some(entry_path), child);
                 } else if metadata.is_file() {
-                    reporter.add_file(&metadata, &mut seen_inodes, Some(entry_path));
+                    reporter.add_file(&metadata, &mut seen_inodes, Some(entry_path), child);
                 } else {
-                    reporter.add_entry(Some(entry_path));
+                    reporter.add_entry(Some(entry_path), child);
                 }
             }
 
@@ -1332,7 +1693,7 @@ fn walk_paths_for_size(
         }
 
         if is_symlink {
-            reporter.add_entry(Some(root.as_path()));
+            reporter.add_entry(Some(root.as_path()), None);
             persist_bookmark_for_scan(root);
             continue;
         }
@@ -1340,13 +1701,14 @@ fn walk_paths_for_size(
         persist_bookmark_for_scan(root);
     }
 
-    let (total_bytes, total_apparent_bytes, total_items) = reporter.totals();
+    let totals = reporter.totals();
     info!(
         "Folder size calculation completed. Physical bytes: {}, Logical bytes: {}, Total items: {}",
-        total_bytes, total_apparent_bytes, total_items
+        totals.bytes, totals.apparent_bytes, totals.items
     );
     reporter.finish(false);
-    false
+    reporter.emit_breakdown();
+    Some(totals)
 }
 
 #[command]
@@ -1356,6 +1718,227 @@ pub fn get_home_directory() -> Result<String, String> {
         .ok_or_else(|| "Could not determine home directory".to_string())
 }
 
+/// Resolve a local path to its real absolute form: `~` expanded, `.`/`..` collapsed and
+/// symlinks followed. With `normalize_only`, only collapse `.`/`..` lexically, which works
+/// for remote URIs and paths that don't exist.
+#[command]
+pub async fn canonicalize_path(
+    path: String,
+    normalize_only: Option<bool>,
+) -> Result<String, String> {
+    if normalize_only.unwrap_or(false) {
+        return Ok(fs_utils::normalize_path_lexically(&path));
+    }
+
+    let resolved = tauri::async_runtime::spawn_blocking(move || {
+        fs_utils::canonicalize_local_path(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format_error(
+                error_codes::ENOENT,
+                &format!("Path does not exist: {}", path),
+            ),
+            std::io::ErrorKind::PermissionDenied => {
+                format_error(error_codes::EPERM, &format!("Permission denied: {}", path))
+            }
+            _ => format!("Failed to resolve path {}: {}", path, e),
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))??;
+
+    Ok(resolved.to_string_lossy().to_string())
+}
+
+/// Default cap on recursive search results.
+const SEARCH_DEFAULT_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchFilesResponse {
+    pub results: Vec<FileItem>,
+    /// True when the results came from the in-memory index rather than a walk.
+    pub from_index: bool,
+    /// True when more matches existed than `limit` allowed.
+    pub truncated: bool,
+    /// Index state for the root when `use_index` was requested.
+    pub index: Option<crate::search_index::SearchIndexStatus>,
+}
+
+/// Recursively find entries under a local `path` whose name contains `query`
+/// (case-insensitive). With `use_index`, the first search starts building an
+/// in-memory index for the root; searches walk the tree until it is ready and are
+/// answered from memory afterwards.
+#[command]
+pub async fn search_files(
+    path: String,
+    query: String,
+    use_index: Option<bool>,
+    include_hidden: Option<bool>,
+    limit: Option<usize>,
+) -> Result<SearchFilesResponse, String> {
+    let root = expand_path(&path)?;
+    if !root.is_dir() {
+        return Err(format_error(
+            error_codes::ENOTDIR,
+            &format!("Not a directory: {}", path),
+        ));
+    }
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    let include_hidden = include_hidden.unwrap_or(false);
+    let limit = limit.unwrap_or(SEARCH_DEFAULT_LIMIT).max(1);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = use_index
+            .unwrap_or(false)
+            .then(|| crate::search_index::ensure_index(&root));
+
+        // Ask for one extra hit so we can tell the caller the list was cut short
+        let indexed = index.as_ref().and_then(|_| {
+            crate::search_index::query_index(&root, &query, include_hidden, limit + 1)
+        });
+        let from_index = indexed.is_some();
+        let mut hits = indexed.unwrap_or_else(|| {
+            crate::search_index::walk_search(&root, &query, include_hidden, limit + 1)
+        });
+        let truncated = hits.len() > limit;
+        hits.truncate(limit);
+
+        let results = hits
+            .iter()
+            .filter_map(|hit| fs_utils::get_file_info(&hit.path).ok())
+            .collect();
+        let index = index.and_then(|_| crate::search_index::index_status(&root));
+
+        SearchFilesResponse {
+            results,
+            from_index,
+            truncated,
+            index,
+        }
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))
+}
+
+/// Matches found by a `search_directory` call so far
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectorySearchBatch {
+    pub request_id: String,
+    pub entries: Vec<FileItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectorySearchResponse {
+    pub location: LocationSummary,
+    pub results: Vec<FileItem>,
+    /// True when the search stopped at its result limit
+    pub truncated: bool,
+    pub cancelled: bool,
+}
+
+/// Search entry names under any location. Matches stream to the UI through
+/// `directory-search:batch` events as they are found, and the full list is returned
+/// once the search ends; `cancel_directory_search` stops it early. Providers with a
+/// search of their own (Google Drive, `find` over SSH) use it.
+#[command]
+pub async fn search_directory(
+    app: AppHandle,
+    state: tauri::State<'_, SearchState>,
+    request_id: String,
+    path: LocationInput,
+    query: String,
+    options: Option<crate::locations::SearchOptions>,
+) -> Result<DirectorySearchResponse, String> {
+    let (provider, location) = resolve_location(path)?;
+    let query = crate::locations::SearchQuery::new(&query, options.unwrap_or_default())?;
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    if let Ok(mut searches) = state.searches.lock() {
+        if let Some(previous) = searches.insert(request_id.clone(), cancel_flag.clone()) {
+            previous.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let sink: crate::locations::SearchSink = {
+        let results = results.clone();
+        let request_id = request_id.clone();
+        Arc::new(move |entries: Vec<FileItem>| {
+            if let Ok(mut results) = results.lock() {
+                results.extend(entries.iter().cloned());
+            }
+            let batch = DirectorySearchBatch {
+                request_id: request_id.clone(),
+                entries,
+            };
+            if let Err(err) = app.emit(DIRECTORY_SEARCH_BATCH_EVENT, &batch) {
+                warn!("Failed to emit search batch: {err}");
+            }
+        })
+    };
+
+    let outcome = provider
+        .search(&location, &query, cancel_flag.clone(), sink)
+        .await;
+
+    if let Ok(mut searches) = state.searches.lock() {
+        if searches
+            .get(&request_id)
+            .is_some_and(|flag| Arc::ptr_eq(flag, &cancel_flag))
+        {
+            searches.remove(&request_id);
+        }
+    }
+
+    let found = outcome?;
+    let results = results.lock().map(|r| r.clone()).unwrap_or_default();
+    let display_path = if location.scheme() == "file" {
+        location.to_path_string()
+    } else {
+        location.to_string()
+    };
+    Ok(DirectorySearchResponse {
+        location: LocationSummary::new(
+            location.scheme(),
+            location.authority().map(|s| s.to_string()),
+            location.path(),
+            display_path,
+        ),
+        results,
+        truncated: found >= query.limit(),
+        cancelled: cancel_flag.load(Ordering::SeqCst),
+    })
+}
+
+/// Stop a running `search_directory`; matches found so far are still returned.
+#[command]
+pub fn cancel_directory_search(
+    state: tauri::State<'_, SearchState>,
+    request_id: String,
+) -> Result<bool, String> {
+    let searches = state
+        .searches
+        .lock()
+        .map_err(|e| format!("Failed to lock searches: {}", e))?;
+    Ok(searches
+        .get(&request_id)
+        .map(|flag| flag.store(true, Ordering::SeqCst))
+        .is_some())
+}
+
+/// Build state of the search index for `path`, or `None` if it isn't indexed.
+#[command]
+pub fn get_search_index_status(
+    path: String,
+) -> Result<Option<crate::search_index::SearchIndexStatus>, String> {
+    let root = expand_path(&path)?;
+    Ok(crate::search_index::index_status(&root))
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DropOperationInfo {
@@ -1510,6 +2093,90 @@ pub fn get_disk_usage(path: String) -> Result<DiskUsageResponse, String> {
     })
 }
 
+/// Check whether copying `sources` into `dest` will fit in the destination's free space.
+/// Folders are walked for their total size, the same way `ensure_space_for_copy` does.
+/// Free space is known for local, SMB and MTP destinations.
+#[command]
+pub async fn check_transfer_feasibility(
+    sources: Vec<String>,
+    dest: String,
+) -> Result<TransferFeasibility, String> {
+    if sources.is_empty() {
+        return Err("No sources provided".to_string());
+    }
+
+    let (_, dest_location) = resolve_location(LocationInput::Raw(dest))?;
+    let local_dest = (dest_location.scheme() == "file")
+        .then(|| expand_path(&dest_location.to_path_string()))
+        .transpose()?;
+
+    let mut total_source_bytes: u64 = 0;
+    let mut required_bytes: u64 = 0;
+    let mut is_estimate = false;
+    let mut local_sources: Vec<PathBuf> = Vec::new();
+
+    for source in sources {
+        let (provider, location) = resolve_location(LocationInput::Raw(source))?;
+        if location.scheme() == "file" {
+            local_sources.push(expand_path(&location.to_path_string())?);
+            continue;
+        }
+
+        let metadata = provider.get_file_metadata(&location).await?;
+        let size = match remote_source_size(&provider, &location, &metadata).await {
+            Ok(size) => size,
+            Err(err) => {
+                warn!("Couldn't measure {}: {}", location.raw(), err);
+                is_estimate = true;
+                metadata.size
+            }
+        };
+        total_source_bytes = total_source_bytes.saturating_add(size);
+        required_bytes = required_bytes.saturating_add(size);
+    }
+
+    let dest_for_walk = local_dest.clone();
+    let (local_total, local_required) = tauri::async_runtime::spawn_blocking(move || {
+        let mut total: u64 = 0;
+        let mut required: u64 = 0;
+        for source in &local_sources {
+            if !source.exists() {
+                continue;
+            }
+            let size = fs_utils::tree_size(source);
+            total = total.saturating_add(size);
+            let is_clone = dest_for_walk
+                .as_deref()
+                .map(|dest| fs_utils::supports_reflink_clone(source, dest))
+                .unwrap_or(false);
+            if !is_clone {
+                required = required.saturating_add(size);
+            }
+        }
+        (total, required)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+
+    total_source_bytes = total_source_bytes.saturating_add(local_total);
+    required_bytes = required_bytes.saturating_add(local_required);
+
+    let available_bytes = available_bytes_at(&dest_location).await?;
+    let margin_bytes = available_bytes.map(|available| {
+        let margin = i128::from(available) - i128::from(required_bytes);
+        margin.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64
+    });
+
+    Ok(TransferFeasibility {
+        total_source_bytes,
+        required_bytes,
+        available_bytes,
+        fits: margin_bytes.map(|margin| margin >= 0),
+        margin_bytes,
+        is_estimate,
+    })
+}
+
 #[command]
 pub async fn get_git_status(path: String) -> Result<Option<GitStatusResponse>, String> {
     let expanded_path = expand_path(&path)?;
@@ -1519,19 +2186,315 @@ pub async fn get_git_status(path: String) -> Result<Option<GitStatusResponse>, S
         return Err("Path does not exist".to_string());
     }
 
-    tauri::async_runtime::spawn_blocking(move || compute_git_status(&path))
+    let summary = tauri::async_runtime::spawn_blocking(move || read_git_summary(&path))
         .await
-        .map_err(|err| format!("Failed to join Git status task: {err}"))?
-}
+        .map_err(|err| format!("Failed to join Git status task: {err}"))??;
+    let Some(GitSummary {
+        mut response,
+        scan_key,
+    }) = summary
+    else {
+        return Ok(None);
+    };
 
-#[command]
-pub async fn read_directory(path: LocationInput) -> Result<DirectoryListingResponse, String> {
-    let (provider, location) = resolve_location(path)?;
-    let listing = provider.read_directory(&location).await?;
-    let capabilities = provider.capabilities(&location);
+    let scan = match crate::git_status::cached(&scan_key) {
+        Some(result) => Some(result),
+        None => wait_for_worktree_scan(scan_key).await,
+    };
+    match scan {
+        Some(Ok(state)) => {
+            response.dirty = state.dirty;
+            response.has_untracked = state.has_untracked;
+        }
+        Some(Err(err)) => return Err(err),
+        None => {
+            response.status_unavailable = Some(
+                "Repository is too large to scan quickly; status will update when the scan finishes"
+                    .to_string(),
+            );
+        }
+    }
 
-    Ok(DirectoryListingResponse {
-        location: listing.location,
+    Ok(Some(response))
+}
+
+/// Every non-clean entry of `repo`, for `get_git_file_statuses`.
+fn collect_file_statuses(
+    repo: &Repository,
+    recurse_untracked: bool,
+) -> Result<crate::git_status::FileStatusMap, String> {
+    let mut status_options = StatusOptions::new();
+    status_options
+        .include_untracked(true)
+        .recurse_untracked_dirs(recurse_untracked)
+        .include_ignored(true)
+        .recurse_ignored_dirs(false)
+        .renames_head_to_index(true)
+        .renames_index_to_workdir(true);
+
+    let statuses = repo
+        .statuses(Some(&mut status_options))
+        .map_err(|err| format!("Failed to compute repository status: {err}"))?;
+
+    let mut map = crate::git_status::FileStatusMap::new();
+    for entry in statuses.iter() {
+        let Some(path) = entry.path() else {
+            continue;
+        };
+        let status = crate::git_status::FileStatus::from_git(entry.status());
+        if status != crate::git_status::FileStatus::Clean {
+            map.insert(path.to_string(), status);
+        }
+    }
+    Ok(map)
+}
+
+fn read_git_file_statuses(
+    dir: &Path,
+) -> Result<HashMap<String, crate::git_status::FileStatus>, String> {
+    let repo = match Repository::discover(dir) {
+        Ok(repo) => repo,
+        Err(err) if err.code() == GitErrorCode::NotFound => return Ok(HashMap::new()),
+        Err(err) => return Err(format!("Failed to open Git repository: {err}")),
+    };
+    let Some(workdir) = repo.workdir() else {
+        return Ok(HashMap::new());
+    };
+    let root = workdir
+        .canonicalize()
+        .unwrap_or_else(|_| workdir.to_path_buf());
+    let dir = dir
+        .canonicalize()
+        .map_err(|err| format!("Failed to resolve {}: {err}", dir.display()))?;
+    let Ok(relative) = dir.strip_prefix(&root) else {
+        return Ok(HashMap::new());
+    };
+    let relative = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let head = repo
+        .head()
+        .ok()
+        .and_then(|head| head.target())
+        .map(|oid| oid.to_string());
+    let key = crate::git_status::ScanKey::new(&root, repo.path(), head);
+    let statuses = match crate::git_status::cached_file_statuses(&key) {
+        Some(statuses) => statuses,
+        None => {
+            let statuses = Arc::new(collect_file_statuses(
+                &repo,
+                crate::git_status::recurse_untracked(),
+            )?);
+            crate::git_status::store_file_statuses(key, Arc::clone(&statuses));
+            statuses
+        }
+    };
+
+    let names = fs::read_dir(&dir)
+        .map_err(|err| format!("Failed to read directory: {err}"))?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string());
+    Ok(crate::git_status::statuses_for_directory(
+        &statuses, &relative, names,
+    ))
+}
+
+/// Git status of each entry directly inside `path`, keyed by name. Empty when the
+/// folder isn't in a repository.
+#[command]
+pub async fn get_git_file_statuses(
+    path: String,
+) -> Result<HashMap<String, crate::git_status::FileStatus>, String> {
+    let dir = expand_path(&path)?;
+    if !dir.is_dir() {
+        return Err("Path is not a directory".to_string());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || read_git_file_statuses(&dir))
+        .await
+        .map_err(|err| format!("Failed to join Git status task: {err}"))?
+}
+
+/// Last commit touching each of `paths`, for a history column. Paths must live in the
+/// repository containing `repo_dir`; untracked ones come back without a commit.
+/// `request_id` lets the frontend cancel a lookup when the view changes.
+#[command]
+pub async fn get_last_commit_for_paths(
+    state: tauri::State<'_, GitHistoryState>,
+    repo_dir: String,
+    paths: Vec<String>,
+    request_id: Option<String>,
+    max_commits: Option<usize>,
+) -> Result<crate::git_history::LastCommitResponse, String> {
+    let repo_dir = expand_path(&repo_dir)?;
+    let max_commits = max_commits.unwrap_or(crate::git_history::DEFAULT_MAX_COMMITS);
+
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let request_id = request_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+    if let Some(id) = &request_id {
+        let mut guard = state
+            .tasks
+            .lock()
+            .map_err(|_| "Failed to access git history state".to_string())?;
+        if let Some(existing) = guard.insert(id.clone(), cancel_flag.clone()) {
+            existing.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let cancel_for_task = cancel_flag.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        crate::git_history::last_commits_for_paths(&repo_dir, &paths, max_commits, &cancel_for_task)
+    })
+    .await;
+
+    if let Some(id) = &request_id {
+        if let Ok(mut guard) = state.tasks.lock() {
+            // A newer lookup may have reused the id; only remove our own flag
+            if guard
+                .get(id)
+                .is_some_and(|flag| Arc::ptr_eq(flag, &cancel_flag))
+            {
+                guard.remove(id);
+            }
+        }
+    }
+
+    result.map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// The last `limit` commits touching `path`, newest first, each linked to its page on
+/// the remote's web UI when the remote is a known host.
+#[command]
+pub async fn get_file_git_log(
+    path: String,
+    limit: Option<usize>,
+) -> Result<crate::git_history::FileLogResponse, String> {
+    let path = expand_path(&path)?;
+    let limit = limit.unwrap_or(crate::git_history::DEFAULT_LOG_LIMIT);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let start = if path.is_dir() {
+            path.as_path()
+        } else {
+            path.parent().unwrap_or(&path)
+        };
+        let repo = Repository::discover(start)
+            .map_err(|err| format!("Failed to open Git repository: {err}"))?;
+        let remote_url = resolve_branch_state(&repo).remote_url;
+        crate::git_history::file_log(&repo, &path, limit, |oid| {
+            let remote = remote_url.as_deref()?;
+            build_remote_branch_url(remote, None, true, Some(oid))
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[command]
+pub fn cancel_last_commit_lookup(
+    state: tauri::State<'_, GitHistoryState>,
+    request_id: String,
+) -> Result<(), String> {
+    let guard = state
+        .tasks
+        .lock()
+        .map_err(|_| "Failed to access git history state".to_string())?;
+    if let Some(flag) = guard.get(request_id.trim()) {
+        flag.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+/// Saved sort preferences for a listing, keyed the way the frontend stores
+/// per-directory preferences so remote listings honour them just like local ones.
+/// The preferences file is parsed once and kept until preferences change.
+fn listing_sort_overrides(
+    state: &SortPreferencesState,
+    location: &Location,
+) -> crate::sorting::SortOverrides {
+    let saved = {
+        let Ok(mut cached) = state.saved.lock() else {
+            return Default::default();
+        };
+        match cached.as_ref() {
+            Some(saved) => Arc::clone(saved),
+            None => {
+                let Ok(prefs) = read_prefs_value() else {
+                    return Default::default();
+                };
+                let saved = Arc::new(crate::sorting::SavedSortPreferences::parse(&prefs));
+                *cached = Some(Arc::clone(&saved));
+                saved
+            }
+        }
+    };
+    let key = if location.scheme() == "file" {
+        location.to_path_string()
+    } else {
+        location.raw().to_string()
+    };
+    saved.for_directory(&normalize_path(key))
+}
+
+/// Drop the parsed sort preferences so the next listing reads the saved file again.
+pub fn forget_sort_preferences(state: &SortPreferencesState) {
+    if let Ok(mut cached) = state.saved.lock() {
+        *cached = None;
+    }
+}
+
+/// List a directory. `filter` is an optional case-insensitive glob matched against
+/// entry names; directories are kept regardless unless `include_directories` is false.
+/// The local provider filters while reading; remote providers still fetch the full
+/// listing and filter it before returning. Entries come back in the stored sort order.
+#[command]
+pub async fn read_directory(
+    menu_state: tauri::State<'_, crate::state::MenuState<tauri::Wry>>,
+    sort_prefs: tauri::State<'_, SortPreferencesState>,
+    path: LocationInput,
+    filter: Option<String>,
+    include_directories: Option<bool>,
+    include_tags: Option<bool>,
+) -> Result<DirectoryListingResponse, String> {
+    let (provider, location) = resolve_location(path)?;
+    let filter = filter
+        .filter(|pattern| !pattern.trim().is_empty())
+        .map(|pattern| EntryFilter::new(&pattern, include_directories.unwrap_or(true)))
+        .transpose()?;
+    let mut listing = match &filter {
+        Some(filter) => provider.read_directory_filtered(&location, filter).await?,
+        None => provider.read_directory(&location).await?,
+    };
+    crate::sorting::sort_listing(
+        &mut listing.entries,
+        &menu_state,
+        listing_sort_overrides(&sort_prefs, &location),
+    );
+    let capabilities = provider.capabilities(&location);
+
+    // Tags cost an attribute read per entry, so only listings that show them ask
+    if include_tags.unwrap_or(false) && capabilities.supports_tags {
+        let entries = std::mem::take(&mut listing.entries);
+        listing.entries = tauri::async_runtime::spawn_blocking(move || {
+            entries
+                .into_iter()
+                .map(|mut entry| {
+                    entry.tags = file_tags::read_tags(Path::new(&entry.path)).ok();
+                    entry
+                })
+                .collect()
+        })
+        .await
+        .map_err(|e| format!("Failed to join tag task: {}", e))?;
+    }
+
+    Ok(DirectoryListingResponse {
+        location: listing.location,
         capabilities,
         entries: listing.entries,
     })
@@ -1632,7 +2595,10 @@ pub async fn read_directory_streaming_command(
                 &path_for_task,
                 session_for_blocking,
                 cancel_for_blocking,
-                |batch| {
+                |mut batch| {
+                    // Skeletons have no sizes yet, so only submodules get flagged here;
+                    // LFS sizes arrive with the metadata updates
+                    crate::git_annotations::annotate_listing(&path_for_task, &mut batch.entries);
                     // Emit skeleton file batches (instant UI)
                     if let Err(e) = app_for_batches.emit(DIRECTORY_BATCH_EVENT, &batch) {
                         warn!("Failed to emit directory batch: {}", e);
@@ -1709,6 +2675,118 @@ pub async fn get_file_metadata(path: LocationInput) -> Result<FileItem, String>
     provider.get_file_metadata(&location).await
 }
 
+/// Entries directly inside a folder. Listings don't include counts, so the UI
+/// requests them for folders as they scroll into view.
+#[command]
+pub async fn get_child_count(path: LocationInput) -> Result<u64, String> {
+    let (provider, location) = resolve_location(path)?;
+    provider.child_count(&location).await
+}
+
+/// File and folder counts, direct file sizes and hidden entries for the folder at
+/// `path`, without descending into subfolders. Cheap enough for the status bar to
+/// show while a full folder-size scan runs.
+#[command]
+pub async fn get_directory_stats(path: LocationInput) -> Result<fs_utils::DirectoryStats, String> {
+    let (provider, location) = resolve_location(path)?;
+    provider.directory_stats(&location).await
+}
+
+const HASH_CHUNK_SIZE: usize = 1024 * 1024;
+
+enum FileHasher {
+    Md5(md5::Context),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+}
+
+impl FileHasher {
+    fn new(algorithm: &str) -> Result<Self, String> {
+        use sha2::Digest;
+        match algorithm.to_ascii_lowercase().as_str() {
+            "md5" => Ok(Self::Md5(md5::Context::new())),
+            "sha1" => Ok(Self::Sha1(sha1::Sha1::new())),
+            "sha256" => Ok(Self::Sha256(sha2::Sha256::new())),
+            other => Err(format!("Unsupported hash algorithm: {}", other)),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        use sha2::Digest;
+        match self {
+            Self::Md5(ctx) => ctx.consume(data),
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finalize_hex(self) -> String {
+        use sha2::Digest;
+        match self {
+            Self::Md5(ctx) => hex::encode(ctx.finalize().0),
+            Self::Sha1(hasher) => hex::encode(hasher.finalize()),
+            Self::Sha256(hasher) => hex::encode(hasher.finalize()),
+        }
+    }
+}
+
+fn hash_file(path: &Path, mut hasher: FileHasher) -> Result<String, String> {
+    let mut file = fs::File::open(path)
+        .map_err(|err| format!("Failed to open {}: {}", path.display(), err))?;
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(format!("Failed to read {}: {}", path.display(), err)),
+        };
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize_hex())
+}
+
+/// Hash a local file with `md5`, `sha1` or `sha256`, reading it in chunks.
+#[command]
+pub async fn compute_file_hash(
+    path: String,
+    algorithm: String,
+) -> Result<FileHashResponse, String> {
+    let file_path = expand_path(&path)?;
+    let hasher = FileHasher::new(&algorithm)?;
+    match fs::metadata(&file_path) {
+        Ok(meta) if meta.is_dir() => {
+            return Err(format_error(
+                error_codes::EISDIR,
+                &format!("Cannot hash a folder: {}", path),
+            ))
+        }
+        Ok(_) => {}
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err(format_error(
+                error_codes::ENOENT,
+                &format!("Path does not exist: {}", path),
+            ))
+        }
+        Err(err) => return Err(format!("Failed to read {}: {}", path, err)),
+    }
+
+    let started = Instant::now();
+    let digest = tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(target_os = "macos")]
+        let _scope_guard = macos_security::retain_access(&file_path)?;
+        hash_file(&file_path, hasher)
+    })
+    .await
+    .map_err(|err| format!("Failed to join hash task: {}", err))??;
+
+    Ok(FileHashResponse {
+        algorithm: algorithm.to_ascii_lowercase(),
+        digest,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
 /// Validate a single path segment (folder name without slashes)
 fn validate_path_segment(name: &str) -> Result<(), String> {
     let trimmed = name.trim();
@@ -1808,6 +2886,156 @@ pub fn resolve_symlink_parent_command(path: String) -> Result<SymlinkResolution,
     resolve_symlink_parent(path)
 }
 
+/// Reject `link_path` when anything, including a dangling symlink, is already there.
+fn ensure_link_path_free(link_path: &Path) -> Result<(), String> {
+    if fs::symlink_metadata(link_path).is_ok() {
+        return Err(format_error(
+            error_codes::EEXIST,
+            &format!("{} already exists", link_path.display()),
+        ));
+    }
+    Ok(())
+}
+
+fn link_error(kind: &str, err: std::io::Error) -> String {
+    // ERROR_PRIVILEGE_NOT_HELD: symlinks need admin rights or Developer Mode on Windows
+    #[cfg(windows)]
+    if err.raw_os_error() == Some(1314) {
+        return format_error(
+            error_codes::EPERM,
+            "Creating symlinks requires administrator rights or Developer Mode",
+        );
+    }
+    if err.kind() == ErrorKind::PermissionDenied {
+        return format_error(
+            error_codes::EPERM,
+            &format!("Permission denied creating {}: {}", kind, err),
+        );
+    }
+    format!("Failed to create {}: {}", kind, err)
+}
+
+/// Create a symlink at `link_path` pointing at `target`. Relative targets are stored
+/// as given, so they resolve against the link's folder.
+#[command]
+pub async fn create_symlink(target: String, link_path: String) -> Result<(), String> {
+    let target = expand_path(&target)?;
+    let link_path = expand_path(&link_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(target_os = "macos")]
+        let _scope_guard = macos_security::retain_access(&link_path)?;
+        ensure_link_path_free(&link_path)?;
+
+        #[cfg(unix)]
+        let result = std::os::unix::fs::symlink(&target, &link_path);
+        #[cfg(windows)]
+        let result = {
+            let resolved = link_path
+                .parent()
+                .map(|parent| parent.join(&target))
+                .unwrap_or_else(|| target.clone());
+            if resolved.is_dir() {
+                std::os::windows::fs::symlink_dir(&target, &link_path)
+            } else {
+                std::os::windows::fs::symlink_file(&target, &link_path)
+            }
+        };
+        result.map_err(|e| link_error("symlink", e))
+    })
+    .await
+    .map_err(|e| format!("Failed to join symlink task: {}", e))?
+}
+
+/// Create a hard link at `link_path` to the existing file `target`.
+#[command]
+pub async fn create_hardlink(target: String, link_path: String) -> Result<(), String> {
+    let target = expand_path(&target)?;
+    let link_path = expand_path(&link_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(target_os = "macos")]
+        let _target_guard = macos_security::retain_access(&target)?;
+        #[cfg(target_os = "macos")]
+        let _link_guard = macos_security::retain_access(&link_path)?;
+
+        match fs::metadata(&target) {
+            Ok(meta) if meta.is_dir() => {
+                return Err(format_error(
+                    error_codes::EISDIR,
+                    "Hard links can only point at files",
+                ))
+            }
+            Ok(_) => {}
+            Err(_) => {
+                return Err(format_error(
+                    error_codes::ENOENT,
+                    &format!("{} does not exist", target.display()),
+                ))
+            }
+        }
+        ensure_link_path_free(&link_path)?;
+        fs::hard_link(&target, &link_path).map_err(|e| link_error("hard link", e))
+    })
+    .await
+    .map_err(|e| format!("Failed to join hard link task: {}", e))?
+}
+
+/// Finder tags on a local file; empty where the platform has no tags
+#[command]
+pub async fn get_file_tags(path: String) -> Result<Vec<FileTag>, String> {
+    let file_path = expand_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(target_os = "macos")]
+        let _scope_guard = macos_security::retain_access(&file_path)?;
+        file_tags::read_tags(&file_path)
+    })
+    .await
+    .map_err(|e| format!("Failed to join tag task: {}", e))?
+}
+
+/// Replace a local file's tags. Does nothing where `supportsTags` is false.
+#[command]
+pub async fn set_file_tags(path: String, tags: Vec<FileTag>) -> Result<(), String> {
+    let file_path = expand_path(&path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(target_os = "macos")]
+        let _scope_guard = macos_security::retain_access(&file_path)?;
+        file_tags::write_tags(&file_path, &tags)
+    })
+    .await
+    .map_err(|e| format!("Failed to join tag task: {}", e))?
+}
+
+/// Raw extended attribute value, base64 encoded, or `None` when it isn't set
+#[command]
+pub async fn get_xattr(path: String, name: String) -> Result<Option<String>, String> {
+    let file_path = expand_path(&path)?;
+    let value = tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(target_os = "macos")]
+        let _scope_guard = macos_security::retain_access(&file_path)?;
+        file_tags::get_xattr(&file_path, &name)
+    })
+    .await
+    .map_err(|e| format!("Failed to join attribute task: {}", e))??;
+    Ok(value.map(|bytes| base64::engine::general_purpose::STANDARD.encode(bytes)))
+}
+
+/// Set an extended attribute from a base64 value, or remove it when `value` is `None`
+#[command]
+pub async fn set_xattr(path: String, name: String, value: Option<String>) -> Result<(), String> {
+    let file_path = expand_path(&path)?;
+    let value = value
+        .map(|encoded| base64::engine::general_purpose::STANDARD.decode(encoded))
+        .transpose()
+        .map_err(|e| format!("Attribute value is not valid base64: {}", e))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(target_os = "macos")]
+        let _scope_guard = macos_security::retain_access(&file_path)?;
+        file_tags::set_xattr(&file_path, &name, value.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Failed to join attribute task: {}", e))?
+}
+
 #[command]
 pub fn create_folder(base_dir: String, name: Option<String>) -> Result<String, String> {
     let base_dir = base_dir.trim();
@@ -1870,83 +3098,90 @@ pub fn create_folder(base_dir: String, name: Option<String>) -> Result<String, S
     Err("Unable to create a unique folder name".to_string())
 }
 
+/// Create a file in `base_dir`, local or remote, and return its path. A taken name
+/// is numbered like "Untitled (2).md" rather than replaced. `contents` fills the
+/// new file, e.g. from a file template.
+#[command]
+pub async fn create_file(
+    base_dir: String,
+    name: Option<String>,
+    contents: Option<String>,
+) -> Result<String, String> {
+    let base_dir = base_dir.trim();
+    if base_dir.is_empty() {
+        return Err("Base directory is required".to_string());
+    }
+
+    let desired = name
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or("Untitled")
+        .to_string();
+
+    validate_new_file_name(&desired)?;
+
+    let (provider, location) = resolve_location(LocationInput::Raw(base_dir.to_string()))?;
+    let capabilities = provider.capabilities(&location);
+    if !capabilities.can_write {
+        return Err(LocationError::Unsupported(format!(
+            "{} locations are read-only",
+            capabilities.display_name
+        ))
+        .to_string());
+    }
+
+    let dir_raw = if location.scheme() == "file" {
+        let base_path = expand_path(&location.to_path_string())?;
+        if !base_path.exists() {
+            return Err(format!("Base directory does not exist: {}", base_dir));
+        }
+        if !base_path.is_dir() {
+            return Err("Base path is not a directory".to_string());
+        }
+        base_path.to_string_lossy().to_string()
+    } else {
+        location.raw().to_string()
+    };
+
+    #[cfg(target_os = "macos")]
+    let _scope_guard = if location.scheme() == "file" {
+        Some(macos_security::retain_access(Path::new(&dir_raw))?)
+    } else {
+        None
+    };
+
+    let contents = contents.unwrap_or_default();
+    let created = provider
+        .create_file(&location, &desired, contents.as_bytes())
+        .await?;
+    let created_path = join_dest_raw(location.scheme(), &dir_raw, &created);
+
+    #[cfg(target_os = "macos")]
+    if location.scheme() == "file" {
+        macos_security::persist_bookmark(Path::new(&created_path), "creating file");
+    }
+
+    Ok(created_path)
+}
+
+/// Templates for "New ▸ …", from `globalPreferences.fileTemplates` or the built-in list.
+#[command]
+pub fn get_file_templates() -> Result<Vec<FileTemplate>, String> {
+    Ok(file_templates::from_preferences(&read_prefs_value()?))
+}
+
+/// Create nested folders from a slash-delimited path (VSCode-style).
+/// For example, "foo/bar/baz" creates all three directories.
+/// Returns the full path to the deepest created directory.
 #[command]
-pub fn create_file(base_dir: String, name: Option<String>) -> Result<String, String> {
+pub fn create_nested_folders(base_dir: String, nested_path: String) -> Result<String, String> {
     let base_dir = base_dir.trim();
     if base_dir.is_empty() {
         return Err("Base directory is required".to_string());
     }
     if base_dir.contains("://") {
-        return Err("New file creation is only supported for local paths".to_string());
-    }
-
-    let base_path = expand_path(base_dir)?;
-    if !base_path.exists() {
-        return Err(format!("Base directory does not exist: {}", base_dir));
-    }
-    if !base_path.is_dir() {
-        return Err("Base path is not a directory".to_string());
-    }
-
-    let base_canon =
-        fs::canonicalize(&base_path).map_err(|e| format!("Failed to resolve base dir: {}", e))?;
-
-    #[cfg(target_os = "macos")]
-    let _scope_guard = macos_security::retain_access(&base_canon)?;
-
-    let desired = name
-        .as_deref()
-        .map(str::trim)
-        .filter(|v| !v.is_empty())
-        .unwrap_or("Untitled")
-        .to_string();
-
-    validate_new_file_name(&desired)?;
-
-    for attempt in 0..1000usize {
-        let candidate_name = if attempt == 0 {
-            desired.clone()
-        } else {
-            format!("{} ({})", desired, attempt)
-        };
-        let candidate_path = base_path.join(&candidate_name);
-        match fs::OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(&candidate_path)
-        {
-            Ok(_file) => {
-                let created_canon = fs::canonicalize(&candidate_path)
-                    .map_err(|e| format!("Failed to resolve created file: {}", e))?;
-                if !created_canon.starts_with(&base_canon) {
-                    let _ = fs::remove_file(&candidate_path);
-                    return Err("File creation escaped the base directory".to_string());
-                }
-
-                #[cfg(target_os = "macos")]
-                macos_security::persist_bookmark(&candidate_path, "creating file");
-
-                return Ok(candidate_path.to_string_lossy().to_string());
-            }
-            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
-            Err(err) => return Err(format!("Failed to create file: {}", err)),
-        }
-    }
-
-    Err("Unable to create a unique file name".to_string())
-}
-
-/// Create nested folders from a slash-delimited path (VSCode-style).
-/// For example, "foo/bar/baz" creates all three directories.
-/// Returns the full path to the deepest created directory.
-#[command]
-pub fn create_nested_folders(base_dir: String, nested_path: String) -> Result<String, String> {
-    let base_dir = base_dir.trim();
-    if base_dir.is_empty() {
-        return Err("Base directory is required".to_string());
-    }
-    if base_dir.contains("://") {
-        return Err("Nested folder creation is only supported for local paths".to_string());
+        return Err("Nested folder creation is only supported for local paths".to_string());
     }
 
     let base_path = expand_path(base_dir)?;
@@ -2020,16 +3255,67 @@ pub async fn create_directory_command(path: LocationInput) -> Result<(), String>
     provider.create_directory(&location).await
 }
 
+/// Resolve the enclosing folder of any location (local, remote, archive) for in-app
+/// "Go to enclosing folder". Returns `None` when already at a root.
+#[command]
+pub fn get_parent_location(location: LocationInput) -> Result<Option<LocationSummary>, String> {
+    let location = location.into_location()?;
+    crate::locations::parent_location(&location)
+}
+
+/// Create a directory and any missing parents, succeeding if it already exists.
+#[command]
+pub async fn ensure_directory(path: LocationInput) -> Result<(), String> {
+    let (provider, location) = resolve_location(path)?;
+    let capabilities = provider.capabilities(&location);
+    if !capabilities.can_create_directories {
+        return Err("Provider does not support creating directories".to_string());
+    }
+    provider.ensure_directory(&location).await
+}
+
+/// Local items go to the trash unless `permanent` is set. Other providers have no
+/// trash, so their items are always deleted permanently.
 #[command]
-pub async fn delete_file(path: LocationInput) -> Result<(), String> {
+pub async fn delete_file(path: LocationInput, permanent: Option<bool>) -> Result<(), String> {
     let (provider, location) = resolve_location(path)?;
     let capabilities = provider.capabilities(&location);
     if !capabilities.can_delete {
         return Err("Provider does not support deleting items".to_string());
     }
+    if location.scheme() == "file" && !permanent.unwrap_or(false) {
+        let target = expand_path(&location.to_path_string())?;
+        if fs::symlink_metadata(&target).is_err() {
+            return Err("Path does not exist".to_string());
+        }
+        return tauri::async_runtime::spawn_blocking(move || move_to_trash(&target))
+            .await
+            .map_err(|err| format!("Failed to join trash task: {err}"))?;
+    }
     provider.delete(&location).await
 }
 
+fn move_to_trash(path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        macos_trash_items(&[path.to_path_buf()]).map(|_| ())
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        trash::delete(path).map_err(|err| format!("Failed to move to trash: {err}"))
+    }
+}
+
+/// Put an item from `trash://` back where it was deleted from; returns its restored path.
+#[command]
+pub async fn restore_from_trash(path: LocationInput) -> Result<String, String> {
+    let location = path.into_location()?;
+    if location.scheme() != "trash" {
+        return Err("Only items in the trash can be restored".to_string());
+    }
+    crate::locations::trash::restore_item(&location).await
+}
+
 #[command]
 pub async fn trash_paths(app: AppHandle, paths: Vec<String>) -> Result<TrashPathsResponse, String> {
     if paths.is_empty() {
@@ -2343,13 +3629,406 @@ pub async fn rename_file(from_path: LocationInput, to_path: LocationInput) -> Re
     from_provider.rename(&from_location, &to_location).await
 }
 
+/// Hide or show a local item. On Unix this renames it (adding or removing the
+/// leading dot), so the returned path may differ from `path`.
+#[command]
+pub async fn set_hidden(path: String, hidden: bool) -> Result<String, String> {
+    let target = expand_path(&path)?;
+    let updated =
+        tauri::async_runtime::spawn_blocking(move || fs_utils::set_hidden(&target, hidden))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+    Ok(updated.to_string_lossy().to_string())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchRenameItem {
+    pub path: String,
+    pub new_name: String,
+}
+
+/// Rename several items in place. With `plan`, nothing is touched and the planned
+/// renames (including conflict-resolved names) are returned for confirmation.
+#[command]
+pub async fn batch_rename(
+    items: Vec<BatchRenameItem>,
+    plan: Option<bool>,
+) -> Result<file_plan::FileOperationResult, String> {
+    let mut requests = Vec::with_capacity(items.len());
+    for item in items {
+        let name = item.new_name.trim();
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            return Err(format!("Invalid name: {}", item.new_name));
+        }
+        let source = expand_path(&item.path)?;
+        let parent = source
+            .parent()
+            .ok_or_else(|| format!("Cannot rename {}", source.display()))?
+            .to_path_buf();
+        requests.push(file_plan::MoveRequest {
+            destination: parent.join(name),
+            source,
+        });
+    }
+
+    let dry_run = plan.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || file_plan::run(&requests, dry_run))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedItem {
+    pub old_path: String,
+    pub new_path: String,
+}
+
+/// The item called `name` in the same folder as `location`, along with the path the
+/// UI uses for it.
+fn sibling_location(location: &Location, name: &str) -> Result<(Location, String), String> {
+    if location.scheme() == "file" {
+        let path = expand_path(&location.to_path_string())?;
+        let parent = path
+            .parent()
+            .ok_or_else(|| format!("Cannot rename {}", path.display()))?;
+        let sibling = parent.join(name).to_string_lossy().to_string();
+        let target = LocationInput::Raw(sibling.clone()).into_location()?;
+        return Ok((target, sibling));
+    }
+    let parent = location
+        .parent()
+        .ok_or_else(|| format!("Cannot rename {}", location.raw()))?;
+    let target = Location::parse(&format!("{}/{}", parent.raw().trim_end_matches('/'), name))?;
+    let raw = target.raw().to_string();
+    Ok((target, raw))
+}
+
+/// Rename items to names built from `pattern` (see [`rename_pattern`]), numbered in
+/// the order given. Every name is worked out and checked for collisions before
+/// anything is renamed, and if a rename fails the ones already done are undone.
+/// Items may take each other's names, as in a swap or a chain. Local items go
+/// through the same planner as `batch_rename`. Items whose name doesn't change are
+/// left out of the result.
+#[command]
+pub async fn batch_rename_pattern(
+    paths: Vec<LocationInput>,
+    pattern: String,
+) -> Result<Vec<RenamedItem>, String> {
+    rename_pattern::validate_pattern(&pattern)?;
+    let count = paths.len();
+    let mut planned: Vec<PlannedRename> = Vec::with_capacity(count);
+
+    for (index, path) in paths.into_iter().enumerate() {
+        let (provider, from) = resolve_location(path)?;
+        if !provider.capabilities(&from).can_rename {
+            return Err(format!("Renaming isn't supported for {}", from.raw()));
+        }
+        let meta = provider.get_file_metadata(&from).await?;
+        let input = rename_pattern::PatternInput {
+            name: &meta.name,
+            is_directory: meta.is_directory,
+            modified: meta.modified.with_timezone(&Local),
+        };
+        let new_name = rename_pattern::expand_pattern(&pattern, &input, index + 1, count)?;
+        if new_name == meta.name {
+            continue;
+        }
+
+        let (to, new_path) = sibling_location(&from, &new_name)?;
+        planned.push(PlannedRename {
+            provider,
+            from,
+            to,
+            old_name: meta.name,
+            new_name,
+            item: RenamedItem {
+                old_path: meta.path,
+                new_path,
+            },
+        });
+    }
+
+    if planned.iter().all(|rename| rename.from.scheme() == "file") {
+        return tauri::async_runtime::spawn_blocking(move || rename_local_items(planned))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?;
+    }
+    rename_provider_items(planned).await
+}
+
+struct PlannedRename {
+    provider: ProviderRef,
+    from: Location,
+    to: Location,
+    old_name: String,
+    new_name: String,
+    item: RenamedItem,
+}
+
+fn duplicate_rename_error(new_name: &str) -> String {
+    format!("More than one item would be renamed to \"{}\"", new_name)
+}
+
+fn existing_item_error(rename: &PlannedRename) -> String {
+    format!(
+        "Renaming {} would replace an existing item: {}",
+        rename.old_name, rename.item.new_path
+    )
+}
+
+/// Local renames, planned and carried out by [`file_plan`]. A collision is an
+/// error rather than a numbered name, since the pattern decides the names.
+fn rename_local_items(planned: Vec<PlannedRename>) -> Result<Vec<RenamedItem>, String> {
+    let requests = planned
+        .iter()
+        .map(|rename| {
+            Ok(file_plan::MoveRequest {
+                source: expand_path(&rename.item.old_path)?,
+                destination: PathBuf::from(&rename.item.new_path),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let actions = file_plan::plan_moves(&requests)?;
+    for (action, rename) in actions.iter().zip(&planned) {
+        match action.conflict {
+            Some(file_plan::PlanConflict::DuplicateTarget) => {
+                return Err(duplicate_rename_error(&rename.new_name))
+            }
+            Some(file_plan::PlanConflict::DestinationExists) => {
+                return Err(existing_item_error(rename))
+            }
+            Some(file_plan::PlanConflict::SourceMissing) => {
+                return Err(format!("Item not found: {}", rename.item.old_path))
+            }
+            _ => {}
+        }
+    }
+
+    let failures = file_plan::execute_plan(&actions, |_, _| {});
+    let Some(failure) = failures.first() else {
+        return Ok(planned.into_iter().map(|rename| rename.item).collect());
+    };
+
+    // Undo the renames that went through, through the planner again so a swap
+    // unwinds as cleanly as it was applied
+    let undo: Vec<file_plan::MoveRequest> = actions
+        .iter()
+        .filter(|action| failures.iter().all(|f| f.source != action.source))
+        .filter_map(|action| {
+            Some(file_plan::MoveRequest {
+                source: PathBuf::from(action.destination.as_ref()?),
+                destination: PathBuf::from(&action.source),
+            })
+        })
+        .collect();
+    let undo_failures = file_plan::plan_moves(&undo)
+        .map(|actions| file_plan::execute_plan(&actions, |_, _| {}))
+        .unwrap_or_else(|error| {
+            vec![file_plan::PlanFailure {
+                source: String::new(),
+                error,
+            }]
+        });
+    let mut message = format!("Failed to rename {}: {}", failure.source, failure.error);
+    if !undo_failures.is_empty() {
+        let errors: Vec<String> = undo_failures
+            .iter()
+            .map(|f| format!("{}: {}", f.source, f.error))
+            .collect();
+        message.push_str(&format!(
+            ". Some renames could not be undone: {}",
+            errors.join("; ")
+        ));
+    }
+    Err(message)
+}
+
+/// Renames through a location provider. Targets are compared without case, since
+/// most remote servers fold it. When an item takes a name another item is giving
+/// up, every item is first moved to a temporary name so none of them collide.
+async fn rename_provider_items(planned: Vec<PlannedRename>) -> Result<Vec<RenamedItem>, String> {
+    let key = |location: &Location| location.raw().to_lowercase();
+    let sources: HashSet<String> = planned.iter().map(|rename| key(&rename.from)).collect();
+    let mut claimed: HashSet<String> = HashSet::new();
+    let mut chained = false;
+
+    for rename in &planned {
+        let target = key(&rename.to);
+        if !claimed.insert(target.clone()) {
+            return Err(duplicate_rename_error(&rename.new_name));
+        }
+        if sources.contains(&target) {
+            // Freed by the item renamed away from it (or a case-only rename)
+            chained |= target != key(&rename.from);
+            continue;
+        }
+        if rename.provider.get_file_metadata(&rename.to).await.is_ok() {
+            return Err(existing_item_error(rename));
+        }
+    }
+
+    // (item, from, to) for every step, in order, so a failure can be undone
+    let mut steps: Vec<(&PlannedRename, Location, Location)> = Vec::new();
+    if chained {
+        let mut final_steps = Vec::with_capacity(planned.len());
+        for (index, rename) in planned.iter().enumerate() {
+            let temp_name = format!(".{}.marlin-rename-{}", rename.old_name, index);
+            let (temp, _) = sibling_location(&rename.from, &temp_name)?;
+            steps.push((rename, rename.from.clone(), temp.clone()));
+            final_steps.push((rename, temp, rename.to.clone()));
+        }
+        steps.extend(final_steps);
+    } else {
+        steps.extend(
+            planned
+                .iter()
+                .map(|rename| (rename, rename.from.clone(), rename.to.clone())),
+        );
+    }
+
+    for (done, (rename, from, to)) in steps.iter().enumerate() {
+        if let Err(err) = rename.provider.rename(from, to).await {
+            let mut rollback_errors = Vec::new();
+            for (applied, from, to) in steps[..done].iter().rev() {
+                if let Err(rollback_err) = applied.provider.rename(to, from).await {
+                    rollback_errors.push(format!("{}: {}", to.raw(), rollback_err));
+                }
+            }
+            let mut message = format!("Failed to rename {}: {}", rename.item.old_path, err);
+            if !rollback_errors.is_empty() {
+                message.push_str(&format!(
+                    ". Some renames could not be undone: {}",
+                    rollback_errors.join("; ")
+                ));
+            }
+            return Err(message);
+        }
+    }
+
+    Ok(planned.into_iter().map(|rename| rename.item).collect())
+}
+
+/// Move local items into `dest_dir`, keeping their names. Supports `plan` like
+/// `batch_rename`.
+#[command]
+pub async fn move_files(
+    paths: Vec<String>,
+    dest_dir: String,
+    plan: Option<bool>,
+) -> Result<file_plan::FileOperationResult, String> {
+    let dest_dir = expand_path(&dest_dir)?;
+    if !dest_dir.is_dir() {
+        return Err(format!("Not a directory: {}", dest_dir.display()));
+    }
+    let mut requests = Vec::with_capacity(paths.len());
+    for path in paths {
+        let source = expand_path(&path)?;
+        let name = source
+            .file_name()
+            .ok_or_else(|| format!("Cannot move {}", source.display()))?
+            .to_owned();
+        requests.push(file_plan::MoveRequest {
+            destination: dest_dir.join(name),
+            source,
+        });
+    }
+
+    let dry_run = plan.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || file_plan::run(&requests, dry_run))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OrganizeProgressPayload {
+    request_id: String,
+    done: usize,
+    total: usize,
+    finished: bool,
+}
+
+async fn run_organize(
+    app: AppHandle,
+    path: LocationInput,
+    strategy: OrganizeStrategy,
+    categories: HashMap<String, String>,
+    plan: Option<bool>,
+    request_id: Option<String>,
+) -> Result<file_plan::FileOperationResult, String> {
+    let (_, location) = resolve_location(path)?;
+    if location.scheme() != "file" {
+        return Err("Organizing is only supported for local folders".to_string());
+    }
+    let dir = expand_path(&location.to_path_string())?;
+    let dry_run = plan.unwrap_or(false);
+    let request_id = request_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let requests = organize::plan_requests(&dir, strategy, &categories)?;
+        let mut last_emit: Option<Instant> = None;
+        file_plan::run_with_progress(&requests, dry_run, |done, total| {
+            let finished = done == total;
+            if !finished && last_emit.is_some_and(|t| t.elapsed() < ORGANIZE_EMIT_INTERVAL) {
+                return;
+            }
+            last_emit = Some(Instant::now());
+            let payload = OrganizeProgressPayload {
+                request_id: request_id.clone(),
+                done,
+                total,
+                finished,
+            };
+            if let Err(err) = app.emit(ORGANIZE_PROGRESS_EVENT, payload) {
+                warn!("Failed to emit organize progress event: {err}");
+            }
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Sort the files directly inside `path` into subfolders. `categories` overrides the
+/// extension to folder mapping used by `ByExtension`. Only local folders are
+/// supported; progress is emitted as `organize-progress` while executing.
+#[command]
+pub async fn organize_directory(
+    app: AppHandle,
+    path: LocationInput,
+    strategy: OrganizeStrategy,
+    categories: Option<HashMap<String, String>>,
+    plan: Option<bool>,
+    request_id: Option<String>,
+) -> Result<file_plan::FileOperationResult, String> {
+    let categories = categories.unwrap_or_default();
+    run_organize(app, path, strategy, categories, plan, request_id).await
+}
+
 #[command]
-pub async fn copy_file(from_path: LocationInput, to_path: LocationInput) -> Result<(), String> {
+pub async fn copy_file(
+    app: AppHandle,
+    from_path: LocationInput,
+    to_path: LocationInput,
+    request_id: Option<String>,
+) -> Result<(), String> {
     let (from_provider, from_location) = resolve_location(from_path)?;
-    let (_, to_location) = resolve_location(to_path)?;
+    let (to_provider, to_location) = resolve_location(to_path)?;
 
     if from_location.scheme() != to_location.scheme() {
-        return Err("Copying across different providers is not yet supported".to_string());
+        let transfer_id = request_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        return transfer_across_providers(
+            &app,
+            &from_provider,
+            &from_location,
+            &to_provider,
+            &to_location,
+            false,
+            transfer_id,
+        )
+        .await;
     }
 
     let capabilities = from_provider.capabilities(&from_location);
@@ -2357,24 +4036,1255 @@ pub async fn copy_file(from_path: LocationInput, to_path: LocationInput) -> Resu
         return Err("Provider does not support copy operations".to_string());
     }
 
+    // Local directory copies report progress and can be cancelled via `cancel_copy_tree`
+    if from_location.scheme() == "file" {
+        let from = expand_path(&from_location.to_path_string())?;
+        if from.is_dir() {
+            let to = expand_path(&to_location.to_path_string())?;
+            let request_id = request_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+            let result = run_copy_tree(&app, from, to, request_id).await?;
+            if result.cancelled {
+                return Err("Copy cancelled".to_string());
+            }
+            return Ok(());
+        }
+    }
+
     from_provider.copy(&from_location, &to_location).await
 }
 
+/// Name for the `attempt`th duplicate of `name`, Finder style: "a copy.txt",
+/// "a copy 2.txt", ... Folders keep any dot in their name. Duplicating a copy counts
+/// on from its base name rather than stacking "copy copy".
+fn duplicate_name(name: &str, is_directory: bool, attempt: usize) -> String {
+    let suffix = if attempt == 1 {
+        " copy".to_string()
+    } else {
+        format!(" copy {attempt}")
+    };
+    let path = Path::new(name);
+    match (is_directory, path.file_stem(), path.extension()) {
+        (false, Some(stem), Some(ext)) => format!(
+            "{}{suffix}.{}",
+            strip_copy_suffix(&stem.to_string_lossy()),
+            ext.to_string_lossy()
+        ),
+        _ => format!("{}{suffix}", strip_copy_suffix(name)),
+    }
+}
+
+/// `stem` without a trailing " copy" or " copy N".
+fn strip_copy_suffix(stem: &str) -> &str {
+    let without_number = stem
+        .rsplit_once(' ')
+        .filter(|(_, number)| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+        .map_or(stem, |(rest, _)| rest);
+    match without_number.strip_suffix(" copy") {
+        Some(base) if !base.is_empty() => base,
+        _ => stem,
+    }
+}
+
+/// Copy `path` alongside itself as "name copy", "name copy 2", ... and return the new
+/// path. Works on any provider that can copy; local copies keep the original's mtime.
 #[command]
-pub async fn move_file(from_path: LocationInput, to_path: LocationInput) -> Result<(), String> {
-    let (from_provider, from_location) = resolve_location(from_path)?;
-    let (_, to_location) = resolve_location(to_path)?;
+pub async fn duplicate_file(path: LocationInput) -> Result<String, String> {
+    let (provider, location) = resolve_location(path)?;
+    let capabilities = provider.capabilities(&location);
+    if !capabilities.can_copy {
+        return Err(format!(
+            "{} does not support copying files",
+            capabilities.display_name
+        ));
+    }
 
-    if from_location.scheme() != to_location.scheme() {
-        return Err("Moving across different providers is not yet supported".to_string());
+    let source = provider.get_file_metadata(&location).await?;
+    let dir_raw = if location.scheme() == "file" {
+        let path = expand_path(&location.to_path_string())?;
+        path.parent()
+            .ok_or_else(|| format!("Cannot duplicate {}", path.display()))?
+            .to_string_lossy()
+            .to_string()
+    } else {
+        location
+            .parent()
+            .ok_or_else(|| format!("Cannot duplicate {}", location.raw()))?
+            .raw()
+            .to_string()
+    };
+
+    for attempt in 1..1000usize {
+        let name = duplicate_name(&source.name, source.is_directory, attempt);
+        let candidate_raw = join_dest_raw(location.scheme(), &dir_raw, &name);
+        let (_, candidate) = resolve_location(LocationInput::Raw(candidate_raw.clone()))?;
+        if provider.get_file_metadata(&candidate).await.is_ok() {
+            continue;
+        }
+
+        provider.copy(&location, &candidate).await?;
+        if location.scheme() == "file" && !source.is_directory {
+            let modified = filetime::FileTime::from_system_time(source.modified.into());
+            let _ = filetime::set_file_mtime(&candidate_raw, modified);
+        }
+        return Ok(candidate_raw);
     }
 
-    let capabilities = from_provider.capabilities(&from_location);
-    if !capabilities.can_move {
-        return Err("Provider does not support move operations".to_string());
+    Err("Unable to allocate unique duplicate name after 999 attempts".to_string())
+}
+
+/// Free bytes on the volume holding `dest`. Only local, SMB and MTP destinations
+/// report free space; anything else gives `None`.
+async fn available_bytes_at(dest: &Location) -> Result<Option<u64>, String> {
+    let available = match dest.scheme() {
+        "file" => {
+            let path = expand_path(&dest.to_path_string())?;
+            tauri::async_runtime::spawn_blocking(move || fs_utils::get_disk_usage(&path))
+                .await
+                .map_err(|e| format!("Task join error: {}", e))??
+                .available_bytes
+        }
+        #[cfg(not(target_os = "windows"))]
+        "smb" => {
+            let authority = dest
+                .authority()
+                .ok_or_else(|| "SMB destination missing server".to_string())?;
+            let (hostname, share, path) =
+                crate::locations::smb::parse_smb_path(authority, dest.path())?;
+            let (_, available) = tauri::async_runtime::spawn_blocking(move || {
+                crate::locations::smb::disk_usage_on_smb(&hostname, &share, &path)
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+            available
+        }
+        "mtp" => {
+            let raw = dest.raw().to_string();
+            let usage = tauri::async_runtime::spawn_blocking(move || {
+                crate::locations::mtp::disk_usage_on_mtp(&raw)
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+            match usage {
+                Some((_, available)) => available,
+                None => return Ok(None),
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(available))
+}
+
+/// Bytes copying a source on a provider writes: the file's size, or for a folder the
+/// total of every file listed under it.
+async fn remote_source_size(
+    provider: &ProviderRef,
+    location: &Location,
+    metadata: &FileItem,
+) -> Result<u64, String> {
+    if !metadata.is_directory {
+        return Ok(metadata.size);
+    }
+    Ok(collect_transfer_tree(provider, location, false)
+        .await?
+        .iter()
+        .filter(|item| !item.is_directory)
+        .map(|item| item.size)
+        .sum())
+}
+
+/// Fail with `ENOSPC` when `sources` won't fit in the free space at `dest`. Copies to
+/// destinations that don't report free space aren't checked.
+async fn ensure_space_for_copy(
+    sources: &[(ProviderRef, Location)],
+    dest: &Location,
+) -> Result<(), String> {
+    let Some(available) = available_bytes_at(dest).await? else {
+        return Ok(());
+    };
+
+    let mut required: u64 = 0;
+    for (provider, location) in sources {
+        let size = if location.scheme() == "file" {
+            let path = expand_path(&location.to_path_string())?;
+            tauri::async_runtime::spawn_blocking(move || fs_utils::tree_size(&path))
+                .await
+                .map_err(|e| format!("Task join error: {}", e))?
+        } else {
+            let metadata = provider.get_file_metadata(location).await?;
+            remote_source_size(provider, location, &metadata).await?
+        };
+        required = required.saturating_add(size);
+    }
+
+    if required > available {
+        return Err(format_error(
+            error_codes::ENOSPC,
+            &format!(
+                "Not enough free space: the copy needs {} bytes but only {} are available",
+                required, available
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Copy `sources` into the folder `dest_dir`, applying `conflict` to every file whose
+/// name is already taken there, and report what happened to each. Folders merge into
+/// same-named folders so the policy is applied file by file. With `check_space`, a
+/// local or SMB destination without room for the sources fails with `ENOSPC` before
+/// anything is copied. Sources must live on the same provider as `dest_dir`.
+#[command]
+pub async fn copy_with_options(
+    sources: Vec<String>,
+    dest_dir: String,
+    conflict: ConflictPolicy,
+    check_space: bool,
+) -> Result<CopyReport, String> {
+    if sources.is_empty() {
+        return Err("No sources provided".to_string());
+    }
+
+    let (provider, dest_location) = resolve_location(LocationInput::Raw(dest_dir))?;
+    let scheme = dest_location.scheme().to_string();
+    let capabilities = provider.capabilities(&dest_location);
+    if !capabilities.can_copy {
+        return Err(format!(
+            "{} does not support copying files",
+            capabilities.display_name
+        ));
+    }
+
+    let mut resolved = Vec::with_capacity(sources.len());
+    for source in sources {
+        let (source_provider, location) = resolve_location(LocationInput::Raw(source))?;
+        if location.scheme() != scheme {
+            return Err(format_error(
+                error_codes::EUNSUPPORTED,
+                "Copying with a conflict policy only works within one provider",
+            ));
+        }
+        resolved.push((source_provider, location));
+    }
+
+    if check_space {
+        ensure_space_for_copy(&resolved, &dest_location).await?;
+    }
+
+    if scheme == "file" {
+        let dest = expand_path(&dest_location.to_path_string())?;
+        let sources = resolved
+            .iter()
+            .map(|(_, location)| expand_path(&location.to_path_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        return tauri::async_runtime::spawn_blocking(move || {
+            let mut report = CopyReport::default();
+            for source in &sources {
+                let name = source
+                    .file_name()
+                    .ok_or_else(|| format!("Cannot copy {}", source.display()))?;
+                if dest.starts_with(source) {
+                    return Err("Cannot copy a folder into itself".to_string());
+                }
+                fs_utils::copy_with_policy(source, &dest.join(name), conflict, &mut report)?;
+            }
+            Ok(report)
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?;
+    }
+
+    // Remote folders whose name is taken by a folder are walked so that, like local
+    // copies, the policy applies to each file rather than the whole tree
+    let dest_raw = dest_location.raw().to_string();
+    let mut report = CopyReport::default();
+    let mut pending: VecDeque<(Location, String)> = resolved
+        .into_iter()
+        .map(|(_, location)| (location, dest_raw.clone()))
+        .collect();
+    while let Some((source, dir_raw)) = pending.pop_front() {
+        let item = provider.get_file_metadata(&source).await?;
+        let target_raw = join_dest_raw(&scheme, &dir_raw, &item.name);
+        let (_, target) = resolve_location(LocationInput::Raw(target_raw.clone()))?;
+        let existing = match provider.get_file_metadata(&target).await {
+            Ok(existing) => existing,
+            Err(_) => {
+                provider.copy(&source, &target).await?;
+                report.copied.push(target_raw);
+                continue;
+            }
+        };
+
+        // Copying something onto itself leaves nothing to merge or overwrite
+        let onto_itself = target.raw() == source.raw();
+        if onto_itself && conflict != ConflictPolicy::Rename {
+            report.skipped.push(target_raw);
+            continue;
+        }
+        if item.is_directory && existing.is_directory && !onto_itself {
+            for entry in provider.read_directory(&source).await?.entries {
+                pending.push_back((Location::parse(&entry.path)?, target_raw.clone()));
+            }
+            continue;
+        }
+
+        match conflict {
+            ConflictPolicy::Skip => report.skipped.push(target_raw),
+            ConflictPolicy::Overwrite => {
+                replace_with_remote_copy(
+                    &provider, &scheme, &dir_raw, &source, &target, &item.name,
+                )
+                .await?;
+                report.overwritten.push(target_raw);
+            }
+            ConflictPolicy::Rename => {
+                let mut renamed = None;
+                for index in 2..1000usize {
+                    let candidate_raw =
+                        join_dest_raw(&scheme, &dir_raw, &numbered_name_variant(&item.name, index));
+                    let (_, candidate) =
+                        resolve_location(LocationInput::Raw(candidate_raw.clone()))?;
+                    if provider.get_file_metadata(&candidate).await.is_err() {
+                        provider.copy(&source, &candidate).await?;
+                        renamed = Some(candidate_raw);
+                        break;
+                    }
+                }
+                let to = renamed.ok_or_else(|| {
+                    format!(
+                        "Unable to find a free name for {} after 999 attempts",
+                        item.name
+                    )
+                })?;
+                report.renamed.push(fs_utils::RenamedCopy {
+                    from: target_raw,
+                    to,
+                });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Copy `source` over the existing `target`, which is called `name` inside `dir_raw`.
+/// The copy goes to a hidden sibling first and is renamed into place once complete, so
+/// a failed, cancelled or dropped copy leaves `target` untouched.
+async fn replace_with_remote_copy(
+    provider: &ProviderRef,
+    scheme: &str,
+    dir_raw: &str,
+    source: &Location,
+    target: &Location,
+    name: &str,
+) -> Result<(), String> {
+    let sibling = |purpose: &str| {
+        let raw = join_dest_raw(
+            scheme,
+            dir_raw,
+            &format!(".{}.marlin-{}-{}", name, purpose, Uuid::new_v4().simple()),
+        );
+        resolve_location(LocationInput::Raw(raw)).map(|(_, location)| location)
+    };
+
+    let staged = sibling("copy")?;
+    if let Err(err) = provider.copy(source, &staged).await {
+        let _ = provider.delete(&staged).await;
+        return Err(err);
+    }
+
+    // Not every provider can rename over an existing entry, so the old one is set
+    // aside first and put back if the new one can't take its place
+    let replaced = sibling("replaced")?;
+    if let Err(err) = provider.rename(target, &replaced).await {
+        let _ = provider.delete(&staged).await;
+        return Err(err);
+    }
+    if let Err(err) = provider.rename(&staged, target).await {
+        let _ = provider.rename(&replaced, target).await;
+        let _ = provider.delete(&staged).await;
+        return Err(err);
+    }
+    if let Err(err) = provider.delete(&replaced).await {
+        warn!("Failed to remove replaced {}: {}", replaced.raw(), err);
+    }
+    Ok(())
+}
+
+/// Recursively copy a local directory, emitting `copy-tree-progress` events tagged with
+/// `request_id`. Cancel with `cancel_copy_tree`.
+#[command]
+pub async fn copy_tree(
+    app: AppHandle,
+    from: String,
+    to: String,
+    request_id: String,
+) -> Result<CopyTreeResult, String> {
+    let from = expand_path(&from)?;
+    let to = expand_path(&to)?;
+    if !from.exists() {
+        return Err(format_error(
+            error_codes::ENOENT,
+            &format!("Path does not exist: {}", from.display()),
+        ));
+    }
+    run_copy_tree(&app, from, to, request_id).await
+}
+
+/// Stop a running `copy_tree`. With `rollback`, everything the copy created is removed.
+#[command]
+pub fn cancel_copy_tree(
+    state: tauri::State<'_, CopyTreeState>,
+    request_id: String,
+    rollback: Option<bool>,
+) -> Result<(), String> {
+    let handle = {
+        let guard = state
+            .tasks
+            .lock()
+            .map_err(|_| "Failed to access copy state".to_string())?;
+        guard.get(request_id.trim()).cloned()
+    };
+
+    if let Some(task) = handle {
+        task.rollback
+            .store(rollback.unwrap_or(false), Ordering::SeqCst);
+        task.cancel_flag.store(true, Ordering::SeqCst);
+    }
+
+    Ok(())
+}
+
+async fn run_copy_tree(
+    app: &AppHandle,
+    from: PathBuf,
+    to: PathBuf,
+    request_id: String,
+) -> Result<CopyTreeResult, String> {
+    if to.starts_with(&from) {
+        return Err("Cannot copy a folder into itself".to_string());
+    }
+
+    let handle = CopyTreeHandle {
+        cancel_flag: Arc::new(AtomicBool::new(false)),
+        rollback: Arc::new(AtomicBool::new(false)),
+    };
+    {
+        let state = app.state::<CopyTreeState>();
+        let mut guard = state
+            .tasks
+            .lock()
+            .map_err(|_| "Failed to access copy state".to_string())?;
+        if let Some(existing) = guard.insert(request_id.clone(), handle.clone()) {
+            existing.cancel_flag.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let emit = {
+        let app = app.clone();
+        let request_id = request_id.clone();
+        move |progress: &TreeCopyProgress,
+              finished: bool,
+              cancelled: bool,
+              rolled_back: bool,
+              error: Option<String>| {
+            let payload = CopyTreeProgressPayload {
+                request_id: request_id.clone(),
+                progress: progress.clone(),
+                finished,
+                cancelled,
+                rolled_back,
+                error,
+            };
+            if let Err(err) = app.emit(COPY_TREE_PROGRESS_EVENT, payload) {
+                warn!("Failed to emit copy progress event: {err}");
+            }
+        }
+    };
+
+    let cancel_flag = handle.cancel_flag.clone();
+    let emit_for_task = emit.clone();
+    let join_result = tauri::async_runtime::spawn_blocking(move || {
+        let mut journal = TreeCopyJournal::default();
+        let mut last = TreeCopyProgress::default();
+        let mut last_emit: Option<Instant> = None;
+        let outcome =
+            fs_utils::copy_tree_with_progress(&from, &to, &cancel_flag, &mut journal, |p| {
+                last = p.clone();
+                if last_emit.map_or(true, |t| t.elapsed() >= COPY_TREE_EMIT_INTERVAL) {
+                    last_emit = Some(Instant::now());
+                    emit_for_task(p, false, false, false, None);
+                }
+            });
+        (outcome, journal, last)
+    })
+    .await;
+
+    if let Ok(mut guard) = app.state::<CopyTreeState>().tasks.lock() {
+        guard.remove(&request_id);
+    }
+
+    let (outcome, mut journal, last) =
+        join_result.map_err(|e| format!("Task join error: {}", e))?;
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            emit(&last, true, false, false, Some(err.clone()));
+            return Err(err);
+        }
+    };
+
+    let cancelled = outcome == TreeCopyOutcome::Cancelled;
+    let mut rolled_back = false;
+    if cancelled && handle.rollback.load(Ordering::SeqCst) {
+        match journal.rollback() {
+            Ok(()) => rolled_back = true,
+            Err(err) => warn!("Failed to roll back cancelled copy: {err}"),
+        }
+    } else if cancelled {
+        // Keep the files that finished copying, including their replacements
+        if let Err(err) = journal.commit() {
+            warn!("Failed to finish replacing files after cancel: {err}");
+        }
+    }
+
+    emit(&last, true, cancelled, rolled_back, None);
+    Ok(CopyTreeResult {
+        cancelled,
+        rolled_back,
+        files_copied: last.files_done,
+        bytes_copied: last.bytes_done,
+    })
+}
+
+#[command]
+pub async fn move_file(
+    app: AppHandle,
+    from_path: LocationInput,
+    to_path: LocationInput,
+    request_id: Option<String>,
+) -> Result<(), String> {
+    let (from_provider, from_location) = resolve_location(from_path)?;
+    let (to_provider, to_location) = resolve_location(to_path)?;
+
+    if from_location.scheme() != to_location.scheme() {
+        let transfer_id = request_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+        return transfer_across_providers(
+            &app,
+            &from_provider,
+            &from_location,
+            &to_provider,
+            &to_location,
+            true,
+            transfer_id,
+        )
+        .await;
+    }
+
+    let capabilities = from_provider.capabilities(&from_location);
+    if !capabilities.can_move {
+        return Err("Provider does not support move operations".to_string());
+    }
+
+    from_provider.move_item(&from_location, &to_location).await
+}
+
+/// Stop an upload started by a cross-provider `copy_file` or `move_file` with this
+/// request id. The partially uploaded file is removed and the source is left alone.
+#[command]
+pub fn cancel_upload(
+    state: tauri::State<'_, UploadState>,
+    transfer_id: String,
+) -> Result<(), String> {
+    let guard = state
+        .transfers
+        .lock()
+        .map_err(|_| "Failed to access upload state".to_string())?;
+    if let Some(flag) = guard.get(transfer_id.trim()) {
+        flag.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+fn emit_upload_progress<R: Runtime>(app: &AppHandle<R>, payload: UploadProgressPayload) {
+    if let Err(err) = app.emit(UPLOAD_PROGRESS_EVENT, payload) {
+        warn!("Failed to emit upload progress event: {err}");
+    }
+}
+
+/// Where one file sits in a folder transfer, so its upload can report progress for
+/// the whole tree
+#[derive(Debug, Clone)]
+struct TreeTransferStep {
+    file_name: String,
+    /// Bytes of the files already sent
+    bytes_before: u64,
+    bytes_total: u64,
+    files_done: usize,
+    files_total: usize,
+}
+
+impl TreeTransferStep {
+    fn payload(&self, transfer_id: &str, transferred: u64) -> UploadProgressPayload {
+        let bytes_transferred = self.bytes_before + transferred;
+        UploadProgressPayload {
+            transfer_id: transfer_id.to_string(),
+            bytes_transferred,
+            // Listed sizes can lag behind the files themselves
+            bytes_total: self.bytes_total.max(bytes_transferred),
+            finished: false,
+            cancelled: false,
+            error: None,
+            current_file: Some(self.file_name.clone()),
+            files_done: Some(self.files_done),
+            files_total: Some(self.files_total),
+        }
+    }
+}
+
+/// Progress for an upload, sent as `upload-progress` events tagged with `transfer_id`
+/// no more often than `UPLOAD_PROGRESS_EMIT_INTERVAL`. With a `step` the bytes count
+/// towards the folder transfer the file is part of.
+fn upload_progress<R: Runtime>(
+    app: &AppHandle<R>,
+    transfer_id: &str,
+    total: u64,
+    cancel_flag: Arc<AtomicBool>,
+    step: Option<TreeTransferStep>,
+) -> TransferProgress {
+    let app = app.clone();
+    let transfer_id = transfer_id.to_string();
+    let last_emit: Mutex<Option<Instant>> = Mutex::new(None);
+    TransferProgress::new(total, cancel_flag, move |transferred, total| {
+        let Ok(mut last_emit) = last_emit.lock() else {
+            return;
+        };
+        if last_emit.is_some_and(|t| t.elapsed() < UPLOAD_PROGRESS_EMIT_INTERVAL) {
+            return;
+        }
+        *last_emit = Some(Instant::now());
+        let payload = match &step {
+            Some(step) => step.payload(&transfer_id, transferred),
+            None => UploadProgressPayload {
+                transfer_id: transfer_id.clone(),
+                bytes_transferred: transferred,
+                bytes_total: total,
+                finished: false,
+                cancelled: false,
+                error: None,
+                current_file: None,
+                files_done: None,
+                files_total: None,
+            },
+        };
+        emit_upload_progress(&app, payload);
+    })
+}
+
+/// Register a cross-provider transfer so `cancel_upload` can stop it. A transfer
+/// already running under the same id is cancelled.
+fn register_upload(app: &AppHandle, transfer_id: &str) -> Result<Arc<AtomicBool>, String> {
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let state = app.state::<UploadState>();
+    let mut guard = state
+        .transfers
+        .lock()
+        .map_err(|_| "Failed to access upload state".to_string())?;
+    if let Some(existing) = guard.insert(transfer_id.to_string(), cancel_flag.clone()) {
+        existing.store(true, Ordering::SeqCst);
+    }
+    Ok(cancel_flag)
+}
+
+fn unregister_upload(app: &AppHandle, transfer_id: &str, cancel_flag: &Arc<AtomicBool>) {
+    if let Ok(mut guard) = app.state::<UploadState>().transfers.lock() {
+        if guard
+            .get(transfer_id)
+            .is_some_and(|flag| Arc::ptr_eq(flag, cancel_flag))
+        {
+            guard.remove(transfer_id);
+        }
+    }
+}
+
+/// Check that `uploaded_name` landed in `dest_dir` as a file of `expected_size` bytes
+/// and return its location.
+async fn verify_upload(
+    to_provider: &ProviderRef,
+    dest_scheme: &str,
+    dest_dir_raw: &str,
+    uploaded_name: &str,
+    expected_size: u64,
+) -> Result<Location, String> {
+    let uploaded_raw = join_dest_raw(dest_scheme, dest_dir_raw, uploaded_name);
+    let (_, uploaded_location) = resolve_location(LocationInput::Raw(uploaded_raw))?;
+    let uploaded = to_provider.get_file_metadata(&uploaded_location).await?;
+    if uploaded.is_directory || uploaded.size != expected_size {
+        return Err(format!(
+            "Upload of {} could not be verified ({} of {} bytes)",
+            uploaded_name, uploaded.size, expected_size
+        ));
+    }
+    Ok(uploaded_location)
+}
+
+/// Copy or move a file or folder between two different providers by staging files
+/// in a local file. For moves the source is only deleted after the uploaded copy is
+/// confirmed to exist with the same size. The upload reports progress under
+/// `transfer_id` and can be stopped with `cancel_upload`.
+async fn transfer_across_providers(
+    app: &AppHandle,
+    from_provider: &ProviderRef,
+    from_location: &Location,
+    to_provider: &ProviderRef,
+    to_location: &Location,
+    remove_source: bool,
+    transfer_id: String,
+) -> Result<(), String> {
+    let from_caps = from_provider.capabilities(from_location);
+    let to_caps = to_provider.capabilities(to_location);
+    if !from_caps.can_read {
+        return Err(format!(
+            "{} does not support reading files",
+            from_caps.display_name
+        ));
+    }
+    if remove_source && !from_caps.can_delete {
+        return Err(format!(
+            "{} does not support deleting files, so they can only be copied out of it",
+            from_caps.display_name
+        ));
+    }
+    if !to_caps.can_write {
+        return Err(format!(
+            "{} does not support writing files",
+            to_caps.display_name
+        ));
+    }
+
+    let source = from_provider.get_file_metadata(from_location).await?;
+    let name = filename_from_location(to_location)
+        .ok_or_else(|| format!("Invalid destination: {}", to_location.raw()))?;
+    let dest_dir_raw = if to_location.scheme() == "file" {
+        let path = expand_path(&to_location.to_path_string())?;
+        path.parent()
+            .ok_or_else(|| format!("Invalid destination: {}", path.display()))?
+            .to_string_lossy()
+            .to_string()
+    } else {
+        to_location
+            .parent()
+            .ok_or_else(|| format!("Invalid destination: {}", to_location.raw()))?
+            .raw()
+            .to_string()
+    };
+    let (_, dest_dir) = resolve_location(LocationInput::Raw(dest_dir_raw.clone()))?;
+
+    if source.is_directory {
+        let cancel_flag = register_upload(app, &transfer_id)?;
+        let result = transfer_tree_across_providers(
+            app,
+            TreeTransfer {
+                from_provider,
+                from_location,
+                to_provider,
+                dest_dir: &dest_dir,
+                dest_dir_raw: &dest_dir_raw,
+                name: &name,
+                remove_source,
+                transfer_id: &transfer_id,
+                cancel_flag: &cancel_flag,
+            },
+        )
+        .await;
+        unregister_upload(app, &transfer_id, &cancel_flag);
+        return result;
+    }
+
+    let (local_path, is_temp) = fetch_to_local(from_location, &source.name, None).await?;
+    let cancel_flag = register_upload(app, &transfer_id)?;
+    let uploaded = async {
+        let local_size = tokio::fs::metadata(&local_path)
+            .await
+            .map_err(|e| format!("Failed to read downloaded file: {}", e))?
+            .len();
+        let progress = upload_progress(app, &transfer_id, local_size, cancel_flag.clone(), None);
+        let result = upload_local_file(&local_path, &dest_dir, &name, Some(&progress)).await;
+        let cancelled = cancel_flag.load(Ordering::SeqCst);
+        emit_upload_progress(
+            app,
+            UploadProgressPayload {
+                transfer_id: transfer_id.clone(),
+                bytes_transferred: if result.is_ok() { local_size } else { 0 },
+                bytes_total: local_size,
+                finished: true,
+                cancelled,
+                error: result.as_ref().err().filter(|_| !cancelled).cloned(),
+                current_file: None,
+                files_done: None,
+                files_total: None,
+            },
+        );
+        let uploaded_name = result?;
+        verify_upload(
+            to_provider,
+            dest_dir.scheme(),
+            &dest_dir_raw,
+            &uploaded_name,
+            local_size,
+        )
+        .await
+    }
+    .await;
+    unregister_upload(app, &transfer_id, &cancel_flag);
+    if is_temp {
+        let _ = tokio::fs::remove_file(&local_path).await;
+    }
+    let uploaded_location = uploaded?;
+
+    if remove_source {
+        from_provider.delete(from_location).await.map_err(|e| {
+            format!(
+                "Copied to {} but failed to delete the original: {}",
+                uploaded_location.raw(),
+                e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// A folder being copied or moved to another provider
+struct TreeTransfer<'a> {
+    from_provider: &'a ProviderRef,
+    from_location: &'a Location,
+    to_provider: &'a ProviderRef,
+    /// Folder the copy is created in
+    dest_dir: &'a Location,
+    dest_dir_raw: &'a str,
+    name: &'a str,
+    remove_source: bool,
+    transfer_id: &'a str,
+    cancel_flag: &'a Arc<AtomicBool>,
+}
+
+/// Something inside a folder being transferred between providers
+struct TreeTransferItem {
+    source: Location,
+    /// Names from the transferred folder down to this item
+    relative: Vec<String>,
+    is_directory: bool,
+    size: u64,
+}
+
+/// Everything under `root`, listed with the provider's `read_directory`, parents
+/// before their children. Links to folders aren't followed: copies skip them, and
+/// moves refuse to start since deleting the source would lose them.
+async fn collect_transfer_tree(
+    provider: &ProviderRef,
+    root: &Location,
+    remove_source: bool,
+) -> Result<Vec<TreeTransferItem>, String> {
+    let mut items = Vec::new();
+    let mut pending = VecDeque::from([(root.clone(), Vec::new())]);
+    while let Some((folder, relative)) = pending.pop_front() {
+        let listing = provider.read_directory(&folder).await?;
+        for entry in listing.entries {
+            if entry.is_directory && entry.is_symlink {
+                if remove_source {
+                    return Err(format!(
+                        "{} is a link to a folder and can't be moved to another provider",
+                        entry.path
+                    ));
+                }
+                warn!(
+                    "Skipping linked folder {} in cross-provider copy",
+                    entry.path
+                );
+                continue;
+            }
+            let source = Location::parse(&entry.path)?;
+            let mut path = relative.clone();
+            path.push(entry.name);
+            if entry.is_directory {
+                pending.push_back((source.clone(), path.clone()));
+            }
+            items.push(TreeTransferItem {
+                source,
+                relative: path,
+                is_directory: entry.is_directory,
+                size: entry.size,
+            });
+        }
+    }
+    Ok(items)
+}
+
+/// Whether something already exists at `location`. Only a not-found answer means the
+/// name is free; any other failure, like a dropped connection, is returned so callers
+/// don't write over an item they couldn't see.
+async fn destination_exists(provider: &ProviderRef, location: &Location) -> Result<bool, String> {
+    match provider.get_file_metadata(location).await {
+        Ok(_) => Ok(true),
+        Err(err)
+            if matches!(
+                LocationError::from_message(err.as_str()),
+                LocationError::NotFound(_)
+            ) =>
+        {
+            Ok(false)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Recreate a folder on another provider: directories with `create_directory`, files
+/// through the same download/upload staging as single files. The copy gets a
+/// numbered name if `name` is taken. A move deletes the source, deepest items first,
+/// only once every file has been uploaded and verified; a failed or cancelled
+/// transfer leaves the source alone and the partial copy in place.
+async fn transfer_tree_across_providers<R: Runtime>(
+    app: &AppHandle<R>,
+    transfer: TreeTransfer<'_>,
+) -> Result<(), String> {
+    let TreeTransfer {
+        from_provider,
+        from_location,
+        to_provider,
+        dest_dir,
+        dest_dir_raw,
+        name,
+        remove_source,
+        transfer_id,
+        cancel_flag,
+    } = transfer;
+    let dest_scheme = dest_dir.scheme();
+
+    let items = collect_transfer_tree(from_provider, from_location, remove_source).await?;
+    let files_total = items.iter().filter(|item| !item.is_directory).count();
+    let bytes_total: u64 = items
+        .iter()
+        .filter(|item| !item.is_directory)
+        .map(|item| item.size)
+        .sum();
+
+    // Same numbering as uploads, without splitting a dotted folder name
+    let mut root_raw = None;
+    for i in 1..1000usize {
+        let candidate = if i == 1 {
+            name.to_string()
+        } else {
+            format!("{name} ({i})")
+        };
+        let raw = join_dest_raw(dest_scheme, dest_dir_raw, &candidate);
+        let (_, location) = resolve_location(LocationInput::Raw(raw.clone()))?;
+        if !destination_exists(to_provider, &location).await? {
+            to_provider.create_directory(&location).await?;
+            root_raw = Some(raw);
+            break;
+        }
+    }
+    let root_raw = root_raw
+        .ok_or_else(|| format!("Unable to allocate a unique destination name for {name}"))?;
+
+    let mut bytes_done = 0u64;
+    let mut files_done = 0usize;
+    let copied: Result<(), String> = async {
+        for item in &items {
+            if cancel_flag.load(Ordering::SeqCst) {
+                return Err(UPLOAD_CANCELLED.to_string());
+            }
+            let Some((item_name, parents)) = item.relative.split_last() else {
+                continue;
+            };
+            let parent_raw = parents.iter().fold(root_raw.clone(), |raw, part| {
+                join_dest_raw(dest_scheme, &raw, part)
+            });
+            if item.is_directory {
+                let raw = join_dest_raw(dest_scheme, &parent_raw, item_name);
+                let (_, location) = resolve_location(LocationInput::Raw(raw))?;
+                to_provider.create_directory(&location).await?;
+                continue;
+            }
+
+            let (_, parent) = resolve_location(LocationInput::Raw(parent_raw.clone()))?;
+            let step = TreeTransferStep {
+                file_name: item.relative.join("/"),
+                bytes_before: bytes_done,
+                bytes_total,
+                files_done,
+                files_total,
+            };
+            let (local_path, is_temp) = fetch_to_local(&item.source, item_name, None).await?;
+            let sent = async {
+                let local_size = tokio::fs::metadata(&local_path)
+                    .await
+                    .map_err(|e| format!("Failed to read downloaded file: {}", e))?
+                    .len();
+                let progress = upload_progress(
+                    app,
+                    transfer_id,
+                    local_size,
+                    cancel_flag.clone(),
+                    Some(step.clone()),
+                );
+                let uploaded_name =
+                    upload_local_file(&local_path, &parent, item_name, Some(&progress)).await?;
+                verify_upload(
+                    to_provider,
+                    dest_scheme,
+                    &parent_raw,
+                    &uploaded_name,
+                    local_size,
+                )
+                .await?;
+                Ok::<_, String>(local_size)
+            }
+            .await;
+            if is_temp {
+                let _ = tokio::fs::remove_file(&local_path).await;
+            }
+            let sent = sent?;
+            bytes_done += sent;
+            files_done += 1;
+            emit_upload_progress(
+                app,
+                TreeTransferStep { files_done, ..step }.payload(transfer_id, sent),
+            );
+        }
+        Ok(())
+    }
+    .await;
+
+    let cancelled = cancel_flag.load(Ordering::SeqCst);
+    emit_upload_progress(
+        app,
+        UploadProgressPayload {
+            transfer_id: transfer_id.to_string(),
+            bytes_transferred: bytes_done,
+            bytes_total: bytes_total.max(bytes_done),
+            finished: true,
+            cancelled,
+            error: copied.as_ref().err().filter(|_| !cancelled).cloned(),
+            current_file: None,
+            files_done: Some(files_done),
+            files_total: Some(files_total),
+        },
+    );
+    copied?;
+
+    if remove_source {
+        let sources = items
+            .iter()
+            .rev()
+            .map(|item| &item.source)
+            .chain(std::iter::once(from_location));
+        for location in sources {
+            from_provider.delete(location).await.map_err(|e| {
+                format!(
+                    "Copied to {} but failed to delete the original {}: {}",
+                    root_raw,
+                    location.raw(),
+                    e
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// A temp path of our own for downloading `name` into. Provider helpers like
+/// `download_sftp_file_to_temp` share one cached copy per remote path, so callers that
+/// delete their copy afterwards must not use those.
+fn private_download_path(name: &str) -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir().join("marlin-fetch");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let safe_name = name
+        .replace(['/', '\\', '\0', ':', '*', '?', '"', '<', '>', '|'], "_")
+        .trim_start_matches('.')
+        .to_string();
+    Ok(dir.join(format!("{}_{}", uuid::Uuid::new_v4().simple(), safe_name)))
+}
+
+/// Get a local copy of the file at `location`. Returns the path and whether it is a
+/// temp file the caller has to remove. With `max_bytes`, remote files are only
+/// downloaded that far, for readers that look at the head of the file.
+async fn fetch_to_local(
+    location: &Location,
+    name: &str,
+    max_bytes: Option<u64>,
+) -> Result<(PathBuf, bool), String> {
+    match location.scheme() {
+        "file" => return Ok((expand_path(&location.to_path_string())?, false)),
+        "gdrive" if max_bytes.is_none() => {
+            let email = location
+                .authority()
+                .ok_or_else(|| "Google Drive source missing account".to_string())?;
+            let file_id = get_file_id_by_path(email, location.path()).await?;
+            // The cached download can be up to an hour old, which is fine for previews
+            // but not for a copy
+            return download_file_uncached(email, &file_id, name)
+                .await
+                .map(|path| (path, true));
+        }
+        _ => {}
+    }
+
+    let dest = private_download_path(name)?;
+    if let Err(err) = download_remote_file(location, &dest, max_bytes).await {
+        let _ = tokio::fs::remove_file(&dest).await;
+        return Err(err);
+    }
+    Ok((dest, true))
+}
+
+/// Download the remote file at `location` to `dest`, or just its first `max_bytes`.
+async fn download_remote_file(
+    location: &Location,
+    dest: &Path,
+    max_bytes: Option<u64>,
+) -> Result<(), String> {
+    match location.scheme() {
+        "sftp" => {
+            let (_, hostname, port, remote_path) =
+                crate::locations::sftp::parse_sftp_url(location.raw())?;
+            match max_bytes {
+                Some(max_bytes) => {
+                    crate::locations::sftp::download_file_head_from_sftp(
+                        &hostname,
+                        port,
+                        &remote_path,
+                        dest,
+                        max_bytes,
+                    )
+                    .await
+                }
+                None => {
+                    crate::locations::sftp::download_file_from_sftp(
+                        &hostname,
+                        port,
+                        &remote_path,
+                        dest,
+                    )
+                    .await
+                }
+            }
+        }
+        #[cfg(not(target_os = "windows"))]
+        "smb" => {
+            let (hostname, share, path) = crate::locations::smb::parse_smb_url(location.raw())?;
+            let dest = dest.to_path_buf();
+            tauri::async_runtime::spawn_blocking(move || match max_bytes {
+                Some(max_bytes) => crate::locations::smb::download_file_head_from_smb(
+                    &hostname, &share, &path, &dest, max_bytes,
+                ),
+                None => {
+                    crate::locations::smb::download_file_from_smb(&hostname, &share, &path, &dest)
+                }
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+        "gdrive" => {
+            let email = location
+                .authority()
+                .ok_or_else(|| "Google Drive source missing account".to_string())?;
+            let file_id = get_file_id_by_path(email, location.path()).await?;
+            download_file_head(email, &file_id, dest, max_bytes.unwrap_or(u64::MAX)).await
+        }
+        "webdav" => {
+            crate::locations::webdav::download_file_from_webdav(location.raw(), dest, max_bytes)
+                .await
+        }
+        "mtp" => crate::locations::mtp::download_mtp_file(location.raw(), dest, max_bytes).await,
+        scheme => Err(format!(
+            "Files can't be transferred out of {} locations",
+            scheme
+        )),
     }
+}
 
-    from_provider.move_item(&from_location, &to_location).await
+/// Upload `local_path` into the folder `dest_dir`, using a numbered name if `name` is
+/// taken. Returns the final name. SMB, SFTP and Drive uploads report to `progress`.
+async fn upload_local_file(
+    local_path: &Path,
+    dest_dir: &Location,
+    name: &str,
+    progress: Option<&TransferProgress>,
+) -> Result<String, String> {
+    match dest_dir.scheme() {
+        "file" => {
+            let dir = expand_path(&dest_dir.to_path_string())?;
+            let local_path = local_path.to_path_buf();
+            let name = name.to_string();
+            tauri::async_runtime::spawn_blocking(move || {
+                let target = allocate_unique_path(&dir, &name)?;
+                fs::copy(&local_path, &target)
+                    .map_err(|e| format!("Failed to copy file: {}", e))?;
+                Ok(target
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or(name))
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+        "sftp" => {
+            let (_, hostname, port, dir_path) =
+                crate::locations::sftp::parse_sftp_url(dest_dir.raw())?;
+            crate::locations::sftp::upload_file_to_sftp(
+                local_path, &hostname, port, &dir_path, name, progress,
+            )
+            .await
+        }
+        #[cfg(not(target_os = "windows"))]
+        "smb" => {
+            let authority = dest_dir
+                .authority()
+                .ok_or_else(|| "SMB destination missing server".to_string())?;
+            let (hostname, share, dir_path) =
+                crate::locations::smb::parse_smb_path(authority, dest_dir.path())?;
+            let local_path = local_path.to_path_buf();
+            let name = name.to_string();
+            let progress = progress.cloned();
+            tauri::async_runtime::spawn_blocking(move || {
+                crate::locations::smb::upload_file_to_smb(
+                    &local_path,
+                    &hostname,
+                    &share,
+                    &dir_path,
+                    &name,
+                    progress.as_ref(),
+                )
+            })
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+        }
+        "gdrive" => {
+            let email = dest_dir
+                .authority()
+                .ok_or_else(|| "Google Drive destination missing account".to_string())?;
+            let folder_id = get_folder_id_by_path(email, dest_dir.path()).await?;
+            upload_file_to_gdrive_with_unique_name(email, local_path, &folder_id, name, progress)
+                .await
+        }
+        "webdav" => {
+            let authority = dest_dir
+                .authority()
+                .ok_or_else(|| "WebDAV destination missing server".to_string())?;
+            crate::locations::webdav::upload_file_to_webdav(
+                local_path,
+                authority,
+                dest_dir.path(),
+                name,
+            )
+            .await
+        }
+        "mtp" => crate::locations::mtp::upload_file_to_mtp(local_path, dest_dir, name).await,
+        scheme => Err(format!(
+            "Files can't be transferred into {} locations",
+            scheme
+        )),
+    }
 }
 
 fn filename_from_location(loc: &Location) -> Option<String> {
@@ -2421,6 +5331,8 @@ fn emit_clipboard_progress_init(
             total,
             finished: false,
             error: None,
+            bytes_copied: None,
+            bytes_total: None,
         },
     );
 }
@@ -2442,10 +5354,68 @@ fn emit_clipboard_progress_item(
             total,
             finished: false,
             error,
+            bytes_copied: None,
+            bytes_total: None,
+        },
+    );
+}
+
+fn emit_clipboard_progress_bytes(
+    app: &AppHandle,
+    current_item: String,
+    completed: usize,
+    total: usize,
+    bytes_copied: u64,
+    bytes_total: u64,
+) {
+    emit_clipboard_progress_update(
+        app,
+        ClipboardProgressUpdatePayload {
+            operation: None,
+            destination: None,
+            current_item: Some(current_item),
+            completed,
+            total,
+            finished: false,
+            error: None,
+            bytes_copied: Some(bytes_copied),
+            bytes_total: Some(bytes_total),
         },
     );
 }
 
+/// Move a local item, reporting byte progress if the move has to fall back to
+/// copy-then-delete because source and destination are on different volumes.
+async fn move_local_item_with_progress(
+    app: &AppHandle,
+    from: PathBuf,
+    to: PathBuf,
+    name: String,
+    completed: usize,
+    total_items: usize,
+) -> Result<(), String> {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut last_emit: Option<Instant> = None;
+        fs_utils::move_file_or_directory(&from, &to, |copied, total| {
+            let due = last_emit.map_or(true, |t| t.elapsed() >= Duration::from_millis(250));
+            if due || copied == total {
+                last_emit = Some(Instant::now());
+                emit_clipboard_progress_bytes(
+                    &app,
+                    name.clone(),
+                    completed,
+                    total_items,
+                    copied,
+                    total,
+                );
+            }
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
 fn emit_clipboard_progress_finish(
     app: &AppHandle,
     operation: String,
@@ -2464,6 +5434,8 @@ fn emit_clipboard_progress_finish(
             total,
             finished: true,
             error,
+            bytes_copied: None,
+            bytes_total: None,
         },
     );
 }
@@ -2675,9 +5647,15 @@ pub async fn paste_items_to_location(
                         let dest_raw = candidate.to_string_lossy().to_string();
                         let (_, dest_item_location) = resolve_location(LI::Raw(dest_raw.clone()))?;
                         if is_cut {
-                            source_provider
-                                .move_item(&source_location, &dest_item_location)
-                                .await?;
+                            move_local_item_with_progress(
+                                &app,
+                                source_path.clone(),
+                                candidate.clone(),
+                                name.clone(),
+                                completed,
+                                total_items,
+                            )
+                            .await?;
                         } else {
                             source_provider
                                 .copy(&source_location, &dest_item_location)
@@ -2895,7 +5873,7 @@ pub async fn paste_items_to_location(
                     if !local_path.exists() || !local_path.is_file() {
                         Ok(None)
                     } else {
-                        upload_file_to_gdrive(email, &local_path, &folder_id, &name).await?;
+                        upload_file_to_gdrive(email, &local_path, &folder_id, &name, None).await?;
                         let dest_raw = join_dest_raw(&dest_scheme, &dest_dir_raw, &name);
                         if is_cut {
                             if let Err(e) = fs::remove_file(&local_path) {
@@ -3199,6 +6177,7 @@ pub async fn paste_items_to_location(
                                     &share,
                                     &dir_path,
                                     &name_clone,
+                                    None,
                                 )
                             })
                             .await
@@ -3246,6 +6225,7 @@ pub async fn paste_items_to_location(
                                 &share,
                                 &dir_path,
                                 &name_clone,
+                                None,
                             )
                         })
                         .await
@@ -3302,6 +6282,7 @@ pub async fn paste_items_to_location(
                             sftp_port,
                             &sftp_dir_path,
                             &name,
+                            None,
                         )
                         .await?;
 
@@ -3332,6 +6313,7 @@ pub async fn paste_items_to_location(
                         sftp_port,
                         &sftp_dir_path,
                         &name,
+                        None,
                     )
                     .await;
 
@@ -3367,7 +6349,7 @@ pub async fn paste_items_to_location(
                         .ok_or_else(|| "Google Drive destination missing account".to_string())?;
                     let folder_id = get_folder_id_by_path(email, dest_location.path()).await?;
                     let upload_result =
-                        upload_file_to_gdrive(email, &temp_path, &folder_id, &name).await;
+                        upload_file_to_gdrive(email, &temp_path, &folder_id, &name, None).await;
 
                     let _ = tokio::fs::remove_file(&temp_path).await;
 
@@ -3418,6 +6400,7 @@ pub async fn paste_items_to_location(
                         sftp_port,
                         &sftp_dir_path,
                         &name,
+                        None,
                     )
                     .await;
 
@@ -3467,6 +6450,7 @@ pub async fn paste_items_to_location(
                             &smb_share,
                             &smb_dir_path,
                             &name_clone,
+                            None,
                         )
                     })
                     .await
@@ -3553,6 +6537,8 @@ pub async fn clipboard_paste_image_to_location(
             total: 1,
             finished: false,
             error: None,
+            bytes_copied: None,
+            bytes_total: None,
         },
     );
 
@@ -3574,6 +6560,8 @@ pub async fn clipboard_paste_image_to_location(
                 total: 1,
                 finished: true,
                 error: None,
+                bytes_copied: None,
+                bytes_total: None,
             },
         );
         return Ok(result);
@@ -3615,7 +6603,7 @@ pub async fn clipboard_paste_image_to_location(
         std::fs::write(&temp_path, &image_bytes)
             .map_err(|e| format!("Failed to write temp file: {e}"))?;
 
-        upload_file_to_gdrive(email, &temp_path, &folder_id, &candidate).await?;
+        upload_file_to_gdrive(email, &temp_path, &folder_id, &candidate, None).await?;
 
         // Best-effort cleanup
         let _ = std::fs::remove_file(&temp_path);
@@ -3637,6 +6625,8 @@ pub async fn clipboard_paste_image_to_location(
                 total: 1,
                 finished: true,
                 error: None,
+                bytes_copied: None,
+                bytes_total: None,
             },
         );
         return Ok(result);
@@ -3671,6 +6661,7 @@ pub async fn clipboard_paste_image_to_location(
                 &share,
                 &dir_path,
                 &base_name_for_upload,
+                None,
             )
         })
         .await
@@ -3695,6 +6686,8 @@ pub async fn clipboard_paste_image_to_location(
                 total: 1,
                 finished: true,
                 error: None,
+                bytes_copied: None,
+                bytes_total: None,
             },
         );
         return Ok(result);
@@ -3718,6 +6711,7 @@ pub async fn clipboard_paste_image_to_location(
             sftp_port,
             &sftp_dir_path,
             &base_name,
+            None,
         )
         .await;
 
@@ -3741,6 +6735,8 @@ pub async fn clipboard_paste_image_to_location(
                 total: 1,
                 finished: true,
                 error: None,
+                bytes_copied: None,
+                bytes_total: None,
             },
         );
         return Ok(result);
@@ -3760,6 +6756,8 @@ pub async fn clipboard_paste_image_to_location(
             total: 1,
             finished: true,
             error: Some(error.clone()),
+            bytes_copied: None,
+            bytes_total: None,
         },
     );
     Err(error)
@@ -3793,20 +6791,19 @@ fn allocate_destination_folder(destination_root: &Path, base_name: &str) -> Resu
 fn extract_zip_contents<R: Read + Seek>(
     archive: &mut ZipArchive<R>,
     target_dir: &Path,
-    mut on_entry: impl FnMut(&str),
+    password: Option<&str>,
+    mut on_entry: impl FnMut(&str, u64),
 ) -> Result<(), String> {
     for index in 0..archive.len() {
-        let mut entry = archive
-            .by_index(index)
-            .map_err(|err| format!("Failed to read archive entry {index}: {err}"))?;
+        let mut entry = open_zip_entry(archive, index, password)?;
 
-        let enclosed_path = match entry.enclosed_name() {
-            Some(path) => path.to_path_buf(),
+        // Legacy (non-UTF-8) names are re-decoded so CP437/Shift-JIS entries land with
+        // their real names instead of mojibake
+        let decoded_name = zip_entry_name(entry.name(), entry.name_raw());
+        let enclosed_path = match archive_names::enclosed_path(&decoded_name) {
+            Some(path) => path,
             None => {
-                warn!(
-                    "Skipping archive entry {name:?} due to invalid path",
-                    name = entry.name()
-                );
+                warn!("Skipping archive entry {decoded_name:?} due to invalid path");
                 continue;
             }
         };
@@ -3854,7 +6851,7 @@ fn extract_zip_contents<R: Read + Seek>(
                 );
             }
         }
-        on_entry(&entry_name);
+        on_entry(&entry_name, entry.size());
     }
 
     Ok(())
@@ -3939,7 +6936,7 @@ fn determine_archive_format(
 fn extract_tar_from_reader<R: Read>(
     reader: R,
     target_dir: &Path,
-    mut on_entry: impl FnMut(&str),
+    mut on_entry: impl FnMut(&str, u64),
 ) -> Result<(), String> {
     let mut archive = TarArchive::new(reader);
     let entries = archive
@@ -3953,11 +6950,12 @@ fn extract_tar_from_reader<R: Read>(
             .map_err(|err| format!("Failed to resolve TAR entry path: {}", err))?;
 
         let entry_name = entry_path.to_string_lossy().to_string();
+        let size = entry.size();
         entry
             .unpack_in(target_dir)
             .map_err(|err| format!("Failed to unpack TAR entry {}: {}", entry_name, err))?;
 
-        on_entry(&entry_name);
+        on_entry(&entry_name, size);
     }
 
     Ok(())
@@ -4007,7 +7005,17 @@ fn derive_folder_base_name(path: &Path, format: ArchiveFormat) -> Result<String,
     for pattern in patterns {
         if lower.ends_with(pattern) && name.len() > pattern.len() {
             let end = name.len() - pattern.len();
-            return Ok(name[..end].to_string());
+            let base = &name[..end];
+            // "Movie.part01.rar" extracts into "Movie", not "Movie.part01"
+            if matches!(format, ArchiveFormat::Rar) {
+                if let Some(idx) = base.to_lowercase().rfind(".part") {
+                    let digits = &base[idx + ".part".len()..];
+                    if idx > 0 && !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+                        return Ok(base[..idx].to_string());
+                    }
+                }
+            }
+            return Ok(base.to_string());
         }
     }
 
@@ -4021,12 +7029,16 @@ fn emit_archive_progress_update(
     entry_name: Option<&str>,
     format: ArchiveFormat,
     finished: bool,
+    extracted_bytes: u64,
+    total_bytes: Option<u64>,
 ) {
     let payload = ArchiveProgressUpdatePayload {
         archive_name: archive_name.to_string(),
         entry_name: entry_name.map(|s| s.to_string()),
         format: format.as_str().to_string(),
         finished,
+        extracted_bytes,
+        total_bytes,
     };
 
     if let Err(err) = app.emit(ARCHIVE_PROGRESS_UPDATE_EVENT, payload) {
@@ -4046,6 +7058,407 @@ fn cleanup_directory(path: &Path) {
     }
 }
 
+/// Extract a RAR with the system `unrar` or `unar`, for archives the unrar crate can't
+/// open. They extract into an empty staging folder, and each top-level item is then
+/// moved into `target_dir` under a numbered name if its own is taken, so nothing
+/// already there is overwritten.
+fn extract_rar_with_system(archive_path: &Path, target_dir: &Path) -> Result<(), String> {
+    let staging = target_dir.join(format!(".marlin-unrar-{}", Uuid::new_v4().simple()));
+    fs::create_dir(&staging).map_err(|err| {
+        format!(
+            "Failed to create extraction directory {}: {}",
+            staging.display(),
+            err
+        )
+    })?;
+    let result = run_system_rar_extractor(archive_path, &staging)
+        .and_then(|()| move_into_unique_names(&staging, target_dir));
+    cleanup_directory(&staging);
+    result
+}
+
+/// Move everything in `from` into `to`, numbering names that are already taken there.
+fn move_into_unique_names(from: &Path, to: &Path) -> Result<(), String> {
+    let entries = fs::read_dir(from).map_err(|err| format!("Failed to read directory: {}", err))?;
+    for entry in entries {
+        let entry = entry.map_err(|err| format!("Failed to read entry: {}", err))?;
+        let target = allocate_unique_path(to, &entry.file_name().to_string_lossy())?;
+        fs::rename(entry.path(), &target).map_err(|err| {
+            format!(
+                "Failed to move extracted {} into place: {}",
+                target.display(),
+                err
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Run the system `unrar` or `unar` into the empty folder `target_dir`. Both refuse
+/// absolute and `..` paths on their own.
+fn run_system_rar_extractor(archive_path: &Path, target_dir: &Path) -> Result<(), String> {
+    let mut dir_arg = target_dir.as_os_str().to_os_string();
+    dir_arg.push(std::path::MAIN_SEPARATOR_STR);
+    let attempts: [(&str, Vec<&std::ffi::OsStr>); 2] = [
+        // -p- never prompts for a password, so an encrypted archive fails instead of hanging
+        (
+            "unrar",
+            vec![
+                "x".as_ref(),
+                "-o-".as_ref(),
+                "-y".as_ref(),
+                "-p-".as_ref(),
+                archive_path.as_os_str(),
+                dir_arg.as_os_str(),
+            ],
+        ),
+        (
+            "unar",
+            vec![
+                "-q".as_ref(),
+                "-s".as_ref(),
+                "-D".as_ref(),
+                "-o".as_ref(),
+                target_dir.as_os_str(),
+                archive_path.as_os_str(),
+            ],
+        ),
+    ];
+
+    let mut last_error = "Neither unrar nor unar is installed".to_string();
+    for (program, args) in attempts {
+        let output = match OsCommand::new(program)
+            .args(&args)
+            .stdin(std::process::Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                last_error = format!("Failed to run {}: {}", program, err);
+                continue;
+            }
+        };
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        last_error = format!("{} failed: {}", program, stderr.trim());
+    }
+    Err(last_error)
+}
+
+/// Uncompressed size of everything in the archive, when it can be read without
+/// decompressing the contents: from zip's central directory, RAR headers, or the
+/// headers of an uncompressed tar. Compressed tars have to be decompressed to find
+/// their entries, so they get `None`.
+fn archive_total_bytes(archive_path: &Path, archive_format: ArchiveFormat) -> Option<u64> {
+    match archive_format {
+        ArchiveFormat::Zip => {
+            let archive = ZipArchive::new(fs::File::open(archive_path).ok()?).ok()?;
+            archive
+                .decompressed_size()
+                .and_then(|size| u64::try_from(size).ok())
+        }
+        ArchiveFormat::Rar => {
+            let archive = RarArchive::new(archive_path).open_for_listing().ok()?;
+            let mut total: u64 = 0;
+            for header in archive {
+                let header = header.ok()?;
+                if !header.is_directory() {
+                    total = total.saturating_add(header.unpacked_size);
+                }
+            }
+            Some(total)
+        }
+        ArchiveFormat::Tar => {
+            let mut archive = TarArchive::new(fs::File::open(archive_path).ok()?);
+            let mut total: u64 = 0;
+            // Seeking past each entry's data keeps this a header-only pass
+            for entry in archive.entries_with_seek().ok()? {
+                total = total.saturating_add(entry.ok()?.size());
+            }
+            Some(total)
+        }
+        ArchiveFormat::TarGz
+        | ArchiveFormat::TarBz2
+        | ArchiveFormat::TarXz
+        | ArchiveFormat::TarZst => None,
+    }
+}
+
+/// The single top-level folder every entry of the archive lives under, if any.
+/// Junk entries like `__MACOSX` are ignored; a lone top-level file is not a root.
+fn archive_single_root(
+    archive_path: &Path,
+    archive_format: ArchiveFormat,
+) -> Result<Option<String>, String> {
+    let mut entries: Vec<(PathBuf, bool)> = Vec::new();
+    match archive_format {
+        ArchiveFormat::Zip => {
+            let file = fs::File::open(archive_path).map_err(|err| {
+                format!("Failed to open archive {}: {}", archive_path.display(), err)
+            })?;
+            let mut archive = ZipArchive::new(file).map_err(|err| {
+                format!("Failed to read archive {}: {}", archive_path.display(), err)
+            })?;
+            for index in 0..archive.len() {
+                let entry = archive
+                    .by_index_raw(index)
+                    .map_err(|err| format!("Failed to read archive entry {index}: {err}"))?;
+                let name = zip_entry_name(entry.name(), entry.name_raw());
+                if let Some(path) = archive_names::enclosed_path(&name) {
+                    entries.push((path, entry.is_dir()));
+                }
+            }
+        }
+        ArchiveFormat::Rar => {
+            let archive = RarArchive::new(archive_path)
+                .open_for_listing()
+                .map_err(|err| format!("Failed to open RAR archive: {}", err))?;
+            for header in archive {
+                let header = header.map_err(|err| format!("Failed to read RAR entry: {}", err))?;
+                entries.push((header.filename.clone(), header.is_directory()));
+            }
+        }
+        ArchiveFormat::Tar
+        | ArchiveFormat::TarGz
+        | ArchiveFormat::TarBz2
+        | ArchiveFormat::TarXz
+        | ArchiveFormat::TarZst => {
+            let reader = create_tar_reader(archive_format, archive_path)?;
+            let mut archive = TarArchive::new(reader);
+            let tar_entries = archive
+                .entries()
+                .map_err(|err| format!("Failed to read tar entries: {}", err))?;
+            for entry in tar_entries {
+                let entry = entry.map_err(|err| format!("Failed to read tar entry: {}", err))?;
+                let path = entry
+                    .path()
+                    .map_err(|err| format!("Failed to read tar entry path: {}", err))?
+                    .into_owned();
+                entries.push((path, entry.header().entry_type().is_dir()));
+            }
+        }
+    }
+
+    let mut root: Option<String> = None;
+    let mut root_is_dir = false;
+    for (path, is_dir) in entries {
+        let mut components = path.components().filter_map(|component| match component {
+            std::path::Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        });
+        let Some(first) = components.next() else {
+            continue;
+        };
+        if is_junk_name(&first) {
+            continue;
+        }
+        match &root {
+            Some(existing) if *existing != first => return Ok(None),
+            Some(_) => {}
+            None => root = Some(first),
+        }
+        if is_dir || components.next().is_some() {
+            root_is_dir = true;
+        }
+    }
+
+    Ok(root.filter(|_| root_is_dir))
+}
+
+/// Size (and CRC32 where the format records one) the archive expects for an extracted file.
+struct ExpectedEntry {
+    size: u64,
+    crc32: Option<u32>,
+}
+
+/// Map an archive entry path to where extraction writes it, mirroring the extractors:
+/// root and `.` components are dropped, and entries that climb out with `..` are skipped.
+fn extracted_relative_path(path: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::Normal(name) => relative.push(name),
+            std::path::Component::ParentDir => return None,
+            _ => {}
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+/// Whether a zip entry's extra field carries the WinZip AES header. AE-2 entries
+/// record a CRC32 of 0, and AE-1 ones are already checked by the decryptor, so
+/// verification skips the CRC for both.
+fn zip_entry_is_aes(extra: Option<&[u8]>) -> bool {
+    const AES_EXTRA_FIELD_ID: u16 = 0x9901;
+
+    let mut extra = extra.unwrap_or_default();
+    while let [id_lo, id_hi, len_lo, len_hi, rest @ ..] = extra {
+        if u16::from_le_bytes([*id_lo, *id_hi]) == AES_EXTRA_FIELD_ID {
+            return true;
+        }
+        let len = usize::from(u16::from_le_bytes([*len_lo, *len_hi]));
+        extra = rest.get(len..).unwrap_or_default();
+    }
+    false
+}
+
+fn expected_archive_files(
+    archive_path: &Path,
+    archive_format: ArchiveFormat,
+) -> Result<BTreeMap<PathBuf, ExpectedEntry>, String> {
+    // Later entries overwrite earlier ones on extraction, so the last one wins here too
+    let mut expected = BTreeMap::new();
+    match archive_format {
+        ArchiveFormat::Zip => {
+            let file = fs::File::open(archive_path).map_err(|err| {
+                format!("Failed to open archive {}: {}", archive_path.display(), err)
+            })?;
+            let mut archive = ZipArchive::new(file).map_err(|err| {
+                format!("Failed to read archive {}: {}", archive_path.display(), err)
+            })?;
+            for index in 0..archive.len() {
+                let entry = archive
+                    .by_index_raw(index)
+                    .map_err(|err| format!("Failed to read archive entry {index}: {err}"))?;
+                if entry.is_dir() {
+                    continue;
+                }
+                let name = zip_entry_name(entry.name(), entry.name_raw());
+                if let Some(path) = archive_names::enclosed_path(&name) {
+                    expected.insert(
+                        path,
+                        ExpectedEntry {
+                            size: entry.size(),
+                            crc32: (!zip_entry_is_aes(entry.extra_data())).then(|| entry.crc32()),
+                        },
+                    );
+                }
+            }
+        }
+        ArchiveFormat::Rar => {
+            let archive = RarArchive::new(archive_path)
+                .open_for_listing()
+                .map_err(|err| format!("Failed to open RAR archive: {}", err))?;
+            for header in archive {
+                let header = header.map_err(|err| format!("Failed to read RAR entry: {}", err))?;
+                if header.is_directory() {
+                    continue;
+                }
+                if let Some(path) = extracted_relative_path(&header.filename) {
+                    expected.insert(
+                        path,
+                        ExpectedEntry {
+                            size: header.unpacked_size,
+                            crc32: None,
+                        },
+                    );
+                }
+            }
+        }
+        ArchiveFormat::Tar
+        | ArchiveFormat::TarGz
+        | ArchiveFormat::TarBz2
+        | ArchiveFormat::TarXz
+        | ArchiveFormat::TarZst => {
+            let reader = create_tar_reader(archive_format, archive_path)?;
+            let mut archive = TarArchive::new(reader);
+            let entries = archive
+                .entries()
+                .map_err(|err| format!("Failed to read tar entries: {}", err))?;
+            for entry in entries {
+                let entry = entry.map_err(|err| format!("Failed to read tar entry: {}", err))?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let path = entry
+                    .path()
+                    .map_err(|err| format!("Failed to read tar entry path: {}", err))?;
+                if let Some(path) = extracted_relative_path(&path) {
+                    expected.insert(
+                        path,
+                        ExpectedEntry {
+                            size: entry.size(),
+                            crc32: None,
+                        },
+                    );
+                }
+            }
+        }
+    }
+    Ok(expected)
+}
+
+fn file_crc32(path: &Path) -> std::io::Result<u32> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0u8; 256 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize())
+}
+
+/// Compare every file extracted into `target_dir` with what the archive recorded.
+/// Returns how many entries were checked and the ones that didn't match.
+fn verify_extracted_entries(
+    archive_path: &Path,
+    archive_format: ArchiveFormat,
+    target_dir: &Path,
+) -> Result<(usize, Vec<ExtractVerificationFailure>), String> {
+    let expected = expected_archive_files(archive_path, archive_format)?;
+    let mut checked = 0;
+    let mut failures = Vec::new();
+
+    for (relative, entry) in &expected {
+        // macOS metadata folders are stripped after an Extract Here
+        if relative
+            .components()
+            .any(|c| is_junk_name(&c.as_os_str().to_string_lossy()))
+        {
+            continue;
+        }
+        checked += 1;
+
+        let path = target_dir.join(relative);
+        let reason = match fs::metadata(&path) {
+            Err(_) => Some("missing after extraction".to_string()),
+            Ok(meta) if !meta.is_file() => Some("not extracted as a regular file".to_string()),
+            Ok(meta) if meta.len() != entry.size => Some(format!(
+                "size is {} bytes, archive records {}",
+                meta.len(),
+                entry.size
+            )),
+            Ok(_) => match entry.crc32 {
+                Some(expected_crc) => match file_crc32(&path) {
+                    Ok(actual) if actual != expected_crc => Some(format!(
+                        "CRC32 is {:08x}, archive records {:08x}",
+                        actual, expected_crc
+                    )),
+                    Ok(_) => None,
+                    Err(err) => Some(format!("failed to read back: {}", err)),
+                },
+                None => None,
+            },
+        };
+
+        if let Some(reason) = reason {
+            failures.push(ExtractVerificationFailure {
+                path: relative.to_string_lossy().to_string(),
+                reason,
+            });
+        }
+    }
+
+    Ok((checked, failures))
+}
+
 fn is_junk_name(name: &str) -> bool {
     name.eq_ignore_ascii_case(".ds_store")
         || name.eq_ignore_ascii_case("__macosx")
@@ -4192,14 +7605,15 @@ fn numbered_name_variant(name: &str, index: usize) -> String {
     }
 }
 
-fn collect_local_zip_entries(
+/// Walk local `roots` for archiving, skipping junk files and symlinks, and call `visit`
+/// with each item's path, its path inside the archive and whether it's a folder. Each
+/// root is stored under its own name; with `strip_root`, a folder root's contents go at
+/// the top of the archive instead and the root itself isn't visited.
+fn walk_local_archive_sources(
     roots: &[PathBuf],
     strip_root: bool,
-) -> Result<(Vec<LocalZipEntry>, Vec<String>), String> {
-    let mut file_entries = Vec::new();
-    let mut all_dirs: Vec<String> = Vec::new();
-    let mut non_empty_dirs: HashSet<String> = HashSet::new();
-
+    mut visit: impl FnMut(&Path, String, bool) -> Result<(), String>,
+) -> Result<(), String> {
     for root in roots {
         let root_name = root
             .file_name()
@@ -4231,11 +7645,6 @@ fn collect_local_zip_entries(
             let entry = entry
                 .map_err(|err| format!("Failed to walk {}: {}", root.to_string_lossy(), err))?;
 
-            let file_type = entry.file_type();
-            if file_type.is_symlink() {
-                continue;
-            }
-
             let rel = entry
                 .path()
                 .strip_prefix(base)
@@ -4245,32 +7654,41 @@ fn collect_local_zip_entries(
                 continue;
             }
 
-            let zip_path = zip_entry_name_from_path(rel)?;
+            visit(
+                entry.path(),
+                zip_entry_name_from_path(rel)?,
+                entry.file_type().is_dir(),
+            )?;
+        }
+    }
+    Ok(())
+}
 
-            if file_type.is_dir() {
-                all_dirs.push(zip_path.clone());
-                if let Some(parent) = Path::new(&zip_path).parent() {
-                    let parent_str = parent.to_string_lossy().replace('\\', "/");
-                    if !parent_str.is_empty() {
-                        non_empty_dirs.insert(parent_str);
-                    }
-                }
-                continue;
-            }
+fn collect_local_zip_entries(
+    roots: &[PathBuf],
+    strip_root: bool,
+) -> Result<(Vec<LocalZipEntry>, Vec<String>), String> {
+    let mut file_entries = Vec::new();
+    let mut all_dirs: Vec<String> = Vec::new();
+    let mut non_empty_dirs: HashSet<String> = HashSet::new();
 
+    walk_local_archive_sources(roots, strip_root, |source_path, zip_path, is_dir| {
+        if let Some(parent) = Path::new(&zip_path).parent() {
+            let parent_str = parent.to_string_lossy().replace('\\', "/");
+            if !parent_str.is_empty() {
+                non_empty_dirs.insert(parent_str);
+            }
+        }
+        if is_dir {
+            all_dirs.push(zip_path);
+        } else {
             file_entries.push(LocalZipEntry {
-                source_path: entry.path().to_path_buf(),
-                zip_path: zip_path.clone(),
+                source_path: source_path.to_path_buf(),
+                zip_path,
             });
-
-            if let Some(parent) = Path::new(&zip_path).parent() {
-                let parent_str = parent.to_string_lossy().replace('\\', "/");
-                if !parent_str.is_empty() {
-                    non_empty_dirs.insert(parent_str);
-                }
-            }
         }
-    }
+        Ok(())
+    })?;
 
     let empty_dirs = all_dirs
         .into_iter()
@@ -4551,6 +7969,9 @@ pub async fn extract_archive(
     destination_dir: String,
     format_hint: Option<String>,
     create_subfolder: Option<bool>,
+    extract_here: Option<bool>,
+    verify: Option<bool>,
+    password: Option<String>,
 ) -> Result<ExtractArchiveResponse, String> {
     let expanded_archive = expand_path(&archive_path)?;
     let expanded_destination = expand_path(&destination_dir)?;
@@ -4572,8 +7993,34 @@ pub async fn extract_archive(
     }
 
     let archive_format = determine_archive_format(&archive_path, format_hint.as_deref())?;
+    // Any volume of a multi-volume RAR can be picked; extraction starts from the first
+    let archive_path = if matches!(archive_format, ArchiveFormat::Rar) {
+        rar_first_volume(&archive_path)?
+    } else {
+        archive_path
+    };
     let base_name = derive_folder_base_name(&archive_path, archive_format)?;
-    let create_subfolder = create_subfolder.unwrap_or(true);
+    // "Extract Here" skips the wrapping folder when the archive already has a single
+    // root folder that doesn't collide with anything at the destination
+    let extract_here = extract_here.unwrap_or(false);
+    let direct_root = if extract_here {
+        let archive_for_scan = archive_path.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            archive_single_root(&archive_for_scan, archive_format)
+        })
+        .await
+        .map_err(|err| format!("Failed to join archive scan task: {}", err))??
+        .filter(|root| !destination_root.join(root).exists())
+    } else {
+        None
+    };
+    let create_subfolder = if extract_here {
+        direct_root.is_none()
+    } else {
+        create_subfolder.unwrap_or(true)
+    };
+    let junk_folder = destination_root.join("__MACOSX");
+    let junk_preexisting = junk_folder.exists();
     let allocated_folder = if create_subfolder {
         Some(allocate_destination_folder(&destination_root, &base_name)?)
     } else {
@@ -4583,6 +8030,7 @@ pub async fn extract_archive(
     let archive_for_task = archive_path.clone();
     let destination_for_task = destination_root.clone();
     let folder_name_for_task = allocated_folder.clone();
+    let direct_root_for_task = direct_root.clone();
     let app_handle = app.clone();
     let archive_name = archive_path
         .file_name()
@@ -4598,11 +8046,13 @@ pub async fn extract_archive(
         archive_format.as_str()
     );
 
-    emit_archive_progress_update(&app, &archive_name, None, archive_format, false);
+    emit_archive_progress_update(&app, &archive_name, None, archive_format, false, 0, None);
 
-    let (extracted_path, used_system_fallback) =
-        tauri::async_runtime::spawn_blocking(move || -> Result<(PathBuf, bool), String> {
+    let (extracted_path, used_system_fallback, total_bytes) =
+        tauri::async_runtime::spawn_blocking(move || -> Result<(PathBuf, bool, Option<u64>), String> {
             let archive_name = archive_name_for_task;
+            let total_bytes = archive_total_bytes(&archive_for_task, archive_format);
+            let mut extracted_bytes: u64 = 0;
             let target_dir = if let Some(folder) = &folder_name_for_task {
                 destination_for_task.join(folder)
             } else {
@@ -4618,7 +8068,16 @@ pub async fn extract_archive(
                     )
                 })?;
             }
-            let should_cleanup = folder_name_for_task.is_some();
+            // Only ever clean up what this extraction created, never the destination itself
+            let direct_root_path = direct_root_for_task
+                .as_ref()
+                .map(|root| destination_for_task.join(root));
+            let cleanup_target = if folder_name_for_task.is_some() {
+                Some(target_dir.clone())
+            } else {
+                direct_root_path.clone()
+            };
+            let extracted_root = direct_root_path.unwrap_or_else(|| target_dir.clone());
 
             match archive_format {
                 ArchiveFormat::Zip => {
@@ -4637,22 +8096,30 @@ pub async fn extract_archive(
                                 archive_for_task.display(),
                                 err
                             )
-                        })?;
-
-                        extract_zip_contents(&mut zip_archive, &target_dir, |entry_name| {
-                            emit_archive_progress_update(
-                                &app_handle,
-                                &archive_name,
-                                Some(entry_name),
-                                archive_format,
-                                false,
-                            );
-                        })
+                        })?;
+
+                        extract_zip_contents(
+                            &mut zip_archive,
+                            &target_dir,
+                            password.as_deref(),
+                            |entry_name, size| {
+                                extracted_bytes = extracted_bytes.saturating_add(size);
+                                emit_archive_progress_update(
+                                    &app_handle,
+                                    &archive_name,
+                                    Some(entry_name),
+                                    archive_format,
+                                    false,
+                                    extracted_bytes,
+                                    total_bytes,
+                                );
+                            },
+                        )
                     })();
 
                     native_result.map_err(|err| {
-                        if should_cleanup {
-                            cleanup_directory(&target_dir);
+                        if let Some(dir) = &cleanup_target {
+                            cleanup_directory(dir);
                         }
                         err
                     })?;
@@ -4662,24 +8129,9 @@ pub async fn extract_archive(
                         archive_for_task.display(),
                         target_dir.display()
                     );
-                    Ok((target_dir, false))
+                    Ok((extracted_root, false, total_bytes))
                 }
                 ArchiveFormat::Rar => {
-                    // Check for multi-volume archives
-                    let rar_archive = RarArchive::new(&archive_for_task);
-                    if rar_archive.is_multipart() {
-                        // For multipart archives, ensure we're starting from the first part
-                        if let Some(first_part) = rar_archive.first_part_option() {
-                            if first_part != archive_for_task {
-                                cleanup_directory(&target_dir);
-                                return Err(format!(
-                                    "Multi-volume RAR archive detected. Please extract starting from the first part: {}",
-                                    first_part.display()
-                                ));
-                            }
-                        }
-                    }
-
                     let extraction_result = (|| -> Result<(), String> {
                         let archive = RarArchive::new(&archive_for_task)
                             .open_for_processing()
@@ -4732,12 +8184,18 @@ pub async fn extract_archive(
                                         ));
                                     }
 
+                                    if !entry.is_directory() {
+                                        extracted_bytes =
+                                            extracted_bytes.saturating_add(entry.unpacked_size);
+                                    }
                                     emit_archive_progress_update(
                                         &app_handle,
                                         &archive_name,
                                         Some(&entry_name),
                                         archive_format,
                                         false,
+                                        extracted_bytes,
+                                        total_bytes,
                                     );
 
                                     // Use extract_with_base for directory extraction (not extract_to which expects a file path)
@@ -4768,19 +8226,59 @@ pub async fn extract_archive(
                         Ok(())
                     })();
 
-                    extraction_result.map_err(|err| {
-                        if should_cleanup {
-                            cleanup_directory(&target_dir);
+                    let used_system_fallback = match extraction_result {
+                        Ok(()) => false,
+                        // The crate can't read every RAR (newer or damaged ones); try the
+                        // system tools before giving up, unless it's a password issue
+                        Err(err) if !err.contains("password-protected") => {
+                            warn!(
+                                "Native RAR extraction failed for {}: {}; trying system unrar/unar",
+                                archive_for_task.display(),
+                                err
+                            );
+                            // Drop what the native attempt got through, so the system
+                            // extractor's items don't end up numbered beside them
+                            if let Some(dir) = &cleanup_target {
+                                cleanup_directory(dir);
+                            }
+                            if folder_name_for_task.is_some() {
+                                fs::create_dir(&target_dir).map_err(|create_err| {
+                                    format!(
+                                        "Failed to create extraction directory {}: {}",
+                                        target_dir.display(),
+                                        create_err
+                                    )
+                                })?;
+                            }
+                            if let Err(fallback_err) =
+                                extract_rar_with_system(&archive_for_task, &target_dir)
+                            {
+                                if let Some(dir) = &cleanup_target {
+                                    cleanup_directory(dir);
+                                }
+                                return Err(format!("{} ({})", err, fallback_err));
+                            }
+                            true
                         }
-                        err
-                    })?;
+                        Err(err) => {
+                            if let Some(dir) = &cleanup_target {
+                                cleanup_directory(dir);
+                            }
+                            return Err(err);
+                        }
+                    };
 
                     info!(
-                        "RAR extraction succeeded for {} into {}",
+                        "RAR extraction succeeded for {} into {}{}",
                         archive_for_task.display(),
-                        target_dir.display()
+                        target_dir.display(),
+                        if used_system_fallback {
+                            " using the system extractor"
+                        } else {
+                            ""
+                        }
                     );
-                    Ok((target_dir, false))
+                    Ok((extracted_root, used_system_fallback, total_bytes))
                 }
                 ArchiveFormat::Tar
                 | ArchiveFormat::TarGz
@@ -4789,20 +8287,23 @@ pub async fn extract_archive(
                 | ArchiveFormat::TarZst => {
                     let extraction_result = (|| -> Result<(), String> {
                         let reader = create_tar_reader(archive_format, &archive_for_task)?;
-                        extract_tar_from_reader(reader, &target_dir, |entry_name| {
+                        extract_tar_from_reader(reader, &target_dir, |entry_name, size| {
+                            extracted_bytes = extracted_bytes.saturating_add(size);
                             emit_archive_progress_update(
                                 &app_handle,
                                 &archive_name,
                                 Some(entry_name),
                                 archive_format,
                                 false,
+                                extracted_bytes,
+                                total_bytes,
                             );
                         })
                     })();
 
                     extraction_result.map_err(|err| {
-                        if should_cleanup {
-                            cleanup_directory(&target_dir);
+                        if let Some(dir) = &cleanup_target {
+                            cleanup_directory(dir);
                         }
                         err
                     })?;
@@ -4813,13 +8314,18 @@ pub async fn extract_archive(
                         archive_for_task.display(),
                         target_dir.display()
                     );
-                    Ok((target_dir, false))
+                    Ok((extracted_root, false, total_bytes))
                 }
             }
         })
         .await
         .map_err(|err| format!("Failed to join archive extraction task: {}", err))??;
 
+    // Extracting straight into the destination would leave macOS resource forks behind
+    if direct_root.is_some() && !junk_preexisting && junk_folder.exists() {
+        cleanup_directory(&junk_folder);
+    }
+
     info!(
         "Extraction complete for {} -> {} (fallback: {}, format: {})",
         archive_path.display(),
@@ -4828,12 +8334,65 @@ pub async fn extract_archive(
         archive_format.as_str()
     );
 
-    emit_archive_progress_update(&app, &archive_name, None, archive_format, true);
+    // Verification is opt-in: it re-reads every extracted file
+    let mut verified = false;
+    let mut failed_entries = Vec::new();
+    if verify.unwrap_or(false) {
+        let extraction_dir = match &allocated_folder {
+            Some(folder) => destination_root.join(folder),
+            None => destination_root.clone(),
+        };
+        let archive_for_verify = archive_path.clone();
+        let verification = tauri::async_runtime::spawn_blocking(move || {
+            verify_extracted_entries(&archive_for_verify, archive_format, &extraction_dir)
+        })
+        .await
+        .map_err(|err| format!("Failed to join archive verification task: {}", err))?;
+
+        match verification {
+            Ok((checked, failures)) => {
+                if failures.is_empty() {
+                    info!(
+                        "Verified {} extracted entries from {}",
+                        checked,
+                        archive_path.display()
+                    );
+                } else {
+                    warn!(
+                        "{} of {} extracted entries from {} failed verification",
+                        failures.len(),
+                        checked,
+                        archive_path.display()
+                    );
+                }
+                verified = true;
+                failed_entries = failures;
+            }
+            Err(err) => warn!(
+                "Could not verify extraction of {}: {}",
+                archive_path.display(),
+                err
+            ),
+        }
+    }
+
+    let done_bytes = total_bytes.unwrap_or(0);
+    emit_archive_progress_update(
+        &app,
+        &archive_name,
+        None,
+        archive_format,
+        true,
+        done_bytes,
+        total_bytes,
+    );
 
     Ok(ExtractArchiveResponse {
         folder_path: extracted_path.to_string_lossy().to_string(),
         used_system_fallback,
         format: archive_format.as_str().to_string(),
+        verified,
+        failed_entries,
     })
 }
 
@@ -4844,6 +8403,100 @@ pub async fn extract_archive_entry_to_temp(archive_uri: String) -> Result<String
     Ok(temp_path.to_string_lossy().to_string())
 }
 
+/// Check the password for an encrypted archive (`archive://` URI) and remember it for
+/// the session, after browsing failed with `[ARCHIVE_PASSWORD_REQUIRED]`.
+#[command]
+pub async fn unlock_archive(archive_uri: String, password: String) -> Result<(), String> {
+    crate::locations::archive::unlock_archive(&archive_uri, &password).await
+}
+
+/// Extract only `entry_paths` (archive-internal paths such as `/docs/readme.txt`) from
+/// a local archive into `destination_dir`. Each selected item lands under its own name,
+/// with ` (2)` etc. added on collisions; selected folders bring their contents along.
+/// Returns the paths of the extracted items.
+#[command]
+pub async fn extract_archive_entries(
+    app: AppHandle,
+    archive_path: String,
+    entry_paths: Vec<String>,
+    destination_dir: String,
+    password: Option<String>,
+) -> Result<Vec<String>, String> {
+    if entry_paths.is_empty() {
+        return Err("No archive entries selected".to_string());
+    }
+
+    let archive_path = expand_path(&archive_path)?;
+    if !archive_path.is_file() {
+        return Err("Archive path is not a file".to_string());
+    }
+    let destination_root = expand_path(&destination_dir)?;
+    if !destination_root.is_dir() {
+        return Err("Destination path is not a directory".to_string());
+    }
+    let archive_format = determine_archive_format(&archive_path, None)?;
+
+    let mut selections = Vec::with_capacity(entry_paths.len());
+    let mut claimed: HashSet<PathBuf> = HashSet::new();
+    for internal_path in entry_paths {
+        let name = internal_path
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| format!("Invalid archive entry path: {}", internal_path))?
+            .to_string();
+        let output = fs_utils::allocate_unique_path_with(&destination_root, &name, |p| {
+            p.exists() || claimed.contains(p)
+        })?;
+        claimed.insert(output.clone());
+        selections.push(crate::locations::archive::EntrySelection {
+            internal_path,
+            output,
+        });
+    }
+    let outputs: Vec<String> = selections
+        .iter()
+        .map(|selection| selection.output.to_string_lossy().to_string())
+        .collect();
+
+    let archive_name = archive_path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Archive")
+        .to_string();
+    emit_archive_progress_update(&app, &archive_name, None, archive_format, false, 0, None);
+
+    let app_handle = app.clone();
+    let archive_name_for_task = archive_name.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        crate::locations::archive::extract_entries(
+            &archive_path,
+            &selections,
+            password.as_deref(),
+            |entry_name| {
+                emit_archive_progress_update(
+                    &app_handle,
+                    &archive_name_for_task,
+                    Some(entry_name),
+                    archive_format,
+                    false,
+                    0,
+                    None,
+                );
+            },
+        )
+    })
+    .await
+    .map_err(|err| format!("Failed to join archive extraction task: {}", err))
+    .and_then(|result| result);
+
+    emit_archive_progress_update(&app, &archive_name, None, archive_format, true, 0, None);
+    result?;
+
+    Ok(outputs)
+}
+
 #[command]
 pub async fn compress_to_zip(
     app: AppHandle,
@@ -5086,12 +8739,7 @@ pub async fn compress_to_zip(
 
                 let creds = get_server_credentials(&hostname_for_task)?;
                 let params = serde_json::json!({
-                    "credentials": {
-                        "hostname": hostname_for_task,
-                        "username": creds.username,
-                        "password": creds.password,
-                        "domain": creds.domain
-                    },
+                    "credentials": creds.to_params(&hostname_for_task),
                     "share": share_for_task,
                     "source_path": temp_zip_for_task.to_string_lossy().to_string(),
                     "dest_path": dest_file_rel_for_task
@@ -5217,20 +8865,394 @@ pub async fn compress_to_zip(
             .map_err(|err| format!("Failed to join compression task: {}", err))??;
 
             if let Err(err) =
-                upload_file_to_gdrive(email, &temp_zip_path, &folder_id, &final_name).await
+                upload_file_to_gdrive(email, &temp_zip_path, &folder_id, &final_name, None).await
             {
                 let _ = fs::remove_file(&temp_zip_path);
                 return Err(err);
             }
             let _ = fs::remove_file(&temp_zip_path);
 
-            let dest_dir_raw = dest_location.raw().to_string();
-            let final_path = join_dest_raw(&dest_scheme, &dest_dir_raw, &final_name);
+            let dest_dir_raw = dest_location.raw().to_string();
+            let final_path = join_dest_raw(&dest_scheme, &dest_dir_raw, &final_name);
+
+            Ok(CompressToZipResponse { path: final_path })
+        }
+        _ => Err("Provider not supported for compression".to_string()),
+    }
+}
+
+/// Zip local items, then delete the originals once every archived file has been
+/// read back from the new archive and matches its source.
+#[command]
+pub async fn compress_and_replace(
+    app: AppHandle,
+    source_paths: Vec<LocationInput>,
+    destination_dir: LocationInput,
+    suggested_name: String,
+) -> Result<CompressToZipResponse, String> {
+    let mut sources: Vec<PathBuf> = Vec::with_capacity(source_paths.len());
+    for input in &source_paths {
+        let (_, location) = resolve_location(input.clone())?;
+        if location.scheme() != "file" {
+            return Err("Compress and replace is only supported for local files".to_string());
+        }
+        sources.push(expand_path(&location.to_path_string())?);
+    }
+
+    let (_, dest_location) = resolve_location(destination_dir.clone())?;
+    let dest_path = expand_path(&dest_location.to_path_string())?;
+    if sources.iter().any(|source| dest_path.starts_with(source)) {
+        return Err("The archive can't be saved inside an item it replaces".to_string());
+    }
+
+    let response = compress_to_zip(app, source_paths, destination_dir, suggested_name).await?;
+
+    let archive_path = PathBuf::from(&response.path);
+    tauri::async_runtime::spawn_blocking(move || -> Result<(), String> {
+        if let Err(err) = verify_local_zip(&archive_path, &sources) {
+            let _ = fs::remove_file(&archive_path);
+            return Err(format!(
+                "Archive verification failed, originals were kept: {}",
+                err
+            ));
+        }
+        for source in &sources {
+            delete_file_or_directory(source)?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|err| format!("Failed to join compression task: {}", err))??;
+
+    Ok(response)
+}
+
+/// Check that `archive_path` holds every file `compress_to_zip` would have written for
+/// `sources`, with matching sizes and intact CRCs.
+fn verify_local_zip(archive_path: &Path, sources: &[PathBuf]) -> Result<(), String> {
+    // Symlinks are skipped when zipping, so deleting the originals would lose them
+    for source in sources {
+        for entry in WalkDir::new(source).follow_links(false) {
+            let entry =
+                entry.map_err(|err| format!("Failed to walk {}: {}", source.display(), err))?;
+            if entry.file_type().is_symlink() {
+                return Err(format!(
+                    "{} is a symlink and can't be archived",
+                    entry.path().display()
+                ));
+            }
+        }
+    }
+
+    let strip_root = sources.len() == 1 && sources.first().map(|p| p.is_dir()).unwrap_or(false);
+    let (entries, _) = collect_local_zip_entries(sources, strip_root)?;
+
+    let file = fs::File::open(archive_path)
+        .map_err(|err| format!("Failed to open archive {}: {}", archive_path.display(), err))?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|err| format!("Failed to read archive {}: {}", archive_path.display(), err))?;
+
+    for entry in &entries {
+        let expected = fs::metadata(&entry.source_path)
+            .map_err(|err| format!("Failed to read {}: {}", entry.source_path.display(), err))?
+            .len();
+        let mut zipped = archive
+            .by_name(&entry.zip_path)
+            .map_err(|_| format!("Archive is missing {}", entry.zip_path))?;
+        // Reading to the end makes the zip reader validate the entry's CRC
+        let read = std::io::copy(&mut zipped, &mut std::io::sink())
+            .map_err(|err| format!("Failed to read back {}: {}", entry.zip_path, err))?;
+        if read != expected {
+            return Err(format!(
+                "{} is {} bytes in the archive but {} bytes on disk",
+                entry.zip_path, read, expected
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// A file or folder to write into an archive created by `create_archive`.
+struct ArchiveSourceEntry {
+    source_path: PathBuf,
+    archive_path: String,
+    is_dir: bool,
+}
+
+/// Walk `roots`, storing each one under its own base name so a selection spanning
+/// several folders still lands side by side at the top of the archive.
+fn collect_archive_entries(roots: &[PathBuf]) -> Result<Vec<ArchiveSourceEntry>, String> {
+    let mut root_names: HashSet<&str> = HashSet::new();
+    for root in roots {
+        let Some(root_name) = root.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        if !is_junk_name(root_name) && !root_names.insert(root_name) {
+            return Err(format!(
+                "More than one selected item is named \"{}\"; archives can't hold both",
+                root_name
+            ));
+        }
+    }
+
+    let mut entries = Vec::new();
+    walk_local_archive_sources(roots, false, |source_path, archive_path, is_dir| {
+        entries.push(ArchiveSourceEntry {
+            source_path: source_path.to_path_buf(),
+            archive_path,
+            is_dir,
+        });
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// `base.ext`, or `base (2).ext`, `base (3).ext`, ... if that is taken.
+fn allocate_archive_file_name(
+    destination_root: &Path,
+    base_name: &str,
+    format: ArchiveFormat,
+) -> Result<String, String> {
+    let trimmed = base_name.trim();
+    let base = if trimmed.is_empty() {
+        "Archive"
+    } else {
+        trimmed
+    };
+    let ext = format.as_str();
+    let mut candidate = format!("{base}.{ext}");
+    let mut counter: u32 = 2;
+
+    loop {
+        if !destination_root.join(&candidate).exists() {
+            return Ok(candidate);
+        }
+
+        candidate = format!("{base} ({counter}).{ext}");
+        counter += 1;
+
+        if counter > 10_000 {
+            return Err("Unable to allocate unique name for the archive".to_string());
+        }
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn unix_mode(path: &Path) -> Option<u32> {
+    fs::metadata(path)
+        .ok()
+        .map(|meta| meta.permissions().mode())
+}
+
+#[cfg(not(target_family = "unix"))]
+fn unix_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+fn write_zip_archive(
+    output: fs::File,
+    entries: &[ArchiveSourceEntry],
+    mut on_entry: impl FnMut(&str),
+) -> Result<(), String> {
+    let mut zip = ZipWriter::new(output);
+    let base_options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for entry in entries {
+        let mut options = base_options;
+        if let Some(mode) = unix_mode(&entry.source_path) {
+            options = options.unix_permissions(mode);
+        }
+
+        if entry.is_dir {
+            zip.add_directory(format!("{}/", entry.archive_path), options)
+                .map_err(|err| format!("Failed to add directory to zip: {}", err))?;
+        } else {
+            let mut src = fs::File::open(&entry.source_path).map_err(|err| {
+                format!("Failed to open {}: {}", entry.source_path.display(), err)
+            })?;
+            let size = src.metadata().map(|meta| meta.len()).unwrap_or(0);
+            zip.start_file(
+                entry.archive_path.as_str(),
+                options.large_file(size >= u32::MAX as u64),
+            )
+            .map_err(|err| format!("Failed to add zip entry: {}", err))?;
+            std::io::copy(&mut src, &mut zip)
+                .map_err(|err| format!("Failed to write zip entry: {}", err))?;
+        }
+        on_entry(&entry.archive_path);
+    }
+
+    zip.finish()
+        .map_err(|err| format!("Failed to finalize zip: {}", err))?;
+    Ok(())
+}
+
+/// Write `entries` as a tar stream into `writer` and hand the writer back so the
+/// caller can finish its compressor. Tar headers carry each entry's mode as-is.
+fn write_tar_archive<W: Write>(
+    writer: W,
+    entries: &[ArchiveSourceEntry],
+    mut on_entry: impl FnMut(&str),
+) -> Result<W, String> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+
+    for entry in entries {
+        builder
+            .append_path_with_name(&entry.source_path, &entry.archive_path)
+            .map_err(|err| format!("Failed to add {} to archive: {}", entry.archive_path, err))?;
+        on_entry(&entry.archive_path);
+    }
+
+    builder
+        .into_inner()
+        .map_err(|err| format!("Failed to finalize archive: {}", err))
+}
+
+fn write_archive(
+    output_path: &Path,
+    format: ArchiveFormat,
+    entries: &[ArchiveSourceEntry],
+    on_entry: impl FnMut(&str),
+) -> Result<(), String> {
+    let file = fs::File::create(output_path)
+        .map_err(|err| format!("Failed to create archive file: {}", err))?;
+    let finish_err = |err: std::io::Error| format!("Failed to finalize archive: {}", err);
+
+    match format {
+        ArchiveFormat::Zip => write_zip_archive(file, entries, on_entry)?,
+        ArchiveFormat::TarGz => {
+            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+            write_tar_archive(encoder, entries, on_entry)?
+                .finish()
+                .map_err(finish_err)?;
+        }
+        ArchiveFormat::TarXz => {
+            let encoder = xz2::write::XzEncoder::new(file, 6);
+            write_tar_archive(encoder, entries, on_entry)?
+                .finish()
+                .map_err(finish_err)?;
+        }
+        ArchiveFormat::TarZst => {
+            let encoder = zstd::stream::write::Encoder::new(file, 0).map_err(finish_err)?;
+            write_tar_archive(encoder, entries, on_entry)?
+                .finish()
+                .map_err(finish_err)?;
+        }
+        ArchiveFormat::Rar | ArchiveFormat::Tar | ArchiveFormat::TarBz2 => {
+            return Err(format!(
+                "Creating {} archives is not supported",
+                format.as_str()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Create a `zip`, `tar.gz`, `tar.xz` or `tar.zst` archive of local `source_paths`.
+/// `destination_path` names the archive; if it's taken, ` (2)`, ` (3)`, ... is added
+/// before the extension. Returns the path actually written.
+#[command]
+pub async fn create_archive(
+    app: AppHandle,
+    source_paths: Vec<String>,
+    destination_path: String,
+    format: String,
+) -> Result<CompressToZipResponse, String> {
+    if source_paths.is_empty() {
+        return Err("No source paths provided".to_string());
+    }
+
+    let archive_format = match archive_format_from_hint(&format) {
+        Some(
+            format @ (ArchiveFormat::Zip
+            | ArchiveFormat::TarGz
+            | ArchiveFormat::TarXz
+            | ArchiveFormat::TarZst),
+        ) => format,
+        _ => return Err(format!("Unsupported archive format: {}", format)),
+    };
+
+    let mut sources: Vec<PathBuf> = Vec::with_capacity(source_paths.len());
+    for raw in &source_paths {
+        let path = expand_path(raw)?;
+        if !path.exists() {
+            return Err(format!("Source path does not exist: {}", raw));
+        }
+        sources.push(path);
+    }
+
+    let destination = expand_path(&destination_path)?;
+    let destination_root = destination
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| "Destination path has no parent directory".to_string())?;
+    if !destination_root.is_dir() {
+        return Err("Destination directory does not exist".to_string());
+    }
+    if sources
+        .iter()
+        .any(|source| source.is_dir() && destination_root.starts_with(source))
+    {
+        return Err("The archive can't be saved inside a folder it contains".to_string());
+    }
+
+    let base_name = derive_folder_base_name(&destination, archive_format)?;
+    let archive_name = allocate_archive_file_name(&destination_root, &base_name, archive_format)?;
+    let final_path = destination_root.join(&archive_name);
+    let temp_path = destination_root.join(format!(".marlin-archive-{}.tmp", Uuid::new_v4()));
+
+    info!(
+        "create_archive requested: {} item(s) -> {} (format: {})",
+        sources.len(),
+        final_path.display(),
+        archive_format.as_str()
+    );
+
+    emit_archive_progress_update(&app, &archive_name, None, archive_format, false, 0, None);
 
-            Ok(CompressToZipResponse { path: final_path })
+    let app_handle = app.clone();
+    let archive_name_for_task = archive_name.clone();
+    let final_path_for_task = final_path.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || -> Result<(), String> {
+        let entries = collect_archive_entries(&sources)?;
+        if entries.is_empty() {
+            return Err("No files to compress".to_string());
         }
-        _ => Err("Provider not supported for compression".to_string()),
-    }
+
+        let written = write_archive(&temp_path, archive_format, &entries, |entry_name| {
+            emit_archive_progress_update(
+                &app_handle,
+                &archive_name_for_task,
+                Some(entry_name),
+                archive_format,
+                false,
+                0,
+                None,
+            );
+        })
+        .and_then(|()| {
+            fs::rename(&temp_path, &final_path_for_task)
+                .map_err(|err| format!("Failed to finalize archive: {}", err))
+        });
+        if written.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        written
+    })
+    .await
+    .map_err(|err| format!("Failed to join compression task: {}", err))
+    .and_then(|result| result);
+
+    emit_archive_progress_update(&app, &archive_name, None, archive_format, true, 0, None);
+    result?;
+
+    Ok(CompressToZipResponse {
+        path: final_path.to_string_lossy().to_string(),
+    })
 }
 
 #[command]
@@ -5298,66 +9320,51 @@ pub fn get_application_icon(path: String, size: Option<u32>) -> Result<String, S
     }
 }
 
+/// Render SVG markup to a PNG data URL. Renderings are cached by markup and size,
+/// so icon-heavy views don't rasterize the same SVG over and over.
 #[tauri::command]
-pub fn render_svg_to_png(svg: String, size: Option<u32>) -> Result<String, String> {
-    // Parse SVG (usvg 0.43 API)
-    let opt = resvg::usvg::Options::default();
-    let tree = resvg::usvg::Tree::from_data(svg.as_bytes(), &opt)
-        .map_err(|e| format!("SVG parse error: {:?}", e))?;
-
-    // Allocate pixmap
+pub async fn render_svg_to_png(
+    svg: String,
+    size: Option<u32>,
+    font_family: Option<String>,
+) -> Result<String, String> {
     let target = size.unwrap_or(64).max(1);
-    let mut pixmap = resvg::tiny_skia::Pixmap::new(target, target)
-        .ok_or_else(|| "Failed to allocate pixmap".to_string())?;
-
-    // Fit into square with a small padding to avoid bleeding against edges
-    let svg_size = tree.size();
-    let (w, h) = (svg_size.width().max(1.0), svg_size.height().max(1.0));
-    let padding = (target as f32 * 0.08).round();
-    let inner = (target as f32 - 2.0 * padding).max(1.0);
-    let s = (inner / w).min(inner / h);
-    let scaled_w = w * s;
-    let scaled_h = h * s;
-    let mut ts = resvg::tiny_skia::Transform::from_scale(s, s);
-    let tx = ((target as f32 - scaled_w) * 0.5).round();
-    let ty = ((target as f32 - scaled_h) * 0.5).round();
-    ts = ts.post_translate(tx, ty);
-
-    let mut pmut = pixmap.as_mut();
-    resvg::render(&tree, ts, &mut pmut);
-
-    // Convert premultiplied BGRA pixels -> non-premultiplied RGBA for PNG encoder
-    let data = pixmap.data();
-    let mut rgba = Vec::with_capacity(data.len());
-    for px in data.chunks_exact(4) {
-        let r = px[0] as u32;
-        let g = px[1] as u32;
-        let b = px[2] as u32;
-        let a = px[3] as u32;
-        if a == 0 {
-            rgba.extend_from_slice(&[0, 0, 0, 0]);
-        } else {
-            // Un-premultiply and reorder to RGBA
-            let ur = ((r * 255 + a / 2) / a).min(255) as u8;
-            let ug = ((g * 255 + a / 2) / a).min(255) as u8;
-            let ub = ((b * 255 + a / 2) / a).min(255) as u8;
-            rgba.extend_from_slice(&[ur, ug, ub, a as u8]);
-        }
-    }
-
-    // Encode PNG with the image crate
-    let img = image::ImageBuffer::<image::Rgba<u8>, _>::from_vec(target, target, rgba)
-        .ok_or_else(|| "Failed to create image buffer".to_string())?;
-    let mut out = Vec::new();
-    let mut cursor = std::io::Cursor::new(&mut out);
-    image::DynamicImage::ImageRgba8(img)
-        .write_to(&mut cursor, image::ImageFormat::Png)
-        .map_err(|e| format!("PNG encode error: {}", e))?;
-
-    let data_url = format!(
-        "data:image/png;base64,{}",
-        base64::engine::general_purpose::STANDARD.encode(out)
-    );
+    let key = crate::thumbnails::generate_svg_cache_key(&svg, font_family.as_deref(), target);
+    // Rendering still works if the cache couldn't be set up
+    let service = get_thumbnail_service().await.ok();
+    if let Some(service) = &service {
+        if let Some(data_url) = service.cached_svg(&key).await {
+            return Ok(data_url);
+        }
+    }
+
+    let started = std::time::Instant::now();
+    let data_url = tauri::async_runtime::spawn_blocking(move || {
+        let rendered = crate::thumbnails::generators::svg::rasterize(
+            svg.as_bytes(),
+            target,
+            font_family.as_deref(),
+        )?;
+
+        let mut out = Vec::new();
+        let mut cursor = std::io::Cursor::new(&mut out);
+        image::DynamicImage::ImageRgba8(rendered.image)
+            .write_to(&mut cursor, image::ImageFormat::Png)
+            .map_err(|e| format!("PNG encode error: {}", e))?;
+        Ok::<_, String>(format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(out)
+        ))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))??;
+
+    if let Some(service) = service {
+        let elapsed = started.elapsed().as_millis() as u64;
+        if let Err(err) = service.cache_svg(&key, data_url.clone(), elapsed).await {
+            log::debug!("Failed to cache SVG rendering: {err}");
+        }
+    }
     Ok(data_url)
 }
 
@@ -5452,7 +9459,7 @@ pub fn update_sort_menu_state(
             set_checked(&menu_state.sort_type_item, false);
             set_checked(&menu_state.sort_modified_item, false);
         }
-        "type" => {
+        "type" | "typeName" => {
             set_checked(&menu_state.sort_name_item, false);
             set_checked(&menu_state.sort_size_item, false);
             set_checked(&menu_state.sort_type_item, true);
@@ -5483,22 +9490,80 @@ pub fn update_selection_menu_state(
     Ok(())
 }
 
-#[derive(serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
 pub struct SystemDrive {
     pub name: String,
     pub path: String,
     pub drive_type: String,
     pub is_ejectable: bool,
+    /// Capacity of the volume; `None` when it isn't mounted, can't be queried in
+    /// time, or is a network mount
+    pub total_bytes: Option<u64>,
+    pub available_bytes: Option<u64>,
+    /// Network mounts are listed without capacity, since querying a share whose
+    /// server is unreachable can block for minutes
+    #[serde(skip)]
+    pub is_remote: bool,
 }
 
+/// Capacity queries still running after this are given up on and reported as unknown.
+const DRIVE_USAGE_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[command]
-pub fn get_system_drives() -> Result<Vec<SystemDrive>, String> {
+pub async fn get_system_drives() -> Result<Vec<SystemDrive>, String> {
+    tauri::async_runtime::spawn_blocking(system_drives)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))
+}
+
+/// The drives shown in the sidebar, with the capacity of each local one.
+pub fn system_drives() -> Vec<SystemDrive> {
+    let mut drives = list_system_drives();
+    fill_drive_usage(&mut drives);
+    drives
+}
+
+/// Query drive capacities in parallel, leaving out network mounts and any drive that
+/// doesn't answer within [`DRIVE_USAGE_TIMEOUT`]. A stuck query keeps its thread
+/// until the OS gives up, but no longer holds up the listing.
+fn fill_drive_usage(drives: &mut [SystemDrive]) {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    for (index, drive) in drives.iter().enumerate().filter(|(_, d)| !d.is_remote) {
+        let sender = sender.clone();
+        let path = PathBuf::from(&drive.path);
+        let spawned = std::thread::Builder::new()
+            .name("drive-usage".to_string())
+            .spawn(move || {
+                let _ = sender.send((index, fs_utils::volume_usage(&path)));
+            });
+        if let Err(err) = spawned {
+            warn!("Failed to query capacity of {}: {err}", drive.path);
+        }
+    }
+    drop(sender);
+
+    let deadline = Instant::now() + DRIVE_USAGE_TIMEOUT;
+    while let Ok((index, usage)) =
+        receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+    {
+        // Drives without media or whose mount has gone away keep `None`
+        if let Ok(usage) = usage {
+            drives[index].total_bytes = Some(usage.total_bytes);
+            drives[index].available_bytes = Some(usage.available_bytes);
+        }
+    }
+}
+
+fn list_system_drives() -> Vec<SystemDrive> {
     let mut drives = Vec::new();
 
     #[cfg(target_os = "windows")]
     {
         use std::ffi::OsString;
         use std::os::windows::ffi::OsStringExt;
+        use windows::Win32::System::WindowsProgramming::{
+            DRIVE_CDROM, DRIVE_REMOTE, DRIVE_REMOVABLE,
+        };
 
         // Get all logical drives on Windows
         let mut drive_strings = vec![0u16; 256];
@@ -5516,11 +9581,22 @@ pub fn get_system_drives() -> Result<Vec<SystemDrive>, String> {
 
             for drive in drives_string.split('\0').filter(|s| !s.is_empty()) {
                 if drive.len() >= 3 {
+                    let letter = &drive[..2];
+                    let kind = windows_drive_kind(drive);
+                    let (name, drive_type, is_ejectable) = match kind {
+                        DRIVE_REMOVABLE => (format!("Removable Disk ({letter})"), "volume", true),
+                        DRIVE_CDROM => (format!("CD Drive ({letter})"), "volume", true),
+                        DRIVE_REMOTE => (format!("Network Drive ({letter})"), "volume", false),
+                        _ => (format!("Local Disk ({letter})"), "system", false),
+                    };
                     drives.push(SystemDrive {
-                        name: format!("Local Disk ({})", &drive[..2]),
+                        name,
                         path: drive.to_string(),
-                        drive_type: "system".to_string(),
-                        is_ejectable: false, // TODO: Check if removable drive
+                        drive_type: drive_type.to_string(),
+                        is_ejectable,
+                        total_bytes: None,
+                        available_bytes: None,
+                        is_remote: kind == DRIVE_REMOTE,
                     });
                 }
             }
@@ -5535,11 +9611,19 @@ pub fn get_system_drives() -> Result<Vec<SystemDrive>, String> {
             path: "/".to_string(),
             drive_type: "system".to_string(),
             is_ejectable: false,
+            total_bytes: None,
+            available_bytes: None,
+            is_remote: false,
         });
 
+        // On Linux, add user-visible removable/network mounts
+        #[cfg(target_os = "linux")]
+        drives.extend(linux_user_mounts());
+
         // On macOS, also try to add mounted volumes
         #[cfg(target_os = "macos")]
         {
+            let network_volumes = macos_network_volumes();
             if let Ok(entries) = std::fs::read_dir("/Volumes") {
                 for entry in entries.flatten() {
                     if let Ok(metadata) = entry.metadata() {
@@ -5547,11 +9631,15 @@ pub fn get_system_drives() -> Result<Vec<SystemDrive>, String> {
                             let name = entry.file_name().to_string_lossy().to_string();
                             if name != "Macintosh HD" {
                                 // Skip default system volume
+                                let path = format!("/Volumes/{}", name);
                                 drives.push(SystemDrive {
                                     name: name.clone(),
-                                    path: format!("/Volumes/{}", name),
+                                    is_remote: network_volumes.contains(&path),
+                                    path,
                                     drive_type: "volume".to_string(),
                                     is_ejectable: true,
+                                    total_bytes: None,
+                                    available_bytes: None,
                                 });
                             }
                         }
@@ -5561,11 +9649,146 @@ pub fn get_system_drives() -> Result<Vec<SystemDrive>, String> {
         }
     }
 
-    Ok(drives)
+    drives
+}
+
+/// Mount points of non-local filesystems, read from the kernel's cached mount list
+/// so an unresponsive server can't block it.
+#[cfg(target_os = "macos")]
+fn macos_network_volumes() -> HashSet<String> {
+    let mut mounts: *mut libc::statfs = std::ptr::null_mut();
+    let count = unsafe { libc::getmntinfo(&mut mounts, libc::MNT_NOWAIT) };
+    if count <= 0 || mounts.is_null() {
+        return HashSet::new();
+    }
+    let mounts = unsafe { std::slice::from_raw_parts(mounts, count as usize) };
+    mounts
+        .iter()
+        .filter(|mount| mount.f_flags & libc::MNT_LOCAL as u32 == 0)
+        .map(|mount| {
+            unsafe { std::ffi::CStr::from_ptr(mount.f_mntonname.as_ptr()) }
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect()
+}
+
+/// Mounts under the conventional removable/media roots, read from `/proc/self/mounts`.
+#[cfg(target_os = "linux")]
+fn linux_user_mounts() -> Vec<SystemDrive> {
+    const MOUNT_ROOTS: &[&str] = &["/media/", "/run/media/", "/mnt/"];
+    const NETWORK_FILESYSTEMS: &[&str] = &[
+        "nfs",
+        "nfs4",
+        "cifs",
+        "smb3",
+        "smbfs",
+        "fuse.sshfs",
+        "fuse.rclone",
+        "davfs",
+        "9p",
+        "afs",
+        "ceph",
+        "glusterfs",
+    ];
+
+    let contents = match fs::read_to_string("/proc/self/mounts") {
+        Ok(contents) => contents,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut seen = HashSet::new();
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            Some((fields.next()?, fields.next()?))
+        })
+        // Mount points escape whitespace as octal (e.g. "\040" for a space)
+        .map(|(raw, fs_type)| {
+            let mount = raw
+                .replace("\\040", " ")
+                .replace("\\011", "\t")
+                .replace("\\134", "\\");
+            (mount, fs_type)
+        })
+        .filter(|(mount, _)| MOUNT_ROOTS.iter().any(|root| mount.starts_with(root)))
+        .filter(|(mount, _)| seen.insert(mount.clone()))
+        .map(|(mount, fs_type)| {
+            let name = Path::new(&mount)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| mount.clone());
+            SystemDrive {
+                name,
+                path: mount,
+                drive_type: "volume".to_string(),
+                is_ejectable: true,
+                total_bytes: None,
+                available_bytes: None,
+                is_remote: NETWORK_FILESYSTEMS.contains(&fs_type),
+            }
+        })
+        .collect()
+}
+
+/// `GetDriveTypeW` for a root like `C:\`, e.g. `DRIVE_REMOVABLE` or `DRIVE_FIXED`.
+#[cfg(target_os = "windows")]
+fn windows_drive_kind(root: &str) -> u32 {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+
+    let wide: Vec<u16> = std::ffi::OsStr::new(root)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    unsafe { windows::Win32::Storage::FileSystem::GetDriveTypeW(PCWSTR(wide.as_ptr())) }
+}
+
+/// Watches and search indexes the app holds under a volume, let go of while it is
+/// ejected so they can't be what keeps it busy.
+struct ReleasedVolumeHandles {
+    watches: Vec<(fs_watcher::WatchedDirectory, usize)>,
+    indexes: Vec<PathBuf>,
+    _drive_watcher: crate::drive_watcher::PauseGuard,
+}
+
+impl ReleasedVolumeHandles {
+    fn release(root: &Path) -> Self {
+        let _drive_watcher = crate::drive_watcher::pause();
+        let watches = fs_watcher::get_watcher()
+            .map(|watcher| watcher.release_under(root))
+            .unwrap_or_default();
+        let indexes = crate::search_index::drop_indexes_under(root);
+        Self {
+            watches,
+            indexes,
+            _drive_watcher,
+        }
+    }
+
+    /// The eject failed, so the volume is still there; watch and index it again.
+    fn restore(self) {
+        if let Some(watcher) = fs_watcher::get_watcher() {
+            watcher.restore(self.watches);
+        }
+        for root in &self.indexes {
+            crate::search_index::ensure_index(root);
+        }
+    }
 }
 
 #[command]
 pub async fn eject_drive(path: String) -> Result<(), String> {
+    let released = ReleasedVolumeHandles::release(Path::new(&path));
+    let result = eject_volume(path).await;
+    if result.is_err() {
+        released.restore();
+    }
+    result
+}
+
+async fn eject_volume(path: String) -> Result<(), String> {
     #[cfg(target_os = "macos")]
     {
         // On macOS, use diskutil to eject the volume
@@ -5579,8 +9802,7 @@ pub async fn eject_drive(path: String) -> Result<(), String> {
         if output.status.success() {
             Ok(())
         } else {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
-            Err(format!("Failed to eject drive: {}", error_msg))
+            Err(eject_failure(&String::from_utf8_lossy(&output.stderr)))
         }
     }
 
@@ -5596,16 +9818,15 @@ pub async fn eject_drive(path: String) -> Result<(), String> {
         if output.status.success() {
             Ok(())
         } else {
-            let error_msg = String::from_utf8_lossy(&output.stderr);
-            Err(format!("Failed to eject drive: {}", error_msg))
+            Err(eject_failure(&String::from_utf8_lossy(&output.stderr)))
         }
     }
 
     #[cfg(target_os = "windows")]
     {
-        // On Windows, we would need to use Windows API for safe removal
-        // For now, return an error as it requires more complex implementation
-        Err("Drive ejection not yet implemented for Windows".to_string())
+        tauri::async_runtime::spawn_blocking(move || eject_windows_volume(&path))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
     }
 
     #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
@@ -5614,6 +9835,156 @@ pub async fn eject_drive(path: String) -> Result<(), String> {
     }
 }
 
+/// The error for a failed unmount, coded `EBUSY` when the tool says something still
+/// has the volume open. diskutil names the dissenting process in its message.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn eject_failure(stderr: &str) -> String {
+    let message = format!("Failed to eject drive: {}", stderr.trim());
+    let lower = stderr.to_lowercase();
+    if ["busy", "in use", "dissented"]
+        .iter()
+        .any(|hint| lower.contains(hint))
+    {
+        format_error(error_codes::EBUSY, &message)
+    } else {
+        message
+    }
+}
+
+/// Safely remove a removable or optical drive: lock the volume so nothing else can
+/// open it, dismount it, allow removal, then eject the media. The steps follow
+/// Microsoft's "How to eject removable media" sample.
+#[cfg(target_os = "windows")]
+fn eject_windows_volume(path: &str) -> Result<(), String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{
+        CloseHandle, ERROR_ACCESS_DENIED, ERROR_SHARING_VIOLATION, GENERIC_READ, GENERIC_WRITE,
+        HANDLE, WIN32_ERROR,
+    };
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_FLAGS_AND_ATTRIBUTES, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+    use windows::Win32::System::Ioctl::{
+        FSCTL_DISMOUNT_VOLUME, FSCTL_LOCK_VOLUME, IOCTL_STORAGE_EJECT_MEDIA,
+        IOCTL_STORAGE_MEDIA_REMOVAL, PREVENT_MEDIA_REMOVAL,
+    };
+    use windows::Win32::System::WindowsProgramming::{DRIVE_CDROM, DRIVE_REMOVABLE};
+    use windows::Win32::System::IO::DeviceIoControl;
+
+    const LOCK_ATTEMPTS: u32 = 10;
+    const LOCK_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+    struct Volume(HANDLE);
+    impl Drop for Volume {
+        fn drop(&mut self) {
+            let _ = unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    let letter = path
+        .chars()
+        .next()
+        .filter(|c| c.is_ascii_alphabetic() && path[1..].starts_with(':'))
+        .map(|c| c.to_ascii_uppercase())
+        .ok_or_else(|| format!("Failed to eject drive: {} is not a drive letter", path))?;
+    let root = format!("{letter}:\\");
+    if !matches!(windows_drive_kind(&root), DRIVE_REMOVABLE | DRIVE_CDROM) {
+        return Err(format_error(
+            error_codes::EUNSUPPORTED,
+            &format!("Failed to eject drive: {letter}: is not a removable drive"),
+        ));
+    }
+
+    let in_use = |err: &windows::core::Error| {
+        matches!(
+            WIN32_ERROR::from_error(err),
+            Some(ERROR_ACCESS_DENIED | ERROR_SHARING_VIOLATION)
+        )
+    };
+    let busy = |reason: &str| {
+        format_error(
+            error_codes::EBUSY,
+            &format!("Failed to eject drive: {letter}: is in use ({reason})"),
+        )
+    };
+    let control = |volume: &Volume, code: u32, input: Option<&PREVENT_MEDIA_REMOVAL>| {
+        let mut returned = 0u32;
+        unsafe {
+            DeviceIoControl(
+                volume.0,
+                code,
+                input.map(|i| i as *const _ as *const std::ffi::c_void),
+                input.map_or(0, |_| std::mem::size_of::<PREVENT_MEDIA_REMOVAL>() as u32),
+                None,
+                0,
+                Some(&mut returned),
+                None,
+            )
+        }
+    };
+
+    let device: Vec<u16> = std::ffi::OsStr::new(&format!("\\\\.\\{letter}:"))
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let volume = unsafe {
+        CreateFileW(
+            PCWSTR(device.as_ptr()),
+            (GENERIC_READ | GENERIC_WRITE).0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            None,
+        )
+    }
+    .map(Volume)
+    .map_err(|e| format!("Failed to eject drive: could not open {letter}: ({e})"))?;
+
+    // Another program holding a file open keeps the lock from being granted
+    let mut attempt = 0;
+    loop {
+        match control(&volume, FSCTL_LOCK_VOLUME, None) {
+            Ok(()) => break,
+            Err(e) if in_use(&e) && attempt + 1 < LOCK_ATTEMPTS => {
+                attempt += 1;
+                std::thread::sleep(LOCK_RETRY_DELAY);
+            }
+            Err(e) if in_use(&e) => {
+                return Err(busy("files on it are open in another program"));
+            }
+            Err(e) => {
+                return Err(format!(
+                    "Failed to eject drive: could not lock {letter}: ({e})"
+                ))
+            }
+        }
+    }
+
+    control(&volume, FSCTL_DISMOUNT_VOLUME, None).map_err(|e| {
+        if in_use(&e) {
+            busy("the volume could not be dismounted")
+        } else {
+            format!("Failed to eject drive: could not dismount {letter}: ({e})")
+        }
+    })?;
+
+    let allow = PREVENT_MEDIA_REMOVAL {
+        PreventMediaRemoval: false,
+    };
+    control(&volume, IOCTL_STORAGE_MEDIA_REMOVAL, Some(&allow)).map_err(|e| {
+        if in_use(&e) {
+            busy("another program is preventing media removal")
+        } else {
+            format!("Failed to eject drive: could not unlock media in {letter}: ({e})")
+        }
+    })?;
+
+    control(&volume, IOCTL_STORAGE_EJECT_MEDIA, None)
+        .map_err(|e| format!("Failed to eject drive: {letter}: ({e})"))
+}
+
 // Global thumbnail service instance
 static THUMBNAIL_SERVICE: OnceCell<Result<Arc<crate::thumbnails::ThumbnailService>, String>> =
     OnceCell::const_new();
@@ -5641,36 +10012,239 @@ pub async fn request_thumbnail(
     accent: Option<crate::thumbnails::AccentColor>,
 ) -> Result<crate::thumbnails::ThumbnailResponse, String> {
     let service = get_thumbnail_service().await?;
+    let request = build_thumbnail_request(ThumbnailBatchRequest {
+        path,
+        size,
+        quality,
+        priority,
+        format,
+        accent,
+    });
+    service.request_thumbnail(request).await
+}
+
+/// One entry of a `request_thumbnails_batch` call; same options as `request_thumbnail`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailBatchRequest {
+    pub path: String,
+    pub size: Option<u32>,
+    pub quality: Option<String>,
+    pub priority: Option<String>,
+    pub format: Option<String>,
+    pub accent: Option<crate::thumbnails::AccentColor>,
+}
 
-    let quality = match quality.as_deref() {
+fn build_thumbnail_request(input: ThumbnailBatchRequest) -> crate::thumbnails::ThumbnailRequest {
+    let quality = match input.quality.as_deref() {
         Some("low") => crate::thumbnails::ThumbnailQuality::Low,
         Some("high") => crate::thumbnails::ThumbnailQuality::High,
         _ => crate::thumbnails::ThumbnailQuality::Medium,
     };
 
-    let priority = match priority.as_deref() {
+    let priority = match input.priority.as_deref() {
         Some("high") => crate::thumbnails::ThumbnailPriority::High,
         Some("low") => crate::thumbnails::ThumbnailPriority::Low,
         _ => crate::thumbnails::ThumbnailPriority::Medium,
     };
 
-    let format = match format.as_deref() {
+    let format = match input.format.as_deref() {
         Some("jpeg") => crate::thumbnails::ThumbnailFormat::JPEG,
         Some("png") => crate::thumbnails::ThumbnailFormat::PNG,
+        Some("animated-webp") => crate::thumbnails::ThumbnailFormat::AnimatedWebP,
         _ => crate::thumbnails::ThumbnailFormat::WebP,
     };
 
-    let request = crate::thumbnails::ThumbnailRequest {
+    crate::thumbnails::ThumbnailRequest {
         id: crate::thumbnails::generate_request_id(),
-        path,
-        size: size.unwrap_or(128),
+        path: input.path,
+        size: input.size.unwrap_or(128),
         quality,
         priority,
         format,
-        accent,
+        accent: input.accent,
+    }
+}
+
+/// Request many thumbnails in one round trip. Cache hits are returned directly; the
+/// rest arrive as `thumbnail:batch` events tagged with `batch_id` as they finish.
+#[tauri::command]
+pub async fn request_thumbnails_batch(
+    app: AppHandle,
+    batch_id: String,
+    requests: Vec<ThumbnailBatchRequest>,
+) -> Result<Vec<crate::thumbnails::ThumbnailBatchItem>, String> {
+    let service = get_thumbnail_service().await?;
+    let requests = requests.into_iter().map(build_thumbnail_request).collect();
+
+    Ok(service
+        .request_batch(batch_id, requests, move |item| {
+            if let Err(err) = app.emit(THUMBNAIL_BATCH_EVENT, &item) {
+                warn!("Failed to emit thumbnail batch result: {err}");
+            }
+        })
+        .await)
+}
+
+/// A local copy of `location` for previewing or reading. Returns the path and whether it is a
+/// temp file the caller has to remove. With `max_bytes`, remote files are only fetched
+/// that far.
+async fn preview_local_copy(
+    location: &Location,
+    name: &str,
+    max_bytes: Option<u64>,
+) -> Result<(PathBuf, bool), String> {
+    if location.scheme() == "archive" {
+        return crate::locations::archive::extract_archive_entry_to_temp(location.raw())
+            .await
+            .map(|path| (path, true));
+    }
+    fetch_to_local(location, name, max_bytes).await
+}
+
+/// Preview data for the preview panel: decoded text for text, code and markdown
+/// files, and a large rendering of anything the thumbnail generators can draw.
+#[command]
+pub async fn get_file_preview(
+    path: LocationInput,
+    max_size: Option<u32>,
+    max_text_bytes: Option<usize>,
+) -> Result<crate::preview::FilePreview, String> {
+    use crate::preview::{self, PreviewKind};
+
+    let (_, location) = resolve_location(path)?;
+    let name = Path::new(location.path())
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file")
+        .to_string();
+    let mime_type = preview::mime_type(Path::new(&name));
+    let max_text_bytes = max_text_bytes.unwrap_or(crate::text_file::DEFAULT_TEXT_BYTES);
+
+    let kind = preview::preview_kind(Path::new(&name));
+    if kind != PreviewKind::Visual {
+        let head_bytes = crate::text_file::head_download_bytes(max_text_bytes);
+        let (local, is_temp) = preview_local_copy(&location, &name, Some(head_bytes)).await?;
+        let read_path = local.clone();
+        let result = tauri::async_runtime::spawn_blocking(move || {
+            #[cfg(target_os = "macos")]
+            let _scope_guard = macos_security::retain_access(&read_path)?;
+            preview::text_preview(&read_path, kind, max_text_bytes)
+        })
+        .await
+        .map_err(|e| format!("Failed to join preview task: {}", e))?;
+        if is_temp {
+            let _ = std::fs::remove_file(&local);
+        }
+        return result.map(|preview| preview::FilePreview {
+            mime_type,
+            ..preview
+        });
+    }
+
+    // Same download and dispatch as thumbnails, at preview size
+    let source = if location.scheme() == "file" {
+        expand_path(&location.to_path_string())?
+            .to_string_lossy()
+            .to_string()
+    } else {
+        location.raw().to_string()
     };
+    let size = max_size.unwrap_or(preview::DEFAULT_PREVIEW_SIZE);
+    let prepared = crate::thumbnails::generators::ThumbnailGenerator::prepare(
+        preview::preview_request(source, size),
+    )
+    .await?;
+    let temp_file = prepared.temp_file.clone();
+    let remote_permit = prepared.remote_permit;
+    let request = prepared.request;
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        preview::visual_preview(&request, mime_type, max_text_bytes)
+    })
+    .await
+    .map_err(|e| format!("Failed to join preview task: {}", e));
+    drop(remote_permit);
+    if let Some(temp) = temp_file {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result?
+}
 
-    service.request_thumbnail(request).await
+/// The start of a text file decoded to UTF-8, along with the encoding it was saved
+/// in. Only the start of a remote file is downloaded, to a temp file.
+#[command]
+pub async fn read_text_file(
+    path: LocationInput,
+    max_bytes: Option<usize>,
+) -> Result<crate::text_file::TextContent, String> {
+    let (_, location) = resolve_location(path)?;
+    let name = Path::new(location.path())
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file")
+        .to_string();
+    let max_bytes = max_bytes.unwrap_or(crate::text_file::DEFAULT_TEXT_BYTES);
+
+    let head_bytes = crate::text_file::head_download_bytes(max_bytes);
+    let (local, is_temp) = preview_local_copy(&location, &name, Some(head_bytes)).await?;
+    let read_path = local.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(target_os = "macos")]
+        let _scope_guard = macos_security::retain_access(&read_path)?;
+        crate::text_file::read_text(&read_path, max_bytes)
+    })
+    .await
+    .map_err(|e| format!("Failed to join read task: {}", e))?;
+    if is_temp {
+        let _ = std::fs::remove_file(&local);
+    }
+    result
+}
+
+/// Compare two files: a line diff for text, identical/different for binary files.
+/// Remote files are downloaded to temp files first.
+#[command]
+pub async fn diff_files(
+    left_path: LocationInput,
+    right_path: LocationInput,
+) -> Result<crate::file_diff::FileDiff, String> {
+    let mut copies = Vec::with_capacity(2);
+    for input in [left_path, right_path] {
+        let (_, location) = resolve_location(input)?;
+        let name = Path::new(location.path())
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("file")
+            .to_string();
+        match preview_local_copy(&location, &name, None).await {
+            Ok(copy) => copies.push(copy),
+            Err(e) => {
+                for (path, is_temp) in &copies {
+                    if *is_temp {
+                        let _ = std::fs::remove_file(path);
+                    }
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    let (left, right) = (copies[0].0.clone(), copies[1].0.clone());
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        #[cfg(target_os = "macos")]
+        let _left_guard = macos_security::retain_access(&left)?;
+        #[cfg(target_os = "macos")]
+        let _right_guard = macos_security::retain_access(&right)?;
+        crate::file_diff::diff_files(&left, &right, crate::file_diff::MAX_DIFF_BYTES)
+    })
+    .await
+    .map_err(|e| format!("Failed to join diff task: {}", e));
+    for (path, is_temp) in &copies {
+        if *is_temp {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+    result?
 }
 
 #[tauri::command]
@@ -5708,6 +10282,12 @@ pub async fn cancel_thumbnail(request_id: String) -> Result<bool, String> {
     Ok(service.cancel_request(&request_id).await)
 }
 
+#[tauri::command]
+pub async fn cancel_thumbnail_batch(batch_id: String) -> Result<bool, String> {
+    let service = get_thumbnail_service().await?;
+    Ok(service.cancel_batch(&batch_id).await)
+}
+
 #[tauri::command]
 pub async fn cancel_all_thumbnails() -> Result<bool, String> {
     let service = get_thumbnail_service().await?;
@@ -5880,6 +10460,117 @@ pub fn reveal_in_file_browser(path: String) -> Result<(), String> {
     }
 }
 
+/// Open the platform's terminal with the folder at `path` as its working directory.
+#[command]
+pub fn open_terminal_here(path: String) -> Result<(), String> {
+    let expanded = expand_path(&path)?;
+    let metadata = fs::metadata(&expanded).map_err(map_reveal_path_error)?;
+    if !metadata.is_dir() {
+        return Err(format_error(
+            error_codes::ENOTDIR,
+            "A terminal can only be opened in a folder.",
+        ));
+    }
+    launch_terminal(&expanded)
+}
+
+/// Terminal.app, or iTerm when it's installed and `globalPreferences.preferITerm`
+/// isn't turned off.
+#[cfg(target_os = "macos")]
+fn launch_terminal(dir: &Path) -> Result<(), String> {
+    let prefer_iterm = read_prefs_value()
+        .ok()
+        .and_then(|v| v.get("globalPreferences")?.get("preferITerm")?.as_bool())
+        .unwrap_or(true);
+    let iterm_installed = || {
+        let user_apps = dirs::home_dir().map(|home| home.join("Applications/iTerm.app"));
+        Path::new("/Applications/iTerm.app").exists() || user_apps.is_some_and(|p| p.exists())
+    };
+    let app = if prefer_iterm && iterm_installed() {
+        "iTerm"
+    } else {
+        "Terminal"
+    };
+
+    OsCommand::new("open")
+        .args(["-a", app])
+        .arg(dir)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| {
+            format_error(
+                error_codes::EOPEN,
+                &format!("Failed to launch {}: {}", app, e),
+            )
+        })
+}
+
+/// Windows Terminal, falling back to a new Command Prompt window.
+#[cfg(target_os = "windows")]
+fn launch_terminal(dir: &Path) -> Result<(), String> {
+    match OsCommand::new("wt.exe").arg("-d").arg(dir).spawn() {
+        Ok(_) => return Ok(()),
+        Err(e) => info!("Windows Terminal unavailable, falling back to cmd: {}", e),
+    }
+    // `start` gives cmd a console window of its own
+    OsCommand::new("cmd")
+        .args(["/C", "start", "cmd"])
+        .current_dir(dir)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| {
+            format_error(
+                error_codes::EOPEN,
+                &format!("Failed to launch a terminal: {}", e),
+            )
+        })
+}
+
+/// `$TERMINAL` if set, then the common emulators in order.
+#[cfg(target_os = "linux")]
+fn launch_terminal(dir: &Path) -> Result<(), String> {
+    let from_env = env::var("TERMINAL").ok().and_then(|value| {
+        let mut parts = value.split_whitespace().map(str::to_string);
+        let program = parts.next()?;
+        Some((program, parts.collect::<Vec<_>>()))
+    });
+    let fallbacks = ["gnome-terminal", "konsole", "xterm"]
+        .into_iter()
+        .map(|program| (program.to_string(), Vec::new()));
+
+    for (program, args) in from_env.into_iter().chain(fallbacks) {
+        let mut command = OsCommand::new(&program);
+        command.args(&args).current_dir(dir);
+        // These hand the window to a running server, which ignores our working directory
+        match Path::new(&program).file_name().and_then(|n| n.to_str()) {
+            Some("gnome-terminal") => {
+                command.arg(format!("--working-directory={}", dir.display()));
+            }
+            Some("konsole") => {
+                command.arg("--workdir").arg(dir);
+            }
+            _ => {}
+        }
+        match command.spawn() {
+            Ok(_) => return Ok(()),
+            Err(e) => info!("Could not launch terminal {}: {}", program, e),
+        }
+    }
+
+    Err(format_error(
+        error_codes::EOPEN,
+        "No terminal could be launched. Set $TERMINAL to your terminal emulator.",
+    ))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn launch_terminal(_dir: &Path) -> Result<(), String> {
+    Err(format_error(
+        error_codes::EOPEN,
+        "Opening a terminal is not supported on this platform.",
+    ))
+}
+
 #[command]
 pub fn open_path(path: String) -> Result<(), String> {
     // Normalize path (~ expansion is already handled on the frontend for navigation)
@@ -5980,6 +10671,10 @@ pub fn open_path_with(path: String, application_path: String) -> Result<(), Stri
 
     #[cfg(target_os = "linux")]
     {
+        // Entries from `get_applications_for_file` are .desktop files
+        if app_path.extension().and_then(|e| e.to_str()) == Some("desktop") {
+            return crate::open_with::launch(&app_path, &file_path);
+        }
         return OsCommand::new(&expanded_application)
             .arg(&expanded_path)
             .spawn()
@@ -5993,6 +10688,25 @@ pub fn open_path_with(path: String, application_path: String) -> Result<(), Stri
     }
 }
 
+/// Applications that can open `path`, the default one first and flagged, for an
+/// "Open With" menu. Pass an entry's `path` to `open_path_with`. macOS and Linux only.
+#[command]
+pub async fn get_applications_for_file(
+    path: String,
+    icon_size: Option<u32>,
+) -> Result<Vec<crate::open_with::ApplicationInfo>, String> {
+    let expanded = expand_path(&path)?;
+    if !expanded.exists() {
+        return Err(format_error(error_codes::ENOENT, "Item not found."));
+    }
+    let icon_size = icon_size.unwrap_or(32);
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::open_with::applications_for(&expanded, icon_size)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
 #[command]
 pub fn new_window(app: AppHandle, path: Option<String>) -> Result<(), String> {
     let window_label = format!(
@@ -6954,6 +11668,15 @@ pub async fn calculate_folder_size(
         let app_for_compute = app_for_task.clone();
         let request_for_compute = request_key.clone();
 
+        // Show the last result for unchanged roots right away, then walk again quietly
+        // in case something deeper down changed
+        let cache_key = folder_size_cache::CacheKey::new(&paths_for_task);
+        let cached = folder_size_cache::lookup(&cache_key);
+        if let Some(totals) = cached {
+            info!("Using cached folder size for request {}", request_key);
+            emit_folder_size_totals(&app_for_task, &request_key, totals);
+        }
+
         info!("Spawning blocking task for {} paths", paths_for_task.len());
         let join_result = tauri::async_runtime::spawn_blocking(move || {
             info!(
@@ -6965,11 +11688,20 @@ pub async fn calculate_folder_size(
                 &request_for_compute,
                 &paths_for_task,
                 &cancel_for_task,
+                cached.is_some(),
             )
         })
         .await;
 
-        if let Err(join_err) = join_result {
+        if let Ok(Some(totals)) = &join_result {
+            if cached.is_some_and(|cached| cached != *totals) {
+                emit_folder_size_totals(&app_for_task, &request_key, *totals);
+            }
+            folder_size_cache::store(cache_key, *totals);
+        }
+
+        // A failed re-verification leaves the cached result on screen
+        if let (Err(join_err), None) = (join_result, cached) {
             emit_folder_size_event(
                 &app_for_task,
                 &request_key,
@@ -7047,13 +11779,20 @@ pub fn read_preferences() -> Result<String, String> {
 #[tauri::command]
 pub fn write_preferences(json: String) -> Result<(), String> {
     let path = preferences_path()?;
-    let mut file =
-        fs::File::create(&path).map_err(|e| format!("Failed to create preferences: {}", e))?;
-    file.write_all(json.as_bytes())
-        .map_err(|e| format!("Failed to write preferences: {}", e))?;
+    fs_utils::write_file_atomically(&path, json.as_bytes())?;
+    crate::locations::throttle::configure_from_preferences(&json);
+    crate::git_status::configure_from_preferences(&json);
+    crate::locations::archive::configure_from_preferences(&json);
+    crate::thumbnails::configure_from_preferences(&json);
     Ok(())
 }
 
+/// Limits and in-flight/queued operation counts for each throttled remote scheme.
+#[tauri::command]
+pub fn get_remote_concurrency_stats() -> Vec<crate::locations::throttle::RemoteConcurrencyStats> {
+    crate::locations::throttle::stats()
+}
+
 fn normalize_path(s: String) -> String {
     if s.is_empty() {
         return "/".to_string();
@@ -7099,12 +11838,8 @@ fn read_prefs_value() -> Result<Value, String> {
 
 fn write_prefs_value(v: &Value) -> Result<(), String> {
     let path = preferences_path()?;
-    let mut file =
-        fs::File::create(&path).map_err(|e| format!("Failed to create preferences: {}", e))?;
     let s = serde_json::to_string_pretty(v).map_err(|e| e.to_string())?;
-    file.write_all(s.as_bytes())
-        .map_err(|e| format!("Failed to write preferences: {}", e))?;
-    Ok(())
+    fs_utils::write_file_atomically(&path, s.as_bytes())
 }
 
 #[tauri::command]
@@ -7121,7 +11856,11 @@ pub fn get_dir_prefs(path: String) -> Result<String, String> {
 }
 
 #[tauri::command]
-pub fn set_dir_prefs(path: String, prefs: String) -> Result<(), String> {
+pub fn set_dir_prefs(
+    sort_prefs: tauri::State<'_, SortPreferencesState>,
+    path: String,
+    prefs: String,
+) -> Result<(), String> {
     let norm = normalize_path(path);
     let mut v = read_prefs_value()?;
     let dirs = v
@@ -7142,11 +11881,16 @@ pub fn set_dir_prefs(path: String, prefs: String) -> Result<(), String> {
     let mut new_dirs = serde_json::Map::from_iter(dirs.into_iter());
     new_dirs.insert(norm, merged);
     v["directoryPreferences"] = Value::Object(new_dirs);
-    write_prefs_value(&v)
+    write_prefs_value(&v)?;
+    forget_sort_preferences(&sort_prefs);
+    Ok(())
 }
 
 #[tauri::command]
-pub fn set_global_prefs(prefs: String) -> Result<(), String> {
+pub fn set_global_prefs(
+    sort_prefs: tauri::State<'_, SortPreferencesState>,
+    prefs: String,
+) -> Result<(), String> {
     let mut v = read_prefs_value()?;
     let incoming: Value =
         serde_json::from_str(&prefs).map_err(|e| format!("Invalid prefs JSON: {}", e))?;
@@ -7159,14 +11903,23 @@ pub fn set_global_prefs(prefs: String) -> Result<(), String> {
         merged = incoming;
     }
     v["globalPreferences"] = merged;
-    write_prefs_value(&v)
+    write_prefs_value(&v)?;
+    forget_sort_preferences(&sort_prefs);
+    let json = v.to_string();
+    crate::locations::archive::configure_from_preferences(&json);
+    crate::thumbnails::configure_from_preferences(&json);
+    Ok(())
 }
 
 #[tauri::command]
-pub fn clear_all_dir_prefs() -> Result<(), String> {
+pub fn clear_all_dir_prefs(
+    sort_prefs: tauri::State<'_, SortPreferencesState>,
+) -> Result<(), String> {
     let mut v = read_prefs_value()?;
     v["directoryPreferences"] = json!({});
-    write_prefs_value(&v)
+    write_prefs_value(&v)?;
+    forget_sort_preferences(&sort_prefs);
+    Ok(())
 }
 
 #[tauri::command]
@@ -7198,14 +11951,59 @@ pub fn toggle_menu_visibility(app: AppHandle) -> Result<bool, String> {
     }
 }
 
+/// What a drag out of the app offers to the drop target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NativeDragOperation {
+    Copy,
+    Move,
+    Link,
+    /// Leave it to the OS and the drop target, as before hints existed
+    #[default]
+    Auto,
+}
+
+/// Payload of `native-drag:ended`, sent once a hinted drag finishes.
+#[cfg(target_os = "macos")]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeDragEnded {
+    pub paths: Vec<String>,
+    /// What the drop target performed; `None` if the drag was cancelled or refused
+    pub operation: Option<NativeDragOperation>,
+}
+
+/// Start a native drag of `paths`. `drag_operation` limits the drag to copying,
+/// moving or linking so the cursor badge and the drop target agree, and the
+/// outcome is reported with a `native-drag:ended` event. `auto` (the default)
+/// keeps the OS default and sends no event.
 #[cfg(target_os = "macos")]
 #[tauri::command]
 pub fn start_native_drag(
+    app: AppHandle,
     paths: Vec<String>,
     preview_image: Option<String>,
+    preview_images: Option<Vec<String>>,
     drag_offset_y: Option<f64>,
+    drag_operation: Option<NativeDragOperation>,
 ) -> Result<(), String> {
-    crate::native_drag::start_native_drag(paths, preview_image, drag_offset_y)
+    let ended_paths = paths.clone();
+    crate::native_drag::start_native_drag(
+        paths,
+        preview_image,
+        preview_images,
+        drag_offset_y,
+        drag_operation.unwrap_or_default(),
+        move |operation| {
+            let _ = app.emit(
+                NATIVE_DRAG_ENDED_EVENT,
+                NativeDragEnded {
+                    paths: ended_paths,
+                    operation,
+                },
+            );
+        },
+    )
 }
 
 #[cfg(not(target_os = "macos"))]
@@ -7213,25 +12011,30 @@ pub fn start_native_drag(
 pub fn start_native_drag(
     _paths: Vec<String>,
     _preview_image: Option<String>,
+    _preview_images: Option<Vec<String>>,
     _drag_offset_y: Option<f64>,
+    _drag_operation: Option<NativeDragOperation>,
 ) -> Result<(), String> {
     Err("Native drag is only supported on macOS".to_string())
 }
 
 // File system watcher commands
+/// Watch a directory for changes. With `recursive`, subfolders are watched too, up
+/// to `fs_watcher::MAX_WATCHED_DIRECTORIES` folders across all watches; larger trees
+/// fail with `ELIMIT`.
 #[command]
-pub fn start_watching_directory(path: String) -> Result<(), String> {
+pub fn start_watching_directory(path: String, recursive: Option<bool>) -> Result<(), String> {
     if let Some(watcher) = fs_watcher::get_watcher() {
-        watcher.start_watching(&path)
+        watcher.start_watching(&path, recursive.unwrap_or(false))
     } else {
         Err("File system watcher not initialized".to_string())
     }
 }
 
 #[command]
-pub fn stop_watching_directory(path: String) -> Result<(), String> {
+pub fn stop_watching_directory(path: String, recursive: Option<bool>) -> Result<(), String> {
     if let Some(watcher) = fs_watcher::get_watcher() {
-        watcher.stop_watching(&path)
+        watcher.stop_watching(&path, recursive.unwrap_or(false))
     } else {
         Err("File system watcher not initialized".to_string())
     }
@@ -7257,20 +12060,35 @@ pub fn is_watching_directory(path: String) -> Result<bool, String> {
 }
 
 #[command]
-pub fn get_watched_directories() -> Result<Vec<String>, String> {
+pub fn get_watched_directories() -> Result<Vec<fs_watcher::WatchedDirectory>, String> {
     if let Some(watcher) = fs_watcher::get_watcher() {
-        Ok(watcher.get_watched_paths())
+        Ok(watcher.get_watched_directories())
     } else {
         Ok(vec![])
     }
 }
 
-/// Internal representation stored in JSON (minimal fields for persistence)
+/// How long a watched directory must be quiet before its changes are reported
+#[command]
+pub fn set_watcher_debounce_ms(ms: u64) -> Result<(), String> {
+    if let Some(watcher) = fs_watcher::get_watcher() {
+        watcher.set_debounce_ms(ms)
+    } else {
+        Err("File system watcher not initialized".to_string())
+    }
+}
+
+/// Internal representation stored in JSON (minimal fields for persistence).
+/// `color` and `icon` were added later; files written before them still load.
 #[derive(Serialize, Deserialize, Clone)]
 struct StoredPinnedDirectory {
     pub name: String,
     pub path: String,
     pub pinned_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
 }
 
 /// Public representation with computed metadata (returned to frontend)
@@ -7281,6 +12099,43 @@ pub struct PinnedDirectory {
     pub pinned_at: DateTime<Utc>,
     pub is_git_repo: bool,
     pub is_symlink: bool,
+    /// Hex colour for the pin's folder icon, e.g. "#e5484d"
+    pub color: Option<String>,
+    /// Symbol name or emoji shown instead of the folder icon
+    pub icon: Option<String>,
+}
+
+/// Longest icon accepted for a pin; emoji sequences can run to several code points
+const MAX_PIN_ICON_CHARS: usize = 32;
+
+/// Check a pin colour is `#rgb`, `#rrggbb` or `#rrggbbaa`. Empty means no colour.
+fn validate_pin_color(color: Option<String>) -> Result<Option<String>, String> {
+    let Some(color) = color
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty())
+    else {
+        return Ok(None);
+    };
+    let valid = color.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    });
+    if !valid {
+        return Err(format!(
+            "Invalid pin color \"{color}\"; expected a hex color like #3b82f6"
+        ));
+    }
+    Ok(Some(color.to_ascii_lowercase()))
+}
+
+/// Check a pin icon is a short symbol name or emoji. Empty means the default icon.
+fn validate_pin_icon(icon: Option<String>) -> Result<Option<String>, String> {
+    let Some(icon) = icon.map(|i| i.trim().to_string()).filter(|i| !i.is_empty()) else {
+        return Ok(None);
+    };
+    if icon.chars().count() > MAX_PIN_ICON_CHARS || icon.chars().any(char::is_control) {
+        return Err("Pin icon must be a symbol name or emoji".to_string());
+    }
+    Ok(Some(icon))
 }
 
 fn pinned_directories_path() -> Result<PathBuf, String> {
@@ -7312,6 +12167,14 @@ fn load_stored_pinned_directories() -> Result<Vec<StoredPinnedDirectory>, String
     Ok(pinned_dirs)
 }
 
+/// Paths of the pinned directories, in sidebar order
+pub(crate) fn pinned_directory_paths() -> Result<Vec<PathBuf>, String> {
+    Ok(load_stored_pinned_directories()?
+        .into_iter()
+        .map(|pin| PathBuf::from(pin.path))
+        .collect())
+}
+
 /// Compute git repo and symlink status for a path
 fn compute_pin_metadata(path: &Path) -> (bool, bool) {
     // Check if symlink using symlink_metadata (doesn't follow symlinks)
@@ -7345,6 +12208,8 @@ pub fn get_pinned_directories() -> Result<Vec<PinnedDirectory>, String> {
                 pinned_at: stored_pin.pinned_at,
                 is_git_repo,
                 is_symlink,
+                color: stored_pin.color,
+                icon: stored_pin.icon,
             }
         })
         .collect();
@@ -7357,11 +12222,15 @@ pub async fn add_pinned_directory(
     app: AppHandle,
     path: String,
     name: Option<String>,
+    color: Option<String>,
+    icon: Option<String>,
 ) -> Result<PinnedDirectory, String> {
     let trimmed = path.trim();
     if trimmed.is_empty() {
         return Err("Path is required".to_string());
     }
+    let color = validate_pin_color(color)?;
+    let icon = validate_pin_icon(icon)?;
 
     let input = LocationInput::Raw(trimmed.to_string());
     let parsed = input
@@ -7384,12 +12253,25 @@ pub async fn add_pinned_directory(
             .map(|s| s.to_string());
         (expanded_str, Some(expanded), filename)
     } else {
+        // Remote folders aren't fetched, so a server can be pinned while it's
+        // unreachable; it only has to belong to a provider that can browse it
         let (provider, location) = resolve_location(input)?;
-        let metadata = provider.get_file_metadata(&location).await?;
-        if !metadata.is_directory {
-            return Err("Path is not a directory".to_string());
+        let capabilities = provider.capabilities(&location);
+        if !capabilities.can_read {
+            return Err(format!(
+                "{} locations can't be pinned",
+                capabilities.display_name
+            ));
         }
-        (location.raw().to_string(), None, Some(metadata.name))
+        let folder_name = location
+            .path()
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .filter(|segment| !segment.is_empty())
+            .or(location.authority())
+            .map(|name| name.to_string());
+        (location.raw().to_string(), None, folder_name)
     };
 
     // Normalize path by removing trailing slashes for consistent storage and comparison
@@ -7399,10 +12281,7 @@ pub async fn add_pinned_directory(
     let mut stored_pins = load_stored_pinned_directories()?;
 
     // Check if already pinned (compare normalized paths to handle trailing slash differences)
-    if stored_pins
-        .iter()
-        .any(|p| normalize_trailing_slash(&p.path) == stored_path)
-    {
+    if stored_pins.iter().any(|p| is_same_pin(p, &stored_path)) {
         return Err("Directory is already pinned".to_string());
     }
 
@@ -7425,6 +12304,8 @@ pub async fn add_pinned_directory(
         name: dir_name.clone(),
         path: stored_path.clone(),
         pinned_at: Utc::now(),
+        color,
+        icon,
     };
 
     stored_pins.push(new_stored.clone());
@@ -7445,25 +12326,41 @@ pub async fn add_pinned_directory(
         pinned_at: new_stored.pinned_at,
         is_git_repo,
         is_symlink,
+        color: new_stored.color,
+        icon: new_stored.icon,
     })
 }
 
+/// The form a pin's path is stored and compared in: the expanded path for local
+/// folders, the parsed URI (lowercase scheme, no trailing slash) for remote ones
+fn normalize_pin_path(path: &str) -> Result<String, String> {
+    let location = LocationInput::Raw(path.trim().to_string())
+        .into_location()
+        .map_err(|e| format!("Invalid path: {e}"))?;
+    if location.scheme() == "file" {
+        // For local paths, expand ~ and resolve the path
+        let expanded_path = expand_path(path.trim())?;
+        Ok(normalize_trailing_slash(&expanded_path.to_string_lossy()))
+    } else {
+        Ok(normalize_trailing_slash(location.raw()))
+    }
+}
+
+/// Whether a stored pin is the folder at `normalized`, a `normalize_pin_path` result.
+/// Pins saved by older versions may not be normalized yet.
+fn is_same_pin(pin: &StoredPinnedDirectory, normalized: &str) -> bool {
+    normalize_pin_path(&pin.path).unwrap_or_else(|_| normalize_trailing_slash(&pin.path))
+        == normalized
+}
+
 #[command]
 pub fn remove_pinned_directory(app: AppHandle, path: String) -> Result<bool, String> {
-    // Handle remote URIs (smb://, gdrive://) differently from local paths
-    let normalized_path = if path.starts_with("smb://") || path.starts_with("gdrive://") {
-        // For remote URIs, use the path as-is (just normalize trailing slashes)
-        normalize_trailing_slash(&path)
-    } else {
-        // For local paths, expand ~ and resolve the path
-        let expanded_path = expand_path(&path)?;
-        normalize_trailing_slash(&expanded_path.to_string_lossy())
-    };
+    let normalized_path = normalize_pin_path(&path)?;
 
     let mut stored_pins = load_stored_pinned_directories()?;
 
     let initial_len = stored_pins.len();
-    stored_pins.retain(|p| normalize_trailing_slash(&p.path) != normalized_path);
+    stored_pins.retain(|p| !is_same_pin(p, &normalized_path));
 
     if stored_pins.len() < initial_len {
         save_pinned_directories(&stored_pins)?;
@@ -7475,6 +12372,52 @@ pub fn remove_pinned_directory(app: AppHandle, path: String) -> Result<bool, Str
     }
 }
 
+/// Rename a pin and set its colour and icon. `name` keeps the current name when
+/// absent; an absent or empty `color`/`icon` goes back to the default.
+#[command]
+pub fn update_pinned_directory(
+    app: AppHandle,
+    path: String,
+    name: Option<String>,
+    color: Option<String>,
+    icon: Option<String>,
+) -> Result<PinnedDirectory, String> {
+    let normalized_path = normalize_pin_path(&path)?;
+    let color = validate_pin_color(color)?;
+    let icon = validate_pin_icon(icon)?;
+    let name = name.map(|n| n.trim().to_string());
+    if name.as_deref() == Some("") {
+        return Err("Pin name cannot be empty".to_string());
+    }
+
+    let mut stored_pins = load_stored_pinned_directories()?;
+    let pin = stored_pins
+        .iter_mut()
+        .find(|p| is_same_pin(p, &normalized_path))
+        .ok_or_else(|| "Directory is not pinned".to_string())?;
+    if let Some(name) = name {
+        pin.name = name;
+    }
+    pin.color = color;
+    pin.icon = icon;
+    let updated = pin.clone();
+
+    save_pinned_directories(&stored_pins)?;
+    let _ = app.emit(PINNED_DIRECTORIES_CHANGED_EVENT, ());
+
+    let (is_git_repo, is_symlink) = compute_pin_metadata(Path::new(&updated.path));
+
+    Ok(PinnedDirectory {
+        name: updated.name,
+        path: updated.path,
+        pinned_at: updated.pinned_at,
+        is_git_repo,
+        is_symlink,
+        color: updated.color,
+        icon: updated.icon,
+    })
+}
+
 /// Normalize trailing slashes for path comparison, preserving root "/"
 fn normalize_trailing_slash(path: &str) -> String {
     if path == "/" {
@@ -7511,13 +12454,74 @@ fn save_pinned_directories(pinned_dirs: &[StoredPinnedDirectory]) -> Result<(),
     let json = serde_json::to_string_pretty(pinned_dirs)
         .map_err(|e| format!("Failed to serialize pinned directories: {}", e))?;
 
-    let mut file = fs::File::create(&path)
-        .map_err(|e| format!("Failed to create pinned directories file: {}", e))?;
+    fs_utils::write_file_atomically(&path, json.as_bytes())
+}
+
+/// Point pinned directories, the last opened directory and per-directory preferences
+/// under `old_prefix` at `new_prefix` instead, e.g. after a volume remounts as
+/// `/Volumes/Name (2)`. Returns how many entries changed. The frontend should reload
+/// pins and preferences afterwards.
+#[command]
+pub fn remap_locations(
+    app: AppHandle,
+    old_prefix: String,
+    new_prefix: String,
+) -> Result<usize, String> {
+    let old_prefix = old_prefix.trim();
+    let new_prefix = new_prefix.trim();
+    if old_prefix.is_empty() || new_prefix.is_empty() {
+        return Err("Both prefixes are required".to_string());
+    }
+    let remap = |path: &str| fs_utils::remap_path_prefix(path, old_prefix, new_prefix);
+
+    let mut pins = load_stored_pinned_directories()?;
+    let mut pins_updated = 0usize;
+    for pin in pins.iter_mut() {
+        if let Some(path) = remap(&pin.path) {
+            pin.path = path;
+            pins_updated += 1;
+        }
+    }
+    // A remapped pin may now duplicate one that was already added at the new mount
+    let mut seen = HashSet::new();
+    pins.retain(|pin| seen.insert(normalize_trailing_slash(&pin.path)));
+
+    let mut prefs = read_prefs_value()?;
+    let mut prefs_updated = 0usize;
+    if let Some(last_dir) = prefs.get("lastDir").and_then(|v| v.as_str()) {
+        if let Some(path) = remap(last_dir) {
+            prefs["lastDir"] = Value::String(path);
+            prefs_updated += 1;
+        }
+    }
+    if let Some(dirs) = prefs
+        .get_mut("directoryPreferences")
+        .and_then(|d| d.as_object_mut())
+    {
+        let moved: Vec<(String, String)> = dirs
+            .keys()
+            .filter_map(|key| remap(key).map(|new_key| (key.clone(), normalize_path(new_key))))
+            .collect();
+        for (old_key, new_key) in moved {
+            if let Some(value) = dirs.remove(&old_key) {
+                // Settings already made at the new location win
+                dirs.entry(new_key).or_insert(value);
+                prefs_updated += 1;
+            }
+        }
+    }
 
-    file.write_all(json.as_bytes())
-        .map_err(|e| format!("Failed to write pinned directories: {}", e))?;
+    // Each file is replaced atomically; nothing is written when nothing matched
+    if pins_updated > 0 {
+        save_pinned_directories(&pins)?;
+        let _ = app.emit(PINNED_DIRECTORIES_CHANGED_EVENT, ());
+    }
+    if prefs_updated > 0 {
+        write_prefs_value(&prefs)?;
+        forget_sort_preferences(&app.state::<SortPreferencesState>());
+    }
 
-    Ok(())
+    Ok(pins_updated + prefs_updated)
 }
 
 // ============================================================================
@@ -7585,6 +12589,12 @@ pub async fn download_gdrive_file(
     download_file_to_temp(&email, &file_id, &file_name).await
 }
 
+/// Choose whether downloaded Docs, Sheets and Slides are exported as Office files or PDFs
+#[command]
+pub fn set_gdrive_export_format(format: GoogleExportFormat) {
+    set_export_format(format);
+}
+
 /// Fetch a URL with Google Drive authentication and return as data URL
 /// This is used for thumbnail URLs that require authentication
 #[command]
@@ -7668,8 +12678,19 @@ pub fn add_smb_server(
     username: String,
     password: String,
     domain: Option<String>,
+    guest: Option<bool>,
+    min_protocol: Option<crate::locations::smb::SmbProtocol>,
+    max_protocol: Option<crate::locations::smb::SmbProtocol>,
 ) -> Result<crate::locations::smb::SmbServerInfo, String> {
-    crate::locations::smb::add_smb_server(hostname, username, password, domain)
+    crate::locations::smb::add_smb_server(
+        hostname,
+        username,
+        password,
+        domain,
+        guest.unwrap_or(false),
+        min_protocol,
+        max_protocol,
+    )
 }
 
 /// Add a new SMB server (Windows stub)
@@ -7680,6 +12701,9 @@ pub fn add_smb_server(
     _username: String,
     _password: String,
     _domain: Option<String>,
+    _guest: Option<bool>,
+    _min_protocol: Option<String>,
+    _max_protocol: Option<String>,
 ) -> Result<(), String> {
     Err("SMB on Windows uses native UNC paths. Navigate to \\\\server\\share directly.".to_string())
 }
@@ -7706,8 +12730,19 @@ pub fn test_smb_connection(
     username: String,
     password: String,
     domain: Option<String>,
+    guest: Option<bool>,
+    min_protocol: Option<crate::locations::smb::SmbProtocol>,
+    max_protocol: Option<crate::locations::smb::SmbProtocol>,
 ) -> Result<bool, String> {
-    crate::locations::smb::test_smb_connection(&hostname, &username, &password, domain.as_deref())
+    crate::locations::smb::test_smb_connection(
+        &hostname,
+        &username,
+        &password,
+        domain.as_deref(),
+        guest.unwrap_or(false),
+        min_protocol,
+        max_protocol,
+    )
 }
 
 /// Test connection to an SMB server (Windows stub)
@@ -7718,6 +12753,9 @@ pub fn test_smb_connection(
     _username: String,
     _password: String,
     _domain: Option<String>,
+    _guest: Option<bool>,
+    _min_protocol: Option<String>,
+    _max_protocol: Option<String>,
 ) -> Result<bool, String> {
     Ok(true)
 }
@@ -7819,6 +12857,13 @@ pub async fn remove_sftp_server(hostname: String, port: u16) -> Result<(), Strin
     Ok(())
 }
 
+/// Close the pooled connection to an SFTP server; the next operation reconnects
+#[command]
+pub async fn disconnect_sftp_server(hostname: String, port: u16) -> Result<(), String> {
+    crate::locations::sftp::pool::disconnect(&hostname, port).await;
+    Ok(())
+}
+
 /// Test connection to an SFTP server
 #[command]
 pub async fn test_sftp_connection(
@@ -7840,6 +12885,13 @@ pub async fn test_sftp_connection(
     .await
 }
 
+/// Record the host key an SFTP server offered in `~/.ssh/known_hosts`, after the user
+/// confirmed `fingerprint` from an `[SFTP_HOST_KEY_UNKNOWN]` error.
+#[command]
+pub fn trust_sftp_host_key(hostname: String, port: u16, fingerprint: String) -> Result<(), String> {
+    crate::locations::sftp::host_keys::trust_host_key(&hostname, port, &fingerprint)
+}
+
 /// Download an SFTP file to a temporary location (for drag-out/open-in-external-app).
 /// Returns the temporary file path.
 #[command]
@@ -7925,6 +12977,68 @@ pub fn sftp_connect_window_unready() -> Result<(), String> {
     Ok(())
 }
 
+// ============================================================================
+// WebDAV Server Commands
+// ============================================================================
+
+/// Get all connected WebDAV servers
+#[command]
+pub fn get_webdav_servers() -> Result<Vec<crate::locations::webdav::WebDavServerInfo>, String> {
+    crate::locations::webdav::get_webdav_servers()
+}
+
+/// Add a new WebDAV server. `use_https` defaults to true.
+#[command]
+pub fn add_webdav_server(
+    hostname: String,
+    port: Option<u16>,
+    username: String,
+    password: String,
+    use_https: Option<bool>,
+) -> Result<crate::locations::webdav::WebDavServerInfo, String> {
+    let use_https = use_https.unwrap_or(true);
+    let port = port.unwrap_or(if use_https { 443 } else { 80 });
+    crate::locations::webdav::add_webdav_server(hostname, port, username, password, use_https)
+}
+
+/// Remove a WebDAV server
+#[command]
+pub fn remove_webdav_server(hostname: String, port: u16) -> Result<(), String> {
+    crate::locations::webdav::remove_webdav_server(&hostname, port)
+}
+
+/// Download a WebDAV file to a temporary location (for drag-out/open-in-external-app).
+/// Returns the temporary file path.
+#[command]
+pub async fn download_webdav_file(path: String) -> Result<String, String> {
+    crate::locations::webdav::download_webdav_file_to_temp(&path)
+        .await
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+// ============================================================================
+// MTP Device Commands
+// ============================================================================
+
+/// Phones and cameras connected over MTP. Each device's `id` is the authority of
+/// its `mtp://` paths.
+#[command]
+pub async fn get_mtp_devices() -> Result<Vec<crate::locations::mtp::MtpDeviceInfo>, String> {
+    // Opening a newly attached device can take a few seconds
+    tauri::async_runtime::spawn_blocking(crate::locations::mtp::get_mtp_devices)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Download a file from an MTP device to a temporary location (for drag-out/open-in-external-app).
+/// Returns the temporary file path.
+#[command]
+pub async fn download_mtp_file(path: String) -> Result<String, String> {
+    crate::locations::mtp::download_mtp_file_to_temp(&path)
+        .await
+        .map(|p| p.to_string_lossy().to_string())
+}
+
 // --- Conflict Resolution Window Commands ---
 
 fn show_conflict_window_internal(app: &AppHandle) -> Result<(), String> {
@@ -8534,6 +13648,56 @@ async fn download_single_to_temp(path: &str) -> Result<std::path::PathBuf, Strin
     }
 }
 
+/// Read EXIF (images) or container/codec details (audio/video) for a file.
+/// Remote files are fetched to a temp path first, up to a size cap.
+#[command]
+pub async fn get_media_metadata(
+    path: String,
+) -> Result<crate::media_metadata::MediaMetadata, String> {
+    use crate::media_metadata::{media_kind_for_extension, REMOTE_METADATA_MAX_BYTES};
+
+    let (provider, location) = resolve_location(LocationInput::Raw(path.clone()))?;
+    let name = std::path::Path::new(location.path())
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let ext = std::path::Path::new(&name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    if media_kind_for_extension(ext).is_none() {
+        return Err(format!("Unsupported media type: {}", name));
+    }
+
+    let is_local = location.scheme() == "file";
+    let local_path = if is_local {
+        std::path::PathBuf::from(location.path())
+    } else {
+        let metadata = provider.get_file_metadata(&location).await?;
+        if metadata.size > REMOTE_METADATA_MAX_BYTES {
+            return Err(format!(
+                "File is too large to inspect remotely ({} bytes, limit {})",
+                metadata.size, REMOTE_METADATA_MAX_BYTES
+            ));
+        }
+        download_single_to_temp(&path).await?
+    };
+
+    let read_path = local_path.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        crate::media_metadata::read_media_metadata(&read_path, &name)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?;
+
+    if !is_local {
+        let _ = std::fs::remove_file(&local_path);
+    }
+
+    result
+}
+
 /// Download remote files to a temp directory and copy the temp paths to the OS clipboard.
 /// This bridges remote file providers (GDrive, SMB, SFTP) with the native clipboard so
 /// paste works across windows and into external apps (Finder/Explorer).
@@ -8593,3 +13757,425 @@ pub async fn download_and_copy_to_clipboard(
 
     Ok(temp_paths)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn verifies_aes_encrypted_zip_entries() {
+        let dir = tempdir().unwrap();
+        let archive_path = dir.path().join("secret.zip");
+        let mut zip = ZipWriter::new(fs::File::create(&archive_path).unwrap());
+        // Entries under 20 bytes are written as AE-2, which records a CRC32 of 0
+        let aes = zip::write::SimpleFileOptions::default()
+            .with_aes_encryption(zip::AesMode::Aes256, "password");
+        zip.start_file("short.txt", aes).unwrap();
+        zip.write_all(b"hi").unwrap();
+        zip.start_file("long.txt", aes).unwrap();
+        zip.write_all(b"long enough to be written as AE-1").unwrap();
+        zip.start_file("plain.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"plain").unwrap();
+        zip.finish().unwrap();
+
+        let target = dir.path().join("out");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("short.txt"), b"hi").unwrap();
+        fs::write(
+            target.join("long.txt"),
+            b"long enough to be written as AE-1",
+        )
+        .unwrap();
+        fs::write(target.join("plain.txt"), b"plain").unwrap();
+        let (checked, failures) =
+            verify_extracted_entries(&archive_path, ArchiveFormat::Zip, &target).unwrap();
+        assert_eq!(checked, 3);
+        assert!(failures.is_empty(), "{failures:?}");
+
+        // Plain entries are still checked against their CRC
+        fs::write(target.join("plain.txt"), b"PLAIN").unwrap();
+        let (_, failures) =
+            verify_extracted_entries(&archive_path, ArchiveFormat::Zip, &target).unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].path, "plain.txt");
+    }
+
+    #[tokio::test]
+    async fn pattern_renames_can_swap_names() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("1.txt"), "one").unwrap();
+        fs::write(dir.path().join("2.txt"), "two").unwrap();
+        let path =
+            |name: &str| LocationInput::Raw(dir.path().join(name).to_string_lossy().to_string());
+
+        // Numbered in the order given, so the two trade names
+        let renamed = batch_rename_pattern(vec![path("2.txt"), path("1.txt")], "{n}.txt".into())
+            .await
+            .unwrap();
+        assert_eq!(renamed.len(), 2);
+        assert_eq!(fs::read_to_string(dir.path().join("1.txt")).unwrap(), "two");
+        assert_eq!(fs::read_to_string(dir.path().join("2.txt")).unwrap(), "one");
+
+        // Two items landing on one name is still refused
+        let error = batch_rename_pattern(vec![path("1.txt"), path("2.txt")], "same.txt".into())
+            .await
+            .unwrap_err();
+        assert!(error.contains("More than one item"), "{}", error);
+        assert!(dir.path().join("1.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn folder_transfers_number_taken_names() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("Photos");
+        fs::create_dir_all(source.join("trip")).unwrap();
+        fs::write(source.join("a.txt"), "a").unwrap();
+        fs::write(source.join("trip/b.txt"), "bb").unwrap();
+        let dest = dir.path().join("dest");
+        fs::create_dir_all(dest.join("Photos")).unwrap();
+        fs::write(dest.join("Photos/keep.txt"), "kept").unwrap();
+
+        let app = tauri::test::mock_app();
+        let raw = |path: &Path| path.to_string_lossy().to_string();
+        let (from_provider, from_location) =
+            resolve_location(LocationInput::Raw(raw(&source))).unwrap();
+        let (to_provider, dest_dir) = resolve_location(LocationInput::Raw(raw(&dest))).unwrap();
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+        let transfer = |remove_source| TreeTransfer {
+            from_provider: &from_provider,
+            from_location: &from_location,
+            to_provider: &to_provider,
+            dest_dir: &dest_dir,
+            dest_dir_raw: dest_dir.raw(),
+            name: "Photos",
+            remove_source,
+            transfer_id: "test",
+            cancel_flag: &cancel_flag,
+        };
+
+        transfer_tree_across_providers(app.handle(), transfer(false))
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("Photos/keep.txt")).unwrap(),
+            "kept"
+        );
+        assert!(!dest.join("Photos/a.txt").exists());
+        assert_eq!(
+            fs::read_to_string(dest.join("Photos (2)/a.txt")).unwrap(),
+            "a"
+        );
+        assert_eq!(
+            fs::read_to_string(dest.join("Photos (2)/trip/b.txt")).unwrap(),
+            "bb"
+        );
+
+        // A move takes the next free name and only then removes the source
+        transfer_tree_across_providers(app.handle(), transfer(true))
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("Photos (3)/trip/b.txt")).unwrap(),
+            "bb"
+        );
+        assert!(!source.exists());
+    }
+
+    #[test]
+    fn extracted_items_get_numbered_instead_of_overwriting() {
+        let dir = tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        fs::create_dir_all(staging.join("docs")).unwrap();
+        fs::write(staging.join("a.txt"), "new").unwrap();
+        fs::write(staging.join("b.txt"), "b").unwrap();
+        let target = dir.path().join("target");
+        fs::create_dir_all(target.join("docs")).unwrap();
+        fs::write(target.join("a.txt"), "old").unwrap();
+
+        move_into_unique_names(&staging, &target).unwrap();
+        assert_eq!(fs::read_to_string(target.join("a.txt")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(target.join("a (2).txt")).unwrap(), "new");
+        assert_eq!(fs::read_to_string(target.join("b.txt")).unwrap(), "b");
+        assert!(target.join("docs (2)").is_dir());
+    }
+
+    #[test]
+    fn replaced_originals_must_all_be_in_the_archive() {
+        let dir = tempdir().unwrap();
+        let notes = dir.path().join("notes");
+        fs::create_dir(&notes).unwrap();
+        fs::write(notes.join("a.txt"), "alpha").unwrap();
+        fs::write(dir.path().join("b.txt"), "beta").unwrap();
+        let sources = vec![notes.clone(), dir.path().join("b.txt")];
+
+        let write_zip = |name: &str, files: &[(&str, &str)]| {
+            let path = dir.path().join(name);
+            let mut zip = ZipWriter::new(fs::File::create(&path).unwrap());
+            for (entry, contents) in files {
+                zip.start_file(*entry, zip::write::SimpleFileOptions::default())
+                    .unwrap();
+                zip.write_all(contents.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+            path
+        };
+
+        let complete = write_zip(
+            "complete.zip",
+            &[("notes/a.txt", "alpha"), ("b.txt", "beta")],
+        );
+        verify_local_zip(&complete, &sources).unwrap();
+
+        let missing = write_zip("missing.zip", &[("notes/a.txt", "alpha")]);
+        let error = verify_local_zip(&missing, &sources).unwrap_err();
+        assert!(error.contains("missing b.txt"), "{}", error);
+
+        let short = write_zip("short.zip", &[("notes/a.txt", "alp"), ("b.txt", "beta")]);
+        assert!(verify_local_zip(&short, &sources).is_err());
+    }
+
+    #[test]
+    fn archive_entries_keep_each_root_under_its_name() {
+        let dir = tempdir().unwrap();
+        let photos = dir.path().join("a/photos");
+        fs::create_dir_all(photos.join("2024")).unwrap();
+        fs::write(photos.join("2024/beach.jpg"), "x").unwrap();
+        fs::write(photos.join(".DS_Store"), "junk").unwrap();
+        fs::create_dir_all(dir.path().join("b")).unwrap();
+        fs::write(dir.path().join("b/notes.txt"), "y").unwrap();
+        let roots = vec![photos.clone(), dir.path().join("b/notes.txt")];
+
+        let mut archived: Vec<(String, bool)> = collect_archive_entries(&roots)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.archive_path, entry.is_dir))
+            .collect();
+        archived.sort();
+        assert_eq!(
+            archived,
+            [
+                ("notes.txt".to_string(), false),
+                ("photos".to_string(), true),
+                ("photos/2024".to_string(), true),
+                ("photos/2024/beach.jpg".to_string(), false),
+            ]
+        );
+
+        // The zip collector walks the same way, listing only files and empty folders
+        let (files, empty_dirs) = collect_local_zip_entries(&roots, false).unwrap();
+        let mut zipped: Vec<String> = files.into_iter().map(|entry| entry.zip_path).collect();
+        zipped.sort();
+        assert_eq!(zipped, ["notes.txt", "photos/2024/beach.jpg"]);
+        assert!(empty_dirs.is_empty());
+
+        fs::create_dir_all(dir.path().join("c/photos")).unwrap();
+        let clash = vec![photos, dir.path().join("c/photos")];
+        assert!(collect_archive_entries(&clash).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn archives_leave_out_symlinks() {
+        let dir = tempdir().unwrap();
+        let outside = dir.path().join("outside");
+        fs::create_dir_all(outside.join("private")).unwrap();
+        fs::write(outside.join("secret.txt"), "secret").unwrap();
+        fs::write(outside.join("private/key.pem"), "key").unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir(&project).unwrap();
+        fs::write(project.join("readme.txt"), "hello").unwrap();
+        std::os::unix::fs::symlink(outside.join("secret.txt"), project.join("secret.txt")).unwrap();
+        std::os::unix::fs::symlink(outside.join("private"), project.join("private")).unwrap();
+        let roots = vec![project];
+
+        let entries = collect_archive_entries(&roots).unwrap();
+        let zip_path = dir.path().join("project.zip");
+        write_archive(&zip_path, ArchiveFormat::Zip, &entries, |_| {}).unwrap();
+        let mut zip = ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+        let mut names: Vec<String> = zip.file_names().map(str::to_string).collect();
+        names.sort();
+        assert_eq!(names, ["project/", "project/readme.txt"]);
+        let mut readme = String::new();
+        zip.by_name("project/readme.txt")
+            .unwrap()
+            .read_to_string(&mut readme)
+            .unwrap();
+        assert_eq!(readme, "hello");
+
+        let tar_path = dir.path().join("project.tar.gz");
+        write_archive(&tar_path, ArchiveFormat::TarGz, &entries, |_| {}).unwrap();
+        let mut tar = tar::Archive::new(GzDecoder::new(fs::File::open(&tar_path).unwrap()));
+        let mut names: Vec<String> = tar
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["project", "project/readme.txt"]);
+
+        let (files, empty_dirs) = collect_local_zip_entries(&roots, false).unwrap();
+        let zipped: Vec<String> = files.into_iter().map(|entry| entry.zip_path).collect();
+        assert_eq!(zipped, ["project/readme.txt"]);
+        assert!(empty_dirs.is_empty());
+    }
+
+    #[test]
+    fn duplicate_names_count_up_from_the_original() {
+        assert_eq!(duplicate_name("report.pdf", false, 1), "report copy.pdf");
+        assert_eq!(duplicate_name("report.pdf", false, 3), "report copy 3.pdf");
+        assert_eq!(duplicate_name("Makefile", false, 1), "Makefile copy");
+        assert_eq!(duplicate_name("Makefile", false, 2), "Makefile copy 2");
+        assert_eq!(duplicate_name("v1.2", true, 1), "v1.2 copy");
+
+        // Copies of copies continue the numbering instead of stacking
+        assert_eq!(
+            duplicate_name("report copy.pdf", false, 2),
+            "report copy 2.pdf"
+        );
+        assert_eq!(
+            duplicate_name("report copy 2.pdf", false, 3),
+            "report copy 3.pdf"
+        );
+        assert_eq!(duplicate_name("Photos copy 4", true, 5), "Photos copy 5");
+        // Names that merely end in a number or "copy" are left alone
+        assert_eq!(duplicate_name("Track 2.mp3", false, 1), "Track 2 copy.mp3");
+        assert_eq!(duplicate_name("copy.txt", false, 1), "copy copy.txt");
+    }
+
+    #[test]
+    fn git_summary_counts_stashes_and_compares_every_remote() {
+        fn commit(repo: &Repository, message: &str) -> Oid {
+            let mut index = repo.index().unwrap();
+            index
+                .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+                .unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = git2::Signature::now("Ada", "ada@example.com").unwrap();
+            let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+            let parents: Vec<&git2::Commit<'_>> = parent.iter().collect();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parents,
+            )
+            .unwrap()
+        }
+
+        let dir = tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let mut repo = Repository::init(&root).unwrap();
+        fs::write(root.join("a.txt"), "one").unwrap();
+        let first = commit(&repo, "First");
+        fs::write(root.join("a.txt"), "two").unwrap();
+        let second = commit(&repo, "Second");
+        let branch = repo.head().unwrap().shorthand().unwrap().to_string();
+
+        // origin is a commit behind, backup is up to date, mirror has no such branch
+        for (remote, target) in [
+            ("origin", Some(first)),
+            ("backup", Some(second)),
+            ("mirror", None),
+        ] {
+            repo.remote(remote, &format!("https://example.com/{remote}.git"))
+                .unwrap();
+            if let Some(target) = target {
+                repo.reference(&format!("refs/remotes/{remote}/{branch}"), target, true, "")
+                    .unwrap();
+            }
+        }
+
+        fs::write(root.join("a.txt"), "work in progress").unwrap();
+        let signature = git2::Signature::now("Ada", "ada@example.com").unwrap();
+        repo.stash_save(&signature, "wip", None).unwrap();
+        drop(repo);
+
+        let summary = read_git_summary(&root).unwrap().unwrap().response;
+        assert_eq!(summary.stash_count, 1);
+        let mut remotes: Vec<_> = summary
+            .remotes
+            .iter()
+            .map(|r| (r.name.as_str(), r.ahead, r.behind))
+            .collect();
+        remotes.sort();
+        assert_eq!(remotes, [("backup", 0, 0), ("origin", 1, 0)]);
+    }
+
+    #[tokio::test]
+    async fn failed_remote_overwrites_keep_the_target() {
+        use crate::locations::{LocationProvider, ProviderDirectoryEntries};
+
+        /// Writes half of each file, then fails like a dropped connection
+        struct DroppingCopies(ProviderRef);
+
+        #[async_trait::async_trait]
+        impl LocationProvider for DroppingCopies {
+            fn scheme(&self) -> &'static str {
+                self.0.scheme()
+            }
+            fn capabilities(&self, location: &Location) -> LocationCapabilities {
+                self.0.capabilities(location)
+            }
+            async fn read_directory(
+                &self,
+                location: &Location,
+            ) -> Result<ProviderDirectoryEntries, String> {
+                self.0.read_directory(location).await
+            }
+            async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
+                self.0.get_file_metadata(location).await
+            }
+            async fn create_directory(&self, location: &Location) -> Result<(), String> {
+                self.0.create_directory(location).await
+            }
+            async fn delete(&self, location: &Location) -> Result<(), String> {
+                self.0.delete(location).await
+            }
+            async fn rename(&self, from: &Location, to: &Location) -> Result<(), String> {
+                self.0.rename(from, to).await
+            }
+            async fn copy(&self, from: &Location, to: &Location) -> Result<(), String> {
+                let contents = fs::read(from.to_path_string()).unwrap();
+                fs::write(to.to_path_string(), &contents[..contents.len() / 2]).unwrap();
+                Err("Connection reset by peer".to_string())
+            }
+        }
+
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("new.txt"), "replacement").unwrap();
+        fs::create_dir(dir.path().join("dest")).unwrap();
+        fs::write(dir.path().join("dest/report.txt"), "original").unwrap();
+        let location = |path: &str| {
+            let raw = dir.path().join(path).to_string_lossy().to_string();
+            resolve_location(LocationInput::Raw(raw)).unwrap()
+        };
+        let (provider, source) = location("new.txt");
+        let (_, target) = location("dest/report.txt");
+        let dest_raw = dir.path().join("dest").to_string_lossy().to_string();
+
+        let dropping: ProviderRef = Arc::new(DroppingCopies(provider.clone()));
+        let result =
+            replace_with_remote_copy(&dropping, "file", &dest_raw, &source, &target, "report.txt")
+                .await;
+        assert_eq!(result.unwrap_err(), "Connection reset by peer");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("dest/report.txt")).unwrap(),
+            "original"
+        );
+        assert_eq!(fs::read_dir(dir.path().join("dest")).unwrap().count(), 1);
+
+        replace_with_remote_copy(&provider, "file", &dest_raw, &source, &target, "report.txt")
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("dest/report.txt")).unwrap(),
+            "replacement"
+        );
+        assert_eq!(fs::read_dir(dir.path().join("dest")).unwrap().count(), 1);
+    }
+}
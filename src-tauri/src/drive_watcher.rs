@@ -0,0 +1,305 @@
+//! Watches for volumes being mounted/unmounted and emits `drives-changed` with
+//! the refreshed drive list so the sidebar can live-update.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::{system_drives, SystemDrive};
+
+pub const DRIVES_CHANGED_EVENT: &str = "drives-changed";
+
+struct DriveWatcherHandle {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+static DRIVE_WATCHER: Mutex<Option<DriveWatcherHandle>> = Mutex::new(None);
+/// Pauses held; while any is, the watcher stops querying drives.
+static PAUSES: AtomicUsize = AtomicUsize::new(0);
+/// How often a paused watcher checks whether it may resume.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Keeps the watcher from touching any volume until dropped. Changes that happen
+/// meanwhile are still reported once it resumes.
+pub struct PauseGuard(());
+
+impl Drop for PauseGuard {
+    fn drop(&mut self) {
+        PAUSES.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Hold off drive queries, e.g. while a volume is being ejected and a capacity
+/// query would keep it open.
+pub fn pause() -> PauseGuard {
+    PAUSES.fetch_add(1, Ordering::SeqCst);
+    PauseGuard(())
+}
+
+pub fn init_drive_watcher(app_handle: AppHandle) {
+    let mut guard = match DRIVE_WATCHER.lock() {
+        Ok(guard) => guard,
+        Err(_) => return,
+    };
+    if guard.is_some() {
+        return;
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_for_thread = stop.clone();
+    let spawned = std::thread::Builder::new()
+        .name("drive-watcher".to_string())
+        .spawn(move || run(app_handle, stop_for_thread));
+
+    match spawned {
+        Ok(thread) => *guard = Some(DriveWatcherHandle { stop, thread }),
+        Err(err) => log::warn!("Failed to start drive watcher: {err}"),
+    }
+}
+
+/// Stop the watcher thread and release OS resources. Safe to call more than once.
+pub fn shutdown_drive_watcher() {
+    let handle = DRIVE_WATCHER.lock().ok().and_then(|mut guard| guard.take());
+    if let Some(handle) = handle {
+        handle.stop.store(true, Ordering::SeqCst);
+        if handle.thread.join().is_err() {
+            log::warn!("Drive watcher thread panicked during shutdown");
+        }
+    }
+}
+
+fn snapshot() -> Vec<SystemDrive> {
+    system_drives()
+}
+
+fn run(app_handle: AppHandle, stop: Arc<AtomicBool>) {
+    let mut source = match platform::ChangeSource::new() {
+        Ok(source) => source,
+        Err(err) => {
+            log::warn!("Drive change detection unavailable: {err}");
+            return;
+        }
+    };
+
+    let mut last = snapshot();
+    while !stop.load(Ordering::SeqCst) {
+        if PAUSES.load(Ordering::SeqCst) > 0 {
+            std::thread::sleep(PAUSE_POLL_INTERVAL);
+            continue;
+        }
+        if !source.wait() {
+            continue;
+        }
+
+        let drives = snapshot();
+        if drives != last {
+            if let Err(err) = app_handle.emit(DRIVES_CHANGED_EVENT, &drives) {
+                log::warn!("Failed to emit drives changed event: {err}");
+            }
+            last = drives;
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::fs::File;
+    use std::os::unix::io::AsRawFd;
+
+    /// The kernel flags `/proc/self/mounts` with POLLPRI whenever the mount table changes.
+    pub struct ChangeSource {
+        mounts: File,
+    }
+
+    impl ChangeSource {
+        pub fn new() -> Result<Self, String> {
+            let mounts = File::open("/proc/self/mounts")
+                .map_err(|e| format!("Failed to open /proc/self/mounts: {e}"))?;
+            Ok(Self { mounts })
+        }
+
+        /// Block for up to a second; returns true if the mount table changed.
+        pub fn wait(&mut self) -> bool {
+            let mut fds = libc::pollfd {
+                fd: self.mounts.as_raw_fd(),
+                events: libc::POLLPRI | libc::POLLERR,
+                revents: 0,
+            };
+            let ready = unsafe { libc::poll(&mut fds, 1, 1000) };
+            ready > 0 && (fds.revents & (libc::POLLPRI | libc::POLLERR)) != 0
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    type CFTypeRef = *const c_void;
+    type CFStringRef = *const c_void;
+    type CFRunLoopRef = *const c_void;
+    type DASessionRef = *const c_void;
+    type DADiskRef = *const c_void;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFRunLoopDefaultMode: CFStringRef;
+        fn CFRunLoopGetCurrent() -> CFRunLoopRef;
+        fn CFRunLoopRunInMode(mode: CFStringRef, seconds: f64, return_after_source: u8) -> i32;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    #[link(name = "DiskArbitration", kind = "framework")]
+    extern "C" {
+        fn DASessionCreate(allocator: CFTypeRef) -> DASessionRef;
+        fn DASessionScheduleWithRunLoop(
+            session: DASessionRef,
+            run_loop: CFRunLoopRef,
+            mode: CFStringRef,
+        );
+        fn DASessionUnscheduleFromRunLoop(
+            session: DASessionRef,
+            run_loop: CFRunLoopRef,
+            mode: CFStringRef,
+        );
+        fn DARegisterDiskAppearedCallback(
+            session: DASessionRef,
+            match_: CFTypeRef,
+            callback: extern "C" fn(DADiskRef, *mut c_void),
+            context: *mut c_void,
+        );
+        fn DARegisterDiskDisappearedCallback(
+            session: DASessionRef,
+            match_: CFTypeRef,
+            callback: extern "C" fn(DADiskRef, *mut c_void),
+            context: *mut c_void,
+        );
+        fn DARegisterDiskDescriptionChangedCallback(
+            session: DASessionRef,
+            match_: CFTypeRef,
+            watch: CFTypeRef,
+            callback: extern "C" fn(DADiskRef, CFTypeRef, *mut c_void),
+            context: *mut c_void,
+        );
+        fn DAUnregisterCallback(session: DASessionRef, callback: *mut c_void, context: *mut c_void);
+    }
+
+    static DISKS_DIRTY: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn on_disk_event(_disk: DADiskRef, _context: *mut c_void) {
+        DISKS_DIRTY.store(true, Ordering::SeqCst);
+    }
+
+    extern "C" fn on_disk_description_changed(
+        _disk: DADiskRef,
+        _keys: CFTypeRef,
+        _context: *mut c_void,
+    ) {
+        // Fires when a volume path is assigned/cleared, i.e. on mount and unmount
+        DISKS_DIRTY.store(true, Ordering::SeqCst);
+    }
+
+    /// DiskArbitration session scheduled on the watcher thread's run loop.
+    pub struct ChangeSource {
+        session: DASessionRef,
+        run_loop: CFRunLoopRef,
+    }
+
+    impl ChangeSource {
+        pub fn new() -> Result<Self, String> {
+            unsafe {
+                let session = DASessionCreate(std::ptr::null());
+                if session.is_null() {
+                    return Err("Failed to create DiskArbitration session".to_string());
+                }
+                let run_loop = CFRunLoopGetCurrent();
+                let null = std::ptr::null_mut();
+                DARegisterDiskAppearedCallback(session, std::ptr::null(), on_disk_event, null);
+                DARegisterDiskDisappearedCallback(session, std::ptr::null(), on_disk_event, null);
+                DARegisterDiskDescriptionChangedCallback(
+                    session,
+                    std::ptr::null(),
+                    std::ptr::null(),
+                    on_disk_description_changed,
+                    null,
+                );
+                DASessionScheduleWithRunLoop(session, run_loop, kCFRunLoopDefaultMode);
+                Ok(Self { session, run_loop })
+            }
+        }
+
+        /// Pump the run loop for up to a second; returns true if a disk event arrived.
+        pub fn wait(&mut self) -> bool {
+            unsafe {
+                CFRunLoopRunInMode(kCFRunLoopDefaultMode, 1.0, 0);
+            }
+            DISKS_DIRTY.swap(false, Ordering::SeqCst)
+        }
+    }
+
+    impl Drop for ChangeSource {
+        fn drop(&mut self) {
+            unsafe {
+                let null = std::ptr::null_mut();
+                DAUnregisterCallback(self.session, on_disk_event as *mut c_void, null);
+                DAUnregisterCallback(
+                    self.session,
+                    on_disk_description_changed as *mut c_void,
+                    null,
+                );
+                DASessionUnscheduleFromRunLoop(self.session, self.run_loop, kCFRunLoopDefaultMode);
+                CFRelease(self.session);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::time::Duration;
+    use windows::Win32::Storage::FileSystem::GetLogicalDrives;
+
+    /// Tracks the logical drive bitmask. WM_DEVICECHANGE would need a hidden message
+    /// window; the bitmask is one cheap syscall and catches the same arrivals/removals.
+    pub struct ChangeSource {
+        mask: u32,
+    }
+
+    impl ChangeSource {
+        pub fn new() -> Result<Self, String> {
+            Ok(Self {
+                mask: unsafe { GetLogicalDrives() },
+            })
+        }
+
+        pub fn wait(&mut self) -> bool {
+            std::thread::sleep(Duration::from_secs(1));
+            let mask = unsafe { GetLogicalDrives() };
+            if mask != self.mask {
+                self.mask = mask;
+                true
+            } else {
+                false
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    pub struct ChangeSource;
+
+    impl ChangeSource {
+        pub fn new() -> Result<Self, String> {
+            Err("Drive change detection is not supported on this platform".to_string())
+        }
+
+        pub fn wait(&mut self) -> bool {
+            false
+        }
+    }
+}
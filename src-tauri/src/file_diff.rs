@@ -0,0 +1,275 @@
+//! Comparing two files.
+//!
+//! Text files are decoded with the same encoding detection as `read_text_file` and
+//! diffed line by line into hunks with a few lines of context. Binary files only get
+//! an identical/different answer, from comparing their bytes as they stream in, so
+//! they aren't size-capped the way text diffs are.
+
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::commands::{error_codes, format_error};
+use crate::text_file;
+
+/// Largest file, in bytes, that gets a line diff
+pub const MAX_DIFF_BYTES: u64 = 4 * 1024 * 1024;
+/// Unchanged lines kept around each change
+const CONTEXT_LINES: usize = 3;
+/// After this long the diff settles for a coarser but still correct answer
+const DIFF_TIMEOUT: Duration = Duration::from_secs(5);
+const COMPARE_CHUNK: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineChange {
+    Context,
+    Delete,
+    Insert,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub change: LineChange,
+    /// 1-based line in the left file; `None` for inserted lines
+    pub old_line: Option<usize>,
+    /// 1-based line in the right file; `None` for deleted lines
+    pub new_line: Option<usize>,
+    /// The line without its line ending
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    /// 1-based first line of the hunk in each file, as in a unified diff header
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FileDiff {
+    #[serde(rename_all = "camelCase")]
+    Text {
+        identical: bool,
+        left_encoding: String,
+        right_encoding: String,
+        hunks: Vec<DiffHunk>,
+    },
+    /// At least one side is binary; only equality is reported
+    #[serde(rename_all = "camelCase")]
+    Binary { identical: bool },
+}
+
+/// Line diff of two decoded texts, grouped into hunks.
+pub fn diff_text(left: &str, right: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(left, right);
+
+    diff.grouped_ops(CONTEXT_LINES)
+        .iter()
+        .filter_map(|group| {
+            let first = group.first()?;
+            let last = group.last()?;
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            let lines = group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| DiffLine {
+                    change: match change.tag() {
+                        ChangeTag::Equal => LineChange::Context,
+                        ChangeTag::Delete => LineChange::Delete,
+                        ChangeTag::Insert => LineChange::Insert,
+                    },
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                    text: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                })
+                .collect();
+            Some(DiffHunk {
+                // An empty range starts after the line before it, as in `diff -u`
+                old_start: old_range.start + usize::from(!old_range.is_empty()),
+                old_lines: old_range.len(),
+                new_start: new_range.start + usize::from(!new_range.is_empty()),
+                new_lines: new_range.len(),
+                lines,
+            })
+        })
+        .collect()
+}
+
+/// Compare two files byte by byte without reading either into memory whole.
+pub fn files_identical(left: &Path, right: &Path) -> Result<bool, String> {
+    let open = |path: &Path| {
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+    };
+    let (left_file, right_file) = (open(left)?, open(right)?);
+    let len = |file: &File| {
+        file.metadata()
+            .map(|m| m.len())
+            .map_err(|e| format!("Failed to read file metadata: {}", e))
+    };
+    if len(&left_file)? != len(&right_file)? {
+        return Ok(false);
+    }
+
+    let mut left_reader = BufReader::new(left_file);
+    let mut right_reader = BufReader::new(right_file);
+    let mut left_buf = vec![0u8; COMPARE_CHUNK];
+    let mut right_buf = vec![0u8; COMPARE_CHUNK];
+    loop {
+        let read = read_full(&mut left_reader, &mut left_buf)?;
+        if read_full(&mut right_reader, &mut right_buf)? != read
+            || left_buf[..read] != right_buf[..read]
+        {
+            return Ok(false);
+        }
+        if read == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Fill `buf` as far as the reader allows; short only at end of file.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize, String> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(format!("Failed to read file: {}", e)),
+        }
+    }
+    Ok(filled)
+}
+
+fn read_for_diff(path: &Path, max_bytes: u64) -> Result<(String, &'static str), String> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+    if size > max_bytes {
+        return Err(format_error(
+            error_codes::ELIMIT,
+            &format!(
+                "{} is too large to diff (limit is {} MB)",
+                path.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                max_bytes / (1024 * 1024)
+            ),
+        ));
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let (text, encoding) = text_file::decode_text(&bytes, true);
+    Ok((text, encoding.name()))
+}
+
+/// Diff two local files. Text files over `max_bytes` fail with `ELIMIT`.
+pub fn diff_files(left: &Path, right: &Path, max_bytes: u64) -> Result<FileDiff, String> {
+    if text_file::is_binary_file(left)? || text_file::is_binary_file(right)? {
+        return Ok(FileDiff::Binary {
+            identical: files_identical(left, right)?,
+        });
+    }
+
+    let (left_text, left_encoding) = read_for_diff(left, max_bytes)?;
+    let (right_text, right_encoding) = read_for_diff(right, max_bytes)?;
+    let hunks = diff_text(&left_text, &right_text);
+    Ok(FileDiff::Text {
+        identical: hunks.is_empty(),
+        left_encoding: left_encoding.to_string(),
+        right_encoding: right_encoding.to_string(),
+        hunks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_changes_into_hunks_with_line_numbers() {
+        let left = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let right = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let hunks = diff_text(left, right);
+        assert_eq!(hunks.len(), 2);
+
+        let first = &hunks[0];
+        assert_eq!(
+            (
+                first.old_start,
+                first.old_lines,
+                first.new_start,
+                first.new_lines
+            ),
+            (1, 5, 1, 5)
+        );
+        let changed: Vec<_> = first
+            .lines
+            .iter()
+            .filter(|line| line.change != LineChange::Context)
+            .map(|line| {
+                (
+                    line.change,
+                    line.old_line,
+                    line.new_line,
+                    line.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                (LineChange::Delete, Some(2), None, "b"),
+                (LineChange::Insert, None, Some(2), "B"),
+            ]
+        );
+
+        let last = hunks[1].lines.last().unwrap();
+        assert_eq!(
+            (last.change, last.new_line, last.text.as_str()),
+            (LineChange::Insert, Some(11), "k")
+        );
+        assert!(diff_text(left, left).is_empty());
+    }
+
+    #[test]
+    fn compares_binary_files_and_caps_text_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, data: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, data).unwrap();
+            path
+        };
+        let mut blob = vec![0u8; COMPARE_CHUNK * 2 + 10];
+        let a = write("a.bin", &blob);
+        let b = write("b.bin", &blob);
+        blob[COMPARE_CHUNK + 1] = 1;
+        let c = write("c.bin", &blob);
+
+        assert!(matches!(
+            diff_files(&a, &b, MAX_DIFF_BYTES).unwrap(),
+            FileDiff::Binary { identical: true }
+        ));
+        assert!(matches!(
+            diff_files(&a, &c, MAX_DIFF_BYTES).unwrap(),
+            FileDiff::Binary { identical: false }
+        ));
+
+        let small = write("small.txt", b"one\ntwo\n");
+        let big = write("big.txt", "line\n".repeat(100).as_bytes());
+        let error = diff_files(&small, &big, 64).unwrap_err();
+        assert!(error.starts_with("[ELIMIT] big.txt is too large"));
+    }
+}
@@ -0,0 +1,479 @@
+//! Planning for bulk moves and renames.
+//!
+//! `batch_rename`, `move_files` and the organize commands first turn their request
+//! into a list of [`PlannedAction`]s, then either return it as a preview (`plan: true`)
+//! or execute it. Both go through [`plan_moves`], so the conflicts and renamed
+//! destinations shown in a preview are exactly what the real run does, provided the
+//! filesystem hasn't changed in between.
+
+use crate::fs_utils::{allocate_unique_path_with, move_file_or_directory};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One item to move: `destination` is the full path the caller would like.
+#[derive(Debug, Clone)]
+pub struct MoveRequest {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PlanConflict {
+    /// Something already exists at the destination; the item gets a numbered name.
+    DestinationExists,
+    /// An earlier item in the batch claims the same destination; numbered name.
+    DuplicateTarget,
+    /// Skipped: the source doesn't exist.
+    SourceMissing,
+    /// Skipped: a folder can't be moved into itself.
+    IntoItself,
+    /// Skipped: the item is already where it would go.
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedAction {
+    pub source: String,
+    /// Where the item ends up; `None` when it is skipped.
+    pub destination: Option<String>,
+    pub conflict: Option<PlanConflict>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanFailure {
+    pub source: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileOperationResult {
+    /// True when nothing was touched and `actions` is only the plan.
+    pub dry_run: bool,
+    pub actions: Vec<PlannedAction>,
+    pub failures: Vec<PlanFailure>,
+}
+
+/// What identifies a directory entry regardless of the spelling of its path: the
+/// device and inode on Unix, the case-folded path elsewhere. `None` if nothing is
+/// there.
+#[cfg(unix)]
+type EntryKey = (u64, u64);
+#[cfg(not(unix))]
+type EntryKey = String;
+
+fn entry_key(path: &Path) -> Option<EntryKey> {
+    let metadata = fs::symlink_metadata(path).ok()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some((metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        Some(path.to_string_lossy().to_lowercase())
+    }
+}
+
+/// Whether `a` and `b` name the same directory entry, e.g. a case-only rename on a
+/// case-insensitive volume. Such a destination "exists" but isn't a conflict.
+fn is_same_entry(a: &Path, b: &Path) -> bool {
+    match (entry_key(a), entry_key(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Whether names in `dir` are case-insensitive, probed by looking the folder up
+/// under its own name with the case flipped. Folders whose name has no letters
+/// fall back to the platform's usual default.
+fn is_case_insensitive(dir: &Path) -> bool {
+    let flipped = dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| {
+            let flipped: String = name
+                .chars()
+                .map(|c| {
+                    if c.is_lowercase() {
+                        c.to_uppercase().next().unwrap_or(c)
+                    } else {
+                        c.to_lowercase().next().unwrap_or(c)
+                    }
+                })
+                .collect();
+            (flipped != name).then(|| dir.with_file_name(flipped))
+        });
+    match flipped {
+        Some(flipped) => is_same_entry(dir, &flipped),
+        None => cfg!(any(target_os = "macos", windows)),
+    }
+}
+
+/// Destinations claimed by earlier items in a batch, compared the way the volume
+/// compares names so "A.txt" and "a.txt" collide where they'd be the same file.
+#[derive(Default)]
+struct Claims {
+    paths: HashSet<String>,
+    case_insensitive_dirs: RefCell<HashMap<PathBuf, bool>>,
+}
+
+impl Claims {
+    fn key(&self, path: &Path) -> String {
+        let raw = path.to_string_lossy().to_string();
+        let Some(dir) = path.parent() else {
+            return raw;
+        };
+        let insensitive = *self
+            .case_insensitive_dirs
+            .borrow_mut()
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| is_case_insensitive(dir));
+        if insensitive {
+            raw.to_lowercase()
+        } else {
+            raw
+        }
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(&self.key(path))
+    }
+
+    /// Claim `path`; false if it already was.
+    fn insert(&mut self, path: &Path) -> bool {
+        let key = self.key(path);
+        self.paths.insert(key)
+    }
+}
+
+/// Whether `request` is skipped, and why. Depends only on the source, so the
+/// planner can tell up front which items will leave their current path.
+fn skip_reason(request: &MoveRequest) -> Option<PlanConflict> {
+    let source = &request.source;
+    if fs::symlink_metadata(source).is_err() {
+        Some(PlanConflict::SourceMissing)
+    } else if request.destination == *source {
+        Some(PlanConflict::Unchanged)
+    } else if source.is_dir() && request.destination.starts_with(source) {
+        Some(PlanConflict::IntoItself)
+    } else {
+        None
+    }
+}
+
+/// Resolve every request to a final destination without touching the filesystem.
+/// Taken destinations get the same " (2)" style names `allocate_unique_path` uses.
+/// A path held by another item in the batch counts as free, since that item moves
+/// out first, so swaps and chains like `a → b, b → c` keep the names asked for.
+pub fn plan_moves(requests: &[MoveRequest]) -> Result<Vec<PlannedAction>, String> {
+    let skips: Vec<Option<PlanConflict>> = requests.iter().map(skip_reason).collect();
+    let vacating: HashSet<EntryKey> = requests
+        .iter()
+        .zip(&skips)
+        .filter(|(_, skip)| skip.is_none())
+        .filter_map(|(request, _)| entry_key(&request.source))
+        .collect();
+    let mut claimed = Claims::default();
+    let mut requested = Claims::default();
+    let mut actions = Vec::with_capacity(requests.len());
+
+    for (request, skip) in requests.iter().zip(skips) {
+        let source = &request.source;
+        if let Some(conflict) = skip {
+            actions.push(PlannedAction {
+                source: source.to_string_lossy().to_string(),
+                destination: None,
+                conflict: Some(conflict),
+            });
+            continue;
+        }
+
+        let dir = request
+            .destination
+            .parent()
+            .ok_or_else(|| format!("Invalid destination: {}", request.destination.display()))?;
+        let name = request
+            .destination
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| format!("Invalid destination: {}", request.destination.display()))?;
+
+        // Another item leaving this path frees it; the item itself being there is
+        // a case-only rename
+        let on_disk = |p: &Path| entry_key(p).is_some_and(|key| !vacating.contains(&key));
+        let conflict =
+            if !requested.insert(&request.destination) || claimed.contains(&request.destination) {
+                Some(PlanConflict::DuplicateTarget)
+            } else if on_disk(&request.destination) {
+                Some(PlanConflict::DestinationExists)
+            } else {
+                None
+            };
+        let destination =
+            allocate_unique_path_with(dir, name, |p| claimed.contains(p) || on_disk(p))?;
+
+        claimed.insert(&destination);
+        actions.push(PlannedAction {
+            source: source.to_string_lossy().to_string(),
+            destination: Some(destination.to_string_lossy().to_string()),
+            conflict,
+        });
+    }
+
+    Ok(actions)
+}
+
+/// Carry out a plan from [`plan_moves`], creating destination folders as needed.
+/// An item whose destination is still held by another item that hasn't moved yet
+/// is parked under a temporary name next to its source and finished once everything
+/// else has moved, which resolves swaps and chains. Items whose destination appeared
+/// since planning are reported as failures rather than overwritten.
+/// `on_item(done, total)` is called after each item is finished.
+pub fn execute_plan<F>(actions: &[PlannedAction], mut on_item: F) -> Vec<PlanFailure>
+where
+    F: FnMut(usize, usize),
+{
+    let total = actions.len();
+    let mut done = 0;
+    let mut failures = Vec::new();
+    let mut pending: HashSet<EntryKey> = actions
+        .iter()
+        .filter(|action| action.destination.is_some())
+        .filter_map(|action| entry_key(Path::new(&action.source)))
+        .collect();
+    // (action, where its source was parked)
+    let mut parked: Vec<(&PlannedAction, PathBuf)> = Vec::new();
+
+    for action in actions {
+        let Some(destination) = &action.destination else {
+            done += 1;
+            on_item(done, total);
+            continue;
+        };
+        let source = Path::new(&action.source);
+        let destination = Path::new(destination);
+        if let Some(key) = entry_key(source) {
+            pending.remove(&key);
+        }
+
+        let blocked = entry_key(destination).is_some_and(|key| pending.contains(&key));
+        let result = if blocked {
+            park(source).map(|temp| parked.push((action, temp)))
+        } else {
+            move_into_place(source, destination)
+        };
+        match result {
+            Ok(()) if blocked => continue,
+            Ok(()) => {}
+            Err(error) => failures.push(PlanFailure {
+                source: action.source.clone(),
+                error,
+            }),
+        }
+        done += 1;
+        on_item(done, total);
+    }
+
+    for (action, temp) in parked {
+        let destination = Path::new(action.destination.as_deref().unwrap_or_default());
+        if let Err(error) = move_into_place(&temp, destination) {
+            // Put it back where it came from if that is still free
+            let source = Path::new(&action.source);
+            if fs::symlink_metadata(source).is_err() {
+                let _ = fs::rename(&temp, source);
+            }
+            failures.push(PlanFailure {
+                source: action.source.clone(),
+                error,
+            });
+        }
+        done += 1;
+        on_item(done, total);
+    }
+    failures
+}
+
+/// Move `source` to a hidden temporary name in its own folder.
+fn park(source: &Path) -> Result<PathBuf, String> {
+    let dir = source
+        .parent()
+        .ok_or_else(|| format!("Invalid source: {}", source.display()))?;
+    let name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid source: {}", source.display()))?;
+    let temp = allocate_unique_path_with(dir, &format!(".{}.marlin-move", name), |p| {
+        fs::symlink_metadata(p).is_ok()
+    })?;
+    fs::rename(source, &temp).map_err(|e| format!("Failed to move {}: {}", source.display(), e))?;
+    Ok(temp)
+}
+
+fn move_into_place(source: &Path, destination: &Path) -> Result<(), String> {
+    if fs::symlink_metadata(destination).is_ok() && !is_same_entry(source, destination) {
+        return Err(format!(
+            "Destination already exists: {}",
+            destination.display()
+        ));
+    }
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    move_file_or_directory(source, destination, |_, _| {})
+}
+
+/// Plan `requests` and, unless `dry_run`, execute the plan.
+pub fn run(requests: &[MoveRequest], dry_run: bool) -> Result<FileOperationResult, String> {
+    run_with_progress(requests, dry_run, |_, _| {})
+}
+
+/// [`run`] with a progress callback, see [`execute_plan`].
+pub fn run_with_progress<F>(
+    requests: &[MoveRequest],
+    dry_run: bool,
+    on_item: F,
+) -> Result<FileOperationResult, String>
+where
+    F: FnMut(usize, usize),
+{
+    let actions = plan_moves(requests)?;
+    let failures = if dry_run {
+        Vec::new()
+    } else {
+        execute_plan(&actions, on_item)
+    };
+    Ok(FileOperationResult {
+        dry_run,
+        actions,
+        failures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn preview_matches_execution() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        let dest = root.join("dest");
+        fs::create_dir(&dest).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+        fs::write(dest.join("a.txt"), "existing").unwrap();
+
+        let requests = vec![
+            MoveRequest {
+                source: root.join("a.txt"),
+                destination: dest.join("a.txt"),
+            },
+            // Renamed onto the same name as the first item
+            MoveRequest {
+                source: root.join("b.txt"),
+                destination: dest.join("a.txt"),
+            },
+            MoveRequest {
+                source: root.join("missing.txt"),
+                destination: dest.join("missing.txt"),
+            },
+            MoveRequest {
+                source: dest.clone(),
+                destination: dest.join("nested"),
+            },
+        ];
+
+        let preview = run(&requests, true).unwrap();
+        assert!(preview.dry_run);
+        let conflicts: Vec<_> = preview.actions.iter().map(|a| a.conflict).collect();
+        assert_eq!(
+            conflicts,
+            [
+                Some(PlanConflict::DestinationExists),
+                Some(PlanConflict::DuplicateTarget),
+                Some(PlanConflict::SourceMissing),
+                Some(PlanConflict::IntoItself),
+            ]
+        );
+        // Nothing moved yet
+        assert!(root.join("a.txt").exists());
+
+        let executed = run(&requests, false).unwrap();
+        assert!(executed.failures.is_empty());
+        let planned: Vec<_> = preview.actions.iter().map(|a| &a.destination).collect();
+        let actual: Vec<_> = executed.actions.iter().map(|a| &a.destination).collect();
+        assert_eq!(planned, actual);
+        assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "existing");
+        assert_eq!(fs::read_to_string(dest.join("a (2).txt")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dest.join("a (3).txt")).unwrap(), "b");
+    }
+
+    fn request(source: PathBuf, destination: PathBuf) -> MoveRequest {
+        MoveRequest {
+            source,
+            destination,
+        }
+    }
+
+    #[test]
+    fn swaps_keep_the_requested_names() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+
+        let requests = vec![
+            request(root.join("a.txt"), root.join("b.txt")),
+            request(root.join("b.txt"), root.join("a.txt")),
+        ];
+        let result = run(&requests, false).unwrap();
+        assert!(result.failures.is_empty());
+        assert!(result.actions.iter().all(|a| a.conflict.is_none()));
+        assert_eq!(fs::read_to_string(root.join("a.txt")).unwrap(), "b");
+        assert_eq!(fs::read_to_string(root.join("b.txt")).unwrap(), "a");
+        assert_eq!(fs::read_dir(root).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn chains_keep_the_requested_names() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        for name in ["1.txt", "2.txt", "3.txt"] {
+            fs::write(root.join(name), name).unwrap();
+        }
+
+        // Each item takes the name of the one after it, in either order
+        let requests = vec![
+            request(root.join("1.txt"), root.join("2.txt")),
+            request(root.join("2.txt"), root.join("3.txt")),
+            request(root.join("3.txt"), root.join("4.txt")),
+        ];
+        let preview = run(&requests, true).unwrap();
+        let destinations: Vec<_> = preview
+            .actions
+            .iter()
+            .map(|a| a.destination.clone().unwrap())
+            .collect();
+        assert_eq!(
+            destinations,
+            ["2.txt", "3.txt", "4.txt"].map(|n| root.join(n).to_string_lossy().to_string())
+        );
+
+        let result = run(&requests, false).unwrap();
+        assert!(result.failures.is_empty());
+        assert!(!root.join("1.txt").exists());
+        assert_eq!(fs::read_to_string(root.join("2.txt")).unwrap(), "1.txt");
+        assert_eq!(fs::read_to_string(root.join("3.txt")).unwrap(), "2.txt");
+        assert_eq!(fs::read_to_string(root.join("4.txt")).unwrap(), "3.txt");
+        assert_eq!(fs::read_dir(root).unwrap().count(), 3);
+    }
+}
@@ -0,0 +1,266 @@
+//! Finder tags and other extended attributes on local files.
+//!
+//! macOS keeps tags in `com.apple.metadata:_kMDItemUserTags`, a binary plist of strings
+//! that are each a tag name, optionally followed by a newline and a colour index. The
+//! label colour older Finder views draw lives separately, in the `com.apple.FinderInfo`
+//! flags. Other Unixes use the freedesktop `user.xdg.tags` attribute, a comma-separated
+//! list of names. Windows has no equivalent: reads come back empty and writes do nothing.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Whether this platform stores tags; surfaced as `supportsTags` on local locations
+pub const TAGS_SUPPORTED: bool = cfg!(unix);
+
+/// Finder's colour indices, in order: none, gray, green, purple, blue, yellow, red, orange
+#[cfg_attr(not(unix), allow(dead_code))]
+const COLOR_NAMES: [&str; 8] = [
+    "", "Gray", "Green", "Purple", "Blue", "Yellow", "Red", "Orange",
+];
+
+#[cfg(target_os = "macos")]
+const MAC_TAGS_ATTR: &str = "com.apple.metadata:_kMDItemUserTags";
+#[cfg(target_os = "macos")]
+const FINDER_INFO_ATTR: &str = "com.apple.FinderInfo";
+#[cfg(target_os = "macos")]
+const FINDER_INFO_LEN: usize = 32;
+/// The label colour sits in bits 1-3 of the second byte of the Finder flags
+#[cfg(target_os = "macos")]
+const FINDER_LABEL_BYTE: usize = 9;
+#[cfg(target_os = "macos")]
+const FINDER_LABEL_MASK: u8 = 0x0E;
+
+#[cfg(all(unix, not(target_os = "macos")))]
+const XDG_TAGS_ATTR: &str = "user.xdg.tags";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileTag {
+    pub name: String,
+    /// Finder colour index from 1 to 7; `None` for a tag without a colour
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<u8>,
+}
+
+impl FileTag {
+    /// Parse Finder's `name\ncolor` form.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    fn from_finder(raw: &str) -> Self {
+        match raw.rsplit_once('\n') {
+            Some((name, color)) => Self {
+                name: name.to_string(),
+                color: color.trim().parse().ok().filter(|c| (1..=7).contains(c)),
+            },
+            None => Self {
+                name: raw.to_string(),
+                color: None,
+            },
+        }
+    }
+
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    fn to_finder(&self) -> String {
+        match self.color {
+            Some(color) => format!("{}\n{}", self.name, color),
+            None => self.name.clone(),
+        }
+    }
+
+    /// A tag stored by name only, coloured when it's named after a Finder colour.
+    #[cfg_attr(any(target_os = "macos", not(unix)), allow(dead_code))]
+    fn named(name: &str) -> Self {
+        let color = COLOR_NAMES
+            .iter()
+            .skip(1)
+            .position(|color| color.eq_ignore_ascii_case(name))
+            .map(|index| index as u8 + 1);
+        Self {
+            name: name.to_string(),
+            color,
+        }
+    }
+}
+
+fn validate_tags(tags: &[FileTag]) -> Result<(), String> {
+    for tag in tags {
+        if tag.name.trim().is_empty() {
+            return Err("Tag names cannot be empty".to_string());
+        }
+        if tag.name.contains(['\n', ',']) {
+            return Err(format!(
+                "Tag name '{}' cannot contain commas or line breaks",
+                tag.name
+            ));
+        }
+        if tag.color.is_some_and(|color| !(1..=7).contains(&color)) {
+            return Err(format!("Tag '{}' has an unknown colour", tag.name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn get_xattr(path: &Path, name: &str) -> Result<Option<Vec<u8>>, String> {
+    xattr::get(path, name).map_err(|e| format!("Failed to read attribute {}: {}", name, e))
+}
+
+/// Set an attribute, or remove it when `value` is `None`.
+#[cfg(unix)]
+pub fn set_xattr(path: &Path, name: &str, value: Option<&[u8]>) -> Result<(), String> {
+    let result = match value {
+        Some(value) => xattr::set(path, name, value),
+        None if get_xattr(path, name)?.is_some() => xattr::remove(path, name),
+        None => Ok(()),
+    };
+    result.map_err(|e| format!("Failed to write attribute {}: {}", name, e))
+}
+
+#[cfg(not(unix))]
+pub fn get_xattr(_path: &Path, _name: &str) -> Result<Option<Vec<u8>>, String> {
+    Err("Extended attributes are not supported on this platform".to_string())
+}
+
+#[cfg(not(unix))]
+pub fn set_xattr(_path: &Path, _name: &str, _value: Option<&[u8]>) -> Result<(), String> {
+    Err("Extended attributes are not supported on this platform".to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn finder_info_color(info: &[u8]) -> Option<u8> {
+    let color = (info.get(FINDER_LABEL_BYTE)? & FINDER_LABEL_MASK) >> 1;
+    (color != 0).then_some(color)
+}
+
+/// `info` with its label colour replaced, or `None` once nothing is left in it.
+#[cfg(target_os = "macos")]
+fn with_finder_info_color(info: Option<Vec<u8>>, color: u8) -> Option<Vec<u8>> {
+    let mut info = info.unwrap_or_default();
+    info.resize(info.len().max(FINDER_INFO_LEN), 0);
+    info[FINDER_LABEL_BYTE] = (info[FINDER_LABEL_BYTE] & !FINDER_LABEL_MASK) | (color << 1);
+    info.iter().any(|&byte| byte != 0).then_some(info)
+}
+
+#[cfg(target_os = "macos")]
+pub fn read_tags(path: &Path) -> Result<Vec<FileTag>, String> {
+    let mut tags: Vec<FileTag> = match get_xattr(path, MAC_TAGS_ATTR)? {
+        Some(data) => plist::from_bytes::<Vec<String>>(&data)
+            .map_err(|e| format!("Failed to parse Finder tags: {}", e))?
+            .iter()
+            .map(|raw| FileTag::from_finder(raw))
+            .collect(),
+        None => Vec::new(),
+    };
+
+    // Files labelled before tags existed only carry the Finder label colour
+    if tags.is_empty() {
+        if let Some(color) = get_xattr(path, FINDER_INFO_ATTR)?
+            .as_deref()
+            .and_then(finder_info_color)
+        {
+            tags.push(FileTag {
+                name: COLOR_NAMES[color as usize].to_string(),
+                color: Some(color),
+            });
+        }
+    }
+    Ok(tags)
+}
+
+#[cfg(target_os = "macos")]
+pub fn write_tags(path: &Path, tags: &[FileTag]) -> Result<(), String> {
+    validate_tags(tags)?;
+
+    if tags.is_empty() {
+        set_xattr(path, MAC_TAGS_ATTR, None)?;
+    } else {
+        let raw: Vec<String> = tags.iter().map(FileTag::to_finder).collect();
+        let mut data = Vec::new();
+        plist::to_writer_binary(&mut data, &raw)
+            .map_err(|e| format!("Failed to encode Finder tags: {}", e))?;
+        set_xattr(path, MAC_TAGS_ATTR, Some(&data))?;
+    }
+
+    // Finder shows the first coloured tag as the label colour
+    let color = tags.iter().find_map(|tag| tag.color).unwrap_or(0);
+    let info = get_xattr(path, FINDER_INFO_ATTR)?;
+    if info.is_none() && color == 0 {
+        return Ok(());
+    }
+    set_xattr(
+        path,
+        FINDER_INFO_ATTR,
+        with_finder_info_color(info, color).as_deref(),
+    )
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn read_tags(path: &Path) -> Result<Vec<FileTag>, String> {
+    let Some(data) = get_xattr(path, XDG_TAGS_ATTR)? else {
+        return Ok(Vec::new());
+    };
+    Ok(String::from_utf8_lossy(&data)
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(FileTag::named)
+        .collect())
+}
+
+/// Only names are kept: `user.xdg.tags` has no place for colours, which come back
+/// for tags named after a Finder colour.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn write_tags(path: &Path, tags: &[FileTag]) -> Result<(), String> {
+    validate_tags(tags)?;
+    let names: Vec<&str> = tags.iter().map(|tag| tag.name.as_str()).collect();
+    let value = names.join(",");
+    set_xattr(
+        path,
+        XDG_TAGS_ATTR,
+        (!value.is_empty()).then_some(value.as_bytes()),
+    )
+}
+
+#[cfg(not(unix))]
+pub fn read_tags(_path: &Path) -> Result<Vec<FileTag>, String> {
+    Ok(Vec::new())
+}
+
+#[cfg(not(unix))]
+pub fn write_tags(_path: &Path, tags: &[FileTag]) -> Result<(), String> {
+    validate_tags(tags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_finder_tag_strings() {
+        assert_eq!(
+            FileTag::from_finder("Red\n6"),
+            FileTag {
+                name: "Red".to_string(),
+                color: Some(6)
+            }
+        );
+        assert_eq!(FileTag::from_finder("Work\n0").color, None);
+        assert_eq!(FileTag::from_finder("Work").to_finder(), "Work");
+        assert_eq!(FileTag::named("orange").color, Some(7));
+        assert_eq!(FileTag::named("Invoices").color, None);
+        assert!(validate_tags(&[FileTag::named("a,b")]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn round_trips_tags_through_xattrs() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let tags = vec![FileTag::named("Green"), FileTag::named("Taxes")];
+        // Some filesystems (tmpfs on older kernels) refuse user attributes
+        if write_tags(file.path(), &tags).is_err() {
+            return;
+        }
+        assert_eq!(read_tags(file.path()).unwrap(), tags);
+
+        write_tags(file.path(), &[]).unwrap();
+        assert!(read_tags(file.path()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,98 @@
+//! Templates offered under "New" when creating a file.
+//!
+//! Users configure them as `globalPreferences.fileTemplates`, a list of
+//! `{ name, extension, contents }`. Without that key the built-in list is used;
+//! an empty list turns templates off. Entries missing a name or with an extension
+//! that isn't a plain suffix are skipped.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTemplate {
+    /// Shown in the menu, e.g. "Markdown Document"
+    pub name: String,
+    /// Without the dot; empty for files without an extension
+    #[serde(default)]
+    pub extension: String,
+    #[serde(default)]
+    pub contents: String,
+}
+
+impl FileTemplate {
+    fn new(name: &str, extension: &str, contents: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            extension: extension.to_string(),
+            contents: contents.to_string(),
+        }
+    }
+}
+
+pub fn default_templates() -> Vec<FileTemplate> {
+    vec![
+        FileTemplate::new("Text Document", "txt", ""),
+        FileTemplate::new("Markdown Document", "md", "# Untitled\n"),
+    ]
+}
+
+/// The templates configured in the preferences JSON, or the built-in ones.
+pub fn from_preferences(prefs: &Value) -> Vec<FileTemplate> {
+    let Some(list) = prefs
+        .get("globalPreferences")
+        .and_then(|g| g.get("fileTemplates"))
+        .and_then(Value::as_array)
+    else {
+        return default_templates();
+    };
+
+    list.iter()
+        .filter_map(|entry| serde_json::from_value::<FileTemplate>(entry.clone()).ok())
+        .filter_map(|mut template| {
+            template.name = template.name.trim().to_string();
+            template.extension = template
+                .extension
+                .trim()
+                .trim_start_matches('.')
+                .to_string();
+            let plain_extension = !template
+                .extension
+                .contains(|c: char| c == '/' || c == '\\' || c == '.' || c.is_whitespace());
+            (!template.name.is_empty() && plain_extension).then_some(template)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn configured_templates_replace_the_defaults() {
+        assert_eq!(from_preferences(&json!({})), default_templates());
+
+        let prefs = json!({
+            "globalPreferences": {
+                "fileTemplates": [
+                    { "name": "Markdown Document", "extension": ".md", "contents": "# Notes\n" },
+                    { "name": "Makefile" },
+                    { "name": "  ", "extension": "txt" },
+                    { "name": "Sneaky", "extension": "../txt" },
+                    "not a template"
+                ]
+            }
+        });
+        assert_eq!(
+            from_preferences(&prefs),
+            vec![
+                FileTemplate::new("Markdown Document", "md", "# Notes\n"),
+                FileTemplate::new("Makefile", "", ""),
+            ]
+        );
+
+        let disabled = json!({ "globalPreferences": { "fileTemplates": [] } });
+        assert!(from_preferences(&disabled).is_empty());
+    }
+}
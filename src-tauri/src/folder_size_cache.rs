@@ -0,0 +1,134 @@
+//! Remembered results of `calculate_folder_size`.
+//!
+//! Walking a big tree takes minutes, so the last totals for a set of roots are kept
+//! along with each root's mtime. A root's mtime only changes when its direct
+//! children do, so a hit is shown right away and then re-verified by a quiet walk;
+//! changes the fs watcher reports under a root drop its entry outright.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Maximum number of root sets to remember
+const MAX_ENTRIES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FolderTotals {
+    pub bytes: u64,
+    pub apparent_bytes: u64,
+    pub items: u64,
+}
+
+/// The scanned roots and their mtimes when the scan started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    roots: Vec<PathBuf>,
+    mtimes: Vec<Option<SystemTime>>,
+}
+
+impl CacheKey {
+    pub fn new(roots: &[PathBuf]) -> Self {
+        let mut roots = roots.to_vec();
+        roots.sort();
+        let mtimes = roots
+            .iter()
+            .map(|root| fs::metadata(root).and_then(|m| m.modified()).ok())
+            .collect();
+        Self { roots, mtimes }
+    }
+}
+
+struct CachedTotals {
+    key: CacheKey,
+    totals: FolderTotals,
+    stored_at: SystemTime,
+}
+
+static CACHE: Lazy<Mutex<HashMap<Vec<PathBuf>, CachedTotals>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Totals from the last scan of these roots, if none of their mtimes moved since.
+pub fn lookup(key: &CacheKey) -> Option<FolderTotals> {
+    let cache = CACHE.lock().ok()?;
+    let cached = cache.get(&key.roots)?;
+    (cached.key == *key).then_some(cached.totals)
+}
+
+pub fn store(key: CacheKey, totals: FolderTotals) {
+    let Ok(mut cache) = CACHE.lock() else {
+        return;
+    };
+    if cache.len() >= MAX_ENTRIES && !cache.contains_key(&key.roots) {
+        let oldest = cache
+            .iter()
+            .min_by_key(|(_, cached)| cached.stored_at)
+            .map(|(roots, _)| roots.clone());
+        if let Some(oldest) = oldest {
+            cache.remove(&oldest);
+        }
+    }
+    cache.insert(
+        key.roots.clone(),
+        CachedTotals {
+            key,
+            totals,
+            stored_at: SystemTime::now(),
+        },
+    );
+}
+
+/// Drop every entry with a root containing (or inside) one of `paths`.
+pub fn invalidate_paths(paths: &[String]) {
+    let Ok(mut cache) = CACHE.lock() else {
+        return;
+    };
+    if cache.is_empty() {
+        return;
+    }
+    cache.retain(|roots, _| {
+        !roots.iter().any(|root| {
+            paths.iter().any(|p| {
+                let changed = Path::new(p);
+                changed.starts_with(root) || root.starts_with(changed)
+            })
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn hits_until_the_root_changes() {
+        let dir = tempdir().expect("tempdir");
+        let photos = dir.path().join("Photos");
+        let music = dir.path().join("Music");
+        fs::create_dir(&photos).unwrap();
+        fs::create_dir(&music).unwrap();
+        let totals = FolderTotals {
+            bytes: 4096,
+            apparent_bytes: 1000,
+            items: 3,
+        };
+
+        // Root order doesn't matter
+        store(CacheKey::new(&[photos.clone(), music.clone()]), totals);
+        let key = CacheKey::new(&[music.clone(), photos.clone()]);
+        assert_eq!(lookup(&key), Some(totals));
+        assert_eq!(lookup(&CacheKey::new(&[photos.clone()])), None);
+
+        invalidate_paths(&[dir.path().join("Elsewhere").to_string_lossy().into()]);
+        assert_eq!(lookup(&key), Some(totals));
+        invalidate_paths(&[photos.join("2024/img.jpg").to_string_lossy().into()]);
+        assert_eq!(lookup(&key), None);
+
+        store(key.clone(), totals);
+        filetime::set_file_mtime(&music, filetime::FileTime::from_unix_time(1, 0)).unwrap();
+        assert_eq!(lookup(&CacheKey::new(&[photos, music])), None);
+    }
+}
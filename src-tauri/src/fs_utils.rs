@@ -1,10 +1,12 @@
 use chrono::{DateTime, Utc};
 use image::ImageReader;
+use once_cell::sync::Lazy;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 #[cfg(target_family = "unix")]
 use std::ffi::CString;
 use std::fs;
+use std::io::{self, Read, Write};
 #[cfg(target_family = "unix")]
 use std::os::unix::ffi::OsStrExt;
 #[cfg(target_os = "windows")]
@@ -13,8 +15,7 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 
-#[cfg(target_family = "unix")]
-use std::io;
+use crate::file_tags::FileTag;
 
 #[cfg(target_os = "windows")]
 use windows::core::PCWSTR;
@@ -78,6 +79,20 @@ pub struct FileItem {
     /// Remote download URL (e.g., Google Drive web content link)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub download_url: Option<String>,
+    /// Finder-style tags; only read when a listing asks for them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<FileTag>>,
+    /// Directory is the root of a Git submodule
+    #[serde(default)]
+    pub is_git_submodule: bool,
+    /// Real size of a Git LFS file when only its pointer is checked out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lfs_size: Option<u64>,
+    /// Identity that survives renames, so refreshed listings can be matched up with
+    /// the previous one: device and inode on Unix, volume serial and file index on
+    /// Windows. Providers without either leave it empty and the registry uses the path.
+    #[serde(default)]
+    pub id: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -110,6 +125,8 @@ pub struct FileMetadataUpdate {
     pub child_count: Option<u64>,
     pub image_width: Option<u32>,
     pub image_height: Option<u32>,
+    /// Skeletons have no size to go on, so LFS pointers are recognized here
+    pub lfs_size: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -141,9 +158,199 @@ pub fn is_hidden_file(name: &str) -> bool {
             .any(|&f| name.eq_ignore_ascii_case(f))
 }
 
+/// [`FileItem::id`] for a local file, from its own (not a symlink target's) metadata.
+fn local_file_id(path: &Path, metadata: &fs::Metadata) -> String {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let _ = path;
+        format!("{}:{}", metadata.dev(), metadata.ino())
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let _ = metadata;
+        windows_file_index(path).unwrap_or_else(|| path.to_string_lossy().to_string())
+    }
+    #[cfg(not(any(target_family = "unix", target_os = "windows")))]
+    {
+        let _ = metadata;
+        path.to_string_lossy().to_string()
+    }
+}
+
+/// Volume serial and file index, which Windows only hands out for an open handle.
+#[cfg(target_os = "windows")]
+fn windows_file_index(path: &Path) -> Option<String> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS,
+        FILE_FLAG_OPEN_REPARSE_POINT,
+    };
+
+    // No access rights are needed to query the index; backup semantics opens folders
+    let file = fs::OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0 | FILE_FLAG_OPEN_REPARSE_POINT.0)
+        .open(path)
+        .ok()?;
+    let mut info = BY_HANDLE_FILE_INFORMATION::default();
+    unsafe { GetFileInformationByHandle(HANDLE(file.as_raw_handle() as _), &mut info) }.ok()?;
+    let index = (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow);
+    Some(windows_file_id(info.dwVolumeSerialNumber, index))
+}
+
+#[cfg(target_os = "windows")]
+fn windows_file_id(volume_serial: u32, index: u64) -> String {
+    format!("{:x}:{:x}", volume_serial, index)
+}
+
+/// Ids for every entry of a folder from one handle on the folder, keyed by name.
+/// The directory records carry each entry's file index, so no entry is opened.
+#[cfg(target_os = "windows")]
+fn windows_folder_ids(
+    folder: &Path,
+) -> Option<std::collections::HashMap<std::ffi::OsString, String>> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::{
+        FileIdBothDirectoryInfo, FileIdBothDirectoryRestartInfo, GetFileInformationByHandle,
+        GetFileInformationByHandleEx, BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS,
+        FILE_ID_BOTH_DIR_INFO, FILE_LIST_DIRECTORY,
+    };
+
+    let folder = fs::OpenOptions::new()
+        .access_mode(FILE_LIST_DIRECTORY.0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0)
+        .open(folder)
+        .ok()?;
+    let handle = HANDLE(folder.as_raw_handle() as _);
+    let mut info = BY_HANDLE_FILE_INFORMATION::default();
+    unsafe { GetFileInformationByHandle(handle, &mut info) }.ok()?;
+
+    let mut ids = std::collections::HashMap::new();
+    // u64s keep the records 8-byte aligned
+    let mut buffer = vec![0u64; 8 * 1024];
+    let buffer_len = (buffer.len() * std::mem::size_of::<u64>()) as u32;
+    let mut class = FileIdBothDirectoryRestartInfo;
+    // Each call fills the buffer with as many records as fit, until ERROR_NO_MORE_FILES
+    while unsafe {
+        GetFileInformationByHandleEx(handle, class, buffer.as_mut_ptr().cast(), buffer_len)
+    }
+    .is_ok()
+    {
+        class = FileIdBothDirectoryInfo;
+        let mut offset = 0usize;
+        loop {
+            let record = unsafe {
+                &*(buffer.as_ptr().cast::<u8>().add(offset) as *const FILE_ID_BOTH_DIR_INFO)
+            };
+            let name = unsafe {
+                std::slice::from_raw_parts(
+                    record.FileName.as_ptr(),
+                    record.FileNameLength as usize / 2,
+                )
+            };
+            ids.insert(
+                OsString::from_wide(name),
+                windows_file_id(info.dwVolumeSerialNumber, record.FileId as u64),
+            );
+            if record.NextEntryOffset == 0 {
+                break;
+            }
+            offset += record.NextEntryOffset as usize;
+        }
+    }
+    Some(ids)
+}
+
+/// What a folder's readdir entries need for their [`FileItem::id`], gathered once per
+/// listing so entries don't each need a stat or an open handle.
+#[derive(Default)]
+struct FolderIds {
+    /// The folder's device; entries bring their own inode
+    #[cfg(target_family = "unix")]
+    device: Option<u64>,
+    #[cfg(target_os = "windows")]
+    by_name: std::collections::HashMap<std::ffi::OsString, String>,
+}
+
+impl FolderIds {
+    fn read(folder: &Path) -> Self {
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::MetadataExt;
+            Self {
+                device: fs::metadata(folder).ok().map(|metadata| metadata.dev()),
+            }
+        }
+        #[cfg(target_os = "windows")]
+        {
+            Self {
+                by_name: windows_folder_ids(folder).unwrap_or_default(),
+            }
+        }
+        #[cfg(not(any(target_family = "unix", target_os = "windows")))]
+        {
+            let _ = folder;
+            Self::default()
+        }
+    }
+
+    /// The id of `entry`, if the folder read produced one.
+    fn get(&self, entry: &std::fs::DirEntry) -> Option<String> {
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::DirEntryExt;
+            self.device
+                .map(|device| format!("{}:{}", device, entry.ino()))
+        }
+        #[cfg(target_os = "windows")]
+        {
+            self.by_name.get(&entry.file_name()).cloned()
+        }
+        #[cfg(not(any(target_family = "unix", target_os = "windows")))]
+        {
+            let _ = entry;
+            None
+        }
+    }
+}
+
+/// [`FileItem::id`] for a readdir entry, falling back to the entry's own metadata for
+/// entries the folder read missed.
+fn entry_file_id(entry: &std::fs::DirEntry, folder_ids: &FolderIds) -> String {
+    if let Some(id) = folder_ids.get(entry) {
+        return id;
+    }
+    let path = entry.path();
+    match fs::symlink_metadata(&path) {
+        Ok(metadata) => local_file_id(&path, &metadata),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
+/// Hardlinks in one folder share a file id. Those get their name appended so ids stay
+/// unique within a listing; every other id is left alone.
+fn disambiguate_hardlink_ids(items: &mut [FileItem]) {
+    let mut counts = std::collections::HashMap::<String, usize>::new();
+    for item in items.iter() {
+        *counts.entry(item.id.clone()).or_default() += 1;
+    }
+    for item in items.iter_mut() {
+        if counts[&item.id] > 1 {
+            item.id = format!("{}:{}", item.id, item.name);
+        }
+    }
+}
+
 /// Build a skeleton FileItem from a DirEntry without any stat() calls.
 /// Uses only information available from readdir (name, file_type via d_type on Unix).
-fn build_file_item_skeleton(entry: &std::fs::DirEntry) -> Option<FileItem> {
+fn build_file_item_skeleton(entry: &std::fs::DirEntry, folder_ids: &FolderIds) -> Option<FileItem> {
     let path = entry.path();
     let file_name = entry.file_name().to_string_lossy().to_string();
 
@@ -171,17 +378,28 @@ fn build_file_item_skeleton(entry: &std::fs::DirEntry) -> Option<FileItem> {
         is_symlink: file_type.is_symlink(),
         is_git_repo: false, // Filled in by metadata update
         extension,
-        child_count: None,  // Filled in by metadata update
+        child_count: None,  // Fetched on demand with get_child_count
         image_width: None,  // Filled in by metadata update
         image_height: None, // Filled in by metadata update
         remote_id: None,
         thumbnail_url: None,
         download_url: None,
+        tags: None,
+        is_git_submodule: false,
+        lfs_size: None,
+        id: entry_file_id(entry, folder_ids),
     })
 }
 
+/// Whether `path` is the top of a Git working tree. `.git` is a file rather than a
+/// folder in submodules and linked worktrees.
+pub fn is_git_repo_root(path: &Path) -> bool {
+    let git_path = path.join(".git");
+    git_path.is_dir() || git_path.is_file()
+}
+
 /// Build full metadata for a file (called in background after skeleton is displayed)
-fn build_file_metadata(path: &Path) -> Option<FileMetadataUpdate> {
+fn build_file_metadata(path: &Path, lfs_enabled: bool) -> Option<FileMetadataUpdate> {
     let symlink_metadata = fs::symlink_metadata(path).ok()?;
     let is_symlink = symlink_metadata.file_type().is_symlink();
 
@@ -194,21 +412,7 @@ fn build_file_metadata(path: &Path) -> Option<FileMetadataUpdate> {
     let metadata = target_metadata.as_ref().unwrap_or(&symlink_metadata);
     let is_directory = metadata.is_dir();
 
-    let is_git_repo = if is_directory {
-        let git_path = path.join(".git");
-        git_path.is_dir() || git_path.is_file()
-    } else {
-        false
-    };
-
-    // Compute shallow child count for directories
-    let child_count = if is_directory {
-        fs::read_dir(path)
-            .ok()
-            .map(|entries| entries.count() as u64)
-    } else {
-        None
-    };
+    let is_git_repo = is_directory && is_git_repo_root(path);
 
     // Extract image dimensions for supported image formats
     let extension = path
@@ -236,6 +440,12 @@ fn build_file_metadata(path: &Path) -> Option<FileMetadataUpdate> {
         .map(|time| DateTime::from(time))
         .unwrap_or_else(|_| Utc::now());
 
+    let lfs_size = if lfs_enabled && !is_directory {
+        crate::git_annotations::lfs_pointer_size(path, metadata.len())
+    } else {
+        None
+    };
+
     Some(FileMetadataUpdate {
         path: path.to_string_lossy().to_string(),
         size: metadata.len(),
@@ -243,9 +453,10 @@ fn build_file_metadata(path: &Path) -> Option<FileMetadataUpdate> {
         is_directory,
         is_symlink,
         is_git_repo,
-        child_count,
+        child_count: None,
         image_width,
         image_height,
+        lfs_size,
     })
 }
 
@@ -271,7 +482,8 @@ pub fn resolve_symlink_parent(path: &Path) -> Result<SymlinkResolution, String>
     })
 }
 
-fn build_file_item(path: &Path) -> Result<FileItem, String> {
+/// `id` is the entry's id when the caller already has it from the folder listing.
+fn build_file_item(path: &Path, id: Option<String>) -> Result<FileItem, String> {
     let symlink_metadata =
         fs::symlink_metadata(path).map_err(|e| format!("Failed to get metadata: {}", e))?;
 
@@ -311,22 +523,7 @@ fn build_file_item(path: &Path) -> Result<FileItem, String> {
         None
     };
 
-    let is_git_repo = if is_directory {
-        let git_path = path.join(".git");
-        git_path.is_dir() || git_path.is_file()
-    } else {
-        false
-    };
-
-    // Compute shallow child count for directories
-    let child_count = if is_directory {
-        match fs::read_dir(path) {
-            Ok(entries) => Some(entries.count() as u64),
-            Err(_) => None, // Can't read directory (permissions, etc.)
-        }
-    } else {
-        None
-    };
+    let is_git_repo = is_directory && is_git_repo_root(path);
 
     // Extract image dimensions for supported image formats
     // This only reads file headers, not the full image data
@@ -363,12 +560,16 @@ fn build_file_item(path: &Path) -> Result<FileItem, String> {
         is_symlink,
         is_git_repo,
         extension,
-        child_count,
+        child_count: None,
         image_width,
         image_height,
         remote_id: None,
         thumbnail_url: None,
         download_url: None,
+        tags: None,
+        is_git_submodule: false,
+        lfs_size: None,
+        id: id.unwrap_or_else(|| local_file_id(path, &symlink_metadata)),
     })
 }
 
@@ -415,6 +616,12 @@ pub fn get_disk_usage(path: &Path) -> Result<DiskUsage, String> {
     })
 }
 
+/// Capacity of the volume mounted at `root`, for drive lists. Unlike
+/// [`get_disk_usage`] it doesn't stat the path first or record a bookmark for it.
+pub fn volume_usage(root: &Path) -> Result<DiskUsageMetrics, String> {
+    query_platform_disk_usage(root)
+}
+
 #[cfg(target_os = "windows")]
 fn query_platform_disk_usage(path: &Path) -> Result<DiskUsageMetrics, String> {
     let mut wide_path: Vec<u16> = path.as_os_str().encode_wide().collect();
@@ -494,24 +701,128 @@ fn query_platform_disk_usage(path: &Path) -> Result<DiskUsageMetrics, String> {
     }
 }
 
+/// Whether copying `source` into `dest_dir` can be done as a copy-on-write clone,
+/// which shares blocks instead of consuming new space. `fs::copy` clones on APFS
+/// (via `fclonefileat`) and on Btrfs/XFS (via `copy_file_range`) when both paths
+/// live on the same volume.
+#[cfg(target_os = "macos")]
+pub fn supports_reflink_clone(source: &Path, dest_dir: &Path) -> bool {
+    match (get_fs_info(source), get_fs_info(dest_dir)) {
+        (Ok((src_id, src_type)), Ok((dst_id, _))) => {
+            src_id == dst_id && src_type.eq_ignore_ascii_case("apfs")
+        }
+        _ => false,
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn supports_reflink_clone(source: &Path, dest_dir: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    const BTRFS_SUPER_MAGIC: i64 = 0x9123_683E;
+    const XFS_SUPER_MAGIC: i64 = 0x5846_5342;
+
+    let same_device = match (fs::metadata(source), fs::metadata(dest_dir)) {
+        (Ok(src), Ok(dst)) => src.dev() == dst.dev(),
+        _ => false,
+    };
+    if !same_device {
+        return false;
+    }
+
+    let Ok(c_path) = CString::new(dest_dir.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let fs_type = unsafe {
+        let mut stats: libc::statfs = std::mem::zeroed();
+        if libc::statfs(c_path.as_ptr(), &mut stats) != 0 {
+            return false;
+        }
+        stats.f_type as i64
+    };
+    fs_type == BTRFS_SUPER_MAGIC || fs_type == XFS_SUPER_MAGIC
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn supports_reflink_clone(_source: &Path, _dest_dir: &Path) -> bool {
+    false
+}
+
+/// Below this many entries a listing is built on the calling thread; spreading a
+/// handful of stats across threads costs more than it saves.
+const PARALLEL_LISTING_THRESHOLD: usize = 64;
+
+/// Threads that gather entry metadata for listings. Child counts, git checks and image
+/// headers are mostly waiting on the disk, so a few more threads than cores pay off,
+/// but the cap keeps one huge folder from flooding a network share with requests.
+static LISTING_POOL: Lazy<Option<rayon::ThreadPool>> = Lazy::new(|| {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads((num_cpus::get() * 2).clamp(4, 16))
+        .thread_name(|i| format!("listing-{}", i))
+        .build()
+        .map_err(|e| log::warn!("Failed to create listing thread pool: {}", e))
+        .ok()
+});
+
+/// Run `work` on the listing pool, or on the global rayon pool if it couldn't be built.
+fn on_listing_pool<T: Send>(work: impl FnOnce() -> T + Send) -> T {
+    match LISTING_POOL.as_ref() {
+        Some(pool) => pool.install(work),
+        None => work(),
+    }
+}
+
 pub fn read_directory_contents(path: &Path) -> Result<Vec<FileItem>, String> {
+    read_directory_contents_matching(path, |_, _| true)
+}
+
+/// Like `read_directory_contents`, but only builds items for entries where
+/// `keep(file_name, is_directory)` is true. The check runs before any metadata
+/// is gathered, so filtered-out entries cost just the directory read.
+pub fn read_directory_contents_matching<F>(path: &Path, keep: F) -> Result<Vec<FileItem>, String>
+where
+    F: Fn(&str, bool) -> bool,
+{
     #[cfg(target_os = "macos")]
     let _scope_guard = macos_security::retain_access(path)?;
 
     let entries = fs::read_dir(path).map_err(|e| format!("Failed to read directory: {}", e))?;
 
-    let mut files = Vec::new();
+    let folder_ids = FolderIds::read(path);
+    let mut paths = Vec::new();
 
     for entry in entries {
         let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
         let file_path = entry.path();
 
-        match build_file_item(&file_path) {
-            Ok(file_item) => files.push(file_item),
-            Err(_) => continue,
+        let file_name = entry.file_name();
+        let is_directory = entry
+            .file_type()
+            .map(|t| t.is_dir() || (t.is_symlink() && file_path.is_dir()))
+            .unwrap_or(false);
+        if !keep(&file_name.to_string_lossy(), is_directory) {
+            continue;
         }
+        paths.push((file_path, folder_ids.get(&entry)));
     }
 
+    // Entries that vanish or can't be read mid-listing are left out. Parallel
+    // collection keeps the readdir order, so listings come out the same either way.
+    let mut files: Vec<FileItem> = if paths.len() < PARALLEL_LISTING_THRESHOLD {
+        paths
+            .into_iter()
+            .filter_map(|(path, id)| build_file_item(&path, id).ok())
+            .collect()
+    } else {
+        on_listing_pool(|| {
+            paths
+                .into_par_iter()
+                .filter_map(|(path, id)| build_file_item(&path, id).ok())
+                .collect()
+        })
+    };
+    disambiguate_hardlink_ids(&mut files);
+
     #[cfg(target_os = "macos")]
     macos_security::persist_bookmark(path, "reading directory contents");
 
@@ -522,7 +833,7 @@ pub fn get_file_info(path: &Path) -> Result<FileItem, String> {
     #[cfg(target_os = "macos")]
     let _scope_guard = macos_security::retain_access(path)?;
 
-    let item = build_file_item(path);
+    let item = build_file_item(path, None);
 
     #[cfg(target_os = "macos")]
     if item.is_ok() {
@@ -532,6 +843,69 @@ pub fn get_file_info(path: &Path) -> Result<FileItem, String> {
     item
 }
 
+/// Shallow entry count for a directory. Listings leave `child_count` empty, since
+/// an extra readdir per subfolder adds up; the UI asks for visible folders only.
+pub fn count_children(path: &Path) -> Result<u64, String> {
+    #[cfg(target_os = "macos")]
+    let _scope_guard = macos_security::retain_access(path)?;
+
+    let entries = fs::read_dir(path).map_err(|e| format!("Failed to read directory: {}", e))?;
+    Ok(entries.count() as u64)
+}
+
+/// Counts for the entries directly inside a folder
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryStats {
+    pub file_count: u64,
+    pub directory_count: u64,
+    /// Combined size of the files, not counting anything inside subfolders
+    pub file_bytes: u64,
+    /// Files and folders that listings treat as hidden
+    pub hidden_count: u64,
+}
+
+impl DirectoryStats {
+    pub fn from_entries(entries: &[FileItem]) -> Self {
+        let mut stats = Self::default();
+        for entry in entries {
+            stats.add(entry.is_directory, entry.size, entry.is_hidden);
+        }
+        stats
+    }
+
+    fn add(&mut self, is_directory: bool, size: u64, is_hidden: bool) {
+        if is_directory {
+            self.directory_count += 1;
+        } else {
+            self.file_count += 1;
+            self.file_bytes = self.file_bytes.saturating_add(size);
+        }
+        if is_hidden {
+            self.hidden_count += 1;
+        }
+    }
+}
+
+/// [`DirectoryStats`] for `path` from one readdir, without building `FileItem`s or
+/// descending into subfolders. Links count as whatever they point to, like listings.
+pub fn directory_stats(path: &Path) -> Result<DirectoryStats, String> {
+    #[cfg(target_os = "macos")]
+    let _scope_guard = macos_security::retain_access(path)?;
+
+    let entries = fs::read_dir(path).map_err(|e| format!("Failed to read directory: {}", e))?;
+    let mut stats = DirectoryStats::default();
+    for entry in entries.flatten() {
+        let is_hidden = is_hidden_file(&entry.file_name().to_string_lossy());
+        match fs::metadata(entry.path()) {
+            Ok(metadata) => stats.add(metadata.is_dir(), metadata.len(), is_hidden),
+            // Broken links show up in listings as files
+            Err(_) => stats.add(false, 0, is_hidden),
+        }
+    }
+    Ok(stats)
+}
+
 pub fn create_directory(path: &Path) -> Result<(), String> {
     #[cfg(target_os = "macos")]
     let _scope_guard = macos_security::retain_access(path)?;
@@ -569,6 +943,73 @@ pub fn rename_file_or_directory(from: &Path, to: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// Hide or show an item by toggling `FILE_ATTRIBUTE_HIDDEN`. The item stays where it
+/// is, so this always returns `path`.
+#[cfg(target_os = "windows")]
+pub fn set_hidden(path: &Path, hidden: bool) -> Result<PathBuf, String> {
+    use windows::Win32::Storage::FileSystem::{
+        GetFileAttributesW, SetFileAttributesW, FILE_ATTRIBUTE_HIDDEN, FILE_FLAGS_AND_ATTRIBUTES,
+        INVALID_FILE_ATTRIBUTES,
+    };
+
+    let mut wide_path: Vec<u16> = path.as_os_str().encode_wide().collect();
+    wide_path.push(0);
+
+    unsafe {
+        let attributes = GetFileAttributesW(PCWSTR(wide_path.as_ptr()));
+        if attributes == INVALID_FILE_ATTRIBUTES {
+            return Err(format!(
+                "Failed to read attributes: {}",
+                io::Error::last_os_error()
+            ));
+        }
+        let updated = if hidden {
+            attributes | FILE_ATTRIBUTE_HIDDEN.0
+        } else {
+            attributes & !FILE_ATTRIBUTE_HIDDEN.0
+        };
+        if updated != attributes {
+            SetFileAttributesW(
+                PCWSTR(wide_path.as_ptr()),
+                FILE_FLAGS_AND_ATTRIBUTES(updated),
+            )
+            .map_err(|e| format!("Failed to set attributes: {}", e))?;
+        }
+    }
+
+    Ok(path.to_path_buf())
+}
+
+/// Hide or show an item by adding or dropping the leading dot of its name, numbering
+/// the new name if it's taken. Returns where the item ended up.
+#[cfg(not(target_os = "windows"))]
+pub fn set_hidden(path: &Path, hidden: bool) -> Result<PathBuf, String> {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| "Invalid file name".to_string())?;
+    let parent = path
+        .parent()
+        .ok_or_else(|| "Cannot change visibility of a root directory".to_string())?;
+    if fs::symlink_metadata(path).is_err() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+
+    let desired = match (hidden, name.strip_prefix('.')) {
+        (true, None) => format!(".{}", name),
+        (false, Some(visible)) if !visible.is_empty() && !visible.starts_with('.') => {
+            visible.to_string()
+        }
+        (false, Some(_)) => return Err(format!("Cannot unhide {}", name)),
+        // Already in the requested state
+        _ => return Ok(path.to_path_buf()),
+    };
+
+    let target = allocate_unique_path(parent, &desired)?;
+    rename_file_or_directory(path, &target)?;
+    Ok(target)
+}
+
 pub fn copy_file_or_directory(from: &Path, to: &Path) -> Result<(), String> {
     #[cfg(target_os = "macos")]
     let _from_scope = macos_security::retain_access(from)?;
@@ -617,6 +1058,560 @@ fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// What to do when a copied file's name is already taken at the destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    Overwrite,
+    Skip,
+    /// Copy under the next free "name (N)" instead
+    Rename,
+}
+
+/// A file copied under a numbered name because its own was taken
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedCopy {
+    pub from: String,
+    pub to: String,
+}
+
+/// Destination paths of a [`copy_with_policy`] run, by what happened to each
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyReport {
+    /// Written where nothing existed before
+    pub copied: Vec<String>,
+    pub overwritten: Vec<String>,
+    pub skipped: Vec<String>,
+    pub renamed: Vec<RenamedCopy>,
+}
+
+/// Copy `from` to `to`, applying `policy` to every file whose name is taken. Folders
+/// merge into existing folders, so the policy decides file by file rather than for a
+/// whole tree.
+pub fn copy_with_policy(
+    from: &Path,
+    to: &Path,
+    policy: ConflictPolicy,
+    report: &mut CopyReport,
+) -> Result<(), String> {
+    let existing = fs::symlink_metadata(to).ok();
+    let display = |path: &Path| path.to_string_lossy().to_string();
+
+    // Copying something onto itself leaves nothing to merge or overwrite
+    let onto_itself = from == to;
+    if onto_itself && policy != ConflictPolicy::Rename {
+        report.skipped.push(display(to));
+        return Ok(());
+    }
+
+    if !onto_itself && from.is_dir() && existing.as_ref().map_or(true, |meta| meta.is_dir()) {
+        fs::create_dir_all(to)
+            .map_err(|e| format!("Failed to create destination directory: {}", e))?;
+        for entry in
+            fs::read_dir(from).map_err(|e| format!("Failed to read source directory: {}", e))?
+        {
+            let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+            copy_with_policy(&entry.path(), &to.join(entry.file_name()), policy, report)?;
+        }
+        return Ok(());
+    }
+
+    let Some(existing) = existing else {
+        copy_file_or_directory(from, to)?;
+        report.copied.push(display(to));
+        return Ok(());
+    };
+
+    match policy {
+        ConflictPolicy::Skip => report.skipped.push(display(to)),
+        ConflictPolicy::Overwrite => {
+            replace_with_copy(from, to)?;
+            report.overwritten.push(display(to));
+        }
+        ConflictPolicy::Rename => {
+            let parent = to
+                .parent()
+                .ok_or_else(|| format!("Cannot copy to {}", to.display()))?;
+            let name = to
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| "Invalid file name".to_string())?;
+            let target = allocate_unique_path(parent, name)?;
+            copy_file_or_directory(from, &target)?;
+            report.renamed.push(RenamedCopy {
+                from: display(to),
+                to: display(&target),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy `from` over the existing `to`. The copy is staged under a hidden name next to
+/// `to` and only swapped in once it's complete, so a failed copy leaves `to` as it was.
+fn replace_with_copy(from: &Path, to: &Path) -> Result<(), String> {
+    let dir = to
+        .parent()
+        .ok_or_else(|| format!("Cannot copy to {}", to.display()))?;
+    let name = to
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Cannot copy to {}", to.display()))?;
+    let is_taken = |p: &Path| fs::symlink_metadata(p).is_ok();
+
+    let staged = allocate_unique_path_with(dir, &format!(".{}.marlin-copy", name), is_taken)?;
+    if let Err(err) = copy_file_or_directory(from, &staged) {
+        remove_entry(&staged);
+        return Err(err);
+    }
+
+    // Set the old entry aside rather than deleting it, so it can be put back if the
+    // staged copy can't take its place
+    let replaced = allocate_unique_path_with(dir, &format!(".{}.marlin-replaced", name), is_taken)?;
+    if let Err(err) = fs::rename(to, &replaced) {
+        remove_entry(&staged);
+        return Err(format!("Failed to replace {}: {}", to.display(), err));
+    }
+    if let Err(err) = fs::rename(&staged, to) {
+        let _ = fs::rename(&replaced, to);
+        remove_entry(&staged);
+        return Err(format!("Failed to replace {}: {}", to.display(), err));
+    }
+    remove_entry(&replaced);
+    Ok(())
+}
+
+/// Best-effort removal of a file, link or folder tree.
+fn remove_entry(path: &Path) {
+    let result = match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(_) => return,
+    };
+    if let Err(err) = result {
+        log::warn!("Failed to remove {}: {}", path.display(), err);
+    }
+}
+
+/// Buffer size for streamed copies (also the progress reporting granularity)
+const STREAMED_COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Move a file or directory, falling back to copy-then-delete when the source and
+/// destination live on different filesystems (`EXDEV`).
+///
+/// `on_progress(copied_bytes, total_bytes)` is only called during the copy fallback.
+/// The source is removed only after the copy completes; a failed copy removes the
+/// partial destination and leaves the source untouched.
+pub fn move_file_or_directory<P>(from: &Path, to: &Path, on_progress: P) -> Result<(), String>
+where
+    P: FnMut(u64, u64),
+{
+    #[cfg(target_os = "macos")]
+    let _from_scope = macos_security::retain_access(from)?;
+    #[cfg(target_os = "macos")]
+    let _to_scope = macos_security::retain_access(to)?;
+
+    move_with_rename(from, to, |a, b| fs::rename(a, b), on_progress)?;
+
+    #[cfg(target_os = "macos")]
+    macos_security::persist_bookmark(to, "moving");
+
+    Ok(())
+}
+
+fn move_with_rename<R, P>(
+    from: &Path,
+    to: &Path,
+    rename: R,
+    mut on_progress: P,
+) -> Result<(), String>
+where
+    R: Fn(&Path, &Path) -> io::Result<()>,
+    P: FnMut(u64, u64),
+{
+    match rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(err) if is_cross_device_error(&err) => {
+            log::info!(
+                "Cross-device move detected, copying {} -> {}",
+                from.display(),
+                to.display()
+            );
+        }
+        Err(err) => return Err(format!("Failed to rename: {}", err)),
+    }
+
+    let total = tree_size(from);
+    let mut copied = 0u64;
+    on_progress(0, total);
+
+    if let Err(err) = copy_tree_streamed(from, to, &mut copied, total, &mut on_progress) {
+        // Leave the source intact and clean up whatever made it to the destination
+        let cleanup = if fs::symlink_metadata(to)
+            .map(|m| m.is_dir())
+            .unwrap_or(false)
+        {
+            fs::remove_dir_all(to)
+        } else {
+            fs::remove_file(to)
+        };
+        if let Err(cleanup_err) = cleanup {
+            if cleanup_err.kind() != io::ErrorKind::NotFound {
+                log::warn!(
+                    "Failed to clean up partial copy at {}: {}",
+                    to.display(),
+                    cleanup_err
+                );
+            }
+        }
+        return Err(err);
+    }
+
+    let removed = if fs::symlink_metadata(from)
+        .map(|m| m.is_dir())
+        .unwrap_or(false)
+    {
+        fs::remove_dir_all(from)
+    } else {
+        fs::remove_file(from)
+    };
+    removed.map_err(|e| format!("Copied to destination but failed to remove source: {}", e))
+}
+
+fn is_cross_device_error(err: &io::Error) -> bool {
+    #[cfg(target_family = "unix")]
+    {
+        err.raw_os_error() == Some(libc::EXDEV)
+    }
+    #[cfg(target_os = "windows")]
+    {
+        // ERROR_NOT_SAME_DEVICE
+        err.raw_os_error() == Some(17)
+    }
+    #[cfg(not(any(target_family = "unix", target_os = "windows")))]
+    {
+        let _ = err;
+        false
+    }
+}
+
+/// Total size in bytes of all regular files under `path` (symlinks are not followed).
+pub fn tree_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+fn copy_tree_streamed<P>(
+    src: &Path,
+    dst: &Path,
+    copied: &mut u64,
+    total: u64,
+    on_progress: &mut P,
+) -> Result<(), String>
+where
+    P: FnMut(u64, u64),
+{
+    let meta =
+        fs::symlink_metadata(src).map_err(|e| format!("Failed to read source metadata: {}", e))?;
+    let file_type = meta.file_type();
+
+    if file_type.is_symlink() {
+        return copy_symlink(src, dst);
+    }
+
+    if file_type.is_dir() {
+        fs::create_dir(dst)
+            .map_err(|e| format!("Failed to create destination directory: {}", e))?;
+        for entry in
+            fs::read_dir(src).map_err(|e| format!("Failed to read source directory: {}", e))?
+        {
+            let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+            copy_tree_streamed(
+                &entry.path(),
+                &dst.join(entry.file_name()),
+                copied,
+                total,
+                on_progress,
+            )?;
+        }
+        let _ = fs::set_permissions(dst, meta.permissions());
+        return Ok(());
+    }
+
+    let mut reader =
+        fs::File::open(src).map_err(|e| format!("Failed to open source file: {}", e))?;
+    let mut writer = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dst)
+        .map_err(|e| format!("Failed to create destination file: {}", e))?;
+    let mut buffer = vec![0u8; STREAMED_COPY_BUFFER_SIZE];
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read source file: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        writer
+            .write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to write destination file: {}", e))?;
+        *copied += read as u64;
+        on_progress(*copied, total);
+    }
+    writer
+        .sync_all()
+        .map_err(|e| format!("Failed to flush destination file: {}", e))?;
+    drop(writer);
+
+    let _ = fs::set_permissions(dst, meta.permissions());
+    if let Ok(modified) = meta.modified() {
+        let _ = filetime::set_file_mtime(dst, filetime::FileTime::from_system_time(modified));
+    }
+
+    Ok(())
+}
+
+/// Recreate the symlink at `src` as `dst`, pointing at the same target.
+fn copy_symlink(src: &Path, dst: &Path) -> Result<(), String> {
+    let target = fs::read_link(src).map_err(|e| format!("Failed to read link: {}", e))?;
+    #[cfg(target_family = "unix")]
+    return std::os::unix::fs::symlink(&target, dst)
+        .map_err(|e| format!("Failed to create symlink: {}", e));
+    #[cfg(target_os = "windows")]
+    return if src.is_dir() {
+        std::os::windows::fs::symlink_dir(&target, dst)
+    } else {
+        std::os::windows::fs::symlink_file(&target, dst)
+    }
+    .map_err(|e| format!("Failed to create symlink: {}", e));
+    #[cfg(not(any(target_family = "unix", target_os = "windows")))]
+    return Err(format!(
+        "Symlinks not supported on this platform: {}",
+        target.display()
+    ));
+}
+
+/// Running totals for `copy_tree_with_progress`. Totals come from a pre-scan of the source.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeCopyProgress {
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub current_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeCopyOutcome {
+    Completed,
+    Cancelled,
+}
+
+/// Paths a tree copy created, so a cancelled copy can be undone without touching
+/// anything that was already at the destination. Files that replace an existing one
+/// are staged under a `.part` name and only renamed over it by [`Self::commit`].
+#[derive(Debug, Default)]
+pub struct TreeCopyJournal {
+    created: Vec<PathBuf>,
+    /// `(staged copy, file it replaces)`
+    staged: Vec<(PathBuf, PathBuf)>,
+}
+
+impl TreeCopyJournal {
+    /// Move the staged replacements into place.
+    pub fn commit(&mut self) -> Result<(), String> {
+        for (part, dst) in self.staged.drain(..) {
+            if let Err(err) = fs::rename(&part, &dst) {
+                let _ = fs::remove_file(&part);
+                return Err(format!("Failed to replace {}: {}", dst.display(), err));
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove everything the copy created, deepest first, and drop the staged
+    /// replacements so overwritten files keep their old contents.
+    pub fn rollback(&self) -> Result<(), String> {
+        for (part, _) in &self.staged {
+            let _ = fs::remove_file(part);
+        }
+        for path in self.created.iter().rev() {
+            let removed = match fs::symlink_metadata(path) {
+                Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+                Ok(_) => fs::remove_file(path),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => Err(err),
+            };
+            removed.map_err(|e| format!("Failed to roll back {}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Count the files (including symlinks) and regular-file bytes under `path`.
+fn scan_tree(path: &Path) -> (u64, u64) {
+    walkdir::WalkDir::new(path)
+        .follow_links(false)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_type().is_dir())
+        .fold((0, 0), |(files, bytes), entry| {
+            let len = if entry.file_type().is_file() {
+                entry.metadata().map(|m| m.len()).unwrap_or(0)
+            } else {
+                0
+            };
+            (files + 1, bytes + len)
+        })
+}
+
+/// Recursively copy `from` into `to`, merging into `to` if it already exists.
+///
+/// Each file is copied with `fs::copy` (so clones and in-kernel copies still apply) to a
+/// `.part` sibling and renamed into place once complete. `on_progress` is called after
+/// every file. When `cancel` is set the copy stops before the next file and returns
+/// `Cancelled`, leaving the caller to [`TreeCopyJournal::rollback`] or
+/// [`TreeCopyJournal::commit`] the journal; a completed copy is committed here.
+pub fn copy_tree_with_progress<P>(
+    from: &Path,
+    to: &Path,
+    cancel: &AtomicBool,
+    journal: &mut TreeCopyJournal,
+    mut on_progress: P,
+) -> Result<TreeCopyOutcome, String>
+where
+    P: FnMut(&TreeCopyProgress),
+{
+    #[cfg(target_os = "macos")]
+    let _from_scope = macos_security::retain_access(from)?;
+    #[cfg(target_os = "macos")]
+    let _to_scope = macos_security::retain_access(to)?;
+
+    let (files_total, bytes_total) = scan_tree(from);
+    let mut progress = TreeCopyProgress {
+        files_total,
+        bytes_total,
+        ..Default::default()
+    };
+    on_progress(&progress);
+
+    if let Err(err) = copy_tree_entry(from, to, cancel, journal, &mut progress, &mut on_progress) {
+        let _ = journal.commit();
+        return Err(err);
+    }
+
+    if cancel.load(Ordering::SeqCst) {
+        return Ok(TreeCopyOutcome::Cancelled);
+    }
+    journal.commit()?;
+
+    #[cfg(target_os = "macos")]
+    macos_security::persist_bookmark(to, "copying directory");
+
+    progress.current_path = None;
+    on_progress(&progress);
+    Ok(TreeCopyOutcome::Completed)
+}
+
+fn copy_tree_entry<P>(
+    src: &Path,
+    dst: &Path,
+    cancel: &AtomicBool,
+    journal: &mut TreeCopyJournal,
+    progress: &mut TreeCopyProgress,
+    on_progress: &mut P,
+) -> Result<(), String>
+where
+    P: FnMut(&TreeCopyProgress),
+{
+    if cancel.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let meta =
+        fs::symlink_metadata(src).map_err(|e| format!("Failed to read source metadata: {}", e))?;
+    let file_type = meta.file_type();
+    let existed = fs::symlink_metadata(dst).is_ok();
+
+    if file_type.is_dir() {
+        if !existed {
+            fs::create_dir(dst)
+                .map_err(|e| format!("Failed to create destination directory: {}", e))?;
+            journal.created.push(dst.to_path_buf());
+        }
+        for entry in
+            fs::read_dir(src).map_err(|e| format!("Failed to read source directory: {}", e))?
+        {
+            let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+            copy_tree_entry(
+                &entry.path(),
+                &dst.join(entry.file_name()),
+                cancel,
+                journal,
+                progress,
+                on_progress,
+            )?;
+            if cancel.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+        }
+        if !existed {
+            let _ = fs::set_permissions(dst, meta.permissions());
+        }
+        return Ok(());
+    }
+
+    progress.current_path = Some(src.to_string_lossy().to_string());
+
+    if file_type.is_symlink() {
+        if existed {
+            fs::remove_file(dst).map_err(|e| format!("Failed to replace symlink: {}", e))?;
+        }
+        copy_symlink(src, dst)?;
+        if !existed {
+            journal.created.push(dst.to_path_buf());
+        }
+    } else {
+        let part = crate::locations::transfer::partial_download_path(dst);
+        let copied = match fs::copy(src, &part) {
+            Ok(copied) => copied,
+            Err(err) => {
+                let _ = fs::remove_file(&part);
+                return Err(format!("Failed to copy file: {}", err));
+            }
+        };
+        if let Ok(modified) = meta.modified() {
+            let _ = filetime::set_file_mtime(&part, filetime::FileTime::from_system_time(modified));
+        }
+
+        if existed {
+            journal.staged.push((part, dst.to_path_buf()));
+        } else {
+            if let Err(err) = fs::rename(&part, dst) {
+                let _ = fs::remove_file(&part);
+                return Err(format!("Failed to finalize copy: {}", err));
+            }
+            journal.created.push(dst.to_path_buf());
+        }
+        progress.bytes_done += copied;
+    }
+
+    progress.files_done += 1;
+    on_progress(progress);
+    Ok(())
+}
+
 pub fn expand_path(path: &str) -> Result<PathBuf, String> {
     if path.starts_with('~') {
         let home =
@@ -634,6 +1629,117 @@ pub fn expand_path(path: &str) -> Result<PathBuf, String> {
     }
 }
 
+/// Expand `~` and resolve a local path to its canonical absolute form, following
+/// every symlink in the chain.
+pub fn canonicalize_local_path(path: &str) -> io::Result<PathBuf> {
+    let expanded = expand_path(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let canonical = fs::canonicalize(expanded)?;
+    // Windows returns verbatim `\\?\C:\...` paths, which the UI can't display or compare
+    #[cfg(target_os = "windows")]
+    {
+        let text = canonical.to_string_lossy();
+        if let Some(stripped) = text.strip_prefix(r"\\?\") {
+            if !stripped.starts_with("UNC\\") {
+                return Ok(PathBuf::from(stripped));
+            }
+        }
+    }
+    Ok(canonical)
+}
+
+/// Collapse `.` and `..` segments and duplicate separators without touching the
+/// filesystem. URI prefixes (`smb://host`) are kept as-is and only the path part is
+/// normalized; `..` never climbs above the root of an absolute path.
+pub fn normalize_path_lexically(path: &str) -> String {
+    let (prefix, rest) = match path.find("://") {
+        Some(scheme_end) => {
+            let after = &path[scheme_end + 3..];
+            let authority_end = after.find('/').unwrap_or(after.len());
+            path.split_at(scheme_end + 3 + authority_end)
+        }
+        None => ("", path),
+    };
+
+    let absolute = rest.starts_with('/') || !prefix.is_empty();
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in rest.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => match segments.last() {
+                Some(&last) if last != ".." => {
+                    segments.pop();
+                }
+                _ if absolute => {}
+                _ => segments.push(".."),
+            },
+            other => segments.push(other),
+        }
+    }
+
+    let joined = segments.join("/");
+    if absolute {
+        format!("{prefix}/{joined}")
+    } else if joined.is_empty() {
+        ".".to_string()
+    } else {
+        joined
+    }
+}
+
+/// Rewrite `path` when it is `old_prefix` or lies below it, keeping the remainder.
+/// Matching respects path boundaries, so `/Volumes/Data` doesn't match
+/// `/Volumes/Data 2`. Trailing separators on either prefix are ignored.
+pub fn remap_path_prefix(path: &str, old_prefix: &str, new_prefix: &str) -> Option<String> {
+    let old = old_prefix.trim_end_matches(['/', '\\']);
+    let new = new_prefix.trim_end_matches(['/', '\\']);
+    if old.is_empty() {
+        return None;
+    }
+    let rest = path.strip_prefix(old)?;
+    if !(rest.is_empty() || rest.starts_with(['/', '\\'])) {
+        return None;
+    }
+    let remapped = format!("{new}{rest}");
+    Some(if remapped.is_empty() {
+        "/".to_string()
+    } else {
+        remapped
+    })
+}
+
+/// Replace `path` with `contents` so readers see either the old or the new file,
+/// never a partial one: write a sibling temp file, flush it to disk, then rename it
+/// over the target.
+pub fn write_file_atomically(path: &Path, contents: &[u8]) -> Result<(), String> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let dir = path
+        .parent()
+        .ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid path: {}", path.display()))?;
+    let temp = dir.join(format!(
+        ".{}.{}-{}.tmp",
+        name,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+    if let Err(err) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {}", name, err));
+    }
+    Ok(())
+}
+
 /// Batch size for streaming directory reads
 const STREAMING_BATCH_SIZE: usize = 100;
 /// Batch size for metadata updates (smaller for more responsive updates)
@@ -691,10 +1797,12 @@ where
     }
 
     // Build skeleton items (very fast - no stat calls, just uses DirEntry info)
-    let skeleton_items: Vec<FileItem> = dir_entries
+    let folder_ids = FolderIds::read(path);
+    let mut skeleton_items: Vec<FileItem> = dir_entries
         .iter()
-        .filter_map(build_file_item_skeleton)
+        .filter_map(|entry| build_file_item_skeleton(entry, &folder_ids))
         .collect();
+    disambiguate_hardlink_ids(&mut skeleton_items);
 
     // Collect paths for metadata processing
     let entry_paths: Vec<PathBuf> = dir_entries.iter().map(|e| e.path()).collect();
@@ -731,15 +1839,18 @@ where
 
     // Phase 2: Process metadata in parallel and emit updates
     // This runs after skeletons are displayed, so UI is already responsive
-    let metadata_updates: Vec<FileMetadataUpdate> = entry_paths
-        .par_iter()
-        .filter_map(|entry_path| {
-            if cancel_flag.load(Ordering::Relaxed) {
-                return None;
-            }
-            build_file_metadata(entry_path)
-        })
-        .collect();
+    let lfs_enabled = crate::git_annotations::lfs_enabled(path);
+    let metadata_updates: Vec<FileMetadataUpdate> = on_listing_pool(|| {
+        entry_paths
+            .par_iter()
+            .filter_map(|entry_path| {
+                if cancel_flag.load(Ordering::Relaxed) {
+                    return None;
+                }
+                build_file_metadata(entry_path, lfs_enabled)
+            })
+            .collect()
+    });
 
     // Emit metadata updates in batches
     let mut meta_batch_index = 0;
@@ -780,6 +1891,20 @@ where
 /// * `Ok(PathBuf)` - A unique path that doesn't exist
 /// * `Err(String)` - If unable to allocate a unique name after all attempts
 pub fn allocate_unique_path(dir: &Path, desired_name: &str) -> Result<PathBuf, String> {
+    allocate_unique_path_with(dir, desired_name, |p| p.exists())
+}
+
+/// Same naming scheme as [`allocate_unique_path`], with the caller deciding which
+/// candidates are taken. Bulk operations use this to also avoid names claimed by
+/// earlier items in the same batch that don't exist on disk yet.
+pub fn allocate_unique_path_with<F>(
+    dir: &Path,
+    desired_name: &str,
+    is_taken: F,
+) -> Result<PathBuf, String>
+where
+    F: Fn(&Path) -> bool,
+{
     static FALLBACK_COUNTER: AtomicU64 = AtomicU64::new(0);
 
     // Sanitize the desired name to just the filename component
@@ -789,7 +1914,7 @@ pub fn allocate_unique_path(dir: &Path, desired_name: &str) -> Result<PathBuf, S
         .ok_or_else(|| "Invalid file name".to_string())?;
 
     let base = dir.join(desired_name);
-    if !base.exists() {
+    if !is_taken(&base) {
         return Ok(base);
     }
 
@@ -808,7 +1933,7 @@ pub fn allocate_unique_path(dir: &Path, desired_name: &str) -> Result<PathBuf, S
             format!("{stem} ({i})")
         };
         let p = dir.join(candidate);
-        if !p.exists() {
+        if !is_taken(&p) {
             return Ok(p);
         }
     }
@@ -827,10 +1952,504 @@ pub fn allocate_unique_path(dir: &Path, desired_name: &str) -> Result<PathBuf, S
             format!("{stem}_{nanos}_{counter}")
         };
         let p = dir.join(candidate);
-        if !p.exists() {
+        if !is_taken(&p) {
             return Ok(p);
         }
     }
 
     Err("Unable to allocate unique destination name".to_string())
 }
+
+/// Write `contents` to a new file named like `desired_name` in `dir`, numbering the
+/// name if it's taken. The file is opened with `create_new`, so a name claimed
+/// between picking it and writing is skipped rather than overwritten.
+pub fn create_unique_file(
+    dir: &Path,
+    desired_name: &str,
+    contents: &[u8],
+) -> Result<PathBuf, String> {
+    if !dir.is_dir() {
+        return Err("Destination is not a directory".to_string());
+    }
+
+    for _ in 0..8 {
+        let target = allocate_unique_path(dir, desired_name)?;
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&target)
+        {
+            Ok(mut file) => {
+                file.write_all(contents)
+                    .map_err(|e| format!("Failed to write file: {}", e))?;
+                return Ok(target);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to create file: {}", e)),
+        }
+    }
+
+    Err("Unable to allocate unique destination name".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn large_listings_match_readdir_order() {
+        let dir = tempdir().unwrap();
+        for i in 0..PARALLEL_LISTING_THRESHOLD * 2 {
+            fs::write(dir.path().join(format!("file-{i:03}.txt")), b"x").unwrap();
+        }
+        fs::create_dir(dir.path().join("folder")).unwrap();
+        fs::write(dir.path().join("folder").join("inner.txt"), b"x").unwrap();
+
+        let readdir_order: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        let items = read_directory_contents(dir.path()).unwrap();
+        let names: Vec<String> = items.iter().map(|item| item.name.clone()).collect();
+        assert_eq!(names, readdir_order);
+
+        let folder = items.iter().find(|item| item.name == "folder").unwrap();
+        assert!(folder.is_directory);
+        assert_eq!(folder.child_count, None);
+        assert_eq!(count_children(&dir.path().join("folder")).unwrap(), 1);
+    }
+
+    #[test]
+    fn copy_policy_applies_per_file_inside_merged_folders() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("nested")).unwrap();
+        fs::write(src.join("new.txt"), b"new").unwrap();
+        fs::write(src.join("nested").join("clash.txt"), b"from source").unwrap();
+        let dst = dir.path().join("dst");
+        fs::create_dir_all(dst.join("nested")).unwrap();
+        fs::write(dst.join("nested").join("clash.txt"), b"kept").unwrap();
+
+        let mut report = CopyReport::default();
+        copy_with_policy(&src, &dst, ConflictPolicy::Skip, &mut report).unwrap();
+        let clash = dst.join("nested").join("clash.txt");
+        assert_eq!(report.copied, vec![dst.join("new.txt").to_string_lossy()]);
+        assert_eq!(report.skipped, vec![clash.to_string_lossy()]);
+        assert_eq!(fs::read(&clash).unwrap(), b"kept");
+
+        let mut report = CopyReport::default();
+        copy_with_policy(&src, &dst, ConflictPolicy::Rename, &mut report).unwrap();
+        let renamed = dst.join("nested").join("clash (2).txt");
+        assert_eq!(report.renamed.len(), 2);
+        assert_eq!(fs::read(&renamed).unwrap(), b"from source");
+        assert_eq!(fs::read(&clash).unwrap(), b"kept");
+
+        let mut report = CopyReport::default();
+        copy_with_policy(&src, &dst, ConflictPolicy::Overwrite, &mut report).unwrap();
+        assert_eq!(report.overwritten.len(), 2);
+        assert_eq!(fs::read(&clash).unwrap(), b"from source");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_overwrites_keep_the_destination() {
+        let dir = tempdir().unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("report")).unwrap();
+        fs::write(src.join("report").join("good.txt"), b"new").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("missing"), src.join("report/broken.txt"))
+            .unwrap();
+        let dst = dir.path().join("dst");
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(dst.join("report"), b"kept").unwrap();
+        fs::write(dst.join("notes.txt"), b"kept").unwrap();
+
+        // A folder that can't be copied in full doesn't replace the file in its way
+        let mut report = CopyReport::default();
+        let result = copy_with_policy(
+            &src.join("report"),
+            &dst.join("report"),
+            ConflictPolicy::Overwrite,
+            &mut report,
+        );
+        assert!(result.is_err());
+        assert_eq!(fs::read(dst.join("report")).unwrap(), b"kept");
+
+        // Nor does a source that disappeared before it could be read
+        let result = copy_with_policy(
+            &src.join("notes.txt"),
+            &dst.join("notes.txt"),
+            ConflictPolicy::Overwrite,
+            &mut report,
+        );
+        assert!(result.is_err());
+        assert_eq!(fs::read(dst.join("notes.txt")).unwrap(), b"kept");
+        assert!(report.overwritten.is_empty());
+
+        let mut names: Vec<_> = fs::read_dir(&dst)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["notes.txt", "report"]);
+
+        // A complete copy swaps the folder in for the file
+        fs::remove_file(src.join("report/broken.txt")).unwrap();
+        copy_with_policy(
+            &src.join("report"),
+            &dst.join("report"),
+            ConflictPolicy::Overwrite,
+            &mut report,
+        )
+        .unwrap();
+        assert_eq!(fs::read(dst.join("report/good.txt")).unwrap(), b"new");
+        assert_eq!(fs::read_dir(&dst).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn created_files_never_replace_existing_ones() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("Untitled.md"), b"kept").unwrap();
+
+        let first = create_unique_file(dir.path(), "Untitled.md", b"# Title\n").unwrap();
+        let second = create_unique_file(dir.path(), "Untitled.md", b"").unwrap();
+        assert_eq!(first, dir.path().join("Untitled (2).md"));
+        assert_eq!(second, dir.path().join("Untitled (3).md"));
+        assert_eq!(fs::read(dir.path().join("Untitled.md")).unwrap(), b"kept");
+        assert_eq!(fs::read(&first).unwrap(), b"# Title\n");
+        assert!(create_unique_file(&first, "nested.txt", b"").is_err());
+    }
+
+    #[test]
+    fn file_ids_survive_renames() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), b"x").unwrap();
+        let before = read_directory_contents(dir.path()).unwrap();
+        fs::rename(dir.path().join("a.txt"), dir.path().join("b.txt")).unwrap();
+        let after = read_directory_contents(dir.path()).unwrap();
+        assert!(!before[0].id.is_empty());
+        assert_eq!(before[0].id, after[0].id);
+
+        // Streaming skeletons carry the same id as the full listing
+        let entry = fs::read_dir(dir.path()).unwrap().next().unwrap().unwrap();
+        let folder_ids = FolderIds::read(dir.path());
+        assert_eq!(entry_file_id(&entry, &folder_ids), after[0].id);
+        assert_eq!(
+            entry_file_id(&entry, &FolderIds::default()),
+            after[0].id,
+            "the per-entry fallback agrees with the folder read"
+        );
+    }
+
+    #[test]
+    fn hardlinks_get_distinct_ids() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), b"x").unwrap();
+        fs::hard_link(dir.path().join("a.txt"), dir.path().join("b.txt")).unwrap();
+        fs::write(dir.path().join("c.txt"), b"y").unwrap();
+
+        let listing = read_directory_contents(dir.path()).unwrap();
+        let id = |name: &str| {
+            listing
+                .iter()
+                .find(|item| item.name == name)
+                .unwrap()
+                .id
+                .clone()
+        };
+        assert_ne!(id("a.txt"), id("b.txt"));
+        assert_eq!(
+            id("c.txt"),
+            get_file_info(&dir.path().join("c.txt")).unwrap().id
+        );
+    }
+
+    #[test]
+    fn directory_stats_match_the_listing() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), b"hello").unwrap();
+        fs::write(dir.path().join(".hidden"), b"abc").unwrap();
+        fs::create_dir(dir.path().join("folder")).unwrap();
+        fs::write(dir.path().join("folder").join("inner.txt"), b"not counted").unwrap();
+
+        let stats = directory_stats(dir.path()).unwrap();
+        assert_eq!(
+            stats,
+            DirectoryStats {
+                file_count: 2,
+                directory_count: 1,
+                file_bytes: 8,
+                hidden_count: 1,
+            }
+        );
+        let entries = read_directory_contents(dir.path()).unwrap();
+        assert_eq!(DirectoryStats::from_entries(&entries), stats);
+    }
+
+    fn cross_device_error() -> io::Error {
+        #[cfg(target_family = "unix")]
+        {
+            io::Error::from_raw_os_error(libc::EXDEV)
+        }
+        #[cfg(target_os = "windows")]
+        {
+            io::Error::from_raw_os_error(17)
+        }
+    }
+
+    #[test]
+    fn tree_copy_reports_progress_against_prescan() {
+        let dir = tempdir().expect("tempdir");
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("nested")).unwrap();
+        fs::write(src.join("a.txt"), b"hello").unwrap();
+        fs::write(src.join("nested").join("b.txt"), b"world!").unwrap();
+        let dst = dir.path().join("dst");
+
+        let cancel = AtomicBool::new(false);
+        let mut journal = TreeCopyJournal::default();
+        let mut last = TreeCopyProgress::default();
+        let outcome =
+            copy_tree_with_progress(&src, &dst, &cancel, &mut journal, |p| last = p.clone())
+                .expect("copy");
+
+        assert_eq!(outcome, TreeCopyOutcome::Completed);
+        assert_eq!(
+            (
+                last.files_done,
+                last.files_total,
+                last.bytes_done,
+                last.bytes_total
+            ),
+            (2, 2, 11, 11)
+        );
+        assert_eq!(
+            fs::read(dst.join("nested").join("b.txt")).unwrap(),
+            b"world!"
+        );
+    }
+
+    #[test]
+    fn cancelled_tree_copy_rolls_back_only_new_entries() {
+        let dir = tempdir().expect("tempdir");
+        let src = dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        for i in 0..5 {
+            fs::write(src.join(format!("{i}.txt")), b"data").unwrap();
+        }
+        let dst = dir.path().join("dst");
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(dst.join("keep.txt"), b"existing").unwrap();
+
+        let cancel = AtomicBool::new(false);
+        let mut journal = TreeCopyJournal::default();
+        let outcome = copy_tree_with_progress(&src, &dst, &cancel, &mut journal, |p| {
+            if p.files_done == 2 {
+                cancel.store(true, Ordering::SeqCst);
+            }
+        })
+        .expect("copy");
+
+        assert_eq!(outcome, TreeCopyOutcome::Cancelled);
+        assert_eq!(fs::read_dir(&dst).unwrap().count(), 3);
+
+        journal.rollback().expect("rollback");
+        assert!(dst.exists(), "pre-existing destination must survive");
+        let remaining: Vec<_> = fs::read_dir(&dst)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(remaining, ["keep.txt"]);
+    }
+
+    #[test]
+    fn tree_copy_replaces_files_only_once_committed() {
+        let dir = tempdir().expect("tempdir");
+        let src = dir.path().join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("a.txt"), b"new").unwrap();
+        fs::write(src.join("b.txt"), b"new").unwrap();
+        let dst = dir.path().join("dst");
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(dst.join("a.txt"), b"old").unwrap();
+
+        // Cancelled after the last file: the replacement is discarded on rollback
+        let cancel = AtomicBool::new(false);
+        let mut journal = TreeCopyJournal::default();
+        let outcome = copy_tree_with_progress(&src, &dst, &cancel, &mut journal, |p| {
+            if p.files_done == 2 {
+                cancel.store(true, Ordering::SeqCst);
+            }
+        })
+        .expect("copy");
+        assert_eq!(outcome, TreeCopyOutcome::Cancelled);
+        assert_eq!(fs::read(dst.join("a.txt")).unwrap(), b"old");
+        journal.rollback().expect("rollback");
+        let remaining: Vec<_> = fs::read_dir(&dst)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(remaining, ["a.txt"]);
+        assert_eq!(fs::read(dst.join("a.txt")).unwrap(), b"old");
+
+        // A completed copy puts the replacement in place and leaves no .part behind
+        let cancel = AtomicBool::new(false);
+        let mut journal = TreeCopyJournal::default();
+        let outcome =
+            copy_tree_with_progress(&src, &dst, &cancel, &mut journal, |_| {}).expect("copy");
+        assert_eq!(outcome, TreeCopyOutcome::Completed);
+        assert_eq!(fs::read(dst.join("a.txt")).unwrap(), b"new");
+        assert_eq!(fs::read_dir(&dst).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn cross_device_move_falls_back_to_copy() {
+        let dir = tempdir().expect("tempdir");
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("nested")).unwrap();
+        fs::write(src.join("a.txt"), b"hello").unwrap();
+        fs::write(src.join("nested").join("b.txt"), b"world!").unwrap();
+        let dst = dir.path().join("dst");
+
+        let mut last = (0, 0);
+        move_with_rename(
+            &src,
+            &dst,
+            |_, _| Err(cross_device_error()),
+            |copied, total| last = (copied, total),
+        )
+        .expect("move should fall back to copy");
+
+        assert!(!src.exists(), "source should be removed after copy");
+        assert_eq!(fs::read(dst.join("a.txt")).unwrap(), b"hello");
+        assert_eq!(
+            fs::read(dst.join("nested").join("b.txt")).unwrap(),
+            b"world!"
+        );
+        assert_eq!(last, (11, 11));
+    }
+
+    #[test]
+    fn failed_cross_device_copy_keeps_source() {
+        let dir = tempdir().expect("tempdir");
+        let src = dir.path().join("file.txt");
+        fs::write(&src, b"keep me").unwrap();
+        // Parent directory does not exist, so the copy phase fails
+        let dst = dir.path().join("missing").join("file.txt");
+
+        let result = move_with_rename(&src, &dst, |_, _| Err(cross_device_error()), |_, _| {});
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&src).unwrap(), b"keep me");
+        assert!(!dst.exists());
+    }
+
+    #[test]
+    fn other_rename_errors_are_not_retried() {
+        let dir = tempdir().expect("tempdir");
+        let src = dir.path().join("file.txt");
+        fs::write(&src, b"data").unwrap();
+        let dst = dir.path().join("other.txt");
+
+        let result = move_with_rename(
+            &src,
+            &dst,
+            |_, _| Err(io::Error::from(io::ErrorKind::PermissionDenied)),
+            |_, _| {},
+        );
+
+        assert!(result.is_err());
+        assert!(src.exists());
+        assert!(!dst.exists());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn canonicalize_follows_symlink_chains() {
+        let dir = tempdir().expect("tempdir");
+        let real_dir = dir.path().join("real");
+        fs::create_dir(&real_dir).unwrap();
+        fs::write(real_dir.join("file.txt"), b"x").unwrap();
+        std::os::unix::fs::symlink(&real_dir, dir.path().join("hop1")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("hop1"), dir.path().join("hop2")).unwrap();
+
+        let via_chain = dir.path().join("hop2/./sub/../file.txt");
+        fs::create_dir(real_dir.join("sub")).unwrap();
+        let resolved = canonicalize_local_path(&via_chain.to_string_lossy()).unwrap();
+        assert_eq!(
+            resolved,
+            fs::canonicalize(real_dir.join("file.txt")).unwrap()
+        );
+
+        let missing = dir.path().join("hop2/missing.txt");
+        let err = canonicalize_local_path(&missing.to_string_lossy()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn lexical_normalization_collapses_dot_segments() {
+        assert_eq!(normalize_path_lexically("/a/./b/../c//d/"), "/a/c/d");
+        assert_eq!(normalize_path_lexically("/../../etc"), "/etc");
+        assert_eq!(normalize_path_lexically("a/../../b"), "../b");
+        assert_eq!(normalize_path_lexically("a/.."), ".");
+        assert_eq!(
+            normalize_path_lexically("smb://server/share/x/../y/./z"),
+            "smb://server/share/y/z"
+        );
+        assert_eq!(
+            normalize_path_lexically("sftp://host/../.."),
+            "sftp://host/"
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn set_hidden_renames_with_leading_dot() {
+        let dir = tempdir().expect("tempdir");
+        let notes = dir.path().join("notes.txt");
+        fs::write(&notes, b"a").unwrap();
+        fs::write(dir.path().join(".notes.txt"), b"taken").unwrap();
+
+        let hidden = set_hidden(&notes, true).unwrap();
+        assert_eq!(hidden, dir.path().join(".notes (2).txt"));
+        assert_eq!(set_hidden(&hidden, true).unwrap(), hidden);
+
+        let shown = set_hidden(&hidden, false).unwrap();
+        assert_eq!(shown, dir.path().join("notes (2).txt"));
+        assert_eq!(fs::read(&shown).unwrap(), b"a");
+        assert!(set_hidden(&dir.path().join("missing"), true).is_err());
+    }
+
+    #[test]
+    fn remap_path_prefix_respects_boundaries() {
+        let remap = |p| remap_path_prefix(p, "/Volumes/Data/", "/Volumes/Data (2)");
+        assert_eq!(remap("/Volumes/Data").as_deref(), Some("/Volumes/Data (2)"));
+        assert_eq!(
+            remap("/Volumes/Data/Projects/x").as_deref(),
+            Some("/Volumes/Data (2)/Projects/x")
+        );
+        assert_eq!(remap("/Volumes/Data 2/Projects"), None);
+        assert_eq!(remap("/Users/me"), None);
+        assert_eq!(
+            remap_path_prefix("smb://old/share/dir", "smb://old/share", "smb://new/share")
+                .as_deref(),
+            Some("smb://new/share/dir")
+        );
+    }
+
+    #[test]
+    fn atomic_write_replaces_contents_without_leftovers() {
+        let dir = tempdir().expect("tempdir");
+        let target = dir.path().join("preferences.json");
+        fs::write(&target, "old").unwrap();
+
+        write_file_atomically(&target, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        let names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["preferences.json"]);
+    }
+}
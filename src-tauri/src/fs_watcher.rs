@@ -1,39 +1,70 @@
+use notify::event::{ModifyKind, RenameMode};
 use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex, OnceLock};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use tauri::{AppHandle, Emitter};
+use walkdir::WalkDir;
 
 #[cfg(target_os = "macos")]
 use crate::macos_security;
 
+/// Quiet time after a directory's last event before its batch is sent
+pub const DEFAULT_DEBOUNCE_MS: u64 = 100;
+/// Longest debounce `set_debounce_ms` accepts
+pub const MAX_DEBOUNCE_MS: u64 = 10_000;
+/// A batch that keeps receiving events is still sent once it is this many debounce
+/// intervals old, so a long rsync or checkout doesn't hold every update back
+const MAX_BATCH_AGE_FACTOR: u32 = 10;
+/// Folders all watches may cover together. Linux needs one inotify watch per folder
+/// in a recursive tree, and running out of them breaks watching app-wide, so a
+/// recursive watch that would cross this is refused with `ELIMIT` up front.
+pub const MAX_WATCHED_DIRECTORIES: usize = 16_384;
+
+/// A watched root and whether its subfolders are watched too
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchedDirectory {
+    pub path: String,
+    pub recursive: bool,
+}
+
 #[derive(Debug)]
 pub struct FsWatcher {
-    watchers: Arc<Mutex<HashMap<String, WatchRegistration>>>,
+    watchers: Arc<Mutex<HashMap<WatchedDirectory, WatchRegistration>>>,
     app_handle: AppHandle,
+    /// Shared with every watch thread so changes apply to batches already open
+    debounce_ms: Arc<AtomicU64>,
     #[cfg(target_os = "macos")]
-    scope_tokens: Arc<Mutex<HashMap<String, macos_security::AccessToken>>>,
+    scope_tokens: Arc<Mutex<HashMap<WatchedDirectory, macos_security::AccessToken>>>,
 }
 
 #[derive(Debug)]
 struct WatchRegistration {
     _watcher: RecommendedWatcher,
     subscribers: usize,
+    /// Folders covered, counted against `MAX_WATCHED_DIRECTORIES`
+    directories: usize,
 }
 
 impl FsWatcher {
-    pub fn new(app_handle: AppHandle) -> Self {
+    pub fn new(app_handle: AppHandle, debounce_ms: u64) -> Self {
         Self {
             watchers: Arc::new(Mutex::new(HashMap::new())),
             app_handle,
+            debounce_ms: Arc::new(AtomicU64::new(debounce_ms.min(MAX_DEBOUNCE_MS))),
             #[cfg(target_os = "macos")]
             scope_tokens: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    pub fn start_watching(&self, path: &str) -> Result<(), String> {
+    /// Watch `path`, and with `recursive` everything below it. Recursive watches are
+    /// limited to `MAX_WATCHED_DIRECTORIES` folders across all watches.
+    pub fn start_watching(&self, path: &str, recursive: bool) -> Result<(), String> {
         let path_buf = PathBuf::from(path);
 
         if !path_buf.exists() {
@@ -45,25 +76,41 @@ impl FsWatcher {
         }
 
         let normalized_path = path_buf.to_string_lossy().to_string();
+        let key = WatchedDirectory {
+            path: normalized_path.clone(),
+            recursive,
+        };
 
         #[cfg(target_os = "macos")]
         let scope_token = macos_security::retain_access(&path_buf)?;
 
         // React effects can briefly overlap during navigation or Strict Mode. Keep a
         // reference count so an older cleanup cannot tear down a newer registration.
-        {
+        let budget = {
             let mut watchers = self.watchers.lock().unwrap();
-            if let Some(registration) = watchers.get_mut(&normalized_path) {
+            if let Some(registration) = watchers.get_mut(&key) {
                 registration.subscribers += 1;
                 #[cfg(target_os = "macos")]
                 drop(scope_token);
                 return Ok(());
             }
+            let in_use: usize = watchers.values().map(|r| r.directories).sum();
+            MAX_WATCHED_DIRECTORIES.saturating_sub(in_use)
+        };
+
+        let directories = if recursive {
+            count_directories(&path_buf, budget + 1)
+        } else {
+            1
+        };
+        if directories > budget {
+            return Err(watch_limit_error(path));
         }
 
         let (tx, rx) = mpsc::channel();
         let app_handle = self.app_handle.clone();
         let watch_path = normalized_path.clone();
+        let debounce_ms = Arc::clone(&self.debounce_ms);
 
         // Create watcher with custom configuration
         let config = Config::default()
@@ -80,24 +127,31 @@ impl FsWatcher {
         )
         .map_err(|e| format!("Failed to create watcher: {}", e))?;
 
-        // Start watching the directory (non-recursive for performance)
-        watcher
-            .watch(&path_buf, RecursiveMode::NonRecursive)
-            .map_err(|e| format!("Failed to start watching: {}", e))?;
+        // Only recursive when asked; a single level is all a listing needs
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(&path_buf, mode).map_err(|e| match e.kind {
+            notify::ErrorKind::MaxFilesWatch => watch_limit_error(path),
+            _ => format!("Failed to start watching: {}", e),
+        })?;
 
         // Store the watcher. Check again under the insertion lock because another
         // start may have completed while this watcher was being constructed.
         let joined_existing_registration = {
             let mut watchers = self.watchers.lock().unwrap();
-            if let Some(registration) = watchers.get_mut(&normalized_path) {
+            if let Some(registration) = watchers.get_mut(&key) {
                 registration.subscribers += 1;
                 true
             } else {
                 watchers.insert(
-                    normalized_path.clone(),
+                    key.clone(),
                     WatchRegistration {
                         _watcher: watcher,
                         subscribers: 1,
+                        directories,
                     },
                 );
                 false
@@ -113,34 +167,33 @@ impl FsWatcher {
         #[cfg(target_os = "macos")]
         if let Some(token) = scope_token {
             let mut tokens = self.scope_tokens.lock().unwrap();
-            tokens.insert(normalized_path.clone(), token);
+            tokens.insert(key, token);
         }
 
         // Use a thread to handle events since we're not in a Tokio context yet
-        // This implementation batches events within the debounce window instead of dropping them
+        // Events are batched until the directory goes quiet for the debounce interval
+        let watch_root = path_buf.clone();
         std::thread::spawn(move || {
-            use std::collections::HashSet;
-
-            const DEBOUNCE_DURATION: Duration = Duration::from_millis(300);
-
-            // Pending batch of events to emit
-            let mut pending_files: HashSet<String> = HashSet::new();
-            let mut pending_paths: HashSet<String> = HashSet::new();
-            let mut has_creates = false;
-            let mut has_modifies = false;
-            let mut has_removes = false;
-            let mut batch_start: Option<Instant> = None;
+            // Identities of the directory's entries, so deletes can be paired with creates
+            let mut known = snapshot_identities(&watch_root);
+            let mut batch = PendingBatch::default();
+            // When the open batch got its first and latest events
+            let mut batch_window: Option<(Instant, Instant)> = None;
 
             loop {
+                // Read every time round so a changed interval applies from the next wait
+                let debounce = Duration::from_millis(debounce_ms.load(Ordering::Relaxed));
+
                 // Calculate timeout for recv - either wait for debounce or indefinitely
-                let recv_result = if let Some(start) = batch_start {
-                    let elapsed = start.elapsed();
-                    if elapsed >= DEBOUNCE_DURATION {
+                let recv_result = if let Some((first, last)) = batch_window {
+                    let now = Instant::now();
+                    let deadline = flush_deadline(first, last, debounce);
+                    if now >= deadline {
                         // Debounce expired, process batch immediately
                         Err(mpsc::RecvTimeoutError::Timeout)
                     } else {
                         // Wait for next event or remaining debounce time
-                        rx.recv_timeout(DEBOUNCE_DURATION - elapsed)
+                        rx.recv_timeout(deadline - now)
                     }
                 } else {
                     // No pending batch, wait indefinitely for next event
@@ -149,94 +202,19 @@ impl FsWatcher {
 
                 match recv_result {
                     Ok(event) => {
-                        // Filter for relevant event types
-                        let is_relevant = match event.kind {
-                            EventKind::Create(_) => {
-                                has_creates = true;
-                                true
-                            }
-                            EventKind::Modify(_) => {
-                                has_modifies = true;
-                                true
-                            }
-                            EventKind::Remove(_) => {
-                                has_removes = true;
-                                true
-                            }
-                            _ => false,
-                        };
-
-                        if is_relevant {
-                            // Add paths to pending batch
-                            for path in &event.paths {
-                                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                                    pending_files.insert(name.to_string());
-                                }
-                                if let Some(full_path) = path.to_str() {
-                                    pending_paths.insert(full_path.to_string());
-                                }
-                            }
-
-                            // Start debounce timer if not already started
-                            if batch_start.is_none() {
-                                batch_start = Some(Instant::now());
-                            }
+                        // Every relevant event pushes the flush back, up to the age cap
+                        if batch.record(event) {
+                            let now = Instant::now();
+                            let first = batch_window.map_or(now, |(first, _)| first);
+                            batch_window = Some((first, now));
                         }
                     }
                     Err(mpsc::RecvTimeoutError::Timeout) => {
                         // Debounce expired, emit the batch if we have one
-                        if !pending_paths.is_empty() {
-                            // Determine change type (prioritize removes > modifies > creates)
-                            let change_type = if has_removes {
-                                "removed"
-                            } else if has_modifies {
-                                "modified"
-                            } else if has_creates {
-                                "created"
-                            } else {
-                                "changed"
-                            };
-
-                            let affected_files: Vec<String> = pending_files.drain().collect();
-                            let affected_paths: Vec<String> = pending_paths.drain().collect();
-
-                            // Invalidate thumbnail cache for modified/removed files
-                            if matches!(change_type, "modified" | "removed")
-                                && !affected_paths.is_empty()
-                            {
-                                let paths_for_invalidation = affected_paths.clone();
-                                tauri::async_runtime::spawn(async move {
-                                    if let Ok(service) =
-                                        crate::commands::get_thumbnail_service().await
-                                    {
-                                        service.invalidate_paths(&paths_for_invalidation).await;
-                                        log::debug!(
-                                            "Invalidated thumbnail cache for {} paths",
-                                            paths_for_invalidation.len()
-                                        );
-                                    }
-                                });
-                            }
-
-                            // Emit batched event to frontend
-                            let payload = serde_json::json!({
-                                "path": watch_path,
-                                "changeType": change_type,
-                                "affectedFiles": affected_files,
-                                "affectedPaths": affected_paths
-                            });
-
-                            if let Err(e) = app_handle.emit("directory-changed", payload) {
-                                eprintln!("Failed to emit directory-changed event: {}", e);
-                            }
-
-                            // Reset batch state
-                            has_creates = false;
-                            has_modifies = false;
-                            has_removes = false;
-                        }
+                        let changes = std::mem::take(&mut batch).resolve(&mut known);
+                        emit_changes(&app_handle, &watch_path, changes);
                         // Always reset timer to prevent busy-loop on non-UTF8 events
-                        batch_start = None;
+                        batch_window = None;
                     }
                     Err(mpsc::RecvTimeoutError::Disconnected) => {
                         // Channel closed, exit the loop
@@ -252,11 +230,14 @@ impl FsWatcher {
         Ok(())
     }
 
-    pub fn stop_watching(&self, path: &str) -> Result<(), String> {
-        let normalized_path = PathBuf::from(path).to_string_lossy().to_string();
+    pub fn stop_watching(&self, path: &str, recursive: bool) -> Result<(), String> {
+        let key = WatchedDirectory {
+            path: PathBuf::from(path).to_string_lossy().to_string(),
+            recursive,
+        };
 
         let mut watchers = self.watchers.lock().unwrap();
-        let should_remove = match watchers.get_mut(&normalized_path) {
+        let should_remove = match watchers.get_mut(&key) {
             Some(registration) if registration.subscribers > 1 => {
                 registration.subscribers -= 1;
                 false
@@ -266,17 +247,60 @@ impl FsWatcher {
         };
 
         if should_remove {
-            watchers.remove(&normalized_path);
+            watchers.remove(&key);
             #[cfg(target_os = "macos")]
             {
                 let mut tokens = self.scope_tokens.lock().unwrap();
-                tokens.remove(&normalized_path);
+                tokens.remove(&key);
             }
         }
 
         Ok(())
     }
 
+    /// Drop every watch on or under `root`, whatever its subscriber count, so the
+    /// volume holding it can be ejected. Returns what was released for
+    /// [`FsWatcher::restore`].
+    pub fn release_under(&self, root: &Path) -> Vec<(WatchedDirectory, usize)> {
+        let mut watchers = self.watchers.lock().unwrap();
+        let keys: Vec<WatchedDirectory> = watchers
+            .keys()
+            .filter(|key| Path::new(&key.path).starts_with(root))
+            .cloned()
+            .collect();
+        let released: Vec<(WatchedDirectory, usize)> = keys
+            .into_iter()
+            .filter_map(|key| {
+                let registration = watchers.remove(&key)?;
+                Some((key, registration.subscribers))
+            })
+            .collect();
+        drop(watchers);
+
+        #[cfg(target_os = "macos")]
+        {
+            let mut tokens = self.scope_tokens.lock().unwrap();
+            for (key, _) in &released {
+                tokens.remove(key);
+            }
+        }
+
+        released
+    }
+
+    /// Put back watches taken by [`FsWatcher::release_under`] with their subscriber
+    /// counts, skipping any whose folder is gone.
+    pub fn restore(&self, released: Vec<(WatchedDirectory, usize)>) {
+        for (key, subscribers) in released {
+            for _ in 0..subscribers {
+                if let Err(e) = self.start_watching(&key.path, key.recursive) {
+                    log::warn!("Failed to restore watcher for {}: {}", key.path, e);
+                    break;
+                }
+            }
+        }
+    }
+
     pub fn stop_all_watchers(&self) {
         let mut watchers = self.watchers.lock().unwrap();
         watchers.clear();
@@ -291,24 +315,462 @@ impl FsWatcher {
     pub fn is_watching(&self, path: &str) -> bool {
         let normalized_path = PathBuf::from(path).to_string_lossy().to_string();
         let watchers = self.watchers.lock().unwrap();
-        watchers.contains_key(&normalized_path)
+        watchers.keys().any(|key| key.path == normalized_path)
     }
 
-    pub fn get_watched_paths(&self) -> Vec<String> {
+    /// Change how long directories must be quiet before their changes are sent.
+    /// Applies to every watched directory, including batches already waiting.
+    pub fn set_debounce_ms(&self, debounce_ms: u64) -> Result<(), String> {
+        if debounce_ms > MAX_DEBOUNCE_MS {
+            return Err(format!(
+                "Watcher debounce must be at most {} ms",
+                MAX_DEBOUNCE_MS
+            ));
+        }
+        self.debounce_ms.store(debounce_ms, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn get_watched_directories(&self) -> Vec<WatchedDirectory> {
         let watchers = self.watchers.lock().unwrap();
         watchers.keys().cloned().collect()
     }
 }
 
+/// Folders in the tree under `root`, including itself, counting no further than `cap`.
+fn count_directories(root: &Path, cap: usize) -> usize {
+    WalkDir::new(root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| entry.file_type().is_dir())
+        .flatten()
+        .take(cap)
+        .count()
+}
+
+fn watch_limit_error(path: &str) -> String {
+    crate::commands::format_error(
+        crate::commands::error_codes::ELIMIT,
+        &format!(
+            "{} has too many folders to watch recursively (limit {})",
+            path, MAX_WATCHED_DIRECTORIES
+        ),
+    )
+}
+
+/// When a batch with these first and latest events is sent: once events stop for
+/// `debounce`, or when the batch is `MAX_BATCH_AGE_FACTOR` intervals old.
+fn flush_deadline(first: Instant, last: Instant, debounce: Duration) -> Instant {
+    (last + debounce).min(first + debounce * MAX_BATCH_AGE_FACTOR)
+}
+
+/// Entries tracked per watched directory for rename pairing; beyond this, only
+/// renames the platform reports as a pair are recognised.
+const MAX_TRACKED_ENTRIES: usize = 20_000;
+
+/// What identifies a file across a rename the platform reports as a delete plus a
+/// create. The inode alone isn't enough, since a new file can reuse a freed one
+/// straight away; size and mtime survive a rename but rarely match a new file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileIdentity {
+    inode: Option<u64>,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileIdentity {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::symlink_metadata(path).ok()?;
+        #[cfg(unix)]
+        let inode = {
+            use std::os::unix::fs::MetadataExt;
+            Some(metadata.ino())
+        };
+        #[cfg(not(unix))]
+        let inode = None;
+        Some(Self {
+            inode,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    fn same_file(&self, other: &Self) -> bool {
+        let same_inode = match (self.inode, other.inode) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        };
+        same_inode
+            && self.modified.is_some()
+            && self.size == other.size
+            && self.modified == other.modified
+    }
+}
+
+fn snapshot_identities(dir: &Path) -> HashMap<PathBuf, FileIdentity> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .take(MAX_TRACKED_ENTRIES)
+        .filter_map(|entry| {
+            let path = entry.path();
+            FileIdentity::of(&path).map(|identity| (path, identity))
+        })
+        .collect()
+}
+
+/// Events gathered during one debounce window, sorted by what happened to each path.
+#[derive(Debug, Default)]
+struct PendingBatch {
+    arrived: Vec<PathBuf>,
+    departed: Vec<PathBuf>,
+    modified: Vec<PathBuf>,
+    renamed: Vec<(PathBuf, PathBuf)>,
+}
+
+/// A batch after deletes and creates of the same file were paired into renames.
+#[derive(Debug, Default, PartialEq)]
+struct ResolvedChanges {
+    renamed: Vec<(PathBuf, PathBuf)>,
+    created: Vec<PathBuf>,
+    removed: Vec<PathBuf>,
+    modified: Vec<PathBuf>,
+}
+
+impl PendingBatch {
+    /// Add an event to the batch. Returns false for event kinds the UI ignores.
+    fn record(&mut self, event: Event) -> bool {
+        match event.kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                self.arrived.extend(event.paths)
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                self.departed.extend(event.paths)
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                let mut paths = event.paths.into_iter();
+                if let (Some(from), Some(to)) = (paths.next(), paths.next()) {
+                    self.renamed.push((from, to));
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                // FSEvents reports both ends of a rename without saying which is which
+                for path in event.paths {
+                    if path.exists() {
+                        self.arrived.push(path);
+                    } else {
+                        self.departed.push(path);
+                    }
+                }
+            }
+            EventKind::Modify(_) => self.modified.extend(event.paths),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Pair departures with arrivals of the same file, keeping `known` in step with
+    /// the directory. Whatever doesn't pair up is reported as before.
+    fn resolve(self, known: &mut HashMap<PathBuf, FileIdentity>) -> ResolvedChanges {
+        let mut changes = ResolvedChanges::default();
+
+        // inotify reports a rename as From, To and then Both; the Both pair already
+        // covers the other two
+        let rename_sources: HashSet<PathBuf> =
+            self.renamed.iter().map(|(from, _)| from.clone()).collect();
+        let rename_targets: HashSet<PathBuf> =
+            self.renamed.iter().map(|(_, to)| to.clone()).collect();
+
+        for (from, to) in self.renamed {
+            known.remove(&from);
+            if let Some(identity) = FileIdentity::of(&to) {
+                known.insert(to.clone(), identity);
+            }
+            changes.renamed.push((from, to));
+        }
+
+        let mut seen = HashSet::new();
+        let departed: Vec<(PathBuf, Option<FileIdentity>)> = self
+            .departed
+            .into_iter()
+            .filter(|path| !rename_sources.contains(path) && seen.insert(path.clone()))
+            .map(|path| {
+                let identity = known.remove(&path);
+                (path, identity)
+            })
+            .collect();
+
+        seen.clear();
+        let mut arrived: Vec<(PathBuf, Option<FileIdentity>)> = self
+            .arrived
+            .into_iter()
+            .filter(|path| !rename_targets.contains(path) && seen.insert(path.clone()))
+            .map(|path| {
+                let identity = FileIdentity::of(&path);
+                if let Some(identity) = identity {
+                    if known.len() < MAX_TRACKED_ENTRIES || known.contains_key(&path) {
+                        known.insert(path.clone(), identity);
+                    }
+                }
+                (path, identity)
+            })
+            .collect();
+
+        for (from, identity) in departed {
+            let partner = identity.and_then(|identity| {
+                arrived.iter().position(|(to, arrival)| {
+                    *to != from && arrival.is_some_and(|arrival| arrival.same_file(&identity))
+                })
+            });
+            match partner {
+                Some(index) => {
+                    let (to, _) = arrived.remove(index);
+                    changes.renamed.push((from, to));
+                }
+                None => changes.removed.push(from),
+            }
+        }
+        changes.created = arrived.into_iter().map(|(path, _)| path).collect();
+
+        seen.clear();
+        for (from, to) in &changes.renamed {
+            seen.insert(from.clone());
+            seen.insert(to.clone());
+        }
+        for path in self.modified {
+            if !seen.insert(path.clone()) {
+                continue;
+            }
+            if let Some(identity) = FileIdentity::of(&path) {
+                if known.len() < MAX_TRACKED_ENTRIES || known.contains_key(&path) {
+                    known.insert(path.clone(), identity);
+                }
+            }
+            changes.modified.push(path);
+        }
+
+        changes
+    }
+}
+
+fn path_strings<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) -> Vec<String> {
+    paths
+        .into_iter()
+        .filter_map(|path| path.to_str().map(str::to_string))
+        .collect()
+}
+
+fn invalidate_thumbnails(paths: Vec<String>) {
+    if paths.is_empty() {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        if let Ok(service) = crate::commands::get_thumbnail_service().await {
+            service.invalidate_paths(&paths).await;
+            log::debug!("Invalidated thumbnail cache for {} paths", paths.len());
+        }
+    });
+}
+
+/// Tell the frontend about a resolved batch: paired renames as `directory-renamed`,
+/// so rows can be updated in place, and everything else as `directory-changed`.
+fn emit_changes(app_handle: &AppHandle, watch_path: &str, changes: ResolvedChanges) {
+    if !changes.renamed.is_empty() {
+        let renamed_paths = path_strings(changes.renamed.iter().flat_map(|(from, to)| [from, to]));
+        crate::git_status::invalidate_paths(&renamed_paths);
+        crate::folder_size_cache::invalidate_paths(&renamed_paths);
+        invalidate_thumbnails(path_strings(changes.renamed.iter().map(|(from, _)| from)));
+
+        let renames: Vec<serde_json::Value> = changes
+            .renamed
+            .iter()
+            .filter_map(|(from, to)| {
+                Some(serde_json::json!({ "from": from.to_str()?, "to": to.to_str()? }))
+            })
+            .collect();
+        let payload = serde_json::json!({
+            "path": watch_path,
+            "renames": renames
+        });
+        if let Err(e) = app_handle.emit("directory-renamed", payload) {
+            log::warn!("Failed to emit directory-renamed event: {}", e);
+        }
+    }
+
+    // Determine change type (prioritize removes > modifies > creates)
+    let change_type = if !changes.removed.is_empty() {
+        "removed"
+    } else if !changes.modified.is_empty() {
+        "modified"
+    } else if !changes.created.is_empty() {
+        "created"
+    } else {
+        return;
+    };
+
+    let changed: HashSet<&PathBuf> = changes
+        .removed
+        .iter()
+        .chain(&changes.modified)
+        .chain(&changes.created)
+        .collect();
+    let affected_paths = path_strings(changed.iter().copied());
+    if affected_paths.is_empty() {
+        return;
+    }
+    let affected_files: Vec<String> = changed
+        .iter()
+        .filter_map(|path| path.file_name().and_then(|n| n.to_str()))
+        .map(str::to_string)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    // Any change inside a repository makes its cached git status stale, and
+    // any change under a measured folder its cached size
+    crate::git_status::invalidate_paths(&affected_paths);
+    crate::folder_size_cache::invalidate_paths(&affected_paths);
+
+    // Invalidate thumbnail cache for modified/removed files
+    if matches!(change_type, "modified" | "removed") {
+        invalidate_thumbnails(affected_paths.clone());
+    }
+
+    // Emit batched event to frontend
+    let payload = serde_json::json!({
+        "path": watch_path,
+        "changeType": change_type,
+        "affectedFiles": affected_files,
+        "affectedPaths": affected_paths
+    });
+
+    if let Err(e) = app_handle.emit("directory-changed", payload) {
+        eprintln!("Failed to emit directory-changed event: {}", e);
+    }
+}
+
 // Global watcher instance - will be initialized in main.rs
 static GLOBAL_WATCHER: OnceLock<Arc<FsWatcher>> = OnceLock::new();
 
-pub fn init_watcher(app_handle: AppHandle) {
+pub fn init_watcher(app_handle: AppHandle, debounce_ms: u64) {
     GLOBAL_WATCHER
-        .set(Arc::new(FsWatcher::new(app_handle)))
+        .set(Arc::new(FsWatcher::new(app_handle, debounce_ms)))
         .expect("Watcher already initialized");
 }
 
 pub fn get_watcher() -> Option<Arc<FsWatcher>> {
     GLOBAL_WATCHER.get().cloned()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, RemoveKind};
+    use std::fs;
+
+    fn event(kind: EventKind, paths: &[&Path]) -> Event {
+        paths.iter().fold(Event::new(kind), |event, path| {
+            event.add_path(path.to_path_buf())
+        })
+    }
+
+    #[test]
+    fn batches_flush_when_quiet_or_too_old() {
+        let first = Instant::now();
+        let debounce = Duration::from_millis(100);
+        let ms = Duration::from_millis;
+
+        assert_eq!(flush_deadline(first, first, debounce), first + ms(100));
+        assert_eq!(
+            flush_deadline(first, first + ms(450), debounce),
+            first + ms(550)
+        );
+        // A steady stream is still sent after ten intervals
+        assert_eq!(
+            flush_deadline(first, first + ms(980), debounce),
+            first + ms(1000)
+        );
+        assert_eq!(flush_deadline(first, first + ms(40), Duration::ZERO), first);
+    }
+
+    #[test]
+    fn counts_folders_up_to_the_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b/c")).unwrap();
+        fs::create_dir_all(dir.path().join("d")).unwrap();
+        fs::write(dir.path().join("a/file.txt"), "x").unwrap();
+
+        assert_eq!(count_directories(dir.path(), usize::MAX), 5);
+        assert_eq!(count_directories(dir.path(), 3), 3);
+        assert!(watch_limit_error("/huge").starts_with("[ELIMIT] "));
+    }
+
+    #[test]
+    fn pairs_deletes_and_creates_of_the_same_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        fs::write(path("report.txt"), "quarterly numbers").unwrap();
+        fs::write(path("old.log"), "x").unwrap();
+        fs::write(path("draft.md"), "draft").unwrap();
+        let mut known = snapshot_identities(dir.path());
+
+        fs::rename(path("report.txt"), path("final.txt")).unwrap();
+        fs::rename(path("draft.md"), path("post.md")).unwrap();
+        fs::remove_file(path("old.log")).unwrap();
+        fs::write(path("new.log"), "a fresh log").unwrap();
+
+        let mut batch = PendingBatch::default();
+        let remove = EventKind::Remove(RemoveKind::File);
+        let create = EventKind::Create(CreateKind::File);
+        let both = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        assert!(batch.record(event(remove, &[&path("report.txt")])));
+        assert!(batch.record(event(remove, &[&path("old.log")])));
+        assert!(batch.record(event(create, &[&path("new.log")])));
+        assert!(batch.record(event(create, &[&path("final.txt")])));
+        assert!(batch.record(event(both, &[&path("draft.md"), &path("post.md")])));
+        assert!(!batch.record(event(EventKind::Any, &[&path("final.txt")])));
+
+        let changes = batch.resolve(&mut known);
+        assert_eq!(
+            changes.renamed,
+            [
+                (path("draft.md"), path("post.md")),
+                (path("report.txt"), path("final.txt")),
+            ]
+        );
+        assert_eq!(changes.removed, [path("old.log")]);
+        assert_eq!(changes.created, [path("new.log")]);
+        assert!(known.contains_key(&path("final.txt")) && !known.contains_key(&path("report.txt")));
+    }
+
+    #[test]
+    fn inotify_renames_are_reported_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        fs::write(path("notes.txt"), "notes").unwrap();
+        let mut known = snapshot_identities(dir.path());
+
+        fs::rename(path("notes.txt"), path("ideas.txt")).unwrap();
+
+        // What notify's inotify backend sends for one rename
+        let mut batch = PendingBatch::default();
+        let from = EventKind::Modify(ModifyKind::Name(RenameMode::From));
+        let to = EventKind::Modify(ModifyKind::Name(RenameMode::To));
+        let both = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        assert!(batch.record(event(from, &[&path("notes.txt")])));
+        assert!(batch.record(event(to, &[&path("ideas.txt")])));
+        assert!(batch.record(event(both, &[&path("notes.txt"), &path("ideas.txt")])));
+
+        let changes = batch.resolve(&mut known);
+        assert_eq!(
+            changes,
+            ResolvedChanges {
+                renamed: vec![(path("notes.txt"), path("ideas.txt"))],
+                ..Default::default()
+            }
+        );
+        assert!(known.contains_key(&path("ideas.txt")) && !known.contains_key(&path("notes.txt")));
+    }
+}
@@ -0,0 +1,187 @@
+//! Git details for entries in a local listing: submodule roots, and Git LFS pointer
+//! files along with the size of the file they stand in for.
+//!
+//! Listings outside a working tree are left alone. Inside one, `.gitmodules` and
+//! `.gitattributes` at the root decide whether either probe runs, so repositories
+//! without submodules or LFS pay for two `stat` calls.
+
+use git2::Repository;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fs_utils::{is_git_repo_root, FileItem};
+
+/// Pointer files are small text files; the LFS spec caps them below 1 KiB.
+const LFS_POINTER_MAX_SIZE: u64 = 1024;
+/// The `version` line alone is longer than this.
+const LFS_POINTER_MIN_SIZE: u64 = 40;
+const LFS_VERSION_PREFIX: &str = "version https://git-lfs";
+
+/// Flag submodule roots and LFS pointers among `entries`, the listing of `dir`.
+pub fn annotate_listing(dir: &Path, entries: &mut [FileItem]) {
+    let Some(root) = dir.ancestors().find(|ancestor| is_git_repo_root(ancestor)) else {
+        return;
+    };
+
+    if root.join(".gitmodules").is_file() {
+        let submodules = submodule_paths(root);
+        for entry in entries.iter_mut().filter(|entry| entry.is_directory) {
+            entry.is_git_submodule = submodules.contains(Path::new(&entry.path));
+        }
+    }
+
+    if uses_lfs(root) {
+        for entry in entries.iter_mut().filter(|entry| !entry.is_directory) {
+            entry.lfs_size = lfs_pointer_size(Path::new(&entry.path), entry.size);
+        }
+    }
+}
+
+/// Whether `dir` sits in a working tree that stores files in Git LFS. Streaming
+/// listings check this once and then call [`lfs_pointer_size`] as sizes come in.
+pub fn lfs_enabled(dir: &Path) -> bool {
+    dir.ancestors()
+        .find(|ancestor| is_git_repo_root(ancestor))
+        .is_some_and(uses_lfs)
+}
+
+/// The size of the object behind the LFS pointer at `path`, a file of `size` bytes.
+pub fn lfs_pointer_size(path: &Path, size: u64) -> Option<u64> {
+    if !(LFS_POINTER_MIN_SIZE..LFS_POINTER_MAX_SIZE).contains(&size) {
+        return None;
+    }
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| parse_lfs_pointer(&contents))
+}
+
+/// Absolute paths of the submodules registered in the repository at `root`.
+fn submodule_paths(root: &Path) -> HashSet<PathBuf> {
+    let Ok(repo) = Repository::open(root) else {
+        return HashSet::new();
+    };
+    let Ok(submodules) = repo.submodules() else {
+        return HashSet::new();
+    };
+    submodules
+        .iter()
+        .map(|submodule| root.join(submodule.path()))
+        .collect()
+}
+
+/// Whether the root `.gitattributes` routes any files through the LFS filter.
+fn uses_lfs(root: &Path) -> bool {
+    fs::read_to_string(root.join(".gitattributes"))
+        .map(|attributes| attributes.contains("filter=lfs"))
+        .unwrap_or(false)
+}
+
+/// The object size recorded in an LFS pointer, or `None` if `contents` isn't one.
+fn parse_lfs_pointer(contents: &str) -> Option<u64> {
+    let mut lines = contents.lines();
+    if !lines.next()?.starts_with(LFS_VERSION_PREFIX) {
+        return None;
+    }
+    lines
+        .find_map(|line| line.strip_prefix("size "))
+        .and_then(|size| size.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_utils::read_directory_contents;
+    use tempfile::tempdir;
+
+    const POINTER: &str = "version https://git-lfs.github.com/spec/v1\n\
+        oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393\n\
+        size 12345\n";
+
+    #[test]
+    fn parses_lfs_pointers() {
+        assert_eq!(parse_lfs_pointer(POINTER), Some(12345));
+        assert_eq!(parse_lfs_pointer("size 12345\n"), None);
+        assert_eq!(
+            parse_lfs_pointer("version https://git-lfs.github.com/spec/v1\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn flags_lfs_pointers_only_inside_lfs_repos() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("model.bin"), POINTER).unwrap();
+        fs::write(root.join("notes.txt"), "just some text that is long enough").unwrap();
+
+        let mut entries = read_directory_contents(root).unwrap();
+        annotate_listing(root, &mut entries);
+        assert!(entries.iter().all(|entry| entry.lfs_size.is_none()));
+
+        Repository::init(root).unwrap();
+        fs::write(
+            root.join(".gitattributes"),
+            "*.bin filter=lfs diff=lfs merge=lfs -text\n",
+        )
+        .unwrap();
+        let mut entries = read_directory_contents(root).unwrap();
+        annotate_listing(root, &mut entries);
+        let size_of = |name: &str| {
+            entries
+                .iter()
+                .find(|entry| entry.name == name)
+                .and_then(|entry| entry.lfs_size)
+        };
+        assert_eq!(size_of("model.bin"), Some(12345));
+        assert_eq!(size_of("notes.txt"), None);
+    }
+
+    #[test]
+    fn streamed_listings_flag_submodules_and_lfs_pointers() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let repo = Repository::init(root).unwrap();
+        fs::write(
+            root.join(".gitattributes"),
+            "*.bin filter=lfs diff=lfs merge=lfs -text\n",
+        )
+        .unwrap();
+        fs::write(root.join("model.bin"), POINTER).unwrap();
+        fs::write(root.join("notes.txt"), "just some text that is long enough").unwrap();
+
+        // Registers `vendor` in .gitmodules and creates its checkout
+        let url = format!("file://{}", root.display());
+        repo.submodule(&url, Path::new("vendor"), false)
+            .expect("add submodule");
+
+        let cancel = std::sync::atomic::AtomicBool::new(false);
+        let mut skeletons = Vec::new();
+        let mut updates = Vec::new();
+        crate::fs_utils::read_directory_streaming(
+            root,
+            "test".to_string(),
+            std::sync::Arc::new(cancel),
+            |mut batch| {
+                annotate_listing(root, &mut batch.entries);
+                skeletons.extend(batch.entries);
+            },
+            |batch| updates.extend(batch.updates),
+        )
+        .unwrap();
+
+        let vendor = skeletons
+            .iter()
+            .find(|entry| entry.name == "vendor")
+            .unwrap();
+        assert!(vendor.is_git_submodule);
+        let lfs_size_of = |name: &str| {
+            updates
+                .iter()
+                .find(|update| update.path.ends_with(name))
+                .and_then(|update| update.lfs_size)
+        };
+        assert_eq!(lfs_size_of("model.bin"), Some(12345));
+        assert_eq!(lfs_size_of("notes.txt"), None);
+    }
+}
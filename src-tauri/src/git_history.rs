@@ -0,0 +1,482 @@
+//! Last commit that touched each path, for the history column.
+//!
+//! A path (file or directory) counts as changed by a commit when its tree entry
+//! differs from every parent's, which matches git's default history
+//! simplification. Results only depend on HEAD, so they are cached per HEAD oid
+//! and dropped once the repository moves on.
+
+use chrono::{DateTime, TimeZone, Utc};
+use git2::{Commit, Oid, Repository, Sort, Tree};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Default number of commits inspected before giving up on the remaining paths.
+pub const DEFAULT_MAX_COMMITS: usize = 2000;
+/// Hard cap on `max_commits` so a request can't walk a huge history.
+const MAX_COMMITS_LIMIT: usize = 20_000;
+/// Cached entries across all repositories before the cache is reset.
+const CACHE_MAX_ENTRIES: usize = 50_000;
+/// Default number of entries in a file's log.
+pub const DEFAULT_LOG_LIMIT: usize = 50;
+/// Hard cap on a file log's `limit`.
+const LOG_LIMIT_MAX: usize = 500;
+/// Commits inspected for a file log before returning what was found.
+const LOG_MAX_WALK: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LastCommitInfo {
+    pub short_sha: String,
+    pub author: String,
+    pub summary: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastCommitEntry {
+    pub path: String,
+    /// `None` when the path is untracked or wasn't touched within the walked history.
+    pub commit: Option<LastCommitInfo>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastCommitResponse {
+    pub entries: Vec<LastCommitEntry>,
+    /// True when the walk stopped at `max_commits` with paths still unresolved.
+    pub history_truncated: bool,
+    pub cancelled: bool,
+}
+
+/// One commit in a file's history.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileLogEntry {
+    pub sha: String,
+    #[serde(flatten)]
+    pub commit: LastCommitInfo,
+    /// The commit on the remote's web UI, when the remote is a known host.
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileLogResponse {
+    pub entries: Vec<FileLogEntry>,
+    /// True when the walk stopped at its cap before finding `limit` commits.
+    pub history_truncated: bool,
+}
+
+struct RepoCache {
+    head: Oid,
+    /// Keyed by path relative to the repository root. `None` is a cached miss,
+    /// only stored when the walk reached the root commit.
+    entries: HashMap<PathBuf, Option<LastCommitInfo>>,
+}
+
+static CACHE: Lazy<Mutex<HashMap<PathBuf, RepoCache>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn commit_info(commit: &Commit<'_>) -> LastCommitInfo {
+    let sha = commit.id().to_string();
+    let author = commit.author();
+    LastCommitInfo {
+        short_sha: sha[..7.min(sha.len())].to_string(),
+        author: author.name().unwrap_or("Unknown").to_string(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+        timestamp: Utc
+            .timestamp_opt(commit.time().seconds(), 0)
+            .single()
+            .unwrap_or_else(Utc::now),
+    }
+}
+
+fn entry_id(tree: &Tree<'_>, path: &Path) -> Option<Oid> {
+    tree.get_path(path).ok().map(|entry| entry.id())
+}
+
+/// Find the last commit touching each of `paths` (absolute, inside the working tree
+/// of the repository containing `repo_dir`), walking at most `max_commits` commits.
+pub fn last_commits_for_paths(
+    repo_dir: &Path,
+    paths: &[String],
+    max_commits: usize,
+    cancel: &AtomicBool,
+) -> Result<LastCommitResponse, String> {
+    let repo = Repository::discover(repo_dir)
+        .map_err(|err| format!("Failed to open Git repository: {err}"))?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "Repository has no working directory".to_string())?;
+    let root = workdir
+        .canonicalize()
+        .unwrap_or_else(|_| workdir.to_path_buf());
+
+    let relative: Vec<Option<PathBuf>> = paths
+        .iter()
+        .map(|path| {
+            let path = Path::new(path);
+            let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+            path.strip_prefix(&root)
+                .ok()
+                .filter(|rel| !rel.as_os_str().is_empty())
+                .map(Path::to_path_buf)
+        })
+        .collect();
+
+    // Unborn HEAD: nothing has been committed yet
+    let Some(head) = repo.head().ok().and_then(|h| h.target()) else {
+        return Ok(LastCommitResponse {
+            entries: paths
+                .iter()
+                .map(|path| LastCommitEntry {
+                    path: path.clone(),
+                    commit: None,
+                })
+                .collect(),
+            history_truncated: false,
+            cancelled: false,
+        });
+    };
+
+    let mut found: HashMap<PathBuf, Option<LastCommitInfo>> = HashMap::new();
+    if let Ok(cache) = CACHE.lock() {
+        if let Some(repo_cache) = cache.get(&root).filter(|c| c.head == head) {
+            for rel in relative.iter().flatten() {
+                if let Some(hit) = repo_cache.entries.get(rel) {
+                    found.insert(rel.clone(), hit.clone());
+                }
+            }
+        }
+    }
+
+    let mut pending: Vec<PathBuf> = relative
+        .iter()
+        .flatten()
+        .filter(|rel| !found.contains_key(*rel))
+        .cloned()
+        .collect();
+    pending.sort();
+    pending.dedup();
+
+    let mut history_truncated = false;
+    let mut cancelled = false;
+    let mut reached_root = false;
+
+    if !pending.is_empty() {
+        let mut revwalk = repo
+            .revwalk()
+            .map_err(|err| format!("Failed to walk history: {err}"))?;
+        revwalk
+            .set_sorting(Sort::TIME)
+            .map_err(|err| format!("Failed to walk history: {err}"))?;
+        revwalk
+            .push(head)
+            .map_err(|err| format!("Failed to walk history: {err}"))?;
+
+        let max_commits = max_commits.clamp(1, MAX_COMMITS_LIMIT);
+        let mut walked = 0usize;
+        reached_root = true;
+
+        for oid in revwalk {
+            if pending.is_empty() {
+                reached_root = false;
+                break;
+            }
+            if cancel.load(Ordering::SeqCst) {
+                cancelled = true;
+                reached_root = false;
+                break;
+            }
+            if walked >= max_commits {
+                history_truncated = true;
+                reached_root = false;
+                break;
+            }
+            walked += 1;
+
+            let Ok(commit) = oid.and_then(|oid| repo.find_commit(oid)) else {
+                continue;
+            };
+            let Ok(tree) = commit.tree() else {
+                continue;
+            };
+            let parent_trees: Vec<Tree<'_>> = commit
+                .parents()
+                .filter_map(|parent| parent.tree().ok())
+                .collect();
+
+            pending.retain(|rel| {
+                let here = entry_id(&tree, rel);
+                if here.is_none() {
+                    // Not in this commit: it was added later (already handled) or
+                    // removed earlier; keep looking in case it existed before
+                    return true;
+                }
+                let changed = if parent_trees.is_empty() {
+                    true
+                } else {
+                    parent_trees
+                        .iter()
+                        .all(|parent| entry_id(parent, rel) != here)
+                };
+                if changed {
+                    found.insert(rel.clone(), Some(commit_info(&commit)));
+                }
+                !changed
+            });
+        }
+    }
+
+    // Paths still pending after a full walk don't exist in history (untracked);
+    // that answer is as stable as a hit, so cache it too
+    if reached_root {
+        for rel in pending.drain(..) {
+            found.insert(rel, None);
+        }
+    }
+
+    if let Ok(mut cache) = CACHE.lock() {
+        let total: usize = cache.values().map(|c| c.entries.len()).sum();
+        if total > CACHE_MAX_ENTRIES {
+            cache.clear();
+        }
+        let repo_cache = cache.entry(root.clone()).or_insert_with(|| RepoCache {
+            head,
+            entries: HashMap::new(),
+        });
+        if repo_cache.head != head {
+            repo_cache.head = head;
+            repo_cache.entries.clear();
+        }
+        for (rel, info) in &found {
+            repo_cache.entries.insert(rel.clone(), info.clone());
+        }
+    }
+
+    let entries = paths
+        .iter()
+        .zip(&relative)
+        .map(|(path, rel)| LastCommitEntry {
+            path: path.clone(),
+            commit: rel
+                .as_ref()
+                .and_then(|rel| found.get(rel).cloned().flatten()),
+        })
+        .collect();
+
+    Ok(LastCommitResponse {
+        entries,
+        history_truncated,
+        cancelled,
+    })
+}
+
+/// The last `limit` commits that touched `path`, newest first. Like the history
+/// column, a commit counts when the path's tree entry differs from every parent's,
+/// which also catches the commit that deleted it. `commit_url` links each commit to
+/// the remote's web UI.
+pub fn file_log<F>(
+    repo: &Repository,
+    path: &Path,
+    limit: usize,
+    commit_url: F,
+) -> Result<FileLogResponse, String>
+where
+    F: Fn(Oid) -> Option<String>,
+{
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "Repository has no working directory".to_string())?;
+    let root = workdir
+        .canonicalize()
+        .unwrap_or_else(|_| workdir.to_path_buf());
+    let absolute = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let rel = absolute
+        .strip_prefix(&root)
+        .ok()
+        .filter(|rel| !rel.as_os_str().is_empty())
+        .ok_or_else(|| format!("{} is not inside the repository", path.display()))?;
+
+    let mut response = FileLogResponse {
+        entries: Vec::new(),
+        history_truncated: false,
+    };
+    // Unborn HEAD: nothing has been committed yet
+    let Some(head) = repo.head().ok().and_then(|h| h.target()) else {
+        return Ok(response);
+    };
+
+    let mut revwalk = repo
+        .revwalk()
+        .map_err(|err| format!("Failed to walk history: {err}"))?;
+    revwalk
+        .set_sorting(Sort::TIME)
+        .map_err(|err| format!("Failed to walk history: {err}"))?;
+    revwalk
+        .push(head)
+        .map_err(|err| format!("Failed to walk history: {err}"))?;
+
+    let limit = limit.clamp(1, LOG_LIMIT_MAX);
+    for (walked, oid) in revwalk.enumerate() {
+        if response.entries.len() >= limit {
+            break;
+        }
+        if walked >= LOG_MAX_WALK {
+            response.history_truncated = true;
+            break;
+        }
+
+        let Ok(commit) = oid.and_then(|oid| repo.find_commit(oid)) else {
+            continue;
+        };
+        let Ok(tree) = commit.tree() else {
+            continue;
+        };
+        let here = entry_id(&tree, rel);
+        let parent_trees: Vec<Tree<'_>> = commit
+            .parents()
+            .filter_map(|parent| parent.tree().ok())
+            .collect();
+        let changed = if parent_trees.is_empty() {
+            here.is_some()
+        } else {
+            parent_trees
+                .iter()
+                .all(|parent| entry_id(parent, rel) != here)
+        };
+
+        if changed {
+            response.entries.push(FileLogEntry {
+                sha: commit.id().to_string(),
+                commit: commit_info(&commit),
+                url: commit_url(commit.id()),
+            });
+        }
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Signature;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn commit_all(repo: &Repository, message: &str, seconds: i64) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.update_all(["*"].iter(), None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature =
+            Signature::new("Ada", "ada@example.com", &git2::Time::new(seconds, 0)).unwrap();
+        let parents: Vec<Commit<'_>> = repo
+            .head()
+            .ok()
+            .and_then(|h| h.peel_to_commit().ok())
+            .into_iter()
+            .collect();
+        let parent_refs: Vec<&Commit<'_>> = parents.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parent_refs,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn finds_last_commit_for_files_and_directories() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().canonicalize().unwrap();
+        let repo = Repository::init(&root).unwrap();
+
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("README.md"), "hello").unwrap();
+        fs::write(root.join("src/lib.rs"), "v1").unwrap();
+        commit_all(&repo, "Initial import", 1_700_000_000);
+
+        fs::write(root.join("src/lib.rs"), "v2").unwrap();
+        commit_all(&repo, "Tweak lib", 1_700_000_100);
+
+        fs::write(root.join("notes.txt"), "untracked").unwrap();
+
+        let paths: Vec<String> = ["README.md", "src", "src/lib.rs", "notes.txt"]
+            .iter()
+            .map(|p| root.join(p).to_string_lossy().to_string())
+            .collect();
+        let cancel = AtomicBool::new(false);
+        let response = last_commits_for_paths(&root, &paths, 100, &cancel).unwrap();
+
+        let summaries: Vec<Option<&str>> = response
+            .entries
+            .iter()
+            .map(|e| e.commit.as_ref().map(|c| c.summary.as_str()))
+            .collect();
+        assert_eq!(
+            summaries,
+            [
+                Some("Initial import"),
+                Some("Tweak lib"),
+                Some("Tweak lib"),
+                None
+            ]
+        );
+        assert!(!response.history_truncated);
+        let readme = response.entries[0].commit.as_ref().unwrap();
+        assert_eq!(readme.author, "Ada");
+        assert_eq!(readme.short_sha.len(), 7);
+
+        // A cap of one commit can't reach the initial import
+        fs::write(root.join("src/lib.rs"), "v3").unwrap();
+        commit_all(&repo, "Again", 1_700_000_200);
+        let capped = last_commits_for_paths(&root, &paths[..1], 1, &cancel).unwrap();
+        assert!(capped.history_truncated);
+        assert!(capped.entries[0].commit.is_none());
+    }
+
+    #[test]
+    fn file_log_lists_commits_touching_the_file() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().canonicalize().unwrap();
+        let repo = Repository::init(&root).unwrap();
+
+        fs::write(root.join("a.txt"), "v1").unwrap();
+        fs::write(root.join("b.txt"), "v1").unwrap();
+        commit_all(&repo, "Add files", 1_700_000_000);
+        fs::write(root.join("b.txt"), "v2").unwrap();
+        commit_all(&repo, "Edit b", 1_700_000_100);
+        fs::write(root.join("a.txt"), "v2").unwrap();
+        commit_all(&repo, "Edit a", 1_700_000_200);
+
+        let url = |oid: Oid| Some(format!("https://example.com/commit/{oid}"));
+        let log = file_log(&repo, &root.join("a.txt"), 10, url).unwrap();
+        let summaries: Vec<&str> = log
+            .entries
+            .iter()
+            .map(|e| e.commit.summary.as_str())
+            .collect();
+        assert_eq!(summaries, ["Edit a", "Add files"]);
+        assert!(!log.history_truncated);
+        let newest = &log.entries[0];
+        assert_eq!(
+            newest.url.as_deref(),
+            Some(format!("https://example.com/commit/{}", newest.sha).as_str())
+        );
+
+        let limited = file_log(&repo, &root.join("a.txt"), 1, |_| None).unwrap();
+        assert_eq!(limited.entries.len(), 1);
+    }
+}
@@ -0,0 +1,416 @@
+//! Bookkeeping for the working-tree half of `get_git_status`, and the per-file
+//! statuses behind `get_git_file_statuses`.
+//!
+//! Scanning a huge working tree can take seconds, so the scan runs on its own
+//! blocking task and callers only wait up to a timeout. Results are cached per
+//! repository, keyed by HEAD and the index file's mtime, and dropped when the fs
+//! watcher reports a change inside the repository. A scan that was overtaken by
+//! such a change while it ran isn't cached at all. Per-file statuses are cached
+//! the same way, so browsing around one repository only scans it once.
+//!
+//! Settings come from the `gitStatus` object in preferences.json, e.g.
+//! `{ "gitStatus": { "recurseUntracked": false, "timeoutMs": 1500 } }`.
+
+use git2::Status;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+const PREFERENCES_KEY: &str = "gitStatus";
+const DEFAULT_TIMEOUT_MS: u64 = 2000;
+/// Bounds for a configured timeout.
+const MIN_TIMEOUT_MS: u64 = 100;
+const MAX_TIMEOUT_MS: u64 = 60_000;
+
+static RECURSE_UNTRACKED: AtomicBool = AtomicBool::new(true);
+static TIMEOUT_MS: AtomicU64 = AtomicU64::new(DEFAULT_TIMEOUT_MS);
+
+/// Whether untracked directories are walked to find untracked files inside them.
+/// Turning this off makes scans much faster on trees with big ignored-but-untracked
+/// folders, at the cost of only reporting the top-level untracked directory.
+pub fn recurse_untracked() -> bool {
+    RECURSE_UNTRACKED.load(Ordering::Relaxed)
+}
+
+/// How long `get_git_status` waits for a working-tree scan before giving up.
+pub fn timeout() -> Duration {
+    Duration::from_millis(TIMEOUT_MS.load(Ordering::Relaxed))
+}
+
+/// Read `gitStatus` from a preferences JSON document and apply it.
+/// Invalid JSON or missing keys restore the defaults.
+pub fn configure_from_preferences(json: &str) {
+    let settings = serde_json::from_str::<serde_json::Value>(json)
+        .ok()
+        .and_then(|value| value.get(PREFERENCES_KEY).cloned());
+    let recurse = settings
+        .as_ref()
+        .and_then(|s| s.get("recurseUntracked"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true);
+    let timeout_ms = settings
+        .as_ref()
+        .and_then(|s| s.get("timeoutMs"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(DEFAULT_TIMEOUT_MS)
+        .clamp(MIN_TIMEOUT_MS, MAX_TIMEOUT_MS);
+
+    if RECURSE_UNTRACKED.swap(recurse, Ordering::Relaxed) != recurse {
+        // Results computed under the other mode would be wrong now
+        clear();
+    }
+    TIMEOUT_MS.store(timeout_ms, Ordering::Relaxed);
+}
+
+/// Identifies one state of a repository: a scan result is reusable as long as
+/// HEAD and the index haven't moved and no watched file inside it changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanKey {
+    pub root: PathBuf,
+    pub git_dir: PathBuf,
+    pub head: Option<String>,
+    pub index_mtime: Option<SystemTime>,
+}
+
+impl ScanKey {
+    pub fn new(root: &Path, git_dir: &Path, head: Option<String>) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            git_dir: git_dir.to_path_buf(),
+            head,
+            index_mtime: index_mtime(git_dir),
+        }
+    }
+
+    /// Whether the index is still the one this key was made for. A result computed
+    /// for a key that no longer is would be cached under a state that's gone.
+    fn is_current(&self) -> bool {
+        index_mtime(&self.git_dir) == self.index_mtime
+    }
+}
+
+fn index_mtime(git_dir: &Path) -> Option<SystemTime> {
+    std::fs::metadata(git_dir.join("index"))
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorktreeState {
+    pub dirty: bool,
+    pub has_untracked: bool,
+}
+
+/// Status of one entry in a directory listing. Folders take the most significant
+/// status of anything inside them, except that they're only `Ignored` themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileStatus {
+    Clean,
+    Ignored,
+    Untracked,
+    Staged,
+    Modified,
+    Conflicted,
+}
+
+impl FileStatus {
+    pub fn from_git(status: Status) -> Self {
+        if status.contains(Status::CONFLICTED) {
+            FileStatus::Conflicted
+        } else if status.intersects(
+            Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_TYPECHANGE | Status::WT_RENAMED,
+        ) {
+            FileStatus::Modified
+        } else if status.intersects(
+            Status::INDEX_NEW
+                | Status::INDEX_MODIFIED
+                | Status::INDEX_DELETED
+                | Status::INDEX_RENAMED
+                | Status::INDEX_TYPECHANGE,
+        ) {
+            FileStatus::Staged
+        } else if status.contains(Status::WT_NEW) {
+            FileStatus::Untracked
+        } else if status.contains(Status::IGNORED) {
+            FileStatus::Ignored
+        } else {
+            FileStatus::Clean
+        }
+    }
+}
+
+/// Non-clean entries of a repository by path relative to its root, `/`-separated.
+/// Untracked or ignored folders that weren't walked end in `/`.
+pub type FileStatusMap = HashMap<String, FileStatus>;
+
+/// Statuses for the entries `names` of the repository-relative directory `dir`
+/// (empty for the root).
+pub fn statuses_for_directory<I>(
+    statuses: &FileStatusMap,
+    dir: &str,
+    names: I,
+) -> HashMap<String, FileStatus>
+where
+    I: IntoIterator<Item = String>,
+{
+    let prefix = if dir.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", dir.trim_end_matches('/'))
+    };
+
+    // Inside an untracked or ignored folder everything shares its status
+    let mut inherited = FileStatus::Clean;
+    let mut ancestor = String::new();
+    for segment in dir.split('/').filter(|s| !s.is_empty()) {
+        ancestor.push_str(segment);
+        ancestor.push('/');
+        if let Some(status) = statuses.get(&ancestor) {
+            inherited = *status;
+        }
+    }
+
+    let mut result: HashMap<String, FileStatus> = names
+        .into_iter()
+        .filter(|name| name != ".git")
+        .map(|name| (name, inherited))
+        .collect();
+    if inherited != FileStatus::Clean {
+        return result;
+    }
+
+    for (path, status) in statuses {
+        let Some(rest) = path.strip_prefix(&prefix) else {
+            continue;
+        };
+        let (name, nested) = match rest.split_once('/') {
+            Some((name, tail)) => (name, !tail.is_empty()),
+            None => (rest, false),
+        };
+        // Ignored files deep inside don't make their folder ignored
+        if nested && *status == FileStatus::Ignored {
+            continue;
+        }
+        if let Some(current) = result.get_mut(name) {
+            *current = (*current).max(*status);
+        }
+    }
+    result
+}
+
+#[derive(Default)]
+struct ScanCache {
+    results: HashMap<PathBuf, (ScanKey, Result<WorktreeState, String>)>,
+    in_flight: HashSet<PathBuf>,
+    /// Roots whose running scan started before a change the watcher reported
+    overtaken: HashSet<PathBuf>,
+    file_statuses: HashMap<PathBuf, (ScanKey, Arc<FileStatusMap>)>,
+}
+
+static CACHE: Lazy<Mutex<ScanCache>> = Lazy::new(|| Mutex::new(ScanCache::default()));
+
+/// Cached scan result for exactly this repository state.
+pub fn cached(key: &ScanKey) -> Option<Result<WorktreeState, String>> {
+    let cache = CACHE.lock().ok()?;
+    let (cached_key, result) = cache.results.get(&key.root)?;
+    (cached_key == key).then(|| result.clone())
+}
+
+/// Claim the scan for `root`. Returns false if one is already running, in which
+/// case the caller should wait for its result instead of starting another.
+pub fn begin_scan(root: &Path) -> bool {
+    CACHE
+        .lock()
+        .map(|mut cache| cache.in_flight.insert(root.to_path_buf()))
+        .unwrap_or(true)
+}
+
+/// Record a finished scan and release the claim from `begin_scan`. The result is
+/// dropped if the index moved or a change inside the repository was reported while
+/// the scan ran, so the next call scans again.
+pub fn finish_scan(key: ScanKey, result: Result<WorktreeState, String>) {
+    let current = key.is_current();
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.in_flight.remove(&key.root);
+        let overtaken = cache.overtaken.remove(&key.root);
+        if current && !overtaken {
+            cache.results.insert(key.root.clone(), (key, result));
+        }
+    }
+}
+
+/// Cached per-file statuses for exactly this repository state.
+pub fn cached_file_statuses(key: &ScanKey) -> Option<Arc<FileStatusMap>> {
+    let cache = CACHE.lock().ok()?;
+    let (cached_key, statuses) = cache.file_statuses.get(&key.root)?;
+    (cached_key == key).then(|| Arc::clone(statuses))
+}
+
+/// Cache per-file statuses, unless the index moved while they were collected.
+pub fn store_file_statuses(key: ScanKey, statuses: Arc<FileStatusMap>) {
+    if !key.is_current() {
+        return;
+    }
+    if let Ok(mut cache) = CACHE.lock() {
+        cache
+            .file_statuses
+            .insert(key.root.clone(), (key, statuses));
+    }
+}
+
+/// Drop cached results for every repository containing one of `paths`.
+pub fn invalidate_paths(paths: &[String]) {
+    let Ok(mut cache) = CACHE.lock() else {
+        return;
+    };
+    if cache.results.is_empty() && cache.file_statuses.is_empty() && cache.in_flight.is_empty() {
+        return;
+    }
+    let affected = |root: &PathBuf| paths.iter().any(|p| Path::new(p).starts_with(root));
+    cache.results.retain(|root, _| !affected(root));
+    cache.file_statuses.retain(|root, _| !affected(root));
+    let overtaken: Vec<PathBuf> = cache
+        .in_flight
+        .iter()
+        .filter(|root| affected(root))
+        .cloned()
+        .collect();
+    cache.overtaken.extend(overtaken);
+}
+
+fn clear() {
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.results.clear();
+        cache.file_statuses.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn cache_is_keyed_by_head_and_index_and_invalidated_by_changes() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().join("repo");
+        let git_dir = root.join(".git");
+        std::fs::create_dir_all(&git_dir).unwrap();
+        std::fs::write(git_dir.join("index"), b"v1").unwrap();
+
+        let key = ScanKey::new(&root, &git_dir, Some("abc".into()));
+        let clean = WorktreeState {
+            dirty: false,
+            has_untracked: false,
+        };
+        assert!(begin_scan(&root));
+        assert!(!begin_scan(&root), "second scan should wait for the first");
+        finish_scan(key.clone(), Ok(clean));
+        assert_eq!(cached(&key), Some(Ok(clean)));
+
+        // A new commit changes HEAD
+        let moved = ScanKey::new(&root, &git_dir, Some("def".into()));
+        assert_eq!(cached(&moved), None);
+
+        // A change reported inside the repo drops the entry; one elsewhere doesn't
+        invalidate_paths(&[dir.path().join("elsewhere/file").to_string_lossy().into()]);
+        assert_eq!(cached(&key), Some(Ok(clean)));
+        invalidate_paths(&[root.join("src/main.rs").to_string_lossy().into()]);
+        assert_eq!(cached(&key), None);
+    }
+
+    #[test]
+    fn scans_overtaken_by_changes_are_not_cached() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().join("repo");
+        let git_dir = root.join(".git");
+        std::fs::create_dir_all(&git_dir).unwrap();
+        let index = git_dir.join("index");
+        std::fs::write(&index, b"v1").unwrap();
+        let set_index_mtime = |secs| {
+            filetime::set_file_mtime(&index, filetime::FileTime::from_unix_time(secs, 0)).unwrap()
+        };
+        set_index_mtime(1_700_000_000);
+        let clean = WorktreeState {
+            dirty: false,
+            has_untracked: false,
+        };
+
+        // Staging a file while the scan runs moves the index
+        let key = ScanKey::new(&root, &git_dir, Some("abc".into()));
+        assert!(begin_scan(&root));
+        set_index_mtime(1_700_000_100);
+        finish_scan(key.clone(), Ok(clean));
+        assert_eq!(cached(&key), None);
+        store_file_statuses(key, Arc::new(FileStatusMap::new()));
+        let key = ScanKey::new(&root, &git_dir, Some("abc".into()));
+        assert!(cached_file_statuses(&key).is_none());
+
+        // So does an edit the watcher reports mid-scan
+        assert!(begin_scan(&root));
+        invalidate_paths(&[root.join("src/main.rs").to_string_lossy().into()]);
+        finish_scan(key.clone(), Ok(clean));
+        assert_eq!(cached(&key), None);
+
+        // The next scan is cached as usual
+        assert!(begin_scan(&root));
+        finish_scan(key.clone(), Ok(clean));
+        assert_eq!(cached(&key), Some(Ok(clean)));
+    }
+
+    #[test]
+    fn directory_statuses_roll_up_from_children() {
+        let statuses: FileStatusMap = [
+            ("README.md", FileStatus::Modified),
+            ("src/lib.rs", FileStatus::Staged),
+            ("src/ui/view.rs", FileStatus::Conflicted),
+            ("src/generated.rs", FileStatus::Ignored),
+            ("docs/draft.md", FileStatus::Untracked),
+            ("target/", FileStatus::Ignored),
+            ("scratch/", FileStatus::Untracked),
+        ]
+        .into_iter()
+        .map(|(path, status)| (path.to_string(), status))
+        .collect();
+        let names = |list: &[&str]| list.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        let root = statuses_for_directory(
+            &statuses,
+            "",
+            names(&[
+                "README.md",
+                "Cargo.toml",
+                "src",
+                "docs",
+                "target",
+                "scratch",
+                ".git",
+            ]),
+        );
+        assert_eq!(root.get("README.md"), Some(&FileStatus::Modified));
+        assert_eq!(root.get("Cargo.toml"), Some(&FileStatus::Clean));
+        assert_eq!(root.get("src"), Some(&FileStatus::Conflicted));
+        assert_eq!(root.get("docs"), Some(&FileStatus::Untracked));
+        assert_eq!(root.get("target"), Some(&FileStatus::Ignored));
+        assert_eq!(root.get("scratch"), Some(&FileStatus::Untracked));
+        assert!(!root.contains_key(".git"));
+
+        let src = statuses_for_directory(
+            &statuses,
+            "src",
+            names(&["lib.rs", "ui", "generated.rs", "main.rs"]),
+        );
+        assert_eq!(src.get("lib.rs"), Some(&FileStatus::Staged));
+        assert_eq!(src.get("ui"), Some(&FileStatus::Conflicted));
+        assert_eq!(src.get("generated.rs"), Some(&FileStatus::Ignored));
+        assert_eq!(src.get("main.rs"), Some(&FileStatus::Clean));
+
+        let inside_ignored = statuses_for_directory(&statuses, "target/debug", names(&["app"]));
+        assert_eq!(inside_ignored.get("app"), Some(&FileStatus::Ignored));
+    }
+}
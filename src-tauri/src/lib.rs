@@ -1,23 +1,43 @@
 mod clipboard;
 mod commands;
+mod drive_watcher;
+mod file_diff;
+mod file_plan;
+mod file_tags;
+mod file_templates;
+mod folder_size_cache;
 mod fs_utils;
 mod fs_watcher;
+mod git_annotations;
+mod git_history;
+mod git_status;
 mod locations;
 #[cfg(target_os = "macos")]
 mod macos_icons;
 mod macos_security;
+mod media_metadata;
 mod menu;
 #[cfg(target_os = "macos")]
 mod native_drag;
+mod open_with;
+mod organize;
 mod plugins;
+mod preview;
+mod rename_pattern;
+mod search_index;
+mod sorting;
 mod state;
+mod text_file;
 mod thumbnails;
 
 // SMB sidecar module - only compiled for the sidecar binary
 #[cfg(feature = "smb-sidecar")]
 pub mod smb_sidecar;
 
-use state::{DirectoryStreamState, FolderSizeState, MenuState, TrashUndoState};
+use state::{
+    CopyTreeState, DirectoryStreamState, FolderSizeState, GitHistoryState, MenuState, SearchState,
+    SortPreferencesState, TrashUndoState, UploadState,
+};
 use std::sync::Mutex;
 use std::sync::OnceLock;
 
@@ -78,7 +98,7 @@ fn apply_linux_menu_css() {
         }
     });
 }
-use tauri::Manager;
+use tauri::{Listener, Manager};
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     ensure_rustls_crypto_provider();
@@ -103,28 +123,66 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             commands::get_home_directory,
+            commands::canonicalize_path,
+            commands::search_files,
+            commands::get_search_index_status,
+            commands::search_directory,
+            commands::cancel_directory_search,
             commands::get_disk_usage,
+            commands::check_transfer_feasibility,
             commands::get_git_status,
+            commands::get_git_file_statuses,
+            commands::get_last_commit_for_paths,
+            commands::cancel_last_commit_lookup,
+            commands::get_file_git_log,
             commands::read_directory,
             commands::read_directory_streaming_command,
             commands::cancel_directory_stream,
             commands::get_file_metadata,
+            commands::get_child_count,
+            commands::get_directory_stats,
+            commands::compute_file_hash,
             commands::resolve_symlink_parent_command,
+            commands::create_symlink,
+            commands::create_hardlink,
+            commands::get_file_tags,
+            commands::set_file_tags,
+            commands::get_xattr,
+            commands::set_xattr,
             commands::create_folder,
             commands::create_file,
+            commands::get_file_templates,
             commands::create_nested_folders,
             commands::create_directory_command,
+            commands::ensure_directory,
+            commands::get_parent_location,
             commands::delete_file,
+            commands::restore_from_trash,
             commands::trash_paths,
             commands::undo_trash,
             commands::delete_paths_permanently,
             commands::rename_file,
+            commands::set_hidden,
+            commands::batch_rename,
+            commands::batch_rename_pattern,
+            commands::move_files,
+            commands::organize_directory,
             commands::copy_file,
+            commands::duplicate_file,
+            commands::copy_with_options,
+            commands::copy_tree,
+            commands::cancel_copy_tree,
+            commands::cancel_upload,
             commands::move_file,
             commands::extract_archive,
             commands::extract_archive_entry_to_temp,
+            commands::extract_archive_entries,
+            commands::unlock_archive,
             commands::compress_to_zip,
+            commands::compress_and_replace,
+            commands::create_archive,
             commands::open_path_with,
+            commands::get_applications_for_file,
             commands::get_system_accent_color,
             commands::get_application_icon,
             commands::update_hidden_files_menu,
@@ -134,11 +192,17 @@ pub fn run() {
             commands::eject_drive,
             commands::initialize_thumbnail_service,
             commands::request_thumbnail,
+            commands::request_thumbnails_batch,
+            commands::get_file_preview,
+            commands::read_text_file,
+            commands::diff_files,
             commands::cancel_thumbnail,
+            commands::cancel_thumbnail_batch,
             commands::cancel_all_thumbnails,
             commands::get_thumbnail_cache_stats,
             commands::clear_thumbnail_cache,
             commands::reveal_in_file_browser,
+            commands::open_terminal_here,
             commands::open_path,
             commands::new_window,
             commands::open_folder_size_window,
@@ -174,6 +238,7 @@ pub fn run() {
             commands::render_svg_to_png,
             commands::read_preferences,
             commands::write_preferences,
+            commands::get_remote_concurrency_stats,
             commands::get_dir_prefs,
             commands::set_dir_prefs,
             commands::set_global_prefs,
@@ -186,10 +251,13 @@ pub fn run() {
             commands::stop_all_watchers,
             commands::is_watching_directory,
             commands::get_watched_directories,
+            commands::set_watcher_debounce_ms,
             commands::get_pinned_directories,
             commands::add_pinned_directory,
             commands::remove_pinned_directory,
             commands::reorder_pinned_directories,
+            commands::update_pinned_directory,
+            commands::remap_locations,
             commands::paste_items_to_location,
             commands::clipboard_paste_image_to_location,
             commands::resolve_drop_operation,
@@ -199,6 +267,7 @@ pub fn run() {
             commands::remove_google_account,
             commands::resolve_google_drive_url,
             commands::download_gdrive_file,
+            commands::set_gdrive_export_format,
             commands::fetch_gdrive_url,
             commands::get_downloads_dir,
             commands::get_temp_dir,
@@ -216,8 +285,16 @@ pub fn run() {
             commands::get_sftp_servers,
             commands::add_sftp_server,
             commands::remove_sftp_server,
+            commands::disconnect_sftp_server,
             commands::test_sftp_connection,
+            commands::trust_sftp_host_key,
             commands::download_sftp_file,
+            commands::get_webdav_servers,
+            commands::add_webdav_server,
+            commands::remove_webdav_server,
+            commands::download_webdav_file,
+            commands::get_mtp_devices,
+            commands::download_mtp_file,
             commands::open_sftp_connect_window,
             commands::hide_sftp_connect_window,
             commands::sftp_connect_window_ready,
@@ -235,6 +312,7 @@ pub fn run() {
             clipboard::clipboard_get_contents,
             clipboard::clipboard_paste_files,
             clipboard::clipboard_paste_image,
+            commands::get_media_metadata,
             commands::download_and_copy_to_clipboard,
         ])
         .setup(|app| {
@@ -247,7 +325,26 @@ pub fn run() {
             }
 
             // Initialize the file system watcher
-            fs_watcher::init_watcher(app.handle().clone());
+            fs_watcher::init_watcher(app.handle().clone(), fs_watcher::DEFAULT_DEBOUNCE_MS);
+
+            // Live-update the sidebar when volumes mount/unmount
+            drive_watcher::init_drive_watcher(app.handle().clone());
+
+            // Lets token refresh notify the UI when an account must sign in again
+            locations::gdrive::auth::init_auth_events(app.handle().clone());
+
+            // Lets the recent files listing follow the "Show Hidden Files" toggle
+            locations::recent::init_recent(app.handle().clone());
+
+            match commands::read_preferences() {
+                Ok(json) => {
+                    locations::throttle::configure_from_preferences(&json);
+                    git_status::configure_from_preferences(&json);
+                    locations::archive::configure_from_preferences(&json);
+                    thumbnails::configure_from_preferences(&json);
+                }
+                Err(err) => log::warn!("Failed to load preferences at startup: {err}"),
+            }
 
             if let Err(err) = locations::archive::prune_archive_cache_on_startup() {
                 log::warn!("Failed to prune archive cache on startup: {err}");
@@ -309,8 +406,19 @@ pub fn run() {
             });
 
             app.manage(FolderSizeState::default());
+            app.manage(CopyTreeState::default());
+            app.manage(GitHistoryState::default());
+            app.manage(UploadState::default());
             app.manage(TrashUndoState::default());
             app.manage(DirectoryStreamState::default());
+            app.manage(SearchState::default());
+            app.manage(SortPreferencesState::default());
+
+            // Preferences saved elsewhere are announced; the next listing re-reads them
+            let handle = app.handle().clone();
+            app.listen_any(commands::PREFERENCES_UPDATED_EVENT, move |_| {
+                commands::forget_sort_preferences(&handle.state::<SortPreferencesState>());
+            });
 
             Ok(())
         })
@@ -344,6 +452,11 @@ pub fn run() {
                 let _ = event;
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                drive_watcher::shutdown_drive_watcher();
+            }
+        });
 }
@@ -1,8 +1,11 @@
 use chrono::Utc;
+use once_cell::sync::Lazy;
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 const CACHE_DIR_NAME: &str = "marlin-archives";
@@ -17,6 +20,10 @@ const STALE_LOCK_AGE: Duration = Duration::from_secs(60 * 5);
 /// Tracks the last time we ran cache pruning (epoch seconds)
 static LAST_PRUNE_TIME: AtomicU64 = AtomicU64::new(0);
 
+/// Passwords entered for encrypted archives this session, by `src` and fingerprint so a
+/// rebuilt archive asks again. Never written to disk.
+static PASSWORDS: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
 pub struct CacheLock {
     path: PathBuf,
 }
@@ -83,6 +90,61 @@ fn hash_key(key: &str) -> String {
     hex::encode(hasher.finalize())
 }
 
+/// Identifies one build of a source archive by its size and modification time. Cache
+/// keys embed this so a rebuilt archive never serves entries extracted from the
+/// previous build.
+pub fn source_fingerprint(path: &Path) -> String {
+    let Ok(metadata) = fs::metadata(path) else {
+        return String::new();
+    };
+    fingerprint(metadata.len(), metadata.modified().ok())
+}
+
+/// The [`source_fingerprint`] of a remote archive from its provider metadata, for
+/// sources downloaded to a fresh temp file whose own mtime says nothing.
+pub fn fingerprint(size: u64, modified: Option<SystemTime>) -> String {
+    let mtime = modified
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("{}:{}", size, mtime)
+}
+
+/// Cache key for an extracted entry of `src` at `internal_path`. Entries of encrypted
+/// archives also key on a hash of the password they were decrypted with.
+pub fn entry_cache_key(
+    src: &str,
+    internal_path: &str,
+    fingerprint: &str,
+    password: Option<&str>,
+) -> String {
+    match password {
+        Some(password) => format!(
+            "{src}::{internal_path}::{fingerprint}::{}",
+            hash_key(password)
+        ),
+        None => format!("{src}::{internal_path}::{fingerprint}"),
+    }
+}
+
+fn password_key(src: &str, fingerprint: &str) -> String {
+    format!("{src}::{fingerprint}")
+}
+
+pub fn remember_password(src: &str, fingerprint: &str, password: &str) {
+    if let Ok(mut passwords) = PASSWORDS.lock() {
+        passwords.insert(password_key(src, fingerprint), password.to_string());
+    }
+}
+
+pub fn remembered_password(src: &str, fingerprint: &str) -> Option<String> {
+    PASSWORDS
+        .lock()
+        .ok()?
+        .get(&password_key(src, fingerprint))
+        .cloned()
+}
+
 pub fn cached_path_for_key(key: &str, extension: Option<&str>) -> Result<PathBuf, String> {
     let dir = cache_dir()?;
     let mut filename = hash_key(key);
@@ -1,5 +1,6 @@
 use async_trait::async_trait;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use tauri::async_runtime::spawn_blocking;
 use url::Url;
@@ -16,14 +17,19 @@ use crate::locations::{
 use crate::thumbnails::generators::smb::download_smb_file_sync;
 
 mod cache;
+pub(crate) mod names;
 mod reader;
 
+pub use reader::{extract_entries, open_zip_entry, rar_first_volume, EntrySelection};
 use reader::{normalize_internal_path, ArchiveEntry};
 
 #[derive(Debug, Clone)]
 struct ArchiveLocation {
     src: String,
     path: String,
+    /// Reached by opening the archive at its single root folder, so going up from
+    /// that folder leaves the archive rather than landing back in it
+    flattened: bool,
 }
 
 fn parse_archive_uri(raw: &str) -> Result<ArchiveLocation, String> {
@@ -34,12 +40,15 @@ fn parse_archive_uri(raw: &str) -> Result<ArchiveLocation, String> {
 
     let mut src: Option<String> = None;
     let mut path: Option<String> = None;
+    let mut flattened = false;
 
     for (key, value) in url.query_pairs() {
         if key == "src" {
             src = Some(value.to_string());
         } else if key == "path" {
             path = Some(value.to_string());
+        } else if key == "flat" {
+            flattened = value == "1";
         }
     }
 
@@ -47,15 +56,29 @@ fn parse_archive_uri(raw: &str) -> Result<ArchiveLocation, String> {
     let path_raw = path.unwrap_or_else(|| "/".to_string());
     let path = normalize_internal_path(&path_raw)?;
 
-    Ok(ArchiveLocation { src, path })
+    Ok(ArchiveLocation {
+        src,
+        path,
+        flattened,
+    })
 }
 
-fn build_archive_uri(src: &str, internal_path: &str) -> String {
+pub(crate) fn build_archive_uri(src: &str, internal_path: &str) -> String {
     let encoded_src = encode(src);
     let encoded_path = encode(internal_path);
     format!("archive:///?src={encoded_src}&path={encoded_path}")
 }
 
+/// Like `build_archive_uri`, marking folders browsed from a flattened root.
+fn build_folder_uri(src: &str, internal_path: &str, flattened: bool) -> String {
+    let uri = build_archive_uri(src, internal_path);
+    if flattened {
+        format!("{uri}&flat=1")
+    } else {
+        uri
+    }
+}
+
 fn extension_for_name(name: &str) -> Option<String> {
     Path::new(name)
         .extension()
@@ -84,15 +107,20 @@ fn archive_extension_from_path(path: &str) -> Option<String> {
         .map(|s| s.to_string())
 }
 
-fn entry_to_file_item(entry: ArchiveEntry, src: &str) -> FileItem {
-    let extension = if entry.is_directory {
-        None
+/// Folders listed from a flattened root keep the marker; files don't need it and
+/// nested archives are keyed by their URI, so they're left unmarked.
+fn entry_to_file_item(entry: ArchiveEntry, src: &str, flattened: bool) -> FileItem {
+    let (extension, path) = if entry.is_directory {
+        (None, build_folder_uri(src, &entry.internal_path, flattened))
     } else {
-        extension_for_name(&entry.name)
+        (
+            extension_for_name(&entry.name),
+            build_archive_uri(src, &entry.internal_path),
+        )
     };
     FileItem {
         name: entry.name.clone(),
-        path: build_archive_uri(src, &entry.internal_path),
+        path,
         size: entry.size,
         modified: entry.modified,
         is_directory: entry.is_directory,
@@ -106,10 +134,15 @@ fn entry_to_file_item(entry: ArchiveEntry, src: &str) -> FileItem {
         remote_id: None,
         thumbnail_url: None,
         download_url: None,
+        tags: None,
+        is_git_submodule: false,
+        lfs_size: None,
+        id: String::new(),
     }
 }
 
-async fn resolve_non_archive_source(src: &str) -> Result<PathBuf, String> {
+/// Download or locate the outermost archive. Also returns its fingerprint for cache keys.
+async fn resolve_non_archive_source(src: &str) -> Result<(PathBuf, String), String> {
     let input = LocationInput::Raw(src.to_string());
     let location = input
         .into_location()
@@ -118,18 +151,26 @@ async fn resolve_non_archive_source(src: &str) -> Result<PathBuf, String> {
     match location.scheme() {
         "file" => {
             let expanded = expand_path(&location.to_path_string())?;
-            Ok(expanded)
+            let fingerprint = cache::source_fingerprint(&expanded);
+            Ok((expanded, fingerprint))
         }
         "smb" => {
             #[cfg(not(target_os = "windows"))]
             {
+                // Every SMB download is a fresh temp file, so the server's size and mtime
+                // identify the build
+                let (provider, _) =
+                    crate::locations::resolve_location(LocationInput::Raw(src.to_string()))?;
+                let metadata = provider.get_file_metadata(&location).await?;
+                let fingerprint = cache::fingerprint(metadata.size, Some(metadata.modified.into()));
+
                 let smb_path = location.raw().to_string();
                 let temp_path = spawn_blocking(move || {
                     download_smb_file_sync(&smb_path).map(|p| p.to_string_lossy().to_string())
                 })
                 .await
                 .map_err(|e| format!("Task join error: {e}"))??;
-                Ok(PathBuf::from(temp_path))
+                Ok((PathBuf::from(temp_path), fingerprint))
             }
             #[cfg(target_os = "windows")]
             {
@@ -147,7 +188,8 @@ async fn resolve_non_archive_source(src: &str) -> Result<PathBuf, String> {
                 .and_then(|s| s.to_str())
                 .unwrap_or("archive");
             let temp_path = download_file_to_temp(email, &file_id, file_name).await?;
-            Ok(PathBuf::from(temp_path))
+            let fingerprint = cache::source_fingerprint(Path::new(&temp_path));
+            Ok((PathBuf::from(temp_path), fingerprint))
         }
         scheme => Err(format!("Unsupported archive source scheme: {scheme}")),
     }
@@ -156,7 +198,29 @@ async fn resolve_non_archive_source(src: &str) -> Result<PathBuf, String> {
 /// Maximum depth of nested archives to prevent DoS via deeply nested archives
 const MAX_ARCHIVE_NESTING: usize = 10;
 
-async fn resolve_archive_source(src: &str) -> Result<PathBuf, String> {
+/// Open an archive whose root holds a single folder at that folder instead. Off by
+/// default; set with `flattenArchiveRoot` in the global preferences.
+static FLATTEN_SINGLE_ROOT: AtomicBool = AtomicBool::new(false);
+
+/// Read `globalPreferences.flattenArchiveRoot` from a preferences JSON document.
+/// Invalid JSON or a missing key turns flattening off.
+pub fn configure_from_preferences(json: &str) {
+    let flatten = serde_json::from_str::<serde_json::Value>(json)
+        .ok()
+        .and_then(|value| {
+            value
+                .get("globalPreferences")?
+                .get("flattenArchiveRoot")?
+                .as_bool()
+        })
+        .unwrap_or(false);
+    FLATTEN_SINGLE_ROOT.store(flatten, Ordering::Relaxed);
+}
+
+/// Resolve `src` to a local archive file, extracting nested archives through the cache.
+/// The returned fingerprint is that of the outermost source, which determines every
+/// nested level's contents.
+async fn resolve_archive_source(src: &str) -> Result<(PathBuf, String), String> {
     let mut current_src = src.to_string();
     let mut nested_stack: Vec<ArchiveLocation> = Vec::new();
 
@@ -173,11 +237,13 @@ async fn resolve_archive_source(src: &str) -> Result<PathBuf, String> {
         }
     }
 
-    let mut resolved_path = resolve_non_archive_source(&current_src).await?;
+    let (mut resolved_path, fingerprint) = resolve_non_archive_source(&current_src).await?;
 
     while let Some(nested) = nested_stack.pop() {
         let extension = archive_extension_from_path(&nested.path);
-        let cache_key = format!("{}::{}", nested.src, nested.path);
+        let password = cache::remembered_password(&nested.src, &fingerprint);
+        let cache_key =
+            cache::entry_cache_key(&nested.src, &nested.path, &fingerprint, password.as_deref());
         let cache_path = cache::cached_path_for_key(&cache_key, extension.as_deref())?;
 
         if cache::is_cache_fresh(&cache_path) {
@@ -191,7 +257,12 @@ async fn resolve_archive_source(src: &str) -> Result<PathBuf, String> {
         let cache_clone = cache_path.clone();
 
         let extracted = spawn_blocking(move || {
-            reader::extract_entry_to_path(&archive_clone, &path_clone, &cache_clone)
+            reader::extract_entry_to_path(
+                &archive_clone,
+                &path_clone,
+                &cache_clone,
+                password.as_deref(),
+            )
         })
         .await
         .map_err(|e| format!("Task join error: {e}"))??;
@@ -200,14 +271,20 @@ async fn resolve_archive_source(src: &str) -> Result<PathBuf, String> {
         resolved_path = extracted;
     }
 
-    Ok(resolved_path)
+    Ok((resolved_path, fingerprint))
 }
 
 pub async fn extract_archive_entry_to_temp(archive_uri: &str) -> Result<PathBuf, String> {
     let archive_location = parse_archive_uri(archive_uri)?;
-    let archive_path = resolve_archive_source(&archive_location.src).await?;
+    let (archive_path, fingerprint) = resolve_archive_source(&archive_location.src).await?;
     let extension = archive_extension_from_path(&archive_location.path);
-    let cache_key = format!("{}::{}", archive_location.src, archive_location.path);
+    let password = cache::remembered_password(&archive_location.src, &fingerprint);
+    let cache_key = cache::entry_cache_key(
+        &archive_location.src,
+        &archive_location.path,
+        &fingerprint,
+        password.as_deref(),
+    );
     let cache_path = cache::cached_path_for_key(&cache_key, extension.as_deref())?;
 
     if cache::is_cache_fresh(&cache_path) {
@@ -220,7 +297,12 @@ pub async fn extract_archive_entry_to_temp(archive_uri: &str) -> Result<PathBuf,
     let cache_clone = cache_path.clone();
 
     let extracted = spawn_blocking(move || {
-        reader::extract_entry_to_path(&archive_clone, &path_clone, &cache_clone)
+        reader::extract_entry_to_path(
+            &archive_clone,
+            &path_clone,
+            &cache_clone,
+            password.as_deref(),
+        )
     })
     .await
     .map_err(|e| format!("Task join error: {e}"))??;
@@ -229,6 +311,50 @@ pub async fn extract_archive_entry_to_temp(archive_uri: &str) -> Result<PathBuf,
     Ok(extracted)
 }
 
+/// Check `password` against the archive behind `archive_uri` and remember it for the
+/// rest of the session, so browsing and opening its entries doesn't ask again.
+pub async fn unlock_archive(archive_uri: &str, password: &str) -> Result<(), String> {
+    let archive_location = parse_archive_uri(archive_uri)?;
+    let (archive_path, fingerprint) = resolve_archive_source(&archive_location.src).await?;
+    let password_for_task = password.to_string();
+    spawn_blocking(move || reader::check_password(&archive_path, Some(&password_for_task)))
+        .await
+        .map_err(|e| format!("Task join error: {e}"))??;
+
+    cache::remember_password(&archive_location.src, &fingerprint, password);
+    Ok(())
+}
+
+/// Parent inside the archive, or the folder containing the archive when at its root.
+pub(super) fn parent_location(location: &Location) -> Result<Option<LocationSummary>, String> {
+    let archive_location = parse_archive_uri(location.raw())?;
+    if archive_location.path == "/" {
+        let src_location = LocationInput::Raw(archive_location.src).into_location()?;
+        return super::parent_location(&src_location);
+    }
+
+    let parent_path = match archive_location.path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(idx) => archive_location.path[..idx].to_string(),
+    };
+    if parent_path == "/" && archive_location.flattened {
+        let src_location = LocationInput::Raw(archive_location.src).into_location()?;
+        return super::parent_location(&src_location);
+    }
+    let raw = build_folder_uri(
+        &archive_location.src,
+        &parent_path,
+        archive_location.flattened,
+    );
+    Ok(Some(LocationSummary {
+        raw: raw.clone(),
+        scheme: "archive".to_string(),
+        authority: None,
+        path: parent_path,
+        display_path: raw,
+    }))
+}
+
 #[derive(Default)]
 pub struct ArchiveProvider;
 
@@ -248,26 +374,45 @@ impl LocationProvider for ArchiveProvider {
     ) -> Result<ProviderDirectoryEntries, String> {
         let archive_location = parse_archive_uri(location.raw())?;
         let src = archive_location.src.clone();
-        let archive_path = resolve_archive_source(&src).await?;
+        let (archive_path, fingerprint) = resolve_archive_source(&src).await?;
+        let password = cache::remembered_password(&src, &fingerprint);
         let internal_path = archive_location.path.clone();
-        let internal_path_for_task = internal_path.clone();
+        let flatten = internal_path == "/" && FLATTEN_SINGLE_ROOT.load(Ordering::Relaxed);
+
+        let (listed_path, entries) = spawn_blocking(move || {
+            let entries =
+                reader::list_directory(&archive_path, &internal_path, password.as_deref())?;
+            if flatten {
+                if let Some(root) = reader::single_root_directory(&entries) {
+                    let root_path = root.internal_path.clone();
+                    let inner =
+                        reader::list_directory(&archive_path, &root_path, password.as_deref())?;
+                    return Ok((root_path, inner));
+                }
+            }
+            Ok::<_, String>((internal_path, entries))
+        })
+        .await
+        .map_err(|e| format!("Task join error: {e}"))??;
 
-        let entries =
-            spawn_blocking(move || reader::list_directory(&archive_path, &internal_path_for_task))
-                .await
-                .map_err(|e| format!("Task join error: {e}"))??;
+        let flattened = archive_location.flattened || listed_path != archive_location.path;
+        let raw = if listed_path == archive_location.path {
+            location.raw().to_string()
+        } else {
+            build_folder_uri(&src, &listed_path, true)
+        };
 
         let file_items = entries
             .into_iter()
-            .map(|entry| entry_to_file_item(entry, &src))
+            .map(|entry| entry_to_file_item(entry, &src, flattened))
             .collect();
 
         let summary = LocationSummary {
-            raw: location.raw().to_string(),
+            raw: raw.clone(),
             scheme: "archive".to_string(),
             authority: None,
-            path: internal_path,
-            display_path: location.raw().to_string(),
+            path: listed_path,
+            display_path: raw,
         };
 
         Ok(ProviderDirectoryEntries {
@@ -279,7 +424,7 @@ impl LocationProvider for ArchiveProvider {
     async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
         let archive_location = parse_archive_uri(location.raw())?;
         let src = archive_location.src.clone();
-        let archive_path = resolve_archive_source(&src).await?;
+        let (archive_path, _) = resolve_archive_source(&src).await?;
         let internal_path = archive_location.path.clone();
         let internal_path_for_task = internal_path.clone();
 
@@ -289,7 +434,7 @@ impl LocationProvider for ArchiveProvider {
         .await
         .map_err(|e| format!("Task join error: {e}"))??;
 
-        let mut file_item = entry_to_file_item(metadata, &src);
+        let mut file_item = entry_to_file_item(metadata, &src, archive_location.flattened);
         if internal_path == "/" {
             file_item.path = location.raw().to_string();
         }
@@ -316,3 +461,167 @@ impl LocationProvider for ArchiveProvider {
 pub fn prune_archive_cache_on_startup() -> Result<(), String> {
     cache::prune_cache_on_startup()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+    use zip::write::SimpleFileOptions;
+
+    fn write_zip(path: &Path, files: &[(&str, &str)], mtime: i64) {
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, contents) in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+        filetime::set_file_mtime(path, filetime::FileTime::from_unix_time(mtime, 0)).unwrap();
+    }
+
+    #[test]
+    fn extracts_only_selected_entries() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("bundle.zip");
+        write_zip(
+            &zip_path,
+            &[
+                ("docs/readme.txt", "read me"),
+                ("docs/guide/intro.txt", "intro"),
+                ("src/main.rs", "fn main() {}"),
+                ("notes.txt", "notes"),
+            ],
+            1_700_000_000,
+        );
+        let out = dir.path().join("out");
+        std::fs::create_dir(&out).unwrap();
+
+        let mut extracted = Vec::new();
+        extract_entries(
+            &zip_path,
+            &[
+                EntrySelection {
+                    internal_path: "/docs".to_string(),
+                    output: out.join("docs"),
+                },
+                EntrySelection {
+                    internal_path: "/src/main.rs".to_string(),
+                    output: out.join("main.rs"),
+                },
+            ],
+            None,
+            |name| extracted.push(name.to_string()),
+        )
+        .unwrap();
+
+        extracted.sort();
+        assert_eq!(
+            extracted,
+            ["docs/guide/intro.txt", "docs/readme.txt", "src/main.rs"]
+        );
+        assert_eq!(
+            std::fs::read_to_string(out.join("docs/guide/intro.txt")).unwrap(),
+            "intro"
+        );
+        assert_eq!(
+            std::fs::read_to_string(out.join("main.rs")).unwrap(),
+            "fn main() {}"
+        );
+        assert!(!out.join("notes.txt").exists());
+
+        let missing = EntrySelection {
+            internal_path: "/nope.txt".to_string(),
+            output: out.join("nope.txt"),
+        };
+        assert!(extract_entries(&zip_path, &[missing], None, |_| {}).is_err());
+    }
+
+    #[test]
+    fn later_rar_volumes_resolve_to_the_first() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("movie.part01.rar");
+        let second = dir.path().join("movie.part02.rar");
+        let single = dir.path().join("photos.rar");
+        for path in [&first, &second, &single] {
+            std::fs::write(path, b"").unwrap();
+        }
+
+        assert_eq!(rar_first_volume(&second).unwrap(), first);
+        assert_eq!(rar_first_volume(&first).unwrap(), first);
+        assert_eq!(rar_first_volume(&single).unwrap(), single);
+
+        std::fs::remove_file(&first).unwrap();
+        assert!(rar_first_volume(&second).is_err());
+    }
+
+    #[test]
+    fn encrypted_zip_needs_the_right_password() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("secret.zip");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        let options =
+            SimpleFileOptions::default().with_aes_encryption(zip::AesMode::Aes256, "hunter2");
+        writer.start_file("plans.txt", options).unwrap();
+        writer.write_all(b"top secret").unwrap();
+        writer.finish().unwrap();
+
+        let err = reader::list_directory(&zip_path, "/", None).unwrap_err();
+        assert!(err.starts_with("[ARCHIVE_PASSWORD_REQUIRED]"));
+        let err = reader::check_password(&zip_path, Some("wrong")).unwrap_err();
+        assert!(err.starts_with("[ARCHIVE_PASSWORD_INCORRECT]"));
+
+        let entries = reader::list_directory(&zip_path, "/", Some("hunter2")).unwrap();
+        assert_eq!(entries.len(), 1);
+        let out = dir.path().join("plans.txt");
+        reader::extract_entry_to_path(&zip_path, "/plans.txt", &out, Some("hunter2")).unwrap();
+        assert_eq!(std::fs::read_to_string(out).unwrap(), "top secret");
+    }
+
+    #[tokio::test]
+    async fn rebuilt_source_archive_invalidates_cache() {
+        let dir = tempdir().unwrap();
+        let zip_path = dir.path().join("bundle.zip");
+        let src = zip_path.to_string_lossy().to_string();
+        let root = Location::parse(&build_archive_uri(&src, "/")).unwrap();
+        let entry_uri = build_archive_uri(&src, "/a.txt");
+
+        write_zip(&zip_path, &[("a.txt", "one")], 1_700_000_000);
+        let listing = ArchiveProvider.read_directory(&root).await.unwrap();
+        assert_eq!(listing.entries.len(), 1);
+        let extracted = extract_archive_entry_to_temp(&entry_uri).await.unwrap();
+        assert_eq!(std::fs::read_to_string(extracted).unwrap(), "one");
+
+        write_zip(
+            &zip_path,
+            &[("a.txt", "one, rebuilt"), ("b.txt", "two")],
+            1_700_000_100,
+        );
+        let listing = ArchiveProvider.read_directory(&root).await.unwrap();
+        let mut names: Vec<_> = listing.entries.iter().map(|e| e.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["a.txt", "b.txt"]);
+        let extracted = extract_archive_entry_to_temp(&entry_uri).await.unwrap();
+        assert_eq!(std::fs::read_to_string(extracted).unwrap(), "one, rebuilt");
+    }
+
+    #[test]
+    fn flattened_folders_lead_out_of_the_archive() {
+        let src = "/Users/me/project.zip";
+        let root_folder = Location::parse(&build_folder_uri(src, "/project", true)).unwrap();
+        let parent = parent_location(&root_folder).unwrap().unwrap();
+        assert_eq!(parent.scheme, "file");
+        assert_eq!(parent.path, "/Users/me");
+
+        // Deeper folders keep the marker on the way back up
+        let inner = Location::parse(&build_folder_uri(src, "/project/src", true)).unwrap();
+        let parent = parent_location(&inner).unwrap().unwrap();
+        assert_eq!(parent.raw, build_folder_uri(src, "/project", true));
+
+        // The same folder reached without flattening goes up to the archive root
+        let unflattened = Location::parse(&build_archive_uri(src, "/project")).unwrap();
+        let parent = parent_location(&unflattened).unwrap().unwrap();
+        assert_eq!(parent.raw, build_archive_uri(src, "/"));
+    }
+}
@@ -0,0 +1,253 @@
+//! Zip entry names written without the UTF-8 flag (general purpose bit 11).
+//!
+//! The zip crate decodes such names as CP437, which is right for old DOS/Windows
+//! archives but turns Japanese, Chinese and Korean names into mojibake. When the flag
+//! is unset we look at the raw bytes again: valid UTF-8 is taken as-is (many tools
+//! forget the flag), plausible Shift-JIS / GB2312 / EUC-KR is decoded with that code
+//! page, and everything else keeps the CP437 reading.
+
+use encoding_rs::{Encoding, EUC_KR, GBK, SHIFT_JIS};
+use std::path::PathBuf;
+
+/// Best-effort display name for a zip entry, given the crate's decoded `name` and
+/// the raw bytes from the header.
+pub fn zip_entry_name(name: &str, raw: &[u8]) -> String {
+    // With the flag set (or a Unicode path extra field) the crate already decoded the
+    // raw bytes as UTF-8; a CP437 reading of non-ASCII bytes never matches that.
+    if raw.is_ascii() || std::str::from_utf8(raw) == Ok(name) {
+        return name.to_string();
+    }
+    if let Ok(utf8) = std::str::from_utf8(raw) {
+        return utf8.to_string();
+    }
+    decode_cjk(raw).unwrap_or_else(|| name.to_string())
+}
+
+fn decode_with(encoding: &'static Encoding, raw: &[u8]) -> Option<String> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(raw)
+        .map(|s| s.into_owned())
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30FF}' | '\u{FF66}'..='\u{FF9F}')
+}
+
+fn is_han(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}')
+}
+
+fn is_hangul(c: char) -> bool {
+    matches!(c, '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}')
+}
+
+/// CJK punctuation and full-width forms that legitimately appear in these names.
+fn is_cjk_symbol(c: char) -> bool {
+    matches!(c, '\u{3000}'..='\u{303F}' | '\u{FF00}'..='\u{FFEF}')
+}
+
+/// GB2312 and EUC-KR both encode every non-ASCII character as two bytes in A1-FE.
+/// Requiring that keeps CP437 names with accented letters from being misread.
+fn is_euc_shaped(raw: &[u8]) -> bool {
+    let mut bytes = raw.iter();
+    while let Some(&b) = bytes.next() {
+        if b < 0x80 {
+            continue;
+        }
+        let in_range = |b: u8| (0xA1..=0xFE).contains(&b);
+        match bytes.next() {
+            Some(&trail) if in_range(b) && in_range(trail) => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+fn decode_cjk(raw: &[u8]) -> Option<String> {
+    let all_non_ascii = |s: &str, ok: fn(char) -> bool| {
+        s.chars()
+            .filter(|c| !c.is_ascii())
+            .all(|c| ok(c) || is_cjk_symbol(c))
+    };
+
+    let sjis = decode_with(SHIFT_JIS, raw).filter(|s| {
+        s.chars().any(|c| is_kana(c) || is_han(c)) && all_non_ascii(s, |c| is_kana(c) || is_han(c))
+    });
+    let euc_shaped = is_euc_shaped(raw);
+
+    // Full-width kana is a strong Shift-JIS signal. Half-width kana isn't: those
+    // single bytes overlap the GBK/EUC-KR lead range.
+    if let Some(sjis) = &sjis {
+        if sjis.chars().any(|c| matches!(c, '\u{3040}'..='\u{30FF}')) {
+            return Some(sjis.clone());
+        }
+    }
+
+    if euc_shaped {
+        // The two overlap heavily; Chinese archives are the more common case.
+        if let Some(gbk) = decode_with(GBK, raw) {
+            if gbk.chars().any(is_han) && all_non_ascii(&gbk, is_han) {
+                return Some(gbk);
+            }
+        }
+        if let Some(korean) = decode_with(EUC_KR, raw) {
+            if korean.chars().any(is_hangul)
+                && all_non_ascii(&korean, |c| is_hangul(c) || is_han(c))
+            {
+                return Some(korean);
+            }
+        }
+    }
+
+    // Weaker Shift-JIS readings. A single kanji is too easily a CP437 accent followed
+    // by an ASCII letter ("Über"), so ask for at least two.
+    sjis.filter(|s| {
+        s.chars().filter(|c| is_han(*c)).count() >= 2 || (!euc_shaped && s.chars().any(is_kana))
+    })
+}
+
+/// Make one path component safe to create on disk.
+fn sanitize_component(component: &str) -> String {
+    component
+        .chars()
+        .map(|c| match c {
+            c if c.is_control() => '_',
+            #[cfg(target_os = "windows")]
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Turn a decoded entry name into a relative path that stays inside the extraction
+/// directory, like `ZipFile::enclosed_name` but for names we decoded ourselves.
+/// Returns `None` for absolute paths, drive prefixes and `..` that climb out.
+pub fn enclosed_path(name: &str) -> Option<PathBuf> {
+    let name = name.split('\0').next().unwrap_or_default();
+    let normalized = name.replace('\\', "/");
+    if normalized.starts_with('/') {
+        return None;
+    }
+    let bytes = normalized.as_bytes();
+    if bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic() {
+        return None;
+    }
+
+    let mut parts: Vec<String> = Vec::new();
+    for part in normalized.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            other => parts.push(sanitize_component(other)),
+        }
+    }
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    /// A one-entry stored zip whose name is written as raw bytes with bit 11 unset,
+    /// the way old Windows tools produce them.
+    fn legacy_zip(name_raw: &[u8], contents: &[u8]) -> Vec<u8> {
+        let crc = crc32fast::hash(contents);
+        let mut out = Vec::new();
+        let header = |out: &mut Vec<u8>, central: bool| {
+            let le16 = |out: &mut Vec<u8>, v: u16| out.extend_from_slice(&v.to_le_bytes());
+            let le32 = |out: &mut Vec<u8>, v: u32| out.extend_from_slice(&v.to_le_bytes());
+            le32(out, if central { 0x0201_4b50 } else { 0x0403_4b50 });
+            if central {
+                le16(out, 20); // version made by
+            }
+            le16(out, 20); // version needed
+            le16(out, 0); // flags: no UTF-8 bit
+            le16(out, 0); // stored
+            le16(out, 0); // mod time
+            le16(out, 0x21); // mod date (1980-01-01)
+            le32(out, crc);
+            le32(out, contents.len() as u32);
+            le32(out, contents.len() as u32);
+            le16(out, name_raw.len() as u16);
+            le16(out, 0); // extra length
+            if central {
+                le16(out, 0); // comment length
+                le16(out, 0); // disk number
+                le16(out, 0); // internal attributes
+                le32(out, 0); // external attributes
+                le32(out, 0); // local header offset
+            }
+            out.extend_from_slice(name_raw);
+        };
+
+        header(&mut out, false);
+        out.extend_from_slice(contents);
+        let central_start = out.len();
+        header(&mut out, true);
+        let central_len = out.len() - central_start;
+
+        out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]); // disk numbers
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&1u16.to_le_bytes());
+        out.extend_from_slice(&(central_len as u32).to_le_bytes());
+        out.extend_from_slice(&(central_start as u32).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    #[test]
+    fn decodes_legacy_code_pages() {
+        // CP437: 0x82 is 'é'
+        assert_eq!(zip_entry_name("café.txt", b"caf\x82.txt"), "café.txt");
+        // Shift-JIS "テスト.txt"
+        let sjis = b"\x83\x65\x83\x58\x83\x67.txt";
+        assert_eq!(zip_entry_name("garbled", sjis), "テスト.txt");
+        // GBK "中文.txt"
+        assert_eq!(
+            zip_entry_name("garbled", b"\xd6\xd0\xce\xc4.txt"),
+            "中文.txt"
+        );
+        // Unflagged but valid UTF-8
+        assert_eq!(
+            zip_entry_name("garbled", "résumé.pdf".as_bytes()),
+            "résumé.pdf"
+        );
+    }
+
+    #[test]
+    fn enclosed_path_rejects_escapes_and_sanitizes() {
+        assert_eq!(
+            enclosed_path("a/./b/../c.txt"),
+            Some(PathBuf::from("a/c.txt"))
+        );
+        assert_eq!(enclosed_path("../evil"), None);
+        assert_eq!(enclosed_path("/etc/passwd"), None);
+        assert_eq!(enclosed_path("C:\\evil"), None);
+        assert_eq!(
+            enclosed_path("dir\\bell\u{7}.txt"),
+            Some(PathBuf::from("dir/bell_.txt"))
+        );
+    }
+
+    #[test]
+    fn lists_cp437_archive_with_decoded_names() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("legacy.zip");
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(&legacy_zip(b"R\x82sum\x82.txt", b"hello"))
+            .unwrap();
+        drop(file);
+
+        let entries = super::super::reader::list_directory(&path, "/", None).unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["Résumé.txt"]);
+    }
+}
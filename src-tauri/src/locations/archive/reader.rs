@@ -1,7 +1,7 @@
 use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::Read;
+use std::io::{Read, Seek};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
@@ -12,12 +12,21 @@ use tar::Archive as TarArchive;
 use unrar::Archive as RarArchive;
 use uuid::Uuid;
 use xz2::read::XzDecoder;
+use zip::read::ZipFile;
+use zip::result::ZipError;
 use zip::ZipArchive;
 use zstd::stream::read::Decoder as ZstdDecoder;
 
+use super::names::zip_entry_name;
+
 const MAX_ENTRIES: usize = 100_000;
 const MAX_TOTAL_SIZE: u64 = 2 * 1024 * 1024 * 1024;
 const MAX_SINGLE_FILE: u64 = 500 * 1024 * 1024;
+/// Error for an encrypted zip opened without a password; the UI asks for one.
+pub const PASSWORD_REQUIRED_ERROR: &str =
+    "[ARCHIVE_PASSWORD_REQUIRED] This archive is password protected";
+pub const PASSWORD_INCORRECT_ERROR: &str =
+    "[ARCHIVE_PASSWORD_INCORRECT] The password for this archive is incorrect";
 /// Maximum number of archives to cache directory structure for
 const STRUCTURE_CACHE_MAX: usize = 50;
 
@@ -25,6 +34,8 @@ const STRUCTURE_CACHE_MAX: usize = 50;
 struct CachedArchiveStructure {
     /// Archive file modification time when cached
     mtime: SystemTime,
+    /// Archive file size when cached; catches rebuilds within the mtime resolution
+    size: u64,
     /// All entries in the archive (flattened), wrapped in Arc for cheap sharing
     entries: Arc<Vec<CachedEntry>>,
 }
@@ -48,10 +59,11 @@ fn get_structure_cache() -> &'static Mutex<HashMap<PathBuf, CachedArchiveStructu
 /// Get cached entries if valid, or None if cache miss/stale
 /// Returns Arc to avoid cloning the entire entry list
 fn get_cached_entries(archive_path: &Path) -> Option<Arc<Vec<CachedEntry>>> {
-    let current_mtime = fs::metadata(archive_path).ok()?.modified().ok()?;
+    let metadata = fs::metadata(archive_path).ok()?;
+    let current_mtime = metadata.modified().ok()?;
     let cache = get_structure_cache().lock().ok()?;
     let cached = cache.get(archive_path)?;
-    if cached.mtime == current_mtime {
+    if cached.mtime == current_mtime && cached.size == metadata.len() {
         Some(Arc::clone(&cached.entries))
     } else {
         None
@@ -59,7 +71,7 @@ fn get_cached_entries(archive_path: &Path) -> Option<Arc<Vec<CachedEntry>>> {
 }
 
 /// Store structure in cache, evicting oldest if needed
-fn cache_structure(archive_path: &Path, mtime: SystemTime, entries: Vec<CachedEntry>) {
+fn cache_structure(archive_path: &Path, mtime: SystemTime, size: u64, entries: Vec<CachedEntry>) {
     if let Ok(mut cache) = get_structure_cache().lock() {
         // Simple eviction: if at capacity, remove an entry
         if cache.len() >= STRUCTURE_CACHE_MAX && !cache.contains_key(archive_path) {
@@ -71,6 +83,7 @@ fn cache_structure(archive_path: &Path, mtime: SystemTime, entries: Vec<CachedEn
             archive_path.to_path_buf(),
             CachedArchiveStructure {
                 mtime,
+                size,
                 entries: Arc::new(entries),
             },
         );
@@ -139,6 +152,24 @@ pub fn determine_archive_format(path: &Path) -> Result<ArchiveFormat, String> {
         .ok_or_else(|| format!("Unsupported archive format: {}", file_name))
 }
 
+/// The volume a multi-volume RAR (`name.part1.rar`, `name.part01.rar`, ...) has to be
+/// opened from. Single-volume archives and first volumes are returned unchanged.
+pub fn rar_first_volume(archive_path: &Path) -> Result<PathBuf, String> {
+    let archive = RarArchive::new(archive_path);
+    if !archive.is_multipart() {
+        return Ok(archive_path.to_path_buf());
+    }
+    match archive.first_part_option() {
+        Some(first) if first == archive_path => Ok(first),
+        Some(first) if first.exists() => Ok(first),
+        Some(first) => Err(format!(
+            "The first volume of this multi-volume RAR archive is missing: {}",
+            first.display()
+        )),
+        None => Ok(archive_path.to_path_buf()),
+    }
+}
+
 fn normalize_entry_path(raw: &str) -> Result<String, String> {
     if raw.contains('\0') {
         return Err("Archive entry contains NUL byte".to_string());
@@ -235,6 +266,58 @@ fn mtime_to_datetime(secs: u64) -> DateTime<Utc> {
         .unwrap_or_else(Utc::now)
 }
 
+fn zip_entry_error(err: ZipError) -> String {
+    match err {
+        ZipError::UnsupportedArchive(msg) if msg == ZipError::PASSWORD_REQUIRED => {
+            PASSWORD_REQUIRED_ERROR.to_string()
+        }
+        ZipError::InvalidPassword => PASSWORD_INCORRECT_ERROR.to_string(),
+        err => format!("Failed to read zip entry: {err}"),
+    }
+}
+
+/// Open zip entry `index` for reading, decrypting it with `password` if it's encrypted.
+pub fn open_zip_entry<'a, R: Read + Seek>(
+    archive: &'a mut ZipArchive<R>,
+    index: usize,
+    password: Option<&str>,
+) -> Result<ZipFile<'a, R>, String> {
+    match password {
+        Some(password) => archive.by_index_decrypt(index, password.as_bytes()),
+        None => archive.by_index(index),
+    }
+    .map_err(zip_entry_error)
+}
+
+/// Make sure `password` opens the archive's first encrypted entry, if it has any.
+/// Only zips are checked; other formats don't support encryption here.
+pub fn check_password(archive_path: &Path, password: Option<&str>) -> Result<(), String> {
+    if !matches!(determine_archive_format(archive_path)?, ArchiveFormat::Zip) {
+        return Ok(());
+    }
+    let file = File::open(archive_path)
+        .map_err(|e| format!("Failed to open archive {}: {e}", archive_path.display()))?;
+    let mut archive =
+        ZipArchive::new(file).map_err(|e| format!("Failed to read zip archive: {e}"))?;
+    check_zip_password(&mut archive, password)
+}
+
+fn check_zip_password<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> Result<(), String> {
+    for index in 0..archive.len() {
+        let encrypted = archive
+            .by_index_raw(index)
+            .map(|entry| entry.encrypted())
+            .map_err(zip_entry_error)?;
+        if encrypted {
+            return open_zip_entry(archive, index, password).map(|_| ());
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct ArchiveEntry {
     pub name: String,
@@ -313,9 +396,19 @@ fn build_entries(parent_path: &str, map: HashMap<String, EntryInfo>) -> Vec<Arch
     entries
 }
 
+/// The only entry of a listing when it is a directory, as in archives that wrap
+/// everything in one top-level folder like `project-1.0/`.
+pub fn single_root_directory(entries: &[ArchiveEntry]) -> Option<&ArchiveEntry> {
+    match entries {
+        [only] if only.is_directory => Some(only),
+        _ => None,
+    }
+}
+
 pub fn list_directory(
     archive_path: &Path,
     internal_path: &str,
+    password: Option<&str>,
 ) -> Result<Vec<ArchiveEntry>, String> {
     // Check cache first - avoids O(n) scan for repeated listings
     // Uses Arc for cheap cloning of the entry list
@@ -343,10 +436,11 @@ pub fn list_directory(
             if archive.len() > MAX_ENTRIES {
                 return Err("Archive contains too many entries to list".to_string());
             }
+            check_zip_password(&mut archive, password)?;
 
             for i in 0..archive.len() {
                 let file = archive
-                    .by_index(i)
+                    .by_index_raw(i)
                     .map_err(|e| format!("Failed to read zip entry: {e}"))?;
 
                 seen_entries += 1;
@@ -354,8 +448,8 @@ pub fn list_directory(
                     return Err("Archive contains too many entries to list".to_string());
                 }
 
-                let entry_name = file.name();
-                let normalized = match normalize_entry_path(entry_name) {
+                let entry_name = zip_entry_name(file.name(), file.name_raw());
+                let normalized = match normalize_entry_path(&entry_name) {
                     Ok(value) => value,
                     Err(_) => continue,
                 };
@@ -398,7 +492,7 @@ pub fn list_directory(
             }
         }
         ArchiveFormat::Rar => {
-            let archive = RarArchive::new(archive_path)
+            let archive = RarArchive::new(&rar_first_volume(archive_path)?)
                 .open_for_listing()
                 .map_err(|e| format!("Failed to open RAR archive: {e}"))?;
 
@@ -519,8 +613,10 @@ pub fn list_directory(
     }
 
     // Cache the full structure for future lookups
-    if let Ok(mtime) = fs::metadata(archive_path).and_then(|m| m.modified()) {
-        cache_structure(archive_path, mtime, all_entries);
+    if let Ok(metadata) = fs::metadata(archive_path) {
+        if let Ok(mtime) = metadata.modified() {
+            cache_structure(archive_path, mtime, metadata.len(), all_entries);
+        }
     }
 
     Ok(build_entries(parent_rel, children))
@@ -595,10 +691,10 @@ pub fn get_entry_metadata(
 
             for i in 0..archive.len() {
                 let file = archive
-                    .by_index(i)
+                    .by_index_raw(i)
                     .map_err(|e| format!("Failed to read zip entry: {e}"))?;
-                let entry_name = file.name();
-                let normalized_entry = match normalize_entry_path(entry_name) {
+                let entry_name = zip_entry_name(file.name(), file.name_raw());
+                let normalized_entry = match normalize_entry_path(&entry_name) {
                     Ok(value) => value,
                     Err(_) => continue,
                 };
@@ -624,7 +720,7 @@ pub fn get_entry_metadata(
             }
         }
         ArchiveFormat::Rar => {
-            let archive = RarArchive::new(archive_path)
+            let archive = RarArchive::new(&rar_first_volume(archive_path)?)
                 .open_for_listing()
                 .map_err(|e| format!("Failed to open RAR archive: {e}"))?;
 
@@ -722,6 +818,7 @@ pub fn extract_entry_to_dir(
     archive_path: &Path,
     internal_path: &str,
     output_dir: &Path,
+    password: Option<&str>,
 ) -> Result<PathBuf, String> {
     let format = determine_archive_format(archive_path)?;
     let normalized = normalize_internal_path(internal_path)?;
@@ -740,8 +837,9 @@ pub fn extract_entry_to_dir(
             // Find the entry by normalized path (handles ./foo.txt, backslashes, etc.)
             let mut found_index: Option<usize> = None;
             for i in 0..archive.len() {
-                if let Ok(entry) = archive.by_index(i) {
-                    if let Ok(normalized) = normalize_entry_path(entry.name()) {
+                if let Ok(entry) = archive.by_index_raw(i) {
+                    let entry_name = zip_entry_name(entry.name(), entry.name_raw());
+                    if let Ok(normalized) = normalize_entry_path(&entry_name) {
                         if normalized == target_rel {
                             found_index = Some(i);
                             break;
@@ -751,9 +849,7 @@ pub fn extract_entry_to_dir(
             }
 
             let entry_index = found_index.ok_or_else(|| "Archive entry not found".to_string())?;
-            let mut entry = archive
-                .by_index(entry_index)
-                .map_err(|e| format!("Failed to read zip entry: {e}"))?;
+            let mut entry = open_zip_entry(&mut archive, entry_index, password)?;
 
             if entry.is_dir() {
                 return Err("Cannot extract directory entry".to_string());
@@ -799,7 +895,7 @@ pub fn extract_entry_to_dir(
             Ok(out_path)
         }
         ArchiveFormat::Rar => {
-            let mut archive = RarArchive::new(archive_path)
+            let mut archive = RarArchive::new(&rar_first_volume(archive_path)?)
                 .open_for_processing()
                 .map_err(|e| format!("Failed to open RAR archive: {e}"))?;
 
@@ -917,6 +1013,7 @@ pub fn extract_entry_to_path(
     archive_path: &Path,
     internal_path: &str,
     output_path: &Path,
+    password: Option<&str>,
 ) -> Result<PathBuf, String> {
     let parent = output_path
         .parent()
@@ -926,7 +1023,7 @@ pub fn extract_entry_to_path(
     let temp_dir = parent.join(format!(".__marlin_extract_{}", Uuid::new_v4()));
     fs::create_dir_all(&temp_dir).map_err(|e| format!("Failed to create temp directory: {e}"))?;
 
-    let extracted = extract_entry_to_dir(archive_path, internal_path, &temp_dir)?;
+    let extracted = extract_entry_to_dir(archive_path, internal_path, &temp_dir, password)?;
 
     // On Windows, fs::rename fails if target exists, so remove it first
     if output_path.exists() {
@@ -939,6 +1036,223 @@ pub fn extract_entry_to_path(
     Ok(output_path.to_path_buf())
 }
 
+/// An entry chosen for extraction and where it goes. Entries nested under a selected
+/// directory are written at the same relative path below `output`.
+pub struct EntrySelection {
+    pub internal_path: String,
+    pub output: PathBuf,
+}
+
+/// Where `normalized` is written if it is, or sits under, one of `targets`,
+/// along with the index of the matching selection.
+fn selection_output(targets: &[(String, &Path)], normalized: &str) -> Option<(usize, PathBuf)> {
+    targets
+        .iter()
+        .enumerate()
+        .find_map(|(index, (target, output))| {
+            if normalized == target {
+                Some((index, output.to_path_buf()))
+            } else {
+                normalized
+                    .strip_prefix(target.as_str())
+                    .and_then(|rest| rest.strip_prefix('/'))
+                    .map(|rest| (index, output.join(rest)))
+            }
+        })
+}
+
+/// The folder a selection is extracted into; nothing may be written outside it.
+fn output_root<'a>(targets: &[(String, &'a Path)], index: usize) -> &'a Path {
+    let output = targets[index].1;
+    output.parent().unwrap_or(output)
+}
+
+/// Create the parent folders of `out_path` and make sure they still resolve inside `root`.
+fn prepare_output_path(root: &Path, out_path: &Path) -> Result<(), String> {
+    let Some(parent) = out_path.parent() else {
+        return Ok(());
+    };
+    fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {e}"))?;
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let canonical_parent = parent
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {e}", parent.display()))?;
+    if !canonical_parent.starts_with(&canonical_root) {
+        return Err("Archive entry escapes output directory".to_string());
+    }
+    Ok(())
+}
+
+/// Extract only the selected entries (and everything under selected directories).
+/// `on_entry` is called with each entry's archive path as it is written; symlinks
+/// are skipped. Fails if a selection matches nothing in the archive.
+pub fn extract_entries(
+    archive_path: &Path,
+    selections: &[EntrySelection],
+    password: Option<&str>,
+    mut on_entry: impl FnMut(&str),
+) -> Result<(), String> {
+    let format = determine_archive_format(archive_path)?;
+    let mut targets: Vec<(String, &Path)> = Vec::with_capacity(selections.len());
+    for selection in selections {
+        let normalized = normalize_internal_path(&selection.internal_path)?;
+        let rel = normalized.trim_start_matches('/').to_string();
+        if rel.is_empty() {
+            return Err("Cannot extract archive root".to_string());
+        }
+        targets.push((rel, selection.output.as_path()));
+    }
+    let mut found = vec![false; targets.len()];
+
+    match format {
+        ArchiveFormat::Zip => {
+            let file = File::open(archive_path)
+                .map_err(|e| format!("Failed to open archive {}: {e}", archive_path.display()))?;
+            let mut archive =
+                ZipArchive::new(file).map_err(|e| format!("Failed to read zip archive: {e}"))?;
+
+            // Plain files are looked up by name; only directory selections (or names
+            // stored unnormalized) need a pass over the central directory headers
+            let mut selected: Vec<(usize, usize, String, PathBuf)> = Vec::new();
+            let mut by_name: HashSet<usize> = HashSet::new();
+            for (index, (target, output)) in targets.iter().enumerate() {
+                let Some(entry_index) = archive.index_for_name(target) else {
+                    continue;
+                };
+                if archive.by_index_raw(entry_index).is_ok_and(|e| !e.is_dir()) {
+                    selected.push((entry_index, index, target.clone(), output.to_path_buf()));
+                    by_name.insert(entry_index);
+                    found[index] = true;
+                }
+            }
+            if found.iter().any(|found| !found) {
+                for entry_index in 0..archive.len() {
+                    if by_name.contains(&entry_index) {
+                        continue;
+                    }
+                    let entry = archive
+                        .by_index_raw(entry_index)
+                        .map_err(|e| format!("Failed to read zip entry: {e}"))?;
+                    let entry_name = zip_entry_name(entry.name(), entry.name_raw());
+                    let Ok(normalized) = normalize_entry_path(&entry_name) else {
+                        continue;
+                    };
+                    if let Some((index, out_path)) = selection_output(&targets, &normalized) {
+                        found[index] = true;
+                        selected.push((entry_index, index, normalized, out_path));
+                    }
+                }
+            }
+
+            for (entry_index, index, name, out_path) in selected {
+                let mut entry = open_zip_entry(&mut archive, entry_index, password)?;
+                if entry.is_symlink() {
+                    continue;
+                }
+                if entry.is_dir() {
+                    fs::create_dir_all(&out_path)
+                        .map_err(|e| format!("Failed to create directory: {e}"))?;
+                } else {
+                    prepare_output_path(output_root(&targets, index), &out_path)?;
+                    let mut outfile = File::create(&out_path)
+                        .map_err(|e| format!("Failed to create output file: {e}"))?;
+                    std::io::copy(&mut entry, &mut outfile)
+                        .map_err(|e| format!("Failed to write output file: {e}"))?;
+                }
+                #[cfg(target_family = "unix")]
+                if let Some(mode) = entry.unix_mode() {
+                    use std::os::unix::fs::PermissionsExt;
+                    let _ = fs::set_permissions(&out_path, fs::Permissions::from_mode(mode));
+                }
+                on_entry(&name);
+            }
+        }
+        ArchiveFormat::Rar => {
+            let mut archive = RarArchive::new(&rar_first_volume(archive_path)?)
+                .open_for_processing()
+                .map_err(|e| format!("Failed to open RAR archive: {e}"))?;
+
+            loop {
+                let header = match archive.read_header() {
+                    Ok(Some(header)) => header,
+                    Ok(None) => break,
+                    Err(err) => return Err(format!("Failed to read RAR entry: {err}")),
+                };
+                let entry = header.entry();
+                let entry_name = entry.filename.to_string_lossy().to_string();
+                let selection = normalize_entry_path(&entry_name)
+                    .ok()
+                    .and_then(|normalized| {
+                        selection_output(&targets, &normalized).map(|m| (normalized, m))
+                    });
+                let Some((normalized, (index, out_path))) = selection else {
+                    archive = header.skip().map_err(|e| e.to_string())?;
+                    continue;
+                };
+                found[index] = true;
+
+                if entry.is_directory() {
+                    fs::create_dir_all(&out_path)
+                        .map_err(|e| format!("Failed to create directory: {e}"))?;
+                    archive = header.skip().map_err(|e| e.to_string())?;
+                } else {
+                    prepare_output_path(output_root(&targets, index), &out_path)?;
+                    archive = header
+                        .extract_to(&out_path)
+                        .map_err(|e| format!("Failed to extract RAR entry: {e}"))?;
+                }
+                on_entry(&normalized);
+            }
+        }
+        ArchiveFormat::Tar
+        | ArchiveFormat::TarGz
+        | ArchiveFormat::TarBz2
+        | ArchiveFormat::TarXz
+        | ArchiveFormat::TarZst => {
+            let reader = create_tar_reader(format, archive_path)?;
+            let mut archive = TarArchive::new(reader);
+            let entries = archive
+                .entries()
+                .map_err(|e| format!("Failed to read tar entries: {e}"))?;
+
+            for entry_result in entries {
+                let mut entry =
+                    entry_result.map_err(|e| format!("Failed to read tar entry: {e}"))?;
+                let path = entry
+                    .path()
+                    .map_err(|e| format!("Failed to read tar entry path: {e}"))?;
+                let Ok(normalized) = normalize_entry_path(&path.to_string_lossy()) else {
+                    continue;
+                };
+                let Some((index, out_path)) = selection_output(&targets, &normalized) else {
+                    continue;
+                };
+                found[index] = true;
+
+                let entry_type = entry.header().entry_type();
+                if entry_type.is_symlink() || entry_type.is_hard_link() {
+                    continue;
+                }
+                if entry_type.is_dir() {
+                    fs::create_dir_all(&out_path)
+                        .map_err(|e| format!("Failed to create directory: {e}"))?;
+                } else {
+                    prepare_output_path(output_root(&targets, index), &out_path)?;
+                    entry
+                        .unpack(&out_path)
+                        .map_err(|e| format!("Failed to write output file: {e}"))?;
+                }
+                on_entry(&normalized);
+            }
+        }
+    }
+
+    if let Some(index) = found.iter().position(|found| !found) {
+        return Err(format!("Archive entry not found: /{}", targets[index].0));
+    }
+    Ok(())
+}
+
 fn create_tar_reader(format: ArchiveFormat, archive_path: &Path) -> Result<Box<dyn Read>, String> {
     let file = File::open(archive_path)
         .map_err(|e| format!("Failed to open archive {}: {e}", archive_path.display()))?;
@@ -956,3 +1270,55 @@ fn create_tar_reader(format: ArchiveFormat, archive_path: &Path) -> Result<Box<d
     };
     Ok(reader)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn write_zip(path: &Path, files: &[&str]) {
+        let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+        for name in files {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"data").unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn root_of(dir: &Path, name: &str, files: &[&str]) -> Option<String> {
+        let path = dir.join(name);
+        write_zip(&path, files);
+        let entries = list_directory(&path, "/", None).unwrap();
+        single_root_directory(&entries).map(|entry| entry.internal_path.clone())
+    }
+
+    #[test]
+    fn detects_a_single_root_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            root_of(
+                dir.path(),
+                "wrapped.zip",
+                &["project-1.0/README.md", "project-1.0/src/main.rs"]
+            ),
+            Some("/project-1.0".to_string())
+        );
+        // Nothing beside the folder is allowed, not even one file
+        assert_eq!(
+            root_of(
+                dir.path(),
+                "mixed.zip",
+                &["project-1.0/README.md", "LICENSE"]
+            ),
+            None
+        );
+        assert_eq!(
+            root_of(dir.path(), "two.zip", &["a/one.txt", "b/two.txt"]),
+            None
+        );
+        assert_eq!(root_of(dir.path(), "file.zip", &["only.txt"]), None);
+    }
+}
@@ -0,0 +1,236 @@
+//! Provider errors with a code the UI can branch on.
+//!
+//! Provider methods return `Result<_, String>`, and a `LocationError` travels in
+//! that string as `"[CODE] message"`, the form the frontend already parses.
+//! Providers build the variant where they know what went wrong (an SFTP status, an
+//! HTTP status, a sidecar error code). Anything that still leaves a provider as
+//! plain text is classified by its wording on the way out of the registry, which is
+//! only a fallback for messages no provider mapped. `Other` renders without a
+//! prefix, so unclassified messages read exactly as before.
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+use crate::commands::error_codes;
+
+const OTHER_CODE: &str = "EOTHER";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocationError {
+    NotFound(String),
+    NotADirectory(String),
+    PermissionDenied(String),
+    /// The server couldn't be reached or the connection dropped
+    Network(String),
+    /// The provider can't do this at all, e.g. writing to a read-only location
+    Unsupported(String),
+    Other(String),
+}
+
+/// The `CODE` and message of a `"[CODE] message"` string.
+fn split_code(raw: &str) -> Option<(&str, &str)> {
+    let rest = raw.strip_prefix('[')?;
+    let (code, message) = rest.split_once(']')?;
+    let is_code = !code.is_empty()
+        && code
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    is_code.then_some((code, message.trim_start()))
+}
+
+/// Whether `raw` already carries a `[CODE]` somewhere, e.g. wrapped by a caller's
+/// context. Such errors are left alone rather than given a second code.
+fn contains_code(raw: &str) -> bool {
+    raw.match_indices('[')
+        .any(|(index, _)| split_code(&raw[index..]).is_some())
+}
+
+impl LocationError {
+    pub fn code(&self) -> &str {
+        match self {
+            Self::NotFound(_) => error_codes::ENOENT,
+            Self::NotADirectory(_) => error_codes::ENOTDIR,
+            Self::PermissionDenied(_) => error_codes::EPERM,
+            Self::Network(_) => error_codes::ENETWORK,
+            Self::Unsupported(_) => error_codes::EUNSUPPORTED,
+            // Codes this enum doesn't know, like EREAUTH, pass through
+            Self::Other(message) => split_code(message).map_or(OTHER_CODE, |(code, _)| code),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotFound(message)
+            | Self::NotADirectory(message)
+            | Self::PermissionDenied(message)
+            | Self::Network(message)
+            | Self::Unsupported(message) => message,
+            Self::Other(message) => split_code(message).map_or(message.as_str(), |(_, rest)| rest),
+        }
+    }
+
+    /// Read an error string back: a known `[CODE]` prefix picks the variant, any other
+    /// code is kept verbatim, and plain text is classified by its wording.
+    pub fn from_message(raw: impl Into<String>) -> Self {
+        let raw = raw.into();
+        if let Some((code, message)) = split_code(&raw) {
+            let message = message.to_string();
+            return match code {
+                error_codes::ENOENT => Self::NotFound(message),
+                error_codes::ENOTDIR => Self::NotADirectory(message),
+                error_codes::EPERM => Self::PermissionDenied(message),
+                error_codes::ENETWORK => Self::Network(message),
+                error_codes::EUNSUPPORTED => Self::Unsupported(message),
+                _ => Self::Other(raw),
+            };
+        }
+        if contains_code(&raw) {
+            return Self::Other(raw);
+        }
+        Self::classify(raw)
+    }
+
+    /// An HTTP error status from a REST or WebDAV server.
+    pub fn from_http_status(status: u16, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match status {
+            404 | 410 => Self::NotFound(message),
+            401 | 403 => Self::PermissionDenied(message),
+            405 | 501 => Self::Unsupported(message),
+            408 | 502 | 503 | 504 => Self::Network(message),
+            _ => Self::Other(message),
+        }
+    }
+
+    /// A request that never got an HTTP status back, from reqwest.
+    pub fn from_request(context: &str, error: &reqwest::Error) -> Self {
+        let message = format!("{}: {}", context, error);
+        match error.status() {
+            Some(status) => Self::from_http_status(status.as_u16(), message),
+            None if error.is_connect() || error.is_timeout() || error.is_request() => {
+                Self::Network(message)
+            }
+            None => Self::Other(message),
+        }
+    }
+
+    /// Sort a free-form message by the wording SFTP servers, libsmbclient, reqwest and
+    /// the OS use for each kind of failure.
+    fn classify(message: String) -> Self {
+        let lower = message.to_ascii_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|needle| lower.contains(needle));
+
+        if has(&["not a directory"]) {
+            Self::NotADirectory(message)
+        } else if has(&["not found", "no such file", "does not exist", "nosuchfile"]) {
+            Self::NotFound(message)
+        } else if has(&[
+            "permission denied",
+            "access denied",
+            "access_denied",
+            "not permitted",
+            "forbidden",
+            "unauthorized",
+            "authentication failed",
+        ]) {
+            Self::PermissionDenied(message)
+        } else if has(&[
+            "connection refused",
+            "connection reset",
+            "connection closed",
+            "connection lost",
+            "timed out",
+            "network",
+            "unreachable",
+            "broken pipe",
+            "error sending request",
+            "failed to connect",
+        ]) {
+            Self::Network(message)
+        } else if has(&["read-only", "not supported", "unsupported"]) {
+            Self::Unsupported(message)
+        } else {
+            Self::Other(message)
+        }
+    }
+}
+
+impl fmt::Display for LocationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Other(message) => f.write_str(message),
+            _ => write!(f, "[{}] {}", self.code(), self.message()),
+        }
+    }
+}
+
+impl std::error::Error for LocationError {}
+
+impl From<LocationError> for String {
+    fn from(error: LocationError) -> Self {
+        error.to_string()
+    }
+}
+
+impl From<String> for LocationError {
+    fn from(raw: String) -> Self {
+        Self::from_message(raw)
+    }
+}
+
+/// `{ "code": "ENOENT", "message": "..." }`
+impl Serialize for LocationError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("LocationError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_provider_messages() {
+        let code = |raw: &str| LocationError::from_message(raw).code().to_string();
+        assert_eq!(code("Failed to get metadata: No such file"), "ENOENT");
+        assert_eq!(code("API error: 404 Not Found"), "ENOENT");
+        assert_eq!(code("Failed to read directory: Permission denied"), "EPERM");
+        assert_eq!(code("Path is not a directory"), "ENOTDIR");
+        assert_eq!(
+            code("Request failed: error sending request for url (https://x)"),
+            "ENETWORK"
+        );
+        assert_eq!(code("Archive locations are read-only"), "EUNSUPPORTED");
+        assert_eq!(code("Something odd happened"), "EOTHER");
+    }
+
+    #[test]
+    fn keeps_existing_codes_and_plain_messages() {
+        let error = LocationError::from_message("[ENOENT] Path does not exist: /x");
+        assert_eq!(
+            error,
+            LocationError::NotFound("Path does not exist: /x".into())
+        );
+        assert_eq!(error.to_string(), "[ENOENT] Path does not exist: /x");
+
+        // Codes outside the enum, here or wrapped in context, come back untouched
+        let reauth = "[EREAUTH] Sign in to a@b.c again";
+        assert_eq!(LocationError::from_message(reauth).to_string(), reauth);
+        assert_eq!(LocationError::from_message(reauth).code(), "EREAUTH");
+        let wrapped = "Failed to connect: [SFTP_HOST_KEY_CHANGED] key mismatch";
+        assert_eq!(LocationError::from_message(wrapped).to_string(), wrapped);
+
+        assert_eq!(
+            LocationError::from_message("Upload cancelled").to_string(),
+            "Upload cancelled"
+        );
+        assert_eq!(
+            serde_json::to_value(LocationError::from_http_status(403, "Denied")).unwrap(),
+            serde_json::json!({ "code": "EPERM", "message": "Denied" })
+        );
+    }
+}
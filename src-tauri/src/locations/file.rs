@@ -1,17 +1,91 @@
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use tauri::async_runtime::spawn_blocking;
+use walkdir::WalkDir;
 
 use super::{
-    Location, LocationCapabilities, LocationProvider, LocationSummary, ProviderDirectoryEntries,
+    EntryFilter, Location, LocationCapabilities, LocationError, LocationProvider, LocationSummary,
+    ProviderDirectoryEntries, SearchCollector, SearchQuery, SearchSink,
 };
 use crate::fs_utils::{
-    copy_file_or_directory, create_directory, delete_file_or_directory, expand_path, get_file_info,
-    read_directory_contents, rename_file_or_directory, FileItem,
+    copy_file_or_directory, count_children, create_directory, create_unique_file,
+    delete_file_or_directory, directory_stats, expand_path, get_file_info, move_file_or_directory,
+    read_directory_contents, read_directory_contents_matching, rename_file_or_directory,
+    DirectoryStats, FileItem,
 };
 
+/// Local parent directory, using platform path rules so drive roots stop correctly.
+pub(super) fn parent_location(location: &Location) -> Result<Option<LocationSummary>, String> {
+    let expanded = expand_path(&location.to_path_string())?;
+    Ok(expanded.parent().map(|parent| {
+        let normalized = parent.to_string_lossy().to_string();
+        LocationSummary::new(
+            "file",
+            location.authority().map(|s| s.to_string()),
+            normalized.clone(),
+            normalized,
+        )
+    }))
+}
+
+/// Walk `root` for entries matching `query`, flushing batches to `sink` as it goes.
+fn search_tree(
+    root: &Path,
+    query: &SearchQuery,
+    cancel: &AtomicBool,
+    sink: &SearchSink,
+) -> Result<usize, String> {
+    if !root.is_dir() {
+        return Err(
+            LocationError::NotADirectory("Path is not a directory".to_string()).to_string(),
+        );
+    }
+    #[cfg(target_os = "macos")]
+    let _scope_guard = crate::macos_security::retain_access(root)?;
+
+    let max_depth = if query.options().recursive {
+        usize::MAX
+    } else {
+        1
+    };
+    let walker = WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(max_depth)
+        .follow_links(false)
+        .into_iter()
+        // Hidden folders can't match, and neither can anything inside them
+        .filter_entry(|entry| {
+            query.options().include_hidden
+                || entry.depth() == 0
+                || !entry.file_name().to_string_lossy().starts_with('.')
+        });
+
+    let mut matches = SearchCollector::new(query, sink);
+    for entry in walker {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let Ok(entry) = entry else {
+            continue;
+        };
+        if matches.wants(&entry.file_name().to_string_lossy()) {
+            if let Ok(item) = get_file_info(entry.path()) {
+                matches.offer(item);
+            }
+        }
+        matches.flush_if_due();
+        if matches.is_full() {
+            break;
+        }
+    }
+
+    Ok(matches.finish())
+}
+
 #[derive(Default)]
 pub struct FileSystemProvider;
 
@@ -66,6 +140,7 @@ impl LocationProvider for FileSystemProvider {
     fn capabilities(&self, _location: &Location) -> LocationCapabilities {
         LocationCapabilities::new("file", "Local Filesystem", true, true)
             .with_supports_watching(true)
+            .with_supports_tags(crate::file_tags::TAGS_SUPPORTED)
     }
 
     async fn read_directory(
@@ -76,13 +151,16 @@ impl LocationProvider for FileSystemProvider {
 
         spawn_blocking(move || {
             if !path.exists() {
-                return Err("Path does not exist".to_string());
+                return Err(LocationError::NotFound("Path does not exist".to_string()).to_string());
             }
             if !path.is_dir() {
-                return Err("Path is not a directory".to_string());
+                return Err(
+                    LocationError::NotADirectory("Path is not a directory".to_string()).to_string(),
+                );
             }
 
-            let entries = read_directory_contents(&path)?;
+            let mut entries = read_directory_contents(&path)?;
+            crate::git_annotations::annotate_listing(&path, &mut entries);
 
             Ok(ProviderDirectoryEntries {
                 location: summary,
@@ -93,12 +171,85 @@ impl LocationProvider for FileSystemProvider {
         .map_err(|e| format!("Task join error: {}", e))?
     }
 
+    async fn read_directory_filtered(
+        &self,
+        location: &Location,
+        filter: &EntryFilter,
+    ) -> Result<ProviderDirectoryEntries, String> {
+        let (path, summary) = self.resolve_path(location)?;
+        let filter = filter.clone();
+
+        spawn_blocking(move || {
+            if !path.exists() {
+                return Err(LocationError::NotFound("Path does not exist".to_string()).to_string());
+            }
+            if !path.is_dir() {
+                return Err(
+                    LocationError::NotADirectory("Path is not a directory".to_string()).to_string(),
+                );
+            }
+
+            let mut entries = read_directory_contents_matching(&path, |name, is_directory| {
+                filter.matches(name, is_directory)
+            })?;
+            crate::git_annotations::annotate_listing(&path, &mut entries);
+
+            Ok(ProviderDirectoryEntries {
+                location: summary,
+                entries,
+            })
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn search(
+        &self,
+        location: &Location,
+        query: &SearchQuery,
+        cancel: Arc<AtomicBool>,
+        sink: SearchSink,
+    ) -> Result<usize, String> {
+        let root = self.resolve_path_only(location)?;
+        let query = query.clone();
+
+        spawn_blocking(move || search_tree(&root, &query, &cancel, &sink))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn child_count(&self, location: &Location) -> Result<u64, String> {
+        let path = self.resolve_path_only(location)?;
+
+        spawn_blocking(move || count_children(&path))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn directory_stats(&self, location: &Location) -> Result<DirectoryStats, String> {
+        let path = self.resolve_path_only(location)?;
+
+        spawn_blocking(move || {
+            if !path.exists() {
+                return Err(LocationError::NotFound("Path does not exist".to_string()).to_string());
+            }
+            if !path.is_dir() {
+                return Err(
+                    LocationError::NotADirectory("Path is not a directory".to_string()).to_string(),
+                );
+            }
+            directory_stats(&path)
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
     async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
         let path = self.resolve_path_only(location)?;
 
         spawn_blocking(move || {
             if !path.exists() {
-                return Err("Path does not exist".to_string());
+                return Err(LocationError::NotFound("Path does not exist".to_string()).to_string());
             }
             get_file_info(&path)
         })
@@ -114,12 +265,25 @@ impl LocationProvider for FileSystemProvider {
             .map_err(|e| format!("Task join error: {}", e))?
     }
 
+    async fn ensure_directory(&self, location: &Location) -> Result<(), String> {
+        let path = self.resolve_path_only(location)?;
+
+        spawn_blocking(move || {
+            if path.exists() && !path.is_dir() {
+                return Err("A file already exists at this path".to_string());
+            }
+            create_directory(&path)
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
     async fn delete(&self, location: &Location) -> Result<(), String> {
         let path = self.resolve_path_only(location)?;
 
         spawn_blocking(move || {
             if !path.exists() {
-                return Err("Path does not exist".to_string());
+                return Err(LocationError::NotFound("Path does not exist".to_string()).to_string());
             }
             delete_file_or_directory(&path)
         })
@@ -133,7 +297,9 @@ impl LocationProvider for FileSystemProvider {
 
         spawn_blocking(move || {
             if !from_path.exists() {
-                return Err("Source path does not exist".to_string());
+                return Err(
+                    LocationError::NotFound("Source path does not exist".to_string()).to_string(),
+                );
             }
 
             let same_parent = from_path.parent() == to_path.parent();
@@ -167,7 +333,9 @@ impl LocationProvider for FileSystemProvider {
 
         spawn_blocking(move || {
             if !from_path.exists() {
-                return Err("Source path does not exist".to_string());
+                return Err(
+                    LocationError::NotFound("Source path does not exist".to_string()).to_string(),
+                );
             }
             copy_file_or_directory(&from_path, &to_path)
         })
@@ -181,12 +349,36 @@ impl LocationProvider for FileSystemProvider {
 
         spawn_blocking(move || {
             if !from_path.exists() {
-                return Err("Source path does not exist".to_string());
+                return Err(
+                    LocationError::NotFound("Source path does not exist".to_string()).to_string(),
+                );
             }
             if to_path.exists() {
                 return Err("Destination path already exists".to_string());
             }
-            rename_file_or_directory(&from_path, &to_path)
+            // Falls back to copy + delete when crossing filesystems
+            move_file_or_directory(&from_path, &to_path, |_, _| {})
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn create_file(
+        &self,
+        directory: &Location,
+        name: &str,
+        contents: &[u8],
+    ) -> Result<String, String> {
+        let dir = self.resolve_path_only(directory)?;
+        let name = name.to_string();
+        let contents = contents.to_vec();
+
+        spawn_blocking(move || {
+            let target = create_unique_file(&dir, &name, &contents)?;
+            Ok(target
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or(name))
         })
         .await
         .map_err(|e| format!("Task join error: {}", e))?
@@ -3,7 +3,8 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::RwLock;
+use std::sync::{OnceLock, RwLock};
+use tauri::{AppHandle, Emitter};
 use tokio::sync::Mutex;
 use yup_oauth2::authenticator_delegate::InstalledFlowDelegate;
 use yup_oauth2::{
@@ -35,6 +36,16 @@ const GOOGLE_CLIENT_ID_ENV: &str = "GOOGLE_CLIENT_ID";
 const GOOGLE_CLIENT_SECRET_ENV: &str = "GOOGLE_CLIENT_SECRET";
 const GOOGLE_CREDENTIALS_FILE_NAME: &str = "google-credentials.json";
 
+/// Emitted with `{ email }` when an account's refresh token has been revoked.
+pub const ACCOUNT_REAUTH_REQUIRED_EVENT: &str = "account-reauth-required";
+
+/// App handle used to notify the UI about accounts that need re-authentication
+static AUTH_EVENTS_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+pub fn init_auth_events(app_handle: AppHandle) {
+    let _ = AUTH_EVENTS_HANDLE.set(app_handle);
+}
+
 /// Cached service account key (loaded once from environment)
 static SERVICE_ACCOUNT_KEY: Lazy<Option<ServiceAccountKey>> =
     Lazy::new(|| load_service_account_key().ok());
@@ -255,6 +266,9 @@ pub struct GoogleAccountInfo {
     pub email: String,
     pub display_name: Option<String>,
     pub photo_url: Option<String>,
+    /// The refresh token was revoked; the user must sign in again
+    #[serde(default)]
+    pub needs_reauth: bool,
 }
 
 /// Stored account data with tokens
@@ -267,6 +281,8 @@ pub struct GoogleAccount {
     pub access_token: String,
     pub refresh_token: String,
     pub expires_at: DateTime<Utc>,
+    #[serde(default)]
+    pub needs_reauth: bool,
 }
 
 /// Storage structure for accounts file
@@ -339,6 +355,7 @@ pub fn get_google_accounts() -> Result<Vec<GoogleAccountInfo>, String> {
             email: sa_email,
             display_name: Some("Service Account".to_string()),
             photo_url: None,
+            needs_reauth: false,
         });
     }
 
@@ -350,6 +367,7 @@ pub fn get_google_accounts() -> Result<Vec<GoogleAccountInfo>, String> {
                 email: a.email.clone(),
                 display_name: a.display_name.clone(),
                 photo_url: a.photo_url.clone(),
+                needs_reauth: a.needs_reauth,
             }));
             return Ok(result);
         }
@@ -368,6 +386,7 @@ pub fn get_google_accounts() -> Result<Vec<GoogleAccountInfo>, String> {
         email: a.email.clone(),
         display_name: a.display_name.clone(),
         photo_url: a.photo_url.clone(),
+        needs_reauth: a.needs_reauth,
     }));
 
     Ok(result)
@@ -386,6 +405,7 @@ pub fn get_all_accounts() -> Result<Vec<GoogleAccount>, String> {
             access_token: String::new(),  // Will be fetched on demand
             refresh_token: String::new(), // Service accounts use JWT, not refresh tokens
             expires_at: Utc::now(),       // Force refresh on first use
+            needs_reauth: false,
         });
     }
 
@@ -576,6 +596,7 @@ pub async fn add_google_account() -> Result<GoogleAccountInfo, String> {
         existing.expires_at = expires_at;
         existing.display_name = user_info.display_name.clone();
         existing.photo_url = user_info.photo_url.clone();
+        existing.needs_reauth = false;
     } else {
         // Add new account
         log::info!("Adding new account: {}", user_info.email);
@@ -586,6 +607,7 @@ pub async fn add_google_account() -> Result<GoogleAccountInfo, String> {
             access_token,
             refresh_token,
             expires_at,
+            needs_reauth: false,
         });
     }
 
@@ -636,6 +658,7 @@ async fn fetch_user_info(access_token: &str) -> Result<GoogleAccountInfo, String
         email: user_info.email,
         display_name: user_info.name,
         photo_url: user_info.picture,
+        needs_reauth: false,
     })
 }
 
@@ -676,6 +699,11 @@ pub async fn ensure_valid_token(email: &str) -> Result<String, String> {
         .position(|a| a.email == email)
         .ok_or_else(|| format!("Account not found: {}", email))?;
 
+    // Don't keep hammering the token endpoint with a refresh token we know is dead
+    if accounts[account_index].needs_reauth {
+        return Err(reauth_required_error(email));
+    }
+
     // Check if token is still valid (with 5 minute buffer)
     if accounts[account_index].expires_at > Utc::now() + Duration::minutes(5) {
         return Ok(accounts[account_index].access_token.clone());
@@ -707,6 +735,10 @@ pub async fn ensure_valid_token(email: &str) -> Result<String, String> {
             status,
             error_text
         );
+        if is_revoked_refresh_error(status.as_u16(), &error_text) {
+            mark_account_needs_reauth(&mut accounts, account_index)?;
+            return Err(reauth_required_error(email));
+        }
         return Err(format!(
             "Token refresh failed (status {}). Re-authentication may be required.",
             status
@@ -746,11 +778,66 @@ pub async fn ensure_valid_token(email: &str) -> Result<String, String> {
     Ok(token_response.access_token)
 }
 
+/// Error returned for accounts whose refresh token no longer works.
+/// Carries the `EREAUTH` code so the UI can prompt a re-login.
+pub fn reauth_required_error(email: &str) -> String {
+    format!(
+        "[{}] Google account {} needs to be signed in again",
+        crate::commands::error_codes::EREAUTH,
+        email
+    )
+}
+
+/// Google answers a revoked/expired refresh token with `invalid_grant`. Other failures
+/// (network, 5xx, misconfigured client) are transient or global and must not flag the account.
+fn is_revoked_refresh_error(status: u16, body: &str) -> bool {
+    if status != 400 && status != 401 {
+        return false;
+    }
+
+    #[derive(Deserialize)]
+    struct TokenErrorResponse {
+        error: String,
+    }
+
+    serde_json::from_str::<TokenErrorResponse>(body)
+        .map(|response| response.error == "invalid_grant")
+        .unwrap_or(false)
+}
+
+/// Persist the re-auth flag for one account and tell the UI about it.
+fn mark_account_needs_reauth(accounts: &mut [GoogleAccount], index: usize) -> Result<(), String> {
+    let account = &mut accounts[index];
+    account.needs_reauth = true;
+    let email = account.email.clone();
+    log::warn!(
+        "Refresh token revoked for {}; re-authentication required",
+        email
+    );
+
+    save_accounts_to_disk(accounts)?;
+    {
+        let mut cache = ACCOUNTS_CACHE.write().map_err(|e| e.to_string())?;
+        *cache = Some(accounts.to_vec());
+    }
+
+    if let Some(app_handle) = AUTH_EVENTS_HANDLE.get() {
+        if let Err(err) = app_handle.emit(
+            ACCOUNT_REAUTH_REQUIRED_EVENT,
+            serde_json::json!({ "email": email }),
+        ) {
+            log::warn!("Failed to emit account reauth event: {}", err);
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        load_oauth_credentials_from_file, oauth_from_pair, OAuthCredentials, GOOGLE_CLIENT_ID_ENV,
-        GOOGLE_CLIENT_SECRET_ENV,
+        is_revoked_refresh_error, load_oauth_credentials_from_file, oauth_from_pair,
+        OAuthCredentials, GOOGLE_CLIENT_ID_ENV, GOOGLE_CLIENT_SECRET_ENV,
     };
     use std::fs;
 
@@ -811,4 +898,16 @@ mod tests {
         assert_eq!(GOOGLE_CLIENT_ID_ENV, "GOOGLE_CLIENT_ID");
         assert_eq!(GOOGLE_CLIENT_SECRET_ENV, "GOOGLE_CLIENT_SECRET");
     }
+
+    #[test]
+    fn only_invalid_grant_flags_account_for_reauth() {
+        let revoked = r#"{"error": "invalid_grant", "error_description": "Token has been expired or revoked."}"#;
+        assert!(is_revoked_refresh_error(400, revoked));
+        assert!(!is_revoked_refresh_error(500, revoked));
+        assert!(!is_revoked_refresh_error(
+            401,
+            r#"{"error": "unauthorized_client"}"#
+        ));
+        assert!(!is_revoked_refresh_error(400, "not json"));
+    }
 }
@@ -6,13 +6,16 @@ use google_drive3::DriveHub;
 use hyper_util::client::legacy::connect::HttpConnector;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
 use std::time::{Duration as StdDuration, Instant};
 
 use super::auth::{ensure_valid_token, get_all_accounts};
 use crate::fs_utils::FileItem;
+use crate::locations::transfer::{StagedFile, TransferProgress};
 use crate::locations::{
-    Location, LocationCapabilities, LocationProvider, LocationSummary, ProviderDirectoryEntries,
+    walk_search, Location, LocationCapabilities, LocationError, LocationProvider, LocationSummary,
+    ProviderDirectoryEntries, SearchQuery, SearchSink,
 };
 
 /// Virtual root folder names
@@ -58,6 +61,130 @@ const CACHE_TTL: StdDuration = StdDuration::from_secs(30);
 
 type DriveHubType = DriveHub<HttpsConnector<HttpConnector>>;
 
+/// Fields requested for file listings; `nextPageToken` drives [`list_all_pages`]
+const LIST_FIELDS: &str = "nextPageToken,files(id,name,mimeType,size,modifiedTime,parents,thumbnailLink,webContentLink,imageMediaMetadata(width,height))";
+
+/// Largest page the Drive API returns for file listings
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Cap on the "Recent" view, which would otherwise page through the whole Drive
+const RECENT_LIMIT: usize = 200;
+
+/// Drive search hits considered per search; each one costs a walk up its parents
+const SEARCH_CANDIDATE_LIMIT: usize = 1000;
+
+/// Search matches sent to the UI at a time
+const SEARCH_BATCH_SIZE: usize = 25;
+
+/// Optional parameters for [`list_all_pages`]
+#[derive(Default)]
+struct ListOptions<'a> {
+    order_by: Option<&'a str>,
+    /// Restrict the listing to one shared drive
+    drive_id: Option<&'a str>,
+    /// Include items from shared drives (needed for folders inside them)
+    all_drives: bool,
+    /// Stop once this many files have been collected
+    limit: Option<usize>,
+}
+
+/// Run a `files.list` query, following `nextPageToken` until every page is read
+/// or `options.limit` files have been collected.
+async fn list_all_pages(
+    hub: &DriveHubType,
+    query: &str,
+    options: ListOptions<'_>,
+) -> Result<Vec<DriveFile>, google_drive3::Error> {
+    let limit = options.limit.unwrap_or(usize::MAX);
+    let mut files: Vec<DriveFile> = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let page_size = (limit - files.len()).min(MAX_PAGE_SIZE) as i32;
+        let mut call = hub
+            .files()
+            .list()
+            .q(query)
+            .page_size(page_size)
+            .add_scope(google_drive3::api::Scope::Full)
+            .param("fields", LIST_FIELDS);
+        if let Some(order_by) = options.order_by {
+            call = call.order_by(order_by);
+        }
+        if let Some(drive_id) = options.drive_id {
+            call = call.corpora("drive").drive_id(drive_id);
+        }
+        if options.all_drives || options.drive_id.is_some() {
+            call = call
+                .supports_all_drives(true)
+                .include_items_from_all_drives(true);
+        }
+        if let Some(token) = &page_token {
+            call = call.page_token(token);
+        }
+
+        let (_, list) = call.doit().await?;
+        files.extend(list.files.unwrap_or_default());
+        if files.len() >= limit {
+            files.truncate(limit);
+            break;
+        }
+        match list.next_page_token {
+            Some(token) if !token.is_empty() => page_token = Some(token),
+            _ => break,
+        }
+    }
+
+    Ok(files)
+}
+
+/// Code a Drive API failure by the HTTP status it came back with, or as a network
+/// error when the request never reached Google.
+fn drive_error(context: &str, error: google_drive3::Error) -> String {
+    let message = format!("{}: {}", context, error);
+    let status = match &error {
+        google_drive3::Error::BadRequest(body) => body["error"]["code"]
+            .as_u64()
+            .and_then(|code| u16::try_from(code).ok()),
+        google_drive3::Error::Failure(response) => Some(response.status().as_u16()),
+        _ => None,
+    };
+    match (status, &error) {
+        (Some(status), _) => LocationError::from_http_status(status, message),
+        (None, google_drive3::Error::HttpError(_)) => LocationError::Network(message),
+        _ => LocationError::Other(message),
+    }
+    .to_string()
+}
+
+/// Every shared drive the account can see, following `nextPageToken`
+async fn list_all_drives(
+    hub: &DriveHubType,
+) -> Result<Vec<google_drive3::api::Drive>, google_drive3::Error> {
+    let mut drives = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut call = hub
+            .drives()
+            .list()
+            .page_size(100)
+            .add_scope(google_drive3::api::Scope::Full);
+        if let Some(token) = &page_token {
+            call = call.page_token(token);
+        }
+
+        let (_, list) = call.doit().await?;
+        drives.extend(list.drives.unwrap_or_default());
+        match list.next_page_token {
+            Some(token) if !token.is_empty() => page_token = Some(token),
+            _ => break,
+        }
+    }
+
+    Ok(drives)
+}
+
 /// Google Drive location provider
 pub struct GoogleDriveProvider;
 
@@ -124,6 +251,16 @@ impl GoogleDriveProvider {
         (Some(root), rest)
     }
 
+    /// Whether new items can be placed in the folder at `subpath` under `root_folder`:
+    /// anywhere in My Drive, or inside (not beside) a shared drive.
+    fn is_writable_folder(root_folder: Option<&str>, subpath: &[&str]) -> bool {
+        match root_folder {
+            Some(VIRTUAL_MY_DRIVE) => true,
+            Some(VIRTUAL_SHARED_DRIVES) => !subpath.is_empty(),
+            _ => false,
+        }
+    }
+
     /// List the virtual root folders
     fn list_virtual_root(&self, email: &str) -> ProviderDirectoryEntries {
         let now = Utc::now();
@@ -145,6 +282,10 @@ impl GoogleDriveProvider {
                 remote_id: None,
                 thumbnail_url: None,
                 download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
             },
             FileItem {
                 name: VIRTUAL_SHARED_DRIVES.to_string(),
@@ -162,6 +303,10 @@ impl GoogleDriveProvider {
                 remote_id: None,
                 thumbnail_url: None,
                 download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
             },
             FileItem {
                 name: VIRTUAL_SHARED.to_string(),
@@ -179,6 +324,10 @@ impl GoogleDriveProvider {
                 remote_id: None,
                 thumbnail_url: None,
                 download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
             },
             FileItem {
                 name: VIRTUAL_STARRED.to_string(),
@@ -196,6 +345,10 @@ impl GoogleDriveProvider {
                 remote_id: None,
                 thumbnail_url: None,
                 download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
             },
             FileItem {
                 name: VIRTUAL_RECENT.to_string(),
@@ -213,6 +366,10 @@ impl GoogleDriveProvider {
                 remote_id: None,
                 thumbnail_url: None,
                 download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
             },
         ];
 
@@ -291,6 +448,10 @@ impl GoogleDriveProvider {
             },
             thumbnail_url,
             download_url,
+            tags: None,
+            is_git_submodule: false,
+            lfs_size: None,
+            id: String::new(),
         }
     }
 
@@ -301,21 +462,17 @@ impl GoogleDriveProvider {
         email: &str,
     ) -> Result<Vec<FileItem>, String> {
         log::debug!("Listing My Drive root for {}", email);
-        let result = hub
-            .files()
-            .list()
-            .q("'root' in parents and trashed = false")
-            .page_size(1000)
-            .add_scope(google_drive3::api::Scope::Full)
-            .param("fields", "files(id,name,mimeType,size,modifiedTime,parents,thumbnailLink,webContentLink,imageMediaMetadata(width,height))")
-            .doit()
-            .await
-            .map_err(|e| {
-                log::error!("Failed to list Drive files: {}", e);
-                format!("Failed to list Drive files: {}", e)
-            })?;
+        let files = list_all_pages(
+            hub,
+            "'root' in parents and trashed = false",
+            ListOptions::default(),
+        )
+        .await
+        .map_err(|e| {
+            log::error!("Failed to list Drive files: {}", e);
+            drive_error("Failed to list Drive files", e)
+        })?;
 
-        let files = result.1.files.unwrap_or_default();
         log::debug!("Got {} files from My Drive root", files.len());
         let parent_path = format!("/{}", VIRTUAL_MY_DRIVE);
 
@@ -340,20 +497,14 @@ impl GoogleDriveProvider {
             query
         );
 
-        let result = hub
-            .files()
-            .list()
-            .q(&query)
-            .page_size(1000)
-            .supports_all_drives(true)  // Required for shared drives
-            .include_items_from_all_drives(true)  // Include shared drive items
-            .add_scope(google_drive3::api::Scope::Full)
-            .param("fields", "files(id,name,mimeType,size,modifiedTime,parents,thumbnailLink,webContentLink,imageMediaMetadata(width,height))")
-            .doit()
+        let options = ListOptions {
+            all_drives: true, // Required for folders inside shared drives
+            ..Default::default()
+        };
+        let files = list_all_pages(hub, &query, options)
             .await
-            .map_err(|e| format!("Failed to list folder: {}", e))?;
+            .map_err(|e| drive_error("Failed to list folder", e))?;
 
-        let files = result.1.files.unwrap_or_default();
         log::debug!("  -> found {} files", files.len());
 
         Ok(files
@@ -368,18 +519,14 @@ impl GoogleDriveProvider {
         hub: &DriveHubType,
         email: &str,
     ) -> Result<Vec<FileItem>, String> {
-        let result = hub
-            .files()
-            .list()
-            .q("sharedWithMe = true and trashed = false")
-            .page_size(1000)
-            .add_scope(google_drive3::api::Scope::Full)
-            .param("fields", "files(id,name,mimeType,size,modifiedTime,parents,thumbnailLink,webContentLink,imageMediaMetadata(width,height))")
-            .doit()
-            .await
-            .map_err(|e| format!("Failed to list shared files: {}", e))?;
+        let files = list_all_pages(
+            hub,
+            "sharedWithMe = true and trashed = false",
+            ListOptions::default(),
+        )
+        .await
+        .map_err(|e| drive_error("Failed to list shared files", e))?;
 
-        let files = result.1.files.unwrap_or_default();
         let parent_path = format!("/{}", VIRTUAL_SHARED);
 
         Ok(files
@@ -390,18 +537,14 @@ impl GoogleDriveProvider {
 
     /// List starred files
     async fn list_starred(&self, hub: &DriveHubType, email: &str) -> Result<Vec<FileItem>, String> {
-        let result = hub
-            .files()
-            .list()
-            .q("starred = true and trashed = false")
-            .page_size(1000)
-            .add_scope(google_drive3::api::Scope::Full)
-            .param("fields", "files(id,name,mimeType,size,modifiedTime,parents,thumbnailLink,webContentLink,imageMediaMetadata(width,height))")
-            .doit()
-            .await
-            .map_err(|e| format!("Failed to list starred files: {}", e))?;
+        let files = list_all_pages(
+            hub,
+            "starred = true and trashed = false",
+            ListOptions::default(),
+        )
+        .await
+        .map_err(|e| drive_error("Failed to list starred files", e))?;
 
-        let files = result.1.files.unwrap_or_default();
         let parent_path = format!("/{}", VIRTUAL_STARRED);
 
         Ok(files
@@ -412,19 +555,15 @@ impl GoogleDriveProvider {
 
     /// List recent files
     async fn list_recent(&self, hub: &DriveHubType, email: &str) -> Result<Vec<FileItem>, String> {
-        let result = hub
-            .files()
-            .list()
-            .q("trashed = false")
-            .order_by("viewedByMeTime desc")
-            .page_size(50)
-            .add_scope(google_drive3::api::Scope::Full)
-            .param("fields", "files(id,name,mimeType,size,modifiedTime,parents,thumbnailLink,webContentLink,imageMediaMetadata(width,height))")
-            .doit()
+        let options = ListOptions {
+            order_by: Some("viewedByMeTime desc"),
+            limit: Some(RECENT_LIMIT),
+            ..Default::default()
+        };
+        let files = list_all_pages(hub, "trashed = false", options)
             .await
-            .map_err(|e| format!("Failed to list recent files: {}", e))?;
+            .map_err(|e| drive_error("Failed to list recent files", e))?;
 
-        let files = result.1.files.unwrap_or_default();
         let parent_path = format!("/{}", VIRTUAL_RECENT);
 
         Ok(files
@@ -439,16 +578,9 @@ impl GoogleDriveProvider {
         hub: &DriveHubType,
         email: &str,
     ) -> Result<Vec<FileItem>, String> {
-        let result = hub
-            .drives()
-            .list()
-            .page_size(100)
-            .add_scope(google_drive3::api::Scope::Full)
-            .doit()
+        let drives = list_all_drives(hub)
             .await
-            .map_err(|e| format!("Failed to list shared drives: {}", e))?;
-
-        let drives = result.1.drives.unwrap_or_default();
+            .map_err(|e| drive_error("Failed to list shared drives", e))?;
         let parent_path = format!("/{}", VIRTUAL_SHARED_DRIVES);
         let now = Utc::now();
 
@@ -476,6 +608,10 @@ impl GoogleDriveProvider {
                     remote_id: Some(drive_id),
                     thumbnail_url: None,
                     download_url: None,
+                    tags: None,
+                    is_git_submodule: false,
+                    lfs_size: None,
+                    id: String::new(),
                 }
             })
             .collect())
@@ -487,16 +623,9 @@ impl GoogleDriveProvider {
         hub: &DriveHubType,
         name: &str,
     ) -> Result<Option<String>, String> {
-        let result = hub
-            .drives()
-            .list()
-            .page_size(100)
-            .add_scope(google_drive3::api::Scope::Full)
-            .doit()
+        let drives = list_all_drives(hub)
             .await
-            .map_err(|e| format!("Failed to list shared drives: {}", e))?;
-
-        let drives = result.1.drives.unwrap_or_default();
+            .map_err(|e| drive_error("Failed to list shared drives", e))?;
 
         for drive in drives {
             if let Some(drive_name) = &drive.name {
@@ -517,22 +646,14 @@ impl GoogleDriveProvider {
         email: &str,
         path: &str,
     ) -> Result<Vec<FileItem>, String> {
-        let result = hub
-            .files()
-            .list()
-            .q(&format!("'{}' in parents and trashed = false", drive_id))
-            .corpora("drive")
-            .drive_id(drive_id)
-            .include_items_from_all_drives(true)
-            .supports_all_drives(true)
-            .page_size(1000)
-            .add_scope(google_drive3::api::Scope::Full)
-            .param("fields", "files(id,name,mimeType,size,modifiedTime,parents,thumbnailLink,webContentLink,imageMediaMetadata(width,height))")
-            .doit()
+        let query = format!("'{}' in parents and trashed = false", drive_id);
+        let options = ListOptions {
+            drive_id: Some(drive_id),
+            ..Default::default()
+        };
+        let files = list_all_pages(hub, &query, options)
             .await
-            .map_err(|e| format!("Failed to list shared drive contents: {}", e))?;
-
-        let files = result.1.files.unwrap_or_default();
+            .map_err(|e| drive_error("Failed to list shared drive contents", e))?;
 
         Ok(files
             .iter()
@@ -572,7 +693,7 @@ impl GoogleDriveProvider {
                 .param("fields", "files(id,name)")
                 .doit()
                 .await
-                .map_err(|e| format!("Failed to search in shared drive: {}", e))?;
+                .map_err(|e| drive_error("Failed to search in shared drive", e))?;
 
             let files = result.1.files.unwrap_or_default();
             if files.is_empty() {
@@ -595,6 +716,32 @@ impl GoogleDriveProvider {
             .await
     }
 
+    /// Resolve a path under any virtual root to a file ID. My Drive paths start from
+    /// `"root"`; shared drive paths start from the drive's ID, which doubles as the ID of
+    /// its top folder. The bare "Shared with me" and "Shared drives" roots have no ID.
+    async fn find_file_by_virtual_path(
+        &self,
+        hub: &DriveHubType,
+        root_folder: Option<&str>,
+        path_parts: &[&str],
+    ) -> Result<Option<String>, String> {
+        match (root_folder, path_parts) {
+            (Some(VIRTUAL_MY_DRIVE), _) => self.find_file_by_path(hub, path_parts).await,
+            (Some(VIRTUAL_SHARED), [_, ..]) => self.find_shared_file_by_path(hub, path_parts).await,
+            (Some(VIRTUAL_SHARED_DRIVES), [drive_name, inner @ ..]) => {
+                match self.find_shared_drive_by_name(hub, drive_name).await? {
+                    Some(drive_id) => self.find_file_in_shared_drive(hub, &drive_id, inner).await,
+                    None => Ok(None),
+                }
+            }
+            (Some(VIRTUAL_BY_ID), [file_id, inner @ ..]) => {
+                self.find_file_by_path_from_parent(hub, file_id, inner)
+                    .await
+            }
+            _ => Ok(None),
+        }
+    }
+
     /// Find a file by path starting from a specific parent
     async fn find_file_by_path_from_parent(
         &self,
@@ -620,11 +767,13 @@ impl GoogleDriveProvider {
                 .list()
                 .q(&query)
                 .page_size(1)
+                .supports_all_drives(true)
+                .include_items_from_all_drives(true)
                 .add_scope(google_drive3::api::Scope::Full)
                 .param("fields", "files(id)")
                 .doit()
                 .await
-                .map_err(|e| format!("Failed to search for file: {}", e))?;
+                .map_err(|e| drive_error("Failed to search for file", e))?;
 
             let files = result.1.files.unwrap_or_default();
             if let Some(file) = files.first() {
@@ -668,7 +817,7 @@ impl GoogleDriveProvider {
             .param("fields", "files(id,name)")
             .doit()
             .await
-            .map_err(|e| format!("Failed to search for shared file: {}", e))?;
+            .map_err(|e| drive_error("Failed to search for shared file", e))?;
 
         let files = result.1.files.unwrap_or_default();
         log::debug!(
@@ -715,7 +864,7 @@ impl GoogleDriveProvider {
             )
             .doit()
             .await
-            .map_err(|e| format!("Failed to get file: {}", e))?;
+            .map_err(|e| drive_error("Failed to get file", e))?;
 
         Ok(result.1)
     }
@@ -828,6 +977,8 @@ impl LocationProvider for GoogleDriveProvider {
             can_move: true,
             supports_watching: false,
             requires_explicit_refresh: true,
+            supports_tags: false,
+            renames_by_copy: false,
         }
     }
 
@@ -835,6 +986,7 @@ impl LocationProvider for GoogleDriveProvider {
         &self,
         location: &Location,
     ) -> Result<ProviderDirectoryEntries, String> {
+        let _permit = crate::locations::throttle::acquire("gdrive").await?;
         let email = self.get_account_email(location)?;
         let path = location.path();
 
@@ -953,6 +1105,7 @@ impl LocationProvider for GoogleDriveProvider {
     }
 
     async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
+        let _permit = crate::locations::throttle::acquire("gdrive").await?;
         let email = self.get_account_email(location)?;
         let path = location.path();
 
@@ -977,6 +1130,10 @@ impl LocationProvider for GoogleDriveProvider {
                     remote_id: None,
                     thumbnail_url: None,
                     download_url: None,
+                    tags: None,
+                    is_git_submodule: false,
+                    lfs_size: None,
+                    id: String::new(),
                 });
             }
         } else {
@@ -997,6 +1154,10 @@ impl LocationProvider for GoogleDriveProvider {
                 remote_id: None,
                 thumbnail_url: None,
                 download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
             });
         }
 
@@ -1004,7 +1165,7 @@ impl LocationProvider for GoogleDriveProvider {
 
         // Find the file by path
         let file_id = self
-            .find_file_by_path(&hub, &subpath)
+            .find_file_by_virtual_path(&hub, root_folder, &subpath)
             .await?
             .ok_or_else(|| format!("File not found: {}", path))?;
 
@@ -1027,25 +1188,22 @@ impl LocationProvider for GoogleDriveProvider {
 
         let (root_folder, subpath) = self.parse_virtual_path(path);
 
-        if root_folder != Some(VIRTUAL_MY_DRIVE) {
-            return Err("Can only create folders in My Drive".to_string());
-        }
-
         if subpath.is_empty() {
             return Err("Cannot create folder at root".to_string());
         }
 
+        let parent_path = &subpath[..subpath.len() - 1];
+        if !Self::is_writable_folder(root_folder, parent_path) {
+            return Err("Can only create folders in My Drive or a shared drive".to_string());
+        }
+
         let hub = self.create_hub(&email).await?;
 
         // Find parent folder
-        let parent_path = &subpath[..subpath.len() - 1];
-        let parent_id = if parent_path.is_empty() {
-            "root".to_string()
-        } else {
-            self.find_file_by_path(&hub, parent_path)
-                .await?
-                .ok_or_else(|| "Parent folder not found".to_string())?
-        };
+        let parent_id = self
+            .find_file_by_virtual_path(&hub, root_folder, parent_path)
+            .await?
+            .ok_or_else(|| "Parent folder not found".to_string())?;
 
         let folder_name = subpath.last().ok_or_else(|| "Invalid path".to_string())?;
 
@@ -1060,14 +1218,27 @@ impl LocationProvider for GoogleDriveProvider {
         let empty_stream = std::io::Cursor::new(Vec::<u8>::new());
         hub.files()
             .create(folder)
+            .supports_all_drives(true)
             .add_scope(google_drive3::api::Scope::Full)
             .upload(empty_stream, "application/octet-stream".parse().unwrap())
             .await
-            .map_err(|e| format!("Failed to create folder: {}", e))?;
+            .map_err(|e| drive_error("Failed to create folder", e))?;
 
         Ok(())
     }
 
+    async fn create_file(
+        &self,
+        directory: &Location,
+        name: &str,
+        contents: &[u8],
+    ) -> Result<String, String> {
+        let email = self.get_account_email(directory)?;
+        let folder_id = get_folder_id_by_path(&email, directory.path()).await?;
+        let staged = StagedFile::write(contents).await?;
+        upload_file_to_gdrive_with_unique_name(&email, staged.path(), &folder_id, name, None).await
+    }
+
     async fn delete(&self, location: &Location) -> Result<(), String> {
         let email = self.get_account_email(location)?;
         let path = location.path();
@@ -1081,7 +1252,7 @@ impl LocationProvider for GoogleDriveProvider {
         let hub = self.create_hub(&email).await?;
 
         let file_id = self
-            .find_file_by_path(&hub, &subpath)
+            .find_file_by_virtual_path(&hub, root_folder, &subpath)
             .await?
             .ok_or_else(|| "File not found".to_string())?;
 
@@ -1093,10 +1264,11 @@ impl LocationProvider for GoogleDriveProvider {
 
         hub.files()
             .update(update, &file_id)
+            .supports_all_drives(true)
             .add_scope(google_drive3::api::Scope::Full)
             .doit_without_upload()
             .await
-            .map_err(|e| format!("Failed to delete: {}", e))?;
+            .map_err(|e| drive_error("Failed to delete", e))?;
 
         Ok(())
     }
@@ -1112,7 +1284,7 @@ impl LocationProvider for GoogleDriveProvider {
         let from_path = from.path();
         let to_path = to.path();
 
-        let (_, from_subpath) = self.parse_virtual_path(from_path);
+        let (from_root, from_subpath) = self.parse_virtual_path(from_path);
         let (_, to_subpath) = self.parse_virtual_path(to_path);
 
         if from_subpath.is_empty() {
@@ -1122,7 +1294,7 @@ impl LocationProvider for GoogleDriveProvider {
         let hub = self.create_hub(&from_email).await?;
 
         let file_id = self
-            .find_file_by_path(&hub, &from_subpath)
+            .find_file_by_virtual_path(&hub, from_root, &from_subpath)
             .await?
             .ok_or_else(|| "Source file not found".to_string())?;
 
@@ -1137,10 +1309,11 @@ impl LocationProvider for GoogleDriveProvider {
 
         hub.files()
             .update(update, &file_id)
+            .supports_all_drives(true)
             .add_scope(google_drive3::api::Scope::Full)
             .doit_without_upload()
             .await
-            .map_err(|e| format!("Failed to rename: {}", e))?;
+            .map_err(|e| drive_error("Failed to rename", e))?;
 
         Ok(())
     }
@@ -1156,33 +1329,30 @@ impl LocationProvider for GoogleDriveProvider {
         let from_path = from.path();
         let to_path = to.path();
 
-        let (_, from_subpath) = self.parse_virtual_path(from_path);
+        let (from_root, from_subpath) = self.parse_virtual_path(from_path);
         let (to_root, to_subpath) = self.parse_virtual_path(to_path);
 
         if from_subpath.is_empty() {
             return Err("Cannot copy virtual root folders".to_string());
         }
 
-        if to_root != Some(VIRTUAL_MY_DRIVE) {
-            return Err("Can only copy to My Drive".to_string());
+        let dest_parent_path = &to_subpath[..to_subpath.len().saturating_sub(1)];
+        if !Self::is_writable_folder(to_root, dest_parent_path) {
+            return Err("Can only copy to My Drive or a shared drive".to_string());
         }
 
         let hub = self.create_hub(&from_email).await?;
 
         let file_id = self
-            .find_file_by_path(&hub, &from_subpath)
+            .find_file_by_virtual_path(&hub, from_root, &from_subpath)
             .await?
             .ok_or_else(|| "Source file not found".to_string())?;
 
         // Find destination parent
-        let dest_parent_path = &to_subpath[..to_subpath.len().saturating_sub(1)];
-        let dest_parent_id = if dest_parent_path.is_empty() {
-            "root".to_string()
-        } else {
-            self.find_file_by_path(&hub, dest_parent_path)
-                .await?
-                .ok_or_else(|| "Destination folder not found".to_string())?
-        };
+        let dest_parent_id = self
+            .find_file_by_virtual_path(&hub, to_root, dest_parent_path)
+            .await?
+            .ok_or_else(|| "Destination folder not found".to_string())?;
 
         let new_name = to_subpath
             .last()
@@ -1196,10 +1366,11 @@ impl LocationProvider for GoogleDriveProvider {
 
         hub.files()
             .copy(copy_request, &file_id)
+            .supports_all_drives(true)
             .add_scope(google_drive3::api::Scope::Full)
             .doit()
             .await
-            .map_err(|e| format!("Failed to copy: {}", e))?;
+            .map_err(|e| drive_error("Failed to copy", e))?;
 
         Ok(())
     }
@@ -1215,21 +1386,22 @@ impl LocationProvider for GoogleDriveProvider {
         let from_path = from.path();
         let to_path = to.path();
 
-        let (_, from_subpath) = self.parse_virtual_path(from_path);
+        let (from_root, from_subpath) = self.parse_virtual_path(from_path);
         let (to_root, to_subpath) = self.parse_virtual_path(to_path);
 
         if from_subpath.is_empty() {
             return Err("Cannot move virtual root folders".to_string());
         }
 
-        if to_root != Some(VIRTUAL_MY_DRIVE) {
-            return Err("Can only move to My Drive".to_string());
+        let dest_parent_path = &to_subpath[..to_subpath.len().saturating_sub(1)];
+        if !Self::is_writable_folder(to_root, dest_parent_path) {
+            return Err("Can only move to My Drive or a shared drive".to_string());
         }
 
         let hub = self.create_hub(&from_email).await?;
 
         let file_id = self
-            .find_file_by_path(&hub, &from_subpath)
+            .find_file_by_virtual_path(&hub, from_root, &from_subpath)
             .await?
             .ok_or_else(|| "Source file not found".to_string())?;
 
@@ -1238,14 +1410,10 @@ impl LocationProvider for GoogleDriveProvider {
         let current_parents = file.parents.unwrap_or_default();
 
         // Find destination parent
-        let dest_parent_path = &to_subpath[..to_subpath.len().saturating_sub(1)];
-        let dest_parent_id = if dest_parent_path.is_empty() {
-            "root".to_string()
-        } else {
-            self.find_file_by_path(&hub, dest_parent_path)
-                .await?
-                .ok_or_else(|| "Destination folder not found".to_string())?
-        };
+        let dest_parent_id = self
+            .find_file_by_virtual_path(&hub, to_root, dest_parent_path)
+            .await?
+            .ok_or_else(|| "Destination folder not found".to_string())?;
 
         let new_name = to_subpath
             .last()
@@ -1261,13 +1429,85 @@ impl LocationProvider for GoogleDriveProvider {
             .update(update, &file_id)
             .add_parents(&dest_parent_id)
             .remove_parents(&current_parents.join(","))
+            .supports_all_drives(true)
             .add_scope(google_drive3::api::Scope::Full)
             .doit_without_upload()
             .await
-            .map_err(|e| format!("Failed to move: {}", e))?;
+            .map_err(|e| drive_error("Failed to move", e))?;
 
         Ok(())
     }
+
+    /// Recursive substring searches in My Drive use Drive's own name search, one query
+    /// for the whole Drive, keeping the hits that sit under `location`. Drive matches
+    /// names by word prefix ("rep" finds "Q3 report.pdf", "port" doesn't), the price for
+    /// not listing every folder. Other searches walk folders like any provider.
+    async fn search(
+        &self,
+        location: &Location,
+        query: &SearchQuery,
+        cancel: Arc<AtomicBool>,
+        sink: SearchSink,
+    ) -> Result<usize, String> {
+        let (root, _) = self.parse_virtual_path(location.path());
+        let options = query.options();
+        if root != Some(VIRTUAL_MY_DRIVE) || !options.recursive || options.glob {
+            return walk_search(self, location, query, &cancel, &sink).await;
+        }
+
+        let _permit = crate::locations::throttle::acquire("gdrive").await?;
+        let email = self.get_account_email(location)?;
+        let hub = self.create_hub(&email).await?;
+        let drive_query = format!(
+            "name contains '{}' and trashed = false",
+            query.text().replace('\\', "\\\\").replace('\'', "\\'")
+        );
+        let list_options = ListOptions {
+            limit: Some(SEARCH_CANDIDATE_LIMIT),
+            ..Default::default()
+        };
+        let files = list_all_pages(&hub, &drive_query, list_options)
+            .await
+            .map_err(|e| drive_error("Failed to search Drive", e))?;
+
+        let account_prefix = format!("gdrive://{}", email);
+        let scope = format!(
+            "{}{}/",
+            account_prefix,
+            location.path().trim_end_matches('/')
+        );
+        let limit = query.limit();
+        let mut found = 0;
+        let mut batch = Vec::new();
+        for file in &files {
+            if cancel.load(Ordering::Relaxed) || found >= limit {
+                break;
+            }
+            if !query.matches(file.name.as_deref().unwrap_or_default()) {
+                continue;
+            }
+            // Items outside My Drive come back as id paths and never fall in scope
+            let Ok(path) = self.build_file_path(&hub, file, &email).await else {
+                continue;
+            };
+            if !path.starts_with(&scope) {
+                continue;
+            }
+            let Some((parent_path, _)) = path[account_prefix.len()..].rsplit_once('/') else {
+                continue;
+            };
+            batch.push(self.drive_file_to_file_item(file, &email, parent_path));
+            found += 1;
+            if batch.len() >= SEARCH_BATCH_SIZE {
+                sink(std::mem::take(&mut batch));
+            }
+        }
+        if !batch.is_empty() {
+            sink(batch);
+        }
+
+        Ok(found)
+    }
 }
 
 /// Resolve a Google Drive file ID to a full path, trying all connected accounts
@@ -1371,10 +1611,15 @@ async fn try_resolve_folder_id(email: &str, folder_id: &str) -> Result<(String,
         .bearer_auth(&access_token)
         .send()
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| LocationError::from_request("Request failed", &e).to_string())?;
 
     if !response.status().is_success() {
-        return Err(format!("API error: {}", response.status()));
+        let status = response.status();
+        return Err(LocationError::from_http_status(
+            status.as_u16(),
+            format!("API error: {}", status),
+        )
+        .to_string());
     }
 
     let metadata: serde_json::Value = response
@@ -1440,10 +1685,15 @@ async fn get_shared_drive_name(access_token: &str, drive_id: &str) -> Result<Str
         .bearer_auth(access_token)
         .send()
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| LocationError::from_request("Request failed", &e).to_string())?;
 
     if !response.status().is_success() {
-        return Err(format!("API error: {}", response.status()));
+        let status = response.status();
+        return Err(LocationError::from_http_status(
+            status.as_u16(),
+            format!("API error: {}", status),
+        )
+        .to_string());
     }
 
     let data: serde_json::Value = response
@@ -1479,7 +1729,7 @@ async fn build_shared_drive_path(
             .bearer_auth(access_token)
             .send()
             .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+            .map_err(|e| LocationError::from_request("Request failed", &e).to_string())?;
 
         if !response.status().is_success() {
             return Err("Cannot access parent".to_string());
@@ -1553,7 +1803,7 @@ async fn build_path_from_parents(folder_id: &str, access_token: &str) -> Result<
             .bearer_auth(access_token)
             .send()
             .await
-            .map_err(|e| format!("Request failed: {}", e))?;
+            .map_err(|e| LocationError::from_request("Request failed", &e).to_string())?;
 
         if !response.status().is_success() {
             return Err("Cannot access parent".to_string());
@@ -1614,11 +1864,15 @@ pub async fn fetch_url_with_auth(email: &str, url: &str) -> Result<String, Strin
         .bearer_auth(&access_token)
         .send()
         .await
-        .map_err(|e| format!("Failed to fetch URL: {}", e))?;
+        .map_err(|e| LocationError::from_request("Failed to fetch URL", &e).to_string())?;
 
     if !response.status().is_success() {
         let status = response.status();
-        return Err(format!("Fetch failed with status {}", status));
+        return Err(LocationError::from_http_status(
+            status.as_u16(),
+            format!("Fetch failed with status {}", status),
+        )
+        .to_string());
     }
 
     let content_type = response
@@ -1660,15 +1914,181 @@ fn sanitize_filename(name: &str) -> String {
         .to_string()
 }
 
+/// What native Google files (Docs, Sheets, Slides) become when downloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GoogleExportFormat {
+    /// docx, xlsx and pptx
+    Office,
+    Pdf,
+}
+
+static EXPORT_FORMAT: Lazy<RwLock<GoogleExportFormat>> =
+    Lazy::new(|| RwLock::new(GoogleExportFormat::Office));
+
+pub fn set_export_format(format: GoogleExportFormat) {
+    if let Ok(mut current) = EXPORT_FORMAT.write() {
+        *current = format;
+    }
+}
+
+fn export_format() -> GoogleExportFormat {
+    EXPORT_FORMAT
+        .read()
+        .map(|format| *format)
+        .unwrap_or(GoogleExportFormat::Office)
+}
+
+const GOOGLE_APPS_MIME_PREFIX: &str = "application/vnd.google-apps.";
+const PDF_MIME: &str = "application/pdf";
+
+/// The export MIME type and extension for a native Google file, or `None` for files
+/// with ordinary binary content. Google types with no export (Forms, Sites, ...) are an error.
+pub(super) fn export_target(
+    mime_type: &str,
+    format: GoogleExportFormat,
+) -> Result<Option<(&'static str, &'static str)>, String> {
+    let Some(kind) = mime_type.strip_prefix(GOOGLE_APPS_MIME_PREFIX) else {
+        return Ok(None);
+    };
+    let pdf = format == GoogleExportFormat::Pdf;
+    let target = match kind {
+        "document" if pdf => (PDF_MIME, "pdf"),
+        "document" => (
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+            "docx",
+        ),
+        "spreadsheet" if pdf => (PDF_MIME, "pdf"),
+        "spreadsheet" => (
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            "xlsx",
+        ),
+        "presentation" if pdf => (PDF_MIME, "pdf"),
+        "presentation" => (
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+            "pptx",
+        ),
+        "drawing" if pdf => (PDF_MIME, "pdf"),
+        "drawing" => ("image/png", "png"),
+        _ => {
+            return Err(format!(
+                "Google Drive can't export this file ({}); open it in Google Drive instead",
+                mime_type
+            ))
+        }
+    };
+    Ok(Some(target))
+}
+
+async fn fetch_mime_type(access_token: &str, file_id: &str) -> Result<String, String> {
+    let url = format!(
+        "https://www.googleapis.com/drive/v3/files/{}?fields=mimeType&supportsAllDrives=true",
+        file_id
+    );
+    let response = reqwest::Client::new()
+        .get(&url)
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .map_err(|e| LocationError::from_request("Request failed", &e).to_string())?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        return Err(LocationError::from_http_status(
+            status.as_u16(),
+            format!("API error: {}", status),
+        )
+        .to_string());
+    }
+
+    let metadata: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    Ok(metadata["mimeType"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Fetch the content of a Google Drive file, or its export when `export_mime` is set.
+/// With `max_bytes`, only that much from the start of the file is requested; exports
+/// can't be ranged and always come back whole.
+async fn fetch_file_content(
+    access_token: &str,
+    file_id: &str,
+    export_mime: Option<&str>,
+    max_bytes: Option<u64>,
+) -> Result<bytes::Bytes, String> {
+    // Download using direct HTTPS request with the access token
+    // The Google Drive API download endpoint: https://www.googleapis.com/drive/v3/files/{fileId}?alt=media
+    // Native Google files have no content there and go through /export instead
+    let download_url = match export_mime {
+        Some(mime) => format!(
+            "https://www.googleapis.com/drive/v3/files/{}/export?mimeType={}",
+            file_id,
+            urlencoding::encode(mime)
+        ),
+        None => format!(
+            "https://www.googleapis.com/drive/v3/files/{}?alt=media&supportsAllDrives=true",
+            file_id
+        ),
+    };
+
+    let client = reqwest::Client::new();
+    let mut request = client.get(&download_url).bearer_auth(access_token);
+    if let (Some(max_bytes), None) = (max_bytes, export_mime) {
+        request = request.header("Range", format!("bytes=0-{}", max_bytes.saturating_sub(1)));
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| LocationError::from_request("Failed to download file", &e).to_string())?;
+
+    // An empty file has no byte range to return
+    if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && max_bytes.is_some() {
+        return Ok(bytes::Bytes::new());
+    }
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        log::error!("Download failed with status {}: {}", status, body);
+        return Err(LocationError::from_http_status(
+            status.as_u16(),
+            format!(
+                "Download failed (status {}). Check logs for details.",
+                status
+            ),
+        )
+        .to_string());
+    }
+
+    response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read file content: {}", e))
+}
+
+/// Whether a cached download at `path` is recent enough to reuse (within 1 hour).
+/// This prevents re-downloading the same archive for every directory listing.
+fn is_fresh_download(path: &std::path::Path) -> bool {
+    const CACHE_TTL: StdDuration = StdDuration::from_secs(60 * 60);
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age <= CACHE_TTL)
+}
+
 /// Download a Google Drive file to a temporary location and return the path
-/// This is used for opening files that need to be downloaded first
+/// This is used for opening files that need to be downloaded first. Native Google
+/// files are exported and get the export's extension, so they open in the right app.
 pub async fn download_file_to_temp(
     email: &str,
     file_id: &str,
     file_name: &str,
 ) -> Result<String, String> {
     use std::io::Write;
-    use std::time::{Duration, SystemTime};
 
     log::info!(
         "download_file_to_temp: email={}, file_id={}, name={}",
@@ -1689,74 +2109,114 @@ pub async fn download_file_to_temp(
     let temp_path = temp_dir.join(format!("{}_{}", file_id, safe_name));
     let temp_path_str = temp_path.to_string_lossy().to_string();
 
-    // Check if we have a recent cached version (within 1 hour)
-    // This prevents re-downloading the same archive for every directory listing
-    const CACHE_TTL: Duration = Duration::from_secs(60 * 60); // 1 hour
-    if temp_path.exists() {
-        if let Ok(metadata) = std::fs::metadata(&temp_path) {
-            if let Ok(modified) = metadata.modified() {
-                if let Ok(age) = SystemTime::now().duration_since(modified) {
-                    if age <= CACHE_TTL {
-                        log::info!("Using cached file: {} (age: {:?})", temp_path_str, age);
-                        return Ok(temp_path_str);
-                    }
-                }
-            }
-        }
+    // Ordinary files are cached under their own name, so a hit needs no API calls
+    if is_fresh_download(&temp_path) {
+        log::info!("Using cached file: {}", temp_path_str);
+        return Ok(temp_path_str);
     }
 
-    // Get the access token
     let access_token = ensure_valid_token(email).await?;
+    let mime_type = fetch_mime_type(&access_token, file_id).await?;
+    let export = export_target(&mime_type, export_format())?;
+    let temp_path = match export {
+        Some((_, extension)) => temp_dir.join(format!("{}_{}.{}", file_id, safe_name, extension)),
+        None => temp_path,
+    };
+    let temp_path_str = temp_path.to_string_lossy().to_string();
+    if export.is_some() && is_fresh_download(&temp_path) {
+        log::info!("Using cached export: {}", temp_path_str);
+        return Ok(temp_path_str);
+    }
 
-    // Download using direct HTTPS request with the access token
-    // The Google Drive API download endpoint: https://www.googleapis.com/drive/v3/files/{fileId}?alt=media
-    let download_url = format!(
-        "https://www.googleapis.com/drive/v3/files/{}?alt=media&supportsAllDrives=true",
-        file_id
-    );
-
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&download_url)
-        .bearer_auth(&access_token)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download file: {}", e))?;
+    let bytes =
+        fetch_file_content(&access_token, file_id, export.map(|(mime, _)| mime), None).await?;
+    let byte_count = bytes.len();
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let body = response.text().await.unwrap_or_default();
-        log::error!("Download failed with status {}: {}", status, body);
-        return Err(format!(
-            "Download failed (status {}). Check logs for details.",
-            status
-        ));
-    }
+    // Publish via a unique .part file so the cache check above never sees a partial file
+    crate::locations::transfer::download_to_stable_path(&temp_path, |part_path| async move {
+        let mut file = std::fs::File::create(&part_path)
+            .map_err(|e| format!("Failed to create temp file: {}", e))?;
+        file.write_all(&bytes)
+            .map_err(|e| format!("Failed to write temp file: {}", e))
+    })
+    .await?;
 
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Failed to read file content: {}", e))?;
+    log::info!("Downloaded {} bytes to {}", byte_count, temp_path_str);
 
-    let mut file = std::fs::File::create(&temp_path)
-        .map_err(|e| format!("Failed to create temp file: {}", e))?;
+    Ok(temp_path_str)
+}
 
-    file.write_all(&bytes)
-        .map_err(|e| format!("Failed to write temp file: {}", e))?;
+/// Download a Google Drive file to a new temp file, bypassing the cache used by
+/// [`download_file_to_temp`]. The caller owns the returned file and should remove it.
+pub async fn download_file_uncached(
+    email: &str,
+    file_id: &str,
+    file_name: &str,
+) -> Result<std::path::PathBuf, String> {
+    let temp_dir = std::env::temp_dir().join("marlin-gdrive-cache");
+    tokio::fs::create_dir_all(&temp_dir)
+        .await
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let access_token = ensure_valid_token(email).await?;
+    let mime_type = fetch_mime_type(&access_token, file_id).await?;
+    let export = export_target(&mime_type, export_format())?;
+    let mut temp_name = format!(
+        "{}_{}",
+        uuid::Uuid::new_v4().simple(),
+        sanitize_filename(file_name)
+    );
+    if let Some((_, extension)) = export {
+        temp_name = format!("{}.{}", temp_name, extension);
+    }
+    let temp_path = temp_dir.join(temp_name);
 
-    log::info!("Downloaded {} bytes to {}", bytes.len(), temp_path_str);
+    let bytes =
+        fetch_file_content(&access_token, file_id, export.map(|(mime, _)| mime), None).await?;
+    if let Err(e) = tokio::fs::write(&temp_path, &bytes).await {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        return Err(format!("Failed to write temp file: {}", e));
+    }
+    Ok(temp_path)
+}
 
-    Ok(temp_path_str)
+/// Download at most `max_bytes` from the start of a Google Drive file to `dest`.
+pub async fn download_file_head(
+    email: &str,
+    file_id: &str,
+    dest: &std::path::Path,
+    max_bytes: u64,
+) -> Result<(), String> {
+    let access_token = ensure_valid_token(email).await?;
+    let mime_type = fetch_mime_type(&access_token, file_id).await?;
+    let export = export_target(&mime_type, export_format())?;
+    let bytes = fetch_file_content(
+        &access_token,
+        file_id,
+        export.map(|(mime, _)| mime),
+        Some(max_bytes),
+    )
+    .await?;
+    let head = &bytes[..bytes.len().min(max_bytes as usize)];
+    tokio::fs::write(dest, head)
+        .await
+        .map_err(|e| format!("Failed to write temp file: {}", e))
 }
 
+/// Bytes sent per request in a resumable upload; Drive wants multiples of 256 KiB
+const UPLOAD_CHUNK_SIZE: u64 = 32 * 256 * 1024;
+
 /// Upload a local file to Google Drive
-/// Returns the file ID of the uploaded file
+/// Returns the file ID of the uploaded file. The file goes up through a resumable
+/// session one chunk at a time, so `progress` can follow it and cancel it.
 pub async fn upload_file_to_gdrive(
     email: &str,
     local_path: &std::path::Path,
     parent_folder_id: &str,
     file_name: &str,
+    progress: Option<&TransferProgress>,
 ) -> Result<String, String> {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
     log::info!(
         "upload_file_to_gdrive: email={}, local_path={:?}, parent={}, name={}",
         email,
@@ -1767,9 +2227,14 @@ pub async fn upload_file_to_gdrive(
 
     let access_token = ensure_valid_token(email).await?;
 
-    // Read the file content
-    let file_content =
-        std::fs::read(local_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut file = tokio::fs::File::open(local_path)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let total = file
+        .metadata()
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?
+        .len();
 
     // Determine MIME type based on extension
     let mime_type = mime_guess::from_path(local_path)
@@ -1782,30 +2247,14 @@ pub async fn upload_file_to_gdrive(
         "parents": [parent_folder_id]
     });
 
-    // Use multipart upload
+    // Open a resumable session; its URL is where the content goes
     let client = reqwest::Client::new();
-    let boundary = "----WebKitFormBoundary7MA4YWxkTrZu0gW";
-
-    let mut body = Vec::new();
-
-    // Metadata part
-    body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
-    body.extend_from_slice(b"Content-Type: application/json; charset=UTF-8\r\n\r\n");
-    body.extend_from_slice(metadata.to_string().as_bytes());
-    body.extend_from_slice(b"\r\n");
-
-    // File content part
-    body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
-    body.extend_from_slice(format!("Content-Type: {}\r\n\r\n", mime_type).as_bytes());
-    body.extend_from_slice(&file_content);
-    body.extend_from_slice(b"\r\n");
-    body.extend_from_slice(format!("--{}--", boundary).as_bytes());
-
     let response = client
-        .post("https://www.googleapis.com/upload/drive/v3/files?uploadType=multipart&supportsAllDrives=true")
+        .post("https://www.googleapis.com/upload/drive/v3/files?uploadType=resumable&supportsAllDrives=true")
         .bearer_auth(&access_token)
-        .header("Content-Type", format!("multipart/related; boundary={}", boundary))
-        .body(body)
+        .header("X-Upload-Content-Type", &mime_type)
+        .header("X-Upload-Content-Length", total)
+        .json(&metadata)
         .send()
         .await
         .map_err(|e| format!("Failed to upload file: {}", e))?;
@@ -1814,10 +2263,82 @@ pub async fn upload_file_to_gdrive(
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
         log::error!("Upload failed with status {}: {}", status, body);
-        return Err(format!(
-            "Upload failed (status {}). Check logs for details.",
-            status
-        ));
+        return Err(LocationError::from_http_status(
+            status.as_u16(),
+            format!("Upload failed (status {}). Check logs for details.", status),
+        )
+        .to_string());
+    }
+
+    let session_url = response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| "No upload session in response".to_string())?
+        .to_string();
+
+    let mut sent = 0u64;
+    let mut buffer = vec![0u8; UPLOAD_CHUNK_SIZE as usize];
+    let response = loop {
+        let len = (total - sent).min(UPLOAD_CHUNK_SIZE) as usize;
+        file.read_exact(&mut buffer[..len])
+            .await
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        let content_range = if total == 0 {
+            "bytes */0".to_string()
+        } else {
+            format!("bytes {}-{}/{}", sent, sent + len as u64 - 1, total)
+        };
+
+        let response = client
+            .put(&session_url)
+            .bearer_auth(&access_token)
+            .header(reqwest::header::CONTENT_RANGE, content_range)
+            .body(buffer[..len].to_vec())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload file: {}", e))?;
+
+        // 308 means Drive wants more; `Range` says how much it kept
+        if response.status().as_u16() != 308 {
+            break response;
+        }
+        sent = response
+            .headers()
+            .get(reqwest::header::RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|range| range.rsplit('-').next())
+            .and_then(|end| end.parse::<u64>().ok())
+            .map_or(0, |end| end + 1);
+        file.seek(std::io::SeekFrom::Start(sent))
+            .await
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+
+        if let Some(progress) = progress {
+            if let Err(err) = progress.update(sent) {
+                // Drive drops the partial upload when its session is deleted
+                let _ = client
+                    .delete(&session_url)
+                    .bearer_auth(&access_token)
+                    .send()
+                    .await;
+                return Err(err);
+            }
+        }
+    };
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        log::error!("Upload failed with status {}: {}", status, body);
+        return Err(LocationError::from_http_status(
+            status.as_u16(),
+            format!("Upload failed (status {}). Check logs for details.", status),
+        )
+        .to_string());
+    }
+    if let Some(progress) = progress {
+        let _ = progress.update(total);
     }
 
     let result: serde_json::Value = response
@@ -1864,16 +2385,20 @@ pub async fn create_gdrive_folder(
         .body(metadata.to_string())
         .send()
         .await
-        .map_err(|e| format!("Failed to create folder: {}", e))?;
+        .map_err(|e| drive_error("Failed to create folder", e))?;
 
     if !response.status().is_success() {
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
         log::error!("Create folder failed with status {}: {}", status, body);
-        return Err(format!(
-            "Create folder failed (status {}). Check logs for details.",
-            status
-        ));
+        return Err(LocationError::from_http_status(
+            status.as_u16(),
+            format!(
+                "Create folder failed (status {}). Check logs for details.",
+                status
+            ),
+        )
+        .to_string());
     }
 
     let result: serde_json::Value = response
@@ -1930,7 +2455,7 @@ pub async fn upload_directory_to_gdrive(
         if path.is_dir() {
             Box::pin(upload_directory_to_gdrive(email, &path, &folder_id)).await?;
         } else {
-            upload_file_to_gdrive(email, &path, &folder_id, name).await?;
+            upload_file_to_gdrive(email, &path, &folder_id, name, None).await?;
         }
     }
 
@@ -2031,7 +2556,7 @@ pub async fn extract_gdrive_zip(
         if path.is_dir() {
             upload_directory_to_gdrive(email, &path, &dest_folder_id).await?;
         } else {
-            upload_file_to_gdrive(email, &path, &dest_folder_id, name).await?;
+            upload_file_to_gdrive(email, &path, &dest_folder_id, name, None).await?;
         }
     }
 
@@ -2047,7 +2572,8 @@ pub async fn extract_gdrive_zip(
 }
 
 /// Look up the folder ID for a given Google Drive path
-/// Path format: /My Drive/folder1/folder2 or /Shared with me/folder1
+/// Path format: /My Drive/folder1/folder2, /Shared with me/folder1 or
+/// /Shared drives/Drive name/folder1
 pub async fn get_folder_id_by_path(email: &str, path: &str) -> Result<String, String> {
     log::info!("get_folder_id_by_path: email={}, path={}", email, path);
 
@@ -2078,6 +2604,16 @@ pub async fn get_folder_id_by_path(email: &str, path: &str) -> Result<String, St
                     .ok_or_else(|| format!("Folder not found: {}", path))
             }
         }
+        Some(VIRTUAL_SHARED_DRIVES) => {
+            if subpath.is_empty() {
+                Err("Cannot get folder ID for Shared drives root".to_string())
+            } else {
+                provider
+                    .find_file_by_virtual_path(&hub, root_folder, &subpath)
+                    .await?
+                    .ok_or_else(|| format!("Folder not found: {}", path))
+            }
+        }
         Some(VIRTUAL_BY_ID) => {
             // Direct ID navigation - the ID is in the path
             if subpath.is_empty() {
@@ -2090,6 +2626,37 @@ pub async fn get_folder_id_by_path(email: &str, path: &str) -> Result<String, St
     }
 }
 
+/// Parent of a Drive location within the virtual folder layout. Top-level virtual
+/// folders go back to the account root; ID-based folders don't encode their
+/// ancestry, so they return to "Shared with me" where they are reached from.
+pub fn parent_location(location: &Location) -> Option<LocationSummary> {
+    let email = location.authority()?.to_string();
+    let parts: Vec<&str> = location
+        .path()
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    let parent_path = match parts.as_slice() {
+        [] => return None,
+        [_] => "/".to_string(),
+        [VIRTUAL_BY_ID, _] => format!("/{}", VIRTUAL_SHARED),
+        [ancestors @ .., _] => format!("/{}", ancestors.join("/")),
+    };
+    let display_path = if parent_path == "/" {
+        email.clone()
+    } else {
+        format!("{}{}", email, parent_path)
+    };
+
+    Some(LocationSummary::new(
+        "gdrive",
+        Some(email),
+        parent_path,
+        display_path,
+    ))
+}
+
 /// Get the file ID for a Google Drive path (file or folder).
 /// This is used when we need to operate on items referenced by `gdrive://` paths (e.g. download/delete).
 pub async fn get_file_id_by_path(email: &str, path: &str) -> Result<String, String> {
@@ -2121,6 +2688,16 @@ pub async fn get_file_id_by_path(email: &str, path: &str) -> Result<String, Stri
                     .ok_or_else(|| format!("File not found: {}", path))
             }
         }
+        Some(VIRTUAL_SHARED_DRIVES) => {
+            if subpath.is_empty() {
+                Err("Cannot get file ID for Shared drives root".to_string())
+            } else {
+                provider
+                    .find_file_by_virtual_path(&hub, root_folder, &subpath)
+                    .await?
+                    .ok_or_else(|| format!("File not found: {}", path))
+            }
+        }
         Some(VIRTUAL_BY_ID) => {
             if subpath.is_empty() {
                 Err("Missing file ID in path".to_string())
@@ -2164,6 +2741,39 @@ pub async fn name_exists_in_folder(
     Ok(!result.1.files.unwrap_or_default().is_empty())
 }
 
+/// Upload a local file into a Drive folder under the first free name among
+/// `preferred_name`, "name (2).ext", ... Drive allows duplicate names, but paths
+/// need to stay unique. Returns the name used.
+pub async fn upload_file_to_gdrive_with_unique_name(
+    email: &str,
+    local_path: &std::path::Path,
+    parent_folder_id: &str,
+    preferred_name: &str,
+    progress: Option<&TransferProgress>,
+) -> Result<String, String> {
+    let p = std::path::Path::new(preferred_name);
+    let stem = p
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(preferred_name);
+    let ext = p.extension().and_then(|e| e.to_str());
+    for i in 1..1000usize {
+        let candidate = if i == 1 {
+            preferred_name.to_string()
+        } else if let Some(e) = ext {
+            format!("{stem} ({i}).{e}")
+        } else {
+            format!("{stem} ({i})")
+        };
+        if !name_exists_in_folder(email, parent_folder_id, &candidate).await? {
+            upload_file_to_gdrive(email, local_path, parent_folder_id, &candidate, progress)
+                .await?;
+            return Ok(candidate);
+        }
+    }
+    Err("Unable to allocate unique destination name on Google Drive".to_string())
+}
+
 // Suppress warnings for unused cache variables (will be used for optimization)
 #[allow(dead_code)]
 fn _use_caches() {
@@ -2171,3 +2781,21 @@ fn _use_caches() {
     let _ = &*PATH_CACHE;
     let _ = CACHE_TTL;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drive_errors_are_coded_by_status() {
+        let code = |status: u64| {
+            let body = serde_json::json!({ "error": { "code": status, "message": "nope" } });
+            let error = drive_error("Failed to get file", google_drive3::Error::BadRequest(body));
+            LocationError::from_message(error).code().to_string()
+        };
+        assert_eq!(code(404), "ENOENT");
+        assert_eq!(code(403), "EPERM");
+        assert_eq!(code(503), "ENETWORK");
+        assert_eq!(code(400), "EOTHER");
+    }
+}
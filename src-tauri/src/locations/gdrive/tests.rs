@@ -246,4 +246,26 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_export_targets_for_native_google_files() {
+        use crate::locations::gdrive::provider::{export_target, GoogleExportFormat};
+
+        let office = GoogleExportFormat::Office;
+        assert_eq!(export_target("image/png", office), Ok(None));
+        assert_eq!(
+            export_target("application/vnd.google-apps.spreadsheet", office)
+                .unwrap()
+                .map(|(_, ext)| ext),
+            Some("xlsx")
+        );
+        assert_eq!(
+            export_target(
+                "application/vnd.google-apps.document",
+                GoogleExportFormat::Pdf
+            ),
+            Ok(Some(("application/pdf", "pdf")))
+        );
+        assert!(export_target("application/vnd.google-apps.form", office).is_err());
+    }
 }
@@ -1,32 +1,47 @@
 use async_trait::async_trait;
+use globset::{GlobBuilder, GlobMatcher};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-use crate::fs_utils::FileItem;
+use crate::fs_utils::{DirectoryStats, FileItem};
 
 pub mod archive;
+pub mod error;
 mod file;
 pub mod gdrive;
+pub mod mtp;
+pub mod recent;
 pub mod sftp;
 #[cfg(not(target_os = "windows"))]
 pub mod smb;
+pub mod throttle;
+pub mod transfer;
+pub mod trash;
+pub mod webdav;
 
 pub use archive::ArchiveProvider;
+pub use error::LocationError;
 pub use file::FileSystemProvider;
 pub use gdrive::GoogleDriveProvider;
+pub use mtp::MtpProvider;
+pub use recent::RecentProvider;
 pub use sftp::SftpProvider;
 #[cfg(not(target_os = "windows"))]
 pub use smb::SmbProvider;
+pub use trash::TrashProvider;
+pub use webdav::WebDavProvider;
 
 pub type ProviderRef = Arc<dyn LocationProvider + Send + Sync>;
 
 type ProviderMap = HashMap<String, ProviderRef>;
 
 static REGISTRY: Lazy<RwLock<ProviderMap>> = Lazy::new(|| {
-    let mut map = HashMap::new();
+    let mut map: ProviderMap = HashMap::new();
     let file_provider: ProviderRef = Arc::new(FileSystemProvider::default());
     map.insert(file_provider.scheme().to_string(), file_provider);
     let archive_provider: ProviderRef = Arc::new(ArchiveProvider::default());
@@ -35,14 +50,132 @@ static REGISTRY: Lazy<RwLock<ProviderMap>> = Lazy::new(|| {
     map.insert(gdrive_provider.scheme().to_string(), gdrive_provider);
     let sftp_provider: ProviderRef = Arc::new(SftpProvider::default());
     map.insert(sftp_provider.scheme().to_string(), sftp_provider);
+    let webdav_provider: ProviderRef = Arc::new(WebDavProvider::default());
+    map.insert(webdav_provider.scheme().to_string(), webdav_provider);
+    let mtp_provider: ProviderRef = Arc::new(MtpProvider::default());
+    map.insert(mtp_provider.scheme().to_string(), mtp_provider);
+    let trash_provider: ProviderRef = Arc::new(TrashProvider::default());
+    map.insert(trash_provider.scheme().to_string(), trash_provider);
+    let recent_provider: ProviderRef = Arc::new(RecentProvider::default());
+    map.insert(recent_provider.scheme().to_string(), recent_provider);
     #[cfg(not(target_os = "windows"))]
     {
         let smb_provider: ProviderRef = Arc::new(SmbProvider::default());
         map.insert(smb_provider.scheme().to_string(), smb_provider);
     }
+    let map = map
+        .into_iter()
+        .map(|(scheme, provider)| {
+            let coded: ProviderRef = Arc::new(CodedProvider(provider));
+            (scheme, coded)
+        })
+        .collect();
     RwLock::new(map)
 });
 
+/// Registry wrapper that gives every error leaving a provider a `LocationError` code,
+/// so providers that only pass a library's message along still report
+/// `ENOENT`/`EPERM`/`ENETWORK` like the ones that set codes themselves.
+struct CodedProvider(ProviderRef);
+
+fn coded<T>(result: Result<T, String>) -> Result<T, String> {
+    result.map_err(|err| LocationError::from_message(err).to_string())
+}
+
+/// Give entries from providers without file ids their path as one.
+fn with_path_id(mut item: FileItem) -> FileItem {
+    if item.id.is_empty() {
+        item.id = item.path.clone();
+    }
+    item
+}
+
+fn with_path_ids(mut listing: ProviderDirectoryEntries) -> ProviderDirectoryEntries {
+    listing.entries = listing.entries.into_iter().map(with_path_id).collect();
+    listing
+}
+
+#[async_trait]
+impl LocationProvider for CodedProvider {
+    fn scheme(&self) -> &'static str {
+        self.0.scheme()
+    }
+
+    fn capabilities(&self, location: &Location) -> LocationCapabilities {
+        self.0.capabilities(location)
+    }
+
+    async fn read_directory(
+        &self,
+        location: &Location,
+    ) -> Result<ProviderDirectoryEntries, String> {
+        coded(self.0.read_directory(location).await).map(with_path_ids)
+    }
+
+    async fn read_directory_filtered(
+        &self,
+        location: &Location,
+        filter: &EntryFilter,
+    ) -> Result<ProviderDirectoryEntries, String> {
+        coded(self.0.read_directory_filtered(location, filter).await).map(with_path_ids)
+    }
+
+    async fn search(
+        &self,
+        location: &Location,
+        query: &SearchQuery,
+        cancel: Arc<AtomicBool>,
+        sink: SearchSink,
+    ) -> Result<usize, String> {
+        coded(self.0.search(location, query, cancel, sink).await)
+    }
+
+    async fn child_count(&self, location: &Location) -> Result<u64, String> {
+        coded(self.0.child_count(location).await)
+    }
+
+    async fn directory_stats(&self, location: &Location) -> Result<DirectoryStats, String> {
+        coded(self.0.directory_stats(location).await)
+    }
+
+    async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
+        coded(self.0.get_file_metadata(location).await).map(with_path_id)
+    }
+
+    async fn create_directory(&self, location: &Location) -> Result<(), String> {
+        coded(self.0.create_directory(location).await)
+    }
+
+    async fn delete(&self, location: &Location) -> Result<(), String> {
+        coded(self.0.delete(location).await)
+    }
+
+    async fn rename(&self, from: &Location, to: &Location) -> Result<(), String> {
+        coded(self.0.rename(from, to).await)
+    }
+
+    async fn copy(&self, from: &Location, to: &Location) -> Result<(), String> {
+        coded(self.0.copy(from, to).await)
+    }
+
+    async fn move_item(&self, from: &Location, to: &Location) -> Result<(), String> {
+        coded(self.0.move_item(from, to).await)
+    }
+
+    async fn ensure_directory(&self, location: &Location) -> Result<(), String> {
+        coded(self.0.ensure_directory(location).await)
+    }
+
+    async fn create_file(
+        &self,
+        directory: &Location,
+        name: &str,
+        contents: &[u8],
+    ) -> Result<String, String> {
+        coded(self.0.create_file(directory, name, contents).await)
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LocationSummary {
@@ -89,6 +222,11 @@ pub struct LocationCapabilities {
     pub can_move: bool,
     pub supports_watching: bool,
     pub requires_explicit_refresh: bool,
+    /// Whether files here can carry Finder-style tags
+    pub supports_tags: bool,
+    /// Whether a rename may be done by copying and deleting, so it takes as long
+    /// as re-uploading the file
+    pub renames_by_copy: bool,
 }
 
 impl LocationCapabilities {
@@ -110,6 +248,8 @@ impl LocationCapabilities {
             can_move: can_write,
             supports_watching: false,
             requires_explicit_refresh: false,
+            supports_tags: false,
+            renames_by_copy: false,
         }
     }
 
@@ -117,6 +257,21 @@ impl LocationCapabilities {
         self.supports_watching = supports;
         self
     }
+
+    pub fn with_supports_tags(mut self, supports: bool) -> Self {
+        self.supports_tags = supports;
+        self
+    }
+
+    pub fn with_requires_explicit_refresh(mut self, requires: bool) -> Self {
+        self.requires_explicit_refresh = requires;
+        self
+    }
+
+    pub fn with_renames_by_copy(mut self, renames_by_copy: bool) -> Self {
+        self.renames_by_copy = renames_by_copy;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -169,6 +324,27 @@ impl Location {
         &self.raw
     }
 
+    /// The containing directory, or `None` at the root of the location's namespace.
+    pub fn parent(&self) -> Option<Location> {
+        let trimmed = self.path.trim_end_matches('/');
+        if trimmed.is_empty() {
+            return None;
+        }
+        let idx = trimmed.rfind('/')?;
+        let path = if idx == 0 {
+            "/".to_string()
+        } else {
+            trimmed[..idx].to_string()
+        };
+        let raw = compose_raw_uri(&self.scheme, self.authority.as_deref(), &path);
+        Some(Location {
+            scheme: self.scheme.clone(),
+            authority: self.authority.clone(),
+            path,
+            raw,
+        })
+    }
+
     pub fn to_path_string(&self) -> String {
         if let Some(authority) = &self.authority {
             let mut path = String::from("//");
@@ -298,11 +474,244 @@ fn sanitize_path(input: impl Into<String>) -> String {
     value
 }
 
+/// Case-insensitive glob filter applied to entry names in a directory listing.
+#[derive(Debug, Clone)]
+pub struct EntryFilter {
+    matcher: GlobMatcher,
+    include_directories: bool,
+}
+
+impl EntryFilter {
+    /// When `include_directories` is set, folders are always kept so the user can
+    /// still navigate into them while a filter is active.
+    pub fn new(pattern: &str, include_directories: bool) -> Result<Self, String> {
+        let glob = GlobBuilder::new(pattern.trim())
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("Invalid filter pattern: {}", e))?;
+        Ok(Self {
+            matcher: glob.compile_matcher(),
+            include_directories,
+        })
+    }
+
+    pub fn matches(&self, name: &str, is_directory: bool) -> bool {
+        (is_directory && self.include_directories) || self.matcher.is_match(name)
+    }
+}
+
+/// How `search_directory` interprets the query.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    /// Treat the query as a glob (`*.rs`) instead of a substring of the name
+    pub glob: bool,
+    /// Look in subfolders too, not just the folder itself
+    pub recursive: bool,
+    pub include_hidden: bool,
+    pub limit: Option<usize>,
+}
+
+/// Default cap on search matches
+pub const SEARCH_DEFAULT_LIMIT: usize = 1000;
+
+/// A name query ready to be matched against entries.
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    text: String,
+    options: SearchOptions,
+    matcher: Option<GlobMatcher>,
+    /// Lowercased unless the search is case-sensitive
+    needle: String,
+}
+
+impl SearchQuery {
+    pub fn new(text: &str, options: SearchOptions) -> Result<Self, String> {
+        let text = text.trim().to_string();
+        if text.is_empty() {
+            return Err("Search query is empty".to_string());
+        }
+        let matcher = if options.glob {
+            let glob = GlobBuilder::new(&text)
+                .case_insensitive(!options.case_sensitive)
+                .build()
+                .map_err(|e| format!("Invalid search pattern: {}", e))?;
+            Some(glob.compile_matcher())
+        } else {
+            None
+        };
+        let needle = if options.case_sensitive {
+            text.clone()
+        } else {
+            text.to_lowercase()
+        };
+        Ok(Self {
+            text,
+            options,
+            matcher,
+            needle,
+        })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn options(&self) -> &SearchOptions {
+        &self.options
+    }
+
+    pub fn limit(&self) -> usize {
+        self.options.limit.unwrap_or(SEARCH_DEFAULT_LIMIT).max(1)
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        if !self.options.include_hidden && name.starts_with('.') {
+            return false;
+        }
+        match &self.matcher {
+            Some(matcher) => matcher.is_match(name),
+            None if self.options.case_sensitive => name.contains(&self.needle),
+            None => name.to_lowercase().contains(&self.needle),
+        }
+    }
+
+    /// Whether a search should descend into the folder called `name`
+    pub fn enters(&self, name: &str) -> bool {
+        self.options.recursive && (self.options.include_hidden || !name.starts_with('.'))
+    }
+}
+
+/// Receives batches of search matches as they are found
+pub type SearchSink = Arc<dyn Fn(Vec<FileItem>) + Send + Sync>;
+
+/// Matches are handed to the UI in batches of this size, or sooner after this long
+const SEARCH_BATCH_SIZE: usize = 100;
+const SEARCH_BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Gathers a search's matches: checks names against the query, stops at its limit and
+/// hands batches to the sink. Every search strategy goes through one of these, so
+/// they agree on what matches.
+pub struct SearchCollector<'a> {
+    query: &'a SearchQuery,
+    sink: &'a SearchSink,
+    found: usize,
+    batch: Vec<FileItem>,
+    last_flush: Instant,
+}
+
+impl<'a> SearchCollector<'a> {
+    pub fn new(query: &'a SearchQuery, sink: &'a SearchSink) -> Self {
+        Self {
+            query,
+            sink,
+            found: 0,
+            batch: Vec::new(),
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Whether an entry called `name` would be kept. Lets callers skip building
+    /// entries that can't match.
+    pub fn wants(&self, name: &str) -> bool {
+        !self.is_full() && self.query.matches(name)
+    }
+
+    /// Keep `item` if its name matches and the limit hasn't been reached.
+    pub fn offer(&mut self, item: FileItem) {
+        if self.wants(&item.name) {
+            self.found += 1;
+            self.batch.push(item);
+            self.flush_if_due();
+        }
+    }
+
+    pub fn found(&self) -> usize {
+        self.found
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.found >= self.query.limit()
+    }
+
+    /// Send the pending batch if it's big or old enough.
+    pub fn flush_if_due(&mut self) {
+        if self.batch.len() >= SEARCH_BATCH_SIZE
+            || (!self.batch.is_empty() && self.last_flush.elapsed() >= SEARCH_BATCH_INTERVAL)
+        {
+            self.flush();
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if !self.batch.is_empty() {
+            (self.sink)(std::mem::take(&mut self.batch));
+        }
+        self.last_flush = Instant::now();
+    }
+
+    /// Send whatever is left and return how many matches were found.
+    pub fn finish(mut self) -> usize {
+        self.flush();
+        self.found
+    }
+}
+
+/// Search by listing folders one at a time with `read_directory`. Works for every
+/// provider; folders that fail to list (permissions, vanished) are skipped.
+/// Returns how many matches were sent to `sink`.
+pub async fn walk_search<P: LocationProvider + ?Sized>(
+    provider: &P,
+    location: &Location,
+    query: &SearchQuery,
+    cancel: &AtomicBool,
+    sink: &SearchSink,
+) -> Result<usize, String> {
+    let mut matches = SearchCollector::new(query, sink);
+    let mut pending = VecDeque::from([location.clone()]);
+    let mut first = true;
+
+    while let Some(folder) = pending.pop_front() {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let listing = match provider.read_directory(&folder).await {
+            Ok(listing) => listing,
+            Err(err) if first => return Err(err),
+            Err(err) => {
+                log::debug!("Search skipped {}: {}", folder, err);
+                continue;
+            }
+        };
+        first = false;
+
+        for entry in listing.entries {
+            if entry.is_directory && !entry.is_symlink && query.enters(&entry.name) {
+                if let Ok(child) = Location::parse(&entry.path) {
+                    pending.push_back(child);
+                }
+            }
+            matches.offer(entry);
+        }
+        // Remote folders list slowly, so show each one's matches right away
+        matches.flush();
+        if matches.is_full() {
+            break;
+        }
+    }
+
+    Ok(matches.finish())
+}
+
 pub struct ProviderDirectoryEntries {
     pub location: LocationSummary,
     pub entries: Vec<FileItem>,
 }
 
+/// Errors are strings for the frontend; ones with a known cause are rendered from
+/// a `LocationError` so they start with its `[CODE]`. Providers fetched from the
+/// registry code the rest by their wording.
 #[async_trait]
 pub trait LocationProvider: Send + Sync {
     fn scheme(&self) -> &'static str;
@@ -310,6 +719,45 @@ pub trait LocationProvider: Send + Sync {
 
     async fn read_directory(&self, location: &Location)
         -> Result<ProviderDirectoryEntries, String>;
+    /// List only entries whose names match `filter`. The default lists everything
+    /// and filters afterwards; remote providers rely on this, so it saves transfer
+    /// to the UI but not the round trip to the server.
+    async fn read_directory_filtered(
+        &self,
+        location: &Location,
+        filter: &EntryFilter,
+    ) -> Result<ProviderDirectoryEntries, String> {
+        let mut listing = self.read_directory(location).await?;
+        listing
+            .entries
+            .retain(|entry| filter.matches(&entry.name, entry.is_directory));
+        Ok(listing)
+    }
+    /// Find entries under `location` whose names match `query`, handing matches to
+    /// `sink` as they turn up until `cancel` is set or the query's limit is reached.
+    /// Returns how many were found. The default walks folders with `read_directory`;
+    /// providers with a search of their own override it.
+    async fn search(
+        &self,
+        location: &Location,
+        query: &SearchQuery,
+        cancel: Arc<AtomicBool>,
+        sink: SearchSink,
+    ) -> Result<usize, String> {
+        walk_search(self, location, query, &cancel, &sink).await
+    }
+    /// Number of entries directly inside the directory at `location`. Listings leave
+    /// `child_count` empty so the UI only asks for folders that are on screen;
+    /// providers override this when they can count without fetching metadata.
+    async fn child_count(&self, location: &Location) -> Result<u64, String> {
+        Ok(self.read_directory(location).await?.entries.len() as u64)
+    }
+    /// Counts and file sizes for the entries directly inside `location`. The default
+    /// tallies a listing; providers override this when they can skip building one.
+    async fn directory_stats(&self, location: &Location) -> Result<DirectoryStats, String> {
+        let listing = self.read_directory(location).await?;
+        Ok(DirectoryStats::from_entries(&listing.entries))
+    }
     async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String>;
     async fn create_directory(&self, location: &Location) -> Result<(), String>;
     async fn delete(&self, location: &Location) -> Result<(), String>;
@@ -318,6 +766,45 @@ pub trait LocationProvider: Send + Sync {
     async fn move_item(&self, from: &Location, to: &Location) -> Result<(), String> {
         self.rename(from, to).await
     }
+
+    /// Create `location` and any missing parents. Succeeds if it already exists as a
+    /// directory; errors if something other than a directory occupies the path.
+    async fn ensure_directory(&self, location: &Location) -> Result<(), String> {
+        match self.get_file_metadata(location).await {
+            Ok(meta) if meta.is_directory => return Ok(()),
+            Ok(_) => return Err("A file already exists at this path".to_string()),
+            Err(_) => {}
+        }
+
+        if let Some(parent) = location.parent() {
+            self.ensure_directory(&parent).await?;
+        }
+
+        if let Err(err) = self.create_directory(location).await {
+            // Another writer may have created it in the meantime
+            match self.get_file_metadata(location).await {
+                Ok(meta) if meta.is_directory => return Ok(()),
+                _ => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a file called `name` holding `contents` inside `directory`, numbering
+    /// the name like "name (2).ext" if it's taken. Returns the name that was used.
+    /// Providers that can't write files keep the default, which refuses.
+    async fn create_file(
+        &self,
+        directory: &Location,
+        _name: &str,
+        _contents: &[u8],
+    ) -> Result<String, String> {
+        Err(LocationError::Unsupported(format!(
+            "Files can't be created in {} locations",
+            self.capabilities(directory).display_name
+        ))
+        .to_string())
+    }
 }
 
 pub fn get_provider_for_scheme(scheme: &str) -> Option<ProviderRef> {
@@ -333,6 +820,20 @@ pub fn ensure_provider(scheme: &str) -> Result<ProviderRef, String> {
         .ok_or_else(|| format!("No provider registered for scheme '{scheme}'"))
 }
 
+/// The folder containing `location`, as the UI should navigate to it. Returns `None`
+/// at the top of a namespace (filesystem root, server root, Drive account root).
+pub fn parent_location(location: &Location) -> Result<Option<LocationSummary>, String> {
+    match location.scheme() {
+        "file" => file::parent_location(location),
+        "archive" => archive::parent_location(location),
+        "gdrive" => Ok(gdrive::provider::parent_location(location)),
+        _ => Ok(location.parent().map(|parent| {
+            let display_path = parent.raw.clone();
+            LocationSummary::new(parent.scheme, parent.authority, parent.path, display_path)
+        })),
+    }
+}
+
 pub fn resolve_location(input: LocationInput) -> Result<(ProviderRef, Location), String> {
     let location = input.into_location()?;
     let provider = ensure_provider(location.scheme())?;
@@ -363,6 +864,19 @@ mod tests {
         assert_eq!(loc.raw(), "s3://bucket/path");
     }
 
+    #[test]
+    fn parent_walks_up_to_root() {
+        let loc = Location::parse("sftp://user@host:22/home/user/docs").unwrap();
+        let parent = loc.parent().unwrap();
+        assert_eq!(parent.path(), "/home/user");
+        assert_eq!(parent.authority(), Some("user@host:22"));
+        assert_eq!(parent.raw(), "sftp://user@host:22/home/user");
+
+        let top = Location::parse("/home").unwrap().parent().unwrap();
+        assert_eq!(top.path(), "/");
+        assert!(top.parent().is_none());
+    }
+
     #[test]
     fn sanitize_path_edge_cases() {
         // Empty path becomes root
@@ -383,4 +897,132 @@ mod tests {
         // Triple slash reduces to double (network path prefix)
         assert_eq!(sanitize_path("///"), "//");
     }
+
+    #[test]
+    fn parent_location_for_remote_schemes() {
+        let smb = Location::parse("smb://server/share").unwrap();
+        let parent = parent_location(&smb).unwrap().unwrap();
+        assert_eq!(parent.raw, "smb://server/");
+        assert!(parent_location(&Location::parse("smb://server/").unwrap())
+            .unwrap()
+            .is_none());
+
+        let sftp = Location::parse("sftp://user@host:22/home/user").unwrap();
+        let parent = parent_location(&sftp).unwrap().unwrap();
+        assert_eq!(parent.path, "/home");
+        assert_eq!(parent.authority.as_deref(), Some("user@host:22"));
+    }
+
+    #[test]
+    fn parent_location_for_gdrive_virtual_folders() {
+        let parent_of = |raw: &str| parent_location(&Location::parse(raw).unwrap()).unwrap();
+
+        assert!(parent_of("gdrive://me@example.com/").is_none());
+
+        let root = parent_of("gdrive://me@example.com/My Drive").unwrap();
+        assert_eq!(root.path, "/");
+        assert_eq!(root.display_path, "me@example.com");
+
+        let nested = parent_of("gdrive://me@example.com/My Drive/Projects/2024").unwrap();
+        assert_eq!(nested.path, "/My Drive/Projects");
+        assert_eq!(nested.display_path, "me@example.com/My Drive/Projects");
+
+        let by_id = parent_of("gdrive://me@example.com/id/abc123").unwrap();
+        assert_eq!(by_id.path, "/Shared with me");
+    }
+
+    #[test]
+    fn parent_location_for_archive_paths() {
+        let src = "/Users/me/backup.zip";
+        let inner = Location::parse(&archive::build_archive_uri(src, "/docs/notes")).unwrap();
+        let parent = parent_location(&inner).unwrap().unwrap();
+        assert_eq!(parent.scheme, "archive");
+        assert_eq!(parent.path, "/docs");
+        assert_eq!(parent.raw, archive::build_archive_uri(src, "/docs"));
+
+        // Leaving the archive root lands in the folder that holds the archive
+        let root = Location::parse(&archive::build_archive_uri(src, "/")).unwrap();
+        let parent = parent_location(&root).unwrap().unwrap();
+        assert_eq!(parent.scheme, "file");
+        assert_eq!(parent.path, "/Users/me");
+
+        // A nested archive's root goes back into the outer archive
+        let outer = archive::build_archive_uri(src, "/inner/nested.tar");
+        let nested_root = Location::parse(&archive::build_archive_uri(&outer, "/")).unwrap();
+        let parent = parent_location(&nested_root).unwrap().unwrap();
+        assert_eq!(parent.raw, archive::build_archive_uri(src, "/inner"));
+    }
+
+    #[test]
+    fn entry_filter_matches_case_insensitively() {
+        let filter = EntryFilter::new("*.JPG", true).unwrap();
+        assert!(filter.matches("holiday.jpg", false));
+        assert!(filter.matches("IMG_0001.Jpg", false));
+        assert!(!filter.matches("notes.txt", false));
+        assert!(filter.matches("Photos", true));
+
+        let files_only = EntryFilter::new("*.jpg", false).unwrap();
+        assert!(!files_only.matches("Photos", true));
+        assert!(EntryFilter::new("[", true).is_err());
+    }
+
+    #[test]
+    fn search_query_matching() {
+        let substring = SearchQuery::new(" Report ", SearchOptions::default()).unwrap();
+        assert!(substring.matches("annual-report.pdf"));
+        assert!(!substring.matches(".report"));
+        assert!(!substring.enters("Reports"));
+
+        let options = SearchOptions {
+            case_sensitive: true,
+            glob: true,
+            recursive: true,
+            ..Default::default()
+        };
+        let glob = SearchQuery::new("*.RS", options).unwrap();
+        assert!(glob.matches("MAIN.RS"));
+        assert!(!glob.matches("main.rs"));
+        assert!(glob.enters("src"));
+        assert!(!glob.enters(".git"));
+
+        assert!(SearchQuery::new("  ", SearchOptions::default()).is_err());
+    }
+
+    #[test]
+    fn search_collector_stops_at_the_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let items: Vec<FileItem> = ["report-1.txt", "notes.txt", "report-2.txt", "report-3.txt"]
+            .iter()
+            .map(|name| {
+                let path = dir.path().join(name);
+                std::fs::write(&path, b"").unwrap();
+                crate::fs_utils::get_file_info(&path).unwrap()
+            })
+            .collect();
+
+        let options = SearchOptions {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let query = SearchQuery::new("report", options).unwrap();
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink: SearchSink = {
+            let received = received.clone();
+            Arc::new(move |batch: Vec<FileItem>| {
+                received
+                    .lock()
+                    .unwrap()
+                    .extend(batch.into_iter().map(|item| item.name))
+            })
+        };
+
+        let mut matches = SearchCollector::new(&query, &sink);
+        for item in items {
+            matches.offer(item);
+        }
+        assert!(matches.is_full());
+        assert!(!matches.wants("report-4.txt"));
+        assert_eq!(matches.finish(), 2);
+        assert_eq!(*received.lock().unwrap(), ["report-1.txt", "report-2.txt"]);
+    }
 }
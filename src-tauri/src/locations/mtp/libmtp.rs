@@ -0,0 +1,505 @@
+//! Bindings to the parts of libmtp the provider uses, and the blocking operations
+//! built on them.
+//!
+//! libmtp isn't thread-safe, so every call goes through `SESSION`. The session also
+//! keeps devices open between calls since opening one takes a second or more.
+
+use chrono::{DateTime, TimeZone, Utc};
+use once_cell::sync::Lazy;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::{MtpDeviceInfo, MtpObject, MtpStorage};
+use crate::locations::LocationError;
+
+// Layouts mirror libmtp.h; fields the provider never reads keep the offsets right
+#[repr(C)]
+#[allow(dead_code)]
+struct DeviceEntry {
+    vendor: *mut c_char,
+    vendor_id: u16,
+    product: *mut c_char,
+    product_id: u16,
+    device_flags: u32,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct RawDevice {
+    device_entry: DeviceEntry,
+    bus_location: u32,
+    devnum: u8,
+}
+
+/// The leading fields of `LIBMTP_mtpdevice_t`; only ever used behind a pointer.
+#[repr(C)]
+#[allow(dead_code)]
+struct Device {
+    object_bitsize: u8,
+    params: *mut c_void,
+    usbinfo: *mut c_void,
+    storage: *mut DeviceStorage,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct DeviceStorage {
+    id: u32,
+    storage_type: u16,
+    filesystem_type: u16,
+    access_capability: u16,
+    max_capacity: u64,
+    free_space_in_bytes: u64,
+    free_space_in_objects: u64,
+    storage_description: *mut c_char,
+    volume_identifier: *mut c_char,
+    next: *mut DeviceStorage,
+    prev: *mut DeviceStorage,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct File {
+    item_id: u32,
+    parent_id: u32,
+    storage_id: u32,
+    filename: *mut c_char,
+    filesize: u64,
+    modificationdate: libc::time_t,
+    filetype: c_int,
+    next: *mut File,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct ErrorEntry {
+    errornumber: c_int,
+    error_text: *mut c_char,
+    next: *mut ErrorEntry,
+}
+
+type ProgressFn = Option<unsafe extern "C" fn(u64, u64, *const c_void) -> c_int>;
+
+const ERROR_NONE: c_int = 0;
+const ERROR_NO_DEVICE_ATTACHED: c_int = 5;
+const FILETYPE_FOLDER: c_int = 0;
+/// `LIBMTP_FILETYPE_UNKNOWN`; devices pick the format from the file name
+const FILETYPE_UNKNOWN: c_int = 44;
+const STORAGE_SORTBY_NONE: c_int = 0;
+
+#[link(name = "mtp")]
+extern "C" {
+    fn LIBMTP_Init();
+    fn LIBMTP_Detect_Raw_Devices(devices: *mut *mut RawDevice, count: *mut c_int) -> c_int;
+    fn LIBMTP_Open_Raw_Device_Uncached(raw: *mut RawDevice) -> *mut Device;
+    fn LIBMTP_Release_Device(device: *mut Device);
+    fn LIBMTP_Get_Friendlyname(device: *mut Device) -> *mut c_char;
+    fn LIBMTP_Get_Manufacturername(device: *mut Device) -> *mut c_char;
+    fn LIBMTP_Get_Modelname(device: *mut Device) -> *mut c_char;
+    fn LIBMTP_Get_Serialnumber(device: *mut Device) -> *mut c_char;
+    fn LIBMTP_Get_Storage(device: *mut Device, sortby: c_int) -> c_int;
+    fn LIBMTP_Get_Files_And_Folders(device: *mut Device, storage: u32, parent: u32) -> *mut File;
+    fn LIBMTP_Get_Filemetadata(device: *mut Device, id: u32) -> *mut File;
+    fn LIBMTP_new_file_t() -> *mut File;
+    fn LIBMTP_destroy_file_t(file: *mut File);
+    fn LIBMTP_Get_File_To_File(
+        device: *mut Device,
+        id: u32,
+        path: *const c_char,
+        progress: ProgressFn,
+        data: *const c_void,
+    ) -> c_int;
+    fn LIBMTP_GetPartialObject(
+        device: *mut Device,
+        id: u32,
+        offset: u64,
+        maxbytes: u32,
+        data: *mut *mut u8,
+        size: *mut c_uint,
+    ) -> c_int;
+    fn LIBMTP_Send_File_From_File(
+        device: *mut Device,
+        path: *const c_char,
+        file: *mut File,
+        progress: ProgressFn,
+        data: *const c_void,
+    ) -> c_int;
+    fn LIBMTP_Delete_Object(device: *mut Device, id: u32) -> c_int;
+    fn LIBMTP_Create_Folder(
+        device: *mut Device,
+        name: *mut c_char,
+        parent: u32,
+        storage: u32,
+    ) -> u32;
+    fn LIBMTP_Set_File_Name(device: *mut Device, file: *mut File, name: *const c_char) -> c_int;
+    fn LIBMTP_Get_Errorstack(device: *mut Device) -> *mut ErrorEntry;
+    fn LIBMTP_Clear_Errorstack(device: *mut Device);
+}
+
+struct OpenDevice {
+    info: MtpDeviceInfo,
+    bus_location: u32,
+    devnum: u8,
+    handle: *mut Device,
+}
+
+impl Drop for OpenDevice {
+    fn drop(&mut self) {
+        unsafe { LIBMTP_Release_Device(self.handle) };
+    }
+}
+
+#[derive(Default)]
+struct Session {
+    initialized: bool,
+    devices: Vec<OpenDevice>,
+}
+
+// Device handles are only touched while the session lock is held
+unsafe impl Send for Session {}
+
+static SESSION: Lazy<Mutex<Session>> = Lazy::new(|| Mutex::new(Session::default()));
+
+impl Session {
+    /// Open newly attached devices and release unplugged ones.
+    fn refresh(&mut self) -> Result<(), String> {
+        unsafe {
+            if !self.initialized {
+                LIBMTP_Init();
+                self.initialized = true;
+            }
+
+            let mut raw: *mut RawDevice = std::ptr::null_mut();
+            let mut count: c_int = 0;
+            let raw_devices: &mut [RawDevice] =
+                match LIBMTP_Detect_Raw_Devices(&mut raw, &mut count) {
+                    ERROR_NONE if !raw.is_null() && count > 0 => {
+                        std::slice::from_raw_parts_mut(raw, count as usize)
+                    }
+                    ERROR_NONE | ERROR_NO_DEVICE_ATTACHED => &mut [],
+                    code => {
+                        return Err(format!(
+                            "Failed to detect MTP devices (libmtp error {})",
+                            code
+                        ))
+                    }
+                };
+
+            self.devices.retain(|device| {
+                raw_devices.iter().any(|raw| {
+                    raw.bus_location == device.bus_location && raw.devnum == device.devnum
+                })
+            });
+
+            for raw_device in raw_devices.iter_mut() {
+                let already_open = self.devices.iter().any(|device| {
+                    device.bus_location == raw_device.bus_location
+                        && device.devnum == raw_device.devnum
+                });
+                if already_open {
+                    continue;
+                }
+                let handle = LIBMTP_Open_Raw_Device_Uncached(raw_device);
+                if handle.is_null() {
+                    // Usually another process (a desktop's GVfs or Android File
+                    // Transfer) has claimed the device
+                    log::warn!(
+                        "Failed to open MTP device {:04x}:{:04x}; it may be in use by another app",
+                        raw_device.device_entry.vendor_id,
+                        raw_device.device_entry.product_id
+                    );
+                    continue;
+                }
+                self.devices.push(OpenDevice {
+                    info: device_info(handle, raw_device),
+                    bus_location: raw_device.bus_location,
+                    devnum: raw_device.devnum,
+                    handle,
+                });
+            }
+
+            if !raw.is_null() {
+                libc::free(raw as *mut c_void);
+            }
+        }
+        Ok(())
+    }
+
+    fn device(&mut self, id: &str) -> Result<*mut Device, String> {
+        if !self.devices.iter().any(|device| device.info.id == id) {
+            self.refresh()?;
+        }
+        self.devices
+            .iter()
+            .find(|device| device.info.id == id)
+            .map(|device| device.handle)
+            .ok_or_else(|| {
+                LocationError::NotFound(format!("MTP device {} is not connected", id)).to_string()
+            })
+    }
+}
+
+/// Run `op` against the open device `id`. A failure may mean the device was
+/// unplugged, so the device list is refreshed before the error is returned.
+fn with_device<T>(
+    id: &str,
+    op: impl FnOnce(*mut Device) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut session = SESSION
+        .lock()
+        .map_err(|_| "MTP session lock poisoned".to_string())?;
+    let handle = session.device(id)?;
+    let result = op(handle);
+    if result.is_err() {
+        let _ = session.refresh();
+    }
+    result
+}
+
+/// Take a string libmtp allocated for the caller.
+unsafe fn take_string(ptr: *mut c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let value = CStr::from_ptr(ptr).to_string_lossy().trim().to_string();
+    libc::free(ptr as *mut c_void);
+    (!value.is_empty()).then_some(value)
+}
+
+unsafe fn borrowed_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let value = CStr::from_ptr(ptr).to_string_lossy().trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+unsafe fn device_info(handle: *mut Device, raw: &RawDevice) -> MtpDeviceInfo {
+    let manufacturer = take_string(LIBMTP_Get_Manufacturername(handle))
+        .or_else(|| borrowed_string(raw.device_entry.vendor));
+    let model = take_string(LIBMTP_Get_Modelname(handle))
+        .or_else(|| borrowed_string(raw.device_entry.product));
+    let name = take_string(LIBMTP_Get_Friendlyname(handle))
+        .or_else(|| model.clone())
+        .unwrap_or_else(|| "MTP Device".to_string());
+    // The serial survives replugging; the USB address is the fallback
+    let id = take_string(LIBMTP_Get_Serialnumber(handle))
+        .map(|serial| super::sanitize_device_id(&serial))
+        .filter(|serial| !serial.is_empty())
+        .unwrap_or_else(|| {
+            format!(
+                "{:04x}-{:04x}-{}-{}",
+                raw.device_entry.vendor_id,
+                raw.device_entry.product_id,
+                raw.bus_location,
+                raw.devnum
+            )
+        });
+    MtpDeviceInfo {
+        id,
+        name,
+        manufacturer,
+        model,
+    }
+}
+
+/// The newest message on the device's error stack, which is then cleared.
+unsafe fn device_error(handle: *mut Device, action: &str) -> String {
+    let mut message = None;
+    let mut entry = LIBMTP_Get_Errorstack(handle);
+    while !entry.is_null() {
+        if let Some(text) = borrowed_string((*entry).error_text) {
+            message = Some(text);
+        }
+        entry = (*entry).next;
+    }
+    LIBMTP_Clear_Errorstack(handle);
+    match message {
+        Some(text) => format!("Failed to {}: {}", action, text),
+        None => format!("Failed to {}", action),
+    }
+}
+
+unsafe fn to_object(file: &File) -> MtpObject {
+    let modified: Option<DateTime<Utc>> = if file.modificationdate > 0 {
+        Utc.timestamp_opt(file.modificationdate as i64, 0).single()
+    } else {
+        None
+    };
+    MtpObject {
+        id: file.item_id,
+        storage_id: file.storage_id,
+        name: borrowed_string(file.filename).unwrap_or_default(),
+        size: file.filesize,
+        modified,
+        is_folder: file.filetype == FILETYPE_FOLDER,
+    }
+}
+
+fn path_cstring(path: &Path) -> Result<CString, String> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| "Invalid local path".to_string())
+}
+
+fn name_cstring(name: &str) -> Result<CString, String> {
+    CString::new(name).map_err(|_| "Invalid file name".to_string())
+}
+
+pub fn devices() -> Result<Vec<MtpDeviceInfo>, String> {
+    let mut session = SESSION
+        .lock()
+        .map_err(|_| "MTP session lock poisoned".to_string())?;
+    session.refresh()?;
+    Ok(session
+        .devices
+        .iter()
+        .map(|device| device.info.clone())
+        .collect())
+}
+
+pub fn storages(device: &str) -> Result<Vec<MtpStorage>, String> {
+    with_device(device, |handle| unsafe {
+        if LIBMTP_Get_Storage(handle, STORAGE_SORTBY_NONE) != 0 {
+            return Err(device_error(handle, "read storage volumes"));
+        }
+        let mut storages = Vec::new();
+        let mut storage = (*handle).storage;
+        while !storage.is_null() {
+            let entry = &*storage;
+            storages.push(MtpStorage {
+                id: entry.id,
+                description: borrowed_string(entry.storage_description)
+                    .or_else(|| borrowed_string(entry.volume_identifier)),
+                // Devices that don't track space report a zero capacity
+                capacity: (entry.max_capacity > 0).then_some(entry.max_capacity),
+                free: (entry.max_capacity > 0).then_some(entry.free_space_in_bytes),
+            });
+            storage = entry.next;
+        }
+        Ok(storages)
+    })
+}
+
+pub fn children(device: &str, storage: u32, parent: u32) -> Result<Vec<MtpObject>, String> {
+    with_device(device, |handle| unsafe {
+        let mut objects = Vec::new();
+        let mut file = LIBMTP_Get_Files_And_Folders(handle, storage, parent);
+        while !file.is_null() {
+            objects.push(to_object(&*file));
+            let next = (*file).next;
+            LIBMTP_destroy_file_t(file);
+            file = next;
+        }
+        Ok(objects)
+    })
+}
+
+pub fn download(device: &str, object: u32, destination: &Path) -> Result<(), String> {
+    let destination = path_cstring(destination)?;
+    with_device(device, |handle| unsafe {
+        if LIBMTP_Get_File_To_File(handle, object, destination.as_ptr(), None, std::ptr::null())
+            != 0
+        {
+            return Err(device_error(handle, "download file"));
+        }
+        Ok(())
+    })
+}
+
+/// Up to `max_bytes` from the start of `object`. Partial reads are an optional part of
+/// MTP, so this fails on devices that don't support them.
+pub fn read_head(device: &str, object: u32, max_bytes: u32) -> Result<Vec<u8>, String> {
+    with_device(device, |handle| unsafe {
+        let mut data: *mut u8 = std::ptr::null_mut();
+        let mut size: c_uint = 0;
+        let status = LIBMTP_GetPartialObject(handle, object, 0, max_bytes, &mut data, &mut size);
+        let bytes = if data.is_null() {
+            Vec::new()
+        } else {
+            let bytes = std::slice::from_raw_parts(data, size as usize).to_vec();
+            libc::free(data as *mut c_void);
+            bytes
+        };
+        if status != 0 {
+            return Err(device_error(handle, "read file"));
+        }
+        Ok(bytes)
+    })
+}
+
+pub fn upload(
+    device: &str,
+    storage: u32,
+    parent: u32,
+    name: &str,
+    source: &Path,
+) -> Result<u32, String> {
+    let size = std::fs::metadata(source)
+        .map_err(|e| format!("Failed to read local file: {}", e))?
+        .len();
+    let source = path_cstring(source)?;
+    let name = name_cstring(name)?;
+    with_device(device, |handle| unsafe {
+        let file = LIBMTP_new_file_t();
+        if file.is_null() {
+            return Err("Failed to allocate MTP file".to_string());
+        }
+        // libmtp frees the name along with the struct
+        (*file).filename = libc::strdup(name.as_ptr());
+        (*file).filesize = size;
+        (*file).filetype = FILETYPE_UNKNOWN;
+        (*file).parent_id = parent;
+        (*file).storage_id = storage;
+
+        let result =
+            LIBMTP_Send_File_From_File(handle, source.as_ptr(), file, None, std::ptr::null());
+        let item_id = (*file).item_id;
+        LIBMTP_destroy_file_t(file);
+        if result != 0 {
+            return Err(device_error(handle, "upload file"));
+        }
+        Ok(item_id)
+    })
+}
+
+pub fn delete(device: &str, object: u32) -> Result<(), String> {
+    with_device(device, |handle| unsafe {
+        if LIBMTP_Delete_Object(handle, object) != 0 {
+            return Err(device_error(handle, "delete"));
+        }
+        Ok(())
+    })
+}
+
+pub fn create_folder(device: &str, storage: u32, parent: u32, name: &str) -> Result<u32, String> {
+    let name = name_cstring(name)?;
+    with_device(device, |handle| unsafe {
+        // libmtp may rewrite the name for the device's character set, so it gets a
+        // buffer it owns
+        let buffer = libc::strdup(name.as_ptr());
+        let id = LIBMTP_Create_Folder(handle, buffer, parent, storage);
+        libc::free(buffer as *mut c_void);
+        if id == 0 {
+            return Err(device_error(handle, "create folder"));
+        }
+        Ok(id)
+    })
+}
+
+pub fn rename(device: &str, object: u32, name: &str) -> Result<(), String> {
+    let name = name_cstring(name)?;
+    with_device(device, |handle| unsafe {
+        let file = LIBMTP_Get_Filemetadata(handle, object);
+        if file.is_null() {
+            return Err(device_error(handle, "read file details"));
+        }
+        let result = LIBMTP_Set_File_Name(handle, file, name.as_ptr());
+        LIBMTP_destroy_file_t(file);
+        if result != 0 {
+            return Err(device_error(handle, "rename"));
+        }
+        Ok(())
+    })
+}
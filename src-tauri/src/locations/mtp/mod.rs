@@ -0,0 +1,670 @@
+//! Phones, cameras and media players attached over USB with MTP, addressed as
+//! `mtp://<device>/<storage>/path`.
+//!
+//! `mtp://` on its own lists the connected devices, and each device lists its
+//! storage volumes ("Internal shared storage", "SD card"). MTP addresses objects by
+//! id rather than by path, so paths are resolved one folder at a time. Windows
+//! reaches devices through Windows Portable Devices (WPD), which every build
+//! includes. macOS and Linux go through libmtp, built in with the `mtp` feature;
+//! builds without it see no devices.
+//!
+//! Many devices can't rename in place, so `rename` falls back to copying and
+//! deleting, which capabilities report as `renames_by_copy`.
+
+#[cfg(all(feature = "mtp", not(target_os = "windows")))]
+mod libmtp;
+#[cfg(not(any(feature = "mtp", target_os = "windows")))]
+mod unsupported;
+#[cfg(target_os = "windows")]
+mod wpd;
+
+#[cfg(all(feature = "mtp", not(target_os = "windows")))]
+use libmtp as backend;
+#[cfg(not(any(feature = "mtp", target_os = "windows")))]
+use unsupported as backend;
+#[cfg(target_os = "windows")]
+use wpd as backend;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::fs_utils::FileItem;
+use crate::locations::transfer::StagedFile;
+use crate::locations::{
+    Location, LocationCapabilities, LocationError, LocationProvider, LocationSummary,
+    ProviderDirectoryEntries,
+};
+use crate::sorting::{sort_entries, SortOptions};
+
+/// Parent id that lists the top of a storage volume (`LIBMTP_FILES_AND_FOLDERS_ROOT`).
+const STORAGE_ROOT: u32 = 0xFFFF_FFFF;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MtpDeviceInfo {
+    /// Stable id used as the authority in `mtp://` paths
+    pub id: String,
+    pub name: String,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+}
+
+// Only the libmtp and WPD backends build these
+#[cfg_attr(not(any(feature = "mtp", target_os = "windows")), allow(dead_code))]
+pub(crate) struct MtpStorage {
+    pub id: u32,
+    pub description: Option<String>,
+    /// Size of the volume in bytes, when the device reports it
+    pub capacity: Option<u64>,
+    /// Bytes still free on the volume, when the device reports them
+    pub free: Option<u64>,
+}
+
+#[cfg_attr(not(any(feature = "mtp", target_os = "windows")), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub(crate) struct MtpObject {
+    pub id: u32,
+    pub storage_id: u32,
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub is_folder: bool,
+}
+
+/// What an `mtp://` path points at.
+enum Node {
+    Devices,
+    Device,
+    Storage(u32),
+    Object(MtpObject),
+}
+
+#[derive(Default)]
+pub struct MtpProvider;
+
+/// Keep device ids usable as a URI authority.
+#[cfg_attr(not(any(feature = "mtp", target_os = "windows")), allow(dead_code))]
+pub(crate) fn sanitize_device_id(serial: &str) -> String {
+    serial
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Connected devices, opening any that were plugged in since the last call.
+pub fn get_mtp_devices() -> Result<Vec<MtpDeviceInfo>, String> {
+    backend::devices()
+}
+
+async fn run<T: Send + 'static>(
+    op: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(op)
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+fn device_of(location: &Location) -> Option<String> {
+    location
+        .authority()
+        .filter(|authority| !authority.is_empty())
+        .map(str::to_string)
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    path.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+/// Folder names for each storage, made unique when two volumes share a description.
+fn storage_names(storages: &[MtpStorage]) -> Vec<(String, u32)> {
+    let mut seen = HashSet::new();
+    storages
+        .iter()
+        .map(|storage| {
+            let base = storage
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("Storage {:08x}", storage.id));
+            let name = if seen.insert(base.clone()) {
+                base
+            } else {
+                format!("{} ({:08x})", base, storage.id)
+            };
+            (name, storage.id)
+        })
+        .collect()
+}
+
+fn find_child(
+    device: &str,
+    storage: u32,
+    parent: u32,
+    name: &str,
+) -> Result<Option<MtpObject>, String> {
+    Ok(backend::children(device, storage, parent)?
+        .into_iter()
+        .find(|child| child.name == name))
+}
+
+fn resolve(device: Option<&str>, path: &str) -> Result<Node, String> {
+    let parts = segments(path);
+    let Some(device) = device else {
+        if parts.is_empty() {
+            return Ok(Node::Devices);
+        }
+        return Err(LocationError::NotFound(format!("Not found: mtp://{}", path)).to_string());
+    };
+    let Some((storage_name, rest)) = parts.split_first() else {
+        return Ok(Node::Device);
+    };
+
+    let storage = storage_names(&backend::storages(device)?)
+        .into_iter()
+        .find(|(name, _)| name == storage_name)
+        .map(|(_, id)| id)
+        .ok_or_else(|| {
+            LocationError::NotFound(format!("Storage not found: {}", storage_name)).to_string()
+        })?;
+
+    let mut parent = STORAGE_ROOT;
+    let mut node = Node::Storage(storage);
+    for (index, name) in rest.iter().enumerate() {
+        let object = find_child(device, storage, parent, name)?
+            .ok_or_else(|| LocationError::NotFound(format!("Not found: {}", path)).to_string())?;
+        if !object.is_folder && index + 1 < rest.len() {
+            return Err(
+                LocationError::NotADirectory(format!("Not a directory: {}", name)).to_string(),
+            );
+        }
+        parent = object.id;
+        node = Node::Object(object);
+    }
+    Ok(node)
+}
+
+/// The storage and parent id that list the contents of `node`.
+fn folder_of(node: &Node) -> Result<(u32, u32), String> {
+    match node {
+        Node::Storage(storage) => Ok((*storage, STORAGE_ROOT)),
+        Node::Object(object) if object.is_folder => Ok((object.storage_id, object.id)),
+        Node::Object(object) => Err(LocationError::NotADirectory(format!(
+            "Not a directory: {}",
+            object.name
+        ))
+        .to_string()),
+        Node::Devices | Node::Device => Err(LocationError::Unsupported(
+            "Files can only be stored inside a storage volume".to_string(),
+        )
+        .to_string()),
+    }
+}
+
+/// The containing folder and final name of `location`.
+fn split_parent(location: &Location) -> Result<(String, String), String> {
+    let parts = segments(location.path());
+    let (name, parent) = parts
+        .split_last()
+        .ok_or_else(|| "Path has no file name".to_string())?;
+    Ok((format!("/{}", parent.join("/")), name.to_string()))
+}
+
+fn same_device(from: &Location, to: &Location) -> Result<String, String> {
+    let device = device_of(from).ok_or_else(|| {
+        LocationError::Unsupported("Devices can't be moved or copied".to_string()).to_string()
+    })?;
+    if device_of(to).as_deref() != Some(device.as_str()) {
+        return Err(LocationError::Unsupported(
+            "Cannot move or copy directly between different MTP devices".to_string(),
+        )
+        .to_string());
+    }
+    Ok(device)
+}
+
+fn transfer_temp_dir() -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir().join("marlin-mtp-transfers");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Copy `object` into the folder `(storage, parent)` as `name`, folders recursively.
+/// MTP has no server-side copy, so files pass through a local temp file.
+fn copy_object(
+    device: &str,
+    object: &MtpObject,
+    storage: u32,
+    parent: u32,
+    name: &str,
+) -> Result<(), String> {
+    if object.is_folder {
+        let folder = backend::create_folder(device, storage, parent, name)?;
+        for child in backend::children(device, object.storage_id, object.id)? {
+            copy_object(device, &child, storage, folder, &child.name)?;
+        }
+        return Ok(());
+    }
+
+    let temp = transfer_temp_dir()?.join(uuid::Uuid::new_v4().to_string());
+    let result = backend::download(device, object.id, &temp)
+        .and_then(|_| backend::upload(device, storage, parent, name, &temp).map(|_| ()));
+    let _ = std::fs::remove_file(&temp);
+    result
+}
+
+/// Delete `object`; folders are emptied first since many devices refuse to delete
+/// one that still has contents.
+fn delete_object(device: &str, object: &MtpObject) -> Result<(), String> {
+    if object.is_folder {
+        for child in backend::children(device, object.storage_id, object.id)? {
+            delete_object(device, &child)?;
+        }
+    }
+    backend::delete(device, object.id)
+}
+
+/// Copy or move `from` to `to` on one device, refusing to overwrite.
+fn transfer(from: &Location, to: &Location, remove_source: bool) -> Result<(), String> {
+    let device = same_device(from, to)?;
+    let from_path = from.path().trim_end_matches('/');
+    if to.path().starts_with(&format!("{}/", from_path)) {
+        return Err("Cannot copy a folder into itself".to_string());
+    }
+
+    let Node::Object(object) = resolve(Some(&device), from.path())? else {
+        return Err(LocationError::Unsupported(
+            "Devices and storage volumes can't be moved or copied".to_string(),
+        )
+        .to_string());
+    };
+    let (from_parent, _) = split_parent(from)?;
+    let (to_parent, name) = split_parent(to)?;
+
+    if remove_source && from_parent == to_parent {
+        match backend::rename(&device, object.id, &name) {
+            Ok(()) => return Ok(()),
+            Err(err) => log::debug!("MTP rename failed, copying instead: {}", err),
+        }
+    }
+
+    let (storage, parent) = folder_of(&resolve(Some(&device), &to_parent)?)?;
+    if find_child(&device, storage, parent, &name)?.is_some() {
+        return Err("An item with this name already exists".to_string());
+    }
+    copy_object(&device, &object, storage, parent, &name)?;
+    if remove_source {
+        delete_object(&device, &object)?;
+    }
+    Ok(())
+}
+
+fn file_item(
+    path: String,
+    name: String,
+    is_directory: bool,
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+) -> FileItem {
+    let extension = if is_directory {
+        None
+    } else {
+        Path::new(&name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(String::from)
+    };
+    FileItem {
+        is_hidden: name.starts_with('.'),
+        path,
+        name,
+        is_directory,
+        size,
+        modified: modified.unwrap_or_else(Utc::now),
+        extension,
+        is_symlink: false,
+        is_git_repo: false,
+        child_count: None,
+        image_width: None,
+        image_height: None,
+        remote_id: None,
+        thumbnail_url: None,
+        download_url: None,
+        tags: None,
+        is_git_submodule: false,
+        lfs_size: None,
+        id: String::new(),
+    }
+}
+
+fn child_raw(location: &Location, name: &str) -> String {
+    let base = location.raw().trim_end_matches('/');
+    format!("{}/{}", base, name)
+}
+
+fn list(location: &Location) -> Result<Vec<FileItem>, String> {
+    let device = device_of(location);
+    let items = match resolve(device.as_deref(), location.path())? {
+        Node::Devices => backend::devices()?
+            .into_iter()
+            .map(|info| file_item(format!("mtp://{}/", info.id), info.name, true, 0, None))
+            .collect(),
+        Node::Device => {
+            let device = device.unwrap_or_default();
+            storage_names(&backend::storages(&device)?)
+                .into_iter()
+                .map(|(name, _)| file_item(child_raw(location, &name), name, true, 0, None))
+                .collect()
+        }
+        node => {
+            let device = device.unwrap_or_default();
+            let (storage, parent) = folder_of(&node)?;
+            backend::children(&device, storage, parent)?
+                .into_iter()
+                .map(|child| {
+                    file_item(
+                        child_raw(location, &child.name),
+                        child.name,
+                        child.is_folder,
+                        child.size,
+                        child.modified,
+                    )
+                })
+                .collect()
+        }
+    };
+    Ok(items)
+}
+
+fn metadata(location: &Location) -> Result<FileItem, String> {
+    let device = device_of(location);
+    let raw = location.raw().to_string();
+    Ok(match resolve(device.as_deref(), location.path())? {
+        Node::Devices => file_item(raw, "Devices".to_string(), true, 0, None),
+        Node::Device => {
+            let device = device.unwrap_or_default();
+            let name = backend::devices()?
+                .into_iter()
+                .find(|info| info.id == device)
+                .map_or(device, |info| info.name);
+            file_item(raw, name, true, 0, None)
+        }
+        Node::Storage(_) => {
+            let (_, name) = split_parent(location)?;
+            file_item(raw, name, true, 0, None)
+        }
+        Node::Object(object) => file_item(
+            raw,
+            object.name,
+            object.is_folder,
+            object.size,
+            object.modified,
+        ),
+    })
+}
+
+#[async_trait]
+impl LocationProvider for MtpProvider {
+    fn scheme(&self) -> &'static str {
+        "mtp"
+    }
+
+    fn capabilities(&self, location: &Location) -> LocationCapabilities {
+        // Writes only make sense inside a storage volume
+        let in_storage = device_of(location).is_some() && !segments(location.path()).is_empty();
+        LocationCapabilities::new("mtp", "MTP Device", true, in_storage)
+            .with_requires_explicit_refresh(true)
+            .with_renames_by_copy(true)
+    }
+
+    async fn read_directory(
+        &self,
+        location: &Location,
+    ) -> Result<ProviderDirectoryEntries, String> {
+        let target = location.clone();
+        let mut items = run(move || list(&target)).await?;
+
+        // Default order; read_directory re-sorts with the user's preferences
+        sort_entries(&mut items, &SortOptions::default());
+
+        Ok(ProviderDirectoryEntries {
+            location: LocationSummary::new(
+                "mtp",
+                device_of(location),
+                location.path().to_string(),
+                location.raw().to_string(),
+            ),
+            entries: items,
+        })
+    }
+
+    async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
+        let target = location.clone();
+        run(move || metadata(&target)).await
+    }
+
+    async fn create_directory(&self, location: &Location) -> Result<(), String> {
+        let target = location.clone();
+        run(move || {
+            let device = device_of(&target).ok_or_else(|| {
+                LocationError::Unsupported("Folders can't be created here".to_string()).to_string()
+            })?;
+            let (parent_path, name) = split_parent(&target)?;
+            let (storage, parent) = folder_of(&resolve(Some(&device), &parent_path)?)?;
+            if find_child(&device, storage, parent, &name)?.is_some() {
+                return Err("A file or folder already exists at this path".to_string());
+            }
+            backend::create_folder(&device, storage, parent, &name).map(|_| ())
+        })
+        .await
+    }
+
+    async fn create_file(
+        &self,
+        directory: &Location,
+        name: &str,
+        contents: &[u8],
+    ) -> Result<String, String> {
+        let staged = StagedFile::write(contents).await?;
+        upload_file_to_mtp(staged.path(), directory, name).await
+    }
+
+    async fn delete(&self, location: &Location) -> Result<(), String> {
+        let target = location.clone();
+        run(move || {
+            let device = device_of(&target);
+            match resolve(device.as_deref(), target.path())? {
+                Node::Object(object) => delete_object(&device.unwrap_or_default(), &object),
+                _ => Err(LocationError::Unsupported(
+                    "Devices and storage volumes can't be deleted".to_string(),
+                )
+                .to_string()),
+            }
+        })
+        .await
+    }
+
+    async fn rename(&self, from: &Location, to: &Location) -> Result<(), String> {
+        let (from, to) = (from.clone(), to.clone());
+        run(move || transfer(&from, &to, true)).await
+    }
+
+    async fn copy(&self, from: &Location, to: &Location) -> Result<(), String> {
+        let (from, to) = (from.clone(), to.clone());
+        run(move || transfer(&from, &to, false)).await
+    }
+}
+
+/// Upload a local file into the folder `dest_dir`, picking a free name like
+/// "name (2).ext" if needed. Returns the final filename used.
+pub async fn upload_file_to_mtp(
+    local_path: &Path,
+    dest_dir: &Location,
+    preferred_name: &str,
+) -> Result<String, String> {
+    let local_path = local_path.to_path_buf();
+    let dest_dir = dest_dir.clone();
+    let preferred_name = preferred_name.to_string();
+    run(move || {
+        let device =
+            device_of(&dest_dir).ok_or_else(|| "MTP destination missing device".to_string())?;
+        let (storage, parent) = folder_of(&resolve(Some(&device), dest_dir.path())?)?;
+        let taken: HashSet<String> = backend::children(&device, storage, parent)?
+            .into_iter()
+            .map(|child| child.name)
+            .collect();
+
+        let p = Path::new(&preferred_name);
+        let stem = p
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or(&preferred_name);
+        let ext = p.extension().and_then(|e| e.to_str());
+        let candidate = (1..1000usize)
+            .map(|i| match (i, ext) {
+                (1, _) => preferred_name.clone(),
+                (_, Some(e)) => format!("{stem} ({i}).{e}"),
+                (_, None) => format!("{stem} ({i})"),
+            })
+            .find(|candidate| !taken.contains(candidate))
+            .ok_or_else(|| {
+                "Unable to allocate unique destination name on the device".to_string()
+            })?;
+
+        backend::upload(&device, storage, parent, &candidate, &local_path)?;
+        Ok(candidate)
+    })
+    .await
+}
+
+/// Download a file from a device to a temp location.
+/// Returns the temporary file path.
+pub async fn download_mtp_file_to_temp(mtp_url: &str) -> Result<PathBuf, String> {
+    use sha2::{Digest, Sha256};
+
+    let location = Location::parse(mtp_url)?;
+    let device = device_of(&location).ok_or_else(|| "MTP path missing device".to_string())?;
+    let (_, original_name) = split_parent(&location)?;
+
+    let temp_dir = std::env::temp_dir().join("marlin-mtp-downloads");
+    tokio::fs::create_dir_all(&temp_dir)
+        .await
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(device.as_bytes());
+    hasher.update(location.path().as_bytes());
+    let hash = hex::encode(hasher.finalize());
+
+    let safe_name = original_name
+        .replace(['/', '\\', '\0', ':', '*', '?', '"', '<', '>', '|'], "_")
+        .trim_start_matches('.')
+        .to_string();
+    let temp_path = temp_dir.join(format!("{}_{}", &hash[..12], safe_name));
+
+    crate::locations::transfer::download_to_stable_path(&temp_path, |part_path| async move {
+        download_mtp_file(mtp_url, &part_path, None).await
+    })
+    .await?;
+
+    Ok(temp_path)
+}
+
+/// Download the file at `mtp_url` to `dest`. With `max_bytes`, only the start of the
+/// file is read where the device supports partial reads.
+pub async fn download_mtp_file(
+    mtp_url: &str,
+    dest: &Path,
+    max_bytes: Option<u64>,
+) -> Result<(), String> {
+    let location = Location::parse(mtp_url)?;
+    let device = device_of(&location).ok_or_else(|| "MTP path missing device".to_string())?;
+    let dest = dest.to_path_buf();
+    run(move || {
+        let Node::Object(object) = resolve(Some(&device), location.path())? else {
+            return Err("Not a file".to_string());
+        };
+        if object.is_folder {
+            return Err("Folders can't be downloaded as a file".to_string());
+        }
+        if let Some(max_bytes) = max_bytes {
+            let max_bytes = u32::try_from(max_bytes).unwrap_or(u32::MAX);
+            match backend::read_head(&device, object.id, max_bytes) {
+                Ok(head) => {
+                    return std::fs::write(&dest, head)
+                        .map_err(|e| format!("Failed to write downloaded file: {}", e))
+                }
+                Err(err) => log::debug!("MTP partial read failed, downloading it all: {}", err),
+            }
+        }
+        backend::download(&device, object.id, &dest)
+    })
+    .await
+}
+
+/// Total and available bytes of the storage volume holding `mtp_url`, or `None` when
+/// the path isn't on a volume or the device doesn't report them.
+pub fn disk_usage_on_mtp(mtp_url: &str) -> Result<Option<(u64, u64)>, String> {
+    let location = Location::parse(mtp_url)?;
+    let Some(device) = device_of(&location) else {
+        return Ok(None);
+    };
+    let storage_id = match resolve(Some(&device), location.path())? {
+        Node::Storage(id) => id,
+        Node::Object(object) => object.storage_id,
+        Node::Devices | Node::Device => return Ok(None),
+    };
+    Ok(backend::storages(&device)?
+        .into_iter()
+        .find(|storage| storage.id == storage_id)
+        .and_then(|storage| storage.capacity.zip(storage.free)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_storages_uniquely() {
+        let storages = [
+            MtpStorage {
+                id: 0x0001_0001,
+                description: Some("Internal shared storage".to_string()),
+                capacity: None,
+                free: None,
+            },
+            MtpStorage {
+                id: 0x0002_0001,
+                description: Some("Internal shared storage".to_string()),
+                capacity: None,
+                free: None,
+            },
+            MtpStorage {
+                id: 0x0003_0001,
+                description: None,
+                capacity: None,
+                free: None,
+            },
+        ];
+        let names: Vec<String> = storage_names(&storages)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "Internal shared storage",
+                "Internal shared storage (00020001)",
+                "Storage 00030001",
+            ]
+        );
+        assert_eq!(sanitize_device_id("R58M 12/AB"), "R58M_12_AB");
+    }
+}
@@ -0,0 +1,61 @@
+//! Stand-in backend for macOS and Linux builds without the `mtp` feature: no
+//! devices are ever connected.
+
+use std::path::Path;
+
+use super::{MtpDeviceInfo, MtpObject, MtpStorage};
+use crate::locations::LocationError;
+
+fn unsupported<T>() -> Result<T, String> {
+    Err(LocationError::Unsupported(
+        "MTP devices aren't supported in this build of Marlin".to_string(),
+    )
+    .to_string())
+}
+
+pub fn devices() -> Result<Vec<MtpDeviceInfo>, String> {
+    Ok(Vec::new())
+}
+
+pub fn storages(_device: &str) -> Result<Vec<MtpStorage>, String> {
+    unsupported()
+}
+
+pub fn children(_device: &str, _storage: u32, _parent: u32) -> Result<Vec<MtpObject>, String> {
+    unsupported()
+}
+
+pub fn download(_device: &str, _object: u32, _destination: &Path) -> Result<(), String> {
+    unsupported()
+}
+
+pub fn read_head(_device: &str, _object: u32, _max_bytes: u32) -> Result<Vec<u8>, String> {
+    unsupported()
+}
+
+pub fn upload(
+    _device: &str,
+    _storage: u32,
+    _parent: u32,
+    _name: &str,
+    _source: &Path,
+) -> Result<u32, String> {
+    unsupported()
+}
+
+pub fn delete(_device: &str, _object: u32) -> Result<(), String> {
+    unsupported()
+}
+
+pub fn create_folder(
+    _device: &str,
+    _storage: u32,
+    _parent: u32,
+    _name: &str,
+) -> Result<u32, String> {
+    unsupported()
+}
+
+pub fn rename(_device: &str, _object: u32, _name: &str) -> Result<(), String> {
+    unsupported()
+}
@@ -0,0 +1,638 @@
+//! Windows Portable Devices (WPD) backend. Windows claims MTP phones and PTP
+//! cameras with its own driver, so they're reached through WPD rather than libmtp.
+//!
+//! WPD names objects with strings ("DEVICE", "s10001", "o2A1F") where MTP uses
+//! numbers, so each open device hands out a small handle for every object id it
+//! has listed. Devices stay open in `SESSION` between calls, as with libmtp.
+
+use chrono::{DateTime, TimeZone, Utc};
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use windows::core::{Interface, GUID, PCWSTR, PWSTR};
+use windows::Win32::Devices::PortableDevices::*;
+use windows::Win32::Foundation::{GENERIC_READ, GENERIC_WRITE, PROPERTYKEY, S_FALSE};
+use windows::Win32::System::Com::StructuredStorage::{
+    PropVariantClear, PropVariantToFileTime, PROPVARIANT,
+};
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CoTaskMemFree, IStream, CLSCTX_INPROC_SERVER,
+    COINIT_MULTITHREADED, STGC_DEFAULT, STGM_READ,
+};
+use windows::Win32::System::Variant::{PSTF_LOCAL, VT_LPWSTR};
+
+use super::{MtpDeviceInfo, MtpObject, MtpStorage, STORAGE_ROOT};
+use crate::locations::LocationError;
+
+/// Buffer size for streams whose driver doesn't suggest one.
+const TRANSFER_CHUNK_BYTES: usize = 256 * 1024;
+
+/// Properties read for every listed file or folder.
+const OBJECT_KEYS: [PROPERTYKEY; 5] = [
+    WPD_OBJECT_ORIGINAL_FILE_NAME,
+    WPD_OBJECT_NAME,
+    WPD_OBJECT_SIZE,
+    WPD_OBJECT_DATE_MODIFIED,
+    WPD_OBJECT_CONTENT_TYPE,
+];
+
+struct OpenDevice {
+    info: MtpDeviceInfo,
+    pnp_id: String,
+    device: IPortableDevice,
+    content: IPortableDeviceContent,
+    /// WPD object ids, each at its handle minus one
+    objects: Vec<String>,
+    handles: HashMap<String, u32>,
+}
+
+impl OpenDevice {
+    fn handle_for(&mut self, object_id: String) -> u32 {
+        if let Some(handle) = self.handles.get(&object_id) {
+            return *handle;
+        }
+        self.objects.push(object_id.clone());
+        let handle = self.objects.len() as u32;
+        self.handles.insert(object_id, handle);
+        handle
+    }
+
+    fn object_id(&self, handle: u32) -> Result<String, String> {
+        handle
+            .checked_sub(1)
+            .and_then(|index| self.objects.get(index as usize))
+            .cloned()
+            .ok_or_else(|| LocationError::NotFound("MTP object not found".to_string()).to_string())
+    }
+
+    /// The WPD id of the folder `(storage, parent)`, as the provider addresses it.
+    fn folder_id(&self, storage: u32, parent: u32) -> Result<String, String> {
+        if parent == STORAGE_ROOT {
+            self.object_id(storage)
+        } else {
+            self.object_id(parent)
+        }
+    }
+}
+
+impl Drop for OpenDevice {
+    fn drop(&mut self) {
+        let _ = unsafe { self.device.Close() };
+    }
+}
+
+#[derive(Default)]
+struct Session {
+    manager: Option<IPortableDeviceManager>,
+    devices: Vec<OpenDevice>,
+    /// Attached portable devices that aren't MTP or PTP, such as USB drives that
+    /// already show up as volumes
+    skipped: HashSet<String>,
+}
+
+// Devices are opened free-threaded and only touched while the session lock is held
+unsafe impl Send for Session {}
+
+static SESSION: Lazy<Mutex<Session>> = Lazy::new(|| Mutex::new(Session::default()));
+
+impl Session {
+    /// Open newly attached devices and close unplugged ones.
+    fn refresh(&mut self) -> Result<(), String> {
+        unsafe {
+            let manager = match &self.manager {
+                Some(manager) => manager.clone(),
+                None => {
+                    let manager: IPortableDeviceManager =
+                        create(&PortableDeviceManager, "open the device manager")?;
+                    self.manager = Some(manager.clone());
+                    manager
+                }
+            };
+            let _ = manager.RefreshDeviceList();
+
+            let mut count = 0u32;
+            manager
+                .GetDevices(std::ptr::null_mut(), &mut count)
+                .map_err(failed("list portable devices"))?;
+            let mut ids = vec![PWSTR::null(); count as usize];
+            if count > 0 {
+                manager
+                    .GetDevices(ids.as_mut_ptr(), &mut count)
+                    .map_err(failed("list portable devices"))?;
+            }
+            let attached: Vec<String> = ids
+                .into_iter()
+                .take(count as usize)
+                .filter_map(|id| take_string(id))
+                .collect();
+
+            self.devices
+                .retain(|device| attached.contains(&device.pnp_id));
+            self.skipped.retain(|pnp_id| attached.contains(pnp_id));
+
+            for pnp_id in attached {
+                let known = self.skipped.contains(&pnp_id)
+                    || self.devices.iter().any(|device| device.pnp_id == pnp_id);
+                if known {
+                    continue;
+                }
+                match open_device(&pnp_id) {
+                    Ok(Some(device)) => self.devices.push(device),
+                    Ok(None) => {
+                        self.skipped.insert(pnp_id);
+                    }
+                    Err(err) => {
+                        // Usually the device is locked or waiting for the user to
+                        // allow file access, so it's tried again next time
+                        log::warn!("Failed to open portable device {}: {}", pnp_id, err);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn device(&mut self, id: &str) -> Result<&mut OpenDevice, String> {
+        if !self.devices.iter().any(|device| device.info.id == id) {
+            self.refresh()?;
+        }
+        self.devices
+            .iter_mut()
+            .find(|device| device.info.id == id)
+            .ok_or_else(|| {
+                LocationError::NotFound(format!("MTP device {} is not connected", id)).to_string()
+            })
+    }
+}
+
+/// Run `op` against the open device `id`. A failure may mean the device was
+/// unplugged, so the device list is refreshed before the error is returned.
+fn with_device<T>(
+    id: &str,
+    op: impl FnOnce(&mut OpenDevice) -> Result<T, String>,
+) -> Result<T, String> {
+    init_com();
+    let mut session = SESSION
+        .lock()
+        .map_err(|_| "MTP session lock poisoned".to_string())?;
+    let result = op(session.device(id)?);
+    if result.is_err() {
+        let _ = session.refresh();
+    }
+    result
+}
+
+/// Join the blocking pool thread to the multithreaded apartment. Threads that
+/// already joined one, in either model, keep it.
+fn init_com() {
+    let _ = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+}
+
+fn failed(action: &'static str) -> impl Fn(windows::core::Error) -> String {
+    move |err| format!("Failed to {}: {}", action, err.message())
+}
+
+unsafe fn create<T: Interface>(class: &GUID, action: &'static str) -> Result<T, String> {
+    CoCreateInstance(class, None, CLSCTX_INPROC_SERVER).map_err(failed(action))
+}
+
+fn wide(value: &str) -> Vec<u16> {
+    value.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Take a string WPD allocated for the caller.
+unsafe fn take_string(value: PWSTR) -> Option<String> {
+    if value.is_null() {
+        return None;
+    }
+    let text = String::from_utf16_lossy(value.as_wide());
+    CoTaskMemFree(Some(value.0 as *const _));
+    Some(text)
+}
+
+unsafe fn string_value(values: &IPortableDeviceValues, key: &PROPERTYKEY) -> Option<String> {
+    let text = take_string(values.GetStringValue(key).ok()?)?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// A `VT_DATE` property. Devices keep their clock in local time.
+unsafe fn date_value(values: &IPortableDeviceValues, key: &PROPERTYKEY) -> Option<DateTime<Utc>> {
+    let mut value = values.GetValue(key).ok()?;
+    let filetime = PropVariantToFileTime(&value, PSTF_LOCAL).ok();
+    let _ = PropVariantClear(&mut value);
+    let filetime = filetime?;
+    // FILETIME counts 100ns ticks since 1601
+    let ticks = ((filetime.dwHighDateTime as u64) << 32) | filetime.dwLowDateTime as u64;
+    let seconds = (ticks / 10_000_000) as i64 - 11_644_473_600;
+    (seconds > 0)
+        .then(|| Utc.timestamp_opt(seconds, 0).single())
+        .flatten()
+}
+
+unsafe fn key_collection(keys: &[PROPERTYKEY]) -> Result<IPortableDeviceKeyCollection, String> {
+    let collection: IPortableDeviceKeyCollection =
+        create(&PortableDeviceKeyCollection, "read device properties")?;
+    for key in keys {
+        collection
+            .Add(key)
+            .map_err(failed("read device properties"))?;
+    }
+    Ok(collection)
+}
+
+unsafe fn new_values() -> Result<IPortableDeviceValues, String> {
+    create(&PortableDeviceValues, "set device properties")
+}
+
+/// Open `pnp_id` if it's an MTP or PTP device; anything else is left closed.
+unsafe fn open_device(pnp_id: &str) -> Result<Option<OpenDevice>, String> {
+    let client = new_values()?;
+    let pnp = wide(pnp_id);
+    let open = || -> windows::core::Result<IPortableDevice> {
+        client.SetStringValue(&WPD_CLIENT_NAME, windows::core::w!("Marlin"))?;
+        client.SetUnsignedIntegerValue(&WPD_CLIENT_MAJOR_VERSION, 1)?;
+        client.SetUnsignedIntegerValue(&WPD_CLIENT_MINOR_VERSION, 0)?;
+        client.SetUnsignedIntegerValue(&WPD_CLIENT_REVISION, 0)?;
+        client.SetUnsignedIntegerValue(
+            &WPD_CLIENT_DESIRED_ACCESS,
+            (GENERIC_READ | GENERIC_WRITE).0,
+        )?;
+        let device: IPortableDevice =
+            CoCreateInstance(&PortableDeviceFTM, None, CLSCTX_INPROC_SERVER)?;
+        device.Open(PCWSTR(pnp.as_ptr()), &client)?;
+        Ok(device)
+    };
+    let device = open().map_err(failed("open device"))?;
+    let content = device.Content().map_err(failed("open device"))?;
+
+    let keys = key_collection(&[
+        WPD_DEVICE_PROTOCOL,
+        WPD_DEVICE_FRIENDLY_NAME,
+        WPD_DEVICE_MANUFACTURER,
+        WPD_DEVICE_MODEL,
+        WPD_DEVICE_SERIAL_NUMBER,
+    ])?;
+    let values = content
+        .Properties()
+        .and_then(|properties| properties.GetValues(WPD_DEVICE_OBJECT_ID, &keys))
+        .map_err(failed("read device details"))?;
+
+    // "MTP: 1.00" for phones and players, "PTP: ..." for cameras
+    let protocol = string_value(&values, &WPD_DEVICE_PROTOCOL).unwrap_or_default();
+    if !(protocol.starts_with("MTP") || protocol.starts_with("PTP")) {
+        let _ = device.Close();
+        return Ok(None);
+    }
+
+    let model = string_value(&values, &WPD_DEVICE_MODEL);
+    let name = string_value(&values, &WPD_DEVICE_FRIENDLY_NAME)
+        .or_else(|| model.clone())
+        .unwrap_or_else(|| "MTP Device".to_string());
+    // The serial survives replugging; the PnP id is the fallback
+    let id = string_value(&values, &WPD_DEVICE_SERIAL_NUMBER)
+        .map(|serial| super::sanitize_device_id(&serial))
+        .filter(|serial| !serial.is_empty())
+        .unwrap_or_else(|| super::sanitize_device_id(pnp_id));
+
+    Ok(Some(OpenDevice {
+        info: MtpDeviceInfo {
+            id,
+            name,
+            manufacturer: string_value(&values, &WPD_DEVICE_MANUFACTURER),
+            model,
+        },
+        pnp_id: pnp_id.to_string(),
+        device,
+        content,
+        objects: Vec::new(),
+        handles: HashMap::new(),
+    }))
+}
+
+/// Ids of the objects directly inside `parent`.
+unsafe fn child_ids(content: &IPortableDeviceContent, parent: &str) -> Result<Vec<String>, String> {
+    let parent = wide(parent);
+    let objects = content
+        .EnumObjects(0, PCWSTR(parent.as_ptr()), None)
+        .map_err(failed("list folder"))?;
+    let mut ids = Vec::new();
+    let mut batch = [PWSTR::null(); 64];
+    loop {
+        let mut fetched = 0u32;
+        let status = objects.Next(&mut batch, &mut fetched);
+        ids.extend(
+            batch
+                .iter()
+                .take(fetched as usize)
+                .filter_map(|id| take_string(*id)),
+        );
+        status.ok().map_err(failed("list folder"))?;
+        if status == S_FALSE || fetched == 0 {
+            return Ok(ids);
+        }
+    }
+}
+
+/// The default data stream of a file, and the chunk size its driver prefers.
+unsafe fn read_stream(device: &OpenDevice, object: u32) -> Result<(IStream, usize), String> {
+    let id = wide(&device.object_id(object)?);
+    let resources = device.content.Transfer().map_err(failed("read file"))?;
+    let mut stream = None;
+    let mut optimal = 0u32;
+    resources
+        .GetStream(
+            PCWSTR(id.as_ptr()),
+            &WPD_RESOURCE_DEFAULT,
+            STGM_READ.0,
+            &mut optimal,
+            &mut stream,
+        )
+        .map_err(failed("read file"))?;
+    let stream = stream.ok_or_else(|| "Failed to read file".to_string())?;
+    Ok((stream, chunk_size(optimal)))
+}
+
+fn chunk_size(optimal: u32) -> usize {
+    if optimal == 0 {
+        TRANSFER_CHUNK_BYTES
+    } else {
+        optimal as usize
+    }
+}
+
+/// Read up to `buffer.len()` bytes; zero means the stream is done.
+unsafe fn read_chunk(stream: &IStream, buffer: &mut [u8]) -> Result<usize, String> {
+    let mut read = 0u32;
+    stream
+        .Read(
+            buffer.as_mut_ptr().cast(),
+            buffer.len() as u32,
+            Some(&mut read),
+        )
+        .ok()
+        .map_err(failed("read file"))?;
+    Ok(read as usize)
+}
+
+pub fn devices() -> Result<Vec<MtpDeviceInfo>, String> {
+    init_com();
+    let mut session = SESSION
+        .lock()
+        .map_err(|_| "MTP session lock poisoned".to_string())?;
+    session.refresh()?;
+    Ok(session
+        .devices
+        .iter()
+        .map(|device| device.info.clone())
+        .collect())
+}
+
+pub fn storages(device: &str) -> Result<Vec<MtpStorage>, String> {
+    with_device(device, |open| unsafe {
+        let properties = open
+            .content
+            .Properties()
+            .map_err(failed("read storage volumes"))?;
+        let keys = key_collection(&[
+            WPD_FUNCTIONAL_OBJECT_CATEGORY,
+            WPD_STORAGE_DESCRIPTION,
+            WPD_OBJECT_NAME,
+            WPD_STORAGE_CAPACITY,
+            WPD_STORAGE_FREE_SPACE_IN_BYTES,
+        ])?;
+
+        let mut storages = Vec::new();
+        // Storage volumes are functional objects directly under the device object
+        let device_object = WPD_DEVICE_OBJECT_ID.to_string().unwrap_or_default();
+        for id in child_ids(&open.content, &device_object)? {
+            let object = wide(&id);
+            let values = properties
+                .GetValues(PCWSTR(object.as_ptr()), &keys)
+                .map_err(failed("read storage volumes"))?;
+            if values.GetGuidValue(&WPD_FUNCTIONAL_OBJECT_CATEGORY).ok()
+                != Some(WPD_FUNCTIONAL_CATEGORY_STORAGE)
+            {
+                continue;
+            }
+            // Devices that don't track space report a zero capacity
+            let capacity = values
+                .GetUnsignedLargeIntegerValue(&WPD_STORAGE_CAPACITY)
+                .ok()
+                .filter(|capacity| *capacity > 0);
+            storages.push(MtpStorage {
+                description: string_value(&values, &WPD_STORAGE_DESCRIPTION)
+                    .or_else(|| string_value(&values, &WPD_OBJECT_NAME)),
+                capacity,
+                free: capacity.and_then(|_| {
+                    values
+                        .GetUnsignedLargeIntegerValue(&WPD_STORAGE_FREE_SPACE_IN_BYTES)
+                        .ok()
+                }),
+                id: open.handle_for(id),
+            });
+        }
+        Ok(storages)
+    })
+}
+
+pub fn children(device: &str, storage: u32, parent: u32) -> Result<Vec<MtpObject>, String> {
+    with_device(device, |open| unsafe {
+        let folder = open.folder_id(storage, parent)?;
+        let properties = open.content.Properties().map_err(failed("list folder"))?;
+        let keys = key_collection(&OBJECT_KEYS)?;
+
+        let mut objects = Vec::new();
+        for id in child_ids(&open.content, &folder)? {
+            let object = wide(&id);
+            let values = properties
+                .GetValues(PCWSTR(object.as_ptr()), &keys)
+                .map_err(failed("list folder"))?;
+            let content_type = values.GetGuidValue(&WPD_OBJECT_CONTENT_TYPE).ok();
+            if content_type == Some(WPD_CONTENT_TYPE_FUNCTIONAL_OBJECT) {
+                continue;
+            }
+            // The object name often drops the extension; the original file name keeps it
+            let Some(name) = string_value(&values, &WPD_OBJECT_ORIGINAL_FILE_NAME)
+                .or_else(|| string_value(&values, &WPD_OBJECT_NAME))
+            else {
+                continue;
+            };
+            objects.push(MtpObject {
+                storage_id: storage,
+                name,
+                size: values
+                    .GetUnsignedLargeIntegerValue(&WPD_OBJECT_SIZE)
+                    .unwrap_or(0),
+                modified: date_value(&values, &WPD_OBJECT_DATE_MODIFIED),
+                is_folder: content_type == Some(WPD_CONTENT_TYPE_FOLDER),
+                id: open.handle_for(id),
+            });
+        }
+        Ok(objects)
+    })
+}
+
+pub fn download(device: &str, object: u32, destination: &Path) -> Result<(), String> {
+    with_device(device, |open| unsafe {
+        let (stream, chunk) = read_stream(open, object)?;
+        let mut file =
+            File::create(destination).map_err(|e| format!("Failed to create local file: {}", e))?;
+        let mut buffer = vec![0u8; chunk];
+        loop {
+            let read = read_chunk(&stream, &mut buffer)?;
+            if read == 0 {
+                return Ok(());
+            }
+            file.write_all(&buffer[..read])
+                .map_err(|e| format!("Failed to write local file: {}", e))?;
+        }
+    })
+}
+
+/// Up to `max_bytes` from the start of `object`, without reading the rest.
+pub fn read_head(device: &str, object: u32, max_bytes: u32) -> Result<Vec<u8>, String> {
+    with_device(device, |open| unsafe {
+        let (stream, _) = read_stream(open, object)?;
+        let mut bytes = vec![0u8; max_bytes as usize];
+        let mut filled = 0;
+        while filled < bytes.len() {
+            let read = read_chunk(&stream, &mut bytes[filled..])?;
+            if read == 0 {
+                break;
+            }
+            filled += read;
+        }
+        bytes.truncate(filled);
+        Ok(bytes)
+    })
+}
+
+pub fn upload(
+    device: &str,
+    storage: u32,
+    parent: u32,
+    name: &str,
+    source: &Path,
+) -> Result<u32, String> {
+    let mut file = File::open(source).map_err(|e| format!("Failed to read local file: {}", e))?;
+    let size = file
+        .metadata()
+        .map_err(|e| format!("Failed to read local file: {}", e))?
+        .len();
+    with_device(device, |open| unsafe {
+        let folder = wide(&open.folder_id(storage, parent)?);
+        let name = wide(name);
+        let values = new_values()?;
+        let describe = || -> windows::core::Result<()> {
+            values.SetStringValue(&WPD_OBJECT_PARENT_ID, PCWSTR(folder.as_ptr()))?;
+            values.SetStringValue(&WPD_OBJECT_NAME, PCWSTR(name.as_ptr()))?;
+            values.SetStringValue(&WPD_OBJECT_ORIGINAL_FILE_NAME, PCWSTR(name.as_ptr()))?;
+            values.SetUnsignedLargeIntegerValue(&WPD_OBJECT_SIZE, size)?;
+            // Devices pick the format from the file name, as with libmtp
+            values.SetGuidValue(&WPD_OBJECT_CONTENT_TYPE, &WPD_CONTENT_TYPE_GENERIC_FILE)?;
+            values.SetGuidValue(&WPD_OBJECT_FORMAT, &WPD_OBJECT_FORMAT_UNSPECIFIED)
+        };
+        describe().map_err(failed("upload file"))?;
+
+        let mut stream = None;
+        let mut optimal = 0u32;
+        let mut cookie = PWSTR::null();
+        open.content
+            .CreateObjectWithPropertiesAndData(&values, &mut stream, &mut optimal, &mut cookie)
+            .map_err(failed("upload file"))?;
+        take_string(cookie);
+        let stream = stream.ok_or_else(|| "Failed to upload file".to_string())?;
+
+        let mut buffer = vec![0u8; chunk_size(optimal)];
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .map_err(|e| format!("Failed to read local file: {}", e))?;
+            if read == 0 {
+                break;
+            }
+            let mut written = 0u32;
+            stream
+                .Write(buffer.as_ptr().cast(), read as u32, Some(&mut written))
+                .ok()
+                .map_err(failed("upload file"))?;
+        }
+        // Nothing is created on the device until the stream is committed
+        stream.Commit(STGC_DEFAULT).map_err(failed("upload file"))?;
+
+        let id = stream
+            .cast::<IPortableDeviceDataStream>()
+            .and_then(|data| data.GetObjectID())
+            .map_err(failed("upload file"))?;
+        let id = take_string(id).ok_or_else(|| "Failed to upload file".to_string())?;
+        Ok(open.handle_for(id))
+    })
+}
+
+pub fn delete(device: &str, object: u32) -> Result<(), String> {
+    with_device(device, |open| unsafe {
+        let mut id = wide(&open.object_id(object)?);
+        let ids: IPortableDevicePropVariantCollection =
+            create(&PortableDevicePropVariantCollection, "delete")?;
+        // The collection copies the string, so the value can borrow the buffer
+        let mut value = PROPVARIANT::default();
+        let fields = &mut *value.Anonymous.Anonymous;
+        fields.vt = VT_LPWSTR;
+        fields.Anonymous.pwszVal = PWSTR(id.as_mut_ptr());
+        ids.Add(&value).map_err(failed("delete"))?;
+        open.content
+            .Delete(
+                PORTABLE_DEVICE_DELETE_NO_RECURSION.0 as u32,
+                &ids,
+                std::ptr::null_mut(),
+            )
+            .map_err(failed("delete"))
+    })
+}
+
+pub fn create_folder(device: &str, storage: u32, parent: u32, name: &str) -> Result<u32, String> {
+    with_device(device, |open| unsafe {
+        let folder = wide(&open.folder_id(storage, parent)?);
+        let name = wide(name);
+        let values = new_values()?;
+        let describe = || -> windows::core::Result<()> {
+            values.SetStringValue(&WPD_OBJECT_PARENT_ID, PCWSTR(folder.as_ptr()))?;
+            values.SetStringValue(&WPD_OBJECT_NAME, PCWSTR(name.as_ptr()))?;
+            values.SetStringValue(&WPD_OBJECT_ORIGINAL_FILE_NAME, PCWSTR(name.as_ptr()))?;
+            values.SetGuidValue(&WPD_OBJECT_CONTENT_TYPE, &WPD_CONTENT_TYPE_FOLDER)
+        };
+        describe().map_err(failed("create folder"))?;
+
+        let mut id = PWSTR::null();
+        open.content
+            .CreateObjectWithPropertiesOnly(&values, &mut id)
+            .map_err(failed("create folder"))?;
+        let id = take_string(id).ok_or_else(|| "Failed to create folder".to_string())?;
+        Ok(open.handle_for(id))
+    })
+}
+
+pub fn rename(device: &str, object: u32, name: &str) -> Result<(), String> {
+    with_device(device, |open| unsafe {
+        let id = wide(&open.object_id(object)?);
+        let name = wide(name);
+        let values = new_values()?;
+        values
+            .SetStringValue(&WPD_OBJECT_ORIGINAL_FILE_NAME, PCWSTR(name.as_ptr()))
+            .map_err(failed("rename"))?;
+        let results = open
+            .content
+            .Properties()
+            .and_then(|properties| properties.SetValues(PCWSTR(id.as_ptr()), &values))
+            .map_err(failed("rename"))?;
+        // Each property reports its own result; read-only names fail here
+        if let Ok(status) = results.GetErrorValue(&WPD_OBJECT_ORIGINAL_FILE_NAME) {
+            status.ok().map_err(failed("rename"))?;
+        }
+        Ok(())
+    })
+}
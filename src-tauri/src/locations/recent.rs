@@ -0,0 +1,243 @@
+//! `recent://` lists the most recently modified files across the pinned directories.
+//!
+//! Only `recent:///` exists; its entries keep their real local paths, so opening,
+//! revealing or dragging one acts on the file itself. The listing is read-only and
+//! cached for a few seconds, since each refresh walks every pinned tree.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use tauri::async_runtime::spawn_blocking;
+use tauri::{AppHandle, Manager};
+use walkdir::WalkDir;
+
+use super::{
+    Location, LocationCapabilities, LocationProvider, LocationSummary, ProviderDirectoryEntries,
+};
+use crate::fs_utils::{get_file_info, FileItem};
+
+/// Levels below each pinned directory that are searched
+const MAX_DEPTH: usize = 4;
+/// Files shown in the listing
+const MAX_RESULTS: usize = 200;
+/// Entries visited per pinned directory, so a huge tree can't stall the listing
+const MAX_ENTRIES_PER_ROOT: usize = 50_000;
+const CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// App handle used to read the "Show Hidden Files" menu state
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+struct CachedListing {
+    built_at: Instant,
+    show_hidden: bool,
+    entries: Vec<FileItem>,
+}
+
+static CACHE: Lazy<Mutex<Option<CachedListing>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn init_recent(app_handle: AppHandle) {
+    let _ = APP_HANDLE.set(app_handle);
+}
+
+fn show_hidden() -> bool {
+    APP_HANDLE
+        .get()
+        .and_then(|app| app.try_state::<crate::state::MenuState<tauri::Wry>>())
+        .and_then(|state| state.show_hidden_checked.lock().ok().map(|flag| *flag))
+        .unwrap_or(false)
+}
+
+fn ensure_root(location: &Location) -> Result<(), String> {
+    if location.scheme() != "recent" {
+        return Err("RecentProvider only supports recent:// locations".to_string());
+    }
+    if location.path().trim_matches('/').is_empty() {
+        Ok(())
+    } else {
+        Err("Recent files has no subfolders".to_string())
+    }
+}
+
+/// The `limit` newest files under `roots`, newest first. Hidden files and folders are
+/// skipped unless `show_hidden` is set; overlapping roots don't produce duplicates.
+fn newest_files(roots: &[PathBuf], limit: usize, show_hidden: bool) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    let mut files: Vec<(SystemTime, PathBuf)> = Vec::new();
+
+    for root in roots {
+        let walker = WalkDir::new(root)
+            .max_depth(MAX_DEPTH)
+            .follow_links(false)
+            .into_iter()
+            .filter_entry(|entry| {
+                show_hidden
+                    || entry.depth() == 0
+                    || !entry.file_name().to_string_lossy().starts_with('.')
+            });
+        for entry in walker.take(MAX_ENTRIES_PER_ROOT).flatten() {
+            if !entry.file_type().is_file() || !seen.insert(entry.path().to_path_buf()) {
+                continue;
+            }
+            if let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) {
+                files.push((modified, entry.into_path()));
+            }
+        }
+    }
+
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+    files.truncate(limit);
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+fn build_listing(show_hidden: bool) -> Result<Vec<FileItem>, String> {
+    let roots: Vec<PathBuf> = crate::commands::pinned_directory_paths()?
+        .into_iter()
+        .filter(|root| root.is_dir())
+        .collect();
+    Ok(newest_files(&roots, MAX_RESULTS, show_hidden)
+        .iter()
+        .filter_map(|path| get_file_info(path).ok())
+        .collect())
+}
+
+#[derive(Default)]
+pub struct RecentProvider;
+
+#[async_trait]
+impl LocationProvider for RecentProvider {
+    fn scheme(&self) -> &'static str {
+        "recent"
+    }
+
+    fn capabilities(&self, _location: &Location) -> LocationCapabilities {
+        LocationCapabilities::new("recent", "Recent", true, false)
+    }
+
+    async fn read_directory(
+        &self,
+        location: &Location,
+    ) -> Result<ProviderDirectoryEntries, String> {
+        ensure_root(location)?;
+        let show_hidden = show_hidden();
+        let summary = LocationSummary::new("recent", None, "/", "Recent");
+
+        if let Ok(cache) = CACHE.lock() {
+            if let Some(cached) = cache.as_ref().filter(|cached| {
+                cached.show_hidden == show_hidden && cached.built_at.elapsed() < CACHE_TTL
+            }) {
+                return Ok(ProviderDirectoryEntries {
+                    location: summary,
+                    entries: cached.entries.clone(),
+                });
+            }
+        }
+
+        let entries = spawn_blocking(move || build_listing(show_hidden))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+        if let Ok(mut cache) = CACHE.lock() {
+            *cache = Some(CachedListing {
+                built_at: Instant::now(),
+                show_hidden,
+                entries: entries.clone(),
+            });
+        }
+
+        Ok(ProviderDirectoryEntries {
+            location: summary,
+            entries,
+        })
+    }
+
+    async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
+        ensure_root(location)?;
+        Ok(FileItem {
+            name: "Recent".to_string(),
+            path: "recent:///".to_string(),
+            size: 0,
+            modified: Utc::now(),
+            is_directory: true,
+            is_hidden: false,
+            is_symlink: false,
+            is_git_repo: false,
+            extension: None,
+            child_count: None,
+            image_width: None,
+            image_height: None,
+            remote_id: None,
+            thumbnail_url: None,
+            download_url: None,
+            tags: None,
+            is_git_submodule: false,
+            lfs_size: None,
+            id: String::new(),
+        })
+    }
+
+    async fn create_directory(&self, _location: &Location) -> Result<(), String> {
+        Err("Recent files is read-only".to_string())
+    }
+
+    async fn delete(&self, _location: &Location) -> Result<(), String> {
+        Err("Recent files is read-only".to_string())
+    }
+
+    async fn rename(&self, _from: &Location, _to: &Location) -> Result<(), String> {
+        Err("Recent files is read-only".to_string())
+    }
+
+    async fn copy(&self, _from: &Location, _to: &Location) -> Result<(), String> {
+        Err("Recent files is read-only".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::path::Path;
+
+    fn touch(path: &Path, secs_ago: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let file = File::create(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(secs_ago))
+            .unwrap();
+    }
+
+    #[test]
+    fn lists_newest_files_across_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let docs = dir.path().join("docs");
+        let photos = dir.path().join("photos");
+        touch(&docs.join("old.txt"), 300);
+        touch(&docs.join("nested/new.txt"), 10);
+        touch(&docs.join(".secret"), 1);
+        touch(&docs.join(".cache/blob"), 2);
+        touch(&photos.join("today.jpg"), 20);
+        touch(&docs.join("a/b/c/d/e/too-deep.txt"), 0);
+
+        let names = |paths: Vec<PathBuf>| -> Vec<String> {
+            paths
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+                .collect()
+        };
+
+        // `docs` is listed twice through an overlapping root
+        let roots = [docs.clone(), photos, docs.join("nested")];
+        assert_eq!(
+            names(newest_files(&roots, 10, false)),
+            ["new.txt", "today.jpg", "old.txt"]
+        );
+        assert_eq!(
+            names(newest_files(&roots, 2, false)),
+            ["new.txt", "today.jpg"]
+        );
+        assert_eq!(names(newest_files(&roots, 2, true)), [".secret", "blob"]);
+    }
+}
@@ -14,6 +14,7 @@ pub struct SftpServerInfo {
     pub port: u16,
     pub username: String,
     pub auth_method: String, // "password", "key", "agent"
+    /// Private key for "key" auth; `None` tries `~/.ssh/id_ed25519`, `id_ecdsa`, `id_rsa`
     pub key_path: Option<String>,
 }
 
@@ -33,6 +34,7 @@ struct SftpServer {
 pub struct SftpServerCredentials {
     pub username: String,
     pub auth_method: String,
+    /// The password, or the key passphrase for "key" auth
     pub password: Option<String>,
     pub key_path: Option<String>,
 }
@@ -263,6 +265,10 @@ pub fn add_sftp_server(
     key_path: Option<String>,
 ) -> Result<SftpServerInfo, String> {
     let mut servers = load_servers_from_disk()?;
+    // No key path means the default keys in ~/.ssh are tried
+    let key_path = key_path
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty());
 
     if let Some(existing) = servers
         .iter_mut()
@@ -0,0 +1,150 @@
+//! Host key verification against `~/.ssh/known_hosts`.
+//!
+//! An unknown host is rejected with `[SFTP_HOST_KEY_UNKNOWN]` followed by a JSON
+//! [`HostKeyPrompt`]. The offered key is kept in memory so `trust_sftp_host_key`
+//! records exactly the key whose fingerprint the user confirmed. A key that no
+//! longer matches the recorded one is rejected with `[SFTP_HOST_KEY_CHANGED]` and
+//! can't be trusted from the app; the user has to fix `known_hosts` themselves.
+
+use once_cell::sync::Lazy;
+use russh::keys::known_hosts::{check_known_hosts_path, learn_known_hosts_path};
+use russh::keys::{HashAlg, PublicKey};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Details shown to the user before trusting a new host
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyPrompt {
+    pub hostname: String,
+    pub port: u16,
+    pub algorithm: String,
+    pub fingerprint: String,
+}
+
+/// Keys offered by hosts that aren't in known_hosts yet, by (hostname, port)
+static PENDING_KEYS: Lazy<Mutex<HashMap<(String, u16), PublicKey>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn known_hosts_path() -> Result<PathBuf, String> {
+    dirs::home_dir()
+        .map(|home| home.join(".ssh").join("known_hosts"))
+        .ok_or_else(|| "Could not determine home directory".to_string())
+}
+
+fn fingerprint(key: &PublicKey) -> String {
+    key.fingerprint(HashAlg::Sha256).to_string()
+}
+
+/// Check the key a server presented. `Err` carries the message to report
+/// instead of the generic SSH connection failure.
+pub(crate) fn verify(hostname: &str, port: u16, key: &PublicKey) -> Result<(), String> {
+    verify_at(&known_hosts_path()?, hostname, port, key)
+}
+
+fn verify_at(path: &Path, hostname: &str, port: u16, key: &PublicKey) -> Result<(), String> {
+    let host = hostname.to_lowercase();
+    match check_known_hosts_path(&host, port, key, path) {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            let prompt = HostKeyPrompt {
+                hostname: host.clone(),
+                port,
+                algorithm: key.algorithm().to_string(),
+                fingerprint: fingerprint(key),
+            };
+            if let Ok(mut pending) = PENDING_KEYS.lock() {
+                pending.insert((host, port), key.clone());
+            }
+            let details = serde_json::to_string(&prompt)
+                .map_err(|e| format!("Failed to serialize host key: {}", e))?;
+            Err(format!("[SFTP_HOST_KEY_UNKNOWN] {}", details))
+        }
+        Err(russh::keys::Error::KeyChanged { line }) => Err(format!(
+            "[SFTP_HOST_KEY_CHANGED] The host key for {}:{} has changed and no longer \
+             matches line {} of {}. The server now presents {} {}. If this change is \
+             expected, remove the old entry from known_hosts and connect again.",
+            host,
+            port,
+            line,
+            path.display(),
+            key.algorithm(),
+            fingerprint(key)
+        )),
+        Err(e) => Err(format!("Failed to check known_hosts: {}", e)),
+    }
+}
+
+/// Append the key last offered by `hostname:port` to known_hosts, provided it still
+/// has the fingerprint the user confirmed.
+pub fn trust_host_key(hostname: &str, port: u16, expected_fingerprint: &str) -> Result<(), String> {
+    trust_at(&known_hosts_path()?, hostname, port, expected_fingerprint)
+}
+
+fn trust_at(
+    path: &Path,
+    hostname: &str,
+    port: u16,
+    expected_fingerprint: &str,
+) -> Result<(), String> {
+    let host = hostname.to_lowercase();
+    let mut pending = PENDING_KEYS
+        .lock()
+        .map_err(|_| "Failed to access pending host keys".to_string())?;
+    let key = pending.get(&(host.clone(), port)).ok_or_else(|| {
+        format!(
+            "No host key is waiting to be trusted for {}:{}; connect again first",
+            host, port
+        )
+    })?;
+    if fingerprint(key) != expected_fingerprint.trim() {
+        return Err(
+            "The server's host key doesn't match the confirmed fingerprint; connect again and re-check it"
+                .to_string(),
+        );
+    }
+
+    learn_known_hosts_path(&host, port, key, path)
+        .map_err(|e| format!("Failed to update known_hosts: {}", e))?;
+    pending.remove(&(host, port));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const KEY_A: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIF2AFUyP2EImHIJpxYyvmupo3pro8enlC1Ei+wne1ApA";
+    const KEY_B: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIG75NyaQg1T733AsxTdeNA40BmZLCOdMetYS6bSdrUPM";
+
+    #[test]
+    fn unknown_then_trusted_then_changed() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("known_hosts");
+        let key_a = PublicKey::from_openssh(KEY_A).unwrap();
+        let key_b = PublicKey::from_openssh(KEY_B).unwrap();
+
+        let err = verify_at(&path, "Files.Example.com", 2222, &key_a).unwrap_err();
+        let details = err.strip_prefix("[SFTP_HOST_KEY_UNKNOWN] ").unwrap();
+        let prompt: serde_json::Value = serde_json::from_str(details).unwrap();
+        assert_eq!(prompt["hostname"], "files.example.com");
+        assert_eq!(prompt["port"], 2222);
+        let confirmed = prompt["fingerprint"].as_str().unwrap();
+        assert!(confirmed.starts_with("SHA256:"));
+
+        // A fingerprint the user never saw is refused
+        assert!(trust_at(&path, "files.example.com", 2222, "SHA256:bogus").is_err());
+        trust_at(&path, "files.example.com", 2222, confirmed).unwrap();
+        assert!(verify_at(&path, "files.example.com", 2222, &key_a).is_ok());
+
+        let changed = verify_at(&path, "files.example.com", 2222, &key_b).unwrap_err();
+        assert!(changed.starts_with("[SFTP_HOST_KEY_CHANGED]"));
+        // The same host on another port is a different entry
+        assert!(verify_at(&path, "files.example.com", 22, &key_a).is_err());
+    }
+}
@@ -1,12 +1,18 @@
 pub mod auth;
+pub mod host_keys;
 pub mod pool;
 
 use crate::fs_utils::FileItem;
+use crate::locations::transfer::{StagedFile, TransferProgress, UPLOAD_CANCELLED};
 use crate::locations::{
-    Location, LocationCapabilities, LocationProvider, LocationSummary, ProviderDirectoryEntries,
+    walk_search, Location, LocationCapabilities, LocationError, LocationProvider, LocationSummary,
+    ProviderDirectoryEntries, SearchCollector, SearchQuery, SearchSink,
 };
+use crate::sorting::{sort_entries, SortOptions};
 use async_trait::async_trait;
 use chrono::{TimeZone, Utc};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 pub use auth::{add_sftp_server, get_sftp_servers, remove_sftp_server, SftpServerInfo};
 
@@ -27,6 +33,7 @@ impl LocationProvider for SftpProvider {
         &self,
         location: &Location,
     ) -> Result<ProviderDirectoryEntries, String> {
+        let _permit = crate::locations::throttle::acquire("sftp").await?;
         let authority = location
             .authority()
             .ok_or_else(|| "SFTP path requires server: sftp://user@host:port/path".to_string())?;
@@ -39,12 +46,12 @@ impl LocationProvider for SftpProvider {
             &path
         };
 
-        let sftp = pool::get_sftp_session(&hostname, port).await?;
-
-        let entries = sftp
-            .read_dir(remote_path)
-            .await
-            .map_err(|e| format!("Failed to read directory: {}", e))?;
+        let entries = pool::with_session(&hostname, port, |sftp| async move {
+            sftp.read_dir(remote_path)
+                .await
+                .map_err(|e| sftp_error("Failed to read directory", e))
+        })
+        .await?;
 
         let mut items: Vec<FileItem> = Vec::new();
 
@@ -98,14 +105,15 @@ impl LocationProvider for SftpProvider {
                 remote_id: None,
                 thumbnail_url: None,
                 download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
             });
         }
 
-        items.sort_by(|a, b| match (a.is_directory, b.is_directory) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        });
+        // Default order; read_directory re-sorts with the user's preferences
+        sort_entries(&mut items, &SortOptions::default());
 
         let display_path = if port == 22 {
             format!("sftp://{}@{}{}", username, hostname, remote_path)
@@ -124,7 +132,98 @@ impl LocationProvider for SftpProvider {
         })
     }
 
+    async fn search(
+        &self,
+        location: &Location,
+        query: &SearchQuery,
+        cancel: Arc<AtomicBool>,
+        sink: SearchSink,
+    ) -> Result<usize, String> {
+        let authority = location
+            .authority()
+            .ok_or_else(|| "SFTP path requires server: sftp://user@host:port/path".to_string())?;
+        let (username, hostname, port) = parse_sftp_authority(authority)?;
+        let root = match location.path() {
+            "" => "/",
+            path => path,
+        };
+
+        // One `find` on the server beats listing every folder over SFTP. Servers without
+        // a shell, or without GNU find, get the folder-by-folder walk instead.
+        let prefix = format!("sftp://{}@{}:{}", username, hostname, port);
+        let mut matches = SearchCollector::new(query, &sink);
+        let mut pending = Vec::new();
+        let result = {
+            let _permit = crate::locations::throttle::acquire("sftp").await?;
+            let command = find_command(root, query);
+            pool::exec_command_streaming(&hostname, port, &command, &cancel, |data| {
+                pending.extend_from_slice(data);
+                // Records end in NUL; keep a partial one for the next chunk
+                let complete = pending.iter().rposition(|&b| b == 0).map_or(0, |i| i + 1);
+                for record in pending[..complete].split(|&b| b == 0) {
+                    if let Some(item) = parse_find_record(record, &prefix) {
+                        matches.offer(item);
+                    }
+                }
+                pending.drain(..complete);
+                matches.flush_if_due();
+                !matches.is_full()
+            })
+            .await
+        };
+
+        match result {
+            Err(e) if matches.found() == 0 => {
+                log::debug!(
+                    "Server-side search on {}:{} failed, walking folders instead: {}",
+                    hostname,
+                    port,
+                    e
+                );
+                walk_search(self, location, query, &cancel, &sink).await
+            }
+            // What turned up before the connection broke is still worth showing
+            Err(e) => {
+                log::debug!(
+                    "Server-side search on {}:{} broke off: {}",
+                    hostname,
+                    port,
+                    e
+                );
+                Ok(matches.finish())
+            }
+            Ok(()) => Ok(matches.finish()),
+        }
+    }
+
+    async fn child_count(&self, location: &Location) -> Result<u64, String> {
+        let _permit = crate::locations::throttle::acquire("sftp").await?;
+        let authority = location
+            .authority()
+            .ok_or_else(|| "SFTP path requires server".to_string())?;
+        let (_, hostname, port) = parse_sftp_authority(authority)?;
+        let remote_path = match location.path() {
+            "" => "/",
+            path => path,
+        };
+
+        let entries = pool::with_session(&hostname, port, |sftp| async move {
+            sftp.read_dir(remote_path)
+                .await
+                .map_err(|e| sftp_error("Failed to read directory", e))
+        })
+        .await?;
+
+        Ok(entries
+            .filter(|entry| {
+                let name = entry.file_name();
+                name != "." && name != ".."
+            })
+            .count() as u64)
+    }
+
     async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
+        let _permit = crate::locations::throttle::acquire("sftp").await?;
         let authority = location
             .authority()
             .ok_or_else(|| "SFTP path requires server".to_string())?;
@@ -153,15 +252,22 @@ impl LocationProvider for SftpProvider {
                 remote_id: None,
                 thumbnail_url: None,
                 download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
             });
         }
 
-        let sftp = pool::get_sftp_session(&hostname, port).await?;
-
-        let attrs = sftp
-            .metadata(&remote_path)
-            .await
-            .map_err(|e| format!("Failed to get metadata: {}", e))?;
+        let attrs = pool::with_session(&hostname, port, |sftp| {
+            let remote_path = remote_path.clone();
+            async move {
+                sftp.metadata(remote_path)
+                    .await
+                    .map_err(|e| sftp_error("Failed to get metadata", e))
+            }
+        })
+        .await?;
 
         let name = std::path::Path::new(&remote_path)
             .file_name()
@@ -205,6 +311,10 @@ impl LocationProvider for SftpProvider {
             remote_id: None,
             thumbnail_url: None,
             download_url: None,
+            tags: None,
+            is_git_submodule: false,
+            lfs_size: None,
+            id: String::new(),
         })
     }
 
@@ -218,11 +328,25 @@ impl LocationProvider for SftpProvider {
         let sftp = pool::get_sftp_session(&hostname, port).await?;
         sftp.create_dir(&remote_path)
             .await
-            .map_err(|e| format!("Failed to create directory: {}", e))?;
+            .map_err(|e| sftp_error("Failed to create directory", e))?;
 
         Ok(())
     }
 
+    async fn create_file(
+        &self,
+        directory: &Location,
+        name: &str,
+        contents: &[u8],
+    ) -> Result<String, String> {
+        let authority = directory
+            .authority()
+            .ok_or_else(|| "SFTP path requires server".to_string())?;
+        let (_username, hostname, port) = parse_sftp_authority(authority)?;
+        let staged = StagedFile::write(contents).await?;
+        upload_file_to_sftp(staged.path(), &hostname, port, directory.path(), name, None).await
+    }
+
     async fn delete(&self, location: &Location) -> Result<(), String> {
         let authority = location
             .authority()
@@ -235,14 +359,14 @@ impl LocationProvider for SftpProvider {
         let attrs = sftp
             .metadata(&remote_path)
             .await
-            .map_err(|e| format!("Failed to stat path for deletion: {}", e))?;
+            .map_err(|e| sftp_error("Failed to stat path for deletion", e))?;
 
         if attrs.is_dir() {
             recursive_delete(&sftp, &remote_path).await?;
         } else {
             sftp.remove_file(&remote_path)
                 .await
-                .map_err(|e| format!("Failed to remove file: {}", e))?;
+                .map_err(|e| sftp_error("Failed to remove file", e))?;
         }
 
         Ok(())
@@ -260,13 +384,16 @@ impl LocationProvider for SftpProvider {
         let (_to_user, to_host, to_port) = parse_sftp_authority(to_authority)?;
 
         if from_host != to_host || from_port != to_port {
-            return Err("Cannot rename across different servers".to_string());
+            return Err(LocationError::Unsupported(
+                "Cannot rename across different servers".to_string(),
+            )
+            .into());
         }
 
         let sftp = pool::get_sftp_session(&from_host, from_port).await?;
         sftp.rename(from.path(), to.path())
             .await
-            .map_err(|e| format!("Failed to rename: {}", e))?;
+            .map_err(|e| sftp_error("Failed to rename", e))?;
 
         Ok(())
     }
@@ -279,27 +406,238 @@ impl LocationProvider for SftpProvider {
             .authority()
             .ok_or_else(|| "SFTP path requires server".to_string())?;
 
-        let (_from_user, from_host, from_port) = parse_sftp_authority(from_authority)?;
-        let (_to_user, to_host, to_port) = parse_sftp_authority(to_authority)?;
+        if !same_server(from_authority, to_authority)? {
+            return Err(LocationError::Unsupported(
+                "Copying across different SFTP servers is not supported".to_string(),
+            )
+            .into());
+        }
+        let (_username, hostname, port) = parse_sftp_authority(from_authority)?;
 
-        if from_host != to_host || from_port != to_port {
-            return Err("Copying across different SFTP servers is not supported".to_string());
+        let sftp = pool::get_sftp_session(&hostname, port).await?;
+        let is_dir = sftp
+            .metadata(from.path())
+            .await
+            .map_err(|e| sftp_error("Failed to stat source", e))?
+            .is_dir();
+
+        // SFTP has no copy operation, so ask the server's shell to do it in place
+        let command = copy_command(from.path(), to.path(), is_dir);
+        match pool::exec_command(&hostname, port, &command).await {
+            Ok(()) => return Ok(()),
+            Err(e) => log::debug!(
+                "Server-side copy on {}:{} failed, copying over SFTP instead: {}",
+                hostname,
+                port,
+                e
+            ),
         }
 
-        // SFTP has no server-side copy; download into memory and re-upload
-        let sftp = pool::get_sftp_session(&from_host, from_port).await?;
+        if is_dir {
+            recursive_copy(&sftp, from.path(), to.path()).await
+        } else {
+            copy_file_in_memory(&sftp, from.path(), to.path()).await
+        }
+    }
+}
 
-        let data = sftp
-            .read(from.path())
-            .await
-            .map_err(|e| format!("Failed to read source file: {}", e))?;
+/// Copy one file by downloading it into memory and writing it back.
+async fn copy_file_in_memory(
+    sftp: &russh_sftp::client::SftpSession,
+    from: &str,
+    to: &str,
+) -> Result<(), String> {
+    let data = sftp
+        .read(from)
+        .await
+        .map_err(|e| sftp_error("Failed to read source file", e))?;
 
-        sftp.write(to.path(), &data)
-            .await
-            .map_err(|e| format!("Failed to write destination file: {}", e))?;
+    sftp.write(to, &data)
+        .await
+        .map_err(|e| sftp_error("Failed to write destination file", e))
+}
 
-        Ok(())
+/// Recursively copy a directory over SFTP, one file at a time.
+async fn recursive_copy(
+    sftp: &russh_sftp::client::SftpSession,
+    from: &str,
+    to: &str,
+) -> Result<(), String> {
+    if let Err(e) = sftp.create_dir(to).await {
+        // A failed server-side copy may already have created it
+        let exists = sftp.metadata(to).await.map(|a| a.is_dir()).unwrap_or(false);
+        if !exists {
+            return Err(sftp_error(&format!("Failed to create directory {}", to), e));
+        }
     }
+
+    let entries = sftp
+        .read_dir(from)
+        .await
+        .map_err(|e| sftp_error("Failed to list directory for copying", e))?;
+
+    for entry in entries {
+        let name = entry.file_name();
+        if name == "." || name == ".." {
+            continue;
+        }
+
+        let source = join_remote_path(from, &name);
+        let target = join_remote_path(to, &name);
+        if entry.file_type().is_dir() {
+            Box::pin(recursive_copy(sftp, &source, &target)).await?;
+        } else {
+            copy_file_in_memory(sftp, &source, &target).await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn join_remote_path(dir: &str, name: &str) -> String {
+    if dir == "/" {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", dir.trim_end_matches('/'), name)
+    }
+}
+
+/// Quote `value` for a POSIX shell.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// The `cp` invocation that copies `from` to `to` on the server itself.
+fn copy_command(from: &str, to: &str, is_dir: bool) -> String {
+    let flags = if is_dir { "-pr" } else { "-p" };
+    format!("cp {} -- {} {}", flags, shell_quote(from), shell_quote(to))
+}
+
+/// Escape glob metacharacters so `find -name` matches `text` literally.
+fn escape_glob(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// The `find` invocation for `query` under `root`. Each match is printed as
+/// `type<TAB>size<TAB>mtime<TAB>path<NUL>`. Only GNU find has `-printf`, so other
+/// finds are turned away up front; unreadable subfolders don't fail the search.
+fn find_command(root: &str, query: &SearchQuery) -> String {
+    let options = query.options();
+    let mut command = format!(
+        "find --version >/dev/null 2>&1 || exit 127; find {} -mindepth 1",
+        shell_quote(root)
+    );
+    if !options.recursive {
+        command.push_str(" -maxdepth 1");
+    }
+    if !options.include_hidden {
+        command.push_str(" -name '.*' -prune -o");
+    }
+    let pattern = if options.glob {
+        query.text().to_string()
+    } else {
+        format!("*{}*", escape_glob(query.text()))
+    };
+    let test = if options.case_sensitive {
+        "-name"
+    } else {
+        "-iname"
+    };
+    command.push_str(&format!(
+        " {} {} -printf '%y\\t%s\\t%T@\\t%p\\0' 2>/dev/null; exit 0",
+        test,
+        shell_quote(&pattern)
+    ));
+    command
+}
+
+/// Turn one NUL-terminated `find_command` record into a listing entry with `prefix`
+/// (`sftp://user@host:port`).
+fn parse_find_record(record: &[u8], prefix: &str) -> Option<FileItem> {
+    let record = String::from_utf8_lossy(record);
+    let mut fields = record.splitn(4, '\t');
+    let kind = fields.next()?;
+    let size = fields.next()?.parse::<u64>().unwrap_or(0);
+    let mtime = fields
+        .next()?
+        .split('.')
+        .next()?
+        .parse::<i64>()
+        .unwrap_or(0);
+    let path = fields.next()?;
+    let name = path.rsplit('/').next().filter(|name| !name.is_empty())?;
+
+    let is_directory = kind == "d";
+    let extension = if is_directory {
+        None
+    } else {
+        std::path::Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(String::from)
+    };
+    Some(FileItem {
+        name: name.to_string(),
+        path: format!("{}{}", prefix, path),
+        size: if is_directory { 0 } else { size },
+        modified: Utc
+            .timestamp_opt(mtime, 0)
+            .single()
+            .unwrap_or_else(Utc::now),
+        is_directory,
+        is_hidden: name.starts_with('.'),
+        is_symlink: kind == "l",
+        is_git_repo: false,
+        extension,
+        child_count: None,
+        image_width: None,
+        image_height: None,
+        remote_id: None,
+        thumbnail_url: None,
+        download_url: None,
+        tags: None,
+        is_git_submodule: false,
+        lfs_size: None,
+        id: String::new(),
+    })
+}
+
+/// `context` plus what went wrong, coded by the status the server sent rather than
+/// by its wording, which varies between servers.
+pub(crate) fn sftp_error(context: &str, error: russh_sftp::client::error::Error) -> String {
+    use russh_sftp::client::error::Error as SftpError;
+    use russh_sftp::protocol::StatusCode;
+
+    let message = format!("{}: {}", context, error);
+    match &error {
+        SftpError::Status(status) => match status.status_code {
+            StatusCode::NoSuchFile => LocationError::NotFound(message),
+            StatusCode::PermissionDenied => LocationError::PermissionDenied(message),
+            StatusCode::NoConnection | StatusCode::ConnectionLost => {
+                LocationError::Network(message)
+            }
+            StatusCode::OpUnsupported => LocationError::Unsupported(message),
+            _ => LocationError::Other(message),
+        },
+        SftpError::Timeout | SftpError::IO(_) => LocationError::Network(message),
+        _ => LocationError::Other(message),
+    }
+    .into()
+}
+
+/// Whether two "user@host:port" authorities point at the same server. The user
+/// doesn't matter: connections are pooled per host and port.
+fn same_server(a: &str, b: &str) -> Result<bool, String> {
+    let (_, a_host, a_port) = parse_sftp_authority(a)?;
+    let (_, b_host, b_port) = parse_sftp_authority(b)?;
+    Ok(a_host.eq_ignore_ascii_case(&b_host) && a_port == b_port)
 }
 
 /// Recursively delete a directory and all its contents.
@@ -310,7 +648,7 @@ async fn recursive_delete(
     let entries = sftp
         .read_dir(path)
         .await
-        .map_err(|e| format!("Failed to list directory for deletion: {}", e))?;
+        .map_err(|e| sftp_error("Failed to list directory for deletion", e))?;
 
     for entry in entries {
         let name = entry.file_name();
@@ -329,13 +667,13 @@ async fn recursive_delete(
         } else {
             sftp.remove_file(&child_path)
                 .await
-                .map_err(|e| format!("Failed to delete file {}: {}", child_path, e))?;
+                .map_err(|e| sftp_error(&format!("Failed to delete file {}", child_path), e))?;
         }
     }
 
     sftp.remove_dir(path)
         .await
-        .map_err(|e| format!("Failed to remove directory {}: {}", path, e))?;
+        .map_err(|e| sftp_error(&format!("Failed to remove directory {}", path), e))?;
 
     Ok(())
 }
@@ -394,27 +732,99 @@ pub fn parse_sftp_url(url: &str) -> Result<(String, String, u16, String), String
     Ok((username, hostname, port, path))
 }
 
-/// Download a file from SFTP to a local path.
-/// Acquires a concurrency permit to avoid overwhelming the server.
+/// Chunk size for streaming transfers, so large files never sit in memory whole.
+const TRANSFER_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Copy `reader` into `writer` one chunk at a time, then flush and close the writer.
+/// With `progress`, each chunk is reported and a cancelled transfer stops early.
+async fn stream_in_chunks<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    progress: Option<&TransferProgress>,
+) -> std::io::Result<u64>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut buf = vec![0u8; TRANSFER_CHUNK_SIZE];
+    let mut total = 0u64;
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n]).await?;
+        total += n as u64;
+        if let Some(progress) = progress {
+            progress
+                .update(total)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Interrupted, e))?;
+        }
+    }
+    writer.shutdown().await?;
+    Ok(total)
+}
+
+/// Download a file from SFTP to a local path, streaming it in chunks.
+/// Acquires a concurrency permit to avoid overwhelming the server, and starts over
+/// on a new connection if the pooled one drops mid-transfer.
 pub async fn download_file_from_sftp(
     hostname: &str,
     port: u16,
     remote_path: &str,
     dest: &std::path::Path,
 ) -> Result<(), String> {
-    let sftp = pool::get_sftp_session(hostname, port).await?;
-    let _permit = pool::acquire_permit(hostname, port).await?;
+    pool::with_session(hostname, port, |sftp| async move {
+        let _permit = pool::acquire_permit(hostname, port).await?;
 
-    let data = sftp
-        .read(remote_path)
-        .await
-        .map_err(|e| format!("Failed to download file: {}", e))?;
+        let mut remote = sftp
+            .open(remote_path)
+            .await
+            .map_err(|e| sftp_error("Failed to download file", e))?;
+        let mut local = tokio::fs::File::create(dest)
+            .await
+            .map_err(|e| format!("Failed to write downloaded file: {}", e))?;
 
-    tokio::fs::write(dest, &data)
-        .await
-        .map_err(|e| format!("Failed to write downloaded file: {}", e))?;
+        stream_in_chunks(&mut remote, &mut local, None)
+            .await
+            .map_err(|e| format!("Failed to download file: {}", e))?;
 
-    Ok(())
+        Ok(())
+    })
+    .await
+}
+
+/// Download at most `max_bytes` from the start of a file on SFTP to a local path, for
+/// readers that only look at the head of the file.
+pub async fn download_file_head_from_sftp(
+    hostname: &str,
+    port: u16,
+    remote_path: &str,
+    dest: &std::path::Path,
+    max_bytes: u64,
+) -> Result<(), String> {
+    use tokio::io::AsyncReadExt;
+
+    pool::with_session(hostname, port, |sftp| async move {
+        let _permit = pool::acquire_permit(hostname, port).await?;
+
+        let remote = sftp
+            .open(remote_path)
+            .await
+            .map_err(|e| sftp_error("Failed to download file", e))?;
+        let mut local = tokio::fs::File::create(dest)
+            .await
+            .map_err(|e| format!("Failed to write downloaded file: {}", e))?;
+
+        stream_in_chunks(&mut remote.take(max_bytes), &mut local, None)
+            .await
+            .map_err(|e| format!("Failed to download file: {}", e))?;
+
+        Ok(())
+    })
+    .await
 }
 
 /// Upload a local file to SFTP, handling name collisions.
@@ -425,10 +835,12 @@ pub async fn upload_file_to_sftp(
     port: u16,
     dest_dir: &str,
     preferred_name: &str,
+    progress: Option<&TransferProgress>,
 ) -> Result<String, String> {
     let sftp = pool::get_sftp_session(hostname, port).await?;
+    let _permit = pool::acquire_permit(hostname, port).await?;
 
-    let data = tokio::fs::read(local_path)
+    let mut local = tokio::fs::File::open(local_path)
         .await
         .map_err(|e| format!("Failed to read local file: {}", e))?;
 
@@ -463,9 +875,19 @@ pub async fn upload_file_to_sftp(
             Ok(_) => continue, // File exists, try next name
             Err(_) => {
                 // File doesn't exist, upload here
-                sftp.write(&dest_path, &data)
+                let mut remote = sftp
+                    .create(&dest_path)
                     .await
-                    .map_err(|e| format!("Failed to upload file: {}", e))?;
+                    .map_err(|e| sftp_error("Failed to upload file", e))?;
+                if let Err(e) = stream_in_chunks(&mut local, &mut remote, progress).await {
+                    // Don't leave a truncated file behind
+                    drop(remote);
+                    let _ = sftp.remove_file(&dest_path).await;
+                    if progress.is_some_and(TransferProgress::is_cancelled) {
+                        return Err(UPLOAD_CANCELLED.to_string());
+                    }
+                    return Err(format!("Failed to upload file: {}", e));
+                }
                 return Ok(candidate);
             }
         }
@@ -508,7 +930,10 @@ pub async fn download_sftp_file_to_temp(sftp_url: &str) -> Result<std::path::Pat
 
     let temp_path = temp_dir.join(format!("{}_{}", hash_prefix, safe_name));
 
-    download_file_from_sftp(&hostname, port, &remote_path, &temp_path).await?;
+    crate::locations::transfer::download_to_stable_path(&temp_path, |part_path| async move {
+        download_file_from_sftp(&hostname, port, &remote_path, &part_path).await
+    })
+    .await?;
 
     Ok(temp_path)
 }
@@ -538,6 +963,81 @@ mod tests {
         assert!(parse_sftp_authority("host.com").is_err());
     }
 
+    #[test]
+    fn test_same_server() {
+        assert!(same_server("demo@Files.Example.com", "demo@files.example.com:22").unwrap());
+        assert!(same_server("alice@host:2222", "bob@host:2222").unwrap());
+        assert!(!same_server("demo@host:2222", "demo@host").unwrap());
+        assert!(!same_server("demo@host-a", "demo@host-b").unwrap());
+        assert!(same_server("host", "demo@host").is_err());
+    }
+
+    #[test]
+    fn test_copy_command_quotes_paths() {
+        assert_eq!(
+            copy_command("/srv/it's here.iso", "/srv/copy.iso", false),
+            "cp -p -- '/srv/it'\\''s here.iso' '/srv/copy.iso'"
+        );
+        assert_eq!(
+            copy_command("/srv/dir", "/srv/dir copy", true),
+            "cp -pr -- '/srv/dir' '/srv/dir copy'"
+        );
+    }
+
+    #[test]
+    fn test_find_command_and_output() {
+        let query = SearchQuery::new("a*b", Default::default()).unwrap();
+        assert_eq!(
+            find_command("/srv/data", &query),
+            "find --version >/dev/null 2>&1 || exit 127; \
+             find '/srv/data' -mindepth 1 -maxdepth 1 -name '.*' -prune -o \
+             -iname '*a\\*b*' -printf '%y\\t%s\\t%T@\\t%p\\0' 2>/dev/null; exit 0"
+        );
+
+        let output =
+            b"f\t42\t1700000000.5\t/srv/data/a*b.txt\0d\t4096\t1700000000.0\t/srv/data/a*b dir\0";
+        let items: Vec<FileItem> = output
+            .split(|&b| b == 0)
+            .filter_map(|record| parse_find_record(record, "sftp://me@host:22"))
+            .collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].path, "sftp://me@host:22/srv/data/a*b.txt");
+        assert_eq!(items[0].size, 42);
+        assert_eq!(items[0].extension.as_deref(), Some("txt"));
+        assert!(items[1].is_directory);
+        assert_eq!(items[1].size, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_in_chunks_copies_across_chunk_boundaries() {
+        let data: Vec<u8> = (0..TRANSFER_CHUNK_SIZE * 2 + 17)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let mut reader = data.as_slice();
+        let mut written = Vec::new();
+        let copied = stream_in_chunks(&mut reader, &mut written, None)
+            .await
+            .unwrap();
+        assert_eq!(copied, data.len() as u64);
+        assert_eq!(written, data);
+    }
+
+    #[tokio::test]
+    async fn test_stream_in_chunks_stops_when_cancelled() {
+        let data = vec![7u8; TRANSFER_CHUNK_SIZE * 3];
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        // Cancel as soon as the first chunk is reported
+        let progress = TransferProgress::new(data.len() as u64, cancel, move |_, _| {
+            flag.store(true, Ordering::SeqCst);
+        });
+        let mut reader = data.as_slice();
+        let mut written = Vec::new();
+        let result = stream_in_chunks(&mut reader, &mut written, Some(&progress)).await;
+        assert!(result.is_err());
+        assert_eq!(written.len(), TRANSFER_CHUNK_SIZE);
+    }
+
     #[test]
     fn test_parse_sftp_url_basic() {
         let (user, host, port, path) =
@@ -571,4 +1071,31 @@ mod tests {
     fn test_parse_sftp_url_no_scheme() {
         assert!(parse_sftp_url("user@host/path").is_err());
     }
+
+    #[test]
+    fn sftp_errors_are_coded_by_status() {
+        use russh_sftp::client::error::Error as SftpError;
+        use russh_sftp::protocol::{Status, StatusCode};
+
+        let status = |status_code| {
+            SftpError::Status(Status {
+                id: 1,
+                status_code,
+                // Servers word these however they like; only the code counts
+                error_message: "nope".to_string(),
+                language_tag: "en".to_string(),
+            })
+        };
+        let code = |error| {
+            LocationError::from_message(sftp_error("Failed to open", error))
+                .code()
+                .to_string()
+        };
+        assert_eq!(code(status(StatusCode::NoSuchFile)), "ENOENT");
+        assert_eq!(code(status(StatusCode::PermissionDenied)), "EPERM");
+        assert_eq!(code(status(StatusCode::ConnectionLost)), "ENETWORK");
+        assert_eq!(code(status(StatusCode::OpUnsupported)), "EUNSUPPORTED");
+        assert_eq!(code(SftpError::Timeout), "ENETWORK");
+        assert_eq!(code(status(StatusCode::Failure)), "EOTHER");
+    }
 }
@@ -1,17 +1,23 @@
 use once_cell::sync::Lazy;
-use russh::keys::PublicKey;
-use russh::{client, ChannelId};
+use russh::keys::{PrivateKeyWithHashAlg, PublicKey};
+use russh::{client, ChannelId, ChannelMsg, Disconnect};
 use russh_sftp::client::SftpSession;
 use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, Semaphore};
 
-use super::auth;
+use super::{auth, host_keys};
+use crate::locations::LocationError;
 
 type PoolKey = (String, u16); // (hostname, port)
 
 struct PooledConnection {
+    /// The SSH connection the SFTP channel runs on, kept for exec channels
+    ssh: Arc<client::Handle<SshHandler>>,
     sftp: Arc<SftpSession>,
     semaphore: Arc<Semaphore>,
     last_used: Instant,
@@ -28,19 +34,50 @@ const MAX_CONCURRENT_OPS: usize = 1;
 /// Skip the liveness check if the connection was used within this many seconds.
 /// Avoids a network round trip per operation when many requests are queued.
 const LIVENESS_SKIP_SECS: u64 = 30;
-
-/// SSH client handler that accepts all host keys
-struct SshHandler;
+/// A half-open TCP connection can leave the liveness stat hanging; give up after this.
+const LIVENESS_TIMEOUT_SECS: u64 = 5;
+/// Send an SSH keepalive after this long without hearing from the server, so idle
+/// pooled sessions aren't dropped by server or NAT timeouts.
+const KEEPALIVE_INTERVAL_SECS: u64 = 30;
+/// Unanswered keepalives before russh closes the connection as dead.
+const KEEPALIVE_MAX: usize = 3;
+/// How often a streaming remote command that's producing no output checks for cancel.
+const EXEC_CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Error text russh and russh-sftp produce once the connection under a session is gone
+const STALE_SESSION_ERRORS: &[&str] = &[
+    "channel closed",
+    "session closed",
+    "broken pipe",
+    "connection reset",
+    "senderror",
+];
+
+/// SSH client handler that checks host keys against `~/.ssh/known_hosts`
+struct SshHandler {
+    hostname: String,
+    port: u16,
+    /// Why the host key was rejected, reported instead of russh's generic error
+    rejection: Arc<std::sync::Mutex<Option<String>>>,
+}
 
 impl client::Handler for SshHandler {
     type Error = russh::Error;
 
     fn check_server_key(
         &mut self,
-        _server_public_key: &PublicKey,
+        server_public_key: &PublicKey,
     ) -> impl std::future::Future<Output = Result<bool, Self::Error>> + Send {
-        // TODO: known_hosts verification
-        async { Ok(true) }
+        let accepted = match host_keys::verify(&self.hostname, self.port, server_public_key) {
+            Ok(()) => true,
+            Err(reason) => {
+                if let Ok(mut rejection) = self.rejection.lock() {
+                    *rejection = Some(reason);
+                }
+                false
+            }
+        };
+        async move { Ok(accepted) }
     }
 
     fn channel_open_confirmation(
@@ -63,77 +100,162 @@ impl client::Handler for SshHandler {
     }
 }
 
-/// Create a new SFTP session from credentials
+/// Keys looked for in `~/.ssh`, in order, when a server has no key configured.
+const DEFAULT_KEY_NAMES: &[&str] = &["id_ed25519", "id_ecdsa", "id_rsa"];
+
+fn expand_key_path(key_path: &str) -> PathBuf {
+    match (key_path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest.trim_start_matches(['/', '\\'])),
+        _ => PathBuf::from(key_path),
+    }
+}
+
+/// The configured key, or whichever default keys exist in `~/.ssh`.
+fn candidate_key_paths(key_path: Option<&str>) -> Vec<PathBuf> {
+    if let Some(key_path) = key_path.map(str::trim).filter(|p| !p.is_empty()) {
+        return vec![expand_key_path(key_path)];
+    }
+    let Some(ssh_dir) = dirs::home_dir().map(|home| home.join(".ssh")) else {
+        return Vec::new();
+    };
+    DEFAULT_KEY_NAMES
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Offer each key in turn; `Ok(true)` once the server accepts one. With `required`,
+/// a key that can't be read or decrypted is an error (so it isn't mistaken for a
+/// rejected key); otherwise it is skipped.
+async fn try_keys(
+    session: &mut client::Handle<SshHandler>,
+    username: &str,
+    keys: &[PathBuf],
+    passphrase: Option<&str>,
+    required: bool,
+) -> Result<bool, String> {
+    let mut load_error = None;
+    let mut offered = false;
+
+    for path in keys {
+        let private_key = match russh::keys::load_secret_key(path, passphrase) {
+            Ok(key) => key,
+            Err(e) => {
+                log::debug!("Skipping SSH key {}: {}", path.display(), e);
+                load_error = Some(format!(
+                    "[SFTP_KEY_UNREADABLE] Failed to load SSH key {}: {}",
+                    path.display(),
+                    e
+                ));
+                continue;
+            }
+        };
+        offered = true;
+
+        // RSA keys need an rsa-sha2-* signature on modern servers
+        let hash_alg = if private_key.algorithm().is_rsa() {
+            session
+                .best_supported_rsa_hash()
+                .await
+                .ok()
+                .flatten()
+                .flatten()
+        } else {
+            None
+        };
+        let key_with_alg = PrivateKeyWithHashAlg::new(Arc::new(private_key), hash_alg);
+        let auth_result = session
+            .authenticate_publickey(username, key_with_alg)
+            .await
+            .map_err(|e| format!("Key authentication failed: {}", e))?;
+        if auth_result.success() {
+            return Ok(true);
+        }
+    }
+
+    match load_error {
+        Some(err) if required && !offered => Err(err),
+        _ => Ok(false),
+    }
+}
+
+/// Connect, authenticate and open an SFTP session from credentials
 async fn create_session(
     hostname: &str,
     port: u16,
     creds: &auth::SftpServerCredentials,
-) -> Result<SftpSession, String> {
+) -> Result<(client::Handle<SshHandler>, SftpSession), String> {
     let config = client::Config {
+        keepalive_interval: Some(Duration::from_secs(KEEPALIVE_INTERVAL_SECS)),
+        keepalive_max: KEEPALIVE_MAX,
         ..Default::default()
     };
 
+    let rejection = Arc::new(std::sync::Mutex::new(None));
+    let handler = SshHandler {
+        hostname: hostname.to_string(),
+        port,
+        rejection: rejection.clone(),
+    };
+
     let mut session = tokio::time::timeout(
         Duration::from_secs(CONNECT_TIMEOUT_SECS),
-        client::connect(Arc::new(config), (hostname, port), SshHandler),
+        client::connect(Arc::new(config), (hostname, port), handler),
     )
     .await
     .map_err(|_| {
-        format!(
+        LocationError::Network(format!(
             "SSH connection to {}:{} timed out after {}s",
             hostname, port, CONNECT_TIMEOUT_SECS
-        )
+        ))
+        .to_string()
     })?
-    .map_err(|e| format!("SSH connection failed: {}", e))?;
+    .map_err(|e| {
+        // A rejected host key already says why, with its own code
+        rejection
+            .lock()
+            .ok()
+            .and_then(|mut r| r.take())
+            .unwrap_or_else(|| {
+                LocationError::Network(format!("SSH connection failed: {}", e)).to_string()
+            })
+    })?;
 
     // Authenticate
     match creds.auth_method.as_str() {
         "password" => {
-            let password = creds
-                .password
-                .as_deref()
-                .ok_or("Password auth selected but no password available")?;
-            let auth_result = session
-                .authenticate_password(&creds.username, password)
-                .await
-                .map_err(|e| format!("Password authentication failed: {}", e))?;
-            if !auth_result.success() {
-                return Err("Password authentication rejected by server".to_string());
+            // Servers that accept one of the user's default keys never need the password
+            let default_keys = candidate_key_paths(None);
+            if try_keys(&mut session, &creds.username, &default_keys, None, false).await? {
+                log::debug!("SFTP {}:{} accepted a default SSH key", hostname, port);
+            } else {
+                let password = creds
+                    .password
+                    .as_deref()
+                    .ok_or("Password auth selected but no password available")?;
+                let auth_result = session
+                    .authenticate_password(&creds.username, password)
+                    .await
+                    .map_err(|e| format!("Password authentication failed: {}", e))?;
+                if !auth_result.success() {
+                    return Err(
+                        "[SFTP_AUTH_FAILED] Password authentication rejected by server".to_string(),
+                    );
+                }
             }
         }
         "key" => {
-            let key_path_str = creds
-                .key_path
-                .as_deref()
-                .ok_or("Key auth selected but no key path available")?;
-
-            // Expand ~ in key path
-            let expanded = if key_path_str.starts_with('~') {
-                if let Some(home) = dirs::home_dir() {
-                    home.join(&key_path_str[1..].trim_start_matches('/'))
-                } else {
-                    std::path::PathBuf::from(key_path_str)
-                }
-            } else {
-                std::path::PathBuf::from(key_path_str)
-            };
-
-            let private_key = if let Some(passphrase) = creds.password.as_deref() {
-                russh::keys::load_secret_key(&expanded, Some(passphrase))
-                    .map_err(|e| format!("Failed to load SSH key: {}", e))?
-            } else {
-                russh::keys::load_secret_key(&expanded, None)
-                    .map_err(|e| format!("Failed to load SSH key: {}", e))?
-            };
-
-            let key_with_alg = russh::keys::PrivateKeyWithHashAlg::new(Arc::new(private_key), None);
-
-            let auth_result = session
-                .authenticate_publickey(&creds.username, key_with_alg)
-                .await
-                .map_err(|e| format!("Key authentication failed: {}", e))?;
-            if !auth_result.success() {
-                return Err("Key authentication rejected by server".to_string());
+            let keys = candidate_key_paths(creds.key_path.as_deref());
+            if keys.is_empty() {
+                return Err(
+                    "[SFTP_KEY_UNREADABLE] No SSH key configured and none found in ~/.ssh"
+                        .to_string(),
+                );
+            }
+            let passphrase = creds.password.as_deref().filter(|p| !p.is_empty());
+            if !try_keys(&mut session, &creds.username, &keys, passphrase, true).await? {
+                return Err("[SFTP_AUTH_FAILED] Key authentication rejected by server".to_string());
             }
         }
         "agent" => {
@@ -186,7 +308,10 @@ async fn create_session(
             }
 
             if !authenticated {
-                return Err("No SSH agent key was accepted by the server".to_string());
+                return Err(LocationError::PermissionDenied(
+                    "No SSH agent key was accepted by the server".to_string(),
+                )
+                .into());
             }
         }
         other => {
@@ -198,7 +323,7 @@ async fn create_session(
     let channel = session
         .channel_open_session()
         .await
-        .map_err(|e| format!("Failed to open SSH channel: {}", e))?;
+        .map_err(|e| LocationError::Network(format!("Failed to open SSH channel: {}", e)))?;
 
     channel
         .request_subsystem(true, "sftp")
@@ -209,7 +334,7 @@ async fn create_session(
         .await
         .map_err(|e| format!("Failed to initialize SFTP session: {}", e))?;
 
-    Ok(sftp)
+    Ok((session, sftp))
 }
 
 /// Get an SFTP session, reusing from pool if possible.
@@ -222,7 +347,8 @@ pub async fn get_sftp_session(hostname: &str, port: u16) -> Result<Arc<SftpSessi
         let mut pool = POOL.lock().await;
         if let Some(conn) = pool.get_mut(&key) {
             let elapsed = conn.last_used.elapsed().as_secs();
-            if elapsed < IDLE_TIMEOUT_SECS {
+            // A closed handle means keepalives went unanswered or the server hung up
+            if elapsed < IDLE_TIMEOUT_SECS && !conn.ssh.is_closed() {
                 conn.last_used = Instant::now();
                 Some((conn.sftp.clone(), elapsed))
             } else {
@@ -242,10 +368,20 @@ pub async fn get_sftp_session(hostname: &str, port: u16) -> Result<Arc<SftpSessi
         }
 
         // Verify liveness outside the lock
-        match sftp.metadata(".").await {
-            Ok(_) => return Ok(sftp),
-            Err(_) => {
-                // Connection is dead, remove from pool
+        let liveness = tokio::time::timeout(
+            Duration::from_secs(LIVENESS_TIMEOUT_SECS),
+            sftp.metadata("."),
+        )
+        .await;
+        match liveness {
+            Ok(Ok(_)) => return Ok(sftp),
+            _ => {
+                // Connection is dead or unresponsive, reconnect below
+                log::debug!(
+                    "SFTP {}:{} pooled session is dead; reconnecting",
+                    hostname,
+                    port
+                );
                 let mut pool = POOL.lock().await;
                 pool.remove(&key);
             }
@@ -254,7 +390,8 @@ pub async fn get_sftp_session(hostname: &str, port: u16) -> Result<Arc<SftpSessi
 
     // Create a new connection
     let creds = auth::get_server_credentials(hostname, port)?;
-    let sftp = Arc::new(create_session(hostname, port, &creds).await?);
+    let (ssh, sftp) = create_session(hostname, port, &creds).await?;
+    let (ssh, sftp) = (Arc::new(ssh), Arc::new(sftp));
     let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_OPS));
 
     // Store in pool
@@ -263,6 +400,7 @@ pub async fn get_sftp_session(hostname: &str, port: u16) -> Result<Arc<SftpSessi
         pool.insert(
             key,
             PooledConnection {
+                ssh,
                 sftp: sftp.clone(),
                 semaphore: semaphore.clone(),
                 last_used: Instant::now(),
@@ -273,6 +411,38 @@ pub async fn get_sftp_session(hostname: &str, port: u16) -> Result<Arc<SftpSessi
     Ok(sftp)
 }
 
+/// Whether `error` means the pooled connection died rather than the operation failing.
+pub fn is_stale_session_error(error: &str) -> bool {
+    let error = error.to_lowercase();
+    STALE_SESSION_ERRORS
+        .iter()
+        .any(|needle| error.contains(needle))
+}
+
+/// Run `op` on the server's pooled session. If the connection turns out to have died
+/// (see [`is_stale_session_error`]), it is dropped and `op` runs once more on a fresh
+/// one, so only pass operations that are safe to repeat.
+pub async fn with_session<T, F, Fut>(hostname: &str, port: u16, op: F) -> Result<T, String>
+where
+    F: Fn(Arc<SftpSession>) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let sftp = get_sftp_session(hostname, port).await?;
+    match op(sftp).await {
+        Err(err) if is_stale_session_error(&err) => {
+            log::debug!(
+                "SFTP {}:{} connection dropped ({}); retrying on a new session",
+                hostname,
+                port,
+                err
+            );
+            drop_connections(hostname, port).await;
+            op(get_sftp_session(hostname, port).await?).await
+        }
+        result => result,
+    }
+}
+
 /// Acquire a concurrency permit for the given server.
 /// Limits parallel SFTP operations to avoid overwhelming servers.
 /// Hold the returned permit for the duration of the operation.
@@ -292,6 +462,105 @@ pub async fn acquire_permit(
         .map_err(|e| format!("Failed to acquire SFTP permit: {}", e))
 }
 
+/// Run `command` on the server over an exec channel of the pooled connection.
+/// Fails if the server refuses exec (common on SFTP-only accounts) or the command
+/// exits non-zero; the error includes whatever it wrote to stderr.
+pub async fn exec_command(hostname: &str, port: u16, command: &str) -> Result<(), String> {
+    exec_command_output(hostname, port, command)
+        .await
+        .map(|_| ())
+}
+
+/// Like [`exec_command`], returning what the command wrote to stdout.
+pub async fn exec_command_output(
+    hostname: &str,
+    port: u16,
+    command: &str,
+) -> Result<Vec<u8>, String> {
+    let mut stdout = Vec::new();
+    exec_command_streaming(hostname, port, command, &AtomicBool::new(false), |data| {
+        stdout.extend_from_slice(data);
+        true
+    })
+    .await?;
+    Ok(stdout)
+}
+
+/// Run `command` on the server, handing stdout to `on_stdout` as it arrives instead of
+/// buffering it. The command is abandoned (its channel closed) once `on_stdout`
+/// returns false or `cancel` is set; that counts as success.
+pub async fn exec_command_streaming<F>(
+    hostname: &str,
+    port: u16,
+    command: &str,
+    cancel: &AtomicBool,
+    mut on_stdout: F,
+) -> Result<(), String>
+where
+    F: FnMut(&[u8]) -> bool,
+{
+    // Make sure a live connection is pooled before borrowing its SSH handle
+    get_sftp_session(hostname, port).await?;
+    let key = (hostname.to_lowercase(), port);
+    let ssh = {
+        let pool = POOL.lock().await;
+        pool.get(&key)
+            .map(|c| c.ssh.clone())
+            .ok_or_else(|| "No pooled connection for exec".to_string())?
+    };
+
+    let mut channel = ssh
+        .channel_open_session()
+        .await
+        .map_err(|e| LocationError::Network(format!("Failed to open SSH channel: {}", e)))?;
+    channel
+        .exec(true, command)
+        .await
+        .map_err(|e| format!("Failed to start remote command: {}", e))?;
+
+    let mut exit_status = None;
+    let mut stderr = Vec::new();
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            let _ = channel.close().await;
+            return Ok(());
+        }
+        // Wake up now and then so a quiet command can still be cancelled
+        let msg = match tokio::time::timeout(EXEC_CANCEL_POLL_INTERVAL, channel.wait()).await {
+            Ok(Some(msg)) => msg,
+            Ok(None) => break,
+            Err(_) => continue,
+        };
+        match msg {
+            ChannelMsg::Failure => {
+                return Err(LocationError::Unsupported(
+                    "Server does not allow remote commands".to_string(),
+                )
+                .into());
+            }
+            ChannelMsg::Data { data } => {
+                if !on_stdout(&data) {
+                    let _ = channel.close().await;
+                    return Ok(());
+                }
+            }
+            ChannelMsg::ExtendedData { data, .. } => stderr.extend_from_slice(&data),
+            ChannelMsg::ExitStatus { exit_status: code } => exit_status = Some(code),
+            _ => {}
+        }
+    }
+
+    match exit_status {
+        Some(0) => Ok(()),
+        Some(code) => Err(format!(
+            "Remote command exited with status {}: {}",
+            code,
+            String::from_utf8_lossy(&stderr).trim()
+        )),
+        None => Err("Remote command ended without an exit status".to_string()),
+    }
+}
+
 /// Drop all connections for a specific server
 pub async fn drop_connections(hostname: &str, port: u16) {
     let key = (hostname.to_lowercase(), port);
@@ -299,6 +568,23 @@ pub async fn drop_connections(hostname: &str, port: u16) {
     pool.remove(&key);
 }
 
+/// Close the pooled connection for a server, telling the server we're leaving.
+/// The next operation on it connects again. Returns whether a connection was open.
+pub async fn disconnect(hostname: &str, port: u16) -> bool {
+    let key = (hostname.to_lowercase(), port);
+    let Some(conn) = POOL.lock().await.remove(&key) else {
+        return false;
+    };
+    if let Err(e) = conn
+        .ssh
+        .disconnect(Disconnect::ByApplication, "", "en")
+        .await
+    {
+        log::debug!("SFTP {}:{} disconnect failed: {}", hostname, port, e);
+    }
+    true
+}
+
 /// Test SFTP connection without caching the session
 pub async fn test_connection(
     hostname: &str,
@@ -315,7 +601,7 @@ pub async fn test_connection(
         key_path: key_path.map(|s| s.to_string()),
     };
 
-    let sftp = create_session(hostname, port, &creds).await?;
+    let (_ssh, sftp) = create_session(hostname, port, &creds).await?;
 
     // Verify we can actually list the root directory
     sftp.metadata(".")
@@ -324,3 +610,21 @@ pub async fn test_connection(
 
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_stale_session_errors() {
+        assert!(is_stale_session_error(
+            "Failed to read directory: I/O: Broken pipe (os error 32)"
+        ));
+        assert!(is_stale_session_error(
+            "Failed to get metadata: SendError: channel closed"
+        ));
+        assert!(!is_stale_session_error(
+            "Failed to read directory: No such file: /missing"
+        ));
+    }
+}
@@ -6,6 +6,40 @@ use std::sync::RwLock;
 
 const SMB_KEYRING_SERVICE: &str = "marlin-smb";
 
+/// SMB dialect a server can be limited to; mirrors the sidecar's `SmbProtocol`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SmbProtocol {
+    /// SMB1
+    Nt1,
+    Smb2,
+    Smb3,
+}
+
+impl SmbProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmbProtocol::Nt1 => "NT1",
+            SmbProtocol::Smb2 => "SMB2",
+            SmbProtocol::Smb3 => "SMB3",
+        }
+    }
+}
+
+fn validate_protocols(
+    min_protocol: Option<SmbProtocol>,
+    max_protocol: Option<SmbProtocol>,
+) -> Result<(), String> {
+    match (min_protocol, max_protocol) {
+        (Some(min), Some(max)) if min > max => Err(format!(
+            "Minimum SMB protocol {} is newer than maximum {}",
+            min.as_str(),
+            max.as_str()
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// Information about a connected SMB server (safe to expose to frontend)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -13,6 +47,12 @@ pub struct SmbServerInfo {
     pub hostname: String,
     pub username: String,
     pub domain: Option<String>,
+    #[serde(default)]
+    pub guest: bool,
+    #[serde(default)]
+    pub min_protocol: Option<SmbProtocol>,
+    #[serde(default)]
+    pub max_protocol: Option<SmbProtocol>,
 }
 
 /// Stored server data on disk (no secrets)
@@ -22,6 +62,51 @@ pub struct SmbServer {
     pub hostname: String,
     pub username: String,
     pub domain: Option<String>,
+    /// Connect anonymously; no password is kept in the keychain
+    #[serde(default)]
+    pub guest: bool,
+    /// Dialect limits; `None` leaves negotiation to libsmbclient
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_protocol: Option<SmbProtocol>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_protocol: Option<SmbProtocol>,
+}
+
+impl SmbServer {
+    /// Servers saved without a username are treated as guest servers too
+    pub fn is_guest(&self) -> bool {
+        self.guest || self.username.trim().is_empty()
+    }
+
+    fn info(&self) -> SmbServerInfo {
+        SmbServerInfo {
+            hostname: self.hostname.clone(),
+            username: self.username.clone(),
+            domain: self.domain.clone(),
+            guest: self.is_guest(),
+            min_protocol: self.min_protocol,
+            max_protocol: self.max_protocol,
+        }
+    }
+
+    fn credentials(&self) -> Result<SmbServerCredentials, String> {
+        if self.is_guest() {
+            return Ok(SmbServerCredentials {
+                username: String::new(),
+                password: String::new(),
+                domain: self.domain.clone(),
+                min_protocol: self.min_protocol,
+                max_protocol: self.max_protocol,
+            });
+        }
+        Ok(SmbServerCredentials {
+            username: self.username.clone(),
+            password: get_password(&self.hostname)?,
+            domain: self.domain.clone(),
+            min_protocol: self.min_protocol,
+            max_protocol: self.max_protocol,
+        })
+    }
 }
 
 /// Server credentials resolved from keychain (internal use)
@@ -30,6 +115,22 @@ pub struct SmbServerCredentials {
     pub username: String,
     pub password: String,
     pub domain: Option<String>,
+    pub min_protocol: Option<SmbProtocol>,
+    pub max_protocol: Option<SmbProtocol>,
+}
+
+impl SmbServerCredentials {
+    /// The `credentials` object sent with every sidecar request
+    pub fn to_params(&self, hostname: &str) -> serde_json::Value {
+        serde_json::json!({
+            "hostname": hostname,
+            "username": self.username,
+            "password": self.password,
+            "domain": self.domain,
+            "min_protocol": self.min_protocol,
+            "max_protocol": self.max_protocol
+        })
+    }
 }
 
 /// Storage structure for servers file
@@ -130,6 +231,12 @@ fn load_servers_from_disk() -> Result<Vec<SmbServer>, String> {
         #[serde(default)]
         password: Option<String>,
         domain: Option<String>,
+        #[serde(default)]
+        guest: bool,
+        #[serde(default)]
+        min_protocol: Option<SmbProtocol>,
+        #[serde(default)]
+        max_protocol: Option<SmbProtocol>,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -153,6 +260,9 @@ fn load_servers_from_disk() -> Result<Vec<SmbServer>, String> {
             hostname: server.hostname,
             username: server.username,
             domain: server.domain,
+            guest: server.guest,
+            min_protocol: server.min_protocol,
+            max_protocol: server.max_protocol,
         });
     }
 
@@ -185,14 +295,7 @@ pub fn get_smb_servers() -> Result<Vec<SmbServerInfo>, String> {
     {
         let cache = SERVERS_CACHE.read().map_err(|e| e.to_string())?;
         if let Some(servers) = &*cache {
-            return Ok(servers
-                .iter()
-                .map(|s| SmbServerInfo {
-                    hostname: s.hostname.clone(),
-                    username: s.username.clone(),
-                    domain: s.domain.clone(),
-                })
-                .collect());
+            return Ok(servers.iter().map(SmbServer::info).collect());
         }
     }
 
@@ -205,14 +308,7 @@ pub fn get_smb_servers() -> Result<Vec<SmbServerInfo>, String> {
         *cache = Some(servers.clone());
     }
 
-    Ok(servers
-        .iter()
-        .map(|s| SmbServerInfo {
-            hostname: s.hostname.clone(),
-            username: s.username.clone(),
-            domain: s.domain.clone(),
-        })
-        .collect())
+    Ok(servers.iter().map(SmbServer::info).collect())
 }
 
 /// Get credentials for a specific server (internal use)
@@ -225,12 +321,7 @@ pub fn get_server_credentials(hostname: &str) -> Result<SmbServerCredentials, St
                 .iter()
                 .find(|s| s.hostname.eq_ignore_ascii_case(hostname))
             {
-                let password = get_password(&server.hostname)?;
-                return Ok(SmbServerCredentials {
-                    username: server.username.clone(),
-                    password,
-                    domain: server.domain.clone(),
-                });
+                return server.credentials();
             }
         }
     }
@@ -255,21 +346,22 @@ pub fn get_server_credentials(hostname: &str) -> Result<SmbServerCredentials, St
             )
         })?;
 
-    let password = get_password(&server.hostname)?;
-    Ok(SmbServerCredentials {
-        username: server.username,
-        password,
-        domain: server.domain,
-    })
+    server.credentials()
 }
 
-/// Add a new SMB server
+/// Add a new SMB server. Guest servers (`guest`, or an empty username) store no password.
 pub fn add_smb_server(
     hostname: String,
     username: String,
     password: String,
     domain: Option<String>,
+    guest: bool,
+    min_protocol: Option<SmbProtocol>,
+    max_protocol: Option<SmbProtocol>,
 ) -> Result<SmbServerInfo, String> {
+    validate_protocols(min_protocol, max_protocol)?;
+    let guest = guest || username.trim().is_empty();
+    let username = if guest { String::new() } else { username };
     let mut servers = load_servers_from_disk()?;
     let mut keychain_hostname = hostname.clone();
 
@@ -282,16 +374,27 @@ pub fn add_smb_server(
         keychain_hostname = existing.hostname.clone();
         existing.username = username.clone();
         existing.domain = domain.clone();
+        existing.guest = guest;
+        existing.min_protocol = min_protocol;
+        existing.max_protocol = max_protocol;
     } else {
         // Add new
         servers.push(SmbServer {
             hostname: hostname.clone(),
             username: username.clone(),
             domain: domain.clone(),
+            guest,
+            min_protocol,
+            max_protocol,
         });
     }
 
-    set_password(&keychain_hostname, &password)?;
+    if guest {
+        // Drop any password left over from when the server used an account
+        delete_password(&keychain_hostname)?;
+    } else {
+        set_password(&keychain_hostname, &password)?;
+    }
     save_servers_to_disk(&servers)?;
 
     // Update cache
@@ -304,6 +407,9 @@ pub fn add_smb_server(
         hostname,
         username,
         domain,
+        guest,
+        min_protocol,
+        max_protocol,
     })
 }
 
@@ -336,31 +442,41 @@ pub fn remove_smb_server(hostname: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// Test connection to an SMB server (without saving credentials)
+/// Test connection to an SMB server (without saving credentials).
+/// Guest connections are tested with empty credentials.
 pub fn test_smb_connection(
     hostname: &str,
     username: &str,
     password: &str,
     domain: Option<&str>,
+    guest: bool,
+    min_protocol: Option<SmbProtocol>,
+    max_protocol: Option<SmbProtocol>,
 ) -> Result<bool, String> {
+    validate_protocols(min_protocol, max_protocol)?;
     use super::client::{self, SidecarStatus};
 
     // Check sidecar availability
     if !client::is_available() {
         let status = client::initialize();
         if status != SidecarStatus::Available {
-            return Err(status
-                .error_message()
-                .unwrap_or_else(|| "SMB support is not available".to_string()));
+            return Err(status.unavailable_error());
         }
     }
 
+    let (username, password) = if guest || username.trim().is_empty() {
+        ("", "")
+    } else {
+        (username, password)
+    };
     let params = serde_json::json!({
         "credentials": {
             "hostname": hostname,
             "username": username,
             "password": password,
-            "domain": domain
+            "domain": domain,
+            "min_protocol": min_protocol,
+            "max_protocol": max_protocol
         }
     });
 
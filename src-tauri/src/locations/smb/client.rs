@@ -13,6 +13,8 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 use std::time::Duration;
 
+use crate::locations::LocationError;
+
 /// Default timeout for RPC calls (30 seconds).
 const DEFAULT_TIMEOUT_MS: u64 = 30_000;
 
@@ -53,6 +55,16 @@ impl SidecarStatus {
             SidecarStatus::StartFailed(reason) => Some(format!("SMB sidecar failed to start: {}", reason)),
         }
     }
+
+    /// The coded error returned when an SMB operation can't run because the
+    /// sidecar isn't usable.
+    pub fn unavailable_error(&self) -> String {
+        LocationError::Unsupported(
+            self.error_message()
+                .unwrap_or_else(|| "SMB support is not available".to_string()),
+        )
+        .to_string()
+    }
 }
 
 /// Sidecar process state.
@@ -126,17 +138,11 @@ pub fn call_method_with_timeout<P: Serialize, R: DeserializeOwned>(
     if state.process.is_none() || state.status != SidecarStatus::Available {
         // Try to restart
         if state.restart_attempts >= MAX_RESTART_ATTEMPTS {
-            return Err(state
-                .status
-                .error_message()
-                .unwrap_or_else(|| "SMB sidecar is not available".to_string()));
+            return Err(state.status.unavailable_error());
         }
         state.status = start_sidecar(&mut state);
         if state.status != SidecarStatus::Available {
-            return Err(state
-                .status
-                .error_message()
-                .unwrap_or_else(|| "SMB sidecar failed to start".to_string()));
+            return Err(state.status.unavailable_error());
         }
     }
 
@@ -160,14 +166,18 @@ pub fn call_method_with_timeout<P: Serialize, R: DeserializeOwned>(
         log::warn!("Failed to write to sidecar stdin: {}", e);
         state.process = None;
         state.restart_attempts += 1;
-        return Err("SMB connection lost. Please try again.".to_string());
+        return Err(
+            LocationError::Network("SMB connection lost. Please try again.".to_string()).into(),
+        );
     }
 
     if let Err(e) = process.stdin.flush() {
         log::warn!("Failed to flush sidecar stdin: {}", e);
         state.process = None;
         state.restart_attempts += 1;
-        return Err("SMB connection lost. Please try again.".to_string());
+        return Err(
+            LocationError::Network("SMB connection lost. Please try again.".to_string()).into(),
+        );
     }
 
     // Read response
@@ -177,7 +187,9 @@ pub fn call_method_with_timeout<P: Serialize, R: DeserializeOwned>(
         log::warn!("Failed to read from sidecar stdout: {}", e);
         state.process = None;
         state.restart_attempts += 1;
-        return Err("SMB connection lost. Please try again.".to_string());
+        return Err(
+            LocationError::Network("SMB connection lost. Please try again.".to_string()).into(),
+        );
     }
 
     // Parse response
@@ -199,8 +211,10 @@ pub fn call_method_with_timeout<P: Serialize, R: DeserializeOwned>(
         let message = error
             .get("message")
             .and_then(|m| m.as_str())
-            .unwrap_or("Unknown error");
-        return Err(message.to_string());
+            .unwrap_or("Unknown error")
+            .to_string();
+        let code = error.get("code").and_then(|c| c.as_i64()).unwrap_or(0);
+        return Err(sidecar_error(code, message).to_string());
     }
 
     // Extract result
@@ -212,6 +226,22 @@ pub fn call_method_with_timeout<P: Serialize, R: DeserializeOwned>(
     serde_json::from_value(result).map_err(|e| format!("Failed to parse result: {}", e))
 }
 
+/// Map a sidecar error code onto the error the rest of the app understands. The
+/// codes mirror `smb_sidecar::protocol::error_codes`, which only the sidecar binary
+/// compiles.
+fn sidecar_error(code: i64, message: String) -> LocationError {
+    match code {
+        -1003 => LocationError::NotFound(message),
+        // Authentication failed, permission denied
+        -1002 | -1004 => LocationError::PermissionDenied(message),
+        // Connection failed, timed out
+        -1001 | -1006 => LocationError::Network(message),
+        // libsmbclient missing
+        -1005 => LocationError::Unsupported(message),
+        _ => LocationError::from_message(message),
+    }
+}
+
 /// Start the sidecar process.
 fn start_sidecar(state: &mut SidecarState) -> SidecarStatus {
     // Find the sidecar binary
@@ -430,3 +460,21 @@ pub fn shutdown() {
         let _ = process.child.kill();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sidecar_errors_are_coded_by_error_code() {
+        let code = |raw: i64| sidecar_error(raw, "nope".to_string()).code().to_string();
+        assert_eq!(code(-1003), "ENOENT");
+        assert_eq!(code(-1004), "EPERM");
+        assert_eq!(code(-1006), "ENETWORK");
+        assert_eq!(code(-1005), "EUNSUPPORTED");
+        assert_eq!(code(-32603), "EOTHER");
+
+        let missing = LocationError::from_message(SidecarStatus::NotInstalled.unavailable_error());
+        assert_eq!(missing.code(), "EUNSUPPORTED");
+    }
+}
@@ -2,15 +2,19 @@ mod auth;
 pub mod client;
 
 use crate::fs_utils::FileItem;
+use crate::locations::transfer::{StagedFile, TransferProgress};
 use crate::locations::{
-    Location, LocationCapabilities, LocationProvider, LocationSummary, ProviderDirectoryEntries,
+    Location, LocationCapabilities, LocationError, LocationProvider, LocationSummary,
+    ProviderDirectoryEntries,
 };
+use crate::sorting::{sort_entries, SortOptions};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
 pub use auth::get_server_credentials;
 pub use auth::{
-    add_smb_server, get_smb_servers, remove_smb_server, test_smb_connection, SmbServerInfo,
+    add_smb_server, get_smb_servers, remove_smb_server, test_smb_connection, SmbProtocol,
+    SmbServerInfo,
 };
 pub use client::SidecarStatus;
 
@@ -31,13 +35,12 @@ impl LocationProvider for SmbProvider {
         &self,
         location: &Location,
     ) -> Result<ProviderDirectoryEntries, String> {
+        let _permit = crate::locations::throttle::acquire("smb").await?;
         // Check sidecar availability
         if !client::is_available() {
             let status = client::initialize();
             if status != SidecarStatus::Available {
-                return Err(status
-                    .error_message()
-                    .unwrap_or_else(|| "SMB support is not available".to_string()));
+                return Err(status.unavailable_error());
             }
         }
 
@@ -60,12 +63,7 @@ impl LocationProvider for SmbProvider {
 
         // Build sidecar request params
         let params = serde_json::json!({
-            "credentials": {
-                "hostname": hostname,
-                "username": creds.username,
-                "password": creds.password,
-                "domain": creds.domain
-            },
+            "credentials": creds.to_params(&hostname),
             "share": share,
             "path": dir_path
         });
@@ -127,15 +125,15 @@ impl LocationProvider for SmbProvider {
                 remote_id: None,
                 thumbnail_url: None,
                 download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
             });
         }
 
-        // Sort: directories first, then by name
-        items.sort_by(|a, b| match (a.is_directory, b.is_directory) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        });
+        // Default order; read_directory re-sorts with the user's preferences
+        sort_entries(&mut items, &SortOptions::default());
 
         let display_path = format!("smb://{}/{}{}", hostname, share, dir_path);
 
@@ -150,14 +148,51 @@ impl LocationProvider for SmbProvider {
         })
     }
 
+    async fn child_count(&self, location: &Location) -> Result<u64, String> {
+        let location_path = location.path();
+        if location_path == "/" || location_path.is_empty() {
+            // Share lists are short; no cheaper call exists for them
+            return Ok(self.read_directory(location).await?.entries.len() as u64);
+        }
+
+        let _permit = crate::locations::throttle::acquire("smb").await?;
+        if !client::is_available() {
+            let status = client::initialize();
+            if status != SidecarStatus::Available {
+                return Err(status.unavailable_error());
+            }
+        }
+
+        let authority = location
+            .authority()
+            .ok_or_else(|| "SMB path requires server".to_string())?;
+        let (hostname, share, path) = parse_smb_path(authority, location_path)?;
+        let creds = get_server_credentials(&hostname)?;
+        let params = serde_json::json!({
+            "credentials": creds.to_params(&hostname),
+            "share": share,
+            "path": path
+        });
+
+        let result: serde_json::Value = tokio::task::spawn_blocking(move || {
+            client::call_method::<serde_json::Value, serde_json::Value>("count_children", params)
+        })
+        .await
+        .map_err(|e| format!("SMB task failed: {}", e))??;
+
+        result
+            .get("count")
+            .and_then(|c| c.as_u64())
+            .ok_or_else(|| "Invalid response from sidecar".to_string())
+    }
+
     async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
+        let _permit = crate::locations::throttle::acquire("smb").await?;
         // Check sidecar availability
         if !client::is_available() {
             let status = client::initialize();
             if status != SidecarStatus::Available {
-                return Err(status
-                    .error_message()
-                    .unwrap_or_else(|| "SMB support is not available".to_string()));
+                return Err(status.unavailable_error());
             }
         }
 
@@ -184,6 +219,10 @@ impl LocationProvider for SmbProvider {
                 remote_id: None,
                 thumbnail_url: None,
                 download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
             });
         }
 
@@ -192,12 +231,7 @@ impl LocationProvider for SmbProvider {
         let location_raw = location.raw().to_string();
 
         let params = serde_json::json!({
-            "credentials": {
-                "hostname": hostname,
-                "username": creds.username,
-                "password": creds.password,
-                "domain": creds.domain
-            },
+            "credentials": creds.to_params(&hostname),
             "share": share,
             "path": path
         });
@@ -249,6 +283,10 @@ impl LocationProvider for SmbProvider {
             remote_id: None,
             thumbnail_url: None,
             download_url: None,
+            tags: None,
+            is_git_submodule: false,
+            lfs_size: None,
+            id: String::new(),
         })
     }
 
@@ -256,9 +294,7 @@ impl LocationProvider for SmbProvider {
         if !client::is_available() {
             let status = client::initialize();
             if status != SidecarStatus::Available {
-                return Err(status
-                    .error_message()
-                    .unwrap_or_else(|| "SMB support is not available".to_string()));
+                return Err(status.unavailable_error());
             }
         }
 
@@ -270,12 +306,7 @@ impl LocationProvider for SmbProvider {
         let creds = get_server_credentials(&hostname)?;
 
         let params = serde_json::json!({
-            "credentials": {
-                "hostname": hostname,
-                "username": creds.username,
-                "password": creds.password,
-                "domain": creds.domain
-            },
+            "credentials": creds.to_params(&hostname),
             "share": share,
             "path": path
         });
@@ -289,13 +320,30 @@ impl LocationProvider for SmbProvider {
         Ok(())
     }
 
+    async fn create_file(
+        &self,
+        directory: &Location,
+        name: &str,
+        contents: &[u8],
+    ) -> Result<String, String> {
+        let authority = directory
+            .authority()
+            .ok_or_else(|| "SMB path requires server".to_string())?;
+        let (hostname, share, dir_path) = parse_smb_path(authority, directory.path())?;
+        let staged = StagedFile::write(contents).await?;
+        let name = name.to_string();
+        tauri::async_runtime::spawn_blocking(move || {
+            upload_file_to_smb(staged.path(), &hostname, &share, &dir_path, &name, None)
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
     async fn delete(&self, location: &Location) -> Result<(), String> {
         if !client::is_available() {
             let status = client::initialize();
             if status != SidecarStatus::Available {
-                return Err(status
-                    .error_message()
-                    .unwrap_or_else(|| "SMB support is not available".to_string()));
+                return Err(status.unavailable_error());
             }
         }
 
@@ -307,12 +355,7 @@ impl LocationProvider for SmbProvider {
         let creds = get_server_credentials(&hostname)?;
 
         let params = serde_json::json!({
-            "credentials": {
-                "hostname": hostname,
-                "username": creds.username,
-                "password": creds.password,
-                "domain": creds.domain
-            },
+            "credentials": creds.to_params(&hostname),
             "share": share,
             "path": path
         });
@@ -330,9 +373,7 @@ impl LocationProvider for SmbProvider {
         if !client::is_available() {
             let status = client::initialize();
             if status != SidecarStatus::Available {
-                return Err(status
-                    .error_message()
-                    .unwrap_or_else(|| "SMB support is not available".to_string()));
+                return Err(status.unavailable_error());
             }
         }
 
@@ -344,25 +385,26 @@ impl LocationProvider for SmbProvider {
             .ok_or_else(|| "SMB path requires server".to_string())?;
 
         if from_authority != to_authority {
-            return Err("Cannot rename across different servers".to_string());
+            return Err(LocationError::Unsupported(
+                "Cannot rename across different servers".to_string(),
+            )
+            .into());
         }
 
         let (hostname, share, from_path) = parse_smb_path(from_authority, from.path())?;
         let (_, to_share, to_path) = parse_smb_path(to_authority, to.path())?;
 
         if share != to_share {
-            return Err("Cannot rename across different shares".to_string());
+            return Err(LocationError::Unsupported(
+                "Cannot rename across different shares".to_string(),
+            )
+            .into());
         }
 
         let creds = get_server_credentials(&hostname)?;
 
         let params = serde_json::json!({
-            "credentials": {
-                "hostname": hostname,
-                "username": creds.username,
-                "password": creds.password,
-                "domain": creds.domain
-            },
+            "credentials": creds.to_params(&hostname),
             "share": share,
             "from_path": from_path,
             "to_path": to_path
@@ -381,9 +423,7 @@ impl LocationProvider for SmbProvider {
         if !client::is_available() {
             let status = client::initialize();
             if status != SidecarStatus::Available {
-                return Err(status
-                    .error_message()
-                    .unwrap_or_else(|| "SMB support is not available".to_string()));
+                return Err(status.unavailable_error());
             }
         }
 
@@ -395,25 +435,26 @@ impl LocationProvider for SmbProvider {
             .ok_or_else(|| "SMB path requires server".to_string())?;
 
         if from_authority != to_authority {
-            return Err("Copying across different SMB servers is not supported".to_string());
+            return Err(LocationError::Unsupported(
+                "Copying across different SMB servers is not supported".to_string(),
+            )
+            .into());
         }
 
         let (hostname, share, from_path) = parse_smb_path(from_authority, from.path())?;
         let (_, to_share, to_path) = parse_smb_path(to_authority, to.path())?;
 
         if share != to_share {
-            return Err("Copying across different SMB shares is not supported".to_string());
+            return Err(LocationError::Unsupported(
+                "Copying across different SMB shares is not supported".to_string(),
+            )
+            .into());
         }
 
         let creds = get_server_credentials(&hostname)?;
 
         let params = serde_json::json!({
-            "credentials": {
-                "hostname": hostname,
-                "username": creds.username,
-                "password": creds.password,
-                "domain": creds.domain
-            },
+            "credentials": creds.to_params(&hostname),
             "share": share,
             "from_path": from_path,
             "to_path": to_path
@@ -432,15 +473,9 @@ impl LocationProvider for SmbProvider {
 impl SmbProvider {
     async fn list_shares(&self, hostname: &str) -> Result<ProviderDirectoryEntries, String> {
         let creds = get_server_credentials(hostname)?;
-        let hostname_clone = hostname.to_string();
 
         let params = serde_json::json!({
-            "credentials": {
-                "hostname": hostname_clone,
-                "username": creds.username,
-                "password": creds.password,
-                "domain": creds.domain
-            }
+            "credentials": creds.to_params(hostname)
         });
 
         let result: serde_json::Value = tokio::task::spawn_blocking(move || {
@@ -474,6 +509,10 @@ impl SmbProvider {
                     remote_id: None,
                     thumbnail_url: None,
                     download_url: None,
+                    tags: None,
+                    is_git_submodule: false,
+                    lfs_size: None,
+                    id: String::new(),
                 })
             })
             .collect();
@@ -597,14 +636,19 @@ pub fn strip_url_credentials(url: &str) -> String {
     url.to_string()
 }
 
+/// Bytes sent per sidecar call when an upload reports progress
+const UPLOAD_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+
 /// Upload a local file to an SMB share via the sidecar, handling name collisions.
-/// Returns the final filename used on the SMB share.
+/// Returns the final filename used on the SMB share. With `progress` the file is sent
+/// in chunks so each one can be reported, and a cancelled upload is removed.
 pub fn upload_file_to_smb(
     local_path: &std::path::Path,
     hostname: &str,
     share: &str,
     dest_dir: &str,
     preferred_name: &str,
+    progress: Option<&TransferProgress>,
 ) -> Result<String, String> {
     use client::SidecarStatus;
     use std::path::Path;
@@ -613,9 +657,7 @@ pub fn upload_file_to_smb(
     if !client::is_available() {
         let status = client::initialize();
         if status != SidecarStatus::Available {
-            return Err(status
-                .error_message()
-                .unwrap_or_else(|| "SMB support is not available".to_string()));
+            return Err(status.unavailable_error());
         }
     }
 
@@ -648,16 +690,14 @@ pub fn upload_file_to_smb(
             format!("{}/{}", dest_dir.trim_end_matches('/'), candidate)
         };
 
+        let credentials = creds.to_params(hostname);
+        // The first call creates the file, so a name collision fails it like before
         let params = serde_json::json!({
-            "credentials": {
-                "hostname": hostname,
-                "username": creds.username,
-                "password": creds.password,
-                "domain": creds.domain
-            },
+            "credentials": credentials,
             "share": share,
             "source_path": local_path.to_string_lossy(),
-            "dest_path": dest_rel
+            "dest_path": dest_rel,
+            "length": progress.map(|_| UPLOAD_CHUNK_SIZE)
         });
 
         match client::call_method_with_timeout::<_, serde_json::Value>(
@@ -665,7 +705,12 @@ pub fn upload_file_to_smb(
             params,
             client::DOWNLOAD_TIMEOUT_MS,
         ) {
-            Ok(_) => return Ok(candidate),
+            Ok(_) => {
+                if let Some(progress) = progress {
+                    upload_remaining_chunks(&credentials, share, local_path, &dest_rel, progress)?;
+                }
+                return Ok(candidate);
+            }
             Err(e) => {
                 // If the file already exists, try the next name
                 let lower = e.to_lowercase();
@@ -680,6 +725,55 @@ pub fn upload_file_to_smb(
     Err("Unable to allocate unique destination name on SMB share".to_string())
 }
 
+/// Send the rest of a file whose first chunk created `dest_path`, reporting each chunk.
+/// The partial file is deleted if a chunk fails or the upload is cancelled.
+fn upload_remaining_chunks(
+    credentials: &serde_json::Value,
+    share: &str,
+    local_path: &std::path::Path,
+    dest_path: &str,
+    progress: &TransferProgress,
+) -> Result<(), String> {
+    let total = progress.total();
+    let send = || -> Result<(), String> {
+        let mut offset = UPLOAD_CHUNK_SIZE.min(total);
+        progress.update(offset)?;
+        while offset < total {
+            let params = serde_json::json!({
+                "credentials": credentials,
+                "share": share,
+                "source_path": local_path.to_string_lossy(),
+                "dest_path": dest_path,
+                "offset": offset,
+                "length": UPLOAD_CHUNK_SIZE
+            });
+            let result: serde_json::Value = client::call_method_with_timeout(
+                "upload_file",
+                params,
+                client::DOWNLOAD_TIMEOUT_MS,
+            )?;
+            let written = result["size"].as_u64().unwrap_or(0);
+            if written == 0 {
+                return Err("Upload stopped before the whole file was sent".to_string());
+            }
+            offset += written;
+            progress.update(offset)?;
+        }
+        Ok(())
+    };
+
+    let result = send();
+    if result.is_err() {
+        let params = serde_json::json!({
+            "credentials": credentials,
+            "share": share,
+            "path": dest_path
+        });
+        let _ = client::call_method::<_, serde_json::Value>("delete", params);
+    }
+    result
+}
+
 /// Download an SMB file to a local path via the sidecar.
 pub fn download_file_from_smb(
     hostname: &str,
@@ -692,21 +786,14 @@ pub fn download_file_from_smb(
     if !client::is_available() {
         let status = client::initialize();
         if status != SidecarStatus::Available {
-            return Err(status
-                .error_message()
-                .unwrap_or_else(|| "SMB support is not available".to_string()));
+            return Err(status.unavailable_error());
         }
     }
 
     let creds = get_server_credentials(hostname)?;
 
     let params = serde_json::json!({
-        "credentials": {
-            "hostname": hostname,
-            "username": creds.username,
-            "password": creds.password,
-            "domain": creds.domain
-        },
+        "credentials": creds.to_params(hostname),
         "share": share,
         "path": file_path,
         "dest_path": dest_path.to_string_lossy()
@@ -718,6 +805,70 @@ pub fn download_file_from_smb(
     Ok(())
 }
 
+/// Download at most `max_bytes` from the start of an SMB file to a local path via the
+/// sidecar.
+pub fn download_file_head_from_smb(
+    hostname: &str,
+    share: &str,
+    file_path: &str,
+    dest_path: &std::path::Path,
+    max_bytes: u64,
+) -> Result<(), String> {
+    use client::SidecarStatus;
+
+    if !client::is_available() {
+        let status = client::initialize();
+        if status != SidecarStatus::Available {
+            return Err(status.unavailable_error());
+        }
+    }
+
+    let creds = get_server_credentials(hostname)?;
+
+    let params = serde_json::json!({
+        "credentials": creds.to_params(hostname),
+        "share": share,
+        "path": file_path,
+        "dest_path": dest_path.to_string_lossy(),
+        "max_bytes": max_bytes
+    });
+
+    let _result: serde_json::Value =
+        client::call_method_with_timeout("download_partial", params, client::DOWNLOAD_TIMEOUT_MS)?;
+
+    Ok(())
+}
+
+/// Total and available bytes of the share volume holding `path`, via the sidecar.
+pub fn disk_usage_on_smb(hostname: &str, share: &str, path: &str) -> Result<(u64, u64), String> {
+    use client::SidecarStatus;
+
+    if !client::is_available() {
+        let status = client::initialize();
+        if status != SidecarStatus::Available {
+            return Err(status.unavailable_error());
+        }
+    }
+
+    let creds = get_server_credentials(hostname)?;
+
+    let params = serde_json::json!({
+        "credentials": creds.to_params(hostname),
+        "share": share,
+        "path": path
+    });
+
+    let result: serde_json::Value = client::call_method("disk_usage", params)?;
+    let field = |name: &str| {
+        result
+            .get(name)
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| format!("SMB disk usage response missing {}", name))
+    };
+
+    Ok((field("total_bytes")?, field("available_bytes")?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -785,4 +936,19 @@ mod tests {
         let stripped = strip_url_credentials(url);
         assert_eq!(stripped, "smb://server.local/share/path");
     }
+
+    #[test]
+    fn test_credentials_params_carry_protocols() {
+        let creds = auth::SmbServerCredentials {
+            username: String::new(),
+            password: String::new(),
+            domain: None,
+            min_protocol: Some(SmbProtocol::Nt1),
+            max_protocol: None,
+        };
+        let params = creds.to_params("nas.local");
+        assert_eq!(params["hostname"], "nas.local");
+        assert_eq!(params["min_protocol"], "NT1");
+        assert!(params["max_protocol"].is_null());
+    }
 }
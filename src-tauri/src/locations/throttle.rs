@@ -0,0 +1,222 @@
+//! App-wide concurrency limits for remote providers. Directory reads, metadata
+//! lookups and thumbnail downloads all take a permit for their scheme so a large
+//! folder can't flood an SMB share, SFTP or WebDAV server, or the Drive API at once.
+//!
+//! Limits come from the `remoteConcurrency` object in preferences.json, e.g.
+//! `{ "remoteConcurrency": { "smb": 2, "gdrive": 16 } }`; missing schemes keep their defaults.
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Schemes that are throttled, with their default in-flight limits.
+const DEFAULT_LIMITS: &[(&str, usize)] = &[("smb", 4), ("sftp", 8), ("gdrive", 10), ("webdav", 8)];
+
+/// Upper bound for a configured limit; anything larger is clamped.
+const MAX_LIMIT: usize = 64;
+
+const PREFERENCES_KEY: &str = "remoteConcurrency";
+
+struct SchemeLimiter {
+    limit: usize,
+    semaphore: Arc<Semaphore>,
+    in_flight: Arc<AtomicUsize>,
+    waiting: Arc<AtomicUsize>,
+}
+
+impl SchemeLimiter {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            semaphore: Arc::new(Semaphore::new(limit)),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            waiting: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+}
+
+static LIMITERS: Lazy<RwLock<HashMap<&'static str, SchemeLimiter>>> = Lazy::new(|| {
+    RwLock::new(
+        DEFAULT_LIMITS
+            .iter()
+            .map(|(scheme, limit)| (*scheme, SchemeLimiter::new(*limit)))
+            .collect(),
+    )
+});
+
+/// Held for the duration of one remote operation. Dropping it frees the slot.
+#[derive(Debug)]
+pub struct RemotePermit {
+    _permit: Option<OwnedSemaphorePermit>,
+    in_flight: Option<Arc<AtomicUsize>>,
+}
+
+impl Drop for RemotePermit {
+    fn drop(&mut self) {
+        if let Some(counter) = &self.in_flight {
+            counter.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Decrements the waiting counter even if the acquiring future is cancelled.
+struct WaitingGuard(Arc<AtomicUsize>);
+
+impl Drop for WaitingGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Wait for a slot on `scheme`. Schemes without a limit (local files, archives)
+/// get a no-op permit immediately.
+pub async fn acquire(scheme: &str) -> Result<RemotePermit, String> {
+    let entry = {
+        let limiters = LIMITERS
+            .read()
+            .map_err(|_| "Remote throttle lock poisoned".to_string())?;
+        limiters.get(scheme).map(|limiter| {
+            (
+                limiter.semaphore.clone(),
+                limiter.in_flight.clone(),
+                limiter.waiting.clone(),
+            )
+        })
+    };
+
+    let Some((semaphore, in_flight, waiting)) = entry else {
+        return Ok(RemotePermit {
+            _permit: None,
+            in_flight: None,
+        });
+    };
+
+    waiting.fetch_add(1, Ordering::SeqCst);
+    let waiting_guard = WaitingGuard(waiting);
+    let permit = semaphore
+        .acquire_owned()
+        .await
+        .map_err(|e| format!("Failed to acquire {} permit: {}", scheme, e))?;
+    drop(waiting_guard);
+
+    in_flight.fetch_add(1, Ordering::SeqCst);
+    Ok(RemotePermit {
+        _permit: Some(permit),
+        in_flight: Some(in_flight),
+    })
+}
+
+/// Apply new limits. Operations already holding a permit finish under the old
+/// semaphore; new requests queue against the new limit.
+pub fn configure(limits: &HashMap<String, usize>) {
+    let Ok(mut limiters) = LIMITERS.write() else {
+        return;
+    };
+    for (scheme, default) in DEFAULT_LIMITS {
+        let limit = limits
+            .get(*scheme)
+            .copied()
+            .unwrap_or(*default)
+            .clamp(1, MAX_LIMIT);
+        let Some(limiter) = limiters.get_mut(scheme) else {
+            continue;
+        };
+        if limiter.limit == limit {
+            continue;
+        }
+        log::info!("Remote concurrency for {} set to {}", scheme, limit);
+        // Keep the counters so diagnostics still include operations on the old semaphore
+        limiter.limit = limit;
+        limiter.semaphore = Arc::new(Semaphore::new(limit));
+    }
+}
+
+/// Read `remoteConcurrency` from a preferences JSON document and apply it.
+/// Invalid JSON leaves the current limits untouched.
+pub fn configure_from_preferences(json: &str) {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return;
+    };
+    let limits = value
+        .get(PREFERENCES_KEY)
+        .and_then(|v| v.as_object())
+        .map(|obj| {
+            obj.iter()
+                .filter_map(|(scheme, v)| Some((scheme.clone(), v.as_u64()? as usize)))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+    configure(&limits);
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteConcurrencyStats {
+    pub scheme: String,
+    pub limit: usize,
+    pub in_flight: usize,
+    pub waiting: usize,
+}
+
+/// Current limit and in-flight/queued counts for every throttled scheme.
+pub fn stats() -> Vec<RemoteConcurrencyStats> {
+    let Ok(limiters) = LIMITERS.read() else {
+        return Vec::new();
+    };
+    let mut stats: Vec<_> = limiters
+        .iter()
+        .map(|(scheme, limiter)| RemoteConcurrencyStats {
+            scheme: scheme.to_string(),
+            limit: limiter.limit,
+            in_flight: limiter.in_flight.load(Ordering::SeqCst),
+            waiting: limiter.waiting.load(Ordering::SeqCst),
+        })
+        .collect();
+    stats.sort_by(|a, b| a.scheme.cmp(&b.scheme));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn stats_for(scheme: &str) -> RemoteConcurrencyStats {
+        stats().into_iter().find(|s| s.scheme == scheme).unwrap()
+    }
+
+    #[tokio::test]
+    async fn permits_bound_in_flight_and_release_on_drop() {
+        // Only this test touches "sftp" so the shared limiter state is predictable
+        configure_from_preferences(r#"{"remoteConcurrency":{"sftp":2}}"#);
+        assert_eq!(stats_for("sftp").limit, 2);
+
+        let first = acquire("sftp").await.unwrap();
+        let second = acquire("sftp").await.unwrap();
+        assert_eq!(stats_for("sftp").in_flight, 2);
+
+        let third = tokio::spawn(async { acquire("sftp").await.map(|_| ()) });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!third.is_finished());
+        assert_eq!(stats_for("sftp").waiting, 1);
+
+        drop(first);
+        third.await.unwrap().unwrap();
+        drop(second);
+        let after = stats_for("sftp");
+        assert_eq!((after.in_flight, after.waiting), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn unthrottled_schemes_never_wait() {
+        let mut permits = Vec::new();
+        for _ in 0..100 {
+            permits.push(acquire("file").await.unwrap());
+        }
+        assert_eq!(permits.len(), 100);
+        assert!(stats().iter().all(|s| s.scheme != "file"));
+    }
+}
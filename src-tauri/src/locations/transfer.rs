@@ -0,0 +1,247 @@
+//! Progress reporting and cancellation for uploads to remote providers.
+//!
+//! An upload gets a [`TransferProgress`] for the file it is sending. Providers call
+//! [`TransferProgress::update`] after each chunk; the callback decides how often to
+//! tell the UI, and an `Err` comes back once the transfer was cancelled so the
+//! upload loop can stop and clean up its partial file.
+//!
+//! Providers that only know how to upload files use a [`StagedFile`] to send
+//! contents that exist in memory.
+//!
+//! Downloads into the local temp cache go through [`download_to_stable_path`], so a
+//! cached path only ever holds a complete file.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+pub const UPLOAD_CANCELLED: &str = "Upload cancelled";
+
+type ProgressCallback = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct TransferProgress {
+    total: u64,
+    cancel: Arc<AtomicBool>,
+    on_progress: ProgressCallback,
+}
+
+impl TransferProgress {
+    /// `on_progress` receives `(transferred, total)` bytes.
+    pub fn new(
+        total: u64,
+        cancel: Arc<AtomicBool>,
+        on_progress: impl Fn(u64, u64) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            total,
+            cancel,
+            on_progress: Arc::new(on_progress),
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    /// Report `transferred` bytes so far; fails once the transfer has been cancelled.
+    pub fn update(&self, transferred: u64) -> Result<(), String> {
+        (self.on_progress)(transferred, self.total);
+        if self.is_cancelled() {
+            Err(UPLOAD_CANCELLED.to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Contents written to a local temp file so they can go through an upload helper.
+/// The file is removed when this is dropped.
+pub struct StagedFile {
+    path: PathBuf,
+}
+
+impl StagedFile {
+    pub async fn write(contents: &[u8]) -> Result<Self, String> {
+        let dir = std::env::temp_dir().join("marlin-staged");
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| format!("Failed to create temp dir: {e}"))?;
+        let path = dir.join(uuid::Uuid::new_v4().to_string());
+        tokio::fs::write(&path, contents)
+            .await
+            .map_err(|e| format!("Failed to stage file: {e}"))?;
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A unique `.part` sibling of `final_path` for an in-progress download. Each
+/// download gets its own file, so concurrent fetches of the same remote file
+/// never write into each other.
+pub fn partial_download_path(final_path: &Path) -> PathBuf {
+    let name = final_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "download".to_string());
+    final_path.with_file_name(format!("{}.{}.part", name, uuid::Uuid::new_v4().simple()))
+}
+
+/// Download into a private `.part` file via `download`, then atomically rename it to
+/// `final_path`. Readers only ever see `final_path` once it holds a complete file.
+/// If a concurrent download already published the same path and the rename fails,
+/// the existing file wins and this download is discarded.
+pub async fn download_to_stable_path<F, Fut>(final_path: &Path, download: F) -> Result<(), String>
+where
+    F: FnOnce(PathBuf) -> Fut,
+    Fut: std::future::Future<Output = Result<(), String>>,
+{
+    let part_path = partial_download_path(final_path);
+    let downloaded = download(part_path.clone()).await;
+    publish_download(&part_path, final_path, downloaded)
+}
+
+/// [`download_to_stable_path`] for callers already on a blocking thread.
+pub fn download_to_stable_path_blocking<F>(final_path: &Path, download: F) -> Result<(), String>
+where
+    F: FnOnce(&Path) -> Result<(), String>,
+{
+    let part_path = partial_download_path(final_path);
+    let downloaded = download(&part_path);
+    publish_download(&part_path, final_path, downloaded)
+}
+
+/// Rename a finished `.part` file to `final_path`, or remove it if the download failed.
+fn publish_download(
+    part_path: &Path,
+    final_path: &Path,
+    downloaded: Result<(), String>,
+) -> Result<(), String> {
+    if let Err(err) = downloaded {
+        let _ = std::fs::remove_file(part_path);
+        return Err(err);
+    }
+
+    if let Err(err) = std::fs::rename(part_path, final_path) {
+        let _ = std::fs::remove_file(part_path);
+        // On Windows the rename fails if a reader has the published file open
+        if final_path.exists() {
+            return Ok(());
+        }
+        return Err(format!("Failed to finalize download: {}", err));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[test]
+    fn reports_progress_until_cancelled() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let progress = TransferProgress::new(10, cancel.clone(), move |done, total| {
+            sink.lock().unwrap().push((done, total));
+        });
+
+        assert!(progress.update(4).is_ok());
+        cancel.store(true, Ordering::SeqCst);
+        assert_eq!(progress.update(8), Err(UPLOAD_CANCELLED.to_string()));
+        assert_eq!(*seen.lock().unwrap(), [(4, 10), (8, 10)]);
+    }
+
+    #[tokio::test]
+    async fn concurrent_downloads_publish_a_complete_file() {
+        let dir = tempdir().expect("tempdir");
+        let final_path = dir.path().join("abc123_report.pdf");
+        let payload: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+
+        let mut tasks = Vec::new();
+        for _ in 0..8 {
+            let final_path = final_path.clone();
+            let payload = payload.clone();
+            tasks.push(tokio::spawn(async move {
+                download_to_stable_path(&final_path, |part_path| async move {
+                    // Write in chunks and yield so the downloads interleave
+                    let mut file = tokio::fs::File::create(&part_path)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    for chunk in payload.chunks(4096) {
+                        tokio::io::AsyncWriteExt::write_all(&mut file, chunk)
+                            .await
+                            .map_err(|e| e.to_string())?;
+                        tokio::task::yield_now().await;
+                    }
+                    Ok(())
+                })
+                .await
+            }));
+        }
+        for task in tasks {
+            task.await.expect("join").expect("download");
+        }
+
+        assert_eq!(fs::read(&final_path).unwrap(), payload);
+        let leftovers: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".part"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_download_leaves_nothing_behind() {
+        let dir = tempdir().expect("tempdir");
+        let final_path = dir.path().join("file.bin");
+
+        let result = download_to_stable_path(&final_path, |part_path| async move {
+            fs::write(&part_path, b"half").map_err(|e| e.to_string())?;
+            Err("connection reset".to_string())
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert!(!final_path.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn blocking_downloads_publish_only_complete_files() {
+        let dir = tempdir().expect("tempdir");
+        let final_path = dir.path().join("photo.jpg");
+
+        let result = download_to_stable_path_blocking(&final_path, |part_path| {
+            fs::write(part_path, b"half").map_err(|e| e.to_string())?;
+            Err("connection reset".to_string())
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        download_to_stable_path_blocking(&final_path, |part_path| {
+            fs::write(part_path, b"whole").map_err(|e| e.to_string())
+        })
+        .unwrap();
+        assert_eq!(fs::read(&final_path).unwrap(), b"whole");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
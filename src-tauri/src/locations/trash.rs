@@ -0,0 +1,356 @@
+//! `trash://` lists what's in the OS trash (recycle bin).
+//!
+//! Items are only shown at the top level: `trash:///` lists them and each one lives
+//! at `trash:///<encoded id>`, where the id is the platform's handle for the item.
+//! Deleting an item purges it and deleting the root empties the trash. Restoring
+//! goes through [`restore_item`] rather than rename, since the destination is fixed.
+//! On macOS the trash has no API for the original location, so items can be listed
+//! and purged but not restored.
+
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tauri::async_runtime::spawn_blocking;
+use urlencoding::{decode, encode};
+
+use super::{
+    Location, LocationCapabilities, LocationProvider, LocationSummary, ProviderDirectoryEntries,
+};
+use crate::fs_utils::FileItem;
+
+/// One item in the trash, independent of platform
+struct TrashEntry {
+    id: String,
+    name: String,
+    original_path: Option<PathBuf>,
+    deleted_at: DateTime<Utc>,
+    is_directory: bool,
+    size: u64,
+}
+
+fn item_uri(id: &str) -> String {
+    format!("trash:///{}", encode(id))
+}
+
+/// The item id a `trash://` location refers to; `None` for the trash itself
+fn item_id(location: &Location) -> Result<Option<String>, String> {
+    if location.scheme() != "trash" {
+        return Err("TrashProvider only supports trash:// locations".to_string());
+    }
+    let encoded = location.path().trim_start_matches('/');
+    if encoded.is_empty() {
+        return Ok(None);
+    }
+    if encoded.contains('/') {
+        return Err("Folders in the trash can't be opened; restore them first".to_string());
+    }
+    decode(encoded)
+        .map(|id| Some(id.into_owned()))
+        .map_err(|e| format!("Invalid trash location: {}", e))
+}
+
+fn to_file_item(entry: TrashEntry) -> FileItem {
+    let extension = if entry.is_directory {
+        None
+    } else {
+        Path::new(&entry.name)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+    };
+    FileItem {
+        is_hidden: entry.name.starts_with('.'),
+        path: item_uri(&entry.id),
+        name: entry.name,
+        size: entry.size,
+        modified: entry.deleted_at,
+        is_directory: entry.is_directory,
+        is_symlink: false,
+        is_git_repo: false,
+        extension,
+        child_count: None,
+        image_width: None,
+        image_height: None,
+        // Where the item goes back to on restore
+        remote_id: entry
+            .original_path
+            .map(|path| path.to_string_lossy().to_string()),
+        thumbnail_url: None,
+        download_url: None,
+        tags: None,
+        is_git_submodule: false,
+        lfs_size: None,
+        id: String::new(),
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+mod platform {
+    use super::TrashEntry;
+    use chrono::DateTime;
+    use trash::os_limited;
+    use trash::{TrashItem, TrashItemSize};
+
+    fn find(id: &str) -> Result<TrashItem, String> {
+        os_limited::list()
+            .map_err(|e| format!("Failed to read the trash: {}", e))?
+            .into_iter()
+            .find(|item| item.id.to_string_lossy() == id)
+            .ok_or_else(|| "[TRASH_ITEM_NOT_FOUND] This item is no longer in the trash".to_string())
+    }
+
+    pub(super) fn list() -> Result<Vec<TrashEntry>, String> {
+        let items = os_limited::list().map_err(|e| format!("Failed to read the trash: {}", e))?;
+        Ok(items
+            .into_iter()
+            .map(|item| {
+                let (is_directory, size) = match os_limited::metadata(&item).map(|m| m.size) {
+                    Ok(TrashItemSize::Bytes(bytes)) => (false, bytes),
+                    Ok(TrashItemSize::Entries(_)) => (true, 0),
+                    Err(_) => (false, 0),
+                };
+                TrashEntry {
+                    id: item.id.to_string_lossy().to_string(),
+                    name: item.name.to_string_lossy().to_string(),
+                    original_path: Some(item.original_path()),
+                    deleted_at: DateTime::from_timestamp(item.time_deleted, 0).unwrap_or_default(),
+                    is_directory,
+                    size,
+                }
+            })
+            .collect())
+    }
+
+    pub(super) fn restore(id: &str) -> Result<String, String> {
+        let item = find(id)?;
+        let original = item.original_path();
+        // The folder it came from may have been deleted since
+        std::fs::create_dir_all(&item.original_parent).map_err(|e| {
+            format!(
+                "Failed to recreate {}: {}",
+                item.original_parent.display(),
+                e
+            )
+        })?;
+        os_limited::restore_all([item]).map_err(|e| match e {
+            trash::Error::RestoreCollision { path, .. } => format!(
+                "[TRASH_RESTORE_COLLISION] Something already exists at {}",
+                path.display()
+            ),
+            other => format!("Failed to restore from the trash: {}", other),
+        })?;
+        Ok(original.to_string_lossy().to_string())
+    }
+
+    pub(super) fn purge(id: &str) -> Result<(), String> {
+        let item = find(id)?;
+        os_limited::purge_all([item]).map_err(|e| format!("Failed to delete from the trash: {}", e))
+    }
+
+    pub(super) fn empty() -> Result<(), String> {
+        let items = os_limited::list().map_err(|e| format!("Failed to read the trash: {}", e))?;
+        os_limited::purge_all(items).map_err(|e| format!("Failed to empty the trash: {}", e))
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+mod platform {
+    //! The user's `~/.Trash`, read directly. Ids are the paths inside it.
+
+    use super::TrashEntry;
+    use chrono::{DateTime, Utc};
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn trash_dir() -> Result<PathBuf, String> {
+        dirs::home_dir()
+            .map(|home| home.join(".Trash"))
+            .ok_or_else(|| "Could not determine home directory".to_string())
+    }
+
+    fn find(id: &str) -> Result<PathBuf, String> {
+        let path = PathBuf::from(id);
+        if path.parent() != Some(trash_dir()?.as_path()) || !path.exists() {
+            return Err("[TRASH_ITEM_NOT_FOUND] This item is no longer in the trash".to_string());
+        }
+        Ok(path)
+    }
+
+    pub(super) fn list() -> Result<Vec<TrashEntry>, String> {
+        let entries =
+            fs::read_dir(trash_dir()?).map_err(|e| format!("Failed to read the trash: {}", e))?;
+        Ok(entries
+            .flatten()
+            .filter(|entry| entry.file_name() != ".DS_Store")
+            .map(|entry| {
+                let metadata = entry.metadata().ok();
+                let is_directory = metadata.as_ref().is_some_and(|m| m.is_dir());
+                TrashEntry {
+                    id: entry.path().to_string_lossy().to_string(),
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    original_path: None,
+                    deleted_at: metadata
+                        .as_ref()
+                        .and_then(|m| m.modified().ok())
+                        .map(DateTime::<Utc>::from)
+                        .unwrap_or_default(),
+                    is_directory,
+                    size: if is_directory {
+                        0
+                    } else {
+                        metadata.map(|m| m.len()).unwrap_or(0)
+                    },
+                }
+            })
+            .collect())
+    }
+
+    pub(super) fn restore(id: &str) -> Result<String, String> {
+        find(id)?;
+        Err("[TRASH_RESTORE_UNSUPPORTED] Use Put Back in Finder to restore this item".to_string())
+    }
+
+    pub(super) fn purge(id: &str) -> Result<(), String> {
+        let path = find(id)?;
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        result.map_err(|e| format!("Failed to delete from the trash: {}", e))
+    }
+
+    pub(super) fn empty() -> Result<(), String> {
+        for entry in list()? {
+            purge(&entry.id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Put a trashed item back where it was deleted from. Returns the restored path.
+pub async fn restore_item(location: &Location) -> Result<String, String> {
+    let id =
+        item_id(location)?.ok_or_else(|| "Choose an item in the trash to restore".to_string())?;
+    spawn_blocking(move || platform::restore(&id))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[derive(Default)]
+pub struct TrashProvider;
+
+#[async_trait]
+impl LocationProvider for TrashProvider {
+    fn scheme(&self) -> &'static str {
+        "trash"
+    }
+
+    fn capabilities(&self, _location: &Location) -> LocationCapabilities {
+        LocationCapabilities {
+            scheme: "trash".to_string(),
+            display_name: "Trash".to_string(),
+            can_read: true,
+            can_write: false,
+            can_create_directories: false,
+            can_delete: true,
+            can_rename: false,
+            can_copy: false,
+            can_move: false,
+            supports_watching: false,
+            requires_explicit_refresh: true,
+            supports_tags: false,
+            renames_by_copy: false,
+        }
+    }
+
+    async fn read_directory(
+        &self,
+        location: &Location,
+    ) -> Result<ProviderDirectoryEntries, String> {
+        if item_id(location)?.is_some() {
+            return Err("Folders in the trash can't be opened; restore them first".to_string());
+        }
+
+        let entries = spawn_blocking(platform::list)
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+        Ok(ProviderDirectoryEntries {
+            location: LocationSummary::new("trash", None, "/", "Trash"),
+            entries: entries.into_iter().map(to_file_item).collect(),
+        })
+    }
+
+    async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
+        let Some(id) = item_id(location)? else {
+            return Ok(FileItem {
+                name: "Trash".to_string(),
+                path: "trash:///".to_string(),
+                size: 0,
+                modified: Utc::now(),
+                is_directory: true,
+                is_hidden: false,
+                is_symlink: false,
+                is_git_repo: false,
+                extension: None,
+                child_count: None,
+                image_width: None,
+                image_height: None,
+                remote_id: None,
+                thumbnail_url: None,
+                download_url: None,
+                tags: None,
+                is_git_submodule: false,
+                lfs_size: None,
+                id: String::new(),
+            });
+        };
+
+        let entries = spawn_blocking(platform::list)
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
+        entries
+            .into_iter()
+            .find(|entry| entry.id == id)
+            .map(to_file_item)
+            .ok_or_else(|| "[TRASH_ITEM_NOT_FOUND] This item is no longer in the trash".to_string())
+    }
+
+    async fn create_directory(&self, _location: &Location) -> Result<(), String> {
+        Err("Folders can't be created in the trash".to_string())
+    }
+
+    async fn delete(&self, location: &Location) -> Result<(), String> {
+        let id = item_id(location)?;
+        spawn_blocking(move || match id {
+            Some(id) => platform::purge(&id),
+            None => platform::empty(),
+        })
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+    }
+
+    async fn rename(&self, _from: &Location, _to: &Location) -> Result<(), String> {
+        Err("Items in the trash can't be renamed".to_string())
+    }
+
+    async fn copy(&self, _from: &Location, _to: &Location) -> Result<(), String> {
+        Err("Restore items from the trash before copying them".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn item_ids_round_trip_through_uris() {
+        let id = "/home/me/.local/share/Trash/info/report (2).pdf.trashinfo";
+        let location = Location::parse(&item_uri(id)).unwrap();
+        assert_eq!(item_id(&location).unwrap().as_deref(), Some(id));
+
+        let root = Location::parse("trash:///").unwrap();
+        assert_eq!(item_id(&root).unwrap(), None);
+        assert!(item_id(&Location::parse("/tmp/file").unwrap()).is_err());
+    }
+}
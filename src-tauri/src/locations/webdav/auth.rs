@@ -0,0 +1,282 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+const WEBDAV_KEYRING_SERVICE: &str = "marlin-webdav";
+
+/// Information about a connected WebDAV server (safe to expose to frontend)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavServerInfo {
+    pub hostname: String,
+    pub port: u16,
+    pub username: String,
+    pub use_https: bool,
+}
+
+/// Stored server data on disk (no secrets)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebDavServer {
+    pub hostname: String,
+    pub port: u16,
+    pub username: String,
+    #[serde(default = "default_use_https")]
+    pub use_https: bool,
+}
+
+fn default_use_https() -> bool {
+    true
+}
+
+impl WebDavServer {
+    fn info(&self) -> WebDavServerInfo {
+        WebDavServerInfo {
+            hostname: self.hostname.clone(),
+            port: self.port,
+            username: self.username.clone(),
+            use_https: self.use_https,
+        }
+    }
+
+    fn matches(&self, hostname: &str, port: u16) -> bool {
+        self.hostname.eq_ignore_ascii_case(hostname) && self.port == port
+    }
+}
+
+/// Server credentials resolved from keychain (internal use)
+#[derive(Debug, Clone)]
+pub struct WebDavServerCredentials {
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    pub use_https: bool,
+}
+
+/// Storage structure for servers file
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct ServerStorage {
+    servers: Vec<WebDavServer>,
+}
+
+/// In-memory cache of servers
+static SERVERS_CACHE: Lazy<RwLock<Option<Vec<WebDavServer>>>> = Lazy::new(|| RwLock::new(None));
+
+fn keyring_entry(hostname: &str, port: u16, username: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(
+        WEBDAV_KEYRING_SERVICE,
+        &format!("{}@{}:{}", username, hostname, port),
+    )
+    .map_err(|e| format!("Failed to create keyring entry: {}", e))
+}
+
+fn set_password(hostname: &str, port: u16, username: &str, password: &str) -> Result<(), String> {
+    let entry = keyring_entry(hostname, port, username)?;
+    match entry.set_password(password) {
+        Ok(()) => Ok(()),
+        Err(keyring::Error::Ambiguous(_)) => {
+            let _ = entry.delete_credential();
+            let entry = keyring_entry(hostname, port, username)?;
+            entry
+                .set_password(password)
+                .map_err(|e| format!("Failed to store password in keychain: {}", e))
+        }
+        Err(e) => {
+            let msg = e.to_string().to_lowercase();
+            if msg.contains("already exists") || msg.contains("duplicate") {
+                let _ = entry.delete_credential();
+                let entry = keyring_entry(hostname, port, username)?;
+                entry.set_password(password).map_err(|retry_err| {
+                    format!("Failed to store password in keychain: {}", retry_err)
+                })
+            } else {
+                Err(format!("Failed to store password in keychain: {}", e))
+            }
+        }
+    }
+}
+
+fn get_password(hostname: &str, port: u16, username: &str) -> Result<String, String> {
+    let entry = keyring_entry(hostname, port, username)?;
+    entry.get_password().map_err(|e| {
+        format!(
+            "[WEBDAV_NO_CREDENTIALS] Failed to read password from keychain: {}",
+            e
+        )
+    })
+}
+
+fn delete_password(hostname: &str, port: u16, username: &str) -> Result<(), String> {
+    let entry = keyring_entry(hostname, port, username)?;
+    match entry.delete_credential() {
+        Ok(_) => Ok(()),
+        Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete password from keychain: {}", e)),
+    }
+}
+
+/// Get the path to the servers storage file
+fn get_servers_path() -> Result<PathBuf, String> {
+    let config_dir =
+        dirs::config_dir().ok_or_else(|| "Could not determine config directory".to_string())?;
+    let marlin_dir = config_dir.join("marlin");
+
+    if !marlin_dir.exists() {
+        fs::create_dir_all(&marlin_dir)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    Ok(marlin_dir.join("webdav-servers.json"))
+}
+
+/// Load servers from disk
+fn load_servers_from_disk() -> Result<Vec<WebDavServer>, String> {
+    let path = get_servers_path()?;
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read servers file: {}", e))?;
+    let storage: ServerStorage = serde_json::from_str(&contents)
+        .map_err(|e| format!("Failed to parse servers file: {}", e))?;
+
+    Ok(storage.servers)
+}
+
+/// Save servers to disk
+fn save_servers_to_disk(servers: &[WebDavServer]) -> Result<(), String> {
+    let path = get_servers_path()?;
+
+    let storage = ServerStorage {
+        servers: servers.to_vec(),
+    };
+
+    let contents = serde_json::to_string_pretty(&storage)
+        .map_err(|e| format!("Failed to serialize servers: {}", e))?;
+
+    fs::write(&path, contents).map_err(|e| format!("Failed to write servers file: {}", e))?;
+
+    Ok(())
+}
+
+fn load_servers_cached() -> Result<Vec<WebDavServer>, String> {
+    {
+        let cache = SERVERS_CACHE.read().map_err(|e| e.to_string())?;
+        if let Some(servers) = &*cache {
+            return Ok(servers.clone());
+        }
+    }
+
+    let servers = load_servers_from_disk()?;
+    {
+        let mut cache = SERVERS_CACHE.write().map_err(|e| e.to_string())?;
+        *cache = Some(servers.clone());
+    }
+    Ok(servers)
+}
+
+/// Get all connected WebDAV servers (safe info only)
+pub fn get_webdav_servers() -> Result<Vec<WebDavServerInfo>, String> {
+    Ok(load_servers_cached()?
+        .iter()
+        .map(WebDavServer::info)
+        .collect())
+}
+
+/// Get credentials for a specific server (internal use). Without a port, the first
+/// server stored for `hostname` is used, so `webdav://user@host/path` works for
+/// servers on their default port.
+pub fn get_server_credentials(
+    hostname: &str,
+    port: Option<u16>,
+) -> Result<WebDavServerCredentials, String> {
+    let server = load_servers_cached()?
+        .into_iter()
+        .find(|s| match port {
+            Some(port) => s.matches(hostname, port),
+            None => s.hostname.eq_ignore_ascii_case(hostname),
+        })
+        .ok_or_else(|| {
+            format!(
+                "[WEBDAV_NO_CREDENTIALS] No credentials stored for server: {}",
+                hostname
+            )
+        })?;
+
+    let password = get_password(&server.hostname, server.port, &server.username)?;
+    Ok(WebDavServerCredentials {
+        port: server.port,
+        username: server.username,
+        password,
+        use_https: server.use_https,
+    })
+}
+
+/// Add a new WebDAV server, or update the one already stored for `hostname:port`
+pub fn add_webdav_server(
+    hostname: String,
+    port: u16,
+    username: String,
+    password: String,
+    use_https: bool,
+) -> Result<WebDavServerInfo, String> {
+    let mut servers = load_servers_from_disk()?;
+    let mut keychain_hostname = hostname.clone();
+
+    if let Some(existing) = servers.iter_mut().find(|s| s.matches(&hostname, port)) {
+        keychain_hostname = existing.hostname.clone();
+        if existing.username != username {
+            delete_password(&existing.hostname, existing.port, &existing.username)?;
+        }
+        existing.username = username.clone();
+        existing.use_https = use_https;
+    } else {
+        servers.push(WebDavServer {
+            hostname: hostname.clone(),
+            port,
+            username: username.clone(),
+            use_https,
+        });
+    }
+
+    set_password(&keychain_hostname, port, &username, &password)?;
+    save_servers_to_disk(&servers)?;
+
+    {
+        let mut cache = SERVERS_CACHE.write().map_err(|e| e.to_string())?;
+        *cache = Some(servers);
+    }
+
+    Ok(WebDavServerInfo {
+        hostname,
+        port,
+        username,
+        use_https,
+    })
+}
+
+/// Remove a WebDAV server
+pub fn remove_webdav_server(hostname: &str, port: u16) -> Result<(), String> {
+    let mut servers = load_servers_from_disk()?;
+
+    let removed = servers
+        .iter()
+        .find(|s| s.matches(hostname, port))
+        .cloned()
+        .ok_or_else(|| format!("Server not found: {}:{}", hostname, port))?;
+    servers.retain(|s| !s.matches(hostname, port));
+
+    delete_password(&removed.hostname, removed.port, &removed.username)?;
+    save_servers_to_disk(&servers)?;
+
+    {
+        let mut cache = SERVERS_CACHE.write().map_err(|e| e.to_string())?;
+        *cache = Some(servers);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,767 @@
+//! WebDAV servers (Nextcloud, ownCloud, Apache mod_dav, ...) addressed as
+//! `webdav://user@host:port/path`.
+//!
+//! Listings and metadata come from PROPFIND; writes map onto PUT, MKCOL, DELETE,
+//! MOVE and COPY. Passwords live in the OS keychain via [`auth`], and whether a
+//! server speaks HTTPS is stored alongside it (HTTPS unless told otherwise).
+
+pub mod auth;
+
+use crate::fs_utils::FileItem;
+use crate::locations::transfer::StagedFile;
+use crate::locations::{
+    Location, LocationCapabilities, LocationError, LocationProvider, LocationSummary,
+    ProviderDirectoryEntries,
+};
+use crate::sorting::{sort_entries, SortOptions};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+
+pub use auth::{add_webdav_server, get_webdav_servers, remove_webdav_server, WebDavServerInfo};
+
+/// Read size for streamed uploads.
+const TRANSFER_CHUNK_SIZE: usize = 256 * 1024;
+
+const PROPFIND_BODY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:resourcetype/>
+    <d:getcontentlength/>
+    <d:getlastmodified/>
+  </d:prop>
+</d:propfind>"#;
+
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+
+#[derive(Default)]
+pub struct WebDavProvider;
+
+/// One `<d:response>` from a multistatus body.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DavEntry {
+    /// Decoded server path without a trailing slash ("/" for the root).
+    pub path: String,
+    pub is_collection: bool,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// A server resolved from a location's authority plus its stored credentials.
+struct DavServer {
+    authority: String,
+    base_url: String,
+    username: String,
+    password: String,
+}
+
+impl DavServer {
+    fn connect(location: &Location) -> Result<Self, String> {
+        let authority = location.authority().ok_or_else(|| {
+            "WebDAV path requires server: webdav://user@host:port/path".to_string()
+        })?;
+        let (username, hostname, port) = parse_webdav_authority(authority)?;
+        let credentials = auth::get_server_credentials(&hostname, port)?;
+
+        let port = port.unwrap_or(credentials.port);
+        let (scheme, default_port) = if credentials.use_https {
+            ("https", 443)
+        } else {
+            ("http", 80)
+        };
+        let base_url = if port == default_port {
+            format!("{}://{}", scheme, hostname)
+        } else {
+            format!("{}://{}:{}", scheme, hostname, port)
+        };
+
+        Ok(Self {
+            authority: authority.to_string(),
+            base_url,
+            username,
+            password: credentials.password,
+        })
+    }
+
+    /// Collections get a trailing slash; strict servers redirect or refuse without it.
+    fn url(&self, path: &str, collection: bool) -> String {
+        let mut url = format!("{}{}", self.base_url, encode_path(path));
+        if collection && !url.ends_with('/') {
+            url.push('/');
+        }
+        url
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        collection: bool,
+    ) -> Result<RequestBuilder, String> {
+        let method = Method::from_bytes(method.as_bytes())
+            .map_err(|e| format!("Invalid HTTP method {}: {}", method, e))?;
+        Ok(HTTP_CLIENT
+            .request(method, self.url(path, collection))
+            .basic_auth(&self.username, Some(&self.password)))
+    }
+
+    async fn propfind(&self, path: &str, depth: u8) -> Result<Vec<DavEntry>, String> {
+        let response = self
+            .request("PROPFIND", path, depth > 0)?
+            .header("Depth", depth.to_string())
+            .header("Content-Type", "application/xml; charset=utf-8")
+            .body(PROPFIND_BODY)
+            .send()
+            .await
+            .map_err(|e| LocationError::from_request("WebDAV request failed", &e).to_string())?;
+        let response = check_status(response, "read")?;
+        let body = response.text().await.map_err(|e| {
+            LocationError::from_request("Failed to read WebDAV response", &e).to_string()
+        })?;
+        let request_path = normalize_dav_path(path);
+        parse_multistatus(&body, &request_path)
+    }
+
+    async fn stat(&self, path: &str) -> Result<DavEntry, String> {
+        self.propfind(path, 0)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| LocationError::NotFound(format!("Not found: {}", path)).to_string())
+    }
+
+    async fn exists(&self, path: &str) -> Result<bool, String> {
+        match self.stat(path).await {
+            Ok(_) => Ok(true),
+            Err(err)
+                if matches!(
+                    LocationError::from_message(&*err),
+                    LocationError::NotFound(_)
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// MOVE or COPY `from` onto `to` on this server without overwriting.
+    async fn transfer(&self, method: &str, from: &str, to: &str) -> Result<(), String> {
+        let entry = self.stat(from).await?;
+        let destination = self.url(to, entry.is_collection);
+        let response = self
+            .request(method, from, entry.is_collection)?
+            .header("Destination", destination)
+            .header("Overwrite", "F")
+            .header("Depth", "infinity")
+            .send()
+            .await
+            .map_err(|e| LocationError::from_request("WebDAV request failed", &e).to_string())?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Err("An item with this name already exists".to_string());
+        }
+        check_status(response, &method.to_ascii_lowercase())?;
+        Ok(())
+    }
+
+    fn file_item(&self, entry: &DavEntry) -> FileItem {
+        let name = entry
+            .path
+            .rsplit('/')
+            .find(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| self.authority.clone());
+        let extension = if entry.is_collection {
+            None
+        } else {
+            std::path::Path::new(&name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(String::from)
+        };
+
+        FileItem {
+            is_hidden: name.starts_with('.'),
+            path: format!("webdav://{}{}", self.authority, entry.path),
+            name,
+            is_directory: entry.is_collection,
+            size: entry.size,
+            modified: entry.modified.unwrap_or_else(Utc::now),
+            extension,
+            is_symlink: false,
+            is_git_repo: false,
+            child_count: None,
+            image_width: None,
+            image_height: None,
+            remote_id: None,
+            thumbnail_url: None,
+            download_url: None,
+            tags: None,
+            is_git_submodule: false,
+            lfs_size: None,
+            id: String::new(),
+        }
+    }
+}
+
+/// Turn an HTTP error status into a message; 207 Multi-Status only counts as success
+/// for PROPFIND, elsewhere it means part of the operation failed.
+fn check_status(response: Response, action: &str) -> Result<Response, String> {
+    let status = response.status();
+    match status {
+        StatusCode::MULTI_STATUS if action == "read" => Ok(response),
+        StatusCode::MULTI_STATUS => Err(format!(
+            "Failed to {}: the server reported errors for some items",
+            action
+        )),
+        s if s.is_success() => Ok(response),
+        StatusCode::UNAUTHORIZED => Err(
+            "[WEBDAV_AUTH_FAILED] The server rejected the stored username or password".to_string(),
+        ),
+        StatusCode::FORBIDDEN => Err(LocationError::PermissionDenied(format!(
+            "Failed to {}: permission denied",
+            action
+        ))
+        .to_string()),
+        StatusCode::NOT_FOUND => {
+            Err(LocationError::NotFound("Not found on the WebDAV server".to_string()).to_string())
+        }
+        StatusCode::INSUFFICIENT_STORAGE => {
+            Err(format!("Failed to {}: the server is out of space", action))
+        }
+        s => Err(LocationError::from_http_status(
+            s.as_u16(),
+            format!("Failed to {}: HTTP {}", action, s),
+        )
+        .to_string()),
+    }
+}
+
+#[async_trait]
+impl LocationProvider for WebDavProvider {
+    fn scheme(&self) -> &'static str {
+        "webdav"
+    }
+
+    fn capabilities(&self, _location: &Location) -> LocationCapabilities {
+        LocationCapabilities::new("webdav", "WebDAV Server", true, true)
+    }
+
+    async fn read_directory(
+        &self,
+        location: &Location,
+    ) -> Result<ProviderDirectoryEntries, String> {
+        let _permit = crate::locations::throttle::acquire("webdav").await?;
+        let server = DavServer::connect(location)?;
+        let dir_path = normalize_dav_path(location.path());
+
+        let entries = server.propfind(&dir_path, 1).await?;
+        let mut items: Vec<FileItem> = entries
+            .iter()
+            .filter(|entry| entry.path != dir_path)
+            .map(|entry| server.file_item(entry))
+            .collect();
+
+        // Default order; read_directory re-sorts with the user's preferences
+        sort_entries(&mut items, &SortOptions::default());
+
+        let display_path = format!("webdav://{}{}", server.authority, dir_path);
+        Ok(ProviderDirectoryEntries {
+            location: LocationSummary::new(
+                "webdav",
+                Some(server.authority.clone()),
+                dir_path,
+                display_path,
+            ),
+            entries: items,
+        })
+    }
+
+    async fn get_file_metadata(&self, location: &Location) -> Result<FileItem, String> {
+        let _permit = crate::locations::throttle::acquire("webdav").await?;
+        let server = DavServer::connect(location)?;
+        let path = normalize_dav_path(location.path());
+        let mut entry = server.stat(&path).await?;
+        // Some servers answer with a differently encoded href; trust the request
+        entry.path = path;
+        Ok(server.file_item(&entry))
+    }
+
+    async fn create_directory(&self, location: &Location) -> Result<(), String> {
+        let server = DavServer::connect(location)?;
+        let response = server
+            .request("MKCOL", location.path(), true)?
+            .send()
+            .await
+            .map_err(|e| LocationError::from_request("WebDAV request failed", &e).to_string())?;
+        if response.status() == StatusCode::METHOD_NOT_ALLOWED {
+            return Err("A file or folder already exists at this path".to_string());
+        }
+        if response.status() == StatusCode::CONFLICT {
+            return Err(LocationError::NotFound("Parent folder does not exist".to_string()).into());
+        }
+        check_status(response, "create directory")?;
+        Ok(())
+    }
+
+    async fn create_file(
+        &self,
+        directory: &Location,
+        name: &str,
+        contents: &[u8],
+    ) -> Result<String, String> {
+        let authority = directory
+            .authority()
+            .ok_or_else(|| "WebDAV path requires server".to_string())?;
+        let staged = StagedFile::write(contents).await?;
+        upload_file_to_webdav(staged.path(), authority, directory.path(), name).await
+    }
+
+    async fn delete(&self, location: &Location) -> Result<(), String> {
+        let server = DavServer::connect(location)?;
+        let entry = server.stat(location.path()).await?;
+        let response = server
+            .request("DELETE", location.path(), entry.is_collection)?
+            .send()
+            .await
+            .map_err(|e| LocationError::from_request("WebDAV request failed", &e).to_string())?;
+        check_status(response, "delete")?;
+        Ok(())
+    }
+
+    async fn rename(&self, from: &Location, to: &Location) -> Result<(), String> {
+        let server = same_server(from, to)?;
+        server.transfer("MOVE", from.path(), to.path()).await
+    }
+
+    async fn copy(&self, from: &Location, to: &Location) -> Result<(), String> {
+        let server = same_server(from, to)?;
+        server.transfer("COPY", from.path(), to.path()).await
+    }
+}
+
+/// Server-side MOVE/COPY only work within one server.
+fn same_server(from: &Location, to: &Location) -> Result<DavServer, String> {
+    let server = DavServer::connect(from)?;
+    let target = DavServer::connect(to)?;
+    if server.base_url != target.base_url {
+        return Err(LocationError::Unsupported(
+            "Cannot move or copy across different WebDAV servers".to_string(),
+        )
+        .into());
+    }
+    Ok(server)
+}
+
+/// Percent-encode each path segment, keeping the separators.
+fn encode_path(path: &str) -> String {
+    let encoded: Vec<String> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect();
+    format!("/{}", encoded.join("/"))
+}
+
+/// Collapse duplicate slashes and drop the trailing one, keeping "/" for the root.
+fn normalize_dav_path(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    format!("/{}", segments.join("/"))
+}
+
+/// Resolve an `<d:href>` to a decoded server path. Servers send absolute URLs
+/// (`https://host/dav/a%20b/`), absolute paths (`/dav/a%20b/`) or, rarely, paths
+/// relative to the requested collection (`a%20b/`).
+fn normalize_href(href: &str, request_path: &str) -> String {
+    let href = href.trim();
+    let path = match href.find("://") {
+        Some(idx) => {
+            let after = &href[idx + 3..];
+            after.find('/').map_or("/", |slash| &after[slash..])
+        }
+        None => href,
+    };
+    let decoded = urlencoding::decode(path)
+        .map(|p| p.into_owned())
+        .unwrap_or_else(|_| path.to_string());
+    if decoded.starts_with('/') {
+        normalize_dav_path(&decoded)
+    } else {
+        normalize_dav_path(&format!(
+            "{}/{}",
+            request_path,
+            decoded.trim_start_matches("./")
+        ))
+    }
+}
+
+fn is_dav_element(node: roxmltree::Node<'_, '_>, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
+
+fn dav_child<'a, 'i>(node: roxmltree::Node<'a, 'i>, name: &str) -> Option<roxmltree::Node<'a, 'i>> {
+    node.children().find(|n| is_dav_element(*n, name))
+}
+
+/// Parse a PROPFIND multistatus body. Elements are matched by local name so odd
+/// namespace prefixes from some servers don't matter.
+pub(crate) fn parse_multistatus(xml: &str, request_path: &str) -> Result<Vec<DavEntry>, String> {
+    let doc =
+        roxmltree::Document::parse(xml).map_err(|e| format!("Invalid WebDAV response: {}", e))?;
+
+    let mut entries = Vec::new();
+    for response in doc.descendants().filter(|n| is_dav_element(*n, "response")) {
+        let Some(href) = dav_child(response, "href").and_then(|n| n.text()) else {
+            continue;
+        };
+        // Properties the server couldn't return come in a separate 404 propstat
+        let prop = response
+            .children()
+            .filter(|n| is_dav_element(*n, "propstat"))
+            .find(|propstat| {
+                dav_child(*propstat, "status")
+                    .and_then(|n| n.text())
+                    .map_or(true, |status| status.contains(" 200"))
+            })
+            .and_then(|propstat| dav_child(propstat, "prop"));
+        let field = |name: &str| prop.and_then(|p| dav_child(p, name));
+        let text = |name: &str| field(name).and_then(|n| n.text()).map(str::trim);
+
+        let is_collection = field("resourcetype").map_or(false, |rt| {
+            rt.children().any(|n| is_dav_element(n, "collection"))
+        }) || href.trim_end().ends_with('/');
+        let size = text("getcontentlength")
+            .and_then(|t| t.parse().ok())
+            .unwrap_or(0);
+        let modified = text("getlastmodified")
+            .and_then(|t| DateTime::parse_from_rfc2822(t).ok())
+            .map(|t| t.with_timezone(&Utc));
+
+        entries.push(DavEntry {
+            path: normalize_href(href, request_path),
+            is_collection,
+            size,
+            modified,
+        });
+    }
+    Ok(entries)
+}
+
+/// Parse authority part: "user@host:port" -> (user, host, port). Without a port the
+/// stored server for the host decides it.
+pub fn parse_webdav_authority(authority: &str) -> Result<(String, String, Option<u16>), String> {
+    let (user_part, host_part) = authority.rsplit_once('@').ok_or_else(|| {
+        format!(
+            "WebDAV authority must include username: user@host (got '{}')",
+            authority
+        )
+    })?;
+
+    let username = urlencoding::decode(user_part)
+        .map(|u| u.into_owned())
+        .unwrap_or_else(|_| user_part.to_string());
+    if username.is_empty() {
+        return Err("WebDAV username cannot be empty".to_string());
+    }
+
+    let (hostname, port) = if let Some((h, p)) = host_part.rsplit_once(':') {
+        let port: u16 = p
+            .parse()
+            .map_err(|_| format!("Invalid port number: {}", p))?;
+        (h.to_string(), Some(port))
+    } else {
+        (host_part.to_string(), None)
+    };
+
+    if hostname.is_empty() {
+        return Err("WebDAV hostname cannot be empty".to_string());
+    }
+
+    Ok((username, hostname, port))
+}
+
+/// Parse a full WebDAV URL: "webdav://user@host:port/path" -> (user, host, port, path)
+pub fn parse_webdav_url(url: &str) -> Result<(String, String, Option<u16>, String), String> {
+    let remainder = url
+        .strip_prefix("webdav://")
+        .ok_or_else(|| format!("Invalid WebDAV URL scheme (expected webdav://): {}", url))?;
+
+    let (authority_part, path_part) = if let Some(slash_idx) = remainder.find('/') {
+        let (auth, path) = remainder.split_at(slash_idx);
+        (auth, path.to_string())
+    } else {
+        (remainder, "/".to_string())
+    };
+
+    let (username, hostname, port) = parse_webdav_authority(authority_part)?;
+    Ok((username, hostname, port, normalize_dav_path(&path_part)))
+}
+
+/// Upload a local file into `dest_dir` on the server in `authority`, picking a free
+/// name like "name (2).ext" if needed. Returns the final filename used.
+pub async fn upload_file_to_webdav(
+    local_path: &std::path::Path,
+    authority: &str,
+    dest_dir: &str,
+    preferred_name: &str,
+) -> Result<String, String> {
+    let location = Location::parse(&format!("webdav://{}{}", authority, dest_dir))?;
+    let server = DavServer::connect(&location)?;
+    let _permit = crate::locations::throttle::acquire("webdav").await?;
+
+    let p = std::path::Path::new(preferred_name);
+    let stem = p
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(preferred_name)
+        .to_string();
+    let ext = p
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|s| s.to_string());
+
+    for i in 1..1000usize {
+        let candidate = if i == 1 {
+            preferred_name.to_string()
+        } else if let Some(ref e) = ext {
+            format!("{stem} ({i}).{e}")
+        } else {
+            format!("{stem} ({i})")
+        };
+        let dest_path = normalize_dav_path(&format!("{}/{}", dest_dir, candidate));
+
+        if server.exists(&dest_path).await? {
+            continue;
+        }
+        // Streamed from disk so large files never sit in memory whole; the length is
+        // sent up front since some servers refuse chunked uploads
+        let file = tokio::fs::File::open(local_path)
+            .await
+            .map_err(|e| format!("Failed to read local file: {}", e))?;
+        let length = file
+            .metadata()
+            .await
+            .map_err(|e| format!("Failed to read local file: {}", e))?
+            .len();
+        let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::with_capacity(
+            file,
+            TRANSFER_CHUNK_SIZE,
+        ));
+        // If-None-Match keeps a racing upload from being overwritten
+        let response = server
+            .request("PUT", &dest_path, false)?
+            .header("If-None-Match", "*")
+            .header(reqwest::header::CONTENT_LENGTH, length)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| LocationError::from_request("Failed to upload file", &e).to_string())?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            continue;
+        }
+        check_status(response, "upload file")?;
+        return Ok(candidate);
+    }
+
+    Err("Unable to allocate unique destination name on WebDAV server".to_string())
+}
+
+/// Download the WebDAV file at `webdav_url` to `dest`, writing the body as it arrives.
+/// With `max_bytes`, only that much from the start of the file is fetched.
+pub async fn download_file_from_webdav(
+    webdav_url: &str,
+    dest: &std::path::Path,
+    max_bytes: Option<u64>,
+) -> Result<(), String> {
+    use tokio::io::AsyncWriteExt;
+
+    let location = Location::parse(webdav_url)?;
+    let server = DavServer::connect(&location)?;
+    let remote_path = normalize_dav_path(location.path());
+    let _permit = crate::locations::throttle::acquire("webdav").await?;
+
+    let mut request = server.request("GET", &remote_path, false)?;
+    if let Some(max_bytes) = max_bytes {
+        // Servers without range support send the whole body, which is cut off below
+        request = request.header("Range", format!("bytes=0-{}", max_bytes.saturating_sub(1)));
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| LocationError::from_request("Failed to download file", &e).to_string())?;
+
+    let mut local = tokio::fs::File::create(dest)
+        .await
+        .map_err(|e| format!("Failed to write downloaded file: {}", e))?;
+    // An empty file has no byte range to return
+    if max_bytes.is_some() && response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(());
+    }
+    let mut response = check_status(response, "download file")?;
+
+    let limit = max_bytes.unwrap_or(u64::MAX);
+    let mut written = 0u64;
+    while written < limit {
+        let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| LocationError::from_request("Failed to download file", &e).to_string())?
+        else {
+            break;
+        };
+        let take = (limit - written).min(chunk.len() as u64) as usize;
+        local
+            .write_all(&chunk[..take])
+            .await
+            .map_err(|e| format!("Failed to write downloaded file: {}", e))?;
+        written += take as u64;
+    }
+    local
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to write downloaded file: {}", e))
+}
+
+/// Download a WebDAV file to a temp location.
+/// Returns the temporary file path.
+pub async fn download_webdav_file_to_temp(webdav_url: &str) -> Result<std::path::PathBuf, String> {
+    use sha2::{Digest, Sha256};
+
+    let location = Location::parse(webdav_url)?;
+    let server = DavServer::connect(&location)?;
+    let remote_path = normalize_dav_path(location.path());
+
+    let temp_dir = std::env::temp_dir().join("marlin-webdav-downloads");
+    tokio::fs::create_dir_all(&temp_dir)
+        .await
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+
+    let original_name = remote_path
+        .rsplit('/')
+        .find(|s| !s.is_empty())
+        .unwrap_or("file");
+
+    let mut hasher = Sha256::new();
+    hasher.update(server.authority.as_bytes());
+    hasher.update(remote_path.as_bytes());
+    let hash = hex::encode(hasher.finalize());
+    let hash_prefix = hash[..12].to_string();
+
+    let safe_name = original_name
+        .replace(['/', '\\', '\0', ':', '*', '?', '"', '<', '>', '|'], "_")
+        .trim_start_matches('.')
+        .to_string();
+
+    let temp_path = temp_dir.join(format!("{}_{}", hash_prefix, safe_name));
+
+    crate::locations::transfer::download_to_stable_path(&temp_path, |part_path| async move {
+        download_file_from_webdav(webdav_url, &part_path, None).await
+    })
+    .await?;
+
+    Ok(temp_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_statuses_are_coded() {
+        let code = |status: u16| {
+            let response = tauri::http::Response::builder()
+                .status(status)
+                .body("")
+                .unwrap();
+            let error = check_status(Response::from(response), "delete").unwrap_err();
+            LocationError::from_message(error).code().to_string()
+        };
+        assert_eq!(code(404), "ENOENT");
+        assert_eq!(code(403), "EPERM");
+        assert_eq!(code(405), "EUNSUPPORTED");
+        assert_eq!(code(502), "ENETWORK");
+        assert_eq!(code(207), "EOTHER");
+    }
+
+    #[test]
+    fn test_parse_webdav_url() {
+        let (user, host, port, path) =
+            parse_webdav_url("webdav://alice@cloud.example.com/remote.php/dav/files/alice/")
+                .unwrap();
+        assert_eq!(user, "alice");
+        assert_eq!(host, "cloud.example.com");
+        assert_eq!(port, None);
+        assert_eq!(path, "/remote.php/dav/files/alice");
+
+        let (_, host, port, path) = parse_webdav_url("webdav://bob@nas:8443").unwrap();
+        assert_eq!(host, "nas");
+        assert_eq!(port, Some(8443));
+        assert_eq!(path, "/");
+
+        assert!(parse_webdav_url("webdav://nas/path").is_err());
+        assert!(parse_webdav_url("webdav://bob@nas:http/path").is_err());
+    }
+
+    #[test]
+    fn test_parse_multistatus_absolute_and_relative_hrefs() {
+        let xml = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response>
+    <d:href>/dav/My%20Files/</d:href>
+    <d:propstat>
+      <d:prop><d:resourcetype><d:collection/></d:resourcetype></d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+  <d:response>
+    <d:href>https://cloud.example.com/dav/My%20Files/report%202024.pdf</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:resourcetype/>
+        <d:getcontentlength>1234</d:getcontentlength>
+        <d:getlastmodified>Tue, 02 Jan 2024 10:30:00 GMT</d:getlastmodified>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+    <d:propstat>
+      <d:prop><d:displayname/></d:prop>
+      <d:status>HTTP/1.1 404 Not Found</d:status>
+    </d:propstat>
+  </d:response>
+  <D:response xmlns:D="DAV:">
+    <D:href>Photos/</D:href>
+    <D:propstat>
+      <D:prop><D:resourcetype><D:collection/></D:resourcetype></D:prop>
+      <D:status>HTTP/1.1 200 OK</D:status>
+    </D:propstat>
+  </D:response>
+</d:multistatus>"#;
+
+        let entries = parse_multistatus(xml, "/dav/My Files").unwrap();
+        let paths: Vec<_> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/dav/My Files",
+                "/dav/My Files/report 2024.pdf",
+                "/dav/My Files/Photos"
+            ]
+        );
+        assert!(entries[0].is_collection);
+        assert!(!entries[1].is_collection);
+        assert_eq!(entries[1].size, 1234);
+        assert_eq!(
+            entries[1].modified.unwrap().to_rfc3339(),
+            "2024-01-02T10:30:00+00:00"
+        );
+        assert!(entries[2].is_collection);
+    }
+
+    #[test]
+    fn test_encode_path_round_trips_through_href() {
+        let encoded = encode_path("/dav/My Files/a#b?.txt");
+        assert_eq!(encoded, "/dav/My%20Files/a%23b%3F.txt");
+        assert_eq!(normalize_href(&encoded, "/"), "/dav/My Files/a#b?.txt");
+    }
+}
@@ -0,0 +1,590 @@
+//! Structured metadata extraction for images (EXIF), audio (lofty) and video (FFmpeg).
+
+use exif::{In, Reader, Tag, Value};
+use lofty::file::{AudioFile, FileType, TaggedFileExt};
+use lofty::tag::Accessor;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::process::Command;
+
+use crate::thumbnails::generators::video::VideoGenerator;
+
+/// Largest remote file we will download just to read its metadata.
+pub const REMOTE_METADATA_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaKind {
+    Image,
+    Video,
+    Audio,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpsCoordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageMetadata {
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens_model: Option<String>,
+    pub iso: Option<u32>,
+    /// Shutter speed as photographers write it, e.g. "1/250".
+    pub exposure_time: Option<String>,
+    pub f_number: Option<f64>,
+    pub focal_length: Option<f64>,
+    pub captured_at: Option<String>,
+    pub gps: Option<GpsCoordinates>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub orientation: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamMetadata {
+    pub container: Option<String>,
+    pub duration_secs: Option<f64>,
+    pub bit_rate: Option<u64>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<f64>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaMetadata {
+    pub kind: MediaKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<StreamMetadata>,
+    /// Every raw tag we read (EXIF fields or container tags), for display in a details pane.
+    pub tags: BTreeMap<String, String>,
+}
+
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "mp3", "m4a", "aac", "flac", "wav", "ogg", "oga", "opus", "wma", "aiff", "aif", "alac",
+];
+
+/// Classify a file by extension, or `None` if it isn't a media type we inspect.
+pub fn media_kind_for_extension(ext: &str) -> Option<MediaKind> {
+    let ext = ext.to_ascii_lowercase();
+    if crate::thumbnails::generators::is_video_extension(&ext) {
+        Some(MediaKind::Video)
+    } else if AUDIO_EXTENSIONS.contains(&ext.as_str()) {
+        Some(MediaKind::Audio)
+    } else if matches!(
+        ext.as_str(),
+        "jpg" | "jpeg" | "tif" | "tiff" | "png" | "webp" | "heic" | "heif" | "avif"
+    ) {
+        Some(MediaKind::Image)
+    } else {
+        None
+    }
+}
+
+/// Read metadata from a local file. `name` supplies the extension when `path` is a temp copy.
+pub fn read_media_metadata(path: &Path, name: &str) -> Result<MediaMetadata, String> {
+    let ext = Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let kind =
+        media_kind_for_extension(ext).ok_or_else(|| format!("Unsupported media type: {}", name))?;
+
+    match kind {
+        MediaKind::Image => read_image_metadata(path),
+        MediaKind::Video | MediaKind::Audio => read_stream_metadata(path, kind),
+    }
+}
+
+fn read_image_metadata(path: &Path) -> Result<MediaMetadata, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let exif = Reader::new()
+        .read_from_container(&mut reader)
+        .map_err(|e| format!("Failed to read EXIF data: {}", e))?;
+
+    let text = |tag: Tag| {
+        exif.get_field(tag, In::PRIMARY)
+            .and_then(|field| match &field.value {
+                Value::Ascii(parts) => parts
+                    .iter()
+                    .map(|p| String::from_utf8_lossy(p).trim().to_string())
+                    .find(|s| !s.is_empty()),
+                _ => None,
+            })
+    };
+    let uint = |tag: Tag| {
+        exif.get_field(tag, In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0))
+    };
+    let rational = |tag: Tag| {
+        exif.get_field(tag, In::PRIMARY)
+            .and_then(|field| match &field.value {
+                Value::Rational(values) => values.first().copied(),
+                _ => None,
+            })
+    };
+
+    let exposure_time = rational(Tag::ExposureTime).map(|r| format_exposure(r.num, r.denom));
+    let captured_at = text(Tag::DateTimeOriginal)
+        .or_else(|| text(Tag::DateTime))
+        .map(|raw| normalize_exif_datetime(&raw));
+
+    let mut tags = BTreeMap::new();
+    for field in exif.fields() {
+        if field.ifd_num != In::PRIMARY {
+            continue;
+        }
+        let value = field.display_value().with_unit(&exif).to_string();
+        tags.insert(field.tag.to_string(), value);
+    }
+
+    let image = ImageMetadata {
+        camera_make: text(Tag::Make),
+        camera_model: text(Tag::Model),
+        lens_model: text(Tag::LensModel),
+        iso: uint(Tag::PhotographicSensitivity),
+        exposure_time,
+        f_number: rational(Tag::FNumber).map(|r| r.to_f64()),
+        focal_length: rational(Tag::FocalLength).map(|r| r.to_f64()),
+        captured_at,
+        gps: read_gps(&exif),
+        width: uint(Tag::PixelXDimension).or_else(|| uint(Tag::ImageWidth)),
+        height: uint(Tag::PixelYDimension).or_else(|| uint(Tag::ImageLength)),
+        orientation: uint(Tag::Orientation),
+    };
+
+    Ok(MediaMetadata {
+        kind: MediaKind::Image,
+        image: Some(image),
+        stream: None,
+        tags,
+    })
+}
+
+fn read_gps(exif: &exif::Exif) -> Option<GpsCoordinates> {
+    let dms = |tag: Tag| {
+        exif.get_field(tag, In::PRIMARY)
+            .and_then(|field| match &field.value {
+                Value::Rational(v) if v.len() >= 3 => {
+                    Some(dms_to_degrees(v[0].to_f64(), v[1].to_f64(), v[2].to_f64()))
+                }
+                _ => None,
+            })
+    };
+    let reference = |tag: Tag| {
+        exif.get_field(tag, In::PRIMARY)
+            .and_then(|field| match &field.value {
+                Value::Ascii(parts) => parts
+                    .first()
+                    .map(|p| String::from_utf8_lossy(p).into_owned()),
+                _ => None,
+            })
+    };
+
+    let latitude = dms(Tag::GPSLatitude)?;
+    let longitude = dms(Tag::GPSLongitude)?;
+    let latitude = apply_hemisphere(latitude, reference(Tag::GPSLatitudeRef).as_deref());
+    let longitude = apply_hemisphere(longitude, reference(Tag::GPSLongitudeRef).as_deref());
+
+    let altitude = exif
+        .get_field(Tag::GPSAltitude, In::PRIMARY)
+        .and_then(|field| match &field.value {
+            Value::Rational(v) => v.first().map(|r| r.to_f64()),
+            _ => None,
+        })
+        .map(|alt| {
+            // AltitudeRef 1 means below sea level
+            let below = exif
+                .get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+                == Some(1);
+            if below {
+                -alt
+            } else {
+                alt
+            }
+        });
+
+    Some(GpsCoordinates {
+        latitude,
+        longitude,
+        altitude,
+    })
+}
+
+fn dms_to_degrees(degrees: f64, minutes: f64, seconds: f64) -> f64 {
+    degrees + minutes / 60.0 + seconds / 3600.0
+}
+
+fn apply_hemisphere(value: f64, reference: Option<&str>) -> f64 {
+    match reference.map(|r| r.trim().to_ascii_uppercase()) {
+        Some(r) if r.starts_with('S') || r.starts_with('W') => -value,
+        _ => value,
+    }
+}
+
+fn format_exposure(num: u32, denom: u32) -> String {
+    if denom == 0 {
+        return num.to_string();
+    }
+    if num >= denom {
+        let seconds = num as f64 / denom as f64;
+        return format!("{}", (seconds * 10.0).round() / 10.0);
+    }
+    // Express fast shutter speeds as 1/N
+    let reciprocal = (denom as f64 / num.max(1) as f64).round();
+    format!("1/{}", reciprocal)
+}
+
+/// EXIF stores "YYYY:MM:DD HH:MM:SS"; convert to ISO-8601 without a zone.
+fn normalize_exif_datetime(raw: &str) -> String {
+    let raw = raw.trim();
+    match raw.split_once(' ') {
+        Some((date, time)) => format!("{}T{}", date.replace(':', "-"), time),
+        None => raw.replace(':', "-"),
+    }
+}
+
+fn read_stream_metadata(path: &Path, kind: MediaKind) -> Result<MediaMetadata, String> {
+    let (stream, tags) = match kind {
+        MediaKind::Audio => read_audio_properties(path)?,
+        _ => probe_with_ffmpeg(path)?,
+    };
+    Ok(MediaMetadata {
+        kind,
+        image: None,
+        stream: Some(stream),
+        tags,
+    })
+}
+
+/// Duration, bit rate and tags of an audio file, read by lofty without FFmpeg.
+fn read_audio_properties(
+    path: &Path,
+) -> Result<(StreamMetadata, BTreeMap<String, String>), String> {
+    let tagged =
+        lofty::read_from_path(path).map_err(|e| format!("Failed to read audio file: {}", e))?;
+    let properties = tagged.properties();
+    let duration = properties.duration().as_secs_f64();
+
+    let meta = StreamMetadata {
+        container: Some(format!("{:?}", tagged.file_type()).to_ascii_lowercase()),
+        duration_secs: (duration > 0.0).then_some(duration),
+        bit_rate: properties
+            .overall_bitrate()
+            .or_else(|| properties.audio_bitrate())
+            .map(|kbps| u64::from(kbps) * 1000),
+        audio_codec: audio_codec(tagged.file_type()).map(str::to_string),
+        sample_rate: properties.sample_rate(),
+        channels: properties.channels().map(u32::from),
+        ..Default::default()
+    };
+
+    let mut tags = BTreeMap::new();
+    if let Some(tag) = tagged.primary_tag().or_else(|| tagged.first_tag()) {
+        let fields = [
+            ("title", tag.title()),
+            ("artist", tag.artist()),
+            ("album", tag.album()),
+            ("genre", tag.genre()),
+            ("comment", tag.comment()),
+        ];
+        for (key, value) in fields {
+            if let Some(value) = value.filter(|v| !v.trim().is_empty()) {
+                tags.insert(key.to_string(), value.into_owned());
+            }
+        }
+        if let Some(track) = tag.track() {
+            tags.insert("track".to_string(), track.to_string());
+        }
+    }
+
+    Ok((meta, tags))
+}
+
+/// The codec a container implies, where there's only one it can hold.
+fn audio_codec(file_type: FileType) -> Option<&'static str> {
+    match file_type {
+        FileType::Mpeg => Some("mp3"),
+        FileType::Aac => Some("aac"),
+        FileType::Flac => Some("flac"),
+        FileType::Opus => Some("opus"),
+        FileType::Vorbis => Some("vorbis"),
+        FileType::Speex => Some("speex"),
+        FileType::Ape => Some("ape"),
+        FileType::WavPack => Some("wavpack"),
+        _ => None,
+    }
+}
+
+/// Container and stream details from the header FFmpeg prints for `-i`. The managed
+/// FFmpeg download doesn't include ffprobe everywhere (there's none on macOS), but
+/// every build of ffmpeg describes its input this way.
+pub(crate) fn probe_with_ffmpeg(
+    path: &Path,
+) -> Result<(StreamMetadata, BTreeMap<String, String>), String> {
+    let ffmpeg = VideoGenerator::ensure_ffmpeg()
+        .map_err(|e| format!("Video details need FFmpeg, which isn't available: {}", e))?;
+    // Without an output file ffmpeg exits with an error after printing the header
+    let output = Command::new(ffmpeg)
+        .args(["-hide_banner", "-nostdin", "-i"])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    parse_ffmpeg_header(&stderr).ok_or_else(|| {
+        let reason = stderr.lines().last().unwrap_or_default().trim().to_string();
+        format!("FFmpeg couldn't read the file: {}", reason)
+    })
+}
+
+/// Parse ffmpeg's description of input #0, or `None` if it didn't recognize the file.
+fn parse_ffmpeg_header(stderr: &str) -> Option<(StreamMetadata, BTreeMap<String, String>)> {
+    let mut lines = stderr
+        .lines()
+        .skip_while(|line| !line.starts_with("Input #0, "));
+    let container = lines
+        .next()?
+        .strip_prefix("Input #0, ")?
+        .split(", from '")
+        .next()
+        .map(str::to_string);
+
+    let mut meta = StreamMetadata {
+        container,
+        ..Default::default()
+    };
+    let mut tags = BTreeMap::new();
+    // Only the container's own "Metadata:" block, not the ones under each stream
+    let mut in_format_tags = false;
+
+    for line in lines {
+        let indent = line.len() - line.trim_start().len();
+        let line = line.trim();
+        if indent < 2 || line.starts_with("Input #") || line.starts_with("Output #") {
+            break;
+        }
+        if indent == 2 {
+            in_format_tags = line == "Metadata:";
+            if let Some(rest) = line.strip_prefix("Duration: ") {
+                let parts: Vec<&str> = rest.split(", ").collect();
+                meta.duration_secs = parts.first().and_then(|d| parse_timestamp(d));
+                meta.bit_rate = parts
+                    .iter()
+                    .find_map(|part| part.strip_prefix("bitrate: "))
+                    .and_then(|rate| rate.strip_suffix(" kb/s"))
+                    .and_then(|kbps| kbps.trim().parse::<u64>().ok())
+                    .map(|kbps| kbps * 1000);
+            } else if let Some(stream) = line.strip_prefix("Stream #") {
+                parse_ffmpeg_stream(stream, &mut meta);
+            }
+            continue;
+        }
+        if in_format_tags && indent == 4 {
+            if let Some((key, value)) = line.split_once(':') {
+                let key = key.trim().to_ascii_lowercase();
+                if !key.is_empty() {
+                    tags.insert(key, value.trim().to_string());
+                }
+            }
+        }
+    }
+
+    Some((meta, tags))
+}
+
+/// Fill in codec details from one "Stream #0:1: Audio: aac (LC), 48000 Hz, ..." line.
+fn parse_ffmpeg_stream(line: &str, meta: &mut StreamMetadata) {
+    let (kind, details) = if let Some((_, rest)) = line.split_once(": Video: ") {
+        ("video", rest)
+    } else if let Some((_, rest)) = line.split_once(": Audio: ") {
+        ("audio", rest)
+    } else {
+        return;
+    };
+    let parts = split_outside_parens(details);
+    let codec = parts
+        .first()
+        .and_then(|part| part.split_whitespace().next())
+        .map(str::to_string);
+
+    match kind {
+        // Cover art in audio files shows up as a single-frame video stream
+        "video" if meta.video_codec.is_none() && !details.contains("(attached pic)") => {
+            meta.video_codec = codec;
+            if let Some((width, height)) = parts.iter().find_map(|part| {
+                let (w, h) = part.split_whitespace().next()?.split_once('x')?;
+                Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?))
+            }) {
+                meta.width = Some(width);
+                meta.height = Some(height);
+            }
+            meta.frame_rate = parts
+                .iter()
+                .find_map(|part| part.strip_suffix(" fps"))
+                .and_then(|fps| fps.trim().parse::<f64>().ok())
+                .filter(|fps| *fps > 0.0);
+        }
+        "audio" if meta.audio_codec.is_none() => {
+            meta.audio_codec = codec;
+            meta.sample_rate = parts
+                .iter()
+                .find_map(|part| part.strip_suffix(" Hz"))
+                .and_then(|rate| rate.trim().parse().ok());
+            meta.channels = parts.get(2).and_then(|layout| channel_count(layout));
+        }
+        _ => {}
+    }
+}
+
+/// Split on ", " except inside parentheses, as in "yuvj420p(pc, bt709), 1920x1080".
+fn split_outside_parens(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let bytes = text.as_bytes();
+    for (index, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'(' | b'[' => depth += 1,
+            b')' | b']' => depth = depth.saturating_sub(1),
+            b',' if depth == 0 && bytes.get(index + 1) == Some(&b' ') => {
+                parts.push(text[start..index].trim());
+                start = index + 2;
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+    parts
+}
+
+/// Channels in an ffmpeg channel layout name ("stereo", "5.1(side)", "3 channels").
+fn channel_count(layout: &str) -> Option<u32> {
+    let layout = layout.split('(').next()?.trim();
+    if let Some(count) = layout.strip_suffix(" channels") {
+        return count.trim().parse().ok();
+    }
+    match layout {
+        "mono" => Some(1),
+        "stereo" | "downmix" => Some(2),
+        "2.1" | "3.0" => Some(3),
+        "quad" | "4.0" | "3.1" => Some(4),
+        "4.1" | "5.0" => Some(5),
+        "5.1" | "6.0" => Some(6),
+        "6.1" | "7.0" => Some(7),
+        "7.1" => Some(8),
+        _ => None,
+    }
+}
+
+/// "HH:MM:SS.ss" as seconds; ffmpeg prints "N/A" when it doesn't know.
+fn parse_timestamp(raw: &str) -> Option<f64> {
+    let mut parts = raw.trim().splitn(3, ':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    let total = hours * 3600.0 + minutes * 60.0 + seconds;
+    (total.is_finite() && total > 0.0).then_some(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_gps_and_exposure_values() {
+        let lat = apply_hemisphere(dms_to_degrees(40.0, 26.0, 46.0), Some("S"));
+        assert!((lat + 40.446_111).abs() < 1e-5);
+        assert_eq!(format_exposure(1, 250), "1/250");
+        assert_eq!(format_exposure(10, 4), "2.5");
+        assert_eq!(
+            normalize_exif_datetime("2023:07:14 18:02:09"),
+            "2023-07-14T18:02:09"
+        );
+    }
+
+    #[test]
+    fn parses_ffmpeg_input_header() {
+        let stderr = "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'clip.mp4':
+  Metadata:
+    major_brand     : isom
+    TITLE           : Clip
+  Duration: 00:00:12.51, start: 0.000000, bitrate: 4500 kb/s
+  Stream #0:0[0x1](und): Video: mjpeg (Baseline), yuvj420p(pc, bt470bg/unknown/unknown), 600x600, 90k tbr, 90k tbn (attached pic)
+  Stream #0:1[0x2](und): Video: h264 (High) (avc1 / 0x31637661), yuv420p(progressive), 1920x1080 [SAR 1:1 DAR 16:9], 4400 kb/s, 29.97 fps, 29.97 tbr, 30k tbn (default)
+      Metadata:
+        handler_name    : VideoHandler
+  Stream #0:2[0x3](und): Audio: aac (LC) (mp4a / 0x6134706D), 48000 Hz, 5.1(side), fltp, 128 kb/s (default)
+At least one output file must be specified
+";
+
+        let (meta, tags) = parse_ffmpeg_header(stderr).unwrap();
+        assert_eq!(meta.container.as_deref(), Some("mov,mp4,m4a,3gp,3g2,mj2"));
+        assert_eq!(meta.video_codec.as_deref(), Some("h264"));
+        assert_eq!((meta.width, meta.height), (Some(1920), Some(1080)));
+        assert_eq!(meta.frame_rate, Some(29.97));
+        assert_eq!(meta.audio_codec.as_deref(), Some("aac"));
+        assert_eq!(meta.sample_rate, Some(48000));
+        assert_eq!(meta.channels, Some(6));
+        assert_eq!(meta.duration_secs, Some(12.51));
+        assert_eq!(meta.bit_rate, Some(4_500_000));
+        assert_eq!(tags.get("title").map(String::as_str), Some("Clip"));
+        assert!(!tags.contains_key("handler_name"));
+
+        assert!(
+            parse_ffmpeg_header("clip.mp4: Invalid data found when processing input").is_none()
+        );
+    }
+
+    #[test]
+    fn reads_audio_without_ffmpeg() {
+        // One second of 8 kHz 16-bit mono silence
+        let sample_rate: u32 = 8000;
+        let data_len: u32 = sample_rate * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        wav.resize(wav.len() + data_len as usize, 0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tone.wav");
+        std::fs::write(&path, wav).unwrap();
+
+        let metadata = read_media_metadata(&path, "tone.wav").unwrap();
+        let stream = metadata.stream.unwrap();
+        assert_eq!(metadata.kind, MediaKind::Audio);
+        assert_eq!(stream.container.as_deref(), Some("wav"));
+        assert_eq!(stream.sample_rate, Some(8000));
+        assert_eq!(stream.channels, Some(1));
+        let duration = stream.duration_secs.unwrap();
+        assert!((duration - 1.0).abs() < 0.01, "duration was {}", duration);
+    }
+}
@@ -2,15 +2,189 @@
 
 use base64::Engine as _;
 use objc2::class;
+use objc2::ffi::{NSInteger, NSUInteger};
 use objc2::msg_send;
 use objc2::rc::autoreleasepool;
-use objc2::runtime::{AnyObject, Bool};
-use objc2_foundation::{NSArray, NSPoint, NSSize, NSString};
+use objc2::runtime::{AnyClass, AnyObject, Bool, ClassBuilder, Sel};
+use objc2::sel;
+use objc2_foundation::{NSArray, NSPoint, NSRect, NSSize, NSString};
+use once_cell::sync::Lazy;
+use std::ffi::CStr;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Mutex;
 
+use crate::commands::NativeDragOperation;
+
+/// Most items drawn in a multi-item drag stack; the badge still counts the rest.
+const STACK_MAX_LAYERS: usize = 3;
+/// Edge length of each card in the stack, in points.
+const STACK_TILE: f64 = 96.0;
+/// Offset between stacked cards, in points.
+const STACK_SPREAD: f64 = 10.0;
+/// Room above and to the right of the stack for the count badge.
+const STACK_BADGE_MARGIN: f64 = 12.0;
+/// `NSCompositingOperationSourceOver`
+const COMPOSITE_SOURCE_OVER: usize = 2;
+
+type NSDragOperation = NSUInteger;
+const NS_DRAG_OPERATION_NONE: NSDragOperation = 0;
+const NS_DRAG_OPERATION_COPY: NSDragOperation = 1;
+const NS_DRAG_OPERATION_LINK: NSDragOperation = 2;
+const NS_DRAG_OPERATION_GENERIC: NSDragOperation = 4;
+const NS_DRAG_OPERATION_MOVE: NSDragOperation = 16;
+
+/// `NSEventModifierFlagOption`
+const NS_EVENT_MODIFIER_OPTION: NSUInteger = 1 << 19;
+/// `NSEventModifierFlagCommand`
+const NS_EVENT_MODIFIER_COMMAND: NSUInteger = 1 << 20;
+
+type DragEndHandler = Box<dyn FnOnce(Option<NativeDragOperation>) + Send>;
+
+/// Operations offered by the hinted drag in flight while no modifier is held.
+static SOURCE_OPERATION_MASK: AtomicUsize = AtomicUsize::new(NS_DRAG_OPERATION_NONE);
+/// Told the outcome of the hinted drag in flight; taken when it ends so it runs once.
+static DRAG_END_HANDLER: Lazy<Mutex<Option<DragEndHandler>>> = Lazy::new(|| Mutex::new(None));
+
+/// Dragging source for hinted drags, created on first use and kept for the life of
+/// the app since AppKit may still message it after the drag call returns. The webview
+/// stays the source for `auto` drags so their behavior is unchanged.
+static DRAG_SOURCE: AtomicPtr<AnyObject> = AtomicPtr::new(std::ptr::null_mut());
+
+static DRAG_SOURCE_CLASS: Lazy<&'static AnyClass> = Lazy::new(|| {
+    let superclass = class!(NSObject);
+    let name = CStr::from_bytes_with_nul(b"MarlinDragSource\0").expect("valid drag source name");
+    let mut decl = ClassBuilder::new(name, superclass).expect("create drag source class");
+
+    unsafe {
+        let legacy_mask_fn: extern "C-unwind" fn(_, _, _) -> NSDragOperation =
+            legacy_source_operation_mask;
+        decl.add_method(sel!(draggingSourceOperationMaskForLocal:), legacy_mask_fn);
+
+        let session_mask_fn: extern "C-unwind" fn(_, _, _, _) -> NSDragOperation =
+            session_source_operation_mask;
+        decl.add_method(
+            sel!(draggingSession:sourceOperationMaskForDraggingContext:),
+            session_mask_fn,
+        );
+
+        let legacy_ended_fn: extern "C-unwind" fn(_, _, _, _, _) = drag_ended;
+        decl.add_method(sel!(draggedImage:endedAt:operation:), legacy_ended_fn);
+
+        let session_ended_fn: extern "C-unwind" fn(_, _, _, _, _) = drag_ended;
+        decl.add_method(
+            sel!(draggingSession:endedAtPoint:operation:),
+            session_ended_fn,
+        );
+    }
+
+    decl.register()
+});
+
+extern "C-unwind" fn legacy_source_operation_mask(
+    _this: *mut AnyObject,
+    _sel: Sel,
+    _is_local: Bool,
+) -> NSDragOperation {
+    current_operation_mask()
+}
+
+extern "C-unwind" fn session_source_operation_mask(
+    _this: *mut AnyObject,
+    _sel: Sel,
+    _session: *mut AnyObject,
+    _context: NSInteger,
+) -> NSDragOperation {
+    current_operation_mask()
+}
+
+/// AppKit asks for the mask again whenever the modifiers change, so holding Option
+/// (copy), Command (move) or both (link) part-way through a hinted drag switches
+/// the operation the way it does in Finder. Without a modifier the drag offers
+/// what it was started with.
+fn current_operation_mask() -> NSDragOperation {
+    let flags: NSUInteger = unsafe { msg_send![class!(NSEvent), modifierFlags] };
+    let option = flags & NS_EVENT_MODIFIER_OPTION != 0;
+    let command = flags & NS_EVENT_MODIFIER_COMMAND != 0;
+    match (option, command) {
+        (true, true) => NS_DRAG_OPERATION_LINK,
+        (true, false) => NS_DRAG_OPERATION_COPY,
+        (false, true) => NS_DRAG_OPERATION_MOVE | NS_DRAG_OPERATION_GENERIC,
+        (false, false) => SOURCE_OPERATION_MASK.load(Ordering::SeqCst),
+    }
+}
+
+/// Shared by the legacy `draggedImage:` and the session-based end callbacks; AppKit
+/// may send either or both.
+extern "C-unwind" fn drag_ended(
+    _this: *mut AnyObject,
+    _sel: Sel,
+    _image_or_session: *mut AnyObject,
+    _point: NSPoint,
+    operation: NSDragOperation,
+) {
+    let handler = DRAG_END_HANDLER
+        .lock()
+        .ok()
+        .and_then(|mut slot| slot.take());
+    if let Some(handler) = handler {
+        if catch_unwind(AssertUnwindSafe(|| handler(performed_operation(operation)))).is_err() {
+            log::error!("native drag end handler panicked");
+        }
+    }
+}
+
+/// The shared hinted-drag source. Only called on the main thread.
+unsafe fn drag_source() -> *mut AnyObject {
+    let existing = DRAG_SOURCE.load(Ordering::SeqCst);
+    if !existing.is_null() {
+        return existing;
+    }
+    let created: *mut AnyObject = msg_send![*DRAG_SOURCE_CLASS, new];
+    DRAG_SOURCE.store(created, Ordering::SeqCst);
+    created
+}
+
+/// The operations a hinted drag offers, or `None` for `auto`.
+fn operation_mask(operation: NativeDragOperation) -> Option<NSDragOperation> {
+    match operation {
+        NativeDragOperation::Copy => Some(NS_DRAG_OPERATION_COPY),
+        // Finder and most apps report a move as the generic operation
+        NativeDragOperation::Move => Some(NS_DRAG_OPERATION_MOVE | NS_DRAG_OPERATION_GENERIC),
+        NativeDragOperation::Link => Some(NS_DRAG_OPERATION_LINK),
+        NativeDragOperation::Auto => None,
+    }
+}
+
+fn performed_operation(operation: NSDragOperation) -> Option<NativeDragOperation> {
+    if operation & NS_DRAG_OPERATION_COPY != 0 {
+        Some(NativeDragOperation::Copy)
+    } else if operation & (NS_DRAG_OPERATION_MOVE | NS_DRAG_OPERATION_GENERIC) != 0 {
+        Some(NativeDragOperation::Move)
+    } else if operation & NS_DRAG_OPERATION_LINK != 0 {
+        Some(NativeDragOperation::Link)
+    } else {
+        None
+    }
+}
+
+/// Start a drag of `paths` out of the app.
+///
+/// A single item (or a caller-supplied `preview_image` without per-item previews) uses
+/// that image as-is. For several items, the drag image is a stack of up to three cards
+/// built from `preview_images` (falling back to each file's icon) with a count badge,
+/// and the pasteboard gets one file URL per item so drop targets copy all of them.
+///
+/// Unless `drag_operation` is `auto`, the drag offers that operation (or whichever
+/// one the modifiers held mid-drag ask for) and `on_end` is called with what the
+/// drop target did once the drag finishes.
 pub fn start_native_drag(
     paths: Vec<String>,
     preview_image: Option<String>,
+    preview_images: Option<Vec<String>>,
     _drag_offset_y: Option<f64>,
+    drag_operation: NativeDragOperation,
+    on_end: impl FnOnce(Option<NativeDragOperation>) + Send + 'static,
 ) -> Result<(), String> {
     if paths.is_empty() {
         return Err("No paths provided".into());
@@ -57,8 +231,12 @@ pub fn start_native_drag(
             fromView: std::ptr::null_mut::<AnyObject>()
         ];
 
+        let stacked = paths.len() > 1 && (preview_images.is_some() || preview_image.is_none());
+
         // Create drag image
-        let drag_img: *mut AnyObject = if let Some(ref data_url) = preview_image {
+        let drag_img: *mut AnyObject = if stacked {
+            create_stacked_drag_image(&paths, preview_images.as_deref().unwrap_or(&[]))?
+        } else if let Some(ref data_url) = preview_image {
             match create_drag_image_from_data_url(data_url) {
                 Ok(img) => img,
                 Err(_) => create_drag_image_from_file_icon(&paths[0])?,
@@ -94,13 +272,28 @@ pub fn start_native_drag(
 
         // Clear and set pasteboard types
         let _: () = msg_send![pb, clearContents];
+
+        // One pasteboard item per file so modern drop targets receive every URL; the
+        // legacy filenames list below is still added for older receivers.
+        if paths.len() > 1 {
+            write_file_urls(pb, &paths)?;
+        }
+
         let nsfilenames_type = NSString::from_str("NSFilenamesPboardType");
         let types_array = NSArray::from_retained_slice(&[nsfilenames_type.clone()]);
-        let _: i64 = msg_send![
-            pb,
-            declareTypes: &*types_array,
-            owner: std::ptr::null_mut::<AnyObject>()
-        ];
+        if paths.len() > 1 {
+            let _: i64 = msg_send![
+                pb,
+                addTypes: &*types_array,
+                owner: std::ptr::null_mut::<AnyObject>()
+            ];
+        } else {
+            let _: i64 = msg_send![
+                pb,
+                declareTypes: &*types_array,
+                owner: std::ptr::null_mut::<AnyObject>()
+            ];
+        }
 
         // Create NSArray of file paths
         let path_strings: Vec<_> = paths.iter().map(|path| NSString::from_str(path)).collect();
@@ -125,6 +318,22 @@ pub fn start_native_drag(
             mouse_in_source.y + (final_image_size.height / 2.0), // Adjust for flipped coordinates
         );
 
+        // Hinted drags get their own source so they can restrict the operation mask
+        let drag_source: *mut AnyObject = match operation_mask(drag_operation) {
+            Some(mask) => {
+                let source = drag_source();
+                if source.is_null() {
+                    return Err("Failed to create drag source".into());
+                }
+                SOURCE_OPERATION_MASK.store(mask, Ordering::SeqCst);
+                if let Ok(mut slot) = DRAG_END_HANDLER.lock() {
+                    *slot = Some(Box::new(on_end));
+                }
+                source
+            }
+            None => source_view,
+        };
+
         // Use the deprecated but working dragImage method
         let slide_back = Bool::YES;
         let _: () = msg_send![
@@ -134,7 +343,7 @@ pub fn start_native_drag(
             offset: NSPoint::new(0.0, 0.0),
             event: current_event,
             pasteboard: pb,
-            source: source_view,
+            source: drag_source,
             slideBack: slide_back
         ];
 
@@ -142,6 +351,141 @@ pub fn start_native_drag(
     })
 }
 
+fn write_file_urls(pb: *mut AnyObject, paths: &[String]) -> Result<(), String> {
+    unsafe {
+        let urls: Vec<*mut AnyObject> = paths
+            .iter()
+            .map(|path| -> *mut AnyObject {
+                let path_nsstring = NSString::from_str(path);
+                msg_send![class!(NSURL), fileURLWithPath: &*path_nsstring]
+            })
+            .filter(|url| !url.is_null())
+            .collect();
+        if urls.len() != paths.len() {
+            return Err("Failed to create file URLs for drag".to_string());
+        }
+
+        let urls_array: *mut AnyObject =
+            msg_send![class!(NSArray), arrayWithObjects: urls.as_ptr(), count: urls.len()];
+        let success: Bool = msg_send![pb, writeObjects: urls_array];
+        if success.is_false() {
+            return Err("Failed to write file URLs to pasteboard".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Draw up to `STACK_MAX_LAYERS` cards, front item bottom-left, with a badge showing
+/// the total number of items in the top-right corner.
+fn create_stacked_drag_image(
+    paths: &[String],
+    previews: &[String],
+) -> Result<*mut AnyObject, String> {
+    let layers = paths.len().min(STACK_MAX_LAYERS);
+    let stack_edge = STACK_TILE + STACK_SPREAD * (layers - 1) as f64;
+    let canvas_size = NSSize::new(
+        stack_edge + STACK_BADGE_MARGIN,
+        stack_edge + STACK_BADGE_MARGIN,
+    );
+
+    // Resolve every layer before locking focus so an error can't leave it locked
+    let mut layer_images = Vec::with_capacity(layers);
+    for index in 0..layers {
+        let image = match previews.get(index) {
+            Some(data_url) => create_drag_image_from_data_url(data_url)
+                .or_else(|_| create_drag_image_from_file_icon(&paths[index]))?,
+            None => create_drag_image_from_file_icon(&paths[index])?,
+        };
+        layer_images.push(image);
+    }
+
+    unsafe {
+        let canvas: *mut AnyObject = msg_send![class!(NSImage), alloc];
+        if canvas.is_null() {
+            return Err("Failed to allocate NSImage".to_string());
+        }
+        let canvas: *mut AnyObject = msg_send![canvas, initWithSize: canvas_size];
+        if canvas.is_null() {
+            return Err("Failed to create drag canvas".to_string());
+        }
+
+        let _: () = msg_send![canvas, lockFocus];
+
+        // Back-most card first so the front item ends up on top
+        for (index, image) in layer_images.iter().enumerate().rev() {
+            let offset = STACK_SPREAD * index as f64;
+            let rect = fit_rect(*image, NSPoint::new(offset, offset), STACK_TILE);
+            let fraction = if index == 0 { 1.0 } else { 0.85 };
+            let _: () = msg_send![
+                *image,
+                drawInRect: rect,
+                fromRect: NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(0.0, 0.0)),
+                operation: COMPOSITE_SOURCE_OVER,
+                fraction: fraction
+            ];
+        }
+
+        draw_count_badge(paths.len(), canvas_size);
+
+        let _: () = msg_send![canvas, unlockFocus];
+        Ok(canvas)
+    }
+}
+
+/// Scale `image` to fit a `tile`-sized square at `origin`, keeping its aspect ratio.
+unsafe fn fit_rect(image: *mut AnyObject, origin: NSPoint, tile: f64) -> NSRect {
+    let size: NSSize = msg_send![image, size];
+    if size.width <= 0.0 || size.height <= 0.0 {
+        return NSRect::new(origin, NSSize::new(tile, tile));
+    }
+    let scale = (tile / size.width).min(tile / size.height);
+    let width = size.width * scale;
+    let height = size.height * scale;
+    NSRect::new(
+        NSPoint::new(
+            origin.x + (tile - width) / 2.0,
+            origin.y + (tile - height) / 2.0,
+        ),
+        NSSize::new(width, height),
+    )
+}
+
+/// Red pill with the item count, drawn into the currently focused image.
+unsafe fn draw_count_badge(count: usize, canvas_size: NSSize) {
+    let text = NSString::from_str(&count.to_string());
+    let font: *mut AnyObject = msg_send![class!(NSFont), boldSystemFontOfSize: 13.0f64];
+    let white: *mut AnyObject = msg_send![class!(NSColor), whiteColor];
+    let attributes: *mut AnyObject = msg_send![class!(NSMutableDictionary), dictionary];
+    let font_key = NSString::from_str("NSFont");
+    let color_key = NSString::from_str("NSColor");
+    let _: () = msg_send![attributes, setObject: font, forKey: &*font_key];
+    let _: () = msg_send![attributes, setObject: white, forKey: &*color_key];
+
+    let text_size: NSSize = msg_send![&*text, sizeWithAttributes: attributes];
+    let height = text_size.height + 6.0;
+    let width = (text_size.width + 14.0).max(height);
+    let badge = NSRect::new(
+        NSPoint::new(canvas_size.width - width, canvas_size.height - height),
+        NSSize::new(width, height),
+    );
+
+    let red: *mut AnyObject = msg_send![class!(NSColor), systemRedColor];
+    let _: () = msg_send![red, setFill];
+    let pill: *mut AnyObject = msg_send![
+        class!(NSBezierPath),
+        bezierPathWithRoundedRect: badge,
+        xRadius: height / 2.0,
+        yRadius: height / 2.0
+    ];
+    let _: () = msg_send![pill, fill];
+
+    let text_origin = NSPoint::new(
+        badge.origin.x + (width - text_size.width) / 2.0,
+        badge.origin.y + (height - text_size.height) / 2.0,
+    );
+    let _: () = msg_send![&*text, drawAtPoint: text_origin, withAttributes: attributes];
+}
+
 fn create_drag_image_from_data_url(data_url: &str) -> Result<*mut AnyObject, String> {
     unsafe {
         // Extract base64 data
@@ -0,0 +1,501 @@
+//! Applications that can open a file, for the "Open With" menu.
+//!
+//! macOS asks LaunchServices through NSWorkspace for every app registered for the
+//! file's type. Linux follows the XDG rules: `.desktop` files that list the file's
+//! MIME type, plus the defaults and associations in `mimeapps.list`. Either way the
+//! entry's `path` is what `open_path_with` expects back.
+
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationInfo {
+    pub name: String,
+    /// The `.app` bundle on macOS, the `.desktop` file on Linux
+    pub path: String,
+    /// PNG data URL on macOS; the `.desktop` file's icon name or path on Linux
+    pub icon: Option<String>,
+    /// The app that opens the file on a double click
+    pub is_default: bool,
+}
+
+#[cfg(target_os = "macos")]
+pub fn applications_for(path: &Path, icon_size: u32) -> Result<Vec<ApplicationInfo>, String> {
+    use objc2::rc::autoreleasepool;
+    use objc2::runtime::{AnyObject, Bool};
+    use objc2::{class, msg_send, sel};
+    use objc2_foundation::NSString;
+
+    unsafe fn url_path(url: *mut AnyObject) -> Option<String> {
+        if url.is_null() {
+            return None;
+        }
+        let path: *mut AnyObject = msg_send![url, path];
+        crate::commands::nsstring_to_string(path)
+    }
+
+    let _scope_guard = crate::macos_security::retain_access(path)?;
+    let (default_app, handlers) = autoreleasepool(|_| unsafe {
+        let ns_path = NSString::from_str(&path.to_string_lossy());
+        let url: *mut AnyObject = msg_send![class!(NSURL), fileURLWithPath: &*ns_path];
+        if url.is_null() {
+            return Err(format!(
+                "Failed to create NSURL for path: {}",
+                path.display()
+            ));
+        }
+        let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+        if workspace.is_null() {
+            return Err("NSWorkspace unavailable".to_string());
+        }
+        let default_url: *mut AnyObject = msg_send![workspace, URLForApplicationToOpenURL: url];
+        let default_app = url_path(default_url);
+
+        // Listing every handler needs macOS 12; older systems only get the default
+        let mut handlers = Vec::new();
+        let can_list: Bool = msg_send![
+            workspace,
+            respondsToSelector: sel!(URLsForApplicationsToOpenURL:)
+        ];
+        if can_list.as_bool() {
+            let urls: *mut AnyObject = msg_send![workspace, URLsForApplicationsToOpenURL: url];
+            if !urls.is_null() {
+                let count: usize = msg_send![urls, count];
+                for i in 0..count {
+                    let app_url: *mut AnyObject = msg_send![urls, objectAtIndex: i];
+                    handlers.extend(url_path(app_url));
+                }
+            }
+        }
+
+        let file_manager: *mut AnyObject = msg_send![class!(NSFileManager), defaultManager];
+        let mut named = Vec::new();
+        for app in default_app.iter().chain(&handlers) {
+            if named
+                .iter()
+                .any(|(existing, _): &(String, String)| existing == app)
+            {
+                continue;
+            }
+            // "Safari" rather than "Safari.app", localized like Finder shows it
+            let ns_app = NSString::from_str(app);
+            let display: *mut AnyObject = msg_send![file_manager, displayNameAtPath: &*ns_app];
+            let name = crate::commands::nsstring_to_string(display).unwrap_or_else(|| {
+                Path::new(app)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| app.clone())
+            });
+            named.push((app.clone(), name));
+        }
+        Ok((default_app, named))
+    })?;
+
+    let mut apps: Vec<ApplicationInfo> = handlers
+        .into_iter()
+        .map(|(app, name)| ApplicationInfo {
+            icon: crate::macos_icons::app_icon_png_base64(&app, icon_size).ok(),
+            is_default: default_app.as_deref() == Some(app.as_str()),
+            name,
+            path: app,
+        })
+        .collect();
+    apps.sort_by_key(|app| (!app.is_default, app.name.to_lowercase()));
+    Ok(apps)
+}
+
+#[cfg(target_os = "linux")]
+pub use linux::{applications_for, launch};
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn applications_for(_path: &Path, _icon_size: u32) -> Result<Vec<ApplicationInfo>, String> {
+    Err("Listing applications is only supported on macOS and Linux".to_string())
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use walkdir::WalkDir;
+
+    use super::ApplicationInfo;
+
+    /// The parts of a `.desktop` file that matter for opening files
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct DesktopEntry {
+        pub name: String,
+        pub exec: String,
+        pub icon: Option<String>,
+        pub mime_types: Vec<String>,
+        /// Deleted by the user; also hides entries of the same id in later dirs
+        pub hidden: bool,
+    }
+
+    /// Associations for one MIME type gathered from `mimeapps.list` files
+    #[derive(Debug, Default)]
+    pub struct MimeApps {
+        pub defaults: Vec<String>,
+        pub added: Vec<String>,
+        pub removed: HashSet<String>,
+    }
+
+    fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+        value
+            .split(';')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    }
+
+    /// The `[Desktop Entry]` group of a `.desktop` file, or `None` when it isn't an
+    /// application that can be launched.
+    pub fn parse_desktop_entry(contents: &str) -> Option<DesktopEntry> {
+        let mut in_entry = false;
+        let mut fields = HashMap::new();
+        for line in contents.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+                continue;
+            }
+            if !in_entry || line.starts_with('#') {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                fields.entry(key.trim()).or_insert(value.trim());
+            }
+        }
+        if fields.get("Type") != Some(&"Application") {
+            return None;
+        }
+        Some(DesktopEntry {
+            name: fields.get("Name")?.to_string(),
+            exec: fields.get("Exec")?.to_string(),
+            icon: fields.get("Icon").map(|icon| icon.to_string()),
+            mime_types: fields
+                .get("MimeType")
+                .map(|types| split_list(types).collect())
+                .unwrap_or_default(),
+            hidden: fields.get("Hidden") == Some(&"true"),
+        })
+    }
+
+    /// Add the entries for `mime` from one `mimeapps.list`. Files are read from most
+    /// to least important, so earlier entries keep their place.
+    pub fn read_mime_apps(contents: &str, mime: &str, into: &mut MimeApps) {
+        let mut section = "";
+        for line in contents.lines().map(str::trim) {
+            if line.starts_with('[') {
+                section = line;
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            if key.trim() != mime {
+                continue;
+            }
+            let list = match section {
+                "[Default Applications]" => &mut into.defaults,
+                "[Added Associations]" => &mut into.added,
+                "[Removed Associations]" => {
+                    into.removed.extend(split_list(value));
+                    continue;
+                }
+                _ => continue,
+            };
+            for id in split_list(value) {
+                if !list.contains(&id) {
+                    list.push(id);
+                }
+            }
+        }
+    }
+
+    /// Arguments for running an `Exec` line on `file`: double quotes group words,
+    /// `%f`/`%F`/`%u`/`%U` become the file, `%i` becomes `--icon <icon>` (or nothing
+    /// without an icon), other field codes are dropped, and the file is appended when
+    /// the line doesn't say where it goes.
+    pub fn exec_command(exec: &str, icon: Option<&str>, file: &Path) -> Vec<String> {
+        let mut words = Vec::new();
+        let mut current = String::new();
+        let mut in_word = false;
+        let mut in_quotes = false;
+        let mut chars = exec.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    in_quotes = !in_quotes;
+                    in_word = true;
+                }
+                '\\' if in_quotes => current.extend(chars.next()),
+                c if c.is_whitespace() && !in_quotes => {
+                    if in_word {
+                        words.push(std::mem::take(&mut current));
+                        in_word = false;
+                    }
+                }
+                c => {
+                    current.push(c);
+                    in_word = true;
+                }
+            }
+        }
+        if in_word {
+            words.push(current);
+        }
+
+        let file = file.to_string_lossy();
+        let mut used_file = false;
+        let mut args = Vec::new();
+        for word in words {
+            if word == "%i" {
+                if let Some(icon) = icon {
+                    args.push("--icon".to_string());
+                    args.push(icon.to_string());
+                }
+                continue;
+            }
+            if matches!(word.as_str(), "%c" | "%k") {
+                continue;
+            }
+            let mut arg = String::new();
+            let mut chars = word.chars();
+            while let Some(c) = chars.next() {
+                if c != '%' {
+                    arg.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('%') => arg.push('%'),
+                    Some('f' | 'F' | 'u' | 'U') => {
+                        arg.push_str(&file);
+                        used_file = true;
+                    }
+                    _ => {}
+                }
+            }
+            args.push(arg);
+        }
+        if !used_file {
+            args.push(file.into_owned());
+        }
+        args
+    }
+
+    fn mime_type(path: &Path) -> String {
+        if path.is_dir() {
+            return "inode/directory".to_string();
+        }
+        mime_guess::from_path(path)
+            .first_raw()
+            .unwrap_or("application/octet-stream")
+            .to_string()
+    }
+
+    /// `$XDG_DATA_HOME` then `$XDG_DATA_DIRS`
+    fn data_dirs() -> Vec<PathBuf> {
+        let system = std::env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+        dirs::data_dir()
+            .into_iter()
+            .chain(
+                system
+                    .split(':')
+                    .filter(|d| !d.is_empty())
+                    .map(PathBuf::from),
+            )
+            .collect()
+    }
+
+    /// `mimeapps.list` files, most important first
+    fn mime_apps_lists() -> Vec<PathBuf> {
+        let system_config = std::env::var("XDG_CONFIG_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/etc/xdg".to_string());
+        let config_dirs = dirs::config_dir().into_iter().chain(
+            system_config
+                .split(':')
+                .filter(|d| !d.is_empty())
+                .map(PathBuf::from),
+        );
+        config_dirs
+            .map(|dir| dir.join("mimeapps.list"))
+            .chain(
+                data_dirs()
+                    .into_iter()
+                    .map(|dir| dir.join("applications/mimeapps.list")),
+            )
+            .collect()
+    }
+
+    /// Every `.desktop` file by desktop id ("org.gnome.gedit.desktop"; subfolders
+    /// join with '-'). Earlier data dirs override later ones.
+    fn desktop_entries() -> HashMap<String, (PathBuf, DesktopEntry)> {
+        let mut entries = HashMap::new();
+        for dir in data_dirs() {
+            let apps_dir = dir.join("applications");
+            for file in WalkDir::new(&apps_dir).into_iter().filter_map(Result::ok) {
+                let path = file.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("desktop") {
+                    continue;
+                }
+                let Ok(relative) = path.strip_prefix(&apps_dir) else {
+                    continue;
+                };
+                let id = relative.to_string_lossy().replace('/', "-");
+                if entries.contains_key(&id) {
+                    continue;
+                }
+                let Ok(contents) = fs::read_to_string(path) else {
+                    continue;
+                };
+                if let Some(entry) = parse_desktop_entry(&contents) {
+                    entries.insert(id, (path.to_path_buf(), entry));
+                }
+            }
+        }
+        entries
+    }
+
+    pub fn applications_for(path: &Path, _icon_size: u32) -> Result<Vec<ApplicationInfo>, String> {
+        let mime = mime_type(path);
+        let entries = desktop_entries();
+        let mut associations = MimeApps::default();
+        for list in mime_apps_lists() {
+            if let Ok(contents) = fs::read_to_string(&list) {
+                read_mime_apps(&contents, &mime, &mut associations);
+            }
+        }
+
+        let usable = |id: &String| entries.get(id).is_some_and(|(_, entry)| !entry.hidden);
+        let default_id = associations.defaults.iter().find(|id| usable(*id)).cloned();
+        let mut declared: Vec<&String> = entries
+            .iter()
+            .filter(|(_, (_, entry))| entry.mime_types.contains(&mime))
+            .map(|(id, _)| id)
+            .collect();
+        declared.sort();
+        let candidates = default_id.iter().chain(&associations.added).chain(
+            declared
+                .into_iter()
+                .filter(|id| !associations.removed.contains(*id)),
+        );
+
+        let mut seen = HashSet::new();
+        let mut apps = Vec::new();
+        for id in candidates {
+            if !usable(id) || !seen.insert(id) {
+                continue;
+            }
+            let (desktop_file, entry) = &entries[id];
+            apps.push(ApplicationInfo {
+                name: entry.name.clone(),
+                path: desktop_file.to_string_lossy().to_string(),
+                icon: entry.icon.clone(),
+                is_default: default_id.as_ref() == Some(id),
+            });
+        }
+        apps.sort_by_key(|app| (!app.is_default, app.name.to_lowercase()));
+        Ok(apps)
+    }
+
+    /// Run the application of the `.desktop` file at `desktop_file` on `file`.
+    pub fn launch(desktop_file: &Path, file: &Path) -> Result<(), String> {
+        let contents = fs::read_to_string(desktop_file)
+            .map_err(|e| format!("Failed to read {}: {}", desktop_file.display(), e))?;
+        let entry = parse_desktop_entry(&contents)
+            .ok_or_else(|| format!("{} is not an application", desktop_file.display()))?;
+        let args = exec_command(&entry.exec, entry.icon.as_deref(), file);
+        let (program, rest) = args
+            .split_first()
+            .ok_or_else(|| format!("{} has no command to run", desktop_file.display()))?;
+        Command::new(program)
+            .args(rest)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to launch application: {}", e))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn parses_desktop_entries() {
+            let contents = "\
+[Desktop Entry]
+Type=Application
+Name=Text Editor
+Name[de]=Texteditor
+Exec=gedit %U
+Icon=org.gnome.gedit
+MimeType=text/plain;text/markdown;
+
+[Desktop Action new-window]
+Name=New Window
+Exec=gedit --new-window
+";
+            let entry = parse_desktop_entry(contents).unwrap();
+            assert_eq!(entry.name, "Text Editor");
+            assert_eq!(entry.exec, "gedit %U");
+            assert_eq!(entry.icon.as_deref(), Some("org.gnome.gedit"));
+            assert_eq!(entry.mime_types, ["text/plain", "text/markdown"]);
+            assert!(!entry.hidden);
+
+            assert!(parse_desktop_entry("[Desktop Entry]\nType=Link\nName=x\nExec=x").is_none());
+        }
+
+        #[test]
+        fn reads_mime_associations_in_priority_order() {
+            let mut apps = MimeApps::default();
+            read_mime_apps(
+                "[Default Applications]\ntext/plain=code.desktop\n\
+                 [Added Associations]\ntext/plain=code.desktop;vim.desktop;\n\
+                 [Removed Associations]\ntext/plain=nano.desktop\nimage/png=gimp.desktop",
+                "text/plain",
+                &mut apps,
+            );
+            read_mime_apps(
+                "[Default Applications]\ntext/plain=gedit.desktop",
+                "text/plain",
+                &mut apps,
+            );
+            assert_eq!(apps.defaults, ["code.desktop", "gedit.desktop"]);
+            assert_eq!(apps.added, ["code.desktop", "vim.desktop"]);
+            assert!(apps.removed.contains("nano.desktop"));
+            assert!(!apps.removed.contains("gimp.desktop"));
+        }
+
+        #[test]
+        fn fills_in_exec_field_codes() {
+            let file = Path::new("/home/me/My Notes.txt");
+            assert_eq!(
+                exec_command("gedit %U", None, file),
+                ["gedit", "/home/me/My Notes.txt"]
+            );
+            assert_eq!(
+                exec_command(r#""/opt/My App/app" %i --file=%f"#, Some("my-app"), file),
+                [
+                    "/opt/My App/app",
+                    "--icon",
+                    "my-app",
+                    "--file=/home/me/My Notes.txt"
+                ]
+            );
+            assert_eq!(
+                exec_command(r#""/opt/My App/app" %i --file=%f"#, None, file),
+                ["/opt/My App/app", "--file=/home/me/My Notes.txt"]
+            );
+            assert_eq!(
+                exec_command("xterm -e vim", None, file),
+                ["xterm", "-e", "vim", "/home/me/My Notes.txt"]
+            );
+        }
+    }
+}
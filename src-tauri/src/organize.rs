@@ -0,0 +1,190 @@
+//! Sorting the files of one folder into subfolders, e.g. cleaning up Downloads.
+//!
+//! Only regular, non-hidden files directly inside the folder are moved; existing
+//! subfolders (including ones created by an earlier run) are left alone, so
+//! organizing twice doesn't reshuffle anything.
+
+use crate::file_plan::MoveRequest;
+use crate::media_metadata::{media_kind_for_extension, read_media_metadata, MediaKind};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum OrganizeStrategy {
+    /// `Images/`, `Documents/`, ... by file extension.
+    ByExtension,
+    /// `YYYY/MM/` from the EXIF capture date for photos, otherwise the modification time.
+    ByDate,
+    /// `A/`, `B/`, ..., with `0-9/` for digits and `#/` for everything else.
+    ByFirstLetter,
+}
+
+/// Folder name for a file extension (lowercase, without the dot).
+pub fn category_for_extension(ext: &str) -> &'static str {
+    match ext {
+        "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "tiff" | "tif" | "heic" | "heif"
+        | "avif" | "svg" | "ico" | "raw" | "cr2" | "nef" | "arw" | "dng" | "psd" => "Images",
+        "pdf" | "doc" | "docx" | "odt" | "rtf" | "txt" | "md" | "pages" | "xls" | "xlsx"
+        | "ods" | "csv" | "numbers" | "ppt" | "pptx" | "odp" | "key" | "epub" => "Documents",
+        "mp3" | "wav" | "flac" | "aac" | "m4a" | "ogg" | "opus" | "aiff" | "wma" => "Audio",
+        "mp4" | "mov" | "mkv" | "avi" | "webm" | "m4v" | "wmv" | "flv" => "Videos",
+        "zip" | "rar" | "7z" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" => "Archives",
+        "dmg" | "pkg" | "exe" | "msi" | "deb" | "rpm" | "appimage" | "iso" => "Installers",
+        _ => "Other",
+    }
+}
+
+/// `YYYY/MM` from a photo's EXIF capture date, if it has a usable one.
+fn capture_month(path: &Path, name: &str, ext: &str) -> Option<String> {
+    if media_kind_for_extension(ext) != Some(MediaKind::Image) {
+        return None;
+    }
+    // `captured_at` is normalized to "YYYY-MM-DDTHH:MM:SS"
+    let captured = read_media_metadata(path, name).ok()?.image?.captured_at?;
+    let (year, month) = (captured.get(0..4)?, captured.get(5..7)?);
+    let valid = year.bytes().all(|b| b.is_ascii_digit())
+        && year != "0000"
+        && matches!(month.parse::<u8>(), Ok(1..=12));
+    valid.then(|| format!("{}/{}", year, month))
+}
+
+fn first_letter_folder(name: &str) -> String {
+    match name.chars().next() {
+        Some(c) if c.is_ascii_digit() => "0-9".to_string(),
+        Some(c) if c.is_alphabetic() => c.to_uppercase().collect(),
+        _ => "#".to_string(),
+    }
+}
+
+/// Build the move requests for organizing `dir` with `strategy`. `categories` maps
+/// extensions (without the dot, any case) to folder names and takes precedence over
+/// the built-in [`category_for_extension`] table.
+pub fn plan_requests(
+    dir: &Path,
+    strategy: OrganizeStrategy,
+    categories: &HashMap<String, String>,
+) -> Result<Vec<MoveRequest>, String> {
+    if !dir.is_dir() {
+        return Err(format!("Not a directory: {}", dir.display()));
+    }
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+    let mut overrides = HashMap::new();
+    for (ext, folder) in categories {
+        let folder = folder.trim();
+        if folder.is_empty() {
+            continue;
+        }
+        // Each category is one folder inside `dir`, never a path out of it
+        if folder == "." || folder == ".." || folder.contains(['/', '\\']) {
+            return Err(format!("Invalid category folder name: {}", folder));
+        }
+        overrides.insert(ext.trim_start_matches('.').to_lowercase(), folder);
+    }
+
+    let mut requests = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !file_type.is_file() || name.starts_with('.') {
+            continue;
+        }
+        let source = entry.path();
+        let ext = source
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let folder = match strategy {
+            OrganizeStrategy::ByExtension => overrides
+                .get(&ext)
+                .copied()
+                .unwrap_or_else(|| category_for_extension(&ext))
+                .to_string(),
+            OrganizeStrategy::ByDate => match capture_month(&source, &name, &ext) {
+                Some(month) => month,
+                None => {
+                    let Ok(modified) = entry.metadata().and_then(|m| m.modified()) else {
+                        continue;
+                    };
+                    let modified: DateTime<Local> = modified.into();
+                    modified.format("%Y/%m").to_string()
+                }
+            },
+            OrganizeStrategy::ByFirstLetter => first_letter_folder(&name),
+        };
+        requests.push(MoveRequest {
+            destination: dir.join(folder).join(&name),
+            source,
+        });
+    }
+    requests.sort_by(|a, b| a.source.cmp(&b.source));
+    Ok(requests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn planned(root: &Path, requests: &[MoveRequest]) -> Vec<String> {
+        requests
+            .iter()
+            .map(|r| {
+                let rel = r.destination.strip_prefix(root).unwrap();
+                rel.to_string_lossy().replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn groups_top_level_files_by_type() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        fs::write(root.join("photo.JPG"), "").unwrap();
+        fs::write(root.join("notes.pdf"), "").unwrap();
+        fs::write(root.join("scene.blend"), "").unwrap();
+        fs::write(root.join(".hidden"), "").unwrap();
+        fs::create_dir(root.join("Images")).unwrap();
+        fs::write(root.join("Images/old.png"), "").unwrap();
+
+        let categories = HashMap::from([(".BLEND".to_string(), "3D".to_string())]);
+        let requests = plan_requests(root, OrganizeStrategy::ByExtension, &categories).unwrap();
+        assert_eq!(
+            planned(root, &requests),
+            ["Documents/notes.pdf", "Images/photo.JPG", "3D/scene.blend"]
+        );
+
+        for folder in [".", "..", "../Elsewhere", "a/b", "a\\b"] {
+            let categories = HashMap::from([("pdf".to_string(), folder.to_string())]);
+            assert!(
+                plan_requests(root, OrganizeStrategy::ByExtension, &categories).is_err(),
+                "{folder}"
+            );
+        }
+    }
+
+    #[test]
+    fn groups_by_first_letter() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path();
+        for name in ["apple.txt", "Banana.txt", "2024 report.pdf", "_draft.md"] {
+            fs::write(root.join(name), "").unwrap();
+        }
+
+        let requests =
+            plan_requests(root, OrganizeStrategy::ByFirstLetter, &HashMap::new()).unwrap();
+        assert_eq!(
+            planned(root, &requests),
+            [
+                "0-9/2024 report.pdf",
+                "B/Banana.txt",
+                "#/_draft.md",
+                "A/apple.txt"
+            ]
+        );
+    }
+}
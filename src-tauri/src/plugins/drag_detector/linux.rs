@@ -1,11 +1,24 @@
+use gtk::gdk;
+use gtk::prelude::*;
 use tauri::{Runtime, Window};
 
-use super::DropZoneConfig;
+use super::window_events;
+use super::DragModifiers;
 
-pub fn setup_drag_handlers<R: Runtime>(_window: &Window<R>) -> Result<(), String> {
-    Err("Linux drag detection not yet implemented".to_string())
+pub fn setup_drag_handlers<R: Runtime>(window: &Window<R>) -> Result<(), String> {
+    window_events::install(window, current_modifiers);
+    Ok(())
 }
 
-pub fn set_drop_zone(_zone_id: &str, _enabled: bool, _config: Option<DropZoneConfig>) {
-    // TODO: Implement Linux drag detection using GTK drag-and-drop
+/// Keyboard modifiers held right now; drag events run on the GTK main thread.
+fn current_modifiers() -> DragModifiers {
+    let state = gdk::Display::default()
+        .and_then(|display| gdk::Keymap::for_display(&display))
+        .map(|keymap| gdk::ModifierType::from_bits_truncate(keymap.modifier_state()))
+        .unwrap_or_else(gdk::ModifierType::empty);
+
+    DragModifiers {
+        option_alt: state.contains(gdk::ModifierType::MOD1_MASK),
+        cmd_ctrl: state.contains(gdk::ModifierType::CONTROL_MASK),
+    }
 }
@@ -14,13 +14,17 @@ use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::sync::{Arc, Mutex};
 use tauri::{Emitter, Runtime, Window};
 
-use super::{DragDropEvent, DragEventType, DragModifiers, DropLocation, DropZoneConfig};
+use super::zones::{is_target_enabled, resolve_target};
+use super::{DragDropEvent, DragEventType, DragModifiers, DropLocation};
 
 type DragHandler = dyn Fn(DragDropEvent) + 'static;
 
 type NSDragOperation = NSUInteger;
 const NS_DRAG_OPERATION_NONE: NSDragOperation = 0;
 const NS_DRAG_OPERATION_COPY: NSDragOperation = 1;
+const NS_DRAG_OPERATION_LINK: NSDragOperation = 2;
+const NS_DRAG_OPERATION_GENERIC: NSDragOperation = 4;
+const NS_DRAG_OPERATION_MOVE: NSDragOperation = 16;
 
 const NS_EVENT_MODIFIER_FLAG_OPTION: NSUInteger = 1 << 19;
 const NS_EVENT_MODIFIER_FLAG_COMMAND: NSUInteger = 1 << 20;
@@ -30,14 +34,6 @@ type Id = *mut AnyObject;
 // Static CStr for ivar name to avoid repeated parsing on hot path
 static IVAR_NAME: &CStr = unsafe { CStr::from_bytes_with_nul_unchecked(b"event_handler_ptr\0") };
 
-#[derive(Debug, Clone, Default)]
-struct DropZoneState {
-    enabled: bool,
-    width: Option<f64>,
-}
-
-static DROP_ZONES: Lazy<Mutex<HashMap<String, DropZoneState>>> =
-    Lazy::new(|| Mutex::new(HashMap::new()));
 static INITIALIZED_WINDOWS: Lazy<Mutex<HashMap<String, bool>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
@@ -112,7 +108,7 @@ extern "C-unwind" fn dragging_entered(this: Id, _sel: Sel, sender: Id) -> NSDrag
         autoreleasepool(|_| unsafe {
             let (event, target_id) = compose_event(sender, DragEventType::DragEnter);
             emit_event(&*this, event);
-            drag_operation_for_target(&target_id)
+            drag_operation_for_target(&target_id, sender)
         })
     })) {
         Ok(op) => op,
@@ -128,7 +124,7 @@ extern "C-unwind" fn dragging_updated(this: Id, _sel: Sel, sender: Id) -> NSDrag
         autoreleasepool(|_| unsafe {
             let (event, target_id) = compose_event(sender, DragEventType::DragOver);
             emit_event(&*this, event);
-            drag_operation_for_target(&target_id)
+            drag_operation_for_target(&target_id, sender)
         })
     })) {
         Ok(op) => op,
@@ -244,50 +240,22 @@ unsafe fn get_drag_modifiers() -> DragModifiers {
     }
 }
 
-fn resolve_target(point: NSPoint) -> Option<String> {
-    if point.x < 0.0 || point.y < 0.0 {
-        return None;
-    }
-
-    let zones = DROP_ZONES.lock().unwrap();
-
-    if let Some(zone) = zones.get("sidebar") {
-        if zone.enabled {
-            let width = zone.width.unwrap_or(280.0);
-            // Allow a small tolerance to smooth minor rounding differences.
-            if point.x <= width + 1.0 {
-                return Some("sidebar".to_string());
-            }
-        }
-    }
-
-    if let Some(zone) = zones.get("file-panel") {
-        if zone.enabled {
-            return Some("file-panel".to_string());
-        }
-    }
-
-    None
-}
-
-fn drag_operation_for_target(target: &Option<String>) -> NSDragOperation {
-    if is_target_enabled(target) {
-        NS_DRAG_OPERATION_COPY
-    } else {
-        NS_DRAG_OPERATION_NONE
-    }
-}
-
-fn is_target_enabled(target: &Option<String>) -> bool {
-    match target {
-        Some(id) => DROP_ZONES
-            .lock()
-            .unwrap()
-            .get(id)
-            .map(|zone| zone.enabled)
-            .unwrap_or(false),
-        None => false,
+/// Prefer copying, but accept whatever the source allows so drags limited to
+/// moving or linking aren't refused.
+unsafe fn drag_operation_for_target(target: &Option<String>, sender: Id) -> NSDragOperation {
+    if !is_target_enabled(target) {
+        return NS_DRAG_OPERATION_NONE;
     }
+    let allowed: NSDragOperation = msg_send![sender, draggingSourceOperationMask];
+    [
+        NS_DRAG_OPERATION_COPY,
+        NS_DRAG_OPERATION_MOVE,
+        NS_DRAG_OPERATION_GENERIC,
+        NS_DRAG_OPERATION_LINK,
+    ]
+    .into_iter()
+    .find(|operation| allowed & operation != 0)
+    .unwrap_or(NS_DRAG_OPERATION_NONE)
 }
 
 fn emit_event(this: &AnyObject, event: DragDropEvent) {
@@ -312,7 +280,7 @@ unsafe fn compose_event(sender: Id, event_type: DragEventType) -> (DragDropEvent
     let paths = get_dragged_paths(sender);
     let target_id = match event_type {
         DragEventType::DragLeave => None,
-        _ => resolve_target(point),
+        _ => resolve_target(point.x, point.y),
     };
     let modifiers = get_drag_modifiers();
 
@@ -424,12 +392,3 @@ pub fn setup_drag_handlers<R: Runtime>(window: &Window<R>) -> Result<(), String>
         .map(|guard| guard.clone())
         .map_err(|_| "Failed to acquire drag detector result".to_string())?
 }
-
-pub fn set_drop_zone(zone_id: &str, enabled: bool, config: Option<DropZoneConfig>) {
-    let mut zones = DROP_ZONES.lock().unwrap();
-    let entry = zones.entry(zone_id.to_string()).or_default();
-    entry.enabled = enabled;
-    if let Some(cfg) = config {
-        entry.width = cfg.width;
-    }
-}
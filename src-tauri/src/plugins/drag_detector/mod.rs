@@ -10,6 +10,11 @@ mod windows;
 #[cfg(target_os = "linux")]
 mod linux;
 
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+mod window_events;
+
+mod zones;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DropLocation {
@@ -50,6 +55,11 @@ pub async fn enable_drag_detection<R: Runtime>(window: Window<R>) -> Result<(),
         macos::setup_drag_handlers(&window)?;
     }
 
+    #[cfg(target_os = "windows")]
+    {
+        windows::setup_drag_handlers(&window)?;
+    }
+
     #[cfg(target_os = "linux")]
     {
         linux::setup_drag_handlers(&window)?;
@@ -69,25 +79,9 @@ pub struct DropZoneConfig {
 pub async fn set_drop_zone(
     zone_id: String,
     enabled: bool,
-    #[allow(unused_variables)] config: Option<DropZoneConfig>,
+    config: Option<DropZoneConfig>,
 ) -> Result<(), String> {
-    #[cfg(target_os = "macos")]
-    {
-        macos::set_drop_zone(&zone_id, enabled, config);
-    }
-
-    #[cfg(target_os = "linux")]
-    {
-        linux::set_drop_zone(&zone_id, enabled, config);
-    }
-
-    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-    {
-        let _ = zone_id;
-        let _ = enabled;
-        let _ = config;
-    }
-
+    zones::set_drop_zone(&zone_id, enabled, config);
     Ok(())
 }
 
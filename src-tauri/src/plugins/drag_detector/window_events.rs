@@ -0,0 +1,125 @@
+//! Drag detection built on the window's own drag-drop events, which wry backs with
+//! the WebView2 drop target on Windows and GTK drag-dest signals on Linux.
+//!
+//! Events are reshaped to match the macOS overlay: logical pixels with a
+//! bottom-left origin, and the dragged paths repeated on every event even
+//! though the platform only reports them on enter and drop.
+
+use once_cell::sync::Lazy;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{
+    DragDropEvent as WindowDragDrop, Emitter, PhysicalPosition, Runtime, Window, WindowEvent,
+};
+
+use super::zones::{is_target_enabled, resolve_target};
+use super::{DragDropEvent, DragEventType, DragModifiers, DropLocation};
+
+static INITIALIZED_WINDOWS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+pub fn install<R: Runtime>(window: &Window<R>, modifiers: fn() -> DragModifiers) {
+    if !INITIALIZED_WINDOWS
+        .lock()
+        .unwrap()
+        .insert(window.label().to_string())
+    {
+        return;
+    }
+
+    let dragged_paths: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let emit_window = window.clone();
+
+    window.on_window_event(move |event| {
+        let WindowEvent::DragDrop(drag) = event else {
+            return;
+        };
+
+        let (event_type, position) = match drag {
+            WindowDragDrop::Enter { paths, position } => {
+                *dragged_paths.lock().unwrap() = path_strings(paths);
+                (DragEventType::DragEnter, Some(*position))
+            }
+            WindowDragDrop::Over { position } => (DragEventType::DragOver, Some(*position)),
+            WindowDragDrop::Drop { paths, position } => {
+                *dragged_paths.lock().unwrap() = path_strings(paths);
+                (DragEventType::Drop, Some(*position))
+            }
+            WindowDragDrop::Leave => (DragEventType::DragLeave, None),
+            _ => return,
+        };
+
+        let (x, y) = position
+            .map(|position| to_overlay_point(&emit_window, position))
+            .unwrap_or((0.0, 0.0));
+        let target_id = match event_type {
+            DragEventType::DragLeave => None,
+            _ => resolve_target(x, y),
+        };
+        if matches!(event_type, DragEventType::Drop) && !is_target_enabled(&target_id) {
+            dragged_paths.lock().unwrap().clear();
+            return;
+        }
+
+        let paths = match event_type {
+            DragEventType::Drop | DragEventType::DragLeave => {
+                std::mem::take(&mut *dragged_paths.lock().unwrap())
+            }
+            _ => dragged_paths.lock().unwrap().clone(),
+        };
+
+        log::debug!(
+            "drag detector event: {:?} target={:?} ({}, {})",
+            event_type,
+            target_id,
+            x,
+            y
+        );
+
+        let event = DragDropEvent {
+            paths,
+            location: DropLocation { x, y, target_id },
+            event_type,
+            modifiers: modifiers(),
+        };
+        if let Err(err) = emit_window.emit("drag-drop-event", event) {
+            log::warn!("Failed to emit drag-drop-event: {err}");
+        }
+    });
+}
+
+fn path_strings(paths: &[PathBuf]) -> Vec<String> {
+    paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect()
+}
+
+/// Convert a physical, top-left based position into the overlay's coordinate space.
+fn to_overlay_point<R: Runtime>(window: &Window<R>, position: PhysicalPosition<f64>) -> (f64, f64) {
+    let scale = window.scale_factor().unwrap_or(1.0);
+    let height = window
+        .inner_size()
+        .map(|size| size.to_logical::<f64>(scale).height)
+        .ok();
+    overlay_point(position, scale, height)
+}
+
+/// `height` is the window's logical height; without it the point lands on the bottom edge.
+fn overlay_point(position: PhysicalPosition<f64>, scale: f64, height: Option<f64>) -> (f64, f64) {
+    let logical = position.to_logical::<f64>(scale);
+    (logical.x, height.unwrap_or(logical.y) - logical.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_are_flipped_into_logical_overlay_points() {
+        let position = PhysicalPosition::new(200.0, 100.0);
+        assert_eq!(overlay_point(position, 2.0, Some(600.0)), (100.0, 550.0));
+        assert_eq!(overlay_point(position, 1.0, Some(600.0)), (200.0, 500.0));
+        assert_eq!(overlay_point(position, 1.0, None), (200.0, 0.0));
+    }
+}
@@ -1,11 +1,23 @@
 use tauri::{Runtime, Window};
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetKeyState, VIRTUAL_KEY, VK_CONTROL, VK_MENU};
 
-use super::DropZoneConfig;
+use super::window_events;
+use super::DragModifiers;
 
-pub fn setup_drag_handlers<R: Runtime>(_window: &Window<R>) -> Result<(), String> {
-    Err("Windows drag detection not yet implemented".to_string())
+pub fn setup_drag_handlers<R: Runtime>(window: &Window<R>) -> Result<(), String> {
+    window_events::install(window, current_modifiers);
+    Ok(())
 }
 
-pub fn set_drop_zone(_zone_id: &str, _enabled: bool, _config: Option<DropZoneConfig>) {
-    // TODO: Implement Windows drag detection using IDropTarget
+/// Keyboard modifiers held right now, as seen by the thread handling the drag.
+fn current_modifiers() -> DragModifiers {
+    DragModifiers {
+        option_alt: is_key_down(VK_MENU),
+        cmd_ctrl: is_key_down(VK_CONTROL),
+    }
+}
+
+fn is_key_down(key: VIRTUAL_KEY) -> bool {
+    // The high-order bit of the returned state is set while the key is held
+    unsafe { GetKeyState(i32::from(key.0)) < 0 }
 }
@@ -0,0 +1,82 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::DropZoneConfig;
+
+#[derive(Debug, Clone, Default)]
+struct DropZoneState {
+    enabled: bool,
+    width: Option<f64>,
+}
+
+static DROP_ZONES: Lazy<Mutex<HashMap<String, DropZoneState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn set_drop_zone(zone_id: &str, enabled: bool, config: Option<DropZoneConfig>) {
+    let mut zones = DROP_ZONES.lock().unwrap();
+    let entry = zones.entry(zone_id.to_string()).or_default();
+    entry.enabled = enabled;
+    if let Some(cfg) = config {
+        entry.width = cfg.width;
+    }
+}
+
+/// The drop zone under a point in window coordinates (logical pixels).
+pub fn resolve_target(x: f64, y: f64) -> Option<String> {
+    if x < 0.0 || y < 0.0 {
+        return None;
+    }
+
+    let zones = DROP_ZONES.lock().unwrap();
+
+    if let Some(zone) = zones.get("sidebar") {
+        if zone.enabled {
+            let width = zone.width.unwrap_or(280.0);
+            // Allow a small tolerance to smooth minor rounding differences.
+            if x <= width + 1.0 {
+                return Some("sidebar".to_string());
+            }
+        }
+    }
+
+    if let Some(zone) = zones.get("file-panel") {
+        if zone.enabled {
+            return Some("file-panel".to_string());
+        }
+    }
+
+    None
+}
+
+pub fn is_target_enabled(target: &Option<String>) -> bool {
+    match target {
+        Some(id) => DROP_ZONES
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|zone| zone.enabled)
+            .unwrap_or(false),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drop_points_resolve_to_the_enabled_zone_under_them() {
+        set_drop_zone("sidebar", true, Some(DropZoneConfig { width: Some(200.0) }));
+        set_drop_zone("file-panel", true, None);
+        assert_eq!(resolve_target(150.0, 40.0).as_deref(), Some("sidebar"));
+        assert_eq!(resolve_target(450.0, 40.0).as_deref(), Some("file-panel"));
+        assert_eq!(resolve_target(-1.0, 40.0), None);
+
+        set_drop_zone("sidebar", false, None);
+        assert_eq!(resolve_target(150.0, 40.0).as_deref(), Some("file-panel"));
+        assert!(is_target_enabled(&Some("file-panel".to_string())));
+        assert!(!is_target_enabled(&Some("sidebar".to_string())));
+        assert!(!is_target_enabled(&None));
+    }
+}
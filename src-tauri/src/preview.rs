@@ -0,0 +1,290 @@
+//! Large previews for the preview panel.
+//!
+//! Text and markdown come back as decoded text, cut at a byte budget. Everything else
+//! goes through the thumbnail generators at preview size, except web images small
+//! enough to send as they are. Every response is capped so one preview can't turn
+//! into a huge IPC payload.
+
+use base64::Engine as _;
+use mime_guess::mime::{self, Mime};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::text_file;
+use crate::thumbnails::generators::ThumbnailGenerator;
+use crate::thumbnails::{ThumbnailFormat, ThumbnailPriority, ThumbnailQuality, ThumbnailRequest};
+
+/// Longest edge of a rendered preview when the caller doesn't ask for one
+pub const DEFAULT_PREVIEW_SIZE: u32 = 1600;
+pub const MAX_PREVIEW_SIZE: u32 = 4096;
+/// Web images up to this size are sent unchanged rather than re-encoded
+const MAX_INLINE_IMAGE_BYTES: u64 = 8 * 1024 * 1024;
+/// Bytes looked at when deciding whether an unknown file is text
+const SNIFF_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewKind {
+    Markdown,
+    Text,
+    /// Anything a thumbnail generator can draw: images, PDFs, video frames, ...
+    Visual,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PreviewContent {
+    #[serde(rename_all = "camelCase")]
+    Text { text: String, encoding: String },
+    /// Raw markdown source; the frontend renders it
+    #[serde(rename_all = "camelCase")]
+    Markdown { text: String, encoding: String },
+    #[serde(rename_all = "camelCase")]
+    Image {
+        data_url: String,
+        /// Original dimensions, when the generator knows them
+        width: Option<u32>,
+        height: Option<u32>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePreview {
+    pub mime_type: String,
+    /// Text was cut at the byte budget, or an image was scaled down
+    pub truncated: bool,
+    #[serde(flatten)]
+    pub content: PreviewContent,
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default()
+}
+
+pub fn preview_kind(path: &Path) -> PreviewKind {
+    let ext = extension(path);
+    if matches!(ext.as_str(), "md" | "markdown" | "mdown" | "mkd") {
+        return PreviewKind::Markdown;
+    }
+    let has_text_mime = mime_guess::from_path(path)
+        .iter()
+        .any(|mime| is_text_mime(&mime));
+    // svg is text, but the rendered image is the useful preview
+    if ext != "svg" && (has_text_mime || is_code_extension(&ext)) {
+        PreviewKind::Text
+    } else {
+        PreviewKind::Visual
+    }
+}
+
+fn is_text_mime(mime: &Mime) -> bool {
+    mime.type_() == mime::TEXT
+        || matches!(
+            mime.subtype().as_str(),
+            "json" | "xml" | "javascript" | "x-sh" | "toml" | "yaml" | "x-yaml"
+        )
+}
+
+/// Source and config files mime_guess doesn't file under text
+fn is_code_extension(ext: &str) -> bool {
+    matches!(
+        ext,
+        "rs" | "ts"
+            | "tsx"
+            | "jsx"
+            | "go"
+            | "py"
+            | "rb"
+            | "php"
+            | "swift"
+            | "kt"
+            | "kts"
+            | "scala"
+            | "sql"
+            | "lua"
+            | "pl"
+            | "zsh"
+            | "fish"
+            | "ps1"
+            | "ini"
+            | "cfg"
+            | "conf"
+            | "env"
+            | "lock"
+            | "log"
+            | "gradle"
+            | "dockerfile"
+            | "vue"
+            | "svelte"
+    )
+}
+
+pub fn mime_type(path: &Path) -> String {
+    let guess = mime_guess::from_path(path).first();
+    match preview_kind(path) {
+        PreviewKind::Markdown => "text/markdown".to_string(),
+        // `.ts` guesses as an MPEG transport stream
+        PreviewKind::Text => guess
+            .filter(is_text_mime)
+            .map_or_else(|| "text/plain".to_string(), |mime| mime.to_string()),
+        PreviewKind::Visual => guess.map_or_else(
+            || "application/octet-stream".to_string(),
+            |mime| mime.to_string(),
+        ),
+    }
+}
+
+/// The first `max_bytes` of the local file at `path`, decoded. Fails with `EBINARY`
+/// like [`text_file::read_text`] when the file doesn't look like text.
+pub fn text_preview(
+    path: &Path,
+    kind: PreviewKind,
+    max_bytes: usize,
+) -> Result<FilePreview, String> {
+    let text_file::TextContent {
+        text,
+        encoding,
+        truncated,
+    } = text_file::read_text(path, max_bytes)?;
+    let content = match kind {
+        PreviewKind::Markdown => PreviewContent::Markdown { text, encoding },
+        _ => PreviewContent::Text { text, encoding },
+    };
+    Ok(FilePreview {
+        mime_type: mime_type(path),
+        truncated,
+        content,
+    })
+}
+
+/// A web image that already fits in `size`, as a data URL of the file itself.
+fn inline_image(path: &Path, size: u32) -> Option<FilePreview> {
+    let mime = match extension(path).as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => return None,
+    };
+    if std::fs::metadata(path).ok()?.len() > MAX_INLINE_IMAGE_BYTES {
+        return None;
+    }
+    let (width, height) = image::image_dimensions(path).ok()?;
+    if width.max(height) > size {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    Some(FilePreview {
+        mime_type: mime.to_string(),
+        truncated: false,
+        content: PreviewContent::Image {
+            data_url: format!(
+                "data:{};base64,{}",
+                mime,
+                base64::engine::general_purpose::STANDARD.encode(bytes)
+            ),
+            width: Some(width),
+            height: Some(height),
+        },
+    })
+}
+
+pub fn preview_request(path: String, size: u32) -> ThumbnailRequest {
+    ThumbnailRequest {
+        id: crate::thumbnails::generate_request_id(),
+        path,
+        size: size.clamp(1, MAX_PREVIEW_SIZE),
+        quality: ThumbnailQuality::High,
+        priority: ThumbnailPriority::High,
+        format: ThumbnailFormat::WebP,
+        accent: None,
+    }
+}
+
+/// Render `request` (already prepared by [`ThumbnailGenerator::prepare`]) at preview
+/// size. A local file no generator handles is shown as text if it looks like text.
+pub fn visual_preview(
+    request: &ThumbnailRequest,
+    mime_type: String,
+    max_text_bytes: usize,
+) -> Result<FilePreview, String> {
+    let path = Path::new(&request.path);
+    if let Some(preview) = inline_image(path, request.size) {
+        return Ok(preview);
+    }
+
+    let error = match ThumbnailGenerator::generate(request) {
+        Ok(result) => {
+            let truncated = match (result.image_width, result.image_height) {
+                (Some(width), Some(height)) => width.max(height) > request.size,
+                _ => false,
+            };
+            return Ok(FilePreview {
+                mime_type,
+                truncated,
+                content: PreviewContent::Image {
+                    data_url: result.data_url,
+                    width: result.image_width,
+                    height: result.image_height,
+                },
+            });
+        }
+        Err(error) => error,
+    };
+
+    if path.is_file() {
+        let (head, _) = text_file::read_head(path, SNIFF_BYTES)?;
+        if !text_file::looks_binary(&head) {
+            let mut preview = text_preview(path, PreviewKind::Text, max_text_bytes)?;
+            preview.mime_type = mime_type;
+            return Ok(preview);
+        }
+    }
+    Err(format!("No preview available: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_preview_kind_by_extension() {
+        assert_eq!(preview_kind(Path::new("README.md")), PreviewKind::Markdown);
+        assert_eq!(preview_kind(Path::new("main.rs")), PreviewKind::Text);
+        assert_eq!(mime_type(Path::new("index.ts")), "text/plain");
+        assert_eq!(preview_kind(Path::new("notes.txt")), PreviewKind::Text);
+        assert_eq!(preview_kind(Path::new("logo.svg")), PreviewKind::Visual);
+        assert_eq!(preview_kind(Path::new("report.pdf")), PreviewKind::Visual);
+    }
+
+    #[test]
+    fn text_preview_reports_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("big.txt");
+        std::fs::write(&path, "x".repeat(100)).unwrap();
+
+        let preview = text_preview(&path, PreviewKind::Text, 10).unwrap();
+        assert!(preview.truncated);
+        assert_eq!(preview.mime_type, "text/plain");
+        match preview.content {
+            PreviewContent::Text { text, encoding } => {
+                assert_eq!(text.len(), 10);
+                assert_eq!(encoding, "UTF-8");
+            }
+            other => panic!("unexpected preview {:?}", other),
+        }
+    }
+
+    #[test]
+    fn text_preview_refuses_binary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.txt");
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01").unwrap();
+
+        let error = text_preview(&path, PreviewKind::Text, 1024).unwrap_err();
+        assert!(error.starts_with("[EBINARY]"), "{}", error);
+    }
+}
@@ -0,0 +1,144 @@
+//! Name templates for renaming a batch of items at once.
+//!
+//! A template is literal text with tokens:
+//! - `{name}`: the original name without its extension
+//! - `{ext}`: the extension without the dot; `.{ext}` disappears for items without one
+//! - `{n}`: the 1-based position in the batch, zero-padded to the width of the count
+//! - `{date}` or `{date:FORMAT}`: the modification time, `%Y-%m-%d` unless a strftime
+//!   format is given
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use std::path::Path;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// What a template needs to know about one item of the batch.
+pub struct PatternInput<'a> {
+    pub name: &'a str,
+    pub is_directory: bool,
+    pub modified: DateTime<Local>,
+}
+
+fn split_name(name: &str, is_directory: bool) -> (&str, &str) {
+    if is_directory {
+        return (name, "");
+    }
+    let path = Path::new(name);
+    match (
+        path.file_stem().and_then(|s| s.to_str()),
+        path.extension().and_then(|s| s.to_str()),
+    ) {
+        (Some(stem), Some(ext)) => (stem, ext),
+        _ => (name, ""),
+    }
+}
+
+/// Check that `pattern` only uses known tokens, so a typo fails before anything is renamed.
+pub fn validate_pattern(pattern: &str) -> Result<(), String> {
+    let sample = PatternInput {
+        name: "sample.txt",
+        is_directory: false,
+        modified: Local::now(),
+    };
+    expand_pattern(pattern, &sample, 1, 1).map(|_| ())
+}
+
+/// Expand `pattern` for the item at `index` (1-based) of a batch of `count`.
+pub fn expand_pattern(
+    pattern: &str,
+    input: &PatternInput,
+    index: usize,
+    count: usize,
+) -> Result<String, String> {
+    let (stem, ext) = split_name(input.name, input.is_directory);
+    let width = count.max(1).to_string().len();
+    let mut out = String::with_capacity(pattern.len() + input.name.len());
+    let mut rest = pattern;
+
+    while let Some(open) = rest.find('{') {
+        let (literal, tail) = rest.split_at(open);
+        let close = tail
+            .find('}')
+            .ok_or_else(|| format!("Unclosed token in pattern: {}", pattern))?;
+        let token = &tail[1..close];
+        rest = &tail[close + 1..];
+
+        let value = match token.split_once(':') {
+            None if token == "name" => stem.to_string(),
+            None if token == "ext" => ext.to_string(),
+            None if token == "n" => format!("{:0width$}", index, width = width),
+            None if token == "date" => input.modified.format(DEFAULT_DATE_FORMAT).to_string(),
+            Some(("date", format)) => {
+                // An invalid specifier would panic when formatting
+                if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                    return Err(format!("Invalid date format: {}", format));
+                }
+                input.modified.format(format).to_string()
+            }
+            _ => return Err(format!("Unknown token {{{}}} in pattern", token)),
+        };
+
+        if token == "ext" && value.is_empty() {
+            out.push_str(literal.strip_suffix('.').unwrap_or(literal));
+        } else {
+            out.push_str(literal);
+            out.push_str(&value);
+        }
+    }
+    out.push_str(rest);
+
+    let name = out.trim();
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!(
+            "Pattern produces an invalid name for {}: \"{}\"",
+            input.name, out
+        ));
+    }
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn input(name: &str) -> PatternInput<'_> {
+        PatternInput {
+            name,
+            is_directory: false,
+            modified: Local.with_ymd_and_hms(2024, 3, 9, 14, 30, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn expands_tokens() {
+        let photo = input("IMG_0042.JPG");
+        assert_eq!(
+            expand_pattern("Trip {n} - {name}.{ext}", &photo, 7, 120).unwrap(),
+            "Trip 007 - IMG_0042.JPG"
+        );
+        assert_eq!(
+            expand_pattern("{date}_{n}.{ext}", &photo, 1, 9).unwrap(),
+            "2024-03-09_1.JPG"
+        );
+        assert_eq!(
+            expand_pattern("{date:%Y%m%d-%H%M} {name}", &photo, 1, 1).unwrap(),
+            "20240309-1430 IMG_0042"
+        );
+        // No dangling dot for items without an extension
+        assert_eq!(
+            expand_pattern("{name} copy.{ext}", &input("Makefile"), 1, 1).unwrap(),
+            "Makefile copy"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_patterns() {
+        assert!(validate_pattern("{name}.{ext}").is_ok());
+        assert!(validate_pattern("{title}").is_err());
+        assert!(validate_pattern("{name").is_err());
+        assert!(validate_pattern("{date:%Q}").is_err());
+        assert!(validate_pattern("{name}/{ext}").is_err());
+    }
+}
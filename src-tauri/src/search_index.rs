@@ -0,0 +1,579 @@
+//! Opt-in in-memory name index for recursive search.
+//!
+//! The first indexed search of a root starts a background walk and answers from a
+//! live walk until the index is ready. A recursive watcher keeps a ready index in
+//! sync, so later searches only scan memory. Indexes are capped in size and count,
+//! and ones nobody has searched for a while are dropped.
+
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+use crate::fs_utils::is_hidden_file;
+
+/// Roots indexed at once; the least recently searched one is evicted beyond this.
+const MAX_INDEXES: usize = 4;
+/// Entries per root. Larger trees are not indexed and always use the live walk.
+const MAX_ENTRIES_PER_INDEX: usize = 500_000;
+/// Indexes not searched for this long are dropped on the next index access.
+const IDLE_EVICTION: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchIndexState {
+    Building,
+    Ready,
+    /// The tree exceeded `MAX_ENTRIES_PER_INDEX`; searches walk live instead.
+    TooLarge,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchIndexStatus {
+    pub root: String,
+    pub state: SearchIndexState,
+    pub entries: usize,
+    pub build_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedEntry {
+    name_lower: String,
+    is_directory: bool,
+    is_hidden: bool,
+    size: u64,
+}
+
+/// A search result from either the index or the live walk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    pub is_directory: bool,
+    pub size: u64,
+}
+
+struct RootIndex {
+    root: PathBuf,
+    /// Keyed by full path; ordered so a subtree is one contiguous range.
+    entries: BTreeMap<PathBuf, IndexedEntry>,
+    state: SearchIndexState,
+    error: Option<String>,
+    build_ms: Option<u64>,
+    last_used: Instant,
+    cancel: Arc<AtomicBool>,
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl RootIndex {
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            entries: BTreeMap::new(),
+            state: SearchIndexState::Building,
+            error: None,
+            build_ms: None,
+            last_used: Instant::now(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            _watcher: None,
+        }
+    }
+
+    fn status(&self) -> SearchIndexStatus {
+        SearchIndexStatus {
+            root: self.root.to_string_lossy().to_string(),
+            state: self.state,
+            entries: self.entries.len(),
+            build_ms: self.build_ms,
+            error: self.error.clone(),
+        }
+    }
+
+    fn insert(&mut self, path: PathBuf, metadata: &fs::Metadata) {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            return;
+        };
+        let is_directory = metadata.is_dir();
+        self.entries.insert(
+            path,
+            IndexedEntry {
+                name_lower: name.to_lowercase(),
+                is_directory,
+                is_hidden: is_hidden_file(&name),
+                size: if is_directory { 0 } else { metadata.len() },
+            },
+        );
+    }
+
+    fn remove_subtree(&mut self, path: &Path) {
+        let doomed: Vec<PathBuf> = self
+            .entries
+            .range(path.to_path_buf()..)
+            .map(|(p, _)| p)
+            .take_while(|p| p.starts_with(path))
+            .cloned()
+            .collect();
+        for p in doomed {
+            self.entries.remove(&p);
+        }
+    }
+
+    /// Bring one changed path in line with the disk: re-stat it if it still exists
+    /// (indexing the whole subtree of a directory that moved in), drop it otherwise.
+    fn apply_change(&mut self, path: &Path) {
+        if path == self.root || !path.starts_with(&self.root) {
+            return;
+        }
+        match fs::symlink_metadata(path) {
+            Ok(metadata) => {
+                let known = self.entries.contains_key(path);
+                if metadata.is_dir() && !known {
+                    for entry in WalkDir::new(path).follow_links(false).into_iter().flatten() {
+                        if let Ok(metadata) = entry.metadata() {
+                            self.insert(entry.into_path(), &metadata);
+                        }
+                    }
+                } else {
+                    self.insert(path.to_path_buf(), &metadata);
+                }
+                if self.entries.len() > MAX_ENTRIES_PER_INDEX {
+                    self.mark_too_large();
+                }
+            }
+            Err(_) => self.remove_subtree(path),
+        }
+    }
+
+    fn in_hidden_folder(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.root)
+            .ok()
+            .and_then(Path::parent)
+            .is_some_and(|parent| {
+                parent
+                    .components()
+                    .any(|c| is_hidden_file(&c.as_os_str().to_string_lossy()))
+            })
+    }
+
+    fn mark_too_large(&mut self) {
+        self.state = SearchIndexState::TooLarge;
+        self.entries.clear();
+        self.cancel.store(true, Ordering::SeqCst);
+        self._watcher = None;
+    }
+
+    /// Entries whose name contains `needle_lower`. Without `include_hidden`, hidden
+    /// entries and everything inside hidden folders are left out, as `walk_search`
+    /// does by not descending into them.
+    fn query(&self, needle_lower: &str, include_hidden: bool, limit: usize) -> Vec<SearchHit> {
+        self.entries
+            .iter()
+            .filter(|(_, e)| e.name_lower.contains(needle_lower))
+            .filter(|(path, e)| include_hidden || !(e.is_hidden || self.in_hidden_folder(path)))
+            .take(limit)
+            .map(|(path, e)| SearchHit {
+                path: path.clone(),
+                is_directory: e.is_directory,
+                size: e.size,
+            })
+            .collect()
+    }
+}
+
+type SharedIndex = Arc<RwLock<RootIndex>>;
+
+static INDEXES: Lazy<Mutex<HashMap<PathBuf, SharedIndex>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn evict_idle_and_overflow(indexes: &mut HashMap<PathBuf, SharedIndex>) {
+    let last_used = |index: &SharedIndex| index.read().map(|i| i.last_used).ok();
+
+    indexes.retain(|root, index| {
+        let idle = last_used(index).map_or(true, |t| t.elapsed() >= IDLE_EVICTION);
+        if idle {
+            log::debug!("Dropping idle search index for {}", root.display());
+            cancel(index);
+        }
+        !idle
+    });
+
+    while indexes.len() >= MAX_INDEXES {
+        let Some(oldest) = indexes
+            .iter()
+            .min_by_key(|(_, index)| last_used(index))
+            .map(|(root, _)| root.clone())
+        else {
+            break;
+        };
+        if let Some(index) = indexes.remove(&oldest) {
+            log::debug!("Evicting search index for {}", oldest.display());
+            cancel(&index);
+        }
+    }
+}
+
+/// Stop the build and drop the watcher, which also ends the event thread.
+fn cancel(index: &SharedIndex) {
+    if let Ok(mut index) = index.write() {
+        index.cancel.store(true, Ordering::SeqCst);
+        index._watcher = None;
+    }
+}
+
+/// Return the index for `root`, starting a background build if there is none.
+/// Callers should check the returned status and fall back to `walk_search` until
+/// it is `Ready`.
+pub fn ensure_index(root: &Path) -> SearchIndexStatus {
+    let mut indexes = match INDEXES.lock() {
+        Ok(indexes) => indexes,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    if let Some(index) = indexes.get(root) {
+        if let Ok(mut index) = index.write() {
+            index.last_used = Instant::now();
+            return index.status();
+        }
+    }
+
+    evict_idle_and_overflow(&mut indexes);
+
+    let index = Arc::new(RwLock::new(RootIndex::new(root.to_path_buf())));
+    indexes.insert(root.to_path_buf(), index.clone());
+    let status = index.read().map(|i| i.status()).ok();
+    drop(indexes);
+
+    std::thread::spawn(move || build_index(index));
+
+    status.unwrap_or_else(|| SearchIndexStatus {
+        root: root.to_string_lossy().to_string(),
+        state: SearchIndexState::Building,
+        entries: 0,
+        build_ms: None,
+        error: None,
+    })
+}
+
+/// Current status for `root`, or `None` if it isn't indexed.
+pub fn index_status(root: &Path) -> Option<SearchIndexStatus> {
+    let indexes = INDEXES.lock().ok()?;
+    let index = indexes.get(root)?;
+    let status = index.read().ok()?.status();
+    Some(status)
+}
+
+/// Drop the index for `root`, stopping its build and watcher.
+pub fn drop_index(root: &Path) -> bool {
+    let Ok(mut indexes) = INDEXES.lock() else {
+        return false;
+    };
+    match indexes.remove(root) {
+        Some(index) => {
+            cancel(&index);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Drop every index whose root is on or under `volume`, returning their roots so
+/// they can be rebuilt with [`ensure_index`] if the volume stays mounted.
+pub fn drop_indexes_under(volume: &Path) -> Vec<PathBuf> {
+    let Ok(mut indexes) = INDEXES.lock() else {
+        return Vec::new();
+    };
+    let roots: Vec<PathBuf> = indexes
+        .keys()
+        .filter(|root| root.starts_with(volume))
+        .cloned()
+        .collect();
+    for root in &roots {
+        if let Some(index) = indexes.remove(root) {
+            cancel(&index);
+        }
+    }
+    roots
+}
+
+/// Answer a query from the index. Returns `None` unless the index is ready.
+pub fn query_index(
+    root: &Path,
+    query: &str,
+    include_hidden: bool,
+    limit: usize,
+) -> Option<Vec<SearchHit>> {
+    let index = INDEXES.lock().ok()?.get(root)?.clone();
+    let hits = {
+        let index = index.read().ok()?;
+        if index.state != SearchIndexState::Ready {
+            return None;
+        }
+        index.query(&query.to_lowercase(), include_hidden, limit)
+    };
+    if let Ok(mut index) = index.write() {
+        index.last_used = Instant::now();
+    }
+    Some(hits)
+}
+
+/// Walk `root` and return entries whose name contains `query` (case-insensitive).
+pub fn walk_search(root: &Path, query: &str, include_hidden: bool, limit: usize) -> Vec<SearchHit> {
+    let needle = query.to_lowercase();
+    WalkDir::new(root)
+        .min_depth(1)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| {
+            include_hidden || !is_hidden_file(&entry.file_name().to_string_lossy())
+        })
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .to_lowercase()
+                .contains(&needle)
+        })
+        .take(limit)
+        .map(|entry| {
+            let metadata = entry.metadata().ok();
+            let is_directory = entry.file_type().is_dir();
+            SearchHit {
+                size: metadata
+                    .filter(|_| !is_directory)
+                    .map(|m| m.len())
+                    .unwrap_or(0),
+                is_directory,
+                path: entry.into_path(),
+            }
+        })
+        .collect()
+}
+
+fn build_index(index: SharedIndex) {
+    let (root, cancel) = match index.read() {
+        Ok(i) => (i.root.clone(), i.cancel.clone()),
+        Err(_) => return,
+    };
+    let started = Instant::now();
+
+    // Watch before walking so changes made during the walk aren't lost
+    let (tx, rx) = mpsc::channel::<Event>();
+    let watcher = RecommendedWatcher::new(
+        move |result: Result<Event, notify::Error>| {
+            if let Ok(event) = result {
+                let _ = tx.send(event);
+            }
+        },
+        Config::default(),
+    )
+    .and_then(|mut watcher| {
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+        Ok(watcher)
+    });
+    let watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            if let Ok(mut index) = index.write() {
+                index.state = SearchIndexState::Failed;
+                index.error = Some(format!("Failed to watch {}: {}", root.display(), e));
+            }
+            return;
+        }
+    };
+
+    let mut walked = BTreeMap::new();
+    let mut too_large = false;
+    for entry in WalkDir::new(&root)
+        .min_depth(1)
+        .follow_links(false)
+        .into_iter()
+        .flatten()
+    {
+        if cancel.load(Ordering::SeqCst) {
+            return;
+        }
+        if walked.len() >= MAX_ENTRIES_PER_INDEX {
+            too_large = true;
+            break;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            walked.insert(entry.into_path(), metadata);
+        }
+    }
+
+    {
+        let Ok(mut index) = index.write() else {
+            return;
+        };
+        if too_large {
+            log::info!(
+                "Not indexing {}: more than {} entries",
+                root.display(),
+                MAX_ENTRIES_PER_INDEX
+            );
+            index.mark_too_large();
+            return;
+        }
+        for (path, metadata) in walked {
+            index.insert(path, &metadata);
+        }
+        // Replay whatever changed while we were walking
+        while let Ok(event) = rx.try_recv() {
+            apply_event(&mut index, &event);
+        }
+        index.state = SearchIndexState::Ready;
+        index.build_ms = Some(started.elapsed().as_millis() as u64);
+        index._watcher = Some(watcher);
+        log::debug!(
+            "Indexed {} entries under {} in {}ms",
+            index.entries.len(),
+            root.display(),
+            started.elapsed().as_millis()
+        );
+    }
+
+    // The channel closes once the watcher is dropped (eviction or too large)
+    while let Ok(event) = rx.recv() {
+        if cancel.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(mut index) = index.write() else {
+            break;
+        };
+        apply_event(&mut index, &event);
+    }
+}
+
+fn apply_event(index: &mut RootIndex, event: &Event) {
+    if matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        for path in &event.paths {
+            index.apply_change(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn names(hits: &[SearchHit]) -> Vec<String> {
+        let mut names: Vec<String> = hits
+            .iter()
+            .map(|h| h.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn index_answers_like_live_walk_and_follows_changes() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().to_path_buf();
+        fs::create_dir_all(root.join("photos/2024")).unwrap();
+        fs::write(root.join("photos/2024/Report.pdf"), b"pdf").unwrap();
+        fs::write(root.join("report-notes.txt"), b"notes").unwrap();
+        fs::write(root.join(".report-cache"), b"x").unwrap();
+        fs::write(root.join("other.txt"), b"o").unwrap();
+
+        let mut index = RootIndex::new(root.clone());
+        for entry in WalkDir::new(&root).min_depth(1).into_iter().flatten() {
+            let metadata = entry.metadata().unwrap();
+            index.insert(entry.into_path(), &metadata);
+        }
+
+        let live = walk_search(&root, "REPORT", false, 100);
+        let indexed = index.query("report", false, 100);
+        assert_eq!(names(&live), ["Report.pdf", "report-notes.txt"]);
+        assert_eq!(names(&indexed), names(&live));
+        assert_eq!(names(&index.query("report", true, 100)).len(), 3);
+
+        // A directory moved in gets its whole subtree indexed
+        fs::create_dir_all(root.join("incoming/deep")).unwrap();
+        fs::write(root.join("incoming/deep/report-final.doc"), b"doc").unwrap();
+        index.apply_change(&root.join("incoming"));
+        assert!(names(&index.query("report", false, 100)).contains(&"report-final.doc".into()));
+
+        // Removing a directory drops everything under it
+        fs::remove_dir_all(root.join("photos")).unwrap();
+        index.apply_change(&root.join("photos"));
+        assert_eq!(
+            names(&index.query("report", false, 100)),
+            ["report-final.doc", "report-notes.txt"]
+        );
+        assert!(index.query("2024", false, 100).is_empty());
+    }
+
+    #[test]
+    fn index_skips_hidden_folders_like_live_walk() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().to_path_buf();
+        fs::create_dir_all(root.join(".git/refs")).unwrap();
+        fs::create_dir_all(root.join("src/.cache")).unwrap();
+        fs::write(root.join(".git/refs/main"), b"ref").unwrap();
+        fs::write(root.join("src/.cache/main.o"), b"obj").unwrap();
+        fs::write(root.join("src/main.rs"), b"fn main() {}").unwrap();
+
+        let mut index = RootIndex::new(root.clone());
+        for entry in WalkDir::new(&root).min_depth(1).into_iter().flatten() {
+            let metadata = entry.metadata().unwrap();
+            index.insert(entry.into_path(), &metadata);
+        }
+
+        for include_hidden in [false, true] {
+            let live = walk_search(&root, "main", include_hidden, 100);
+            let indexed = index.query("main", include_hidden, 100);
+            assert_eq!(names(&indexed), names(&live));
+        }
+        assert_eq!(names(&index.query("main", false, 100)), ["main.rs"]);
+    }
+
+    #[test]
+    fn background_build_becomes_ready() {
+        let dir = tempdir().expect("tempdir");
+        let root = dir.path().to_path_buf();
+        fs::write(root.join("needle.txt"), b"n").unwrap();
+
+        let status = ensure_index(&root);
+        assert_eq!(status.state, SearchIndexState::Building);
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while index_status(&root).map(|s| s.state) != Some(SearchIndexState::Ready) {
+            assert!(Instant::now() < deadline, "index never became ready");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let hits = query_index(&root, "needle", false, 10).expect("ready index");
+        assert_eq!(names(&hits), ["needle.txt"]);
+        assert!(drop_index(&root));
+        assert!(index_status(&root).is_none());
+    }
+
+    #[test]
+    fn drops_only_indexes_on_the_volume() {
+        let volume = tempdir().expect("tempdir");
+        let elsewhere = tempdir().expect("tempdir");
+        let inside = volume.path().join("projects");
+        fs::create_dir_all(&inside).unwrap();
+
+        ensure_index(&inside);
+        ensure_index(elsewhere.path());
+
+        assert_eq!(drop_indexes_under(volume.path()), vec![inside.clone()]);
+        assert!(index_status(&inside).is_none());
+        assert!(index_status(elsewhere.path()).is_some());
+        drop_index(elsewhere.path());
+    }
+}
@@ -15,9 +15,10 @@ pub mod operations;
 pub mod protocol;
 
 use protocol::{
-    error_codes, methods, CopyParams, CreateDirectoryParams, DeleteParams, DownloadFileParams,
-    DownloadPartialParams, GetFileMetadataParams, ListSharesParams, PingResult,
-    ReadDirectoryParams, RenameParams, Request, Response, TestConnectionParams, UploadFileParams,
+    error_codes, methods, CopyParams, CountChildrenParams, CreateDirectoryParams, DeleteParams,
+    DiskUsageParams, DownloadFileParams, DownloadPartialParams, GetFileMetadataParams,
+    ListSharesParams, PingResult, ReadDirectoryParams, RenameParams, Request, Response,
+    TestConnectionParams, UploadFileParams,
 };
 use std::io::{BufRead, Write};
 
@@ -93,6 +94,7 @@ fn dispatch_request(request: &Request) -> Response {
     match request.method.as_str() {
         methods::PING => handle_ping(request),
         methods::READ_DIRECTORY => handle_read_directory(request),
+        methods::COUNT_CHILDREN => handle_count_children(request),
         methods::GET_FILE_METADATA => handle_get_file_metadata(request),
         methods::CREATE_DIRECTORY => handle_create_directory(request),
         methods::DELETE => handle_delete(request),
@@ -103,6 +105,7 @@ fn dispatch_request(request: &Request) -> Response {
         methods::DOWNLOAD_FILE => handle_download_file(request),
         methods::DOWNLOAD_PARTIAL => handle_download_partial(request),
         methods::UPLOAD_FILE => handle_upload_file(request),
+        methods::DISK_USAGE => handle_disk_usage(request),
         _ => Response::error(
             request.id,
             error_codes::METHOD_NOT_FOUND,
@@ -138,6 +141,24 @@ fn handle_read_directory(request: &Request) -> Response {
     }
 }
 
+fn handle_count_children(request: &Request) -> Response {
+    let params: CountChildrenParams = match serde_json::from_value(request.params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                request.id,
+                error_codes::INVALID_PARAMS,
+                format!("Invalid params: {}", e),
+            )
+        }
+    };
+
+    match operations::count_children(params) {
+        Ok(result) => Response::success(request.id, result),
+        Err((code, msg)) => Response::error(request.id, code, msg),
+    }
+}
+
 fn handle_get_file_metadata(request: &Request) -> Response {
     let params: GetFileMetadataParams = match serde_json::from_value(request.params.clone()) {
         Ok(p) => p,
@@ -317,3 +338,21 @@ fn handle_upload_file(request: &Request) -> Response {
         Err((code, msg)) => Response::error(request.id, code, msg),
     }
 }
+
+fn handle_disk_usage(request: &Request) -> Response {
+    let params: DiskUsageParams = match serde_json::from_value(request.params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                request.id,
+                error_codes::INVALID_PARAMS,
+                format!("Invalid params: {}", e),
+            )
+        }
+    };
+
+    match operations::disk_usage(params) {
+        Ok(result) => Response::success(request.id, result),
+        Err((code, msg)) => Response::error(request.id, code, msg),
+    }
+}
@@ -4,15 +4,17 @@
 //! All operations receive credentials as parameters (no keychain access).
 
 use crate::smb_sidecar::protocol::{
-    error_codes, CopyParams, CreateDirectoryParams, DeleteParams, DirectoryEntry,
-    DownloadFileParams, DownloadFileResult, DownloadPartialParams, DownloadPartialResult,
-    FileMetadataResult, GetFileMetadataParams, ListSharesParams, ListSharesResult,
-    ReadDirectoryParams, ReadDirectoryResult, RenameParams, ShareEntry, SmbCredentials,
+    error_codes, CopyParams, CountChildrenParams, CountChildrenResult, CreateDirectoryParams,
+    DeleteParams, DirectoryEntry, DiskUsageParams, DiskUsageResult, DownloadFileParams,
+    DownloadFileResult, DownloadPartialParams, DownloadPartialResult, FileMetadataResult,
+    GetFileMetadataParams, ListSharesParams, ListSharesResult, ReadDirectoryParams,
+    ReadDirectoryResult, RenameParams, ShareEntry, SmbCredentials, SmbProtocol,
     TestConnectionParams, TestConnectionResult, UploadFileParams, UploadFileResult,
 };
 use once_cell::sync::Lazy;
 use pavao::{SmbClient, SmbCredentials as PavaoCredentials, SmbMode, SmbOpenOptions, SmbOptions};
-use std::io::{Read, Write};
+use std::ffi::{c_char, c_int, c_void, CStr};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::sync::Mutex;
 
 /// Global mutex to serialize ALL SMB operations.
@@ -22,6 +24,118 @@ static SMB_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 /// Sidecar version string.
 pub const SIDECAR_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+extern "C" {
+    /// Sets `client min protocol` / `client max protocol` (Samba 4.3+); pavao doesn't wrap it.
+    fn smbc_setOptionProtocols(
+        ctx: *mut c_void,
+        min_proto: *const c_char,
+        max_proto: *const c_char,
+    ) -> c_int;
+}
+
+/// Dialects allowed for the connection in use, named in negotiation errors.
+/// `SMB_MUTEX` serializes operations, so this always describes the running one.
+static ACTIVE_DIALECT: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+
+/// Samba's name for the oldest dialect of each family
+fn samba_min_protocol(protocol: SmbProtocol) -> &'static CStr {
+    match protocol {
+        SmbProtocol::Nt1 => c"NT1",
+        SmbProtocol::Smb2 => c"SMB2_02",
+        SmbProtocol::Smb3 => c"SMB3_00",
+    }
+}
+
+/// Samba's name for the newest dialect of each family
+fn samba_max_protocol(protocol: SmbProtocol) -> &'static CStr {
+    match protocol {
+        SmbProtocol::Nt1 => c"NT1",
+        SmbProtocol::Smb2 => c"SMB2_10",
+        SmbProtocol::Smb3 => c"SMB3_11",
+    }
+}
+
+/// The dialect range in `creds`, as shown to users, e.g. "SMB2 or newer".
+fn describe_dialect(creds: &SmbCredentials) -> String {
+    match (creds.min_protocol, creds.max_protocol) {
+        (None, None) => "auto-negotiation".to_string(),
+        (Some(min), Some(max)) if min == max => min.as_str().to_string(),
+        (Some(min), Some(max)) => format!("{} to {}", min.as_str(), max.as_str()),
+        (Some(min), None) => format!("{} or newer", min.as_str()),
+        (None, Some(max)) => format!("up to {}", max.as_str()),
+    }
+}
+
+fn validate_dialect(creds: &SmbCredentials) -> Result<(), (i32, String)> {
+    match (creds.min_protocol, creds.max_protocol) {
+        (Some(min), Some(max)) if min > max => Err((
+            error_codes::INVALID_PARAMS,
+            format!(
+                "Minimum SMB protocol {} is newer than maximum {}",
+                min.as_str(),
+                max.as_str()
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Whether an error means the server and client found no dialect in common.
+fn is_negotiation_error(msg: &str) -> bool {
+    [
+        "negotiation failed",
+        "INVALID_NETWORK_RESPONSE",
+        "Connection reset",
+        "Protocol not supported",
+    ]
+    .iter()
+    .any(|needle| msg.contains(needle))
+}
+
+fn negotiation_error(dialect: &str, detail: &str) -> (i32, String) {
+    (
+        error_codes::SMB_CONNECTION_FAILED,
+        format!(
+            "SMB protocol negotiation failed (tried {}); choose a different protocol version for this server: {}",
+            dialect, detail
+        ),
+    )
+}
+
+/// Create a client for `share`, limited to the dialects in `creds`.
+fn connect(creds: &SmbCredentials, share: &str) -> Result<SmbClient, (i32, String)> {
+    validate_dialect(creds)?;
+    if let Ok(mut active) = ACTIVE_DIALECT.lock() {
+        *active = describe_dialect(creds);
+    }
+
+    let client =
+        SmbClient::new(build_credentials(creds, share), SmbOptions::default()).map_err(|e| {
+            let (code, msg) = map_smb_error(&e);
+            (code, format!("Failed to connect to SMB server: {}", msg))
+        })?;
+
+    // The setting is process-wide in libsmbclient, so "default" has to be set
+    // explicitly to undo a limit from an earlier connection.
+    let min = creds.min_protocol.map_or(c"default", samba_min_protocol);
+    let max = creds.max_protocol.map_or(c"default", samba_max_protocol);
+    let ctx = client.ctx().map_err(|e| map_smb_error(&e))?;
+    // SAFETY: `ctx` is the live context owned by `client`, and both names are
+    // NUL-terminated static strings.
+    let applied =
+        unsafe { smbc_setOptionProtocols(ctx as *mut c_void, min.as_ptr(), max.as_ptr()) };
+    if applied == 0 {
+        return Err((
+            error_codes::SMB_CONNECTION_FAILED,
+            format!(
+                "libsmbclient rejected SMB protocol {}",
+                describe_dialect(creds)
+            ),
+        ));
+    }
+    Ok(client)
+}
+
 /// Build pavao credentials from our protocol credentials.
 fn build_credentials(creds: &SmbCredentials, share: &str) -> PavaoCredentials {
     let smb_url = format!("smb://{}", creds.hostname);
@@ -47,12 +161,30 @@ fn build_credentials(creds: &SmbCredentials, share: &str) -> PavaoCredentials {
 /// Map pavao errors to our error codes.
 fn map_smb_error(e: &pavao::SmbError) -> (i32, String) {
     let msg = e.to_string();
+    if is_negotiation_error(&msg) {
+        let dialect = ACTIVE_DIALECT
+            .lock()
+            .map(|active| active.clone())
+            .unwrap_or_default();
+        return negotiation_error(&dialect, &msg);
+    }
     let code = if msg.contains("LOGON_FAILURE") || msg.contains("authentication") {
         error_codes::SMB_AUTH_FAILED
     } else if msg.contains("NOT_FOUND") || msg.contains("No such file") {
         error_codes::SMB_PATH_NOT_FOUND
     } else if msg.contains("ACCESS_DENIED") || msg.contains("Permission denied") {
         error_codes::SMB_PERMISSION_DENIED
+    } else if [
+        "Connection refused",
+        "No route to host",
+        "Host is down",
+        "Network is unreachable",
+        "timed out",
+    ]
+    .iter()
+    .any(|needle| msg.contains(needle))
+    {
+        error_codes::SMB_CONNECTION_FAILED
     } else {
         error_codes::SMB_ERROR
     };
@@ -73,12 +205,7 @@ pub fn read_directory(params: ReadDirectoryParams) -> Result<ReadDirectoryResult
         )
     })?;
 
-    let credentials = build_credentials(&params.credentials, &params.share);
-
-    let client = SmbClient::new(credentials, SmbOptions::default()).map_err(|e| {
-        let (code, msg) = map_smb_error(&e);
-        (code, format!("Failed to connect to SMB server: {}", msg))
-    })?;
+    let client = connect(&params.credentials, &params.share)?;
 
     // Use list_dirplus to get file metadata inline with listing
     let entries = client.list_dirplus(&params.path).map_err(|e| {
@@ -124,6 +251,31 @@ pub fn read_directory(params: ReadDirectoryParams) -> Result<ReadDirectoryResult
     })
 }
 
+/// Count directory entries. Uses the plain listing, which skips the per-entry
+/// stat that `list_dirplus` does, since only the names are needed.
+pub fn count_children(params: CountChildrenParams) -> Result<CountChildrenResult, (i32, String)> {
+    let _guard = SMB_MUTEX.lock().map_err(|e| {
+        (
+            error_codes::INTERNAL_ERROR,
+            format!("SMB mutex poisoned: {}", e),
+        )
+    })?;
+
+    let client = connect(&params.credentials, &params.share)?;
+
+    let entries = client.list_dir(&params.path).map_err(|e| {
+        let (code, msg) = map_smb_error(&e);
+        (code, format!("Failed to list directory: {}", msg))
+    })?;
+
+    let count = entries
+        .iter()
+        .filter(|entry| entry.name() != "." && entry.name() != "..")
+        .count() as u64;
+
+    Ok(CountChildrenResult { count })
+}
+
 /// Get metadata for a single file or directory.
 pub fn get_file_metadata(
     params: GetFileMetadataParams,
@@ -135,12 +287,7 @@ pub fn get_file_metadata(
         )
     })?;
 
-    let credentials = build_credentials(&params.credentials, &params.share);
-
-    let client = SmbClient::new(credentials, SmbOptions::default()).map_err(|e| {
-        let (code, msg) = map_smb_error(&e);
-        (code, format!("Failed to connect to SMB server: {}", msg))
-    })?;
+    let client = connect(&params.credentials, &params.share)?;
 
     let stat = client.stat(&params.path).map_err(|e| {
         let (code, msg) = map_smb_error(&e);
@@ -182,12 +329,7 @@ pub fn create_directory(params: CreateDirectoryParams) -> Result<(), (i32, Strin
         )
     })?;
 
-    let credentials = build_credentials(&params.credentials, &params.share);
-
-    let client = SmbClient::new(credentials, SmbOptions::default()).map_err(|e| {
-        let (code, msg) = map_smb_error(&e);
-        (code, format!("Failed to connect to SMB server: {}", msg))
-    })?;
+    let client = connect(&params.credentials, &params.share)?;
 
     client
         .mkdir(&params.path, SmbMode::from(0o755))
@@ -206,12 +348,7 @@ pub fn delete(params: DeleteParams) -> Result<(), (i32, String)> {
         )
     })?;
 
-    let credentials = build_credentials(&params.credentials, &params.share);
-
-    let client = SmbClient::new(credentials, SmbOptions::default()).map_err(|e| {
-        let (code, msg) = map_smb_error(&e);
-        (code, format!("Failed to connect to SMB server: {}", msg))
-    })?;
+    let client = connect(&params.credentials, &params.share)?;
 
     let stat = client.stat(&params.path).map_err(|e| {
         let (code, msg) = map_smb_error(&e);
@@ -240,12 +377,7 @@ pub fn rename(params: RenameParams) -> Result<(), (i32, String)> {
         )
     })?;
 
-    let credentials = build_credentials(&params.credentials, &params.share);
-
-    let client = SmbClient::new(credentials, SmbOptions::default()).map_err(|e| {
-        let (code, msg) = map_smb_error(&e);
-        (code, format!("Failed to connect to SMB server: {}", msg))
-    })?;
+    let client = connect(&params.credentials, &params.share)?;
 
     client
         .rename(&params.from_path, &params.to_path)
@@ -264,12 +396,7 @@ pub fn copy(params: CopyParams) -> Result<(), (i32, String)> {
         )
     })?;
 
-    let credentials = build_credentials(&params.credentials, &params.share);
-
-    let client = SmbClient::new(credentials, SmbOptions::default()).map_err(|e| {
-        let (code, msg) = map_smb_error(&e);
-        (code, format!("Failed to connect to SMB server: {}", msg))
-    })?;
+    let client = connect(&params.credentials, &params.share)?;
 
     let mut src = client
         .open_with(&params.from_path, SmbOpenOptions::default().read(true))
@@ -312,42 +439,64 @@ pub fn list_shares(params: ListSharesParams) -> Result<ListSharesResult, (i32, S
         .arg(format!("//{}", params.credentials.hostname))
         .arg("-g"); // Machine-readable output
 
-    // Avoid putting credentials on the process command line.
-    // smbclient supports reading auth data from an authfile via -A.
-    let auth_file_path =
-        std::env::temp_dir().join(format!("marlin-smb-auth-{}.conf", Uuid::new_v4()));
-    let auth_file_contents = {
-        let mut s = format!(
-            "username = {}\npassword = {}\n",
-            params.credentials.username, params.credentials.password
-        );
-        if let Some(domain) = &params.credentials.domain {
-            s.push_str(&format!("domain = {}\n", domain));
-        }
-        s
-    };
+    validate_dialect(&params.credentials)?;
+    if let Some(min) = params.credentials.min_protocol {
+        cmd.arg(format!(
+            "--option=client min protocol={}",
+            samba_min_protocol(min).to_string_lossy()
+        ));
+    }
+    if let Some(max) = params.credentials.max_protocol {
+        cmd.arg("-m")
+            .arg(samba_max_protocol(max).to_string_lossy().as_ref());
+    }
 
-    std::fs::write(&auth_file_path, auth_file_contents).map_err(|e| {
-        (
-            error_codes::INTERNAL_ERROR,
-            format!("Failed to create smbclient auth file: {}", e),
-        )
-    })?;
+    let guest = params.credentials.username.is_empty() && params.credentials.password.is_empty();
+    let auth_file_path = if guest {
+        // Anonymous listing; without -N smbclient would prompt for a password
+        cmd.arg("-N");
+        None
+    } else {
+        // Avoid putting credentials on the process command line.
+        // smbclient supports reading auth data from an authfile via -A.
+        let auth_file_path =
+            std::env::temp_dir().join(format!("marlin-smb-auth-{}.conf", Uuid::new_v4()));
+        let auth_file_contents = {
+            let mut s = format!(
+                "username = {}\npassword = {}\n",
+                params.credentials.username, params.credentials.password
+            );
+            if let Some(domain) = &params.credentials.domain {
+                s.push_str(&format!("domain = {}\n", domain));
+            }
+            s
+        };
 
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        if let Ok(metadata) = std::fs::metadata(&auth_file_path) {
-            let mut perms = metadata.permissions();
-            perms.set_mode(0o600);
-            let _ = std::fs::set_permissions(&auth_file_path, perms);
+        std::fs::write(&auth_file_path, auth_file_contents).map_err(|e| {
+            (
+                error_codes::INTERNAL_ERROR,
+                format!("Failed to create smbclient auth file: {}", e),
+            )
+        })?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Ok(metadata) = std::fs::metadata(&auth_file_path) {
+                let mut perms = metadata.permissions();
+                perms.set_mode(0o600);
+                let _ = std::fs::set_permissions(&auth_file_path, perms);
+            }
         }
-    }
 
-    cmd.arg("-A").arg(&auth_file_path);
+        cmd.arg("-A").arg(&auth_file_path);
+        Some(auth_file_path)
+    };
 
     let output = cmd.output();
-    let _ = std::fs::remove_file(&auth_file_path);
+    if let Some(auth_file_path) = auth_file_path {
+        let _ = std::fs::remove_file(&auth_file_path);
+    }
 
     let output = output.map_err(|e| {
         if e.kind() == std::io::ErrorKind::NotFound {
@@ -365,6 +514,12 @@ pub fn list_shares(params: ListSharesParams) -> Result<ListSharesResult, (i32, S
                 "Authentication failed. Try using your full email as username (e.g., user@domain.com)".to_string(),
             ));
         }
+        if is_negotiation_error(&stderr) {
+            return Err(negotiation_error(
+                &describe_dialect(&params.credentials),
+                stderr.trim(),
+            ));
+        }
         return Err((
             error_codes::SMB_ERROR,
             format!("Failed to list shares: {}", stderr.trim()),
@@ -417,24 +572,20 @@ pub fn test_connection(
         )
     })?;
 
-    let smb_url = format!("smb://{}", params.credentials.hostname);
-
-    let mut credentials = PavaoCredentials::default()
-        .server(&smb_url)
-        .share("/")
-        .username(&params.credentials.username)
-        .password(&params.credentials.password);
-
-    if let Some(domain) = &params.credentials.domain {
-        credentials = credentials.workgroup(domain);
-    }
+    let client = connect(&params.credentials, "/")
+        .map_err(|(code, msg)| (code, format!("Connection failed: {}", msg)))?;
 
-    match SmbClient::new(credentials, SmbOptions::default()) {
+    // Creating the client doesn't touch the network; listing the server's shares
+    // negotiates a dialect and logs in. Only login and connection failures count,
+    // since some servers refuse to enumerate shares to a valid user.
+    match client.list_dir("") {
         Ok(_) => Ok(TestConnectionResult { success: true }),
-        Err(e) => {
-            let (code, msg) = map_smb_error(&e);
-            Err((code, format!("Connection failed: {}", msg)))
-        }
+        Err(e) => match map_smb_error(&e) {
+            (code @ (error_codes::SMB_AUTH_FAILED | error_codes::SMB_CONNECTION_FAILED), msg) => {
+                Err((code, format!("Connection failed: {}", msg)))
+            }
+            _ => Ok(TestConnectionResult { success: true }),
+        },
     }
 }
 
@@ -447,12 +598,7 @@ pub fn download_file(params: DownloadFileParams) -> Result<DownloadFileResult, (
         )
     })?;
 
-    let credentials = build_credentials(&params.credentials, &params.share);
-
-    let client = SmbClient::new(credentials, SmbOptions::default()).map_err(|e| {
-        let (code, msg) = map_smb_error(&e);
-        (code, format!("Failed to connect to SMB server: {}", msg))
-    })?;
+    let client = connect(&params.credentials, &params.share)?;
 
     // Open the remote file for reading
     let mut smb_file = client
@@ -512,12 +658,7 @@ pub fn download_partial(
         )
     })?;
 
-    let credentials = build_credentials(&params.credentials, &params.share);
-
-    let client = SmbClient::new(credentials, SmbOptions::default()).map_err(|e| {
-        let (code, msg) = map_smb_error(&e);
-        (code, format!("Failed to connect to SMB server: {}", msg))
-    })?;
+    let client = connect(&params.credentials, &params.share)?;
 
     // Get total file size first
     let stat = client.stat(&params.path).map_err(|e| {
@@ -574,7 +715,8 @@ pub fn download_partial(
     })
 }
 
-/// Upload a local file to SMB.
+/// Upload a local file to SMB, or the `length` bytes of it starting at `offset` so
+/// the app can send a large file in pieces and report progress between them.
 pub fn upload_file(params: UploadFileParams) -> Result<UploadFileResult, (i32, String)> {
     let _guard = SMB_MUTEX.lock().map_err(|e| {
         (
@@ -583,12 +725,7 @@ pub fn upload_file(params: UploadFileParams) -> Result<UploadFileResult, (i32, S
         )
     })?;
 
-    let credentials = build_credentials(&params.credentials, &params.share);
-
-    let client = SmbClient::new(credentials, SmbOptions::default()).map_err(|e| {
-        let (code, msg) = map_smb_error(&e);
-        (code, format!("Failed to connect to SMB server: {}", msg))
-    })?;
+    let client = connect(&params.credentials, &params.share)?;
 
     let mut local_file = std::fs::File::open(&params.source_path).map_err(|e| {
         (
@@ -597,20 +734,36 @@ pub fn upload_file(params: UploadFileParams) -> Result<UploadFileResult, (i32, S
         )
     })?;
 
+    let open_options = if params.offset == 0 {
+        SmbOpenOptions::default()
+            .write(true)
+            .create(true)
+            .exclusive(true)
+    } else {
+        SmbOpenOptions::default().write(true)
+    };
     let mut smb_file = client
-        .open_with(
-            &params.dest_path,
-            SmbOpenOptions::default()
-                .write(true)
-                .create(true)
-                .exclusive(true),
-        )
+        .open_with(&params.dest_path, open_options)
         .map_err(|e| {
             let (code, msg) = map_smb_error(&e);
             (code, format!("Failed to open SMB destination: {}", msg))
         })?;
 
-    let size = std::io::copy(&mut local_file, &mut smb_file).map_err(|e| {
+    if params.offset > 0 {
+        let seek = SeekFrom::Start(params.offset);
+        local_file
+            .seek(seek)
+            .and_then(|_| smb_file.seek(seek))
+            .map_err(|e| {
+                (
+                    error_codes::SMB_ERROR,
+                    format!("Failed to resume upload: {}", e),
+                )
+            })?;
+    }
+
+    let mut source = local_file.take(params.length.unwrap_or(u64::MAX));
+    let size = std::io::copy(&mut source, &mut smb_file).map_err(|e| {
         (
             error_codes::SMB_ERROR,
             format!("Failed to upload file: {}", e),
@@ -627,6 +780,34 @@ pub fn upload_file(params: UploadFileParams) -> Result<UploadFileResult, (i32, S
     Ok(UploadFileResult { size })
 }
 
+/// Total and free space of the volume holding a path.
+pub fn disk_usage(params: DiskUsageParams) -> Result<DiskUsageResult, (i32, String)> {
+    let _guard = SMB_MUTEX.lock().map_err(|e| {
+        (
+            error_codes::INTERNAL_ERROR,
+            format!("SMB mutex poisoned: {}", e),
+        )
+    })?;
+
+    let client = connect(&params.credentials, &params.share)?;
+
+    let stat = client.statvfs(&params.path).map_err(|e| {
+        let (code, msg) = map_smb_error(&e);
+        (code, format!("Failed to query free space: {}", msg))
+    })?;
+
+    // Block counts are in fragment units; some servers only fill in the block size
+    let unit = if stat.frsize > 0 {
+        stat.frsize
+    } else {
+        stat.bsize
+    };
+    Ok(DiskUsageResult {
+        total_bytes: stat.blocks.saturating_mul(unit),
+        available_bytes: stat.bavail.saturating_mul(unit),
+    })
+}
+
 /// Resolve the smbclient command, searching common paths on macOS.
 fn resolve_smbclient_command() -> (std::ffi::OsString, Option<std::ffi::OsString>) {
     use std::env;
@@ -120,6 +120,26 @@ pub struct RpcError {
     pub data: Option<serde_json::Value>,
 }
 
+/// SMB dialect a connection can be limited to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SmbProtocol {
+    /// SMB1
+    Nt1,
+    Smb2,
+    Smb3,
+}
+
+impl SmbProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmbProtocol::Nt1 => "NT1",
+            SmbProtocol::Smb2 => "SMB2",
+            SmbProtocol::Smb3 => "SMB3",
+        }
+    }
+}
+
 /// SMB credentials passed with each request.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SmbCredentials {
@@ -129,6 +149,12 @@ pub struct SmbCredentials {
     pub password: String,
     #[serde(default)]
     pub domain: Option<String>,
+    /// Oldest dialect to negotiate; `None` leaves it to libsmbclient
+    #[serde(default)]
+    pub min_protocol: Option<SmbProtocol>,
+    /// Newest dialect to negotiate; `None` leaves it to libsmbclient
+    #[serde(default)]
+    pub max_protocol: Option<SmbProtocol>,
 }
 
 /// Parameters for read_directory method.
@@ -139,6 +165,14 @@ pub struct ReadDirectoryParams {
     pub path: String,
 }
 
+/// Parameters for count_children method.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountChildrenParams {
+    pub credentials: SmbCredentials,
+    pub share: String,
+    pub path: String,
+}
+
 /// Parameters for get_file_metadata method.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GetFileMetadataParams {
@@ -212,14 +246,38 @@ pub struct UploadFileParams {
     pub source_path: String,
     /// SMB path to write the file to.
     pub dest_path: String,
+    /// Where in the file this call starts. Zero creates the destination (failing if it
+    /// exists); anything else continues a file an earlier call created.
+    #[serde(default)]
+    pub offset: u64,
+    /// Bytes to copy from `offset`; the rest of the file when absent.
+    #[serde(default)]
+    pub length: Option<u64>,
 }
 
 /// Result of upload_file.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UploadFileResult {
+    /// Bytes written by this call.
     pub size: u64,
 }
 
+/// Parameters for disk_usage method.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskUsageParams {
+    pub credentials: SmbCredentials,
+    pub share: String,
+    pub path: String,
+}
+
+/// Result of disk_usage: the size of the volume holding `path`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskUsageResult {
+    pub total_bytes: u64,
+    /// Free space available to the connected user.
+    pub available_bytes: u64,
+}
+
 /// A directory entry returned by read_directory.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DirectoryEntry {
@@ -238,6 +296,12 @@ pub struct ReadDirectoryResult {
     pub entries: Vec<DirectoryEntry>,
 }
 
+/// Result of count_children.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountChildrenResult {
+    pub count: u64,
+}
+
 /// Result of get_file_metadata.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileMetadataResult {
@@ -305,6 +369,7 @@ pub struct PingResult {
 pub mod methods {
     pub const PING: &str = "ping";
     pub const READ_DIRECTORY: &str = "read_directory";
+    pub const COUNT_CHILDREN: &str = "count_children";
     pub const GET_FILE_METADATA: &str = "get_file_metadata";
     pub const CREATE_DIRECTORY: &str = "create_directory";
     pub const DELETE: &str = "delete";
@@ -315,4 +380,5 @@ pub mod methods {
     pub const DOWNLOAD_FILE: &str = "download_file";
     pub const DOWNLOAD_PARTIAL: &str = "download_partial";
     pub const UPLOAD_FILE: &str = "upload_file";
+    pub const DISK_USAGE: &str = "disk_usage";
 }
@@ -0,0 +1,440 @@
+//! Directory listing sort shared by every provider, driven by the stored
+//! sort preferences (`sort_by`, ascending, folders first) of the directory
+//! being listed.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::fs_utils::FileItem;
+use crate::state::MenuState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Size,
+    Type,
+    Modified,
+    /// Group by extension, then by name within each group. Unlike `Type`, a descending
+    /// order only reverses the groups; names inside each group stay ascending.
+    TypeThenName,
+}
+
+impl SortKey {
+    /// Parse the `sort_by` string stored in menu state. Unknown values fall back to name.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "size" => SortKey::Size,
+            "type" => SortKey::Type,
+            "modified" => SortKey::Modified,
+            "typeName" | "type_name" => SortKey::TypeThenName,
+            _ => SortKey::Name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortOptions {
+    pub key: SortKey,
+    pub ascending: bool,
+    pub folders_first: bool,
+}
+
+impl Default for SortOptions {
+    fn default() -> Self {
+        Self {
+            key: SortKey::Name,
+            ascending: true,
+            folders_first: true,
+        }
+    }
+}
+
+impl SortOptions {
+    /// Read the current preferences from the menu state the UI keeps in sync.
+    pub fn from_menu_state<R: tauri::Runtime>(state: &MenuState<R>) -> Self {
+        let defaults = Self::default();
+        Self {
+            key: state
+                .current_sort_by
+                .lock()
+                .map(|s| SortKey::parse(&s))
+                .unwrap_or(defaults.key),
+            ascending: state
+                .sort_order_asc_checked
+                .lock()
+                .map(|v| *v)
+                .unwrap_or(defaults.ascending),
+            folders_first: state
+                .folders_first_checked
+                .lock()
+                .map(|v| *v)
+                .unwrap_or(defaults.folders_first),
+        }
+    }
+}
+
+/// Sort fields from a saved preferences object (`sortBy`, `sortOrder`, `foldersFirst`).
+/// Fields left unset fall through to whatever sits beneath them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SortOverrides {
+    pub key: Option<SortKey>,
+    pub ascending: Option<bool>,
+    pub folders_first: Option<bool>,
+}
+
+impl SortOverrides {
+    pub fn from_value(value: &Value) -> Self {
+        Self {
+            key: value
+                .get("sortBy")
+                .and_then(Value::as_str)
+                .map(SortKey::parse),
+            ascending: match value.get("sortOrder").and_then(Value::as_str) {
+                Some("asc") => Some(true),
+                Some("desc") => Some(false),
+                _ => None,
+            },
+            folders_first: value.get("foldersFirst").and_then(Value::as_bool),
+        }
+    }
+
+    /// Layer `self` over `below`, field by field.
+    pub fn or(self, below: Self) -> Self {
+        Self {
+            key: self.key.or(below.key),
+            ascending: self.ascending.or(below.ascending),
+            folders_first: self.folders_first.or(below.folders_first),
+        }
+    }
+
+    fn apply(self, options: SortOptions) -> SortOptions {
+        SortOptions {
+            key: self.key.unwrap_or(options.key),
+            ascending: self.ascending.unwrap_or(options.ascending),
+            folders_first: self.folders_first.unwrap_or(options.folders_first),
+        }
+    }
+}
+
+/// Sort preferences parsed out of the preferences file: the global ones plus those
+/// saved for individual directories, keyed by normalized path or URI.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SavedSortPreferences {
+    global: SortOverrides,
+    directories: HashMap<String, SortOverrides>,
+}
+
+impl SavedSortPreferences {
+    pub fn parse(prefs: &Value) -> Self {
+        let directories = prefs
+            .get("directoryPreferences")
+            .and_then(Value::as_object)
+            .map(|dirs| {
+                dirs.iter()
+                    .map(|(path, dir)| (path.clone(), SortOverrides::from_value(dir)))
+                    .filter(|(_, overrides)| *overrides != SortOverrides::default())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            global: prefs
+                .get("globalPreferences")
+                .map(SortOverrides::from_value)
+                .unwrap_or_default(),
+            directories,
+        }
+    }
+
+    /// The overrides for `directory`: its own entry wins, then the global preferences.
+    pub fn for_directory(&self, directory: &str) -> SortOverrides {
+        self.directories
+            .get(directory)
+            .copied()
+            .unwrap_or_default()
+            .or(self.global)
+    }
+}
+
+/// Order a listing for the UI. Every `read_directory` goes through here: the menu
+/// state gives the base options and `overrides` holds any saved preferences on top.
+pub fn sort_listing<R: tauri::Runtime>(
+    entries: &mut [FileItem],
+    menu_state: &MenuState<R>,
+    overrides: SortOverrides,
+) {
+    sort_entries(
+        entries,
+        &overrides.apply(SortOptions::from_menu_state(menu_state)),
+    );
+}
+
+/// Sort a listing in place. Folders-first grouping is never reversed by a descending
+/// order; within a group the key decides, and ties fall back to natural name order.
+pub fn sort_entries(entries: &mut [FileItem], options: &SortOptions) {
+    entries.sort_by(|a, b| compare_entries(a, b, options));
+}
+
+fn compare_entries(a: &FileItem, b: &FileItem, options: &SortOptions) -> Ordering {
+    if options.folders_first && a.is_directory != b.is_directory {
+        return if a.is_directory {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        };
+    }
+
+    let by_name = || natural_cmp(&a.name, &b.name);
+    let by_type = || {
+        let a_ext = a.extension.as_deref().unwrap_or("").to_lowercase();
+        let b_ext = b.extension.as_deref().unwrap_or("").to_lowercase();
+        a_ext.cmp(&b_ext)
+    };
+
+    let primary = match options.key {
+        SortKey::Name => by_name(),
+        SortKey::Size => a.size.cmp(&b.size),
+        SortKey::Modified => a.modified.cmp(&b.modified),
+        SortKey::Type => by_type(),
+        SortKey::TypeThenName => {
+            // Only the grouping follows the sort direction; names stay A→Z in each group
+            let groups = if options.ascending {
+                by_type()
+            } else {
+                by_type().reverse()
+            };
+            return groups.then_with(by_name);
+        }
+    };
+    let ordering = primary.then_with(by_name);
+
+    if options.ascending {
+        ordering
+    } else {
+        ordering.reverse()
+    }
+}
+
+/// Case-insensitive comparison that orders digit runs by numeric value,
+/// so "file2" sorts before "file10". Fullwidth digits count as digits too.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    // "01" sorts after "1", but only once the rest of the names compare equal
+    let mut zero_padding = Ordering::Equal;
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => break,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ac), Some(bc)) if digit_value(ac).is_some() && digit_value(bc).is_some() => {
+                let a_run = take_digits(&mut a_chars);
+                let b_run = take_digits(&mut b_chars);
+                let a_trimmed = a_run.trim_start_matches('0');
+                let b_trimmed = b_run.trim_start_matches('0');
+                let ordering = a_trimmed
+                    .len()
+                    .cmp(&b_trimmed.len())
+                    .then_with(|| a_trimmed.cmp(b_trimmed));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                if zero_padding == Ordering::Equal {
+                    zero_padding = a_run.len().cmp(&b_run.len());
+                }
+            }
+            (Some(ac), Some(bc)) => {
+                let ordering = ac.to_lowercase().cmp(bc.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+
+    // Names that differ only by case still need a stable order
+    zero_padding.then_with(|| a.cmp(b))
+}
+
+/// The ASCII digit a character stands for, accepting fullwidth forms like "２".
+fn digit_value(c: char) -> Option<char> {
+    match c {
+        '0'..='9' => Some(c),
+        '\u{FF10}'..='\u{FF19}' => char::from_u32(c as u32 - 0xFF10 + '0' as u32),
+        _ => None,
+    }
+}
+
+/// Consume a run of digits, normalised to ASCII so runs compare by value.
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut run = String::new();
+    while let Some(digit) = chars.peek().copied().and_then(digit_value) {
+        run.push(digit);
+        chars.next();
+    }
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn item(name: &str, is_directory: bool, size: u64) -> FileItem {
+        FileItem {
+            name: name.to_string(),
+            path: format!("/{}", name),
+            size,
+            modified: Utc.timestamp_opt(size as i64, 0).unwrap(),
+            is_directory,
+            is_hidden: false,
+            is_symlink: false,
+            is_git_repo: false,
+            extension: std::path::Path::new(name)
+                .extension()
+                .map(|e| e.to_string_lossy().to_string()),
+            child_count: None,
+            image_width: None,
+            image_height: None,
+            remote_id: None,
+            thumbnail_url: None,
+            download_url: None,
+            tags: None,
+            is_git_submodule: false,
+            lfs_size: None,
+            id: String::new(),
+        }
+    }
+
+    fn names(entries: &[FileItem]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn natural_order_compares_numbers_by_value() {
+        let mut entries = vec![
+            item("file10.txt", false, 1),
+            item("File2.txt", false, 2),
+            item("file1.txt", false, 3),
+        ];
+        sort_entries(&mut entries, &SortOptions::default());
+        assert_eq!(names(&entries), ["file1.txt", "File2.txt", "file10.txt"]);
+    }
+
+    #[test]
+    fn natural_order_handles_common_names() {
+        let mut sorted = vec![
+            "img10.png",
+            "IMG2.png",
+            "img1.png",
+            "file (10).txt",
+            "file (2).txt",
+            "file.txt",
+            "track007.mp3",
+            "track7.mp3",
+            "track08.mp3",
+            "Écran２.png",
+            "écran10.png",
+        ];
+        sorted.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            sorted,
+            [
+                "file (2).txt",
+                "file (10).txt",
+                "file.txt",
+                "img1.png",
+                "IMG2.png",
+                "img10.png",
+                "track7.mp3",
+                "track007.mp3",
+                "track08.mp3",
+                "Écran２.png",
+                "écran10.png",
+            ]
+        );
+    }
+
+    #[test]
+    fn folders_first_is_kept_when_descending() {
+        let mut entries = vec![
+            item("b.txt", false, 10),
+            item("Docs", true, 0),
+            item("a.txt", false, 20),
+            item("Art", true, 0),
+        ];
+        let options = SortOptions {
+            key: SortKey::Size,
+            ascending: false,
+            folders_first: true,
+        };
+        sort_entries(&mut entries, &options);
+        assert_eq!(names(&entries), ["Docs", "Art", "a.txt", "b.txt"]);
+
+        let mixed = SortOptions {
+            folders_first: false,
+            ..options
+        };
+        sort_entries(&mut entries, &mixed);
+        assert_eq!(names(&entries), ["a.txt", "b.txt", "Docs", "Art"]);
+    }
+
+    #[test]
+    fn directory_preferences_override_global_ones() {
+        let saved = SavedSortPreferences::parse(&serde_json::json!({
+            "globalPreferences": { "sortBy": "size", "sortOrder": "desc", "foldersFirst": false },
+            "directoryPreferences": {
+                "sftp://host/photos": { "sortBy": "modified" },
+                "/tmp/notes": { "viewMode": "list" }
+            }
+        }));
+
+        let photos = saved
+            .for_directory("sftp://host/photos")
+            .apply(SortOptions::default());
+        assert_eq!(photos.key, SortKey::Modified);
+        assert!(!photos.ascending);
+        assert!(!photos.folders_first);
+
+        assert_eq!(saved.for_directory("/tmp").key, Some(SortKey::Size));
+        assert_eq!(saved.directories.len(), 1);
+
+        let unset = SavedSortPreferences::parse(&serde_json::json!({ "sortOrder": "sideways" }));
+        assert_eq!(unset.for_directory("/tmp"), SortOverrides::default());
+    }
+
+    #[test]
+    fn type_then_name_groups_by_extension() {
+        let mut entries = vec![
+            item("z.png", false, 1),
+            item("b.jpg", false, 2),
+            item("a.png", false, 3),
+            item("a10.jpg", false, 4),
+            item("a9.jpg", false, 5),
+        ];
+        let options = SortOptions {
+            key: SortKey::parse("typeName"),
+            ascending: true,
+            folders_first: true,
+        };
+        sort_entries(&mut entries, &options);
+        assert_eq!(
+            names(&entries),
+            ["a9.jpg", "a10.jpg", "b.jpg", "a.png", "z.png"]
+        );
+
+        let descending = SortOptions {
+            ascending: false,
+            ..options
+        };
+        sort_entries(&mut entries, &descending);
+        assert_eq!(
+            names(&entries),
+            ["a.png", "z.png", "a9.jpg", "a10.jpg", "b.jpg"]
+        );
+    }
+}
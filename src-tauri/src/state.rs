@@ -7,6 +7,8 @@ use tauri::menu::CheckMenuItem;
 #[cfg(any(target_os = "windows", target_os = "linux"))]
 use trash::TrashItem;
 
+use crate::sorting::SavedSortPreferences;
+
 pub struct MenuState<R: tauri::Runtime> {
     pub show_hidden_item: Mutex<Option<CheckMenuItem<R>>>,
     pub show_hidden_checked: Mutex<bool>,
@@ -61,6 +63,57 @@ impl Default for DirectoryStreamState {
     }
 }
 
+/// Cancel flags for running `search_directory` calls, by request id
+#[derive(Default)]
+pub struct SearchState {
+    pub searches: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+/// Handle for a running `copy_tree`; `rollback` is read once the copy stops after a cancel
+#[derive(Clone)]
+pub struct CopyTreeHandle {
+    pub cancel_flag: Arc<AtomicBool>,
+    pub rollback: Arc<AtomicBool>,
+}
+
+/// State for tracking in-flight directory copies by request ID
+pub struct CopyTreeState {
+    pub tasks: Mutex<HashMap<String, CopyTreeHandle>>,
+}
+
+impl Default for CopyTreeState {
+    fn default() -> Self {
+        Self {
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Cancel flags for uploads to remote providers, by the copy or move's request ID
+#[derive(Default)]
+pub struct UploadState {
+    pub transfers: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+/// Sort preferences parsed on the first listing, dropped whenever preferences are saved
+#[derive(Default)]
+pub struct SortPreferencesState {
+    pub saved: Mutex<Option<Arc<SavedSortPreferences>>>,
+}
+
+/// Cancel flags for in-flight `get_last_commit_for_paths` lookups by request ID
+pub struct GitHistoryState {
+    pub tasks: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl Default for GitHistoryState {
+    fn default() -> Self {
+        Self {
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
 #[cfg(target_os = "macos")]
 #[derive(Clone)]
 pub struct MacTrashUndoItem {
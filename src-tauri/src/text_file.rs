@@ -0,0 +1,203 @@
+//! Reading text files in whatever encoding they were saved in.
+//!
+//! A BOM decides the encoding when there is one. Otherwise valid UTF-8 is taken as
+//! UTF-8, and anything else goes to chardetng, which guesses among the legacy
+//! encodings browsers support. Files with a lot of control bytes are refused as
+//! binary rather than decoded into noise.
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::commands::{error_codes, format_error};
+
+/// Text read when the caller doesn't ask for an amount
+pub const DEFAULT_TEXT_BYTES: usize = 64 * 1024;
+pub const MAX_TEXT_BYTES: usize = 1024 * 1024;
+/// Bytes looked at when deciding whether a file is binary
+const SNIFF_BYTES: usize = 8 * 1024;
+/// Share of control bytes above which a file is treated as binary. Random data sits
+/// around 10%; text has next to none.
+const MAX_CONTROL_RATIO: f64 = 0.05;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextContent {
+    pub text: String,
+    /// WHATWG name of the encoding the bytes were decoded from, e.g. "Shift_JIS"
+    pub encoding: String,
+    /// The file is longer than the bytes read
+    pub truncated: bool,
+}
+
+/// The encoding of `bytes` and the length of its BOM. `complete` is false when
+/// `bytes` is only the start of the file, so a character cut off at the end
+/// doesn't count against UTF-8.
+pub fn detect_encoding(bytes: &[u8], complete: bool) -> (&'static Encoding, usize) {
+    if let Some(found) = Encoding::for_bom(bytes) {
+        return found;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return (UTF_8, 0),
+        Err(e) if !complete && e.error_len().is_none() => return (UTF_8, 0),
+        Err(_) => {}
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, complete);
+    (detector.guess(None, true), 0)
+}
+
+/// Decode `bytes` with the detected encoding. When `complete` is false a character
+/// cut off at the end is dropped instead of becoming a replacement character.
+pub fn decode_text(bytes: &[u8], complete: bool) -> (String, &'static Encoding) {
+    let (encoding, bom_len) = detect_encoding(bytes, complete);
+    let bytes = &bytes[bom_len..];
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut text = String::with_capacity(
+        decoder
+            .max_utf8_buffer_length(bytes.len())
+            .unwrap_or(bytes.len()),
+    );
+    let _ = decoder.decode_to_string(bytes, &mut text, complete);
+    (text, encoding)
+}
+
+fn is_control_byte(byte: u8) -> bool {
+    // Tab, newlines, form feed and escape turn up in ordinary text and logs
+    matches!(byte, 0x00..=0x08 | 0x0E..=0x1A | 0x1C..=0x1F | 0x7F)
+}
+
+/// Whether the start of a file looks binary: too many control bytes and no UTF-16
+/// BOM to explain its NULs.
+pub fn looks_binary(head: &[u8]) -> bool {
+    if head.is_empty() || Encoding::for_bom(head).is_some() {
+        return false;
+    }
+    let control = head.iter().filter(|&&b| is_control_byte(b)).count();
+    control as f64 / head.len() as f64 > MAX_CONTROL_RATIO
+}
+
+/// Whether the local file at `path` looks binary, judging by its first few KB.
+pub fn is_binary_file(path: &Path) -> Result<bool, String> {
+    let (head, _) = read_head(path, SNIFF_BYTES)?;
+    Ok(looks_binary(&head))
+}
+
+/// Up to `max_bytes` from the start of the file, and whether there was more.
+pub fn read_head(path: &Path, max_bytes: usize) -> Result<(Vec<u8>, bool), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+    let mut bytes = Vec::new();
+    file.take(max_bytes as u64)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok((bytes, len > bytes.len() as u64))
+}
+
+/// How much of a remote file to download for reading `max_bytes` of it: one byte more,
+/// so the local head still shows whether the text was cut off.
+pub fn head_download_bytes(max_bytes: usize) -> u64 {
+    max_bytes.clamp(1, MAX_TEXT_BYTES) as u64 + 1
+}
+
+/// The first `max_bytes` of the local file at `path`, decoded to UTF-8. Fails with
+/// `EBINARY` when the file doesn't look like text.
+pub fn read_text(path: &Path, max_bytes: usize) -> Result<TextContent, String> {
+    let (bytes, truncated) = read_head(path, max_bytes.clamp(1, MAX_TEXT_BYTES))?;
+    if looks_binary(&bytes[..bytes.len().min(SNIFF_BYTES)]) {
+        return Err(format_error(
+            error_codes::EBINARY,
+            "This file looks like a binary file, not text",
+        ));
+    }
+    let (text, encoding) = decode_text(&bytes, !truncated);
+    Ok(TextContent {
+        text,
+        encoding: encoding.name().to_string(),
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+    #[test]
+    fn decodes_boms_utf8_and_legacy_text() {
+        let (text, encoding) = decode_text(b"\xEF\xBB\xBFhi", true);
+        assert_eq!((text.as_str(), encoding), ("hi", UTF_8));
+
+        let (text, encoding) = decode_text(&[0xFF, 0xFE, b'o', 0, b'k', 0], true);
+        assert_eq!((text.as_str(), encoding.name()), ("ok", "UTF-16LE"));
+
+        let (text, encoding) = decode_text(&[0xFE, 0xFF, 0, b'o', 0, b'k'], true);
+        assert_eq!((text.as_str(), encoding.name()), ("ok", "UTF-16BE"));
+
+        // "café" cut in the middle of the é
+        let (text, encoding) = decode_text(&"café".as_bytes()[..4], false);
+        assert_eq!((text.as_str(), encoding), ("caf", UTF_8));
+
+        let (text, encoding) = decode_text(b"caf\xE9", true);
+        assert_eq!((text.as_str(), encoding), ("café", WINDOWS_1252));
+    }
+
+    #[test]
+    fn detects_shift_jis() {
+        let source = "日本語のテキストファイルです。文字化けしないように読み込みます。";
+        let (bytes, _, _) = SHIFT_JIS.encode(source);
+        let (text, encoding) = decode_text(&bytes, true);
+        assert_eq!(encoding, SHIFT_JIS);
+        assert_eq!(text, source);
+    }
+
+    #[test]
+    fn tells_binary_from_text() {
+        assert!(!looks_binary(b"plain ascii\r\n\twith tabs\x0c"));
+        assert!(!looks_binary(&[0xFF, 0xFE, b'o', 0, b'k', 0]));
+        assert!(looks_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+    }
+
+    #[test]
+    fn read_text_reports_truncation_and_binary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let text_path = dir.path().join("big.txt");
+        std::fs::write(&text_path, "x".repeat(100)).unwrap();
+
+        let content = read_text(&text_path, 10).unwrap();
+        assert!(content.truncated);
+        assert_eq!(content.text.len(), 10);
+        assert_eq!(content.encoding, "UTF-8");
+
+        let binary_path = dir.path().join("blob.bin");
+        std::fs::write(&binary_path, [0u8, 1, 2, 3, 4, 5, b'a', b'b']).unwrap();
+        let error = read_text(&binary_path, 1024).unwrap_err();
+        assert!(error.starts_with("[EBINARY]"));
+    }
+
+    #[test]
+    fn downloaded_heads_still_report_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let head_path = dir.path().join("head.txt");
+        let head_len = head_download_bytes(10) as usize;
+
+        // A remote file longer than asked for arrives as a head one byte too long
+        std::fs::write(&head_path, "x".repeat(head_len)).unwrap();
+        let content = read_text(&head_path, 10).unwrap();
+        assert!(content.truncated);
+        assert_eq!(content.text.len(), 10);
+
+        // One exactly as long as asked for arrives whole
+        std::fs::write(&head_path, "x".repeat(10)).unwrap();
+        assert!(!read_text(&head_path, 10).unwrap().truncated);
+
+        assert_eq!(head_download_bytes(0), 2);
+        assert_eq!(head_download_bytes(usize::MAX), MAX_TEXT_BYTES as u64 + 1);
+    }
+}
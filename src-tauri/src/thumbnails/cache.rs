@@ -8,7 +8,7 @@ use std::sync::Arc;
 use tokio::fs;
 use tokio::sync::RwLock;
 
-use super::AccentColor;
+use super::{AccentColor, CACHE_FORMAT_VERSION};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheEntry {
@@ -24,6 +24,10 @@ pub struct CacheEntry {
     /// Original image height in pixels (if available)
     #[serde(default)]
     pub image_height: Option<u32>,
+    /// When set, this entry is a reference and the data lives in the shared
+    /// content-addressed entry with this key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_key: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -37,6 +41,56 @@ pub struct CacheStats {
     pub total_misses: u64,
 }
 
+/// Animated previews are stored next to the still thumbnail of the same file.
+fn animated_variant(key: String, animated: bool) -> String {
+    if animated {
+        format!("{key}-anim")
+    } else {
+        key
+    }
+}
+
+/// Directory holding the disk cache of one format version
+fn version_dir_name(version: u32) -> String {
+    format!("v{version}")
+}
+
+/// Delete cache data written by other format versions under `root`: `v<N>` directories,
+/// plus the loose entries and `shared` directory left from before caches were versioned.
+/// Anything else in `root` is left alone. Returns how many items were removed.
+pub async fn prune_stale_versions(root: &Path, current: u32) -> usize {
+    let Ok(mut entries) = fs::read_dir(root).await else {
+        return 0;
+    };
+    let current = version_dir_name(current);
+    let mut removed = 0;
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Ok(file_type) = entry.file_type().await else {
+            continue;
+        };
+        let is_version_dir = name
+            .strip_prefix('v')
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+
+        let result =
+            if file_type.is_dir() && name != current && (is_version_dir || name == "shared") {
+                fs::remove_dir_all(entry.path()).await
+            } else if file_type.is_file() && name.ends_with(".json") {
+                fs::remove_file(entry.path()).await
+            } else {
+                continue;
+            };
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!("Failed to prune old thumbnail cache {}: {}", name, e),
+        }
+    }
+
+    removed
+}
+
 pub struct ThumbnailCache {
     // L1: In-memory LRU cache for hot thumbnails
     memory_cache: Arc<RwLock<LruCache<String, CacheEntry>>>,
@@ -45,6 +99,13 @@ pub struct ThumbnailCache {
     disk_cache_dir: PathBuf,
     disk_cache_index: Arc<RwLock<HashMap<String, CacheEntry>>>,
 
+    // Content-addressed entries shared by path entries with identical source content
+    shared_cache_dir: PathBuf,
+    shared_cache_index: Arc<RwLock<HashMap<String, CacheEntry>>>,
+
+    // Content fingerprints by path, with the size and mtime they were read at
+    fingerprints: Arc<RwLock<LruCache<PathBuf, (u64, u128, Option<String>)>>>,
+
     // Stats tracking
     stats: Arc<RwLock<CacheStats>>,
 
@@ -56,15 +117,21 @@ pub struct ThumbnailCache {
 
 impl ThumbnailCache {
     pub async fn new() -> Result<Self, String> {
-        let cache_dir = dirs::cache_dir()
+        let cache_root = dirs::cache_dir()
             .ok_or("Could not determine cache directory")?
             .join("marlin_thumbnails");
+        let cache_dir = cache_root.join(version_dir_name(CACHE_FORMAT_VERSION));
 
         // Create cache directory if it doesn't exist
         fs::create_dir_all(&cache_dir)
             .await
             .map_err(|e| format!("Failed to create cache directory: {}", e))?;
 
+        let shared_dir = cache_dir.join("shared");
+        fs::create_dir_all(&shared_dir)
+            .await
+            .map_err(|e| format!("Failed to create shared cache directory: {}", e))?;
+
         let memory_cache = LruCache::new(
             NonZeroUsize::new(1000).unwrap(), // Max 1000 entries in memory
         );
@@ -73,6 +140,9 @@ impl ThumbnailCache {
             memory_cache: Arc::new(RwLock::new(memory_cache)),
             disk_cache_dir: cache_dir,
             disk_cache_index: Arc::new(RwLock::new(HashMap::new())),
+            shared_cache_dir: shared_dir,
+            shared_cache_index: Arc::new(RwLock::new(HashMap::new())),
+            fingerprints: Arc::new(RwLock::new(LruCache::new(NonZeroUsize::new(4096).unwrap()))),
             stats: Arc::new(RwLock::new(CacheStats {
                 memory_entries: 0,
                 memory_size_bytes: 0,
@@ -90,6 +160,15 @@ impl ThumbnailCache {
         // Load disk cache index
         cache.load_disk_cache_index().await?;
 
+        // Thumbnails from older versions can't be served any more; reclaim their space
+        // without holding up startup
+        tokio::spawn(async move {
+            let removed = prune_stale_versions(&cache_root, CACHE_FORMAT_VERSION).await;
+            if removed > 0 {
+                log::info!("Pruned {} stale thumbnail cache entries", removed);
+            }
+        });
+
         Ok(cache)
     }
 
@@ -99,8 +178,11 @@ impl ThumbnailCache {
         path: &str,
         size: u32,
         accent: Option<&AccentColor>,
+        animated: bool,
     ) -> Option<(String, bool, Option<u32>, Option<u32>)> {
-        let cache_key = self.generate_cache_key(path, size, accent).await?;
+        let cache_key = self
+            .generate_cache_key(path, size, accent, animated)
+            .await?;
 
         // Try L1 memory cache first
         {
@@ -135,6 +217,34 @@ impl ThumbnailCache {
             return Some((data_url, has_transparency, image_width, image_height));
         }
 
+        // Identical content may already be cached under another path; link to it
+        if let Some(content_key) = self.content_key_for(path, size, accent, animated).await {
+            if let Some(shared) = self.get_shared(&content_key).await {
+                if let Err(err) = self
+                    .put_disk_reference(&cache_key, &content_key, &shared)
+                    .await
+                {
+                    log::debug!("Failed to link thumbnail to shared entry: {err}");
+                }
+                self.put_memory(
+                    &cache_key,
+                    &shared.data_url,
+                    0,
+                    shared.has_transparency,
+                    shared.image_width,
+                    shared.image_height,
+                )
+                .await;
+                self.record_hit().await;
+                return Some((
+                    shared.data_url,
+                    shared.has_transparency,
+                    shared.image_width,
+                    shared.image_height,
+                ));
+            }
+        }
+
         self.record_miss().await;
         None
     }
@@ -144,6 +254,7 @@ impl ThumbnailCache {
         path: &str,
         size: u32,
         accent: Option<&AccentColor>,
+        animated: bool,
         data_url: String,
         generation_time_ms: u64,
         has_transparency: bool,
@@ -151,7 +262,7 @@ impl ThumbnailCache {
         image_height: Option<u32>,
     ) -> Result<(), String> {
         let cache_key = self
-            .generate_cache_key(path, size, accent)
+            .generate_cache_key(path, size, accent, animated)
             .await
             .ok_or("Failed to generate cache key")?;
 
@@ -165,8 +276,104 @@ impl ThumbnailCache {
             image_height,
         )
         .await;
-        self.put_disk(
-            &cache_key,
+
+        match self.content_key_for(path, size, accent, animated).await {
+            Some(content_key) => {
+                let shared = self
+                    .put_shared(
+                        &content_key,
+                        &data_url,
+                        generation_time_ms,
+                        has_transparency,
+                        image_width,
+                        image_height,
+                    )
+                    .await?;
+                self.put_disk_reference(&cache_key, &content_key, &shared)
+                    .await?;
+            }
+            None => {
+                self.put_disk(
+                    &cache_key,
+                    &data_url,
+                    generation_time_ms,
+                    has_transparency,
+                    image_width,
+                    image_height,
+                )
+                .await?;
+            }
+        }
+
+        // Cleanup if necessary
+        self.cleanup_if_needed().await?;
+
+        Ok(())
+    }
+
+    /// Look up a rendering stored under a content key alone, for sources that
+    /// aren't files. Returns (data_url, has_transparency, image_width, image_height)
+    pub async fn get_content(
+        &self,
+        content_key: &str,
+    ) -> Option<(String, bool, Option<u32>, Option<u32>)> {
+        {
+            let mut memory_cache = self.memory_cache.write().await;
+            if let Some(entry) = memory_cache.get_mut(content_key) {
+                entry.last_accessed = Utc::now();
+                self.record_hit().await;
+                return Some((
+                    entry.data_url.clone(),
+                    entry.has_transparency,
+                    entry.image_width,
+                    entry.image_height,
+                ));
+            }
+        }
+
+        let Some(shared) = self.get_shared(content_key).await else {
+            self.record_miss().await;
+            return None;
+        };
+        self.put_memory(
+            content_key,
+            &shared.data_url,
+            0,
+            shared.has_transparency,
+            shared.image_width,
+            shared.image_height,
+        )
+        .await;
+        self.record_hit().await;
+        Some((
+            shared.data_url,
+            shared.has_transparency,
+            shared.image_width,
+            shared.image_height,
+        ))
+    }
+
+    /// Store a rendering under a content key, in memory and in the shared disk entries.
+    pub async fn put_content(
+        &self,
+        content_key: &str,
+        data_url: String,
+        generation_time_ms: u64,
+        has_transparency: bool,
+        image_width: Option<u32>,
+        image_height: Option<u32>,
+    ) -> Result<(), String> {
+        self.put_memory(
+            content_key,
+            &data_url,
+            generation_time_ms,
+            has_transparency,
+            image_width,
+            image_height,
+        )
+        .await;
+        self.put_shared(
+            content_key,
             &data_url,
             generation_time_ms,
             has_transparency,
@@ -174,11 +381,7 @@ impl ThumbnailCache {
             image_height,
         )
         .await?;
-
-        // Cleanup if necessary
-        self.cleanup_if_needed().await?;
-
-        Ok(())
+        self.cleanup_if_needed().await
     }
 
     async fn put_memory(
@@ -199,6 +402,7 @@ impl ThumbnailCache {
             has_transparency,
             image_width,
             image_height,
+            content_key: None,
         };
 
         let mut memory_cache = self.memory_cache.write().await;
@@ -246,6 +450,7 @@ impl ThumbnailCache {
             has_transparency,
             image_width,
             image_height,
+            content_key: None,
         };
 
         // Write to disk
@@ -264,6 +469,157 @@ impl ThumbnailCache {
         Ok(())
     }
 
+    /// Store (or refresh) the shared content-addressed entry and return it.
+    async fn put_shared(
+        &self,
+        content_key: &str,
+        data_url: &str,
+        generation_time_ms: u64,
+        has_transparency: bool,
+        image_width: Option<u32>,
+        image_height: Option<u32>,
+    ) -> Result<CacheEntry, String> {
+        {
+            let mut index = self.shared_cache_index.write().await;
+            if let Some(existing) = index.get_mut(content_key) {
+                existing.last_accessed = Utc::now();
+                if existing.data_url == data_url {
+                    return Ok(existing.clone());
+                }
+            }
+        }
+
+        let entry = CacheEntry {
+            data_url: data_url.to_string(),
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            size_bytes: data_url.len(),
+            generation_time_ms,
+            has_transparency,
+            image_width,
+            image_height,
+            content_key: None,
+        };
+
+        let file_path = self.shared_cache_dir.join(format!("{}.json", content_key));
+        let json = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize cache entry: {}", e))?;
+        fs::write(&file_path, json)
+            .await
+            .map_err(|e| format!("Failed to write cache file: {}", e))?;
+
+        let mut index = self.shared_cache_index.write().await;
+        index.insert(content_key.to_string(), entry.clone());
+
+        Ok(entry)
+    }
+
+    /// Write a path-keyed entry that points at a shared entry instead of holding data.
+    async fn put_disk_reference(
+        &self,
+        key: &str,
+        content_key: &str,
+        shared: &CacheEntry,
+    ) -> Result<(), String> {
+        let entry = CacheEntry {
+            data_url: String::new(),
+            created_at: Utc::now(),
+            last_accessed: Utc::now(),
+            size_bytes: 0,
+            generation_time_ms: shared.generation_time_ms,
+            has_transparency: shared.has_transparency,
+            image_width: shared.image_width,
+            image_height: shared.image_height,
+            content_key: Some(content_key.to_string()),
+        };
+
+        let file_path = self.disk_cache_dir.join(format!("{}.json", key));
+        let json = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize cache entry: {}", e))?;
+        fs::write(&file_path, json)
+            .await
+            .map_err(|e| format!("Failed to write cache file: {}", e))?;
+
+        let mut index = self.disk_cache_index.write().await;
+        index.insert(key.to_string(), entry);
+
+        Ok(())
+    }
+
+    async fn get_shared(&self, content_key: &str) -> Option<CacheEntry> {
+        {
+            let index = self.shared_cache_index.read().await;
+            if !index.contains_key(content_key) {
+                return None;
+            }
+        }
+
+        let file_path = self.shared_cache_dir.join(format!("{}.json", content_key));
+        let entry = match fs::read_to_string(&file_path).await {
+            Ok(json) => serde_json::from_str::<CacheEntry>(&json).ok(),
+            Err(_) => None,
+        };
+
+        let mut index = self.shared_cache_index.write().await;
+        match entry {
+            Some(entry) => {
+                if let Some(existing) = index.get_mut(content_key) {
+                    existing.last_accessed = Utc::now();
+                }
+                Some(entry)
+            }
+            None => {
+                index.remove(content_key);
+                None
+            }
+        }
+    }
+
+    /// Content-addressed key for local files, or `None` when dedup doesn't apply.
+    async fn content_key_for(
+        &self,
+        path: &str,
+        size: u32,
+        accent: Option<&AccentColor>,
+        animated: bool,
+    ) -> Option<String> {
+        if !super::content_dedup_enabled() || path.contains("://") {
+            return None;
+        }
+
+        let fingerprint = self.content_fingerprint(PathBuf::from(path)).await?;
+        Some(animated_variant(
+            super::generate_content_cache_key(&fingerprint, size, accent),
+            animated,
+        ))
+    }
+
+    /// `content_fingerprint` of `path`, reused while its size and mtime are unchanged so
+    /// the lookup before generating and the store after it read the file once.
+    async fn content_fingerprint(&self, path: PathBuf) -> Option<String> {
+        let identity_path = path.clone();
+        let identity =
+            tokio::task::spawn_blocking(move || super::get_file_identity(&identity_path))
+                .await
+                .ok()?;
+        if let Some((size, mtime_ns, fingerprint)) = self.fingerprints.write().await.get(&path) {
+            if *size == identity.size && *mtime_ns == identity.mtime_ns {
+                return fingerprint.clone();
+            }
+        }
+
+        let read_path = path.clone();
+        let fingerprint =
+            tokio::task::spawn_blocking(move || super::content_fingerprint(&read_path))
+                .await
+                .ok()?;
+        self.fingerprints.write().await.put(
+            path,
+            (identity.size, identity.mtime_ns, fingerprint.clone()),
+        );
+        fingerprint
+    }
+
     async fn get_from_disk(&self, key: &str) -> Option<(String, bool, Option<u32>, Option<u32>)> {
         // Check index first
         {
@@ -278,6 +634,18 @@ impl ThumbnailCache {
         let json = fs::read_to_string(&file_path).await.ok()?;
         let entry: CacheEntry = serde_json::from_str(&json).ok()?;
 
+        // Resolve references to the shared entry; drop dangling references
+        let entry = match entry.content_key.as_deref() {
+            Some(content_key) => match self.get_shared(content_key).await {
+                Some(shared) => shared,
+                None => {
+                    let _ = self.remove_from_disk(key).await;
+                    return None;
+                }
+            },
+            None => entry,
+        };
+
         // Update last accessed time
         {
             let mut index = self.disk_cache_index.write().await;
@@ -299,6 +667,7 @@ impl ThumbnailCache {
         path: &str,
         size: u32,
         accent: Option<&AccentColor>,
+        animated: bool,
     ) -> Option<String> {
         // Handle SMB paths specially - they can't use std::path for identity
         let identity = if path.starts_with("smb://") {
@@ -334,7 +703,10 @@ impl ThumbnailCache {
             let path_obj = Path::new(path);
             super::get_file_identity(path_obj)
         };
-        Some(super::generate_cache_key(path, size, &identity, accent))
+        Some(animated_variant(
+            super::generate_cache_key(path, size, &identity, accent),
+            animated,
+        ))
     }
 
     async fn load_disk_cache_index(&mut self) -> Result<(), String> {
@@ -371,6 +743,26 @@ impl ThumbnailCache {
         let index_len = index.len();
         *self.disk_cache_index.write().await = index;
 
+        let mut shared_index = HashMap::new();
+        if let Ok(mut shared_entries) = fs::read_dir(&self.shared_cache_dir).await {
+            while let Ok(Some(entry)) = shared_entries.next_entry().await {
+                let path = entry.path();
+                if path.extension().map_or(true, |ext| ext != "json") {
+                    continue;
+                }
+                let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                if let Ok(json) = fs::read_to_string(&path).await {
+                    if let Ok(cache_entry) = serde_json::from_str::<CacheEntry>(&json) {
+                        total_size += cache_entry.size_bytes as u64;
+                        shared_index.insert(stem.to_string(), cache_entry);
+                    }
+                }
+            }
+        }
+        *self.shared_cache_index.write().await = shared_index;
+
         // Update stats
         let mut stats = self.stats.write().await;
         stats.disk_entries = index_len;
@@ -392,20 +784,31 @@ impl ThumbnailCache {
 
     async fn cleanup_disk_cache(&self) -> Result<(), String> {
         let mut entries_to_remove = Vec::new();
+        let mut shared_to_remove = Vec::new();
 
         {
             let index = self.disk_cache_index.read().await;
-            let mut entries: Vec<_> = index.iter().collect();
+            let shared_index = self.shared_cache_index.read().await;
+            // (is_shared, key, entry) - references are tiny, shared entries carry the bytes
+            let mut entries: Vec<_> = index
+                .iter()
+                .map(|(key, entry)| (false, key, entry))
+                .chain(shared_index.iter().map(|(key, entry)| (true, key, entry)))
+                .collect();
 
             // Sort by last accessed time (oldest first)
-            entries.sort_by_key(|(_, entry)| entry.last_accessed);
+            entries.sort_by_key(|(_, _, entry)| entry.last_accessed);
 
             let mut current_size = 0;
-            for (key, entry) in entries.iter().rev() {
+            for (is_shared, key, entry) in entries.iter().rev() {
                 current_size += entry.size_bytes;
                 if current_size > self.max_disk_size_bytes * 3 / 4 {
                     // Keep 75% of max size
-                    entries_to_remove.push(key.to_string());
+                    if *is_shared {
+                        shared_to_remove.push(key.to_string());
+                    } else {
+                        entries_to_remove.push(key.to_string());
+                    }
                 }
             }
         }
@@ -414,6 +817,12 @@ impl ThumbnailCache {
         for key in entries_to_remove {
             self.remove_from_disk(&key).await?;
         }
+        // References to evicted shared entries are dropped lazily on next lookup
+        for key in shared_to_remove {
+            let file_path = self.shared_cache_dir.join(format!("{}.json", key));
+            let _ = fs::remove_file(&file_path).await;
+            self.shared_cache_index.write().await.remove(&key);
+        }
 
         Ok(())
     }
@@ -515,11 +924,18 @@ impl ThumbnailCache {
             let _ = fs::remove_file(entry.path()).await;
         }
 
+        if let Ok(mut shared_entries) = fs::read_dir(&self.shared_cache_dir).await {
+            while let Ok(Some(entry)) = shared_entries.next_entry().await {
+                let _ = fs::remove_file(entry.path()).await;
+            }
+        }
+
         // Clear index
         {
             let mut index = self.disk_cache_index.write().await;
             index.clear();
         }
+        self.shared_cache_index.write().await.clear();
 
         // Reset stats
         {
@@ -538,3 +954,30 @@ impl ThumbnailCache {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn prunes_caches_from_other_versions() {
+        let root = tempfile::tempdir().unwrap();
+        let root = root.path();
+        for dir in ["v2/shared", "v3/shared", "v10", "shared", "other"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("0123456789abcdef.json"), "{}").unwrap();
+        std::fs::write(root.join("v3/0123456789abcdef.json"), "{}").unwrap();
+        std::fs::write(root.join("notes.txt"), "keep").unwrap();
+
+        assert_eq!(prune_stale_versions(root, 3).await, 4);
+
+        let mut left: Vec<String> = std::fs::read_dir(root)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["notes.txt", "other", "v3"]);
+        assert!(root.join("v3/0123456789abcdef.json").exists());
+    }
+}
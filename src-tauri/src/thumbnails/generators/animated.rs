@@ -0,0 +1,211 @@
+use super::super::{ThumbnailGenerationResult, ThumbnailQuality, ThumbnailRequest};
+use super::images::DEFAULT_MAX_DECODE_PIXELS;
+use super::ThumbnailGenerator;
+use base64::Engine as _;
+use image::codecs::gif::GifDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, DynamicImage, Frames, ImageDecoder, RgbaImage};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Frames kept in the output; longer clips are sampled evenly down to this.
+const MAX_OUTPUT_FRAMES: usize = 48;
+/// Decoding stops after this many frames, however short they are.
+const MAX_DECODED_FRAMES: usize = 240;
+/// Only the start of long animations is previewed.
+const MAX_DURATION_MS: u32 = 6_000;
+/// Bigger outputs aren't worth keeping over the first frame.
+const MAX_OUTPUT_BYTES: usize = 2 * 1024 * 1024;
+/// Browsers play delays below 20ms at 100ms; match them so previews don't race.
+const MIN_FRAME_DELAY_MS: u32 = 20;
+const DEFAULT_FRAME_DELAY_MS: u32 = 100;
+
+/// One thumbnail-sized frame and how long it stays on screen.
+struct PreviewFrame {
+    image: RgbaImage,
+    delay_ms: u32,
+}
+
+/// Animated WebP preview of an animated GIF or WebP. Returns `Ok(None)` for still
+/// images, and for clips too big to preview, so the caller renders a single frame.
+pub fn generate(request: &ThumbnailRequest) -> Result<Option<ThumbnailGenerationResult>, String> {
+    let path = Path::new(&request.path);
+    let Some(((width, height), frames)) = open_frames(path)? else {
+        return Ok(None);
+    };
+    if u64::from(width) * u64::from(height) > DEFAULT_MAX_DECODE_PIXELS {
+        return Ok(None);
+    }
+
+    let frames = sample_frames(read_frames(frames, request)?, MAX_OUTPUT_FRAMES);
+    if frames.len() < 2 {
+        return Ok(None);
+    }
+
+    let has_transparency = frames
+        .iter()
+        .any(|frame| frame.image.pixels().any(|pixel| pixel.0[3] < 255));
+    let Some(encoded) = encode_animation(&frames, request.quality)? else {
+        return Ok(None);
+    };
+    if encoded.len() > MAX_OUTPUT_BYTES {
+        log::debug!(
+            "Animated thumbnail for {} is {} bytes; using a still frame",
+            request.path,
+            encoded.len()
+        );
+        return Ok(None);
+    }
+
+    Ok(Some(ThumbnailGenerationResult {
+        data_url: format!(
+            "data:image/webp;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(&encoded)
+        ),
+        has_transparency,
+        image_width: Some(width),
+        image_height: Some(height),
+    }))
+}
+
+/// Canvas size and frame iterator, or `None` when the file isn't animated.
+fn open_frames(path: &Path) -> Result<Option<((u32, u32), Frames<'static>)>, String> {
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase());
+    let reader =
+        BufReader::new(File::open(path).map_err(|e| format!("Failed to open image file: {}", e))?);
+
+    match extension.as_deref() {
+        Some("gif") => {
+            let decoder =
+                GifDecoder::new(reader).map_err(|e| format!("Failed to read GIF: {}", e))?;
+            Ok(Some((decoder.dimensions(), decoder.into_frames())))
+        }
+        Some("webp") => {
+            let decoder =
+                WebPDecoder::new(reader).map_err(|e| format!("Failed to read WebP: {}", e))?;
+            if !decoder.has_animation() {
+                return Ok(None);
+            }
+            Ok(Some((decoder.dimensions(), decoder.into_frames())))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Decode frames up to the duration and frame caps, shrinking each one right away so
+/// only thumbnail-sized buffers are held.
+fn read_frames(
+    frames: Frames<'_>,
+    request: &ThumbnailRequest,
+) -> Result<Vec<PreviewFrame>, String> {
+    let mut previews = Vec::new();
+    let mut elapsed_ms = 0u32;
+
+    for frame in frames.take(MAX_DECODED_FRAMES) {
+        let frame = frame.map_err(|e| format!("Failed to decode animation frame: {}", e))?;
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let delay_ms = match numer / denom.max(1) {
+            ms if ms >= MIN_FRAME_DELAY_MS => ms,
+            _ => DEFAULT_FRAME_DELAY_MS,
+        };
+
+        let resized = ThumbnailGenerator::resize_image(
+            DynamicImage::ImageRgba8(frame.into_buffer()),
+            request.size,
+            request.quality,
+        )?;
+        previews.push(PreviewFrame {
+            image: resized.to_rgba8(),
+            delay_ms,
+        });
+
+        elapsed_ms = elapsed_ms.saturating_add(delay_ms);
+        if elapsed_ms >= MAX_DURATION_MS {
+            break;
+        }
+    }
+
+    Ok(previews)
+}
+
+/// Keep every n-th frame so at most `max` remain, folding the dropped frames' time into
+/// the kept ones so the clip plays at its original speed.
+fn sample_frames(frames: Vec<PreviewFrame>, max: usize) -> Vec<PreviewFrame> {
+    if frames.len() <= max {
+        return frames;
+    }
+    let stride = frames.len().div_ceil(max);
+    let mut sampled: Vec<PreviewFrame> = Vec::with_capacity(max);
+    for (index, frame) in frames.into_iter().enumerate() {
+        if index % stride == 0 {
+            sampled.push(frame);
+        } else if let Some(kept) = sampled.last_mut() {
+            kept.delay_ms = kept.delay_ms.saturating_add(frame.delay_ms);
+        }
+    }
+    sampled
+}
+
+fn encode_animation(
+    frames: &[PreviewFrame],
+    quality: ThumbnailQuality,
+) -> Result<Option<Vec<u8>>, String> {
+    let Some(first) = frames.first() else {
+        return Ok(None);
+    };
+    let (width, height) = first.image.dimensions();
+
+    let mut config =
+        webp::WebPConfig::new().map_err(|_| "Failed to configure WebP encoder".to_string())?;
+    config.quality = match quality {
+        ThumbnailQuality::Low => 50.0,
+        ThumbnailQuality::Medium => 75.0,
+        ThumbnailQuality::High => 90.0,
+    };
+
+    let mut encoder = webp::AnimEncoder::new(width, height, &config);
+    let mut timestamp_ms = 0i32;
+    for frame in frames {
+        encoder.add_frame(webp::AnimFrame::from_rgba(
+            frame.image.as_raw(),
+            width,
+            height,
+            timestamp_ms,
+        ));
+        timestamp_ms = timestamp_ms.saturating_add(frame.delay_ms as i32);
+    }
+
+    let encoded = encoder
+        .try_encode()
+        .map_err(|e| format!("Failed to encode animated WebP: {:?}", e))?;
+    Ok(Some(encoded.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(delays: &[u32]) -> Vec<PreviewFrame> {
+        delays
+            .iter()
+            .map(|&delay_ms| PreviewFrame {
+                image: RgbaImage::new(1, 1),
+                delay_ms,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sampling_keeps_the_total_duration() {
+        let sampled = sample_frames(frames(&[100; 10]), 4);
+        let delays: Vec<u32> = sampled.iter().map(|f| f.delay_ms).collect();
+        assert_eq!(delays, [300, 300, 300, 100]);
+
+        let untouched = sample_frames(frames(&[40, 60]), 4);
+        assert_eq!(untouched.len(), 2);
+    }
+}
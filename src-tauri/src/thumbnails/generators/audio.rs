@@ -0,0 +1,48 @@
+use super::super::{ThumbnailGenerationResult, ThumbnailRequest};
+use super::ThumbnailGenerator;
+use lofty::file::TaggedFileExt;
+use lofty::picture::{Picture, PictureType};
+use std::path::Path;
+
+/// Returned when a track decodes fine but carries no cover art, so the frontend can
+/// keep the regular audio icon instead of treating it as a failure.
+pub const NO_EMBEDDED_ARTWORK: &str = "[NO_EMBEDDED_ARTWORK] Track has no embedded artwork";
+
+/// Album art embedded in the track's tags (ID3v2, Vorbis comments, MP4 atoms, ...).
+pub struct AudioGenerator;
+
+impl AudioGenerator {
+    pub fn generate(request: &ThumbnailRequest) -> Result<ThumbnailGenerationResult, String> {
+        let path = Path::new(&request.path);
+        let tagged =
+            lofty::read_from_path(path).map_err(|e| format!("Failed to read audio tags: {}", e))?;
+
+        let pictures: Vec<&Picture> = tagged
+            .primary_tag()
+            .into_iter()
+            .chain(tagged.tags())
+            .flat_map(|tag| tag.pictures())
+            .collect();
+        // Front cover first, then whatever else the tags hold
+        let picture = pictures
+            .iter()
+            .find(|picture| picture.pic_type() == PictureType::CoverFront)
+            .or_else(|| pictures.first())
+            .ok_or_else(|| NO_EMBEDDED_ARTWORK.to_string())?;
+
+        let image = image::load_from_memory(picture.data())
+            .map_err(|e| format!("Failed to decode embedded artwork: {}", e))?;
+
+        let resized = ThumbnailGenerator::resize_image(image, request.size, request.quality)?;
+        let data_url =
+            ThumbnailGenerator::encode_to_data_url(&resized, request.format, request.quality)?;
+
+        Ok(ThumbnailGenerationResult {
+            data_url,
+            has_transparency: false,
+            // The cover's size isn't the track's
+            image_width: None,
+            image_height: None,
+        })
+    }
+}
@@ -0,0 +1,243 @@
+use super::super::{ThumbnailGenerationResult, ThumbnailRequest};
+use super::ThumbnailGenerator;
+use image::DynamicImage;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Office documents (Word, Excel, PowerPoint, OpenDocument).
+///
+/// Most editors save a small first-page preview inside the package:
+/// `docProps/thumbnail.*` for Office Open XML and `Thumbnails/thumbnail.png` for
+/// OpenDocument. When there isn't one, and the `libreoffice` feature is enabled, the
+/// document is converted to PDF with a headless LibreOffice and rendered like any PDF.
+pub struct DocumentGenerator;
+
+/// Preview images never get close to this; anything bigger isn't a thumbnail.
+const MAX_EMBEDDED_THUMBNAIL_BYTES: u64 = 16 * 1024 * 1024;
+
+impl DocumentGenerator {
+    pub fn generate(request: &ThumbnailRequest) -> Result<ThumbnailGenerationResult, String> {
+        let path = Path::new(&request.path);
+
+        if let Some(image) = embedded_thumbnail(path) {
+            let resized = ThumbnailGenerator::resize_image(image, request.size, request.quality)?;
+            let data_url =
+                ThumbnailGenerator::encode_to_data_url(&resized, request.format, request.quality)?;
+            return Ok(ThumbnailGenerationResult {
+                data_url,
+                has_transparency: false,
+                image_width: None,
+                image_height: None,
+            });
+        }
+
+        #[cfg(feature = "libreoffice")]
+        {
+            libreoffice::generate(request)
+        }
+        #[cfg(not(feature = "libreoffice"))]
+        {
+            Err("Document has no embedded thumbnail".to_string())
+        }
+    }
+}
+
+/// Decode the preview image saved inside an OOXML or OpenDocument package, if any.
+/// WMF/EMF previews (written by some Windows apps) can't be decoded and are skipped.
+fn embedded_thumbnail(path: &Path) -> Option<DynamicImage> {
+    let file = File::open(path).ok()?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file)).ok()?;
+
+    let names: Vec<String> = archive
+        .file_names()
+        .filter(|name| {
+            let lower = name.to_ascii_lowercase();
+            lower.starts_with("docprops/thumbnail.") || lower == "thumbnails/thumbnail.png"
+        })
+        .map(str::to_string)
+        .collect();
+
+    names.iter().find_map(|name| {
+        let entry = archive.by_name(name).ok()?;
+        if entry.size() > MAX_EMBEDDED_THUMBNAIL_BYTES {
+            return None;
+        }
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry
+            .take(MAX_EMBEDDED_THUMBNAIL_BYTES)
+            .read_to_end(&mut bytes)
+            .ok()?;
+        image::load_from_memory(&bytes).ok()
+    })
+}
+
+#[cfg(feature = "libreoffice")]
+mod libreoffice {
+    //! First-page previews via `soffice --headless --convert-to pdf`.
+
+    use super::super::super::{ThumbnailGenerationResult, ThumbnailRequest};
+    use super::super::pdf::PdfGenerator;
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+    use uuid::Uuid;
+
+    /// Large spreadsheets can take a while, but a stuck conversion must not hold a
+    /// thumbnail worker forever.
+    const CONVERSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Held for each conversion. A second soffice started on a profile that's in use
+    /// hands its work to the running one and exits, so conversions sharing the profile
+    /// have to take turns.
+    static PROFILE_LOCK: Mutex<()> = Mutex::new(());
+
+    fn soffice_candidates() -> Vec<PathBuf> {
+        let mut candidates = vec![PathBuf::from("soffice"), PathBuf::from("libreoffice")];
+        if cfg!(target_os = "macos") {
+            candidates.push(PathBuf::from(
+                "/Applications/LibreOffice.app/Contents/MacOS/soffice",
+            ));
+        }
+        if cfg!(target_os = "windows") {
+            candidates.push(PathBuf::from(
+                r"C:\Program Files\LibreOffice\program\soffice.exe",
+            ));
+        }
+        candidates
+    }
+
+    pub(super) fn generate(
+        request: &ThumbnailRequest,
+    ) -> Result<ThumbnailGenerationResult, String> {
+        let out_dir = std::env::temp_dir().join(format!("marlin-doc-thumb-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&out_dir)
+            .map_err(|e| format!("Failed to create conversion directory: {}", e))?;
+
+        let result = convert_to_pdf(Path::new(&request.path), &out_dir).and_then(|pdf| {
+            let mut pdf_request = request.clone();
+            pdf_request.path = pdf.to_string_lossy().to_string();
+            PdfGenerator::generate(&pdf_request).map(|mut result| {
+                // The size of the rendered page says nothing about the document
+                result.image_width = None;
+                result.image_height = None;
+                result
+            })
+        });
+
+        let _ = std::fs::remove_dir_all(&out_dir);
+        result
+    }
+
+    fn convert_to_pdf(document: &Path, out_dir: &Path) -> Result<PathBuf, String> {
+        // A profile of our own, so conversions work while the user has LibreOffice open
+        let profile = std::env::temp_dir().join("marlin-soffice-profile");
+        let profile_url = url::Url::from_directory_path(&profile)
+            .map_err(|_| "Invalid LibreOffice profile path".to_string())?;
+        let _profile = PROFILE_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let mut child = None;
+        for soffice in soffice_candidates() {
+            match Command::new(&soffice)
+                .arg(format!("-env:UserInstallation={}", profile_url))
+                .args(["--headless", "--norestore", "--convert-to", "pdf"])
+                .arg("--outdir")
+                .arg(out_dir)
+                .arg(document)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+            {
+                Ok(spawned) => {
+                    child = Some(spawned);
+                    break;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("Failed to run LibreOffice: {}", e)),
+            }
+        }
+        let mut child = child.ok_or_else(|| {
+            "Document has no embedded thumbnail and LibreOffice is not installed".to_string()
+        })?;
+
+        let started = Instant::now();
+        let status = loop {
+            if let Some(status) = child
+                .try_wait()
+                .map_err(|e| format!("Failed to wait for LibreOffice: {}", e))?
+            {
+                break status;
+            }
+            if started.elapsed() > CONVERSION_TIMEOUT {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err("LibreOffice timed out converting the document".to_string());
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        };
+        if !status.success() {
+            return Err(format!("LibreOffice exited with status {}", status));
+        }
+
+        let stem = document
+            .file_stem()
+            .ok_or_else(|| "Document has no file name".to_string())?;
+        let pdf = out_dir.join(format!("{}.pdf", stem.to_string_lossy()));
+        if !pdf.exists() {
+            return Err("LibreOffice did not produce a PDF".to_string());
+        }
+        Ok(pdf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GenericImageView, ImageFormat, RgbImage};
+    use std::io::{Cursor, Write};
+    use zip::write::SimpleFileOptions;
+
+    fn write_package(path: &Path, thumbnail: Option<&str>) {
+        let mut writer = zip::ZipWriter::new(File::create(path).unwrap());
+        writer
+            .start_file("[Content_Types].xml", SimpleFileOptions::default())
+            .unwrap();
+        writer.write_all(b"<Types/>").unwrap();
+        if let Some(name) = thumbnail {
+            let mut png = Vec::new();
+            DynamicImage::ImageRgb8(RgbImage::new(40, 30))
+                .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+                .unwrap();
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(&png).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn reads_embedded_thumbnails() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let docx = dir.path().join("report.docx");
+        write_package(&docx, Some("docProps/thumbnail.png"));
+        assert_eq!(embedded_thumbnail(&docx).unwrap().dimensions(), (40, 30));
+
+        let odt = dir.path().join("notes.odt");
+        write_package(&odt, Some("Thumbnails/thumbnail.png"));
+        assert!(embedded_thumbnail(&odt).is_some());
+
+        let bare = dir.path().join("bare.xlsx");
+        write_package(&bare, None);
+        assert!(embedded_thumbnail(&bare).is_none());
+
+        let legacy = dir.path().join("old.doc");
+        std::fs::write(&legacy, b"\xD0\xCF\x11\xE0 not a zip").unwrap();
+        assert!(embedded_thumbnail(&legacy).is_none());
+    }
+}
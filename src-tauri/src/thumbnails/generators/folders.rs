@@ -0,0 +1,162 @@
+use super::super::{
+    ThumbnailFormat, ThumbnailGenerationResult, ThumbnailQuality, ThumbnailRequest,
+};
+use super::images::{ImageGenerator, DEFAULT_MAX_DECODE_PIXELS};
+use super::ThumbnailGenerator;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Folder previews built from the first few images inside the folder.
+///
+/// The thumbnail cache keys entries on the folder's identity, which includes its mtime,
+/// so adding, removing or renaming a child produces a fresh preview on its own.
+pub struct FolderGenerator;
+
+/// Images shown in one preview
+const MAX_IMAGES: usize = 4;
+/// Directory entries looked at before giving up; huge folders must not stall a worker
+const MAX_SCANNED_ENTRIES: usize = 500;
+
+impl FolderGenerator {
+    pub fn generate(request: &ThumbnailRequest) -> Result<ThumbnailGenerationResult, String> {
+        let path = Path::new(&request.path);
+        let images = Self::find_images(path)?;
+
+        let size = request.size.max(16);
+        let cells = Self::layout(images.len(), size);
+        let mut canvas = RgbaImage::from_pixel(size, size, Rgba([0, 0, 0, 0]));
+        let mut drawn = 0;
+        for (image_path, (x, y, width, height)) in images.iter().zip(cells) {
+            // A broken image just leaves its cell empty
+            let Ok((image, _)) = ImageGenerator::load_upright(
+                image_path,
+                width.max(height),
+                DEFAULT_MAX_DECODE_PIXELS,
+            ) else {
+                continue;
+            };
+            let tile = image.resize_to_fill(width, height, Self::filter(request.quality));
+            imageops::overlay(&mut canvas, &tile.to_rgba8(), i64::from(x), i64::from(y));
+            drawn += 1;
+        }
+        if drawn == 0 {
+            return Err("Folder has no images to preview".to_string());
+        }
+
+        // The gaps between tiles are transparent
+        let format = if request.format == ThumbnailFormat::JPEG {
+            ThumbnailFormat::PNG
+        } else {
+            request.format
+        };
+        let data_url = ThumbnailGenerator::encode_to_data_url(
+            &DynamicImage::ImageRgba8(canvas),
+            format,
+            request.quality,
+        )?;
+
+        Ok(ThumbnailGenerationResult {
+            data_url,
+            // Only the gaps are see-through; no checkerboard behind a collage
+            has_transparency: false,
+            image_width: None,
+            image_height: None,
+        })
+    }
+
+    /// Up to [`MAX_IMAGES`] visible images, in name order so the preview is stable.
+    fn find_images(dir: &Path) -> Result<Vec<PathBuf>, String> {
+        let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read folder: {}", e))?;
+        let mut images: Vec<PathBuf> = entries
+            .take(MAX_SCANNED_ENTRIES)
+            .flatten()
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+            .map(|entry| entry.path())
+            .filter(|path| ThumbnailGenerator::is_image_file(path))
+            .collect();
+        images.sort();
+        images.truncate(MAX_IMAGES);
+        Ok(images)
+    }
+
+    /// Cells (x, y, width, height) for `count` images on a `size` square: one image
+    /// fills it, two sit side by side, three or four share a 2x2 grid.
+    fn layout(count: usize, size: u32) -> Vec<(u32, u32, u32, u32)> {
+        let gap = (size / 48).max(1);
+        let half = (size - gap) / 2;
+        let far = size - half;
+        match count {
+            0 => Vec::new(),
+            1 => vec![(0, 0, size, size)],
+            2 => vec![(0, 0, half, size), (far, 0, half, size)],
+            _ => vec![
+                (0, 0, half, half),
+                (far, 0, half, half),
+                (0, far, half, half),
+                (far, far, half, half),
+            ],
+        }
+    }
+
+    fn filter(quality: ThumbnailQuality) -> FilterType {
+        match quality {
+            ThumbnailQuality::Low => FilterType::Nearest,
+            ThumbnailQuality::Medium => FilterType::Triangle,
+            ThumbnailQuality::High => FilterType::Lanczos3,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, RgbImage};
+
+    #[test]
+    fn picks_visible_images_in_name_order() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "e.png",
+            "b.jpg",
+            ".hidden.png",
+            "a.png",
+            "notes.txt",
+            "c.gif",
+            "d.png",
+        ] {
+            let path = dir.path().join(name);
+            if name.ends_with(".txt") {
+                fs::write(&path, "not an image").unwrap();
+            } else {
+                let format = ImageFormat::from_path(&path).unwrap();
+                DynamicImage::ImageRgb8(RgbImage::new(4, 4))
+                    .save_with_format(&path, format)
+                    .unwrap();
+            }
+        }
+        fs::create_dir(dir.path().join("nested.png")).unwrap();
+
+        let names: Vec<String> = FolderGenerator::find_images(dir.path())
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["a.png", "b.jpg", "c.gif", "d.png"]);
+    }
+
+    #[test]
+    fn layout_fits_the_square() {
+        for count in 1..=4 {
+            for (x, y, width, height) in FolderGenerator::layout(count, 128) {
+                assert!(x + width <= 128 && y + height <= 128);
+            }
+        }
+        assert_eq!(
+            FolderGenerator::layout(2, 128),
+            [(0, 0, 63, 128), (65, 0, 63, 128)]
+        );
+    }
+}
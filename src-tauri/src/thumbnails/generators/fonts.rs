@@ -1,45 +1,51 @@
 use super::super::{ThumbnailGenerationResult, ThumbnailRequest};
+use super::woff;
 use ab_glyph::{Font, FontRef, PxScale, ScaleFont};
 use image::{DynamicImage, Rgba, RgbaImage};
 use std::fs;
 
+/// Samples tried in order; the first one the font fully covers is drawn. The
+/// Latin pair shows an ascender and a descender, the rest cover fonts that
+/// only ship other scripts.
+const SAMPLES: &[&str] = &[
+    "Ag", "Бж", "Αβ", "אב", "اب", "あア", "永", "가", "अ", "ก", "123",
+];
+
 pub struct FontGenerator;
 
 impl FontGenerator {
     pub fn generate(request: &ThumbnailRequest) -> Result<ThumbnailGenerationResult, String> {
-        let font_data =
+        let mut font_data =
             fs::read(&request.path).map_err(|e| format!("Failed to read font file: {}", e))?;
+        if woff::is_web_font(&font_data) {
+            font_data = woff::to_sfnt(&font_data)?;
+        }
 
         let font = FontRef::try_from_slice(&font_data)
             .map_err(|e| format!("Failed to parse font: {}", e))?;
 
         let size = request.size;
 
-        // Create a white background image
-        let mut img = RgbaImage::from_pixel(size, size, Rgba([255u8, 255u8, 255u8, 255u8]));
+        // Glyphs are drawn onto a transparent canvas so the grid's background shows through
+        let mut img = RgbaImage::new(size, size);
 
-        // Sample text that shows character variety (uppercase, lowercase, descender)
-        let sample_text = "Ag";
+        let sample_text = Self::pick_sample(&font, &font_data);
 
         // Calculate font size to fit nicely in the thumbnail (use ~60% of the size)
         let target_height = size as f32 * 0.55;
 
         // Get font metrics to determine proper scaling
-        let scale = Self::calculate_scale_for_height(&font, target_height);
-        let scaled_font = font.as_scaled(scale);
-
-        // Calculate text width for centering
-        let mut text_width = 0.0f32;
-        let mut prev_glyph: Option<ab_glyph::GlyphId> = None;
-
-        for c in sample_text.chars() {
-            let glyph_id = scaled_font.glyph_id(c);
-            if let Some(prev) = prev_glyph {
-                text_width += scaled_font.kern(prev, glyph_id);
-            }
-            text_width += scaled_font.h_advance(glyph_id);
-            prev_glyph = Some(glyph_id);
+        let mut scale = Self::calculate_scale_for_height(&font, target_height);
+
+        // Family names can be long, so shrink until the text fits the width too
+        let max_width = size as f32 * 0.85;
+        let text_width = Self::text_width(&font, scale, &sample_text);
+        if text_width > max_width {
+            let shrink = max_width / text_width;
+            scale = PxScale::from(scale.y * shrink);
         }
+        let scaled_font = font.as_scaled(scale);
+        let text_width = Self::text_width(&font, scale, &sample_text);
 
         // Calculate baseline position for vertical centering
         let ascent = scaled_font.ascent();
@@ -70,27 +76,16 @@ impl FontGenerator {
             if let Some(outlined) = scaled_font.outline_glyph(glyph) {
                 let bounds = outlined.px_bounds();
                 outlined.draw(|x, y, coverage| {
-                    let px = (bounds.min.x as i32 + x as i32) as u32;
-                    let py = (bounds.min.y as i32 + y as i32) as u32;
-
-                    if px < size && py < size {
-                        // Anti-aliased black text on white background
-                        let alpha = (coverage * 255.0) as u8;
-                        if alpha > 0 {
-                            let pixel = img.get_pixel_mut(px, py);
-                            // Blend black text onto white background
-                            let bg = 255u8;
-                            let fg = 0u8;
-                            let blend = |bg: u8, fg: u8, a: u8| -> u8 {
-                                let a_f = a as f32 / 255.0;
-                                ((fg as f32 * a_f) + (bg as f32 * (1.0 - a_f))) as u8
-                            };
-                            *pixel = Rgba([
-                                blend(bg, fg, alpha),
-                                blend(bg, fg, alpha),
-                                blend(bg, fg, alpha),
-                                255,
-                            ]);
+                    let px = bounds.min.x as i32 + x as i32;
+                    let py = bounds.min.y as i32 + y as i32;
+
+                    if (0..size as i32).contains(&px) && (0..size as i32).contains(&py) {
+                        // Black text whose coverage becomes alpha; overlapping glyphs
+                        // keep the stronger coverage
+                        let alpha = (coverage.clamp(0.0, 1.0) * 255.0) as u8;
+                        let pixel = img.get_pixel_mut(px as u32, py as u32);
+                        if alpha > pixel[3] {
+                            *pixel = Rgba([0, 0, 0, alpha]);
                         }
                     }
                 });
@@ -106,12 +101,69 @@ impl FontGenerator {
 
         Ok(ThumbnailGenerationResult {
             data_url,
-            has_transparency: false,
+            has_transparency: true,
             image_width: Some(size),
             image_height: Some(size),
         })
     }
 
+    /// The first sample the font has glyphs for, then its family name, then the
+    /// first couple of characters it maps at all (symbol and icon fonts).
+    fn pick_sample(font: &FontRef, font_data: &[u8]) -> String {
+        let covers = |text: &str| {
+            text.chars()
+                .filter(|c| !c.is_whitespace())
+                .all(|c| font.glyph_id(c).0 != 0)
+        };
+
+        if let Some(sample) = SAMPLES.iter().find(|sample| covers(sample)) {
+            return sample.to_string();
+        }
+        if let Some(name) = Self::family_name(font_data).filter(|name| covers(name)) {
+            return name;
+        }
+
+        let mut mapped: Vec<char> = font
+            .codepoint_ids()
+            .map(|(_, c)| c)
+            .filter(|c| !c.is_control() && !c.is_whitespace())
+            .collect();
+        mapped.sort_unstable();
+        mapped.truncate(2);
+        if mapped.is_empty() {
+            SAMPLES[0].to_string()
+        } else {
+            mapped.into_iter().collect()
+        }
+    }
+
+    fn family_name(font_data: &[u8]) -> Option<String> {
+        let face = ttf_parser::Face::parse(font_data, 0).ok()?;
+        face.names()
+            .into_iter()
+            .filter(|name| name.name_id == ttf_parser::name_id::FAMILY)
+            .find_map(|name| name.to_string())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Advance width of `text` at `scale`, including kerning.
+    fn text_width(font: &FontRef, scale: PxScale, text: &str) -> f32 {
+        let scaled_font = font.as_scaled(scale);
+        let mut width = 0.0f32;
+        let mut prev_glyph: Option<ab_glyph::GlyphId> = None;
+
+        for c in text.chars() {
+            let glyph_id = scaled_font.glyph_id(c);
+            if let Some(prev) = prev_glyph {
+                width += scaled_font.kern(prev, glyph_id);
+            }
+            width += scaled_font.h_advance(glyph_id);
+            prev_glyph = Some(glyph_id);
+        }
+        width
+    }
+
     /// Calculate the scale needed to achieve approximately the target height
     fn calculate_scale_for_height(font: &FontRef, target_height: f32) -> PxScale {
         // Start with a reference scale
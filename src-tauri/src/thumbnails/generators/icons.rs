@@ -0,0 +1,216 @@
+use super::super::{ThumbnailFormat, ThumbnailGenerationResult, ThumbnailRequest};
+use super::ThumbnailGenerator;
+use image::{DynamicImage, RgbaImage};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Icon containers (`.ico`, `.icns`) hold several renditions of the same icon.
+/// Instead of letting a decoder grab the first one, pick the rendition that best
+/// fits the requested thumbnail size and decode only that.
+pub struct IconGenerator;
+
+/// One embedded rendition, as listed in the container's directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Candidate {
+    index: usize,
+    width: u32,
+    height: u32,
+    /// Colour depth where the container records it; higher wins between equal sizes.
+    depth: u16,
+}
+
+impl Candidate {
+    fn edge(&self) -> u32 {
+        self.width.max(self.height)
+    }
+}
+
+impl IconGenerator {
+    pub fn generate(request: &ThumbnailRequest) -> Result<ThumbnailGenerationResult, String> {
+        let path = Path::new(&request.path);
+        let extension = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase());
+
+        let (image, largest) = match extension.as_deref() {
+            Some("icns") => Self::load_icns(path, request.size)?,
+            _ => Self::load_ico(path, request.size)?,
+        };
+
+        let has_transparency = match &image {
+            DynamicImage::ImageRgba8(img) => img.pixels().any(|p| p.0[3] < 255),
+            _ => false,
+        };
+
+        let resized = ThumbnailGenerator::resize_image(image, request.size, request.quality)?;
+
+        // Icons are almost always transparent; keep the alpha channel
+        let format = if request.format == ThumbnailFormat::JPEG {
+            ThumbnailFormat::PNG
+        } else {
+            request.format
+        };
+        let data_url = ThumbnailGenerator::encode_to_data_url(&resized, format, request.quality)?;
+
+        Ok(ThumbnailGenerationResult {
+            data_url,
+            has_transparency,
+            // Report the largest rendition as the icon's size, not the one we happened to pick
+            image_width: Some(largest.0),
+            image_height: Some(largest.1),
+        })
+    }
+
+    fn load_ico(path: &Path, target: u32) -> Result<(DynamicImage, (u32, u32)), String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open icon file: {}", e))?;
+        let dir = ico::IconDir::read(BufReader::new(file))
+            .map_err(|e| format!("Failed to parse ICO file: {}", e))?;
+
+        let candidates: Vec<Candidate> = dir
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| Candidate {
+                index,
+                width: entry.width(),
+                height: entry.height(),
+                depth: entry.bits_per_pixel(),
+            })
+            .collect();
+        let largest = largest_size(&candidates).ok_or("ICO file has no images")?;
+
+        let mut last_error = String::new();
+        for candidate in rank_candidates(&candidates, target) {
+            match dir.entries()[candidate.index].decode() {
+                Ok(icon) => {
+                    let rgba =
+                        RgbaImage::from_raw(icon.width(), icon.height(), icon.rgba_data().to_vec())
+                            .ok_or("Decoded ICO buffer size mismatch")?;
+                    return Ok((DynamicImage::ImageRgba8(rgba), largest));
+                }
+                Err(e) => last_error = format!("Failed to decode ICO image: {}", e),
+            }
+        }
+        Err(last_error)
+    }
+
+    fn load_icns(path: &Path, target: u32) -> Result<(DynamicImage, (u32, u32)), String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open icon file: {}", e))?;
+        let family = icns::IconFamily::read(BufReader::new(file))
+            .map_err(|e| format!("Failed to parse ICNS file: {}", e))?;
+
+        let icon_types = family.available_icons();
+        let candidates: Vec<Candidate> = icon_types
+            .iter()
+            .enumerate()
+            .map(|(index, icon_type)| Candidate {
+                index,
+                width: icon_type.pixel_width(),
+                height: icon_type.pixel_height(),
+                depth: 32,
+            })
+            .collect();
+        let largest = largest_size(&candidates).ok_or("ICNS file has no images")?;
+
+        // Some renditions are JPEG 2000, which the icns crate can't decode; fall through
+        // to the next best one when that happens.
+        let mut last_error = String::new();
+        for candidate in rank_candidates(&candidates, target) {
+            match family.get_icon_with_type(icon_types[candidate.index]) {
+                Ok(icon) => {
+                    let icon = icon.convert_to(icns::PixelFormat::RGBA);
+                    let rgba =
+                        RgbaImage::from_raw(icon.width(), icon.height(), icon.into_data().into())
+                            .ok_or("Decoded ICNS buffer size mismatch")?;
+                    return Ok((DynamicImage::ImageRgba8(rgba), largest));
+                }
+                Err(e) => last_error = format!("Failed to decode ICNS image: {}", e),
+            }
+        }
+        Err(last_error)
+    }
+}
+
+fn largest_size(candidates: &[Candidate]) -> Option<(u32, u32)> {
+    candidates
+        .iter()
+        .max_by_key(|c| (c.edge(), c.depth))
+        .map(|c| (c.width, c.height))
+}
+
+/// Order renditions by preference: the smallest one that covers `target` first
+/// (so we only ever downscale), then progressively smaller ones if none does.
+fn rank_candidates(candidates: &[Candidate], target: u32) -> Vec<Candidate> {
+    let mut ranked = candidates.to_vec();
+    ranked.sort_by(|a, b| {
+        let a_covers = a.edge() >= target;
+        let b_covers = b.edge() >= target;
+        b_covers
+            .cmp(&a_covers)
+            .then_with(|| {
+                if a_covers {
+                    a.edge().cmp(&b.edge())
+                } else {
+                    b.edge().cmp(&a.edge())
+                }
+            })
+            .then_with(|| b.depth.cmp(&a.depth))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn candidate(index: usize, edge: u32, depth: u16) -> Candidate {
+        Candidate {
+            index,
+            width: edge,
+            height: edge,
+            depth,
+        }
+    }
+
+    #[test]
+    fn prefers_smallest_rendition_covering_target() {
+        let candidates = [
+            candidate(0, 16, 32),
+            candidate(1, 256, 32),
+            candidate(2, 48, 8),
+            candidate(3, 48, 32),
+            candidate(4, 32, 32),
+        ];
+        let order: Vec<usize> = rank_candidates(&candidates, 40)
+            .iter()
+            .map(|c| c.index)
+            .collect();
+        assert_eq!(order, [3, 2, 1, 4, 0]);
+
+        // Nothing is big enough: take the largest
+        assert_eq!(rank_candidates(&candidates, 512)[0].index, 1);
+    }
+
+    #[test]
+    fn decodes_the_matching_ico_entry() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("app.ico");
+
+        let mut icon_dir = ico::IconDir::new(ico::ResourceType::Icon);
+        for size in [16u32, 64, 128] {
+            let rgba = vec![200u8; (size * size * 4) as usize];
+            let image = ico::IconImage::from_rgba_data(size, size, rgba);
+            icon_dir.add_entry(ico::IconDirEntry::encode(&image).expect("encode entry"));
+        }
+        icon_dir
+            .write(File::create(&path).expect("create ico"))
+            .expect("write ico");
+
+        let (image, largest) = IconGenerator::load_ico(&path, 48).expect("load ico");
+        assert_eq!((image.width(), image.height()), (64, 64));
+        assert_eq!(largest, (128, 128));
+    }
+}
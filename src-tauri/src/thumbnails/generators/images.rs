@@ -1,19 +1,67 @@
-use super::super::{get_thumbnail_format_from_path, ThumbnailGenerationResult, ThumbnailRequest};
+use super::super::{
+    get_thumbnail_format_from_path, ThumbnailFormat, ThumbnailGenerationResult, ThumbnailRequest,
+};
 use super::ThumbnailGenerator;
-use image::{DynamicImage, GenericImageView, ImageReader};
+use image::metadata::Orientation;
+use image::{DynamicImage, GenericImageView, ImageFormat, ImageReader, Limits};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
 use std::path::Path;
 
+/// Default pixel budget for a full-resolution decode (~100 megapixels).
+/// Anything larger must be downscaled during decode or is rejected as too large.
+pub const DEFAULT_MAX_DECODE_PIXELS: u64 = 100_000_000;
+
+/// Worst-case bytes per decoded pixel (RGBA, 16 bits per channel).
+const MAX_BYTES_PER_PIXEL: u64 = 8;
+
+/// Errors produced while loading a source image for thumbnailing.
+#[derive(Debug)]
+pub enum ImageLoadError {
+    /// The image exceeds the decode budget and cannot be downscaled on decode.
+    TooLarge { width: u32, height: u32 },
+    /// Any other open/probe/decode failure.
+    Failed(String),
+}
+
+impl fmt::Display for ImageLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageLoadError::TooLarge { width, height } => {
+                write!(f, "Image is too large to thumbnail ({width}x{height})")
+            }
+            ImageLoadError::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
 pub struct ImageGenerator;
 
 impl ImageGenerator {
     pub fn generate(request: &ThumbnailRequest) -> Result<ThumbnailGenerationResult, String> {
+        Self::generate_with_budget(request, DEFAULT_MAX_DECODE_PIXELS)
+    }
+
+    /// Same as `generate`, but with an explicit pixel budget for the decoded buffer.
+    pub fn generate_with_budget(
+        request: &ThumbnailRequest,
+        max_pixels: u64,
+    ) -> Result<ThumbnailGenerationResult, String> {
         let path = Path::new(&request.path);
 
-        // Load the image
-        let image = Self::load_image(path)?;
+        #[cfg(feature = "webp")]
+        if request.format == ThumbnailFormat::AnimatedWebP {
+            match super::animated::generate(request) {
+                Ok(Some(result)) => return Ok(result),
+                Ok(None) => {}
+                Err(err) => log::debug!("Animated thumbnail failed, using a still frame: {err}"),
+            }
+        }
 
-        // Get original dimensions before resizing
-        let (image_width, image_height) = image.dimensions();
+        // Load the image (possibly downscaled on decode) along with its original dimensions
+        let (image, (image_width, image_height)) =
+            Self::load_upright(path, request.size, max_pixels).map_err(|e| e.to_string())?;
 
         // Check if the original image has transparency
         let has_transparency = Self::has_transparency(&image);
@@ -22,7 +70,10 @@ impl ImageGenerator {
         let resized = ThumbnailGenerator::resize_image(image, request.size, request.quality)?;
 
         // Determine output format (prefer the format specified in request, or infer from path)
-        let format = if request.format != super::super::ThumbnailFormat::WebP {
+        let format = if !matches!(
+            request.format,
+            ThumbnailFormat::WebP | ThumbnailFormat::AnimatedWebP
+        ) {
             request.format
         } else {
             get_thumbnail_format_from_path(path)
@@ -38,19 +89,234 @@ impl ImageGenerator {
         })
     }
 
-    fn load_image(path: &Path) -> Result<DynamicImage, String> {
-        // Use image crate's built-in format detection
-        let reader =
-            ImageReader::open(path).map_err(|e| format!("Failed to open image file: {}", e))?;
+    /// [`Self::load_image`], turned upright the way photo viewers do. The returned
+    /// dimensions are the original ones, as displayed.
+    pub(super) fn load_upright(
+        path: &Path,
+        target_size: u32,
+        max_pixels: u64,
+    ) -> Result<(DynamicImage, (u32, u32)), ImageLoadError> {
+        let (mut image, (mut width, mut height)) = Self::load_image(path, target_size, max_pixels)?;
+        if let Some(orientation) = Self::read_orientation(path) {
+            image.apply_orientation(orientation);
+            if Self::swaps_dimensions(orientation) {
+                std::mem::swap(&mut width, &mut height);
+            }
+        }
+        Ok((image, (width, height)))
+    }
+
+    /// Decode an image without ever materializing more than `max_pixels` pixels.
+    /// Returns the decoded image and the original (full-resolution) dimensions.
+    fn load_image(
+        path: &Path,
+        target_size: u32,
+        max_pixels: u64,
+    ) -> Result<(DynamicImage, (u32, u32)), ImageLoadError> {
+        if let Some(format) = Self::missing_decoder(path) {
+            return Err(ImageLoadError::Failed(format!(
+                "{format} images aren't supported in this build"
+            )));
+        }
+        #[cfg(feature = "jxl")]
+        if Self::is_jxl(path) {
+            return Self::load_jxl(path, max_pixels);
+        }
+
+        // Probe the header only: format and dimensions, no pixel data
+        let probe = Self::open_reader(path)?;
+        let format = probe.format();
+        let (width, height) = probe
+            .into_dimensions()
+            .map_err(|e| ImageLoadError::Failed(format!("Failed to read image header: {}", e)))?;
+
+        let pixels = u64::from(width) * u64::from(height);
+        if pixels > max_pixels {
+            // JPEG can be decoded at 1/2, 1/4 or 1/8 scale via DCT scaling
+            if format == Some(ImageFormat::Jpeg) {
+                let image = Self::decode_jpeg_scaled(path, target_size, max_pixels).map_err(
+                    |err| match err {
+                        ImageLoadError::TooLarge { .. } => {
+                            ImageLoadError::TooLarge { width, height }
+                        }
+                        other => other,
+                    },
+                )?;
+                return Ok((image, (width, height)));
+            }
+            return Err(ImageLoadError::TooLarge { width, height });
+        }
+
+        let mut limits = Limits::default();
+        limits.max_alloc = Some(max_pixels.saturating_mul(MAX_BYTES_PER_PIXEL));
+
+        let mut reader = Self::open_reader(path)?;
+        reader.limits(limits);
+        let image = reader.decode().map_err(|e| match e {
+            image::ImageError::Limits(_) => ImageLoadError::TooLarge { width, height },
+            other => ImageLoadError::Failed(format!("Failed to decode image: {}", other)),
+        })?;
+
+        Ok((image, (width, height)))
+    }
+
+    /// Name of the format at `path` if its decoder is behind a cargo feature this
+    /// build was compiled without.
+    fn missing_decoder(path: &Path) -> Option<&'static str> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "avif" if !cfg!(feature = "avif") => Some("AVIF"),
+            "jxl" if !cfg!(feature = "jxl") => Some("JPEG XL"),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "jxl")]
+    fn is_jxl(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("jxl"))
+    }
+
+    /// Decode a JPEG XL file. jxl-oxide renders it upright already, so the
+    /// dimensions it reports are the displayed ones.
+    #[cfg(feature = "jxl")]
+    fn load_jxl(
+        path: &Path,
+        max_pixels: u64,
+    ) -> Result<(DynamicImage, (u32, u32)), ImageLoadError> {
+        use image::ImageDecoder;
+
+        let file = File::open(path)
+            .map_err(|e| ImageLoadError::Failed(format!("Failed to open image file: {}", e)))?;
+        let decoder = jxl_oxide::integration::JxlDecoder::new(BufReader::new(file))
+            .map_err(|e| ImageLoadError::Failed(format!("Failed to read image header: {}", e)))?;
+        let (width, height) = decoder.dimensions();
+        if u64::from(width) * u64::from(height) > max_pixels {
+            return Err(ImageLoadError::TooLarge { width, height });
+        }
+
+        let image = DynamicImage::from_decoder(decoder)
+            .map_err(|e| ImageLoadError::Failed(format!("Failed to decode image: {}", e)))?;
+        Ok((image, (width, height)))
+    }
+
+    /// EXIF orientation of formats that carry it (JPEG, TIFF, HEIC, AVIF). Only read here;
+    /// the file itself is never rewritten.
+    fn read_orientation(path: &Path) -> Option<Orientation> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        if !matches!(
+            ext.as_str(),
+            "jpg" | "jpeg" | "jpe" | "tif" | "tiff" | "heic" | "heif" | "avif"
+        ) {
+            return None;
+        }
+        Self::exif_orientation(path)
+    }
+
+    /// Orientation tag of any file kamadak-exif can find EXIF in, whatever its extension.
+    pub(super) fn exif_orientation(path: &Path) -> Option<Orientation> {
+        let file = File::open(path).ok()?;
+        let exif = exif::Reader::new()
+            .read_from_container(&mut BufReader::new(file))
+            .ok()?;
+        let value = exif
+            .get_field(exif::Tag::Orientation, exif::In::PRIMARY)?
+            .value
+            .get_uint(0)?;
+        Orientation::from_exif(u8::try_from(value).ok()?)
+    }
 
-        // Attempt to decode with format detection
-        let image = reader
+    /// Whether `orientation` turns the image on its side.
+    pub(super) fn swaps_dimensions(orientation: Orientation) -> bool {
+        matches!(
+            orientation,
+            Orientation::Rotate90
+                | Orientation::Rotate270
+                | Orientation::Rotate90FlipH
+                | Orientation::Rotate270FlipH
+        )
+    }
+
+    fn open_reader(path: &Path) -> Result<ImageReader<BufReader<File>>, ImageLoadError> {
+        // Use image crate's built-in format detection
+        ImageReader::open(path)
+            .map_err(|e| ImageLoadError::Failed(format!("Failed to open image file: {}", e)))?
             .with_guessed_format()
-            .map_err(|e| format!("Failed to detect image format: {}", e))?
+            .map_err(|e| ImageLoadError::Failed(format!("Failed to detect image format: {}", e)))
+    }
+
+    /// Decode a JPEG using DCT scaling so the output is the smallest scale that still
+    /// covers `target_size`, instead of decoding the full-resolution frame.
+    fn decode_jpeg_scaled(
+        path: &Path,
+        target_size: u32,
+        max_pixels: u64,
+    ) -> Result<DynamicImage, ImageLoadError> {
+        let file = File::open(path)
+            .map_err(|e| ImageLoadError::Failed(format!("Failed to open image file: {}", e)))?;
+        Self::decode_jpeg_reader_scaled(BufReader::new(file), target_size, max_pixels)
+    }
+
+    /// [`Self::decode_jpeg_scaled`] for JPEG data that isn't a file of its own.
+    pub(super) fn decode_jpeg_reader_scaled<R: Read>(
+        reader: R,
+        target_size: u32,
+        max_pixels: u64,
+    ) -> Result<DynamicImage, ImageLoadError> {
+        let mut decoder = jpeg_decoder::Decoder::new(reader);
+        decoder
+            .read_info()
+            .map_err(|e| ImageLoadError::Failed(format!("Failed to read JPEG header: {}", e)))?;
+
+        let requested = target_size.clamp(1, u32::from(u16::MAX)) as u16;
+        let (scaled_width, scaled_height) = decoder
+            .scale(requested, requested)
+            .map_err(|e| ImageLoadError::Failed(format!("Failed to scale JPEG: {}", e)))?;
+
+        let scaled_pixels = u64::from(scaled_width) * u64::from(scaled_height);
+        if scaled_pixels > max_pixels {
+            return Err(ImageLoadError::TooLarge {
+                width: u32::from(scaled_width),
+                height: u32::from(scaled_height),
+            });
+        }
+
+        let pixels = decoder
             .decode()
-            .map_err(|e| format!("Failed to decode image: {}", e))?;
+            .map_err(|e| ImageLoadError::Failed(format!("Failed to decode image: {}", e)))?;
+        let info = decoder
+            .info()
+            .ok_or_else(|| ImageLoadError::Failed("Missing JPEG metadata".to_string()))?;
+        let (w, h) = (u32::from(info.width), u32::from(info.height));
 
-        Ok(image)
+        let image = match info.pixel_format {
+            jpeg_decoder::PixelFormat::RGB24 => {
+                image::RgbImage::from_raw(w, h, pixels).map(DynamicImage::ImageRgb8)
+            }
+            jpeg_decoder::PixelFormat::L8 => {
+                image::GrayImage::from_raw(w, h, pixels).map(DynamicImage::ImageLuma8)
+            }
+            jpeg_decoder::PixelFormat::L16 => {
+                let samples = pixels
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                image::ImageBuffer::from_raw(w, h, samples).map(DynamicImage::ImageLuma16)
+            }
+            jpeg_decoder::PixelFormat::CMYK32 => {
+                let rgb = pixels
+                    .chunks_exact(4)
+                    .flat_map(|c| {
+                        let k = u16::from(255 - c[3]);
+                        [c[0], c[1], c[2]].map(|v| ((u16::from(255 - v) * k) / 255) as u8)
+                    })
+                    .collect();
+                image::RgbImage::from_raw(w, h, rgb).map(DynamicImage::ImageRgb8)
+            }
+        };
+
+        image.ok_or_else(|| ImageLoadError::Failed("Decoded JPEG buffer size mismatch".to_string()))
     }
 
     fn has_transparency(image: &DynamicImage) -> bool {
@@ -74,3 +340,121 @@ impl ImageGenerator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ImageGenerator, ImageLoadError};
+    use image::metadata::Orientation;
+    use image::{DynamicImage, GenericImageView, ImageFormat, RgbImage};
+    use tempfile::tempdir;
+
+    fn write_test_image(path: &std::path::Path, format: ImageFormat) {
+        let image =
+            DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 64, image::Rgb([200, 10, 10])));
+        image.save_with_format(path, format).expect("write image");
+    }
+
+    #[test]
+    fn rejects_png_over_budget() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("big.png");
+        write_test_image(&path, ImageFormat::Png);
+
+        let result = ImageGenerator::load_image(&path, 8, 100);
+        assert!(matches!(
+            result,
+            Err(ImageLoadError::TooLarge {
+                width: 64,
+                height: 64
+            })
+        ));
+    }
+
+    /// Insert an EXIF block holding only an orientation tag right after the JPEG SOI.
+    fn add_exif_orientation(path: &std::path::Path, orientation: u8) {
+        let jpeg = std::fs::read(path).expect("read jpeg");
+        let mut app1 = vec![0xFF, 0xE1, 0x00, 0x22];
+        app1.extend_from_slice(b"Exif\0\0MM\0\x2a\0\0\0\x08");
+        // One IFD entry: Orientation, SHORT, count 1
+        app1.extend_from_slice(&[0x00, 0x01, 0x01, 0x12, 0x00, 0x03, 0, 0, 0, 1]);
+        app1.extend_from_slice(&[0x00, orientation, 0, 0, 0, 0, 0, 0]);
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&app1);
+        out.extend_from_slice(&jpeg[2..]);
+        std::fs::write(path, out).expect("write jpeg");
+    }
+
+    #[test]
+    fn reads_exif_orientation() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("portrait.jpg");
+        DynamicImage::ImageRgb8(RgbImage::new(64, 32))
+            .save_with_format(&path, ImageFormat::Jpeg)
+            .expect("write image");
+        assert_eq!(ImageGenerator::read_orientation(&path), None);
+
+        add_exif_orientation(&path, 6);
+        let orientation = ImageGenerator::read_orientation(&path).expect("orientation");
+        assert_eq!(orientation, Orientation::Rotate90);
+        assert!(ImageGenerator::swaps_dimensions(orientation));
+
+        let (mut image, _) = ImageGenerator::load_image(&path, 64, u64::MAX).expect("decode");
+        image.apply_orientation(orientation);
+        assert_eq!(image.dimensions(), (32, 64));
+
+        // PNGs are left alone even if they carry an orientation
+        let png = dir.path().join("portrait.png");
+        std::fs::copy(&path, &png).unwrap();
+        assert_eq!(ImageGenerator::read_orientation(&png), None);
+    }
+
+    #[test]
+    fn names_formats_without_a_decoder() {
+        let dir = tempdir().expect("tempdir");
+        for (name, format, enabled) in [
+            ("photo.avif", "AVIF", cfg!(feature = "avif")),
+            ("photo.jxl", "JPEG XL", cfg!(feature = "jxl")),
+        ] {
+            if enabled {
+                continue;
+            }
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"not an image").unwrap();
+            let err = ImageGenerator::load_image(&path, 64, u64::MAX).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("{format} images aren't supported in this build")
+            );
+        }
+    }
+
+    #[cfg(feature = "jxl")]
+    #[test]
+    fn decodes_jpeg_xl() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/images/blank.jxl");
+        let (image, dimensions) = ImageGenerator::load_image(&path, 64, u64::MAX).unwrap();
+        assert_eq!(dimensions, (16, 8));
+        assert_eq!(image.dimensions(), (16, 8));
+
+        let err = ImageGenerator::load_image(&path, 64, 100).unwrap_err();
+        assert!(matches!(
+            err,
+            ImageLoadError::TooLarge {
+                width: 16,
+                height: 8
+            }
+        ));
+    }
+
+    #[test]
+    fn downscales_jpeg_over_budget() {
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("big.jpg");
+        write_test_image(&path, ImageFormat::Jpeg);
+
+        let (image, original) = ImageGenerator::load_image(&path, 8, 100).expect("scaled decode");
+        assert_eq!(original, (64, 64));
+        assert_eq!(image.dimensions(), (8, 8));
+    }
+}
@@ -4,17 +4,26 @@ use image::{DynamicImage, GenericImageView, ImageFormat};
 use std::io::Cursor;
 use std::path::Path;
 
+use crate::locations::throttle::{self, RemotePermit};
 #[cfg(target_os = "macos")]
 use crate::macos_security;
+#[cfg(feature = "webp")]
+pub mod animated;
 #[cfg(target_os = "macos")]
 pub mod apps;
+pub mod audio;
+pub mod documents;
+pub mod folders;
 pub mod fonts;
+pub mod icons;
 pub mod images;
 pub mod pdf;
 pub mod psd;
+pub mod raw;
 pub mod stl;
 pub mod svg;
 pub mod video;
+mod woff;
 pub mod zpl;
 
 pub mod sftp;
@@ -48,6 +57,8 @@ pub struct ThumbnailGenerator;
 pub struct PreparedRequest {
     pub request: ThumbnailRequest,
     pub temp_file: Option<std::path::PathBuf>,
+    /// SMB thumbnails download inside `generate()`, so their remote slot is held until it finishes.
+    pub remote_permit: Option<RemotePermit>,
 }
 
 impl ThumbnailGenerator {
@@ -63,6 +74,7 @@ impl ThumbnailGenerator {
             return Ok(PreparedRequest {
                 request: prepared,
                 temp_file: Some(temp_path),
+                remote_permit: None,
             });
         }
 
@@ -74,35 +86,48 @@ impl ThumbnailGenerator {
                 .unwrap_or("file")
                 .to_string();
 
+            let permit = throttle::acquire("gdrive").await?;
             let file_id =
                 crate::locations::gdrive::provider::get_file_id_by_path(&email, &path).await?;
             let temp_path = crate::locations::gdrive::provider::download_file_to_temp(
                 &email, &file_id, &file_name,
             )
             .await?;
+            drop(permit);
 
             let mut prepared = request;
             prepared.path = temp_path.clone();
             return Ok(PreparedRequest {
                 request: prepared,
                 temp_file: Some(std::path::PathBuf::from(temp_path)),
+                remote_permit: None,
             });
         }
 
         if sftp::is_sftp_path(&request.path) {
+            let permit = throttle::acquire("sftp").await?;
             let temp_path = sftp::download_sftp_file_async(&request.path).await?;
+            drop(permit);
             let mut prepared = request;
             prepared.path = temp_path.to_string_lossy().to_string();
             return Ok(PreparedRequest {
                 request: prepared,
                 temp_file: Some(temp_path),
+                remote_permit: None,
             });
         }
 
-        // SMB and local files: no async pre-processing needed
+        // SMB downloads happen in generate(); only reserve the slot here
+        let remote_permit = if smb::is_smb_path(&request.path) {
+            Some(throttle::acquire("smb").await?)
+        } else {
+            None
+        };
+
         Ok(PreparedRequest {
             request,
             temp_file: None,
+            remote_permit,
         })
     }
 
@@ -158,11 +183,26 @@ impl ThumbnailGenerator {
             }
         }
 
+        // Other folders get a collage of the images inside them
+        if path.is_dir() {
+            return folders::FolderGenerator::generate(request);
+        }
+
         // Check if it's a PSD file (handle before regular images)
         if Self::is_psd_file(path) {
             return psd::PsdGenerator::generate(request);
         }
 
+        // Icon containers pick the embedded size closest to the request
+        if Self::is_icon_file(path) {
+            return icons::IconGenerator::generate(request);
+        }
+
+        // Camera RAW files use their embedded JPEG preview
+        if Self::is_raw_file(path) {
+            return raw::RawGenerator::generate(request);
+        }
+
         // Check if it's an image file
         if Self::is_image_file(path) {
             return images::ImageGenerator::generate(request);
@@ -197,7 +237,16 @@ impl ThumbnailGenerator {
             return zpl::ZplGenerator::generate(request);
         }
 
-        // TODO: Add support for documents
+        // Cover art embedded in music files
+        if Self::is_audio_file(path) {
+            return audio::AudioGenerator::generate(request);
+        }
+
+        // Office and OpenDocument files
+        if Self::is_document_file(path) {
+            return documents::DocumentGenerator::generate(request);
+        }
+
         Err("Unsupported file type for thumbnail generation".to_string())
     }
 
@@ -205,13 +254,51 @@ impl ThumbnailGenerator {
         if let Some(extension) = path.extension().and_then(|s| s.to_str()) {
             matches!(
                 extension.to_lowercase().as_str(),
-                "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "tiff" | "tga" | "ico"
+                "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "tiff" | "tga" | "avif" | "jxl"
             )
         } else {
             false
         }
     }
 
+    fn is_raw_file(path: &Path) -> bool {
+        if let Some(extension) = path.extension().and_then(|s| s.to_str()) {
+            matches!(
+                extension.to_lowercase().as_str(),
+                "cr2"
+                    | "cr3"
+                    | "crw"
+                    | "nef"
+                    | "nrw"
+                    | "arw"
+                    | "srf"
+                    | "sr2"
+                    | "dng"
+                    | "orf"
+                    | "rw2"
+                    | "raf"
+                    | "pef"
+                    | "srw"
+                    | "3fr"
+                    | "erf"
+                    | "kdc"
+                    | "mrw"
+                    | "x3f"
+                    | "iiq"
+            )
+        } else {
+            false
+        }
+    }
+
+    fn is_icon_file(path: &Path) -> bool {
+        if let Some(extension) = path.extension().and_then(|s| s.to_str()) {
+            matches!(extension.to_lowercase().as_str(), "ico" | "icns")
+        } else {
+            false
+        }
+    }
+
     fn is_psd_file(path: &Path) -> bool {
         if let Some(extension) = path.extension().and_then(|s| s.to_str()) {
             // Note: PSB (Large Document Format) is not supported by the psd crate
@@ -254,8 +341,61 @@ impl ThumbnailGenerator {
 
     fn is_font_file(path: &Path) -> bool {
         if let Some(extension) = path.extension().and_then(|s| s.to_str()) {
-            // Only TTF and OTF are supported - ab_glyph doesn't reliably parse WOFF/WOFF2
-            matches!(extension.to_lowercase().as_str(), "ttf" | "otf")
+            // WOFF and WOFF2 are unwrapped to plain sfnt data before rendering
+            matches!(
+                extension.to_lowercase().as_str(),
+                "ttf" | "otf" | "woff" | "woff2"
+            )
+        } else {
+            false
+        }
+    }
+
+    fn is_audio_file(path: &Path) -> bool {
+        if let Some(extension) = path.extension().and_then(|s| s.to_str()) {
+            matches!(
+                extension.to_lowercase().as_str(),
+                "mp3"
+                    | "flac"
+                    | "m4a"
+                    | "m4b"
+                    | "ogg"
+                    | "oga"
+                    | "opus"
+                    | "wav"
+                    | "aif"
+                    | "aiff"
+                    | "ape"
+                    | "wv"
+            )
+        } else {
+            false
+        }
+    }
+
+    fn is_document_file(path: &Path) -> bool {
+        if let Some(extension) = path.extension().and_then(|s| s.to_str()) {
+            matches!(
+                extension.to_lowercase().as_str(),
+                "docx"
+                    | "docm"
+                    | "dotx"
+                    | "xlsx"
+                    | "xlsm"
+                    | "xltx"
+                    | "pptx"
+                    | "pptm"
+                    | "potx"
+                    | "odt"
+                    | "ods"
+                    | "odp"
+                    | "odg"
+                    // Legacy formats have no embedded thumbnail; LibreOffice only
+                    | "doc"
+                    | "xls"
+                    | "ppt"
+                    | "rtf"
+            )
         } else {
             false
         }
@@ -353,12 +493,34 @@ impl ThumbnailGenerator {
                     base64::engine::general_purpose::STANDARD.encode(&buffer)
                 ))
             }
-            ThumbnailFormat::WebP => {
+            // Still images have nothing to animate
+            ThumbnailFormat::WebP | ThumbnailFormat::AnimatedWebP => {
                 #[cfg(feature = "webp")]
                 {
-                    // TODO: Implement WebP encoding when webp crate is available
-                    // For now, fall back to PNG
-                    Self::encode_to_data_url(image, ThumbnailFormat::PNG, quality)
+                    let quality_value = match quality {
+                        ThumbnailQuality::Low => 50.0,
+                        ThumbnailQuality::Medium => 75.0,
+                        ThumbnailQuality::High => 90.0,
+                    };
+
+                    // Keep the alpha channel only when the source has one
+                    let (width, height) = image.dimensions();
+                    let pixels;
+                    let encoder = if image.color().has_alpha() {
+                        pixels = image.to_rgba8().into_raw();
+                        webp::Encoder::from_rgba(&pixels, width, height)
+                    } else {
+                        pixels = image.to_rgb8().into_raw();
+                        webp::Encoder::from_rgb(&pixels, width, height)
+                    };
+                    let encoded = encoder
+                        .encode_simple(false, quality_value)
+                        .map_err(|e| format!("Failed to encode WebP: {:?}", e))?;
+
+                    Ok(format!(
+                        "data:image/webp;base64,{}",
+                        base64::engine::general_purpose::STANDARD.encode(&*encoded)
+                    ))
                 }
                 #[cfg(not(feature = "webp"))]
                 {
@@ -411,6 +573,37 @@ mod tests {
         assert_eq!(path, "/My Drive/folder/sub folder/file name.png");
     }
 
+    #[cfg(feature = "webp")]
+    #[test]
+    fn test_webp_encoding_round_trip() {
+        let mut rgba = image::RgbaImage::new(8, 6);
+        for (x, y, pixel) in rgba.enumerate_pixels_mut() {
+            *pixel = image::Rgba([
+                x as u8 * 30,
+                y as u8 * 40,
+                128,
+                if x < 4 { 255 } else { 64 },
+            ]);
+        }
+        let image = DynamicImage::ImageRgba8(rgba);
+
+        let data_url = ThumbnailGenerator::encode_to_data_url(
+            &image,
+            ThumbnailFormat::WebP,
+            ThumbnailQuality::High,
+        )
+        .unwrap();
+        let encoded = data_url
+            .strip_prefix("data:image/webp;base64,")
+            .expect("WebP data URL");
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        let decoded = image::load_from_memory_with_format(&bytes, ImageFormat::WebP).unwrap();
+        assert_eq!(decoded.dimensions(), (8, 6));
+        assert!(decoded.color().has_alpha());
+    }
+
     #[test]
     fn test_parse_gdrive_path_invalid() {
         assert!(parse_gdrive_path("not-a-url").is_err());
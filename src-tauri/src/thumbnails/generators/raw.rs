@@ -0,0 +1,430 @@
+use super::super::{ThumbnailGenerationResult, ThumbnailRequest};
+use super::images::{ImageGenerator, DEFAULT_MAX_DECODE_PIXELS};
+use super::ThumbnailGenerator;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Camera RAW files (`.cr2`, `.nef`, `.arw`, `.dng`, ...) almost always carry a
+/// full-size JPEG preview next to the sensor data. Demosaicing the sensor data is far
+/// too slow for a thumbnail, so only the embedded preview is used; files without one
+/// get no thumbnail.
+pub struct RawGenerator;
+
+/// Start of a JPEG stream: SOI followed by the first marker.
+const JPEG_SIGNATURE: [u8; 3] = [0xFF, 0xD8, 0xFF];
+/// Containers that aren't TIFF-based (CR3, RAF, ...) keep their preview near the
+/// start; only this much of them is scanned for one.
+const SCAN_LIMIT: u64 = 16 * 1024 * 1024;
+/// Largest embedded preview that will be read.
+const MAX_PREVIEW_BYTES: u64 = 64 * 1024 * 1024;
+/// IFDs followed per file, so a corrupt or cyclic chain can't run away.
+const MAX_IFDS: usize = 32;
+/// Entries read from one IFD.
+const MAX_IFD_ENTRIES: u16 = 1024;
+
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_JPEG_INTERCHANGE_FORMAT: u16 = 0x0201;
+const TAG_JPEG_INTERCHANGE_FORMAT_LENGTH: u16 = 0x0202;
+const TAG_EXIF_IFD: u16 = 0x8769;
+/// Old-style JPEG (6) and JPEG (7) strips; DNG uses 7 for its previews.
+const JPEG_COMPRESSIONS: [u32; 2] = [6, 7];
+
+/// Where one JPEG stream sits inside a RAW file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PreviewRange {
+    offset: u64,
+    length: u64,
+}
+
+/// A preview that passed the header probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EmbeddedJpeg {
+    range: PreviewRange,
+    width: u32,
+    height: u32,
+}
+
+impl RawGenerator {
+    pub fn generate(request: &ThumbnailRequest) -> Result<ThumbnailGenerationResult, String> {
+        let path = Path::new(&request.path);
+        let mut file = BufReader::new(
+            File::open(path).map_err(|e| format!("Failed to open RAW file: {}", e))?,
+        );
+        let file_len = file
+            .get_ref()
+            .metadata()
+            .map_err(|e| format!("Failed to open RAW file: {}", e))?
+            .len();
+
+        let candidates = match tiff_preview_ranges(&mut file, file_len) {
+            Some(ranges) => ranges,
+            None => scanned_preview_ranges(&mut file, file_len)
+                .map_err(|e| format!("Failed to read RAW file: {}", e))?,
+        };
+        let preview = largest_preview(&mut file, &candidates)
+            .ok_or_else(|| "RAW file has no embedded preview".to_string())?;
+        let data = read_range(&mut file, preview.range)
+            .map_err(|e| format!("Failed to read RAW preview: {}", e))?;
+
+        let mut image = ImageGenerator::decode_jpeg_reader_scaled(
+            &data[..],
+            request.size,
+            DEFAULT_MAX_DECODE_PIXELS,
+        )
+        .map_err(|e| e.to_string())?;
+
+        // The preview is stored as shot; the orientation lives in the RAW's own EXIF
+        let (mut image_width, mut image_height) = (preview.width, preview.height);
+        if let Some(orientation) = ImageGenerator::exif_orientation(path) {
+            image.apply_orientation(orientation);
+            if ImageGenerator::swaps_dimensions(orientation) {
+                std::mem::swap(&mut image_width, &mut image_height);
+            }
+        }
+
+        let resized = ThumbnailGenerator::resize_image(image, request.size, request.quality)?;
+        let data_url =
+            ThumbnailGenerator::encode_to_data_url(&resized, request.format, request.quality)?;
+
+        Ok(ThumbnailGenerationResult {
+            data_url,
+            has_transparency: false,
+            image_width: Some(image_width),
+            image_height: Some(image_height),
+        })
+    }
+}
+
+/// Byte order of a TIFF file.
+#[derive(Debug, Clone, Copy)]
+enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    fn u16(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Self::Little => u16::from_le_bytes(bytes),
+            Self::Big => u16::from_be_bytes(bytes),
+        }
+    }
+
+    fn u32(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Self::Little => u32::from_le_bytes(bytes),
+            Self::Big => u32::from_be_bytes(bytes),
+        }
+    }
+}
+
+/// One IFD entry with its value field still undecoded.
+struct IfdEntry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    value: [u8; 4],
+}
+
+/// JPEG previews named by the IFDs of a TIFF-based RAW (CR2, NEF, ARW, DNG, ORF,
+/// PEF, RW2, ...): the `JPEGInterchangeFormat`/`Length` pair and single JPEG strips,
+/// in IFD0, its chain, SubIFDs and the EXIF IFD. `None` if the file isn't TIFF.
+fn tiff_preview_ranges<R: Read + Seek>(reader: &mut R, file_len: u64) -> Option<Vec<PreviewRange>> {
+    let mut header = [0u8; 8];
+    reader.seek(SeekFrom::Start(0)).ok()?;
+    reader.read_exact(&mut header).ok()?;
+    let order = match &header[..2] {
+        b"II" => ByteOrder::Little,
+        b"MM" => ByteOrder::Big,
+        _ => return None,
+    };
+    // 42 for TIFF; Olympus and Panasonic use their own magic numbers
+    if !matches!(
+        order.u16([header[2], header[3]]),
+        42 | 0x4F52 | 0x5352 | 0x55
+    ) {
+        return None;
+    }
+
+    let mut ranges = Vec::new();
+    let mut pending = vec![u64::from(
+        order.u32([header[4], header[5], header[6], header[7]]),
+    )];
+    let mut visited = Vec::new();
+    while let Some(offset) = pending.pop() {
+        if offset == 0 || offset >= file_len || visited.contains(&offset) {
+            continue;
+        }
+        if visited.len() >= MAX_IFDS {
+            break;
+        }
+        visited.push(offset);
+
+        let Ok((entries, next)) = read_ifd(reader, order, offset) else {
+            continue;
+        };
+        pending.push(u64::from(next));
+
+        let value = |tag: u16| {
+            entries
+                .iter()
+                .find(|entry| entry.tag == tag && entry.count == 1)
+                .map(|entry| entry_value(order, entry))
+        };
+        if let (Some(offset), Some(length)) = (
+            value(TAG_JPEG_INTERCHANGE_FORMAT),
+            value(TAG_JPEG_INTERCHANGE_FORMAT_LENGTH),
+        ) {
+            ranges.push(PreviewRange {
+                offset: u64::from(offset),
+                length: u64::from(length),
+            });
+        }
+        if value(TAG_COMPRESSION).is_some_and(|c| JPEG_COMPRESSIONS.contains(&c)) {
+            if let (Some(offset), Some(length)) =
+                (value(TAG_STRIP_OFFSETS), value(TAG_STRIP_BYTE_COUNTS))
+            {
+                ranges.push(PreviewRange {
+                    offset: u64::from(offset),
+                    length: u64::from(length),
+                });
+            }
+        }
+        if let Some(exif) = value(TAG_EXIF_IFD) {
+            pending.push(u64::from(exif));
+        }
+        if let Some(sub_ifds) = entries.iter().find(|entry| entry.tag == TAG_SUB_IFDS) {
+            pending.extend(sub_ifd_offsets(reader, order, sub_ifds).unwrap_or_default());
+        }
+    }
+
+    ranges.retain(|range| {
+        range.length > 0
+            && range.length <= MAX_PREVIEW_BYTES
+            && range
+                .offset
+                .checked_add(range.length)
+                .is_some_and(|end| end <= file_len)
+    });
+    Some(ranges)
+}
+
+/// The entries of the IFD at `offset` and the offset of the next IFD.
+fn read_ifd<R: Read + Seek>(
+    reader: &mut R,
+    order: ByteOrder,
+    offset: u64,
+) -> std::io::Result<(Vec<IfdEntry>, u32)> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut count = [0u8; 2];
+    reader.read_exact(&mut count)?;
+    let count = order.u16(count).min(MAX_IFD_ENTRIES);
+
+    let mut entries = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        let mut raw = [0u8; 12];
+        reader.read_exact(&mut raw)?;
+        entries.push(IfdEntry {
+            tag: order.u16([raw[0], raw[1]]),
+            kind: order.u16([raw[2], raw[3]]),
+            count: order.u32([raw[4], raw[5], raw[6], raw[7]]),
+            value: [raw[8], raw[9], raw[10], raw[11]],
+        });
+    }
+    let mut next = [0u8; 4];
+    reader.read_exact(&mut next)?;
+    Ok((entries, order.u32(next)))
+}
+
+/// A single SHORT or LONG value stored inline in the entry.
+fn entry_value(order: ByteOrder, entry: &IfdEntry) -> u32 {
+    match entry.kind {
+        // SHORT
+        3 => u32::from(order.u16([entry.value[0], entry.value[1]])),
+        _ => order.u32(entry.value),
+    }
+}
+
+/// The IFD offsets a SubIFDs entry points at: inline for one, an array otherwise.
+fn sub_ifd_offsets<R: Read + Seek>(
+    reader: &mut R,
+    order: ByteOrder,
+    entry: &IfdEntry,
+) -> std::io::Result<Vec<u64>> {
+    let count = entry.count.min(MAX_IFDS as u32) as usize;
+    if count <= 1 {
+        return Ok(vec![u64::from(order.u32(entry.value))]);
+    }
+    reader.seek(SeekFrom::Start(u64::from(order.u32(entry.value))))?;
+    let mut raw = vec![0u8; count * 4];
+    reader.read_exact(&mut raw)?;
+    Ok(raw
+        .chunks_exact(4)
+        .map(|chunk| u64::from(order.u32([chunk[0], chunk[1], chunk[2], chunk[3]])))
+        .collect())
+}
+
+/// Every JPEG signature in the first `SCAN_LIMIT` bytes, for containers that don't
+/// index their previews the TIFF way. Each range runs to the end of what was read;
+/// the decoder stops at the stream's EOI.
+fn scanned_preview_ranges<R: Read + Seek>(
+    reader: &mut R,
+    file_len: u64,
+) -> std::io::Result<Vec<PreviewRange>> {
+    let scanned = file_len.min(SCAN_LIMIT);
+    let mut data = Vec::with_capacity(scanned as usize);
+    reader.seek(SeekFrom::Start(0))?;
+    reader.take(scanned).read_to_end(&mut data)?;
+    Ok(data
+        .windows(JPEG_SIGNATURE.len())
+        .enumerate()
+        .filter(|(_, window)| *window == JPEG_SIGNATURE)
+        .map(|(offset, _)| PreviewRange {
+            offset: offset as u64,
+            length: (scanned - offset as u64).min(MAX_PREVIEW_BYTES),
+        })
+        .collect())
+}
+
+/// Probe each candidate's JPEG header and keep the one with the most pixels. RAW
+/// files usually hold a small EXIF thumbnail next to the full-size preview, and
+/// candidates that turn out not to be JPEG (or are lossless sensor data, as DNG
+/// raw tiles are) fail the probe and are skipped.
+fn largest_preview<R: Read + Seek>(
+    reader: &mut R,
+    candidates: &[PreviewRange],
+) -> Option<EmbeddedJpeg> {
+    candidates
+        .iter()
+        .filter_map(|&range| {
+            reader.seek(SeekFrom::Start(range.offset)).ok()?;
+            let mut decoder = jpeg_decoder::Decoder::new(reader.by_ref().take(range.length));
+            decoder.read_info().ok()?;
+            let info = decoder.info()?;
+            let usable = info.width > 0
+                && info.height > 0
+                && info.coding_process != jpeg_decoder::CodingProcess::Lossless;
+            usable.then(|| EmbeddedJpeg {
+                range,
+                width: u32::from(info.width),
+                height: u32::from(info.height),
+            })
+        })
+        .max_by_key(|jpeg| u64::from(jpeg.width) * u64::from(jpeg.height))
+}
+
+fn read_range<R: Read + Seek>(reader: &mut R, range: PreviewRange) -> std::io::Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(range.offset))?;
+    let mut data = Vec::with_capacity(range.length as usize);
+    reader.take(range.length).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, ImageFormat, RgbImage};
+    use std::io::Cursor;
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg)
+            .unwrap();
+        bytes
+    }
+
+    /// A little-endian IFD with `entries` of (tag, type, count, value) and no next IFD.
+    fn ifd(entries: &[(u16, u16, u32, u32)]) -> Vec<u8> {
+        let mut bytes = (entries.len() as u16).to_le_bytes().to_vec();
+        for &(tag, kind, count, value) in entries {
+            bytes.extend(tag.to_le_bytes());
+            bytes.extend(kind.to_le_bytes());
+            bytes.extend(count.to_le_bytes());
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(0u32.to_le_bytes());
+        bytes
+    }
+
+    fn previews(raw: &[u8]) -> Option<EmbeddedJpeg> {
+        let mut reader = Cursor::new(raw);
+        let candidates = match tiff_preview_ranges(&mut reader, raw.len() as u64) {
+            Some(ranges) => ranges,
+            None => scanned_preview_ranges(&mut reader, raw.len() as u64).unwrap(),
+        };
+        largest_preview(&mut reader, &candidates)
+    }
+
+    #[test]
+    fn reads_previews_named_by_the_ifds() {
+        let thumbnail = jpeg(16, 8);
+        let preview = jpeg(96, 64);
+        // Sensor data that happens to contain a bigger JPEG nobody points at
+        let stray = jpeg(128, 128);
+
+        // Header, IFD0 (thumbnail via JPEGInterchangeFormat, SubIFD pointer), the
+        // SubIFD (preview as a JPEG strip), then the data
+        let ifd0_len = 2 + 3 * 12 + 4;
+        let sub_ifd_offset = 8 + ifd0_len;
+        let sub_ifd_len = 2 + 3 * 12 + 4;
+        let thumbnail_offset = (sub_ifd_offset + sub_ifd_len) as u32;
+        let preview_offset = thumbnail_offset + thumbnail.len() as u32;
+
+        let mut raw = b"II*\0\x08\0\0\0".to_vec();
+        raw.extend(ifd(&[
+            (TAG_SUB_IFDS, 4, 1, sub_ifd_offset as u32),
+            (TAG_JPEG_INTERCHANGE_FORMAT, 4, 1, thumbnail_offset),
+            (
+                TAG_JPEG_INTERCHANGE_FORMAT_LENGTH,
+                4,
+                1,
+                thumbnail.len() as u32,
+            ),
+        ]));
+        raw.extend(ifd(&[
+            (TAG_COMPRESSION, 3, 1, 7),
+            (TAG_STRIP_OFFSETS, 4, 1, preview_offset),
+            (TAG_STRIP_BYTE_COUNTS, 4, 1, preview.len() as u32),
+        ]));
+        raw.extend(&thumbnail);
+        raw.extend(&preview);
+        raw.extend(&stray);
+
+        assert_eq!(
+            previews(&raw),
+            Some(EmbeddedJpeg {
+                range: PreviewRange {
+                    offset: u64::from(preview_offset),
+                    length: preview.len() as u64,
+                },
+                width: 96,
+                height: 64,
+            })
+        );
+
+        // Ranges running past the end of the file are ignored
+        raw.truncate(preview_offset as usize + 10);
+        assert_eq!(previews(&raw).map(|p| (p.width, p.height)), Some((16, 8)));
+    }
+
+    #[test]
+    fn scans_the_start_of_other_containers() {
+        // Not TIFF: a small thumbnail, a stray signature, then the full-size preview
+        let mut raw = b"ftypcrx ".to_vec();
+        raw.extend(jpeg(16, 8));
+        raw.extend([0x12, 0xFF, 0xD8, 0xFF, 0x00, 0x34]);
+        let preview_offset = raw.len();
+        raw.extend(jpeg(96, 64));
+        raw.extend([0u8; 32]);
+
+        let found = previews(&raw).unwrap();
+        assert_eq!(found.range.offset, preview_offset as u64);
+        assert_eq!((found.width, found.height), (96, 64));
+        assert_eq!(previews(b"II*\0\x08\0\0\0 no preview"), None);
+    }
+}
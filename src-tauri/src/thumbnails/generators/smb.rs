@@ -129,17 +129,21 @@ pub fn download_smb_file_sync(smb_path: &str) -> Result<std::path::PathBuf, Stri
 
     let (hostname, share, file_path, creds_json) = prepare_smb_request(smb_path)?;
     let temp_path = smb_temp_path(&hostname, &share, &file_path, "")?;
-    let temp_path_str = temp_path.to_string_lossy().to_string();
-
-    let params = serde_json::json!({
-        "credentials": creds_json,
-        "share": share,
-        "path": file_path,
-        "dest_path": temp_path_str
-    });
-
-    let _result: serde_json::Value =
-        client::call_method_with_timeout("download_file", params, client::DOWNLOAD_TIMEOUT_MS)?;
+    // The sidecar writes to a .part file; only a complete download gets the real name
+    crate::locations::transfer::download_to_stable_path_blocking(&temp_path, |part_path| {
+        let params = serde_json::json!({
+            "credentials": creds_json,
+            "share": share,
+            "path": file_path,
+            "dest_path": part_path.to_string_lossy()
+        });
+        client::call_method_with_timeout::<_, serde_json::Value>(
+            "download_file",
+            params,
+            client::DOWNLOAD_TIMEOUT_MS,
+        )
+        .map(|_| ())
+    })?;
 
     log::debug!("Downloaded SMB file to {}", temp_path.display());
 
@@ -1,75 +1,277 @@
 use super::super::{ThumbnailGenerationResult, ThumbnailRequest};
-use image::DynamicImage;
+use image::{DynamicImage, RgbaImage};
+use once_cell::sync::Lazy;
+use resvg::tiny_skia::{Rect, Transform};
+use resvg::usvg::fontdb;
 use std::fs;
+use std::sync::Arc;
 
-pub struct SvgGenerator;
+/// System fonts, loaded once so `<text>` elements render without re-scanning per SVG.
+static SYSTEM_FONT_DB: Lazy<Arc<fontdb::Database>> = Lazy::new(|| {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+    log::info!("Loaded {} font faces for SVG rendering", db.len());
+    Arc::new(db)
+});
 
-impl SvgGenerator {
-    pub fn generate(request: &ThumbnailRequest) -> Result<ThumbnailGenerationResult, String> {
-        let svg_data = fs::read(&request.path).map_err(|e| format!("Failed to read SVG: {}", e))?;
+/// usvg options backed by the cached system font database.
+/// `font_family` overrides the default family used when an SVG doesn't specify one.
+pub fn svg_options(font_family: Option<&str>) -> resvg::usvg::Options<'static> {
+    let mut opt = resvg::usvg::Options {
+        fontdb: SYSTEM_FONT_DB.clone(),
+        ..resvg::usvg::Options::default()
+    };
+    if let Some(family) = font_family.map(str::trim).filter(|f| !f.is_empty()) {
+        opt.font_family = family.to_string();
+    }
+    opt
+}
+
+/// An SVG rendered into a square canvas.
+pub struct RenderedSvg {
+    /// Straight (not premultiplied) RGBA pixels
+    pub image: RgbaImage,
+    /// Intrinsic size of the drawing, in CSS pixels
+    pub width: u32,
+    pub height: u32,
+    pub has_transparency: bool,
+}
+
+/// Render `svg_data` centered in a `target`×`target` square, leaving a little padding
+/// so logos don't touch the edges.
+pub fn rasterize(
+    svg_data: &[u8],
+    target: u32,
+    font_family: Option<&str>,
+) -> Result<RenderedSvg, String> {
+    let opt = svg_options(font_family);
+    let tree = resvg::usvg::Tree::from_data(svg_data, &opt)
+        .map_err(|e| format!("SVG parse error: {:?}", e))?;
+
+    // Determine output size while preserving aspect ratio
+    let content = content_box(svg_data, &tree);
+    let (w, h) = (content.width().max(1.0), content.height().max(1.0));
+    let target = target.max(1);
+    let padding = (target as f32 * 0.08).round();
+    let inner = (target as f32 - 2.0 * padding).max(1.0);
+    let scale = (inner / w).min(inner / h);
+    let scaled_w = w * scale;
+    let scaled_h = h * scale;
+
+    // Always render to a square pixmap
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(target, target)
+        .ok_or_else(|| "Failed to allocate pixmap".to_string())?;
+
+    // Move the content box to the origin, scale it into our pixmap and center it
+    let tx = ((target as f32 - scaled_w) * 0.5).round();
+    let ty = ((target as f32 - scaled_h) * 0.5).round();
+    let ts = Transform::from_translate(-content.x(), -content.y())
+        .post_scale(scale, scale)
+        .post_translate(tx, ty);
+    resvg::render(&tree, ts, &mut pixmap.as_mut());
+
+    // Convert premultiplied pixels to straight RGBA
+    let data = pixmap.data();
+    let mut rgba = Vec::with_capacity(data.len());
+    for px in data.chunks_exact(4) {
+        let r = px[0] as u32;
+        let g = px[1] as u32;
+        let b = px[2] as u32;
+        let a = px[3] as u32;
+        if a == 0 {
+            rgba.extend_from_slice(&[0, 0, 0, 0]);
+        } else {
+            let ur = ((r * 255 + a / 2) / a).min(255) as u8;
+            let ug = ((g * 255 + a / 2) / a).min(255) as u8;
+            let ub = ((b * 255 + a / 2) / a).min(255) as u8;
+            rgba.extend_from_slice(&[ur, ug, ub, a as u8]);
+        }
+    }
+
+    // Check for transparency by looking for any alpha values < 255
+    let has_transparency = rgba.chunks_exact(4).any(|pixel| pixel[3] < 255);
+    let image = RgbaImage::from_vec(target, target, rgba)
+        .ok_or_else(|| "Failed to create image buffer".to_string())?;
+
+    Ok(RenderedSvg {
+        image,
+        width: w.round() as u32,
+        height: h.round() as u32,
+        has_transparency,
+    })
+}
+
+/// The part of the tree's canvas the drawing occupies.
+///
+/// usvg fills in a missing `width` or `height` with the viewBox's own dimension,
+/// which letterboxes a drawing whose viewBox has a different aspect ratio, so those
+/// files are sized by their viewBox. Files with neither a size nor a viewBox are
+/// sized by what they draw, so shapes at negative coordinates aren't clipped.
+fn content_box(svg_data: &[u8], tree: &resvg::usvg::Tree) -> Rect {
+    let size = tree.size();
+    let canvas = size.to_non_zero_rect(0.0, 0.0).to_rect();
+    let Some(root) = RootSizing::parse(svg_data) else {
+        return canvas;
+    };
+    if root.width && root.height {
+        return canvas;
+    }
 
-        // Parse SVG using resvg/usvg
-        let opt = resvg::usvg::Options::default();
-        let tree = resvg::usvg::Tree::from_data(&svg_data, &opt)
-            .map_err(|e| format!("SVG parse error: {:?}", e))?;
-
-        // Determine output size while preserving aspect ratio
-        let svg_size = tree.size();
-        let (w, h) = (svg_size.width().max(1.0), svg_size.height().max(1.0));
-        let target = request.size.max(1);
-        // Leave a small padding inside the square so logos don't touch the edges
-        let padding = (target as f32 * 0.08).round();
-        let inner = (target as f32 - 2.0 * padding).max(1.0);
-        let scale = (inner / w).min(inner / h);
-        let scaled_w = w * scale;
-        let scaled_h = h * scale;
-
-        // Always render to a square pixmap
-        let mut pixmap = resvg::tiny_skia::Pixmap::new(target, target)
-            .ok_or_else(|| "Failed to allocate pixmap".to_string())?;
-
-        // Build transform to scale into our pixmap and center with padding
-        let mut ts = resvg::tiny_skia::Transform::from_scale(scale, scale);
-        let tx = ((target as f32 - scaled_w) * 0.5).round();
-        let ty = ((target as f32 - scaled_h) * 0.5).round();
-        ts = ts.post_translate(tx, ty);
-        let mut pmut = pixmap.as_mut();
-        resvg::render(&tree, ts, &mut pmut);
-
-        // Convert premultiplied BGRA to straight RGBA
-        let data = pixmap.data();
-        let mut rgba = Vec::with_capacity(data.len());
-        for px in data.chunks_exact(4) {
-            let r = px[0] as u32;
-            let g = px[1] as u32;
-            let b = px[2] as u32;
-            let a = px[3] as u32;
-            if a == 0 {
-                rgba.extend_from_slice(&[0, 0, 0, 0]);
+    match root.view_box {
+        Some(_) if root.stretches => canvas,
+        Some((vb_w, vb_h)) => {
+            let fit = (size.width() / vb_w).min(size.height() / vb_h);
+            let (w, h) = (vb_w * fit, vb_h * fit);
+            Rect::from_xywh((size.width() - w) / 2.0, (size.height() - h) / 2.0, w, h)
+                .unwrap_or(canvas)
+        }
+        None => {
+            let drawn = tree.root().abs_stroke_bounding_box();
+            if drawn.width() > 0.0 && drawn.height() > 0.0 {
+                drawn
             } else {
-                let ur = ((r * 255 + a / 2) / a).min(255) as u8;
-                let ug = ((g * 255 + a / 2) / a).min(255) as u8;
-                let ub = ((b * 255 + a / 2) / a).min(255) as u8;
-                rgba.extend_from_slice(&[ur, ug, ub, a as u8]);
+                canvas
             }
         }
+    }
+}
 
-        let img = image::ImageBuffer::<image::Rgba<u8>, _>::from_vec(target, target, rgba.clone())
-            .ok_or_else(|| "Failed to create image buffer".to_string())?;
-        let di = DynamicImage::ImageRgba8(img);
+/// How the root `<svg>` element declares its size.
+struct RootSizing {
+    /// `width` is set to an absolute length
+    width: bool,
+    /// `height` is set to an absolute length
+    height: bool,
+    /// viewBox width and height
+    view_box: Option<(f32, f32)>,
+    /// `preserveAspectRatio="none"`, which stretches the viewBox over the canvas
+    stretches: bool,
+}
 
-        // Check for transparency by looking for any alpha values < 255
-        let has_transparency = rgba.chunks_exact(4).any(|pixel| pixel[3] < 255);
+impl RootSizing {
+    fn parse(svg_data: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(svg_data).ok()?;
+        let options = roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        };
+        let doc = roxmltree::Document::parse_with_options(text, options).ok()?;
+        let root = doc.root_element();
+        let absolute = |name: &str| {
+            root.attribute(name)
+                .map(str::trim)
+                .is_some_and(|value| !value.is_empty() && !value.ends_with('%'))
+        };
+        let view_box = root.attribute("viewBox").and_then(|value| {
+            let numbers: Vec<f32> = value
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|part| !part.is_empty())
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .ok()?;
+            match numbers[..] {
+                [_, _, w, h] if w > 0.0 && h > 0.0 => Some((w, h)),
+                _ => None,
+            }
+        });
 
+        Some(Self {
+            width: absolute("width"),
+            height: absolute("height"),
+            view_box,
+            stretches: root
+                .attribute("preserveAspectRatio")
+                .is_some_and(|value| value.trim() == "none"),
+        })
+    }
+}
+
+pub struct SvgGenerator;
+
+impl SvgGenerator {
+    pub fn generate(request: &ThumbnailRequest) -> Result<ThumbnailGenerationResult, String> {
+        let svg_data = fs::read(&request.path).map_err(|e| format!("Failed to read SVG: {}", e))?;
+        let rendered = rasterize(&svg_data, request.size, None)?;
+
+        let di = DynamicImage::ImageRgba8(rendered.image);
         let data_url =
             super::ThumbnailGenerator::encode_to_data_url(&di, request.format, request.quality)?;
 
-        // SVG dimensions from the parsed tree (in pixels)
         Ok(ThumbnailGenerationResult {
             data_url,
-            has_transparency,
-            image_width: Some(w.round() as u32),
-            image_height: Some(h.round() as u32),
+            has_transparency: rendered.has_transparency,
+            image_width: Some(rendered.width),
+            image_height: Some(rendered.height),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{rasterize, svg_options, SYSTEM_FONT_DB};
+
+    #[test]
+    fn renders_text_with_system_fonts() {
+        if SYSTEM_FONT_DB.is_empty() {
+            // Headless environments without any fonts have nothing to render with
+            return;
+        }
+
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="120" height="40">
+            <text x="4" y="30" font-size="28" fill="black">Marlin</text>
+        </svg>"#;
+        let tree = resvg::usvg::Tree::from_data(svg.as_bytes(), &svg_options(Some("sans-serif")))
+            .expect("parse svg");
+
+        let mut pixmap = resvg::tiny_skia::Pixmap::new(120, 40).expect("pixmap");
+        resvg::render(
+            &tree,
+            resvg::tiny_skia::Transform::identity(),
+            &mut pixmap.as_mut(),
+        );
+
+        assert!(
+            pixmap.data().chunks_exact(4).any(|px| px[3] > 0),
+            "text should produce visible pixels"
+        );
+    }
+
+    fn is_opaque(image: &image::RgbaImage, x: u32, y: u32) -> bool {
+        image.get_pixel(x, y)[3] == 255
+    }
+
+    #[test]
+    fn viewbox_only_svg_fills_the_canvas() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 24 24">
+            <rect width="24" height="24" fill="red"/>
+        </svg>"#;
+        let rendered = rasterize(svg.as_bytes(), 100, None).expect("render");
+        assert_eq!((rendered.width, rendered.height), (24, 24));
+        // 8px padding on each side, filled edge to edge inside it
+        assert!(is_opaque(&rendered.image, 9, 9));
+        assert!(is_opaque(&rendered.image, 90, 90));
+        assert!(!is_opaque(&rendered.image, 4, 50));
+    }
+
+    #[test]
+    fn missing_height_follows_the_viewbox_aspect_ratio() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="24" viewBox="0 0 48 24">
+            <rect width="48" height="24" fill="red"/>
+        </svg>"#;
+        let rendered = rasterize(svg.as_bytes(), 100, None).expect("render");
+        assert_eq!((rendered.width, rendered.height), (24, 12));
+        assert!(is_opaque(&rendered.image, 9, 50));
+        assert!(!is_opaque(&rendered.image, 50, 20));
+    }
+
+    #[test]
+    fn unsized_svg_is_framed_by_its_drawing() {
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <rect x="-20" y="-20" width="40" height="40" fill="red"/>
+        </svg>"#;
+        let rendered = rasterize(svg.as_bytes(), 100, None).expect("render");
+        assert_eq!((rendered.width, rendered.height), (40, 40));
+        assert!(is_opaque(&rendered.image, 9, 9));
+        assert!(is_opaque(&rendered.image, 90, 90));
+    }
+}
@@ -17,6 +17,8 @@ impl VideoGenerator {
     const ANALYSIS_BATCH: u32 = 120;
     const MIN_SCALE: u32 = 160;
     const MAX_SCALE: u32 = 1920;
+    /// Fraction of the duration to seek to before picking a frame, past black intros
+    const SEEK_FRACTION: f64 = 0.1;
 
     pub fn generate(request: &ThumbnailRequest) -> Result<ThumbnailGenerationResult, String> {
         let path = Path::new(&request.path);
@@ -27,7 +29,18 @@ impl VideoGenerator {
 
         let ffmpeg_path = Self::ensure_ffmpeg()?;
 
-        let frame_bytes = Self::extract_frame(&ffmpeg_path, path, request)?;
+        let seek = Self::seek_offset(Self::probe_duration(path));
+        let frame_bytes = match seek {
+            // Partial downloads (SMB) report the full duration but can't be seeked
+            // that far, so retry from the start before giving up
+            Some(secs) => {
+                Self::extract_frame(&ffmpeg_path, path, request, Some(secs)).or_else(|err| {
+                    log::debug!("Seeking to {secs:.1}s failed, retrying from the start: {err}");
+                    Self::extract_frame(&ffmpeg_path, path, request, None)
+                })?
+            }
+            None => Self::extract_frame(&ffmpeg_path, path, request, None)?,
+        };
         let frame = image::load_from_memory(&frame_bytes)
             .map_err(|e| format!("Failed to decode FFmpeg output: {e}"))?;
 
@@ -47,6 +60,7 @@ impl VideoGenerator {
 
         let target_format = match request.format {
             ThumbnailFormat::WebP | ThumbnailFormat::PNG | ThumbnailFormat::JPEG => request.format,
+            ThumbnailFormat::AnimatedWebP => ThumbnailFormat::WebP,
         };
 
         let data_url =
@@ -63,6 +77,7 @@ impl VideoGenerator {
         ffmpeg_path: &Path,
         video_path: &Path,
         request: &ThumbnailRequest,
+        seek_secs: Option<f64>,
     ) -> Result<Vec<u8>, String> {
         let filter = Self::build_filter(request.size, request.quality);
 
@@ -71,8 +86,8 @@ impl VideoGenerator {
         command.args(["-loglevel", "error"]);
         command.args(["-nostdin"]);
 
-        // Input video
-        command.args(["-i", Self::path_as_str(video_path)?]);
+        // Input video, seeked to `seek_secs`
+        command.args(Self::input_args(Self::path_as_str(video_path)?, seek_secs));
 
         // Filter selection and frame extraction
         command.args(["-vf", &filter]);
@@ -105,6 +120,34 @@ impl VideoGenerator {
         Ok(buffer)
     }
 
+    fn input_args(video_path: &str, seek_secs: Option<f64>) -> Vec<String> {
+        let mut args = Vec::new();
+        // Input seeking (before -i) jumps to the nearest keyframe without decoding
+        if let Some(secs) = seek_secs {
+            args.extend(["-ss".to_string(), format!("{secs:.3}")]);
+        }
+        args.extend(["-i".to_string(), video_path.to_string()]);
+        args
+    }
+
+    /// Container duration in seconds from FFmpeg's description of the file, if it can
+    /// be determined.
+    fn probe_duration(video_path: &Path) -> Option<f64> {
+        match crate::media_metadata::probe_with_ffmpeg(video_path) {
+            Ok((stream, _)) => stream.duration_secs,
+            Err(err) => {
+                log::debug!("Couldn't read the video's duration: {err}");
+                None
+            }
+        }
+    }
+
+    fn seek_offset(duration_secs: Option<f64>) -> Option<f64> {
+        duration_secs
+            .filter(|d| d.is_finite() && *d > 0.0)
+            .map(|d| d * Self::SEEK_FRACTION)
+    }
+
     fn build_filter(size: u32, quality: ThumbnailQuality) -> String {
         let batch = Self::ANALYSIS_BATCH;
         let scale_width = Self::scale_width(size, quality);
@@ -121,7 +164,7 @@ impl VideoGenerator {
         widened.min(Self::MAX_SCALE)
     }
 
-    fn ensure_ffmpeg() -> Result<PathBuf, String> {
+    pub(crate) fn ensure_ffmpeg() -> Result<PathBuf, String> {
         static INIT: OnceCell<PathBuf> = OnceCell::new();
         INIT.get_or_try_init(|| Self::prepare_ffmpeg_binary())
             .cloned()
@@ -204,4 +247,25 @@ mod tests {
         assert_eq!(VideoGenerator::scale_width(120, Medium), 240);
         assert_eq!(VideoGenerator::scale_width(800, High), 1920);
     }
+
+    #[test]
+    fn seeks_a_tenth_into_known_durations() {
+        assert_eq!(VideoGenerator::seek_offset(Some(120.0)), Some(12.0));
+        assert_eq!(VideoGenerator::seek_offset(Some(0.0)), None);
+        assert_eq!(VideoGenerator::seek_offset(Some(f64::NAN)), None);
+        assert_eq!(VideoGenerator::seek_offset(None), None);
+    }
+
+    #[test]
+    fn seeks_before_opening_the_input() {
+        let seek = VideoGenerator::seek_offset(Some(125.0));
+        assert_eq!(
+            VideoGenerator::input_args("clip.mp4", seek),
+            ["-ss", "12.500", "-i", "clip.mp4"]
+        );
+        assert_eq!(
+            VideoGenerator::input_args("clip.mp4", None),
+            ["-i", "clip.mp4"]
+        );
+    }
 }
@@ -0,0 +1,749 @@
+//! Unwraps WOFF and WOFF2 web fonts into the plain sfnt (TTF/OTF) data the
+//! glyph rasterizer understands. Only what rendering needs is rebuilt; table
+//! checksums are recomputed but metadata and private blocks are dropped.
+
+use std::io::Read;
+
+const WOFF_SIGNATURE: u32 = u32::from_be_bytes(*b"wOFF");
+const WOFF2_SIGNATURE: u32 = u32::from_be_bytes(*b"wOF2");
+const TTC_FLAVOR: u32 = u32::from_be_bytes(*b"ttcf");
+
+/// Refuse to inflate fonts past this, whatever their headers claim.
+const MAX_SFNT_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Tags WOFF2 can encode as a 6-bit index instead of spelling them out.
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+/// Whether `data` is a WOFF or WOFF2 container.
+pub fn is_web_font(data: &[u8]) -> bool {
+    matches!(
+        Reader::new(data).u32(),
+        Ok(WOFF_SIGNATURE | WOFF2_SIGNATURE)
+    )
+}
+
+/// Convert a WOFF or WOFF2 font into sfnt data.
+pub fn to_sfnt(data: &[u8]) -> Result<Vec<u8>, String> {
+    match Reader::new(data).u32()? {
+        WOFF_SIGNATURE => decode_woff(data),
+        WOFF2_SIGNATURE => decode_woff2(data),
+        _ => Err("Not a WOFF font".to_string()),
+    }
+}
+
+struct Table {
+    tag: [u8; 4],
+    data: Vec<u8>,
+}
+
+fn decode_woff(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut header = Reader::new(data);
+    header.skip(4)?;
+    let flavor = header.u32()?;
+    header.skip(4)?;
+    let num_tables = header.u16()?;
+    header.seek(44)?;
+
+    let mut tables = Vec::with_capacity(usize::from(num_tables));
+    for _ in 0..num_tables {
+        let tag = header.tag()?;
+        let offset = header.u32()? as usize;
+        let comp_length = header.u32()? as usize;
+        let orig_length = header.u32()? as usize;
+        header.skip(4)?;
+
+        let stored = slice(data, offset, comp_length).ok_or("WOFF table data is out of bounds")?;
+        let table = if comp_length < orig_length {
+            inflate(flate2::read::ZlibDecoder::new(stored), orig_length as u64)?
+        } else {
+            stored.to_vec()
+        };
+        if table.len() != orig_length {
+            return Err("WOFF table has the wrong length".to_string());
+        }
+        tables.push(Table { tag, data: table });
+    }
+
+    build_sfnt(flavor, tables)
+}
+
+struct Woff2Entry {
+    tag: [u8; 4],
+    /// Bytes the table occupies in the decompressed stream
+    stored_length: usize,
+    transformed: bool,
+}
+
+fn decode_woff2(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut header = Reader::new(data);
+    header.skip(4)?;
+    let flavor = header.u32()?;
+    if flavor == TTC_FLAVOR {
+        return Err("WOFF2 font collections aren't supported".to_string());
+    }
+    header.skip(4)?;
+    let num_tables = header.u16()?;
+    header.skip(6)?;
+    let compressed_size = header.u32()? as usize;
+    header.seek(48)?;
+
+    let mut entries = Vec::with_capacity(usize::from(num_tables));
+    for _ in 0..num_tables {
+        let flags = header.u8()?;
+        let tag = match usize::from(flags & 0x3f) {
+            63 => header.tag()?,
+            index => *KNOWN_TAGS[index],
+        };
+        let version = flags >> 6;
+        // glyf and loca use version 3 for "not transformed", everything else uses 0
+        let transformed = if &tag == b"glyf" || &tag == b"loca" {
+            version != 3
+        } else {
+            version != 0
+        };
+        let orig_length = header.base128()? as usize;
+        let stored_length = if transformed {
+            header.base128()? as usize
+        } else {
+            orig_length
+        };
+        entries.push(Woff2Entry {
+            tag,
+            stored_length,
+            transformed,
+        });
+    }
+
+    let compressed =
+        slice(data, header.pos, compressed_size).ok_or("WOFF2 font data is truncated")?;
+    let stream = inflate(
+        brotli_decompressor::Decompressor::new(compressed, 4096),
+        MAX_SFNT_SIZE,
+    )?;
+
+    let mut offset = 0usize;
+    let mut stored = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        stored.push(
+            slice(&stream, offset, entry.stored_length)
+                .ok_or("WOFF2 table data is out of bounds")?,
+        );
+        offset += entry.stored_length;
+    }
+    let find = |tag: &[u8; 4]| entries.iter().position(|entry| &entry.tag == tag);
+
+    let mut glyf_and_loca = None;
+    if let Some(glyf) = find(b"glyf").filter(|&index| entries[index].transformed) {
+        glyf_and_loca = Some(reconstruct_glyf(stored[glyf])?);
+    }
+
+    let mut tables = Vec::with_capacity(entries.len());
+    for (index, entry) in entries.iter().enumerate() {
+        let data = match (&entry.tag, &glyf_and_loca) {
+            (b"glyf", Some(rebuilt)) => rebuilt.glyf.clone(),
+            (b"loca", Some(rebuilt)) => rebuilt.loca.clone(),
+            (b"hmtx", _) if entry.transformed => {
+                let rebuilt = glyf_and_loca
+                    .as_ref()
+                    .ok_or("Transformed hmtx needs a transformed glyf table")?;
+                let hhea = find(b"hhea").ok_or("Font has no hhea table")?;
+                let num_h_metrics = Reader::new(stored[hhea]).at(34)?.u16()?;
+                reconstruct_hmtx(stored[index], num_h_metrics, &rebuilt.x_mins)?
+            }
+            _ if entry.transformed => {
+                return Err(format!(
+                    "Unsupported WOFF2 transform for the {} table",
+                    String::from_utf8_lossy(&entry.tag)
+                ))
+            }
+            _ => stored[index].to_vec(),
+        };
+        tables.push(Table {
+            tag: entry.tag,
+            data,
+        });
+    }
+
+    build_sfnt(flavor, tables)
+}
+
+struct RebuiltGlyphs {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+    /// Left edge of each glyph, for rebuilding side bearings
+    x_mins: Vec<i16>,
+}
+
+/// Undo the WOFF2 glyf transform: split streams back into per-glyph records and
+/// regenerate the loca offsets that point at them.
+fn reconstruct_glyf(data: &[u8]) -> Result<RebuiltGlyphs, String> {
+    let mut header = Reader::new(data);
+    header.skip(2)?;
+    let option_flags = header.u16()?;
+    let num_glyphs = usize::from(header.u16()?);
+    let index_format = header.u16()?;
+    let mut sizes = [0usize; 7];
+    for size in &mut sizes {
+        *size = header.u32()? as usize;
+    }
+
+    let mut offset = header.pos;
+    let mut stream = |size: usize| {
+        let bytes = slice(data, offset, size).ok_or("WOFF2 glyf stream is truncated")?;
+        offset += size;
+        Ok::<_, String>(Reader::new(bytes))
+    };
+    let mut contours = stream(sizes[0])?;
+    let mut points = stream(sizes[1])?;
+    let mut flags = stream(sizes[2])?;
+    let mut glyphs = stream(sizes[3])?;
+    let mut composites = stream(sizes[4])?;
+    let mut bboxes = stream(sizes[5])?;
+    let mut instructions = stream(sizes[6])?;
+    let overlap_bitmap = if option_flags & 1 != 0 {
+        let length = num_glyphs.div_ceil(8);
+        Some(slice(data, offset, length).ok_or("WOFF2 glyf overlap bitmap is truncated")?)
+    } else {
+        None
+    };
+    let bitmap_length = num_glyphs.div_ceil(32) * 4;
+    let bbox_bitmap = bboxes.bytes(bitmap_length)?;
+    let has_bit = |bitmap: &[u8], index: usize| bitmap[index >> 3] & (0x80 >> (index & 7)) != 0;
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    let mut x_mins = Vec::with_capacity(num_glyphs);
+
+    for index in 0..num_glyphs {
+        offsets.push(glyf.len());
+        let explicit_bbox = if has_bit(bbox_bitmap, index) {
+            Some([bboxes.i16()?, bboxes.i16()?, bboxes.i16()?, bboxes.i16()?])
+        } else {
+            None
+        };
+
+        let contour_count = contours.i16()?;
+        match contour_count {
+            0 => x_mins.push(0),
+            -1 => {
+                let [x_min, y_min, x_max, y_max] =
+                    explicit_bbox.ok_or("WOFF2 composite glyph has no bounding box")?;
+                let start = composites.pos;
+                let mut has_instructions = false;
+                loop {
+                    let component_flags = composites.u16()?;
+                    composites.skip(2)?;
+                    let mut length = if component_flags & 0x0001 != 0 { 4 } else { 2 };
+                    if component_flags & 0x0008 != 0 {
+                        length += 2;
+                    } else if component_flags & 0x0040 != 0 {
+                        length += 4;
+                    } else if component_flags & 0x0080 != 0 {
+                        length += 8;
+                    }
+                    composites.skip(length)?;
+                    has_instructions |= component_flags & 0x0100 != 0;
+                    if component_flags & 0x0020 == 0 {
+                        break;
+                    }
+                }
+                let components = &composites.data[start..composites.pos];
+
+                glyf.extend_from_slice(&(-1i16).to_be_bytes());
+                for value in [x_min, y_min, x_max, y_max] {
+                    glyf.extend_from_slice(&value.to_be_bytes());
+                }
+                glyf.extend_from_slice(components);
+                if has_instructions {
+                    let length = glyphs.u255_16()?;
+                    glyf.extend_from_slice(&length.to_be_bytes());
+                    glyf.extend_from_slice(instructions.bytes(usize::from(length))?);
+                }
+                x_mins.push(x_min);
+            }
+            count if count > 0 => {
+                // glyf stores end points as u16, so a glyph has at most 65536 points
+                let mut end_points = Vec::with_capacity(count as usize);
+                let mut total = 0usize;
+                for _ in 0..count {
+                    total += usize::from(points.u255_16()?);
+                    let end = total
+                        .checked_sub(1)
+                        .and_then(|end| u16::try_from(end).ok())
+                        .ok_or("WOFF2 glyph has an invalid point count")?;
+                    end_points.push(end);
+                }
+
+                let mut deltas = Vec::with_capacity(total);
+                for _ in 0..total {
+                    let flag = flags.u8()?;
+                    let (dx, dy) = decode_triplet(flag & 0x7f, &mut glyphs)?;
+                    deltas.push((dx, dy, flag & 0x80 == 0));
+                }
+                let instruction_length = glyphs.u255_16()?;
+                let instruction_bytes = instructions.bytes(usize::from(instruction_length))?;
+
+                let bbox = explicit_bbox.unwrap_or_else(|| bounding_box(&deltas));
+                x_mins.push(bbox[0]);
+
+                let overlap = overlap_bitmap.is_some_and(|bitmap| has_bit(bitmap, index));
+                glyf.extend_from_slice(&count.to_be_bytes());
+                for value in bbox {
+                    glyf.extend_from_slice(&value.to_be_bytes());
+                }
+                for end in end_points {
+                    glyf.extend_from_slice(&end.to_be_bytes());
+                }
+                glyf.extend_from_slice(&instruction_length.to_be_bytes());
+                glyf.extend_from_slice(instruction_bytes);
+                encode_points(&mut glyf, &deltas, overlap)?;
+            }
+            _ => return Err("WOFF2 glyph has an invalid contour count".to_string()),
+        }
+
+        // Keep every glyph 4-byte aligned, which suits either loca format
+        while glyf.len() % 4 != 0 {
+            glyf.push(0);
+        }
+    }
+    offsets.push(glyf.len());
+
+    let mut loca = Vec::with_capacity(offsets.len() * 4);
+    for offset in offsets {
+        if index_format == 0 {
+            let half = u16::try_from(offset / 2).map_err(|_| "WOFF2 glyf table is too large")?;
+            loca.extend_from_slice(&half.to_be_bytes());
+        } else {
+            let offset = u32::try_from(offset).map_err(|_| "WOFF2 glyf table is too large")?;
+            loca.extend_from_slice(&offset.to_be_bytes());
+        }
+    }
+
+    Ok(RebuiltGlyphs { glyf, loca, x_mins })
+}
+
+/// One point of a WOFF2 glyph: a coordinate delta packed into 1–4 bytes,
+/// with the packing chosen by the point's flag.
+fn decode_triplet(flag: u8, glyphs: &mut Reader) -> Result<(i32, i32), String> {
+    let with_sign = |flag: u8, value: i32| if flag & 1 != 0 { value } else { -value };
+    let flag_value = i32::from(flag);
+    Ok(match flag {
+        0..=9 => {
+            let b0 = i32::from(glyphs.u8()?);
+            (0, with_sign(flag, ((flag_value & 14) << 7) + b0))
+        }
+        10..=19 => {
+            let b0 = i32::from(glyphs.u8()?);
+            (with_sign(flag, (((flag_value - 10) & 14) << 7) + b0), 0)
+        }
+        20..=83 => {
+            let packed = flag_value - 20;
+            let b1 = i32::from(glyphs.u8()?);
+            (
+                with_sign(flag, 1 + (packed & 0x30) + (b1 >> 4)),
+                with_sign(flag >> 1, 1 + ((packed & 0x0c) << 2) + (b1 & 0x0f)),
+            )
+        }
+        84..=119 => {
+            let packed = flag_value - 84;
+            let b1 = i32::from(glyphs.u8()?);
+            let b2 = i32::from(glyphs.u8()?);
+            (
+                with_sign(flag, 1 + ((packed / 12) << 8) + b1),
+                with_sign(flag >> 1, 1 + (((packed % 12) >> 2) << 8) + b2),
+            )
+        }
+        120..=123 => {
+            let b1 = i32::from(glyphs.u8()?);
+            let b2 = i32::from(glyphs.u8()?);
+            let b3 = i32::from(glyphs.u8()?);
+            (
+                with_sign(flag, (b1 << 4) + (b2 >> 4)),
+                with_sign(flag >> 1, ((b2 & 0x0f) << 8) + b3),
+            )
+        }
+        _ => {
+            let dx = i32::from(glyphs.u16()?);
+            let dy = i32::from(glyphs.u16()?);
+            (with_sign(flag, dx), with_sign(flag >> 1, dy))
+        }
+    })
+}
+
+fn bounding_box(deltas: &[(i32, i32, bool)]) -> [i16; 4] {
+    let (mut x, mut y) = (0i32, 0i32);
+    let mut bbox: Option<[i32; 4]> = None;
+    for &(dx, dy, _) in deltas {
+        x += dx;
+        y += dy;
+        bbox = Some(match bbox {
+            None => [x, y, x, y],
+            Some([x_min, y_min, x_max, y_max]) => {
+                [x_min.min(x), y_min.min(y), x_max.max(x), y_max.max(y)]
+            }
+        });
+    }
+    bbox.unwrap_or_default()
+        .map(|value| value.clamp(i32::from(i16::MIN), i32::from(i16::MAX)) as i16)
+}
+
+/// Write points in the regular glyf encoding: one flag per point, then the x
+/// and y deltas, each as a byte when it fits.
+fn encode_points(
+    glyf: &mut Vec<u8>,
+    deltas: &[(i32, i32, bool)],
+    overlap: bool,
+) -> Result<(), String> {
+    const ON_CURVE: u8 = 0x01;
+    const X_SHORT: u8 = 0x02;
+    const Y_SHORT: u8 = 0x04;
+    const X_SAME_OR_POSITIVE: u8 = 0x10;
+    const Y_SAME_OR_POSITIVE: u8 = 0x20;
+    const OVERLAP_SIMPLE: u8 = 0x40;
+
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    for (index, &(dx, dy, on_curve)) in deltas.iter().enumerate() {
+        let mut flag = if on_curve { ON_CURVE } else { 0 };
+        if overlap && index == 0 {
+            flag |= OVERLAP_SIMPLE;
+        }
+        flag |= encode_delta(dx, X_SHORT, X_SAME_OR_POSITIVE, &mut xs)?;
+        flag |= encode_delta(dy, Y_SHORT, Y_SAME_OR_POSITIVE, &mut ys)?;
+        glyf.push(flag);
+    }
+    glyf.extend_from_slice(&xs);
+    glyf.extend_from_slice(&ys);
+    Ok(())
+}
+
+fn encode_delta(
+    delta: i32,
+    short: u8,
+    same_or_positive: u8,
+    out: &mut Vec<u8>,
+) -> Result<u8, String> {
+    if delta == 0 {
+        return Ok(same_or_positive);
+    }
+    if delta.unsigned_abs() < 256 {
+        out.push(delta.unsigned_abs() as u8);
+        return Ok(if delta > 0 {
+            short | same_or_positive
+        } else {
+            short
+        });
+    }
+    let value = i16::try_from(delta).map_err(|_| "WOFF2 glyph coordinate is out of range")?;
+    out.extend_from_slice(&value.to_be_bytes());
+    Ok(0)
+}
+
+/// Undo the WOFF2 hmtx transform, which drops side bearings that equal each
+/// glyph's `x_min`.
+fn reconstruct_hmtx(data: &[u8], num_h_metrics: u16, x_mins: &[i16]) -> Result<Vec<u8>, String> {
+    let mut reader = Reader::new(data);
+    let flags = reader.u8()?;
+    let num_h_metrics = usize::from(num_h_metrics);
+    if num_h_metrics == 0 || num_h_metrics > x_mins.len() {
+        return Err("WOFF2 hmtx table doesn't match the glyph count".to_string());
+    }
+
+    let mut advances = Vec::with_capacity(num_h_metrics);
+    for _ in 0..num_h_metrics {
+        advances.push(reader.u16()?);
+    }
+    let mut bearings = Vec::with_capacity(x_mins.len());
+    for (index, &x_min) in x_mins.iter().enumerate() {
+        let omitted = if index < num_h_metrics {
+            flags & 1 != 0
+        } else {
+            flags & 2 != 0
+        };
+        bearings.push(if omitted { x_min } else { reader.i16()? });
+    }
+
+    let mut hmtx = Vec::with_capacity(num_h_metrics * 4 + (x_mins.len() - num_h_metrics) * 2);
+    for (index, bearing) in bearings.into_iter().enumerate() {
+        if let Some(advance) = advances.get(index) {
+            hmtx.extend_from_slice(&advance.to_be_bytes());
+        }
+        hmtx.extend_from_slice(&bearing.to_be_bytes());
+    }
+    Ok(hmtx)
+}
+
+/// Lay tables out as an sfnt: offset table, sorted table records, then
+/// 4-byte aligned table data.
+fn build_sfnt(flavor: u32, mut tables: Vec<Table>) -> Result<Vec<u8>, String> {
+    tables.sort_by_key(|table| table.tag);
+    // The offset table's search fields are u16 multiples of 16
+    let num_tables = u16::try_from(tables.len())
+        .ok()
+        .filter(|&count| count < 4096)
+        .ok_or("Font has too many tables")?;
+    let entry_selector = if num_tables == 0 {
+        0
+    } else {
+        15 - num_tables.leading_zeros() as u16
+    };
+    let search_range = (1u16 << entry_selector) * 16;
+
+    let mut sfnt = Vec::new();
+    sfnt.extend_from_slice(&flavor.to_be_bytes());
+    sfnt.extend_from_slice(&num_tables.to_be_bytes());
+    sfnt.extend_from_slice(&search_range.to_be_bytes());
+    sfnt.extend_from_slice(&entry_selector.to_be_bytes());
+    sfnt.extend_from_slice(&(num_tables * 16).saturating_sub(search_range).to_be_bytes());
+
+    let mut offset = 12 + tables.len() * 16;
+    for table in &tables {
+        let too_large = || "Font is too large".to_string();
+        let table_offset = u32::try_from(offset).map_err(|_| too_large())?;
+        let table_length = u32::try_from(table.data.len()).map_err(|_| too_large())?;
+        sfnt.extend_from_slice(&table.tag);
+        sfnt.extend_from_slice(&checksum(&table.data).to_be_bytes());
+        sfnt.extend_from_slice(&table_offset.to_be_bytes());
+        sfnt.extend_from_slice(&table_length.to_be_bytes());
+        offset += table.data.len().next_multiple_of(4);
+    }
+    if offset as u64 > MAX_SFNT_SIZE {
+        return Err("Font data is larger than expected".to_string());
+    }
+    for table in tables {
+        sfnt.extend_from_slice(&table.data);
+        sfnt.resize(sfnt.len().next_multiple_of(4), 0);
+    }
+    Ok(sfnt)
+}
+
+/// `length` bytes of `data` from `offset`, or `None` if any of them fall outside it.
+fn slice(data: &[u8], offset: usize, length: usize) -> Option<&[u8]> {
+    data.get(offset..offset.checked_add(length)?)
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn inflate<R: Read>(reader: R, limit: u64) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    reader
+        .take(limit.min(MAX_SFNT_SIZE) + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| format!("Failed to decompress font data: {}", e))?;
+    if out.len() as u64 > limit.min(MAX_SFNT_SIZE) {
+        return Err("Font data is larger than expected".to_string());
+    }
+    Ok(out)
+}
+
+/// Big-endian cursor over font data.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn at(mut self, pos: usize) -> Result<Self, String> {
+        self.seek(pos)?;
+        Ok(self)
+    }
+
+    fn seek(&mut self, pos: usize) -> Result<(), String> {
+        if pos > self.data.len() {
+            return Err("Font data is truncated".to_string());
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), String> {
+        self.bytes(count).map(|_| ())
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let bytes = slice(self.data, self.pos, count).ok_or("Font data is truncated")?;
+        self.pos += count;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        self.u16().map(|value| value as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn tag(&mut self) -> Result<[u8; 4], String> {
+        Ok(self.u32()?.to_be_bytes())
+    }
+
+    /// WOFF2's variable-length `UIntBase128`.
+    fn base128(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for index in 0..5 {
+            let byte = self.u8()?;
+            if index == 0 && byte == 0x80 {
+                return Err("Invalid WOFF2 length".to_string());
+            }
+            if value & 0xFE00_0000 != 0 {
+                return Err("WOFF2 length overflows".to_string());
+            }
+            value = (value << 7) | u32::from(byte & 0x7f);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Invalid WOFF2 length".to_string())
+    }
+
+    /// WOFF2's variable-length `255UInt16`.
+    fn u255_16(&mut self) -> Result<u16, String> {
+        Ok(match self.u8()? {
+            253 => self.u16()?,
+            254 => u16::from(self.u8()?) + 506,
+            255 => u16::from(self.u8()?) + 253,
+            code => u16::from(code),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_point_triplets() {
+        let decode = |flag: u8, bytes: &[u8]| {
+            let mut reader = Reader::new(bytes);
+            let delta = decode_triplet(flag, &mut reader).unwrap();
+            assert_eq!(reader.pos, bytes.len());
+            delta
+        };
+        assert_eq!(decode(1, &[5]), (0, 5));
+        assert_eq!(decode(10, &[5]), (-5, 0));
+        // 4-bit dx and dy packed in one byte
+        assert_eq!(decode(23, &[0x12]), (2, 3));
+        assert_eq!(decode(84, &[1, 2]), (-2, -3));
+        assert_eq!(decode(123, &[0x12, 0x34, 0x56]), (0x123, 0x456));
+        assert_eq!(decode(124, &[0x01, 0x00, 0x00, 0x02]), (-256, -2));
+    }
+
+    #[test]
+    fn rewrites_simple_glyph_points() {
+        let deltas = [(10, 0, true), (0, 300, false), (-10, -300, true)];
+        let mut glyf = Vec::new();
+        encode_points(&mut glyf, &deltas, true).unwrap();
+        assert_eq!(
+            glyf,
+            [
+                0x01 | 0x02 | 0x10 | 0x20 | 0x40,
+                0x10,
+                0x02 | 0x01,
+                10,
+                10,
+                0x01,
+                0x2c,
+                0xfe,
+                0xd4,
+            ]
+        );
+        assert_eq!(bounding_box(&deltas), [0, 0, 10, 300]);
+    }
+
+    /// Decode a fixture and check the result is a font the rasterizer can draw.
+    fn decode_fixture(name: &str) -> (u16, [i16; 4]) {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/fonts")
+            .join(name);
+        let data = std::fs::read(path).unwrap();
+        assert!(is_web_font(&data));
+        let sfnt = to_sfnt(&data).unwrap();
+        let face = ttf_parser::Face::parse(&sfnt, 0).unwrap();
+
+        struct Outline(usize);
+        impl ttf_parser::OutlineBuilder for Outline {
+            fn move_to(&mut self, _: f32, _: f32) {}
+            fn line_to(&mut self, _: f32, _: f32) {
+                self.0 += 1;
+            }
+            fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {
+                self.0 += 1;
+            }
+            fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {
+                self.0 += 1;
+            }
+            fn close(&mut self) {}
+        }
+        let glyph = face.glyph_index('A').unwrap();
+        let mut outline = Outline(0);
+        let bbox = face.outline_glyph(glyph, &mut outline).unwrap();
+        assert!(outline.0 > 0);
+        (
+            face.number_of_glyphs(),
+            [bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max],
+        )
+    }
+
+    #[test]
+    fn decodes_real_fonts() {
+        assert_eq!(decode_fixture("demo.woff"), (2, [6, 0, 541, 656]));
+        assert_eq!(
+            decode_fixture("OpenSans-Regular.woff2"),
+            (902, [0, 0, 1296, 1468])
+        );
+    }
+
+    #[test]
+    fn rejects_corrupt_glyphs() {
+        // One glyph with one contour of zero points
+        let mut glyf = vec![0, 0, 0, 0, 0, 1, 0, 0];
+        for size in [2u32, 1, 0, 1, 0, 4, 0] {
+            glyf.extend_from_slice(&size.to_be_bytes());
+        }
+        glyf.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+        assert!(reconstruct_glyf(&glyf).is_err());
+
+        // Stream sizes that would wrap the offset
+        let mut glyf = vec![0, 0, 0, 0, 0, 1, 0, 0];
+        for size in [u32::MAX; 7] {
+            glyf.extend_from_slice(&size.to_be_bytes());
+        }
+        assert!(reconstruct_glyf(&glyf).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_containers() {
+        assert!(!is_web_font(b"\0\x01\0\0"));
+        assert!(is_web_font(b"wOF2\0\0"));
+        assert!(to_sfnt(b"OTTO").is_err());
+    }
+}
@@ -1,6 +1,8 @@
 use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::UNIX_EPOCH;
 use uuid::Uuid;
 
@@ -8,8 +10,10 @@ pub mod cache;
 pub mod generators;
 pub mod worker;
 
-/// Version prefix for cache keys - increment to invalidate all existing cache entries
-const CACHE_KEY_VERSION: &str = "v2";
+/// Thumbnail format version. Bump it whenever generated thumbnails change (encoder
+/// settings, orientation handling, ...): it feeds every cache key and names the disk
+/// cache directory, so older entries are never served and are pruned on startup.
+pub const CACHE_FORMAT_VERSION: u32 = 4;
 
 /// File identity information used for cache key generation.
 /// Includes multiple attributes to detect file changes that mtime alone might miss.
@@ -63,7 +67,7 @@ pub fn get_file_identity(path: &Path) -> FileIdentity {
 use cache::ThumbnailCache;
 use worker::ThumbnailWorker;
 
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub struct AccentColor {
     pub r: u8,
     pub g: u8,
@@ -102,6 +106,8 @@ pub enum ThumbnailFormat {
     WebP,
     PNG,
     JPEG,
+    /// Animated GIFs and WebPs keep their motion; everything else is a still WebP
+    AnimatedWebP,
 }
 
 /// Result of thumbnail generation including metadata
@@ -128,9 +134,23 @@ pub struct ThumbnailResponse {
     pub image_height: Option<u32>,
 }
 
+/// Outcome of one request in a batch. Cached items come back from the call itself;
+/// the rest are delivered one by one as they finish.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailBatchItem {
+    pub batch_id: String,
+    pub path: String,
+    pub size: u32,
+    pub response: Option<ThumbnailResponse>,
+    pub error: Option<String>,
+}
+
 pub struct ThumbnailService {
     cache: Arc<ThumbnailCache>,
     worker: Arc<ThumbnailWorker>,
+    /// Request ids still queued or generating, per batch id
+    batches: Arc<Mutex<HashMap<String, Vec<String>>>>,
 }
 
 impl ThumbnailService {
@@ -138,7 +158,11 @@ impl ThumbnailService {
         let cache = Arc::new(ThumbnailCache::new().await?);
         let worker = Arc::new(ThumbnailWorker::new(cache.clone()).await?);
 
-        Ok(ThumbnailService { cache, worker })
+        Ok(ThumbnailService {
+            cache,
+            worker,
+            batches: Arc::new(Mutex::new(HashMap::new())),
+        })
     }
 
     pub async fn request_thumbnail(
@@ -162,6 +186,9 @@ impl ThumbnailService {
                     | Some("tiff")
                     | Some("tga")
                     | Some("ico")
+                    | Some("icns")
+                    | Some("avif")
+                    | Some("jxl")
             )
         }
 
@@ -170,7 +197,12 @@ impl ThumbnailService {
         // Try cache first (L1 memory, then L2 disk)
         if let Some((cached_data, has_transparency, image_width, image_height)) = self
             .cache
-            .get(&request.path, request.size, request.accent.as_ref())
+            .get(
+                &request.path,
+                request.size,
+                request.accent.as_ref(),
+                request.format == ThumbnailFormat::AnimatedWebP,
+            )
             .await
         {
             log::info!(
@@ -219,6 +251,129 @@ impl ThumbnailService {
         self.worker.submit_request(request).await
     }
 
+    /// Look up a batch of requests at once. Cache hits are returned right away; the
+    /// misses are queued with their own priorities and handed to `on_complete` as each
+    /// one finishes. Requests for the same path, size and accent are only generated
+    /// once. Reusing the id of a batch still in flight adds to that batch, so
+    /// cancelling it stops both.
+    pub async fn request_batch<F>(
+        &self,
+        batch_id: String,
+        requests: Vec<ThumbnailRequest>,
+        on_complete: F,
+    ) -> Vec<ThumbnailBatchItem>
+    where
+        F: Fn(ThumbnailBatchItem) + Send + Sync + 'static,
+    {
+        let mut seen = HashSet::new();
+        let mut cached = Vec::new();
+        let mut pending = Vec::new();
+
+        for request in requests {
+            // Same key the cache uses, so duplicates would only produce the same thumbnail
+            let animated = request.format == ThumbnailFormat::AnimatedWebP;
+            if !seen.insert((request.path.clone(), request.size, request.accent, animated)) {
+                continue;
+            }
+            match self
+                .cache
+                .get(
+                    &request.path,
+                    request.size,
+                    request.accent.as_ref(),
+                    animated,
+                )
+                .await
+            {
+                Some((data_url, has_transparency, image_width, image_height)) => {
+                    cached.push(ThumbnailBatchItem {
+                        batch_id: batch_id.clone(),
+                        path: request.path,
+                        size: request.size,
+                        response: Some(ThumbnailResponse {
+                            id: request.id,
+                            data_url,
+                            cached: true,
+                            generation_time_ms: 0,
+                            has_transparency,
+                            image_width,
+                            image_height,
+                        }),
+                        error: None,
+                    });
+                }
+                None => pending.push(request),
+            }
+        }
+
+        if pending.is_empty() {
+            return cached;
+        }
+
+        if let Ok(mut batches) = self.batches.lock() {
+            batches
+                .entry(batch_id.clone())
+                .or_default()
+                .extend(pending.iter().map(|request| request.id.clone()));
+        }
+
+        let on_complete = Arc::new(on_complete);
+        for request in pending {
+            let worker = self.worker.clone();
+            let batches = self.batches.clone();
+            let on_complete = on_complete.clone();
+            let batch_id = batch_id.clone();
+            tokio::spawn(async move {
+                let (path, size, id) = (request.path.clone(), request.size, request.id.clone());
+                let result = worker.submit_request(request).await;
+
+                // Cancelled batches are gone from the map; their results are dropped
+                let still_wanted = batches.lock().is_ok_and(|mut batches| {
+                    let Some(ids) = batches.get_mut(&batch_id) else {
+                        return false;
+                    };
+                    ids.retain(|pending_id| *pending_id != id);
+                    if ids.is_empty() {
+                        batches.remove(&batch_id);
+                    }
+                    true
+                });
+                if !still_wanted {
+                    return;
+                }
+
+                let (response, error) = match result {
+                    Ok(response) => (Some(response), None),
+                    Err(err) => (None, Some(err)),
+                };
+                on_complete(ThumbnailBatchItem {
+                    batch_id,
+                    path,
+                    size,
+                    response,
+                    error,
+                });
+            });
+        }
+
+        cached
+    }
+
+    /// Drop everything still queued for `batch_id`. Returns whether the batch had work left.
+    pub async fn cancel_batch(&self, batch_id: &str) -> bool {
+        let ids = match self.batches.lock() {
+            Ok(mut batches) => batches.remove(batch_id),
+            Err(_) => None,
+        };
+        let Some(ids) = ids else {
+            return false;
+        };
+        for id in ids {
+            self.worker.cancel_request(&id).await;
+        }
+        true
+    }
+
     pub async fn cancel_request(&self, id: &str) -> bool {
         self.worker.cancel_request(id).await
     }
@@ -235,6 +390,25 @@ impl ThumbnailService {
         self.cache.clear().await
     }
 
+    /// An SVG rendering stored by [`Self::cache_svg`], keyed by [`generate_svg_cache_key`].
+    pub async fn cached_svg(&self, key: &str) -> Option<String> {
+        self.cache
+            .get_content(key)
+            .await
+            .map(|(data_url, ..)| data_url)
+    }
+
+    pub async fn cache_svg(
+        &self,
+        key: &str,
+        data_url: String,
+        generation_time_ms: u64,
+    ) -> Result<(), String> {
+        self.cache
+            .put_content(key, data_url, generation_time_ms, true, None, None)
+            .await
+    }
+
     /// Invalidate cache entries for the given paths.
     /// Called when files are modified/removed to ensure fresh thumbnails on next request.
     pub async fn invalidate_paths(&self, paths: &[String]) {
@@ -243,7 +417,7 @@ impl ThumbnailService {
 }
 
 /// Generate a cache key for a thumbnail based on path, size, file identity, and accent color.
-/// Uses CACHE_KEY_VERSION prefix to allow invalidation of old cache entries.
+/// Includes CACHE_FORMAT_VERSION so old cache entries are invalidated when it changes.
 pub fn generate_cache_key(
     path: &str,
     thumb_size: u32,
@@ -251,7 +425,7 @@ pub fn generate_cache_key(
     accent: Option<&AccentColor>,
 ) -> String {
     let mut hasher = Sha256::new();
-    hasher.update(CACHE_KEY_VERSION.as_bytes());
+    hasher.update(CACHE_FORMAT_VERSION.to_be_bytes());
     hasher.update(path.as_bytes());
     hasher.update(thumb_size.to_be_bytes());
     hasher.update(identity.size.to_be_bytes());
@@ -266,6 +440,105 @@ pub fn generate_cache_key(
     hex::encode(result)[..16].to_string()
 }
 
+/// Largest file whose thumbnail is shared with identical files. Big videos and raw
+/// photos rarely have byte-identical copies worth the extra reads.
+const CONTENT_DEDUP_MAX_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Bytes hashed from the start, middle and end of a file for its content fingerprint
+const FINGERPRINT_BLOCK_BYTES: u64 = 64 * 1024;
+
+/// Whether identical files share one cached thumbnail; on unless
+/// `globalPreferences.shareIdenticalThumbnails` is false.
+static CONTENT_DEDUP_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Read `globalPreferences.shareIdenticalThumbnails` from a preferences JSON document.
+pub fn configure_from_preferences(json: &str) {
+    let enabled = serde_json::from_str::<serde_json::Value>(json)
+        .ok()
+        .and_then(|value| {
+            value
+                .get("globalPreferences")?
+                .get("shareIdenticalThumbnails")?
+                .as_bool()
+        })
+        .unwrap_or(true);
+    CONTENT_DEDUP_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn content_dedup_enabled() -> bool {
+    CONTENT_DEDUP_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Content fingerprint: a hash of the size and the first, middle and last
+/// `FINGERPRINT_BLOCK_BYTES` (the whole file when it's smaller than that), so identical
+/// files at different paths share one. `None` for files over `CONTENT_DEDUP_MAX_BYTES`.
+pub fn content_fingerprint(path: &Path) -> Option<String> {
+    use siphasher::sip128::{Hasher128, SipHasher13};
+    use std::hash::Hasher;
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(path).ok()?;
+    let size = file.metadata().ok()?.len();
+    if size > CONTENT_DEDUP_MAX_BYTES {
+        return None;
+    }
+
+    let mut hasher = SipHasher13::new();
+    hasher.write(&size.to_le_bytes());
+    let blocks = if size <= 3 * FINGERPRINT_BLOCK_BYTES {
+        vec![(0, size)]
+    } else {
+        vec![
+            (0, FINGERPRINT_BLOCK_BYTES),
+            (
+                (size - FINGERPRINT_BLOCK_BYTES) / 2,
+                FINGERPRINT_BLOCK_BYTES,
+            ),
+            (size - FINGERPRINT_BLOCK_BYTES, FINGERPRINT_BLOCK_BYTES),
+        ]
+    };
+    let mut buffer = Vec::new();
+    for (offset, len) in blocks {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+        buffer.clear();
+        (&mut file).take(len).read_to_end(&mut buffer).ok()?;
+        hasher.write(&buffer);
+    }
+
+    Some(format!("{:032x}", hasher.finish128().as_u128()))
+}
+
+/// Cache key for a content-addressed (shared) thumbnail entry.
+pub fn generate_content_cache_key(
+    fingerprint: &str,
+    thumb_size: u32,
+    accent: Option<&AccentColor>,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(CACHE_FORMAT_VERSION.to_be_bytes());
+    hasher.update(b"content:");
+    hasher.update(fingerprint.as_bytes());
+    hasher.update(thumb_size.to_be_bytes());
+    if let Some(color) = accent {
+        hasher.update([color.r, color.g, color.b]);
+    }
+    let result = hasher.finalize();
+    hex::encode(result)[..16].to_string()
+}
+
+/// Cache key for SVG markup rendered at `thumb_size`, so identical icons share one
+/// entry no matter where the markup came from.
+pub fn generate_svg_cache_key(svg: &str, font_family: Option<&str>, thumb_size: u32) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"svg:");
+    hasher.update(svg.as_bytes());
+    if let Some(family) = font_family {
+        hasher.update(b"font:");
+        hasher.update(family.as_bytes());
+    }
+    generate_content_cache_key(&hex::encode(hasher.finalize()), thumb_size, None)
+}
+
 pub fn get_thumbnail_format_from_path(path: &Path) -> ThumbnailFormat {
     match path
         .extension()
@@ -493,6 +766,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_content_fingerprint_matches_for_identical_files() {
+        let dir = tempdir().expect("Failed to create temp dir");
+        let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let a = dir.path().join("a.png");
+        let b = dir.path().join("nested_copy.png");
+        fs::write(&a, &content).expect("Failed to write");
+        fs::write(&b, &content).expect("Failed to write");
+
+        let fp_a = content_fingerprint(&a).expect("fingerprint a");
+        let fp_b = content_fingerprint(&b).expect("fingerprint b");
+        assert_eq!(fp_a, fp_b, "Identical content should share a fingerprint");
+        assert_eq!(
+            generate_content_cache_key(&fp_a, 128, None),
+            generate_content_cache_key(&fp_b, 128, None)
+        );
+
+        // Changing the tail must change the fingerprint
+        let mut changed = content.clone();
+        *changed.last_mut().unwrap() ^= 0xff;
+        fs::write(&b, &changed).expect("Failed to write");
+        let fp_changed = content_fingerprint(&b).expect("fingerprint changed");
+        assert_ne!(fp_a, fp_changed, "Different content should differ");
+
+        // So must a change in the middle, past the sampled head and tail
+        let mut changed = content.clone();
+        changed[100_000] ^= 0xff;
+        fs::write(&b, &changed).expect("Failed to write");
+        let fp_middle = content_fingerprint(&b).expect("fingerprint middle");
+        assert_ne!(fp_a, fp_middle, "A change in the middle should differ");
+
+        // Files too big to be worth sharing aren't fingerprinted
+        let big = dir.path().join("big.mov");
+        fs::File::create(&big)
+            .and_then(|file| file.set_len(CONTENT_DEDUP_MAX_BYTES + 1))
+            .expect("Failed to size");
+        assert_eq!(content_fingerprint(&big), None);
+    }
+
+    #[test]
+    fn test_content_dedup_follows_preferences() {
+        configure_from_preferences(r#"{"globalPreferences":{"shareIdenticalThumbnails":false}}"#);
+        assert!(!content_dedup_enabled());
+        configure_from_preferences("{}");
+        assert!(content_dedup_enabled());
+    }
+
     #[test]
     fn test_get_file_identity_nonexistent_file() {
         // Use tempdir to create a portable path that definitely doesn't exist
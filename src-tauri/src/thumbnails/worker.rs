@@ -7,7 +7,7 @@ use tokio::sync::{mpsc, oneshot, Semaphore};
 use tokio::task::JoinHandle;
 
 use super::generators::ThumbnailGenerator;
-use super::{cache::ThumbnailCache, ThumbnailRequest, ThumbnailResponse};
+use super::{cache::ThumbnailCache, ThumbnailFormat, ThumbnailRequest, ThumbnailResponse};
 
 #[derive(Debug)]
 struct PriorityRequest {
@@ -176,6 +176,7 @@ impl ThumbnailWorker {
                         };
 
                         let temp_file = prepared.temp_file.clone();
+                        let remote_permit = prepared.remote_permit;
                         let req_for_pool = prepared.request;
 
                         // Phase 2: CPU-bound generation in blocking thread pool (fast, no network I/O)
@@ -184,6 +185,7 @@ impl ThumbnailWorker {
                                 .install(|| ThumbnailGenerator::generate(&req_for_pool))
                         })
                         .await;
+                        drop(remote_permit);
 
                         // Clean up any temp files from remote downloads
                         if let Some(temp) = temp_file {
@@ -205,6 +207,7 @@ impl ThumbnailWorker {
                                         &request.path,
                                         request.size,
                                         request.accent.as_ref(),
+                                        request.format == ThumbnailFormat::AnimatedWebP,
                                         gen_result.data_url.clone(),
                                         generation_time_ms,
                                         gen_result.has_transparency,
@@ -9,22 +9,24 @@ import StatusBar from './components/StatusBar';
 import { useAppStore } from './store/useAppStore';
 import { useToastStore } from './store/useToastStore';
 import { useUndoStore } from './store/useUndoStore';
+import { useDragStore } from './store/useDragStore';
 import { openFolderSizeWindow } from './store/useFolderSizeStore';
 import { useDirectoryStream } from './hooks/useDirectoryStream';
 import { useDirectoryWatcher } from '@/hooks/useDirectoryWatcher';
 import { ask, message, open as openDialog } from '@tauri-apps/plugin-dialog';
 import { getCurrentWindow } from '@tauri-apps/api/window';
 import { platform } from '@tauri-apps/plugin-os';
-import { getEffectiveExtension, isArchiveFile } from './utils/fileTypes';
+import { getEffectiveExtension, isArchiveFile, RAW_EXTENSIONS } from './utils/fileTypes';
 import { basename, dirname } from './utils/pathUtils';
 import { applyAccentVariables, DEFAULT_ACCENT, normalizeHexColor } from '@/utils/accent';
 import { getSuggestedZipName } from './utils/zipNaming';
-import { revealInFileBrowser } from '@/utils/fileBrowser';
+import { parseStructuredError, revealInFileBrowser } from '@/utils/fileBrowser';
 
 import Toast from './components/Toast';
 import FilterInput from './components/FilterInput';
 import { FULL_DISK_ACCESS_DISMISSED_KEY } from '@/utils/fullDiskAccessPrompt';
 import {
+  NATIVE_DRAG_ENDED_EVENT,
   PREFERENCES_UPDATED_EVENT,
   SMB_CONNECT_SUCCESS_EVENT,
   SFTP_CONNECT_SUCCESS_EVENT,
@@ -32,6 +34,7 @@ import {
 import type {
   DirectoryListingResponse,
   FileItem,
+  NativeDragEnded,
   PersistedPreferences,
   SmbConnectSuccessPayload,
   SftpConnectSuccessPayload,
@@ -43,6 +46,9 @@ const ErrorCodes = {
   ENOENT: 'ENOENT', // Path does not exist
   ENOTDIR: 'ENOTDIR', // Path is not a directory
   EPERM: 'EPERM', // Permission denied / Operation not permitted
+  ENETWORK: 'ENETWORK', // Server unreachable or the connection dropped
+  EUNSUPPORTED: 'EUNSUPPORTED', // The location can't perform this operation
+  ENOSPC: 'ENOSPC', // Destination lacks free space for the operation
 } as const;
 
 /** Parse error code from structured error message format "[CODE] message" */
@@ -67,7 +73,8 @@ function isMacTrashPath(path: string) {
 }
 
 async function requestFolderAccessForPath(path: string): Promise<boolean> {
-  if (platform() !== 'macos') return false;
+  // Folder grants only apply to local paths; remote servers deny access themselves
+  if (platform() !== 'macos' || (hasUriScheme(path) && !path.startsWith('file://'))) return false;
 
   const requestsTrashAccess = isMacTrashPath(path);
   const folderName = requestsTrashAccess ? 'Trash' : basename(path) || path;
@@ -256,6 +263,8 @@ function App() {
         'tif',
         'tga',
         'ico',
+        'icns',
+        ...RAW_EXTENSIONS,
         // Videos
         'mp4',
         'mkv',
@@ -576,6 +585,30 @@ function App() {
     loadPinnedDirectories,
   ]);
 
+  // Reset drag state once a hinted native drag out of the app finishes
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let isActive = true;
+
+    const setup = async () => {
+      const unlistenFn = await listen<NativeDragEnded>(NATIVE_DRAG_ENDED_EVENT, (event) => {
+        useDragStore.getState().handleNativeDragEnded(event.payload);
+      });
+      if (!isActive) {
+        unlistenFn();
+      } else {
+        unlisten = unlistenFn;
+      }
+    };
+
+    setup();
+
+    return () => {
+      isActive = false;
+      unlisten?.();
+    };
+  }, []);
+
   // Listen for pinned directories changes from other windows
   useEffect(() => {
     let unlisten: (() => void) | undefined;
@@ -794,7 +827,7 @@ function App() {
         setError(undefined);
 
         // Show native alert dialog
-        await message(`Cannot access: ${currentPath}\n\n${errorMessage}`, {
+        await message(`Cannot access: ${currentPath}\n\n${parseStructuredError(error).message}`, {
           title: 'Directory Error',
           okLabel: 'OK',
           kind: 'error',
@@ -891,7 +924,7 @@ function App() {
         .getState()
         .updateDirectoryPreferences(useAppStore.getState().currentPath, { viewMode: mode });
     };
-    const setSortBy = (sortBy: 'name' | 'size' | 'modified' | 'type') => {
+    const setSortBy = (sortBy: ViewPreferences['sortBy']) => {
       const defaultOrder: 'asc' | 'desc' =
         sortBy === 'size' || sortBy === 'modified' ? 'desc' : 'asc';
       useAppStore.getState().updateDirectoryPreferences(useAppStore.getState().currentPath, {
@@ -1589,7 +1622,7 @@ function App() {
               }
             }
 
-            await message(`Failed to refresh: ${msg}`, {
+            await message(`Failed to refresh: ${parseStructuredError(err).message}`, {
               title: 'Refresh Error',
               okLabel: 'OK',
               kind: 'error',
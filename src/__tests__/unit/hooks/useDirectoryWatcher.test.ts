@@ -4,7 +4,12 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen, type Event } from '@tauri-apps/api/event';
 import { useDirectoryWatcher } from '@/hooks/useDirectoryWatcher';
 import { useAppStore } from '@/store/useAppStore';
-import type { DirectoryChangeEventPayload, DirectoryListingResponse, FileItem } from '@/types';
+import type {
+  DirectoryChangeEventPayload,
+  DirectoryListingResponse,
+  DirectoryRenameEventPayload,
+  FileItem,
+} from '@/types';
 
 vi.mock('@tauri-apps/api/event', () => ({ listen: vi.fn() }));
 vi.mock('@/hooks/useThumbnail', () => ({ invalidateThumbnailsForPaths: vi.fn() }));
@@ -44,17 +49,21 @@ const listing: DirectoryListingResponse = {
     canCopy: true,
     canMove: true,
     supportsWatching: true,
+    supportsTags: false,
     requiresExplicitRefresh: false,
+    renamesByCopy: false,
   },
 };
 
 describe('useDirectoryWatcher', () => {
   let directoryChanged: ((event: Event<DirectoryChangeEventPayload>) => void) | undefined;
+  let directoryRenamed: ((event: Event<DirectoryRenameEventPayload>) => void) | undefined;
   const unlisten = vi.fn();
 
   beforeEach(() => {
     vi.useFakeTimers();
     directoryChanged = undefined;
+    directoryRenamed = undefined;
     useAppStore.setState({
       currentPath: '/test',
       files: [],
@@ -63,8 +72,12 @@ describe('useDirectoryWatcher', () => {
       isStreamingComplete: true,
     });
 
-    mockListen.mockImplementation(async (_event, handler) => {
-      directoryChanged = handler;
+    mockListen.mockImplementation(async (event, handler) => {
+      if (event === 'directory-renamed') {
+        directoryRenamed = handler as typeof directoryRenamed;
+      } else {
+        directoryChanged = handler as typeof directoryChanged;
+      }
       return unlisten;
     });
     mockInvoke.mockImplementation(async (command) => {
@@ -106,6 +119,38 @@ describe('useDirectoryWatcher', () => {
     expect(useAppStore.getState().files).toEqual([downloadedFile]);
 
     unmount();
-    expect(unlisten).toHaveBeenCalledOnce();
+    expect(unlisten).toHaveBeenCalledTimes(2);
+  });
+
+  it('renames rows in place and keeps them selected', async () => {
+    const renamedFile = { ...downloadedFile, name: 'archive.tar', path: '/test/archive.tar' };
+    useAppStore.setState({ files: [downloadedFile], selectedFiles: [downloadedFile.path] });
+    mockInvoke.mockImplementation(async (command) => {
+      if (command === 'read_directory') return { ...listing, entries: [renamedFile] };
+      return undefined;
+    });
+
+    renderHook(() => useDirectoryWatcher('/test'));
+    await act(async () => Promise.resolve());
+
+    act(() => {
+      directoryRenamed?.({
+        event: 'directory-renamed',
+        id: 1,
+        payload: { path: '/test', renames: [{ from: downloadedFile.path, to: renamedFile.path }] },
+      });
+    });
+
+    const [file] = useAppStore.getState().files;
+    expect(file).toMatchObject({
+      name: 'archive.tar',
+      path: '/test/archive.tar',
+      extension: 'tar',
+    });
+    expect(useAppStore.getState().selectedFiles).toEqual(['/test/archive.tar']);
+
+    await act(async () => vi.advanceTimersByTimeAsync(400));
+    expect(mockInvoke).toHaveBeenCalledWith('read_directory', { path: '/test' });
+    expect(useAppStore.getState().selectedFiles).toEqual(['/test/archive.tar']);
   });
 });
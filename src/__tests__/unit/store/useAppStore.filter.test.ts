@@ -45,7 +45,9 @@ describe('useAppStore filter state', () => {
             canCopy: true,
             canMove: true,
             supportsWatching: true,
+            supportsTags: false,
             requiresExplicitRefresh: false,
+            renamesByCopy: false,
           },
         });
       }
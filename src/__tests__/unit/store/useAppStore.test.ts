@@ -62,7 +62,9 @@ describe('useAppStore', () => {
             canCopy: true,
             canMove: true,
             supportsWatching: true,
+            supportsTags: false,
             requiresExplicitRefresh: false,
+            renamesByCopy: false,
           },
         });
       }
@@ -89,7 +91,9 @@ describe('useAppStore', () => {
             canCopy: true,
             canMove: true,
             supportsWatching: true,
+            supportsTags: false,
             requiresExplicitRefresh: false,
+            renamesByCopy: false,
           },
         });
       }
@@ -295,6 +299,31 @@ describe('useAppStore', () => {
     });
   });
 
+  describe('updateFileChildCount', () => {
+    it('keeps a fetched folder count until the folder changes', () => {
+      const folder = {
+        name: 'photos',
+        path: '/test/photos',
+        size: 0,
+        modified: '2024-01-01T00:00:00.000Z',
+        is_directory: true,
+        is_hidden: false,
+        is_symlink: false,
+        is_git_repo: false,
+      };
+
+      useAppStore.getState().setFiles([folder]);
+      useAppStore.getState().updateFileChildCount(folder.path, 12);
+      expect(useAppStore.getState().files[0].child_count).toBe(12);
+
+      useAppStore.getState().setFiles([folder]);
+      expect(useAppStore.getState().files[0].child_count).toBe(12);
+
+      useAppStore.getState().setFiles([{ ...folder, modified: '2024-01-02T00:00:00.000Z' }]);
+      expect(useAppStore.getState().files[0].child_count).toBeUndefined();
+    });
+  });
+
   describe('refreshCurrentDirectory', () => {
     it('should preserve existing image dimensions on refresh when backend does not return them', async () => {
       const smbPath = 'smb://server/share/folder';
@@ -353,7 +382,9 @@ describe('useAppStore', () => {
               canCopy: true,
               canMove: true,
               supportsWatching: false,
+              supportsTags: false,
               requiresExplicitRefresh: false,
+              renamesByCopy: false,
             },
           });
         }
@@ -561,6 +592,26 @@ describe('useAppStore', () => {
       });
     });
 
+    describe('updatePinnedDirectory', () => {
+      it('should keep the other style field when changing one', async () => {
+        useAppStore.setState({
+          pinnedDirectories: [{ ...mockPinnedDir, color: '#3b82f6', icon: '🎵' }],
+        });
+        const updated = { ...mockPinnedDir, color: '#3b82f6', icon: '📁' };
+        mockInvoke.mockResolvedValueOnce(updated);
+
+        await useAppStore.getState().updatePinnedDirectory('/test/directory', { icon: '📁' });
+
+        expect(mockInvoke).toHaveBeenCalledWith('update_pinned_directory', {
+          path: '/test/directory',
+          name: undefined,
+          color: '#3b82f6',
+          icon: '📁',
+        });
+        expect(useAppStore.getState().pinnedDirectories).toEqual([updated]);
+      });
+    });
+
     describe('removePinnedDirectory', () => {
       it('should remove a pinned directory', async () => {
         useAppStore.setState({
@@ -997,7 +1048,9 @@ describe('useAppStore', () => {
                 canCopy: true,
                 canMove: true,
                 supportsWatching: true,
+                supportsTags: false,
                 requiresExplicitRefresh: false,
+                renamesByCopy: false,
               },
             });
           }
@@ -1048,7 +1101,9 @@ describe('useAppStore', () => {
                 canCopy: true,
                 canMove: true,
                 supportsWatching: true,
+                supportsTags: false,
                 requiresExplicitRefresh: false,
+                renamesByCopy: false,
               },
             });
           }
@@ -0,0 +1,63 @@
+import { describe, it, expect, beforeEach } from 'vitest';
+import { useArchiveProgressStore } from '../../../store/useArchiveProgressStore';
+
+describe('useArchiveProgressStore', () => {
+  beforeEach(() => {
+    useArchiveProgressStore.getState().reset();
+    useArchiveProgressStore.getState().setContext({
+      fileName: 'project.zip',
+      destinationDir: '/tmp',
+      format: 'zip',
+    });
+  });
+
+  it('should track extracted bytes against the archive total', () => {
+    const { pushUpdate } = useArchiveProgressStore.getState();
+    pushUpdate({
+      archiveName: 'project.zip',
+      entryName: 'a.txt',
+      extractedBytes: 100,
+      totalBytes: 400,
+    });
+    pushUpdate({
+      archiveName: 'project.zip',
+      entryName: 'b.txt',
+      extractedBytes: 300,
+      totalBytes: 400,
+    });
+
+    const state = useArchiveProgressStore.getState();
+    expect(state.entries).toEqual(['a.txt', 'b.txt']);
+    expect(state.extractedBytes).toBe(300);
+    expect(state.totalBytes).toBe(400);
+  });
+
+  it('should leave the total unknown when the backend cannot report one', () => {
+    useArchiveProgressStore.getState().pushUpdate({
+      archiveName: 'project.tar.gz',
+      entryName: 'a.txt',
+      extractedBytes: 100,
+      totalBytes: null,
+    });
+
+    const state = useArchiveProgressStore.getState();
+    expect(state.extractedBytes).toBe(100);
+    expect(state.totalBytes).toBeNull();
+  });
+
+  it('should start over when a new extraction begins', () => {
+    useArchiveProgressStore.getState().pushUpdate({
+      archiveName: 'project.zip',
+      extractedBytes: 50,
+      totalBytes: 100,
+    });
+    useArchiveProgressStore.getState().setContext({
+      fileName: 'other.zip',
+      destinationDir: '/tmp',
+    });
+
+    const state = useArchiveProgressStore.getState();
+    expect(state.extractedBytes).toBe(0);
+    expect(state.totalBytes).toBeNull();
+  });
+});
@@ -7,8 +7,9 @@ import { openFolderSizeWindow } from '@/store/useFolderSizeStore';
 import { getSuggestedZipName } from '@/utils/zipNaming';
 import { getArchiveBaseName, isArchiveFile } from '@/utils/fileTypes';
 import { getShowInLabel, isLocalPath, revealInFileBrowser } from '@/utils/fileBrowser';
+import type { FileTemplate } from '@/types';
 
-type SortBy = 'name' | 'size' | 'type' | 'modified';
+type SortBy = 'name' | 'size' | 'type' | 'typeName' | 'modified';
 type SortOrder = 'asc' | 'desc';
 
 interface ContextMenuProps {
@@ -33,6 +34,7 @@ export default function ContextMenu({ x, y, isFileContext, onRequestClose }: Con
     updateDirectoryPreferences,
     toggleHiddenFiles,
     toggleFoldersFirst,
+    updateGlobalPreferences,
     beginRenameSelected,
     navigateTo,
     setPendingRevealTarget,
@@ -64,6 +66,20 @@ export default function ContextMenu({ x, y, isFileContext, onRequestClose }: Con
     setPos({ x: Math.max(8, Math.min(x, maxX)), y: Math.max(8, Math.min(y, maxY)) });
   }, [x, y]);
 
+  const [fileTemplates, setFileTemplates] = useState<FileTemplate[]>([]);
+  useEffect(() => {
+    if (isFileContext) return;
+    let cancelled = false;
+    invoke<FileTemplate[]>('get_file_templates')
+      .then((templates) => {
+        if (!cancelled) setFileTemplates(templates);
+      })
+      .catch((error) => console.warn('Failed to load file templates:', error));
+    return () => {
+      cancelled = true;
+    };
+  }, [isFileContext]);
+
   // Sync clipboard state when menu opens
   useEffect(() => {
     void syncClipboardState();
@@ -152,6 +168,12 @@ export default function ContextMenu({ x, y, isFileContext, onRequestClose }: Con
     selectedFileItems.length === 1 && selectedFileItems[0]?.is_symlink
       ? selectedFileItems[0]
       : undefined;
+  const allLocalSelection =
+    selectedFileItems.length > 0 && selectedFileItems.every((item) => isLocalPath(item.path));
+  const singleLocalItem =
+    selectedFileItems.length === 1 && isLocalPath(selectedFileItems[0].path)
+      ? selectedFileItems[0]
+      : undefined;
 
   const menu = (
     <div
@@ -267,6 +289,31 @@ export default function ContextMenu({ x, y, isFileContext, onRequestClose }: Con
                 Reveal Original Location
               </button>
             )}
+            {singleLocalItem && (
+              <button
+                className="w-full text-left px-3 py-2 hover:bg-app-light"
+                onClick={async () => {
+                  onRequestClose();
+                  const hidden = !singleLocalItem.is_hidden;
+                  try {
+                    const newPath = await invoke<string>('set_hidden', {
+                      path: singleLocalItem.path,
+                      hidden,
+                    });
+                    await state.refreshCurrentDirectory();
+                    useAppStore.getState().setSelectedFiles([newPath]);
+                  } catch (error) {
+                    console.warn('Failed to change hidden state:', error);
+                    useToastStore.getState().addToast({
+                      type: 'error',
+                      message: `Unable to ${hidden ? 'hide' : 'show'} "${singleLocalItem.name}".`,
+                    });
+                  }
+                }}
+              >
+                {singleLocalItem.is_hidden ? 'Show This Item' : 'Hide This Item'}
+              </button>
+            )}
             <div className="my-1 h-px bg-app-border" />
             <button
               className={`w-full text-left px-3 py-2 hover:bg-app-light ${!hasFileSelection ? 'text-app-muted' : ''}`}
@@ -298,6 +345,17 @@ export default function ContextMenu({ x, y, isFileContext, onRequestClose }: Con
             >
               Compress to &quot;{suggestedZipName}&quot;
             </button>
+            {allLocalSelection && (
+              <button
+                className="w-full text-left px-3 py-2 hover:bg-app-light"
+                onClick={() => {
+                  onRequestClose();
+                  void compressSelectedToZip(suggestedZipName, { replaceOriginals: true });
+                }}
+              >
+                Compress and Replace Originals
+              </button>
+            )}
             {singleArchive && (
               <>
                 <button
@@ -309,6 +367,15 @@ export default function ContextMenu({ x, y, isFileContext, onRequestClose }: Con
                 >
                   {extractLabel}
                 </button>
+                <button
+                  className="w-full text-left px-3 py-2 hover:bg-app-light"
+                  onClick={() => {
+                    onRequestClose();
+                    void extractArchive(singleArchive, { extractHere: true });
+                  }}
+                >
+                  Extract Here
+                </button>
               </>
             )}
           </>
@@ -324,6 +391,18 @@ export default function ContextMenu({ x, y, isFileContext, onRequestClose }: Con
             >
               New File
             </button>
+            {fileTemplates.map((template) => (
+              <button
+                key={`${template.name}.${template.extension}`}
+                className="w-full text-left px-3 py-2 hover:bg-app-light"
+                onClick={() => {
+                  onRequestClose();
+                  void createNewFile(template);
+                }}
+              >
+                New {template.name}
+              </button>
+            ))}
             <button
               className="w-full text-left px-3 py-2 hover:bg-app-light"
               onClick={() => {
@@ -381,6 +460,12 @@ export default function ContextMenu({ x, y, isFileContext, onRequestClose }: Con
         >
           Type
         </button>
+        <button
+          className={`w-full text-left px-3 py-2 hover:bg-app-light ${prefs.sortBy === 'typeName' ? 'text-accent' : ''}`}
+          onClick={() => onSortBy('typeName')}
+        >
+          Type, then Name
+        </button>
         <button
           className={`w-full text-left px-3 py-2 hover:bg-app-light ${prefs.sortBy === 'modified' ? 'text-accent' : ''}`}
           onClick={() => onSortBy('modified')}
@@ -414,6 +499,15 @@ export default function ContextMenu({ x, y, isFileContext, onRequestClose }: Con
         >
           Folders on Top
         </button>
+        <button
+          className={`w-full text-left px-3 py-2 hover:bg-app-light ${prefs.folderPreviews ? 'text-accent' : ''}`}
+          onClick={() => {
+            updateGlobalPreferences({ folderPreviews: !prefs.folderPreviews });
+            onRequestClose();
+          }}
+        >
+          Folder Previews
+        </button>
       </div>
     </div>
   );
@@ -16,16 +16,25 @@ import AppIcon from '@/components/AppIcon';
 import { invoke } from '@tauri-apps/api/core';
 import { createDragImageForSelection, createDragImageForSelectionAsync } from '@/utils/dragImage';
 // no direct invoke here; background opens the menu
-import { useThumbnail } from '@/hooks/useThumbnail';
+import { getCachedThumbnailDataUrl, useThumbnail } from '@/hooks/useThumbnail';
 import { useFileIcon } from '@/hooks/useFileIcon';
 import { usePlatform } from '@/hooks/usePlatform';
 import { useVisibility } from '@/hooks/useVisibility';
+import { useChildCount } from '@/hooks/useChildCount';
 import { useFileAnimations } from '@/hooks/useFileAnimations';
+import { nativeDragOperationFor } from '@/hooks/useDragDetector';
 import FileNameDisplay from './FileNameDisplay';
 import SymlinkBadge from '@/components/SymlinkBadge';
 import GitRepoBadge from '@/components/GitRepoBadge';
 import { normalizePreviewIcon } from '@/utils/iconSizing';
-import { isArchiveFile, isVideoExtension, isMacOSBundle } from '@/utils/fileTypes';
+import {
+  isArchiveFile,
+  isAudioExtension,
+  isDocumentExtension,
+  isVideoExtension,
+  isMacOSBundle,
+  isRawExtension,
+} from '@/utils/fileTypes';
 import { buildArchiveUri } from '@/utils/archiveUri';
 import { isGoogleDrivePath, parseGoogleDrivePathEmail } from '@/utils/googleDriveUrl';
 import { isSmbPath } from '@/utils/smbPath';
@@ -45,6 +54,7 @@ function GridFilePreview({
   tile,
   isSymlink,
   isGitRepo,
+  folderPreviews,
 }: {
   file: FileItem;
   isMac: boolean;
@@ -52,24 +62,46 @@ function GridFilePreview({
   tile: number;
   isSymlink: boolean;
   isGitRepo: boolean;
+  folderPreviews: boolean;
 }) {
   const { ref: previewRef, stage } = useVisibility<HTMLDivElement>({
     nearMargin: '900px',
     visibleMargin: '0px',
   });
+  useChildCount(file, stage === 'visible');
   const ext = file.extension?.toLowerCase();
   const isImage =
     !!ext &&
-    ['jpg', 'jpeg', 'png', 'gif', 'webp', 'bmp', 'tiff', 'tga', 'ico', 'svg'].includes(ext || '');
+    [
+      'jpg',
+      'jpeg',
+      'png',
+      'gif',
+      'webp',
+      'avif',
+      'jxl',
+      'bmp',
+      'tiff',
+      'tga',
+      'ico',
+      'icns',
+      'svg',
+    ].includes(ext || '');
   const isPdf = ext === 'pdf';
   const isAi = ext === 'ai' || ext === 'eps';
   const isPsd = ext === 'psd' || ext === 'psb';
+  const isRaw = isRawExtension(ext);
+  const isDocument = isDocumentExtension(ext);
+  const isAudio = isAudioExtension(ext);
 
   const isStl = ext === 'stl';
   const isVideo = isVideoExtension(ext);
-  const isFont = !!ext && ['ttf', 'otf'].includes(ext);
+  const isFont = !!ext && ['ttf', 'otf', 'woff', 'woff2'].includes(ext);
   const isZpl = ext === 'zpl';
   const isAppBundle = isMac && file.is_directory && file.name.toLowerCase().endsWith('.app');
+  // Only local folders; remote ones would need every image downloaded
+  const isFolderPreview =
+    folderPreviews && file.is_directory && !isMacOSBundle(file) && !file.path.includes('://');
 
   const badgeSize: 'sm' | 'md' | 'lg' = tile >= 200 ? 'lg' : tile >= 120 ? 'md' : 'sm';
   const badgeOffset =
@@ -107,7 +139,18 @@ function GridFilePreview({
   };
 
   const shouldLoadThumbnail =
-    isImage || isPdf || isAi || isPsd || isStl || isVideo || isFont || isZpl;
+    isImage ||
+    isPdf ||
+    isAi ||
+    isPsd ||
+    isRaw ||
+    isStl ||
+    isVideo ||
+    isFont ||
+    isZpl ||
+    isDocument ||
+    isAudio ||
+    isFolderPreview;
   const requestSize = pickBucket(Math.round((box - pad * 2) * dpr));
   const thumbnailPriority = stage === 'visible' ? 'high' : 'medium';
 
@@ -122,8 +165,8 @@ function GridFilePreview({
     }
   );
 
-  // Image-like previews (real thumbnails)
-  if (shouldLoadThumbnail) {
+  // Image-like previews (real thumbnails); folders keep their icon until a collage arrives
+  if (shouldLoadThumbnail && (!isFolderPreview || dataUrl)) {
     if (dataUrl) {
       return (
         <div
@@ -415,9 +458,10 @@ export default function FileGrid({ files, preferences }: FileGridProps) {
       const dragThreshold = 5; // pixels
       let dragStarted = false;
 
-      const startDrag = () => {
+      const startDrag = (moveEvent: MouseEvent) => {
         if (dragStarted) return;
         dragStarted = true;
+        const dragOperation = nativeDragOperationFor(moveEvent);
 
         // If dragging a directory, track it for potential pinning
         if (file.is_directory) {
@@ -540,13 +584,26 @@ export default function FileGrid({ files, preferences }: FileGridProps) {
               return;
             }
 
-            setNativeDragPaths(dragPaths);
+            setNativeDragPaths(dragPaths, dragOperation);
+
+            // Multi-item drags get a native stacked image built from the thumbnails we
+            // already have; items without one fall back to their file icon.
+            const previewImages =
+              dragPaths.length > 1
+                ? await Promise.all(
+                    selected
+                      .slice(0, 3)
+                      .map(async (f) => (await getCachedThumbnailDataUrl(f.path)) ?? '')
+                  )
+                : undefined;
 
             // Use new unified native drag API
             await invoke('start_native_drag', {
               paths: dragPaths,
               previewImage: dragImageDataUrl,
+              previewImages,
               dragOffsetY: 0,
+              dragOperation,
             });
           } catch (error) {
             console.warn('Native drag failed:', error);
@@ -568,7 +625,7 @@ export default function FileGrid({ files, preferences }: FileGridProps) {
         const distance = Math.sqrt(deltaX * deltaX + deltaY * deltaY);
 
         if (distance >= dragThreshold) {
-          startDrag();
+          startDrag(moveEvent);
         }
       };
 
@@ -969,6 +1026,7 @@ export default function FileGrid({ files, preferences }: FileGridProps) {
             tile={tile}
             isSymlink={file.is_symlink}
             isGitRepo={file.is_git_repo}
+            folderPreviews={preferences.folderPreviews ?? false}
           />
         </div>
 
@@ -17,17 +17,26 @@ import AppIcon from '@/components/AppIcon';
 import { createDragImageForSelection, createDragImageForSelectionAsync } from '@/utils/dragImage';
 import { invoke } from '@tauri-apps/api/core';
 // no direct invoke here; background opens the menu
-import { useThumbnail } from '@/hooks/useThumbnail';
+import { getCachedThumbnailDataUrl, useThumbnail } from '@/hooks/useThumbnail';
 import { formatBytes } from '@/utils/formatBytes';
 import { useFileIcon } from '@/hooks/useFileIcon';
 import { usePlatform } from '@/hooks/usePlatform';
 import { useVisibility } from '@/hooks/useVisibility';
+import { useChildCount } from '@/hooks/useChildCount';
 import { useFileAnimations } from '@/hooks/useFileAnimations';
+import { nativeDragOperationFor } from '@/hooks/useDragDetector';
 import FileNameDisplay from './FileNameDisplay';
 import SymlinkBadge from '@/components/SymlinkBadge';
 import GitRepoBadge from '@/components/GitRepoBadge';
 import { normalizePreviewIcon } from '@/utils/iconSizing';
-import { isArchiveFile, isVideoExtension, isMacOSBundle } from '@/utils/fileTypes';
+import {
+  isArchiveFile,
+  isAudioExtension,
+  isDocumentExtension,
+  isVideoExtension,
+  isMacOSBundle,
+  isRawExtension,
+} from '@/utils/fileTypes';
 import { buildArchiveUri } from '@/utils/archiveUri';
 import { isGoogleDrivePath, parseGoogleDrivePathEmail } from '@/utils/googleDriveUrl';
 import { isSmbPath } from '@/utils/smbPath';
@@ -53,21 +62,49 @@ function ListFilePreview({
     nearMargin: '800px',
     visibleMargin: '0px',
   });
+  useChildCount(file, stage === 'visible');
   const ext = file.extension?.toLowerCase();
   const isImage =
     !!ext &&
-    ['jpg', 'jpeg', 'png', 'gif', 'webp', 'bmp', 'tiff', 'tga', 'ico', 'svg'].includes(ext || '');
+    [
+      'jpg',
+      'jpeg',
+      'png',
+      'gif',
+      'webp',
+      'avif',
+      'jxl',
+      'bmp',
+      'tiff',
+      'tga',
+      'ico',
+      'icns',
+      'svg',
+    ].includes(ext || '');
   const isPdf = ext === 'pdf';
   const isAi = ext === 'ai' || ext === 'eps';
   const isPsd = ext === 'psd' || ext === 'psb';
+  const isRaw = isRawExtension(ext);
+  const isDocument = isDocumentExtension(ext);
+  const isAudio = isAudioExtension(ext);
   const isSvg = ext === 'svg';
   const isStl = ext === 'stl';
   const isVideo = isVideoExtension(ext);
-  const isFont = !!ext && ['ttf', 'otf'].includes(ext);
+  const isFont = !!ext && ['ttf', 'otf', 'woff', 'woff2'].includes(ext);
   const isZpl = ext === 'zpl';
 
   const isThumbnailCandidate =
-    isImage || isPdf || isAi || isPsd || isStl || isVideo || isFont || isZpl;
+    isImage ||
+    isPdf ||
+    isAi ||
+    isPsd ||
+    isRaw ||
+    isStl ||
+    isVideo ||
+    isFont ||
+    isZpl ||
+    isDocument ||
+    isAudio;
   const dpr =
     typeof window !== 'undefined' ? Math.min(2, Math.max(1, window.devicePixelRatio || 1)) : 1;
   const priority = stage === 'visible' ? 'high' : 'medium';
@@ -397,9 +434,10 @@ export default function FileList({ files, preferences }: FileListProps) {
       const dragThreshold = 5; // pixels
       let dragStarted = false;
 
-      const startDrag = () => {
+      const startDrag = (moveEvent: MouseEvent) => {
         if (dragStarted) return;
         dragStarted = true;
+        const dragOperation = nativeDragOperationFor(moveEvent);
 
         // If dragging a directory, track it for potential pinning
         if (file.is_directory) {
@@ -522,13 +560,26 @@ export default function FileList({ files, preferences }: FileListProps) {
               return;
             }
 
-            setNativeDragPaths(dragPaths);
+            setNativeDragPaths(dragPaths, dragOperation);
+
+            // Multi-item drags get a native stacked image built from the thumbnails we
+            // already have; items without one fall back to their file icon.
+            const previewImages =
+              dragPaths.length > 1
+                ? await Promise.all(
+                    selected
+                      .slice(0, 3)
+                      .map(async (f) => (await getCachedThumbnailDataUrl(f.path)) ?? '')
+                  )
+                : undefined;
 
             // Use new unified native drag API
             await invoke('start_native_drag', {
               paths: dragPaths,
               previewImage: dragImageDataUrl,
+              previewImages,
               dragOffsetY: 0,
+              dragOperation,
             });
           } catch (error) {
             console.warn('Native drag failed:', error);
@@ -550,7 +601,7 @@ export default function FileList({ files, preferences }: FileListProps) {
         const distance = Math.sqrt(deltaX * deltaX + deltaY * deltaY);
 
         if (distance >= dragThreshold) {
-          startDrag();
+          startDrag(moveEvent);
         }
       };
 
@@ -22,6 +22,7 @@ import {
 } from 'phosphor-react';
 import { invoke } from '@tauri-apps/api/core';
 import type { DirectoryPreferencesMap, DirectoryListingResponse, FileItem } from '@/types';
+import { nativeDragOperationFor } from '@/hooks/useDragDetector';
 import { useAppStore } from '@/store/useAppStore';
 import { useToastStore } from '@/store/useToastStore';
 import { getCurrentWindow } from '@tauri-apps/api/window';
@@ -150,7 +151,9 @@ const fetchSuggestionsForInfo = async ({
   });
 
   return filtered
-    .sort((a, b) => a.name.localeCompare(b.name, undefined, { sensitivity: 'base' }))
+    .sort((a, b) =>
+      a.name.localeCompare(b.name, undefined, { numeric: true, sensitivity: 'base' })
+    )
     .slice(0, MAX_SUGGESTIONS)
     .map<PathSuggestion>((entry) => ({
       value: `${prefix}${entry.name}${sep}`,
@@ -581,11 +584,13 @@ export default function PathBar() {
           }
 
           cleanup();
-          setNativeDragPaths([currentPath]);
+          const dragOperation = nativeDragOperationFor(moveEvent);
+          setNativeDragPaths([currentPath], dragOperation);
           void invoke('start_native_drag', {
             paths: [currentPath],
             previewImage: null,
             dragOffsetY: 0,
+            dragOperation,
           })
             .catch((error) => {
               console.warn('Native drag failed for current directory:', error);
@@ -19,6 +19,7 @@ import {
   SignOut,
   ShareNetwork,
   Terminal,
+  WarningCircle,
 } from 'phosphor-react';
 import GitRepoBadge from './GitRepoBadge';
 import SymlinkBadge from './SymlinkBadge';
@@ -26,6 +27,7 @@ import type { IconProps } from 'phosphor-react';
 import { useAppStore } from '../store/useAppStore';
 import { useCallback, useEffect, useState, MouseEvent, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import {
   SystemDrive,
   PinnedDirectory,
@@ -39,6 +41,8 @@ import { useDragStore } from '../store/useDragStore';
 import { useSidebarDropZone } from '../hooks/useDragDetector';
 import { usePlatform } from '@/hooks/usePlatform';
 import QuickTooltip from './QuickTooltip';
+import { formatBytes } from '@/utils/formatBytes';
+import { parseStructuredError } from '@/utils/fileBrowser';
 
 type SidebarLink = {
   name: string;
@@ -49,6 +53,12 @@ type SidebarLink = {
 
 const SYSTEM_DRIVE_REFRESH_INTERVAL_MS = 5000;
 
+// Pin icons are a symbol name or an emoji; only emoji can be drawn as-is
+const pinEmoji = (pin: PinnedDirectory) =>
+  pin.icon && !/^[\w-]+$/.test(pin.icon) ? (
+    <span className="text-base leading-none">{pin.icon}</span>
+  ) : null;
+
 const areSystemDrivesEqual = (a: SystemDrive[], b: SystemDrive[]) =>
   a.length === b.length &&
   a.every(
@@ -56,9 +66,16 @@ const areSystemDrivesEqual = (a: SystemDrive[], b: SystemDrive[]) =>
       drive.name === b[index]?.name &&
       drive.path === b[index]?.path &&
       drive.drive_type === b[index]?.drive_type &&
-      drive.is_ejectable === b[index]?.is_ejectable
+      drive.is_ejectable === b[index]?.is_ejectable &&
+      drive.total_bytes === b[index]?.total_bytes &&
+      drive.available_bytes === b[index]?.available_bytes
   );
 
+const driveTitle = (drive: SystemDrive) =>
+  drive.total_bytes != null && drive.available_bytes != null
+    ? `${drive.path}\n${formatBytes(drive.available_bytes)} free of ${formatBytes(drive.total_bytes)}`
+    : drive.path;
+
 export default function Sidebar() {
   const {
     currentPath,
@@ -286,6 +303,60 @@ export default function Sidebar() {
     };
   }, [fetchSystemDrives]);
 
+  // The backend pushes the refreshed list when volumes mount/unmount.
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let isActive = true;
+
+    const setup = async () => {
+      const unlistenFn = await listen<SystemDrive[]>('drives-changed', (event) => {
+        const drives = event.payload;
+        setSystemDrives((current) => (areSystemDrivesEqual(current, drives) ? current : drives));
+      });
+      if (!isActive) {
+        unlistenFn();
+      } else {
+        unlisten = unlistenFn;
+      }
+    };
+
+    void setup();
+
+    return () => {
+      isActive = false;
+      unlisten?.();
+    };
+  }, []);
+
+  // Revoked Google credentials: refresh the account list so the entry shows a reconnect prompt.
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let isActive = true;
+
+    const setup = async () => {
+      const unlistenFn = await listen<{ email: string }>('account-reauth-required', (event) => {
+        void loadGoogleAccounts();
+        useToastStore.getState().addToast({
+          type: 'error',
+          message: `Google account ${event.payload.email} needs to be signed in again`,
+          duration: 5000,
+        });
+      });
+      if (!isActive) {
+        unlistenFn();
+      } else {
+        unlisten = unlistenFn;
+      }
+    };
+
+    void setup();
+
+    return () => {
+      isActive = false;
+      unlisten?.();
+    };
+  }, [loadGoogleAccounts]);
+
   const handleEjectDrive = async (drive: SystemDrive, event: React.MouseEvent) => {
     event.stopPropagation(); // Prevent navigation when clicking eject
 
@@ -297,7 +368,12 @@ export default function Sidebar() {
       await fetchSystemDrives();
     } catch (error) {
       console.error('Failed to eject drive:', error);
-      // TODO: Show error notification to user
+      // An in-use volume comes back as EBUSY with the reason in the message
+      useToastStore.getState().addToast({
+        type: 'error',
+        message: parseStructuredError(error).message,
+        duration: 6000,
+      });
     } finally {
       setEjectingDrives((prev) => {
         const newSet = new Set(prev);
@@ -440,7 +516,10 @@ export default function Sidebar() {
           label: 'Undo',
           onClick: async () => {
             try {
-              await addPinnedDirectory(pin.path);
+              await addPinnedDirectory(pin.path, pin.name, {
+                color: pin.color,
+                icon: pin.icon,
+              });
               addToast({
                 message: `Restored "${pin.name}" to pinned folders`,
                 type: 'success',
@@ -618,8 +697,11 @@ export default function Sidebar() {
                           data-tauri-drag-region={false}
                         >
                           <span className="relative flex-shrink-0 w-5 h-5">
-                            <div className="w-full h-full flex items-center justify-center">
-                              {createIcon(Folder, 'fill', isActive)}
+                            <div
+                              className="w-full h-full flex items-center justify-center"
+                              style={pin.color ? { color: pin.color } : undefined}
+                            >
+                              {pinEmoji(pin) ?? createIcon(Folder, 'fill', isActive)}
                             </div>
                             {pin.is_git_repo && (
                               <GitRepoBadge size="sm" style={{ bottom: -2, right: -2 }} />
@@ -669,17 +751,29 @@ export default function Sidebar() {
                   }`}
                 >
                   <button
-                    onClick={() => navigateTo(gdrivePath)}
+                    onClick={() =>
+                      account.needsReauth ? void handleAddGoogleAccount() : navigateTo(gdrivePath)
+                    }
                     className="flex items-center gap-2 flex-1 min-w-0"
+                    title={account.needsReauth ? 'Sign in again to reconnect' : undefined}
                     data-tauri-drag-region={false}
                   >
                     <GoogleLogo
-                      className={`w-5 h-5 flex-shrink-0 ${isActive ? 'text-accent' : ''}`}
+                      className={`w-5 h-5 flex-shrink-0 ${isActive ? 'text-accent' : ''} ${
+                        account.needsReauth ? 'opacity-50' : ''
+                      }`}
                       weight="fill"
                     />
-                    <span className={`truncate ${isActive ? 'text-accent' : ''}`}>
+                    <span
+                      className={`truncate ${isActive ? 'text-accent' : ''} ${
+                        account.needsReauth ? 'text-app-muted' : ''
+                      }`}
+                    >
                       {account.displayName || account.email}
                     </span>
+                    {account.needsReauth && (
+                      <WarningCircle className="w-3.5 h-3.5 flex-shrink-0 text-amber-400" />
+                    )}
                   </button>
 
                   <button
@@ -724,11 +818,14 @@ export default function Sidebar() {
           const smbPath = `smb://${server.hostname}/`;
           const isActive = currentPath.startsWith(`smb://${server.hostname}`);
           const isDisconnecting = disconnectingSmbServers.has(server.hostname);
-          const displayName = server.username
-            ? `${server.hostname} (${server.username})`
-            : server.hostname;
+          const displayName = server.guest
+            ? `${server.hostname} (Guest)`
+            : `${server.hostname} (${server.username})`;
+          const tooltip = server.guest
+            ? `Guest@${server.hostname}`
+            : `${server.username}@${server.hostname}`;
           return (
-            <QuickTooltip key={server.hostname} text={`${server.username}@${server.hostname}`}>
+            <QuickTooltip key={server.hostname} text={tooltip}>
               {({ onBlur, onFocus, onMouseEnter, onMouseLeave, ref }) => (
                 <div
                   ref={ref}
@@ -894,7 +991,7 @@ export default function Sidebar() {
                   <button
                     onClick={() => navigateTo(drive.path)}
                     className="flex items-center gap-2 flex-1 min-w-0"
-                    title={drive.path}
+                    title={driveTitle(drive)}
                     data-tauri-drag-region={false}
                   >
                     {createIcon(HardDrives, 'regular', isActive)}
@@ -220,8 +220,11 @@ export default function StatusBar() {
     })();
 
     const indicatorItems: ReactNode[] = [];
+    const statusUnknown = Boolean(gitStatus.statusUnavailable);
     const hasDirtyChanges = gitStatus.dirty || gitStatus.hasUntracked;
-    const dirtyTitle = hasDirtyChanges
+    const dirtyTitle = statusUnknown
+      ? (gitStatus.statusUnavailable ?? '')
+      : hasDirtyChanges
       ? [
           gitStatus.dirty ? 'Working tree has staged or unstaged changes' : null,
           gitStatus.hasUntracked ? 'Untracked files present' : null,
@@ -234,12 +237,16 @@ export default function StatusBar() {
       <span
         key="dirty"
         className={`flex items-center gap-1 text-xs ${
-          hasDirtyChanges ? 'text-amber-400' : 'text-emerald-400'
+          statusUnknown
+            ? 'text-app-muted'
+            : hasDirtyChanges
+              ? 'text-amber-400'
+              : 'text-emerald-400'
         }`}
         title={dirtyTitle}
       >
         <span aria-hidden className="inline-flex h-2 w-2 rounded-full bg-current" />
-        <span>{hasDirtyChanges ? 'Dirty' : 'Clean'}</span>
+        <span>{statusUnknown ? 'Status unavailable' : hasDirtyChanges ? 'Dirty' : 'Clean'}</span>
       </span>
     );
 
@@ -269,6 +276,19 @@ export default function StatusBar() {
       );
     }
 
+    if (gitStatus.stashCount > 0) {
+      indicatorItems.push(
+        <span
+          key="stash"
+          className="flex items-center gap-1 text-xs text-sky-400"
+          title={`${gitStatus.stashCount} stash${gitStatus.stashCount === 1 ? '' : 'es'} saved`}
+        >
+          <span aria-hidden>≡</span>
+          <span>{gitStatus.stashCount}</span>
+        </span>
+      );
+    }
+
     const branchTitle = `Repository: ${gitStatus.repositoryRoot}`;
 
     const targetUrl = gitStatus.remoteBranchUrl ?? gitStatus.remoteUrl;
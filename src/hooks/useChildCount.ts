@@ -0,0 +1,47 @@
+import { useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useAppStore } from '@/store/useAppStore';
+import type { FileItem } from '@/types';
+
+// Each count is a readdir, which is a network round trip on SMB/SFTP; keep a few in flight
+const MAX_CONCURRENT_COUNTS = 4;
+const pendingPaths = new Set<string>();
+const countQueue: Array<() => Promise<void>> = [];
+let activeCounts = 0;
+
+const pumpCountQueue = () => {
+  while (activeCounts < MAX_CONCURRENT_COUNTS && countQueue.length > 0) {
+    const run = countQueue.shift()!;
+    activeCounts++;
+    void run().finally(() => {
+      activeCounts--;
+      pumpCountQueue();
+    });
+  }
+};
+
+/**
+ * Fill in a folder's item count once it is on screen. Directory listings leave
+ * `child_count` empty so opening a folder full of subfolders stays fast.
+ */
+export function useChildCount(file: FileItem, visible: boolean) {
+  const updateFileChildCount = useAppStore((state) => state.updateFileChildCount);
+  const needsCount = visible && file.is_directory && file.child_count == null;
+  const path = file.path;
+
+  useEffect(() => {
+    if (!needsCount || pendingPaths.has(path)) return;
+    pendingPaths.add(path);
+    countQueue.push(async () => {
+      try {
+        const count = await invoke<number>('get_child_count', { path });
+        updateFileChildCount(path, count);
+      } catch {
+        // Unreadable folders just show no count
+      } finally {
+        pendingPaths.delete(path);
+      }
+    });
+    pumpCountQueue();
+  }, [needsCount, path, updateFileChildCount]);
+}
@@ -3,7 +3,11 @@ import { invoke } from '@tauri-apps/api/core';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { useAppStore } from '@/store/useAppStore';
 import { invalidateThumbnailsForPaths } from '@/hooks/useThumbnail';
-import type { DirectoryChangeEventPayload, DirectoryListingResponse } from '@/types';
+import type {
+  DirectoryChangeEventPayload,
+  DirectoryListingResponse,
+  DirectoryRenameEventPayload,
+} from '@/types';
 
 const RECONCILE_DELAY_MS = 400;
 const STREAMING_RETRY_MS = 200;
@@ -21,6 +25,7 @@ export function useDirectoryWatcher(currentPath: string) {
     let cancelled = false;
     let watcherStarted = false;
     let unlisten: UnlistenFn | undefined;
+    let unlistenRenames: UnlistenFn | undefined;
     let reconcileTimer: number | undefined;
     let reconcileInFlight = false;
     let reconcileAgain = false;
@@ -103,11 +108,26 @@ export function useDirectoryWatcher(currentPath: string) {
           }
         );
 
+        // Renames are patched into the list right away so rows keep their selection;
+        // the reconciliation that follows still has the final say.
+        const stopListeningRenames = await listen<DirectoryRenameEventPayload>(
+          'directory-renamed',
+          (event) => {
+            if (cancelled || comparablePath(event.payload.path) !== watchedPath) return;
+
+            invalidateThumbnailsForPaths(event.payload.renames.map(({ from }) => from));
+            useAppStore.getState().applyWatcherRenames(event.payload.renames);
+            scheduleReconciliation();
+          }
+        );
+
         if (cancelled) {
           stopListening();
+          stopListeningRenames();
           return;
         }
         unlisten = stopListening;
+        unlistenRenames = stopListeningRenames;
 
         await invoke('start_watching_directory', { path: currentPath });
         watcherStarted = true;
@@ -127,6 +147,7 @@ export function useDirectoryWatcher(currentPath: string) {
       cancelled = true;
       if (reconcileTimer !== undefined) window.clearTimeout(reconcileTimer);
       unlisten?.();
+      unlistenRenames?.();
       if (watcherStarted) {
         void invoke('stop_watching_directory', { path: currentPath }).catch(() => undefined);
       }
@@ -4,6 +4,7 @@ import { listen, UnlistenFn } from '@tauri-apps/api/event';
 import { useDragStore } from '@/store/useDragStore';
 import { useToastStore } from '@/store/useToastStore';
 import { pushUndoAndShowToast } from '@/store/useUndoStore';
+import type { NativeDragOperation } from '@/types';
 
 export interface DragModifiers {
   optionAlt: boolean;
@@ -12,6 +13,34 @@ export interface DragModifiers {
 
 const SAME_LOCATION_DROP_REASON = 'NO_OP_SAME_LOCATION';
 
+/**
+ * Operation for a native drag started with these modifiers held: Option/Alt copies,
+ * Cmd/Ctrl moves, and anything else leaves it to the OS.
+ */
+export const nativeDragOperationFor = (event: {
+  altKey: boolean;
+  metaKey: boolean;
+  ctrlKey: boolean;
+}): NativeDragOperation => {
+  if (event.altKey) return 'copy';
+  if (event.metaKey || event.ctrlKey) return 'move';
+  return 'auto';
+};
+
+/**
+ * Modifiers to validate a drop with. A drag this app started with a copy or move hint
+ * keeps that operation even if the keys were released mid-drag.
+ */
+const dropModifiers = (paths: string[], modifiers: DragModifiers): DragModifiers => {
+  const { nativeDragPaths, nativeDragOperation } = useDragStore.getState();
+  const isOwnDrag =
+    nativeDragPaths.length > 0 && paths.every((path) => nativeDragPaths.includes(path));
+  if (!isOwnDrag) return modifiers;
+  if (nativeDragOperation === 'copy') return { optionAlt: true, cmdCtrl: false };
+  if (nativeDragOperation === 'move') return { optionAlt: false, cmdCtrl: true };
+  return modifiers;
+};
+
 const normalizeLocalPathForDrop = (path: string) =>
   path.replace(/\\/g, '/').replace(/\/+$/, '') || '/';
 
@@ -285,7 +314,11 @@ export function useFilePanelDropZone(
         setIsDraggingOver(true);
         currentDragPaths.current = event.paths;
         // Pre-warm cache for current directory
-        void resolveOperation(event.paths, currentPath, event.modifiers);
+        void resolveOperation(
+          event.paths,
+          currentPath,
+          dropModifiers(event.paths, event.modifiers)
+        );
       },
 
       onDragOver: (event) => {
@@ -335,7 +368,11 @@ export function useFilePanelDropZone(
         }
 
         const updateState = async () => {
-          const opInfo = await resolveOperation(event.paths, targetPath, event.modifiers);
+          const opInfo = await resolveOperation(
+            event.paths,
+            targetPath,
+            dropModifiers(event.paths, event.modifiers)
+          );
 
           // Only highlight if valid
           if (opInfo.valid) {
@@ -406,7 +443,8 @@ export function useFilePanelDropZone(
         operationCache.current.clear();
 
         // Final validation and execution
-        void resolveOperation(event.paths, targetPath, event.modifiers).then(async (opInfo) => {
+        const modifiers = dropModifiers(event.paths, event.modifiers);
+        void resolveOperation(event.paths, targetPath, modifiers).then(async (opInfo) => {
           if (opInfo.reason === SAME_LOCATION_DROP_REASON) {
             return;
           }
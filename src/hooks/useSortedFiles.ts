@@ -48,6 +48,17 @@ export function useSortedFiles(files: FileItem[], preferences: ViewPreferences):
         case 'type':
           compareValue = (a.extension || '').localeCompare(b.extension || '');
           break;
+        case 'typeName': {
+          // Only the type grouping follows the sort order; names stay A→Z within a group
+          const typeCompare = (a.extension || '').localeCompare(b.extension || '');
+          const groupCompare = preferences.sortOrder === 'asc' ? typeCompare : -typeCompare;
+          return groupCompare || nameCollator.compare(a.name, b.name);
+        }
+      }
+
+      // Natural-order name tiebreak keeps equal keys stable and predictable
+      if (compareValue === 0 && effectiveSortBy !== 'name') {
+        compareValue = nameCollator.compare(a.name, b.name);
       }
 
       return preferences.sortOrder === 'asc' ? compareValue : -compareValue;
@@ -13,7 +13,8 @@ export interface ThumbnailRequest {
   size?: number;
   quality?: 'low' | 'medium' | 'high';
   priority?: 'high' | 'medium' | 'low';
-  format?: 'webp' | 'png' | 'jpeg';
+  /** 'animated-webp' keeps GIF/WebP animation; other files get a still WebP */
+  format?: 'webp' | 'png' | 'jpeg' | 'animated-webp';
   accent?: AccentColor;
 }
 
@@ -29,6 +30,15 @@ export interface ThumbnailResponse {
   image_height?: number | null;
 }
 
+/** One result of `request_thumbnails_batch`, also the `thumbnail:batch` event payload */
+export interface ThumbnailBatchItem {
+  batchId: string;
+  path: string;
+  size: number;
+  response?: ThumbnailResponse | null;
+  error?: string | null;
+}
+
 export interface ThumbnailCacheStats {
   memory_entries: number;
   memory_size_bytes: number;
@@ -230,6 +240,31 @@ export function invalidateThumbnailsForPaths(paths: string[]): void {
   }
 }
 
+/**
+ * Return an already-generated thumbnail for a path, if any. Never starts a new request;
+ * a thumbnail still in flight is waited on only briefly.
+ */
+export async function getCachedThumbnailDataUrl(
+  path: string,
+  waitMs = 25
+): Promise<string | undefined> {
+  const pending: Promise<ThumbnailResponse>[] = [];
+  for (const [key, promise] of thumbnailPromises) {
+    if (extractPathFromCacheKey(key) === path) {
+      pending.push(promise);
+    }
+  }
+  if (pending.length === 0) return undefined;
+
+  const timeout = new Promise<undefined>((resolve) => window.setTimeout(resolve, waitMs));
+  try {
+    const response = await Promise.race([...pending, timeout]);
+    return response?.data_url || undefined;
+  } catch {
+    return undefined;
+  }
+}
+
 export interface ThumbnailOptions extends Omit<ThumbnailRequest, 'path'> {
   /** Remote thumbnail URL (e.g., from Google Drive) - used directly instead of generating */
   thumbnailUrl?: string;
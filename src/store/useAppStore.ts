@@ -15,11 +15,14 @@ import {
   DeletePathsResponse,
   DeleteItemPayload,
   GoogleAccountInfo,
+  SmbProtocol,
   SmbServerInfo,
   SftpServerInfo,
   ClipboardInfo,
   PasteResult,
   PasteImageResult,
+  WatcherRename,
+  FileTemplate,
 } from '../types';
 import { invoke } from '@tauri-apps/api/core';
 import { emit } from '@tauri-apps/api/event';
@@ -30,6 +33,7 @@ import { basename } from '@/utils/pathUtils';
 import { useToastStore } from './useToastStore';
 import { useUndoStore, pushUndoAndShowToast } from './useUndoStore';
 import { parseGoogleDriveUrl } from '@/utils/googleDriveUrl';
+import { confirmAndTrustHostKey, parseUnknownHostKey } from '@/utils/sftpHostKey';
 import { getArchiveParentUri, isArchiveUri } from '@/utils/archiveUri';
 import { DEFAULT_THEME_IDS } from '@/themes';
 
@@ -67,7 +71,9 @@ const activeArchiveExtractions = new Set<string>();
 
 const mergeFileItem = (previous: FileItem, next: FileItem): FileItem => ({
   ...next,
-  child_count: next.child_count ?? previous.child_count,
+  // Counts are fetched separately; a folder's mtime moves when entries come or go
+  child_count:
+    next.child_count ?? (next.modified === previous.modified ? previous.child_count : undefined),
   image_width: next.image_width ?? previous.image_width,
   image_height: next.image_height ?? previous.image_height,
   extension: next.extension ?? previous.extension,
@@ -317,6 +323,7 @@ interface AppState {
   addFiles: (files: FileItem[]) => void;
   updateFiles: (files: FileItem[]) => void;
   removeFilesByPath: (paths: Set<string>) => void;
+  applyWatcherRenames: (renames: WatcherRename[]) => void;
   setLoading: (loading: boolean) => void;
   setError: (error?: string) => void;
   setSelectedFiles: (files: string[]) => void;
@@ -348,12 +355,19 @@ interface AppState {
   appendStreamingBatch: (batch: DirectoryBatch) => void;
   applyMetadataUpdates: (batch: MetadataBatch) => void;
   updateFileDimensions: (path: string, width: number, height: number) => void;
+  updateFileChildCount: (path: string, count: number) => void;
   cancelDirectoryStream: () => Promise<void>;
   openFile: (file: FileItem) => Promise<void>;
-  extractArchive: (file: FileItem, options?: { createSubfolder?: boolean }) => Promise<boolean>;
+  extractArchive: (
+    file: FileItem,
+    options?: { createSubfolder?: boolean; extractHere?: boolean; verify?: boolean }
+  ) => Promise<boolean>;
   createNewFolder: () => Promise<void>;
-  createNewFile: () => Promise<void>;
-  compressSelectedToZip: (suggestedName: string) => Promise<void>;
+  createNewFile: (template?: FileTemplate) => Promise<void>;
+  compressSelectedToZip: (
+    suggestedName: string,
+    options?: { replaceOriginals?: boolean }
+  ) => Promise<void>;
   trashSelected: () => Promise<void>;
   deleteSelectedPermanently: () => Promise<void>;
   fetchAppIcon: (path: string, size?: number) => Promise<string | undefined>;
@@ -362,7 +376,15 @@ interface AppState {
   invalidateGitStatus: (path?: string) => void;
   // Pinned directories
   loadPinnedDirectories: () => Promise<void>;
-  addPinnedDirectory: (path: string, name?: string) => Promise<PinnedDirectory>;
+  addPinnedDirectory: (
+    path: string,
+    name?: string,
+    style?: Pick<PinnedDirectory, 'color' | 'icon'>
+  ) => Promise<PinnedDirectory>;
+  updatePinnedDirectory: (
+    path: string,
+    changes: Partial<Pick<PinnedDirectory, 'name' | 'color' | 'icon'>>
+  ) => Promise<PinnedDirectory>;
   removePinnedDirectory: (path: string) => Promise<boolean>;
   reorderPinnedDirectories: (paths: string[]) => Promise<void>;
   // Google Drive accounts
@@ -375,7 +397,10 @@ interface AppState {
     hostname: string,
     username: string,
     password: string,
-    domain?: string
+    domain?: string,
+    guest?: boolean,
+    minProtocol?: SmbProtocol,
+    maxProtocol?: SmbProtocol
   ) => Promise<SmbServerInfo>;
   removeSmbServer: (hostname: string) => Promise<void>;
   setPendingSmbCredentialRequest: (
@@ -526,6 +551,38 @@ export const useAppStore = create<AppState>((set, get) => ({
     set((state) => ({
       files: state.files.filter((f) => !paths.has(f.path)),
     })),
+  applyWatcherRenames: (renames) => {
+    const state = get();
+    const targets = new Map(renames.map(({ from, to }) => [from, to]));
+    if (!state.files.some((file) => targets.has(file.path))) return;
+    const renamed = (path?: string) => (path ? (targets.get(path) ?? path) : path);
+
+    // Update rows in place so the entry keeps its position, selection and animation state
+    set({
+      files: uniqueFilesByPath(
+        state.files.map((file) => {
+          const to = targets.get(file.path);
+          if (!to) return file;
+          const name = basename(to);
+          const dot = name.lastIndexOf('.');
+          return {
+            ...file,
+            name,
+            path: to,
+            is_hidden: name.startsWith('.'),
+            extension:
+              file.is_directory || dot <= 0 ? undefined : name.slice(dot + 1).toLowerCase(),
+          };
+        })
+      ),
+      selectionAnchor: renamed(state.selectionAnchor),
+      selectionLead: renamed(state.selectionLead),
+    });
+    if (state.selectedFiles.some((path) => targets.has(path))) {
+      state.setSelectedFiles(state.selectedFiles.map((path) => renamed(path)!));
+    }
+    for (const { to } of renames) state.addSkipAnimationPath(to);
+  },
   setLoading: (loading) => set({ loading }),
   setError: (error) => set({ error }),
   setSelectedFiles: (files) => {
@@ -918,6 +975,21 @@ export const useAppStore = create<AppState>((set, get) => ({
         }
       }
 
+      // Unknown SFTP host: ask to trust its key, then retry
+      const hostKey = parseUnknownHostKey(msg);
+      if (hostKey) {
+        try {
+          if (await confirmAndTrustHostKey(hostKey)) {
+            await get().refreshCurrentDirectory();
+            return;
+          }
+          setError(`The host key for ${hostKey.hostname} was not trusted.`);
+        } catch (trustErr) {
+          setError(trustErr instanceof Error ? trustErr.message : String(trustErr));
+        }
+        return;
+      }
+
       // Check if this is an SFTP "no credentials" error
       if (msg.includes('[SFTP_NO_CREDENTIALS]')) {
         const sftpMatch = currentPath.match(/^sftp:\/\/(?:([^@]+)@)?([^/:]+)(?::(\d+))?/);
@@ -1056,6 +1128,7 @@ export const useAppStore = create<AppState>((set, get) => ({
           child_count: update.childCount != null ? update.childCount : file.child_count,
           image_width: update.imageWidth != null ? update.imageWidth : file.image_width,
           image_height: update.imageHeight != null ? update.imageHeight : file.image_height,
+          lfs_size: update.lfsSize ?? undefined,
         };
       });
 
@@ -1083,6 +1156,18 @@ export const useAppStore = create<AppState>((set, get) => ({
     });
   },
 
+  updateFileChildCount: (path: string, count: number) => {
+    set((state) => {
+      const idx = state.files.findIndex((f) => f.path === path);
+      if (idx === -1 || state.files[idx].child_count === count) {
+        return {};
+      }
+      const updatedFiles = [...state.files];
+      updatedFiles[idx] = { ...updatedFiles[idx], child_count: count };
+      return { files: updatedFiles };
+    });
+  },
+
   cancelDirectoryStream: async () => {
     const { streamingSessionId } = get();
     if (streamingSessionId) {
@@ -1572,6 +1657,8 @@ export const useAppStore = create<AppState>((set, get) => ({
     const archivePath = file?.path;
     const destinationDir = state.currentPath;
     const createSubfolder = options?.createSubfolder ?? true;
+    const extractHere = options?.extractHere ?? false;
+    const verify = options?.verify ?? false;
 
     if (!archivePath || !destinationDir) {
       console.warn('extractArchive called without a valid path or destination');
@@ -1689,14 +1776,18 @@ export const useAppStore = create<AppState>((set, get) => ({
       } finally {
         window.clearTimeout(progressTimer);
         if (tempArchivePath) {
-          void invoke('delete_file', { path: tempArchivePath }).catch((cleanupError) => {
-            console.warn('Failed to clean up SMB temp archive:', cleanupError);
-          });
+          void invoke('delete_file', { path: tempArchivePath, permanent: true }).catch(
+            (cleanupError) => {
+              console.warn('Failed to clean up SMB temp archive:', cleanupError);
+            }
+          );
         }
         if (extractRoot) {
-          void invoke('delete_file', { path: extractRoot }).catch((cleanupError) => {
-            console.warn('Failed to clean up SMB extract directory:', cleanupError);
-          });
+          void invoke('delete_file', { path: extractRoot, permanent: true }).catch(
+            (cleanupError) => {
+              console.warn('Failed to clean up SMB extract directory:', cleanupError);
+            }
+          );
         }
         void invoke('hide_archive_progress_window').catch((error) => {
           console.warn('Failed to hide archive progress window:', error);
@@ -1821,14 +1912,18 @@ export const useAppStore = create<AppState>((set, get) => ({
         folderPath: string;
         usedSystemFallback?: boolean;
         format?: string;
+        verified?: boolean;
+        failedEntries?: { path: string; reason: string }[];
       }>('extract_archive', {
         archivePath: archivePath,
         destinationDir: destinationDir,
         formatHint: archiveFormat,
         createSubfolder,
+        extractHere,
+        verify,
       });
 
-      if (result?.folderPath && createSubfolder) {
+      if (result?.folderPath && (createSubfolder || extractHere)) {
         set({ pendingRevealTarget: result.folderPath });
       }
 
@@ -1841,6 +1936,23 @@ export const useAppStore = create<AppState>((set, get) => ({
         format: resolvedFormat,
       });
 
+      const failedEntries = result?.failedEntries ?? [];
+      if (failedEntries.length > 0) {
+        const listed = failedEntries
+          .slice(0, 5)
+          .map((entry) => `${entry.path}: ${entry.reason}`)
+          .join('\n');
+        const more = failedEntries.length > 5 ? `\n…and ${failedEntries.length - 5} more` : '';
+        try {
+          await message(
+            `${failedEntries.length} file(s) from ${file.name} don't match the archive:\n${listed}${more}`,
+            { title: 'Archive Extraction', kind: 'warning' }
+          );
+        } catch (dialogError) {
+          console.warn('Failed to show extraction verification dialog:', dialogError);
+        }
+      }
+
       await state.refreshCurrentDirectoryStreaming();
       return true;
     } catch (error) {
@@ -2024,9 +2136,13 @@ export const useAppStore = create<AppState>((set, get) => ({
     }
   },
 
-  addPinnedDirectory: async (path: string, name?: string) => {
+  addPinnedDirectory: async (path, name, style) => {
     try {
-      const newPin = await invoke<PinnedDirectory>('add_pinned_directory', { path, name });
+      const newPin = await invoke<PinnedDirectory>('add_pinned_directory', {
+        path,
+        name,
+        ...style,
+      });
       set((state) => ({
         pinnedDirectories: [...state.pinnedDirectories, newPin],
       }));
@@ -2037,6 +2153,21 @@ export const useAppStore = create<AppState>((set, get) => ({
     }
   },
 
+  updatePinnedDirectory: async (path, changes) => {
+    // Colour and icon are replaced, so a change to one has to resend the other
+    const current = get().pinnedDirectories.find((p) => p.path === path);
+    const updated = await invoke<PinnedDirectory>('update_pinned_directory', {
+      path,
+      name: changes.name,
+      color: 'color' in changes ? changes.color : current?.color,
+      icon: 'icon' in changes ? changes.icon : current?.icon,
+    });
+    set((state) => ({
+      pinnedDirectories: state.pinnedDirectories.map((p) => (p.path === path ? updated : p)),
+    }));
+    return updated;
+  },
+
   removePinnedDirectory: async (path: string) => {
     try {
       const removed = await invoke<boolean>('remove_pinned_directory', { path });
@@ -2086,8 +2217,12 @@ export const useAppStore = create<AppState>((set, get) => ({
   addGoogleAccount: async () => {
     try {
       const newAccount = await invoke<GoogleAccountInfo>('add_google_account');
+      // Re-authenticating an existing account replaces it rather than adding a duplicate
       set((state) => ({
-        googleAccounts: [...state.googleAccounts, newAccount],
+        googleAccounts: [
+          ...state.googleAccounts.filter((a) => a.email !== newAccount.email),
+          newAccount,
+        ],
       }));
       return newAccount;
     } catch (error) {
@@ -2119,13 +2254,24 @@ export const useAppStore = create<AppState>((set, get) => ({
     }
   },
 
-  addSmbServer: async (hostname: string, username: string, password: string, domain?: string) => {
+  addSmbServer: async (
+    hostname: string,
+    username: string,
+    password: string,
+    domain?: string,
+    guest?: boolean,
+    minProtocol?: SmbProtocol,
+    maxProtocol?: SmbProtocol
+  ) => {
     try {
       const newServer = await invoke<SmbServerInfo>('add_smb_server', {
         hostname,
         username,
         password,
         domain,
+        guest,
+        minProtocol,
+        maxProtocol,
       });
       set((state) => ({
         smbServers: [...state.smbServers.filter((s) => s.hostname !== hostname), newServer],
@@ -2538,7 +2684,7 @@ export const useAppStore = create<AppState>((set, get) => ({
         const failedDeletes: string[] = [];
         for (const remotePath of remoteOriginals) {
           try {
-            await invoke('delete_file', { path: remotePath });
+            await invoke('delete_file', { path: remotePath, permanent: true });
           } catch (err) {
             console.warn(`Failed to delete remote original after cut: ${remotePath}`, err);
             failedDeletes.push(remotePath);
@@ -2611,15 +2757,16 @@ export const useAppStore = create<AppState>((set, get) => ({
     }
   },
 
-  createNewFile: async () => {
+  createNewFile: async (template?: FileTemplate) => {
     const state = get();
     const toastStore = useToastStore.getState();
     const baseDir = state.currentPath;
 
-    if (!baseDir || baseDir.includes('://')) {
+    if (!baseDir) return;
+    if (state.currentProviderCapabilities && !state.currentProviderCapabilities.canWrite) {
       toastStore.addToast({
         type: 'error',
-        message: 'New files can only be created in local directories.',
+        message: 'This location is read-only.',
         duration: 5000,
       });
       return;
@@ -2630,9 +2777,18 @@ export const useAppStore = create<AppState>((set, get) => ({
       await state.cancelRename();
     }
 
+    const name = template?.extension ? `Untitled.${template.extension}` : undefined;
     try {
-      const createdPath = await invoke<string>('create_file', { baseDir });
-      await state.refreshCurrentDirectoryStreaming();
+      const createdPath = await invoke<string>('create_file', {
+        baseDir,
+        name,
+        contents: template?.contents,
+      });
+      if (baseDir.includes('://')) {
+        await state.refreshCurrentDirectory();
+      } else {
+        await state.refreshCurrentDirectoryStreaming();
+      }
       set({
         justCreatedPath: createdPath,
         renameTargetPath: createdPath,
@@ -2653,7 +2809,7 @@ export const useAppStore = create<AppState>((set, get) => ({
     }
   },
 
-  compressSelectedToZip: async (suggestedName: string) => {
+  compressSelectedToZip: async (suggestedName, options) => {
     const state = get();
     const toastStore = useToastStore.getState();
     const selectedPaths = state.selectedFiles.slice();
@@ -2689,7 +2845,9 @@ export const useAppStore = create<AppState>((set, get) => ({
     }, 500);
 
     try {
-      const result = await invoke<{ path: string }>('compress_to_zip', {
+      // Replacing deletes the originals, but only once the archive reads back intact
+      const command = options?.replaceOriginals ? 'compress_and_replace' : 'compress_to_zip';
+      const result = await invoke<{ path: string }>(command, {
         sourcePaths: selectedPaths,
         destinationDir: state.currentPath,
         suggestedName,
@@ -2814,14 +2972,14 @@ export const useAppStore = create<AppState>((set, get) => ({
       if (metadata.is_directory) {
         const listing = await invoke<DirectoryListingResponse>('read_directory', { path: target });
         if (listing.entries.length === 0) {
-          await invoke('delete_file', { path: target });
+          await invoke('delete_file', { path: target, permanent: true });
         } else {
           return;
         }
       } else {
         // For files: only delete if still empty (0 bytes)
         if (metadata.size === 0) {
-          await invoke('delete_file', { path: target });
+          await invoke('delete_file', { path: target, permanent: true });
         } else {
           return;
         }
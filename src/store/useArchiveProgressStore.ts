@@ -7,6 +7,8 @@ interface ArchiveProgressState {
   format?: string;
   entries: string[];
   currentEntry?: string;
+  extractedBytes: number;
+  totalBytes: number | null;
   finished: boolean;
   setContext: (payload: ArchiveProgressPayload) => void;
   pushUpdate: (payload: ArchiveProgressUpdatePayload) => void;
@@ -19,6 +21,8 @@ const INITIAL_STATE: Omit<ArchiveProgressState, 'setContext' | 'pushUpdate' | 'r
   format: undefined,
   entries: [],
   currentEntry: undefined,
+  extractedBytes: 0,
+  totalBytes: null,
   finished: false,
 };
 
@@ -31,6 +35,8 @@ export const useArchiveProgressStore = create<ArchiveProgressState>((set) => ({
       format: payload.format,
       entries: [],
       currentEntry: undefined,
+      extractedBytes: 0,
+      totalBytes: null,
       finished: false,
     }),
   pushUpdate: (payload) =>
@@ -39,6 +45,8 @@ export const useArchiveProgressStore = create<ArchiveProgressState>((set) => ({
       format: payload.format ?? state.format,
       entries: payload.entryName ? [...state.entries, payload.entryName] : state.entries,
       currentEntry: payload.entryName ?? state.currentEntry,
+      extractedBytes: Math.max(payload.extractedBytes ?? 0, state.extractedBytes),
+      totalBytes: payload.totalBytes ?? state.totalBytes,
       finished: payload.finished ?? state.finished,
     })),
   reset: () => set({ ...INITIAL_STATE }),
@@ -1,4 +1,5 @@
 import { create } from 'zustand';
+import type { NativeDragEnded, NativeDragOperation } from '@/types';
 
 interface DraggedDirectory {
   path: string;
@@ -10,6 +11,8 @@ interface DragStore {
   nativeDragDirectory: DraggedDirectory | null;
   // Paths placed on the native pasteboard by this app while a native drag is active.
   nativeDragPaths: string[];
+  // Operation the active native drag was limited to, from the modifiers held when it started
+  nativeDragOperation: NativeDragOperation;
   // In-app hover target for non-native drags
   inAppDropTargetId: string | null;
   dropTargetPath: string | null;
@@ -18,10 +21,12 @@ interface DragStore {
 
   // Start tracking a native drag of a directory
   startNativeDrag: (directory: DraggedDirectory) => void;
-  setNativeDragPaths: (paths: string[]) => void;
+  setNativeDragPaths: (paths: string[], operation?: NativeDragOperation) => void;
   clearNativeDragPaths: () => void;
   // End tracking of native drag
   endNativeDrag: () => void;
+  // A hinted native drag finished; drop all drag and drop-target state it left behind
+  handleNativeDragEnded: (ended: NativeDragEnded) => void;
   // Check if a specific path is being dragged
   isDraggedDirectory: (path: string) => boolean;
   // Update in-app drop target for hover feedback
@@ -34,6 +39,7 @@ interface DragStore {
 export const useDragStore = create<DragStore>((set, get) => ({
   nativeDragDirectory: null,
   nativeDragPaths: [],
+  nativeDragOperation: 'auto',
   inAppDropTargetId: null,
   dropTargetPath: null,
   pendingDropOperation: null,
@@ -45,12 +51,12 @@ export const useDragStore = create<DragStore>((set, get) => ({
     });
   },
 
-  setNativeDragPaths: (paths: string[]) => {
-    set({ nativeDragPaths: paths });
+  setNativeDragPaths: (paths: string[], operation: NativeDragOperation = 'auto') => {
+    set({ nativeDragPaths: paths, nativeDragOperation: operation });
   },
 
   clearNativeDragPaths: () => {
-    set({ nativeDragPaths: [] });
+    set({ nativeDragPaths: [], nativeDragOperation: 'auto' });
   },
 
   endNativeDrag: () => {
@@ -60,6 +66,21 @@ export const useDragStore = create<DragStore>((set, get) => ({
     });
   },
 
+  handleNativeDragEnded: (ended: NativeDragEnded) => {
+    // Every window hears the event; only the one that started this drag resets
+    const { nativeDragPaths } = get();
+    if (!ended.paths.some((path) => nativeDragPaths.includes(path))) return;
+    set({
+      nativeDragDirectory: null,
+      nativeDragPaths: [],
+      nativeDragOperation: 'auto',
+      inAppDropTargetId: null,
+      dropTargetPath: null,
+      pendingDropOperation: null,
+      isDraggingOver: false,
+    });
+  },
+
   isDraggedDirectory: (path: string) => {
     const state = get();
     return state.nativeDragDirectory?.path === path;
@@ -14,6 +14,68 @@ export interface FileItem {
   remote_id?: string; // Remote file ID (e.g., Google Drive file ID)
   thumbnail_url?: string; // Remote thumbnail URL (e.g., Google Drive thumbnail link)
   download_url?: string; // Remote download URL (e.g., Google Drive web content link)
+  tags?: FileTag[] | null; // Finder-style tags, only present when the listing asked for them
+  is_git_submodule?: boolean; // Directory is the root of a Git submodule
+  lfs_size?: number; // Real size of a Git LFS file when only its pointer is checked out
+  id?: string; // Survives renames: device+inode or file index locally, the path for remote providers
+}
+
+export interface FileTag {
+  name: string;
+  color?: number | null; // Finder colour index 1-7 (gray, green, purple, blue, yellow, red, orange)
+}
+
+// Result of `get_file_preview`; `truncated` means the text was cut short or the image scaled down
+export type FilePreview = {
+  mimeType: string;
+  truncated: boolean;
+} & (
+  | { kind: 'text' | 'markdown'; text: string; encoding: string }
+  | { kind: 'image'; dataUrl: string; width?: number | null; height?: number | null }
+);
+
+// Result of `read_text_file`; `encoding` is the detected encoding's WHATWG name
+export interface TextFileContent {
+  text: string;
+  encoding: string;
+  truncated: boolean;
+}
+
+export interface DiffLine {
+  change: 'context' | 'delete' | 'insert';
+  oldLine: number | null;
+  newLine: number | null;
+  text: string;
+}
+
+// Line numbers are 1-based, as in a unified diff header
+export interface DiffHunk {
+  oldStart: number;
+  oldLines: number;
+  newStart: number;
+  newLines: number;
+  lines: DiffLine[];
+}
+
+// Result of `diff_files`; binary files are only compared for equality
+export type FileDiff =
+  | {
+      kind: 'text';
+      identical: boolean;
+      leftEncoding: string;
+      rightEncoding: string;
+      hunks: DiffHunk[];
+    }
+  | { kind: 'binary'; identical: boolean };
+
+/** One entry from `get_applications_for_file`; pass `path` to `open_path_with` */
+export interface ApplicationInfo {
+  name: string;
+  /** `.app` bundle on macOS, `.desktop` file on Linux */
+  path: string;
+  /** PNG data URL on macOS; icon theme name or path on Linux */
+  icon: string | null;
+  isDefault: boolean;
 }
 
 export interface LocationSummary {
@@ -35,7 +97,9 @@ export interface LocationCapabilities {
   canCopy: boolean;
   canMove: boolean;
   supportsWatching: boolean;
+  supportsTags: boolean;
   requiresExplicitRefresh: boolean;
+  renamesByCopy: boolean;
 }
 
 export interface DirectoryListingResponse {
@@ -71,6 +135,7 @@ export interface FileMetadataUpdate {
   childCount?: number | null;
   imageWidth?: number | null;
   imageHeight?: number | null;
+  lfsSize?: number | null;
 }
 
 /** A batch of metadata updates for files already in the list */
@@ -82,18 +147,26 @@ export interface MetadataBatch {
 
 export interface ViewPreferences {
   viewMode: 'grid' | 'list' | 'details';
-  sortBy: 'name' | 'size' | 'modified' | 'type';
+  sortBy: 'name' | 'size' | 'modified' | 'type' | 'typeName';
   sortOrder: 'asc' | 'desc';
   showHidden: boolean;
   foldersFirst: boolean;
   // Grid (thumbnail) view tile size in px (min column width)
   gridSize?: number;
+  // Show folders in the grid as a collage of the images inside them
+  folderPreviews?: boolean;
   theme?: Theme;
   darkThemeId?: string;
   lightThemeId?: string;
   customThemes?: ThemeDefinition[];
   accentColorMode?: 'system' | 'custom';
   accentColorCustom?: string;
+  // Open archives whose root is a single folder at that folder
+  flattenArchiveRoot?: boolean;
+  // Identical files share one cached thumbnail (default true)
+  shareIdenticalThumbnails?: boolean;
+  // macOS: open_terminal_here uses iTerm when installed (default true)
+  preferITerm?: boolean;
 }
 
 export type DirectoryPreferencesMap = Record<string, Partial<ViewPreferences>>;
@@ -135,6 +208,17 @@ export interface SystemDrive {
   path: string;
   drive_type: string;
   is_ejectable: boolean;
+  /** Null when the volume isn't mounted or can't be queried */
+  total_bytes: number | null;
+  available_bytes: number | null;
+}
+
+/** Result of `get_directory_stats`; only counts what is directly inside the folder */
+export interface DirectoryStats {
+  fileCount: number;
+  directoryCount: number;
+  fileBytes: number;
+  hiddenCount: number;
 }
 
 export interface DiskUsage {
@@ -143,6 +227,97 @@ export interface DiskUsage {
   availableBytes: number;
 }
 
+/** Result of `compute_file_hash` */
+export interface FileHashResponse {
+  algorithm: 'md5' | 'sha1' | 'sha256';
+  digest: string;
+  elapsedMs: number;
+}
+
+export interface TransferFeasibility {
+  totalSourceBytes: number;
+  requiredBytes: number;
+  availableBytes?: number | null;
+  fits?: boolean | null;
+  marginBytes?: number | null;
+  isEstimate: boolean;
+}
+
+/** How `copy_with_options` handles a file whose name is taken at the destination */
+export type ConflictPolicy = 'overwrite' | 'skip' | 'rename';
+
+/** Destination paths from `copy_with_options`, by what happened to each */
+export interface CopyReport {
+  copied: string[];
+  overwritten: string[];
+  skipped: string[];
+  renamed: { from: string; to: string }[];
+}
+
+export interface CopyTreeProgress {
+  requestId: string;
+  filesDone: number;
+  filesTotal: number;
+  bytesDone: number;
+  bytesTotal: number;
+  currentPath?: string | null;
+  finished: boolean;
+  cancelled: boolean;
+  rolledBack: boolean;
+  error?: string;
+}
+
+export interface CopyTreeResult {
+  cancelled: boolean;
+  rolledBack: boolean;
+  filesCopied: number;
+  bytesCopied: number;
+}
+
+export interface RemoteConcurrencyStats {
+  scheme: string;
+  limit: number;
+  inFlight: number;
+  waiting: number;
+}
+
+export interface SearchIndexStatus {
+  root: string;
+  state: 'building' | 'ready' | 'tooLarge' | 'failed';
+  entries: number;
+  buildMs?: number | null;
+  error?: string | null;
+}
+
+export interface SearchFilesResponse {
+  results: FileItem[];
+  fromIndex: boolean;
+  truncated: boolean;
+  index?: SearchIndexStatus | null;
+}
+
+export interface DirectorySearchOptions {
+  caseSensitive?: boolean;
+  /** Match the query as a glob (`*.rs`) instead of a substring */
+  glob?: boolean;
+  recursive?: boolean;
+  includeHidden?: boolean;
+  limit?: number;
+}
+
+/** Payload of the `directory-search:batch` event */
+export interface DirectorySearchBatch {
+  requestId: string;
+  entries: FileItem[];
+}
+
+export interface DirectorySearchResponse {
+  location: LocationSummary;
+  results: FileItem[];
+  truncated: boolean;
+  cancelled: boolean;
+}
+
 export interface GitStatus {
   repositoryRoot: string;
   branch?: string;
@@ -153,6 +328,96 @@ export interface GitStatus {
   hasUntracked: boolean;
   remoteUrl?: string;
   remoteBranchUrl?: string;
+  /** Set when the working-tree scan timed out; dirty/untracked are unknown */
+  statusUnavailable?: string | null;
+  stashCount: number;
+  /** Every remote with a branch of the same name as the current one */
+  remotes: GitRemoteStatus[];
+}
+
+export interface GitRemoteStatus {
+  name: string;
+  ahead: number;
+  behind: number;
+}
+
+export type GitFileStatus =
+  | 'clean'
+  | 'ignored'
+  | 'untracked'
+  | 'staged'
+  | 'modified'
+  | 'conflicted';
+
+/** Result of `get_git_file_statuses`: entry name -> status */
+export type GitFileStatusMap = Record<string, GitFileStatus>;
+
+export type PlanConflict =
+  | 'destinationExists'
+  | 'duplicateTarget'
+  | 'sourceMissing'
+  | 'intoItself'
+  | 'unchanged';
+
+export interface PlannedAction {
+  source: string;
+  /** Null when the item is skipped */
+  destination: string | null;
+  conflict: PlanConflict | null;
+}
+
+/** Result of batch_rename / move_files / organize_*; `dryRun` means only planned */
+export interface FileOperationResult {
+  dryRun: boolean;
+  actions: PlannedAction[];
+  failures: { source: string; error: string }[];
+}
+
+/** One applied rename from `batch_rename_pattern` */
+export interface RenamedItem {
+  oldPath: string;
+  newPath: string;
+}
+
+export type OrganizeStrategy = 'ByExtension' | 'ByDate' | 'ByFirstLetter';
+
+/** Payload of the `organize-progress` event */
+export interface OrganizeProgress {
+  requestId: string;
+  done: number;
+  total: number;
+  finished: boolean;
+}
+
+export interface LastCommitInfo {
+  shortSha: string;
+  author: string;
+  summary: string;
+  timestamp: string;
+}
+
+export interface LastCommitEntry {
+  path: string;
+  /** Null for untracked paths or ones not touched within the walked history */
+  commit: LastCommitInfo | null;
+}
+
+export interface LastCommitResponse {
+  entries: LastCommitEntry[];
+  historyTruncated: boolean;
+  cancelled: boolean;
+}
+
+/** A commit from `get_file_git_log` */
+export interface FileLogEntry extends LastCommitInfo {
+  sha: string;
+  /** The commit on the remote's web UI, when the remote is a known host */
+  url: string | null;
+}
+
+export interface FileLogResponse {
+  entries: FileLogEntry[];
+  historyTruncated: boolean;
 }
 
 export interface PinnedDirectory {
@@ -161,6 +426,8 @@ export interface PinnedDirectory {
   pinned_at: string; // ISO 8601 string from Rust DateTime<Utc>
   is_git_repo: boolean;
   is_symlink: boolean;
+  color?: string | null; // Hex colour for the folder icon
+  icon?: string | null; // Symbol name or emoji shown instead of the folder icon
 }
 
 export interface PersistedPreferences {
@@ -177,6 +444,17 @@ export interface DirectoryChangeEventPayload {
   affectedPaths?: string[];
 }
 
+export interface WatcherRename {
+  from: string;
+  to: string;
+}
+
+/** Deletes and creates the watcher matched up as renames of the same file */
+export interface DirectoryRenameEventPayload {
+  path: string;
+  renames: WatcherRename[];
+}
+
 export interface FolderSizeProgressPayload {
   requestId: string;
   totalBytes: number;
@@ -188,6 +466,18 @@ export interface FolderSizeProgressPayload {
   error?: string | null;
 }
 
+export interface FolderSizeChild {
+  path: string;
+  bytes: number;
+  items: number;
+}
+
+/** Payload of `folder-size:breakdown`, sent when a scan completes */
+export interface FolderSizeBreakdownPayload {
+  requestId: string;
+  roots: { root: string; children: FolderSizeChild[] }[];
+}
+
 export interface FolderSizeTargetPayload {
   path: string;
   name: string;
@@ -212,6 +502,10 @@ export interface ArchiveProgressUpdatePayload {
   entryName?: string;
   format?: string;
   finished?: boolean;
+  // Uncompressed bytes written so far
+  extractedBytes?: number;
+  // Null when the archive's size can't be known up front (compressed tars)
+  totalBytes?: number | null;
 }
 
 export interface CompressProgressPayload {
@@ -279,6 +573,7 @@ export interface GoogleAccountInfo {
   email: string;
   displayName?: string | null;
   photoUrl?: string | null;
+  needsReauth?: boolean;
 }
 
 export interface ResolveGoogleDriveUrlResult {
@@ -288,10 +583,15 @@ export interface ResolveGoogleDriveUrlResult {
 }
 
 // SMB Network Share Types
+export type SmbProtocol = 'NT1' | 'SMB2' | 'SMB3';
+
 export interface SmbServerInfo {
   hostname: string;
   username: string;
   domain?: string | null;
+  guest: boolean;
+  minProtocol?: SmbProtocol | null;
+  maxProtocol?: SmbProtocol | null;
 }
 
 export interface SmbConnectInitPayload {
@@ -313,6 +613,28 @@ export interface SftpServerInfo {
   keyPath?: string | null;
 }
 
+export interface WebDavServerInfo {
+  hostname: string;
+  port: number;
+  username: string;
+  useHttps: boolean;
+}
+
+// MTP Device Types
+export interface MtpDeviceInfo {
+  id: string;
+  name: string;
+  manufacturer?: string | null;
+  model?: string | null;
+}
+
+// Templates offered under "New" (globalPreferences.fileTemplates)
+export interface FileTemplate {
+  name: string;
+  extension: string;
+  contents: string;
+}
+
 export interface SftpConnectInitPayload {
   initialHostname?: string | null;
   initialPort?: number | null;
@@ -327,6 +649,14 @@ export interface SftpConnectSuccessPayload {
   targetPath?: string | null;
 }
 
+/** Details from an `[SFTP_HOST_KEY_UNKNOWN]` error, shown before trusting a host. */
+export interface SftpHostKeyPrompt {
+  hostname: string;
+  port: number;
+  algorithm: string;
+  fingerprint: string;
+}
+
 // Clipboard Types
 export interface ClipboardInfo {
   hasFiles: boolean;
@@ -373,3 +703,13 @@ export interface ConflictResolution {
   customName?: string | null;
   applyToAll: boolean;
 }
+
+/** What a native drag out of the app offers; `auto` leaves it to the OS */
+export type NativeDragOperation = 'copy' | 'move' | 'link' | 'auto';
+
+/** Payload of `native-drag:ended`, sent when a drag hinted with copy/move/link finishes */
+export interface NativeDragEnded {
+  paths: string[];
+  /** What the drop target performed; null if the drag was cancelled or refused */
+  operation: Exclude<NativeDragOperation, 'auto'> | null;
+}
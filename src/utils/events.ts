@@ -3,3 +3,4 @@ export const SMB_CONNECT_SUCCESS_EVENT = 'smb-connect:success';
 export const SFTP_CONNECT_INIT_EVENT = 'sftp-connect:init';
 export const SFTP_CONNECT_SUCCESS_EVENT = 'sftp-connect:success';
 export const PREFERENCES_UPDATED_EVENT = 'preferences:updated';
+export const NATIVE_DRAG_ENDED_EVENT = 'native-drag:ended';
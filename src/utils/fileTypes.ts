@@ -101,6 +101,82 @@ export function isVideoExtension(ext?: string | null): boolean {
   return VIDEO_EXTENSIONS.has(ext.toLowerCase());
 }
 
+/** Audio formats whose tags can carry album art. */
+export const AUDIO_EXTENSIONS = new Set([
+  'mp3',
+  'flac',
+  'm4a',
+  'm4b',
+  'ogg',
+  'oga',
+  'opus',
+  'wav',
+  'aif',
+  'aiff',
+  'ape',
+  'wv',
+]);
+
+export function isAudioExtension(ext?: string | null): boolean {
+  if (!ext) return false;
+  return AUDIO_EXTENSIONS.has(ext.toLowerCase());
+}
+
+/** Camera RAW formats; thumbnails come from the preview JPEG embedded in the file. */
+export const RAW_EXTENSIONS = new Set([
+  'cr2',
+  'cr3',
+  'crw',
+  'nef',
+  'nrw',
+  'arw',
+  'srf',
+  'sr2',
+  'dng',
+  'orf',
+  'rw2',
+  'raf',
+  'pef',
+  'srw',
+  '3fr',
+  'erf',
+  'kdc',
+  'mrw',
+  'x3f',
+  'iiq',
+]);
+
+export function isRawExtension(ext?: string | null): boolean {
+  if (!ext) return false;
+  return RAW_EXTENSIONS.has(ext.toLowerCase());
+}
+
+/** Office and OpenDocument formats; previews are embedded or rendered by LibreOffice. */
+export const DOCUMENT_EXTENSIONS = new Set([
+  'docx',
+  'docm',
+  'dotx',
+  'xlsx',
+  'xlsm',
+  'xltx',
+  'pptx',
+  'pptm',
+  'potx',
+  'odt',
+  'ods',
+  'odp',
+  'odg',
+  'doc',
+  'xls',
+  'ppt',
+  'rtf',
+]);
+
+export function isDocumentExtension(ext?: string | null): boolean {
+  if (!ext) return false;
+  return DOCUMENT_EXTENSIONS.has(ext.toLowerCase());
+}
+
 export function getEffectiveExtension(
   file: Pick<FileItem, 'name' | 'extension'>
 ): string | undefined {
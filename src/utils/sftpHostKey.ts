@@ -0,0 +1,38 @@
+import { invoke } from '@tauri-apps/api/core';
+import { ask } from '@tauri-apps/plugin-dialog';
+import type { SftpHostKeyPrompt } from '@/types';
+
+const UNKNOWN_HOST_KEY_PREFIX = '[SFTP_HOST_KEY_UNKNOWN] ';
+
+/** The host key details from an `[SFTP_HOST_KEY_UNKNOWN]` error, if `message` is one. */
+export function parseUnknownHostKey(message: string): SftpHostKeyPrompt | null {
+  const start = message.indexOf(UNKNOWN_HOST_KEY_PREFIX);
+  if (start === -1) return null;
+  try {
+    return JSON.parse(message.slice(start + UNKNOWN_HOST_KEY_PREFIX.length)) as SftpHostKeyPrompt;
+  } catch {
+    return null;
+  }
+}
+
+/** Ask whether to trust a server's host key; records it in known_hosts if confirmed. */
+export async function confirmAndTrustHostKey(prompt: SftpHostKeyPrompt): Promise<boolean> {
+  const host = prompt.port === 22 ? prompt.hostname : `${prompt.hostname}:${prompt.port}`;
+  const trusted = await ask(
+    `Marlin hasn't connected to "${host}" before.\n\n${prompt.algorithm} key fingerprint:\n${prompt.fingerprint}\n\nOnly continue if this matches the fingerprint of your server.`,
+    {
+      title: 'Unknown SFTP Host',
+      okLabel: 'Trust and Connect',
+      cancelLabel: 'Cancel',
+      kind: 'warning',
+    }
+  );
+  if (!trusted) return false;
+
+  await invoke('trust_sftp_host_key', {
+    hostname: prompt.hostname,
+    port: prompt.port,
+    fingerprint: prompt.fingerprint,
+  });
+  return true;
+}
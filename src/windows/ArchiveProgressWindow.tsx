@@ -5,6 +5,7 @@ import { getCurrentWindow } from '@tauri-apps/api/window';
 import type { ArchiveProgressPayload, ArchiveProgressUpdatePayload } from '@/types';
 import { useArchiveProgressStore } from '@/store/useArchiveProgressStore';
 import QuickTooltip from '@/components/QuickTooltip';
+import { formatBytes } from '@/utils/formatBytes';
 import { WINDOW_CONTENT_TOP_PADDING } from '@/windows/windowLayout';
 
 export default function ArchiveProgressWindow() {
@@ -15,6 +16,8 @@ export default function ArchiveProgressWindow() {
     format,
     currentEntry,
     entries,
+    extractedBytes,
+    totalBytes,
     finished,
     setContext,
     pushUpdate,
@@ -87,6 +90,13 @@ export default function ArchiveProgressWindow() {
   const message = archiveName ? `Extracting ${archiveName}` : 'Extracting archive';
   const formatLabel = format ? format.toUpperCase() : undefined;
   const currentEntryLabel = currentEntry ?? 'Preparing files…';
+  // Compressed tars don't report a total, so they keep the spinner alone
+  const progressRatio =
+    totalBytes && totalBytes > 0 ? Math.min(extractedBytes / totalBytes, 1) : null;
+  const progressLabel =
+    progressRatio !== null && totalBytes
+      ? `${formatBytes(extractedBytes)} of ${formatBytes(totalBytes)} (${Math.round(progressRatio * 100)}%)`
+      : null;
 
   return (
     <div className="min-h-screen bg-app-dark text-app-text">
@@ -136,6 +146,17 @@ export default function ArchiveProgressWindow() {
           </div>
         </div>
         <div className="flex flex-col gap-3 bg-app-gray/10 border border-app-border/40 rounded-lg p-4 text-xs text-app-text/90 min-h-[176px] overflow-hidden">
+          {progressRatio !== null && (
+            <div>
+              <div className="h-2 rounded-full bg-app-gray/40 overflow-hidden">
+                <div
+                  className="h-full rounded-full bg-[var(--accent)] transition-all duration-200"
+                  style={{ width: `${progressRatio * 100}%` }}
+                />
+              </div>
+              <div className="mt-1 text-app-muted">{progressLabel}</div>
+            </div>
+          )}
           <div>
             <div className="text-app-muted text-[11px] uppercase tracking-wide">
               Currently extracting
@@ -14,7 +14,12 @@ import { useConflictStore } from '@/store/useConflictStore';
 import { useThumbnail } from '@/hooks/useThumbnail';
 import { useFileIcon } from '@/hooks/useFileIcon';
 import { usePlatform } from '@/hooks/usePlatform';
-import { isVideoExtension } from '@/utils/fileTypes';
+import {
+  isAudioExtension,
+  isDocumentExtension,
+  isRawExtension,
+  isVideoExtension,
+} from '@/utils/fileTypes';
 import { formatBytes } from '@/utils/formatBytes';
 import { WINDOW_CONTENT_TOP_PADDING } from '@/windows/windowLayout';
 
@@ -30,6 +35,7 @@ const THUMBNAIL_IMAGE_EXTS = new Set([
   'tiff',
   'tga',
   'ico',
+  'icns',
   'svg',
 ]);
 
@@ -43,9 +49,14 @@ function shouldLoadThumbnail(ext?: string | null): boolean {
     e === 'eps' ||
     e === 'psd' ||
     e === 'psb' ||
+    isRawExtension(e) ||
+    isDocumentExtension(e) ||
+    isAudioExtension(e) ||
     e === 'stl' ||
     e === 'ttf' ||
     e === 'otf' ||
+    e === 'woff' ||
+    e === 'woff2' ||
     isVideoExtension(e)
   );
 }
@@ -25,6 +25,9 @@ export default function PreferencesWindow() {
   const [darkThemeId, setDarkThemeId] = useState(DEFAULT_THEME_IDS.dark);
   const [lightThemeId, setLightThemeId] = useState(DEFAULT_THEME_IDS.light);
   const [customThemes, setCustomThemes] = useState<ThemeDefinition[]>([]);
+  const [flattenArchiveRoot, setFlattenArchiveRoot] = useState(false);
+  const [shareIdenticalThumbnails, setShareIdenticalThumbnails] = useState(true);
+  const [preferITerm, setPreferITerm] = useState(true);
   const themes = useThemeRegistry(customThemes);
   const isMac = platform() === 'macos';
 
@@ -92,6 +95,9 @@ export default function PreferencesWindow() {
           setDarkThemeId(darkId);
           setLightThemeId(lightId);
           setCustomThemes(storedThemes);
+          setFlattenArchiveRoot(global.flattenArchiveRoot === true);
+          setShareIdenticalThumbnails(global.shareIdenticalThumbnails !== false);
+          setPreferITerm(global.preferITerm !== false);
         }
       } catch (error) {
         console.warn('Failed to load preferences:', error);
@@ -212,6 +218,33 @@ export default function PreferencesWindow() {
     [persistPreferences]
   );
 
+  const handleFlattenArchiveRootChange = useCallback(
+    (event: ChangeEvent<HTMLInputElement>) => {
+      const enabled = event.target.checked;
+      setFlattenArchiveRoot(enabled);
+      void persistPreferences({ flattenArchiveRoot: enabled });
+    },
+    [persistPreferences]
+  );
+
+  const handleShareIdenticalThumbnailsChange = useCallback(
+    (event: ChangeEvent<HTMLInputElement>) => {
+      const enabled = event.target.checked;
+      setShareIdenticalThumbnails(enabled);
+      void persistPreferences({ shareIdenticalThumbnails: enabled });
+    },
+    [persistPreferences]
+  );
+
+  const handlePreferITermChange = useCallback(
+    (event: ChangeEvent<HTMLInputElement>) => {
+      const enabled = event.target.checked;
+      setPreferITerm(enabled);
+      void persistPreferences({ preferITerm: enabled });
+    },
+    [persistPreferences]
+  );
+
   const handleImportTheme = useCallback(async () => {
     try {
       const selection = await openDialog({
@@ -342,6 +375,73 @@ export default function PreferencesWindow() {
             </div>
           </section>
 
+          <section className="space-y-3 mt-4">
+            <div className="text-xs uppercase tracking-wide text-app-muted">Archives</div>
+            <div className="rounded-lg border border-app-border bg-app-dark/50 p-4">
+              <label className="flex items-start gap-3 text-sm text-app-text">
+                <input
+                  type="checkbox"
+                  checked={flattenArchiveRoot}
+                  onChange={handleFlattenArchiveRootChange}
+                  className="mt-0.5 accent-[var(--accent)]"
+                  data-tauri-drag-region={false}
+                />
+                <span>
+                  Open single-folder archives at that folder
+                  <span className="block text-xs text-app-muted">
+                    Skips the extra click into archives like project-1.0.zip that wrap everything
+                    in one top-level folder.
+                  </span>
+                </span>
+              </label>
+            </div>
+          </section>
+
+          <section className="space-y-3 mt-4">
+            <div className="text-xs uppercase tracking-wide text-app-muted">Thumbnails</div>
+            <div className="rounded-lg border border-app-border bg-app-dark/50 p-4">
+              <label className="flex items-start gap-3 text-sm text-app-text">
+                <input
+                  type="checkbox"
+                  checked={shareIdenticalThumbnails}
+                  onChange={handleShareIdenticalThumbnailsChange}
+                  className="mt-0.5 accent-[var(--accent)]"
+                  data-tauri-drag-region={false}
+                />
+                <span>
+                  Share thumbnails between identical files
+                  <span className="block text-xs text-app-muted">
+                    Copies of the same file reuse one cached thumbnail instead of each being
+                    generated again.
+                  </span>
+                </span>
+              </label>
+            </div>
+          </section>
+
+          {isMac && (
+            <section className="space-y-3 mt-4">
+              <div className="text-xs uppercase tracking-wide text-app-muted">Terminal</div>
+              <div className="rounded-lg border border-app-border bg-app-dark/50 p-4">
+                <label className="flex items-start gap-3 text-sm text-app-text">
+                  <input
+                    type="checkbox"
+                    checked={preferITerm}
+                    onChange={handlePreferITermChange}
+                    className="mt-0.5 accent-[var(--accent)]"
+                    data-tauri-drag-region={false}
+                  />
+                  <span>
+                    Open folders in iTerm when it&apos;s installed
+                    <span className="block text-xs text-app-muted">
+                      When this is off, or iTerm isn&apos;t installed, Terminal is used.
+                    </span>
+                  </span>
+                </label>
+              </div>
+            </section>
+          )}
+
           <section className="space-y-3 mt-4">
             <div className="text-xs uppercase tracking-wide text-app-muted">Themes</div>
             <div className="rounded-lg border border-app-border bg-app-dark/50 p-4 space-y-4">
@@ -5,6 +5,7 @@ import { getCurrentWindow } from '@tauri-apps/api/window';
 import { CircleNotch, Terminal, CheckCircle, X } from 'phosphor-react';
 import type { SftpConnectInitPayload, SftpConnectSuccessPayload, SftpServerInfo } from '@/types';
 import { SFTP_CONNECT_INIT_EVENT, SFTP_CONNECT_SUCCESS_EVENT } from '@/utils/events';
+import { confirmAndTrustHostKey, parseUnknownHostKey } from '@/utils/sftpHostKey';
 import { WINDOW_CONTENT_TOP_PADDING } from '@/windows/windowLayout';
 
 export default function SftpConnectWindow() {
@@ -106,10 +107,6 @@ export default function SftpConnectWindow() {
       setError('Password is required.');
       return;
     }
-    if (authMethod === 'key' && !keyPath.trim()) {
-      setError('SSH key path is required.');
-      return;
-    }
 
     setIsConnecting(true);
     try {
@@ -117,17 +114,29 @@ export default function SftpConnectWindow() {
       const trimmedUsername = username.trim();
       const effectivePassword =
         authMethod === 'password' ? password : authMethod === 'key' ? password || null : null;
-      const effectiveKeyPath = authMethod === 'key' ? keyPath.trim() : null;
+      const effectiveKeyPath = authMethod === 'key' ? keyPath.trim() || null : null;
 
       // Test the connection BEFORE saving credentials
-      await invoke<boolean>('test_sftp_connection', {
-        hostname: trimmedHostname,
-        port,
-        username: trimmedUsername,
-        password: effectivePassword,
-        authMethod,
-        keyPath: effectiveKeyPath,
-      });
+      const testConnection = () =>
+        invoke<boolean>('test_sftp_connection', {
+          hostname: trimmedHostname,
+          port,
+          username: trimmedUsername,
+          password: effectivePassword,
+          authMethod,
+          keyPath: effectiveKeyPath,
+        });
+      try {
+        await testConnection();
+      } catch (err) {
+        // First connection to this host: confirm its key, then try again
+        const hostKey = parseUnknownHostKey(err instanceof Error ? err.message : String(err));
+        if (!hostKey) throw err;
+        if (!(await confirmAndTrustHostKey(hostKey))) {
+          throw new Error('The server was not trusted.');
+        }
+        await testConnection();
+      }
 
       // Connection succeeded — now persist credentials
       const server = await invoke<SftpServerInfo>('add_sftp_server', {
@@ -303,14 +312,14 @@ export default function SftpConnectWindow() {
             <>
               <div className="space-y-1">
                 <label htmlFor="sftp-key-path" className="block text-xs text-app-muted">
-                  Private Key Path
+                  Private Key Path <span className="text-app-muted/60">(optional)</span>
                 </label>
                 <input
                   id="sftp-key-path"
                   type="text"
                   value={keyPath}
                   onChange={(e) => setKeyPath(e.target.value)}
-                  placeholder="~/.ssh/id_ed25519"
+                  placeholder="~/.ssh/id_ed25519, id_ecdsa or id_rsa"
                   className="w-full px-3 py-2 text-sm bg-app-gray border border-app-border rounded-md focus:outline-none focus:ring-2 focus:ring-accent/50 focus:border-accent disabled:opacity-70"
                   disabled={isConnecting}
                   autoComplete="off"
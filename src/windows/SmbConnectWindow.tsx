@@ -3,10 +3,21 @@ import { invoke } from '@tauri-apps/api/core';
 import { emit, listen } from '@tauri-apps/api/event';
 import { getCurrentWindow } from '@tauri-apps/api/window';
 import { CircleNotch, ShareNetwork, CheckCircle, X } from 'phosphor-react';
-import type { SmbConnectInitPayload, SmbConnectSuccessPayload, SmbServerInfo } from '@/types';
+import type {
+  SmbConnectInitPayload,
+  SmbConnectSuccessPayload,
+  SmbProtocol,
+  SmbServerInfo,
+} from '@/types';
 import { SMB_CONNECT_INIT_EVENT, SMB_CONNECT_SUCCESS_EVENT } from '@/utils/events';
 import { WINDOW_CONTENT_TOP_PADDING } from '@/windows/windowLayout';
 
+const SMB_PROTOCOL_OPTIONS: { value: SmbProtocol; label: string }[] = [
+  { value: 'NT1', label: 'SMB1 (NT1)' },
+  { value: 'SMB2', label: 'SMB2' },
+  { value: 'SMB3', label: 'SMB3' },
+];
+
 export default function SmbConnectWindow() {
   const windowRef = getCurrentWindow();
   const hostnameInputRef = useRef<HTMLInputElement>(null);
@@ -19,6 +30,9 @@ export default function SmbConnectWindow() {
   const [username, setUsername] = useState('');
   const [password, setPassword] = useState('');
   const [domain, setDomain] = useState('');
+  const [guest, setGuest] = useState(false);
+  const [minProtocol, setMinProtocol] = useState<SmbProtocol | ''>('');
+  const [maxProtocol, setMaxProtocol] = useState<SmbProtocol | ''>('');
   const [isConnecting, setIsConnecting] = useState(false);
   const [connected, setConnected] = useState(false);
   const [error, setError] = useState<string | undefined>();
@@ -41,6 +55,9 @@ export default function SmbConnectWindow() {
           setUsername('');
           setPassword('');
           setDomain('');
+          setGuest(false);
+          setMinProtocol('');
+          setMaxProtocol('');
           setError(undefined);
           setIsConnecting(false);
           setConnected(false);
@@ -97,11 +114,11 @@ export default function SmbConnectWindow() {
       setError('Server hostname is required.');
       return;
     }
-    if (!username.trim()) {
+    if (!guest && !username.trim()) {
       setError('Username is required.');
       return;
     }
-    if (!password) {
+    if (!guest && !password) {
       setError('Password is required.');
       return;
     }
@@ -110,9 +127,12 @@ export default function SmbConnectWindow() {
     try {
       const server = await invoke<SmbServerInfo>('add_smb_server', {
         hostname: hostname.trim(),
-        username: username.trim(),
-        password,
+        username: guest ? '' : username.trim(),
+        password: guest ? '' : password,
         domain: domain.trim() || undefined,
+        guest,
+        minProtocol: minProtocol || undefined,
+        maxProtocol: maxProtocol || undefined,
       });
 
       const payload: SmbConnectSuccessPayload = {
@@ -131,7 +151,17 @@ export default function SmbConnectWindow() {
       setError(message);
       setIsConnecting(false);
     }
-  }, [closeWindow, domain, hostname, password, targetPath, username]);
+  }, [
+    closeWindow,
+    domain,
+    guest,
+    hostname,
+    maxProtocol,
+    minProtocol,
+    password,
+    targetPath,
+    username,
+  ]);
 
   const handleFormSubmit = useCallback(
     (e: FormEvent) => {
@@ -196,6 +226,18 @@ export default function SmbConnectWindow() {
             />
           </div>
 
+          <label className="flex items-center gap-2 text-sm cursor-pointer">
+            <input
+              type="checkbox"
+              checked={guest}
+              onChange={(e) => setGuest(e.target.checked)}
+              className="accent-[var(--accent)]"
+              disabled={isConnecting}
+              data-tauri-drag-region={false}
+            />
+            <span>Connect as guest</span>
+          </label>
+
           <div className="space-y-1">
             <label htmlFor="smb-username" className="block text-xs text-app-muted">
               Username
@@ -208,7 +250,7 @@ export default function SmbConnectWindow() {
               onChange={(e) => setUsername(e.target.value)}
               placeholder="user"
               className="w-full px-3 py-2 text-sm bg-app-gray border border-app-border rounded-md focus:outline-none focus:ring-2 focus:ring-accent/50 focus:border-accent disabled:opacity-70"
-              disabled={isConnecting}
+              disabled={isConnecting || guest}
               autoComplete="username"
               autoCorrect="off"
               autoCapitalize="off"
@@ -228,7 +270,7 @@ export default function SmbConnectWindow() {
               onChange={(e) => setPassword(e.target.value)}
               placeholder="••••••••"
               className="w-full px-3 py-2 text-sm bg-app-gray border border-app-border rounded-md focus:outline-none focus:ring-2 focus:ring-accent/50 focus:border-accent disabled:opacity-70"
-              disabled={isConnecting}
+              disabled={isConnecting || guest}
               autoComplete="current-password"
               data-tauri-drag-region={false}
             />
@@ -254,6 +296,49 @@ export default function SmbConnectWindow() {
             />
           </div>
 
+          <div className="grid grid-cols-2 gap-3">
+            <div className="space-y-1">
+              <label htmlFor="smb-min-protocol" className="block text-xs text-app-muted">
+                Oldest protocol
+              </label>
+              <select
+                id="smb-min-protocol"
+                value={minProtocol}
+                onChange={(e) => setMinProtocol(e.target.value as SmbProtocol | '')}
+                className="w-full px-3 py-2 text-sm bg-app-gray border border-app-border rounded-md focus:outline-none focus:ring-2 focus:ring-accent/50 focus:border-accent disabled:opacity-70"
+                disabled={isConnecting}
+                data-tauri-drag-region={false}
+              >
+                <option value="">Automatic</option>
+                {SMB_PROTOCOL_OPTIONS.map((option) => (
+                  <option key={option.value} value={option.value}>
+                    {option.label}
+                  </option>
+                ))}
+              </select>
+            </div>
+            <div className="space-y-1">
+              <label htmlFor="smb-max-protocol" className="block text-xs text-app-muted">
+                Newest protocol
+              </label>
+              <select
+                id="smb-max-protocol"
+                value={maxProtocol}
+                onChange={(e) => setMaxProtocol(e.target.value as SmbProtocol | '')}
+                className="w-full px-3 py-2 text-sm bg-app-gray border border-app-border rounded-md focus:outline-none focus:ring-2 focus:ring-accent/50 focus:border-accent disabled:opacity-70"
+                disabled={isConnecting}
+                data-tauri-drag-region={false}
+              >
+                <option value="">Automatic</option>
+                {SMB_PROTOCOL_OPTIONS.map((option) => (
+                  <option key={option.value} value={option.value}>
+                    {option.label}
+                  </option>
+                ))}
+              </select>
+            </div>
+          </div>
+
           {error && (
             <div className="px-3 py-2 text-sm text-red-400 bg-red-500/10 border border-red-500/20 rounded-md">
               {error}